@@ -1,34 +1,74 @@
 //! Neuromorphic-Barter Bridge
-//! 
+//!
 //! This crate provides integration between neuromorphic trading signals
 //! and the Barter-rs trading framework.
 
-use async_trait::async_trait;
-use barter::engine::{Engine, EngineBuilder};
-use barter::event::{Event, EventTx};
-use barter::strategy::{Strategy, StrategyBuilder};
-use barter::portfolio::{Portfolio, PortfolioBuilder};
-use barter_data::event::MarketEvent;
-use barter_execution::event::ExecutionEvent;
+use barter::engine::audit::{state_replica::StateReplicaManager, EngineAudit};
+use barter::engine::clock::LiveClock;
+use barter::engine::execution_tx::MultiExchangeTxMap;
+use barter::engine::state::global::DefaultGlobalData;
+use barter::engine::state::instrument::data::{DefaultInstrumentMarketData, InstrumentDataState};
+use barter::engine::state::instrument::filter::InstrumentFilter;
+use barter::engine::state::trading::TradingState;
+use barter::engine::state::EngineState;
+use barter::engine::Engine;
+use barter::risk::DefaultRiskManager;
+use barter::strategy::algo::AlgoStrategy;
+use barter::strategy::close_positions::{
+    build_ioc_market_order_to_close_position, close_open_positions_with_market_orders, ClosePositionsStrategy,
+};
+use barter::strategy::on_disconnect::OnDisconnectStrategy;
+use barter::strategy::on_trading_disabled::OnTradingDisabled;
+use barter::system::builder::{AuditMode, EngineFeedMode, SystemArgs, SystemBuild, SystemBuilder};
+use barter::system::config::ExecutionConfig;
+use barter::system::System;
+use barter::EngineEvent;
+use barter_data::books::{Level, OrderBook};
+use barter_data::event::{DataKind, MarketEvent};
+use barter_data::streams::consumer::MarketStreamEvent;
+use barter_data::subscription::book::{OrderBookEvent, OrderBookL1};
+use barter_data::subscription::candle::Candle;
+use barter_data::subscription::trade::PublicTrade;
+use barter_execution::balance::{AssetBalance, Balance};
+use barter_execution::client::mock::MockExecutionConfig;
+use barter_execution::order::id::{ClientOrderId, StrategyId};
+use barter_execution::order::request::{OrderRequestCancel, OrderRequestOpen, RequestOpen};
+use barter_execution::order::{OrderKey, OrderKind, TimeInForce};
+use barter_execution::{InstrumentAccountSnapshot, UnindexedAccountSnapshot};
+use barter_instrument::asset::name::AssetNameInternal;
+use barter_instrument::asset::{Asset, AssetIndex};
+use barter_instrument::exchange::{ExchangeId, ExchangeIndex};
+use barter_instrument::index::IndexedInstruments;
+use barter_instrument::instrument::{Instrument, InstrumentIndex};
+use barter_instrument::Underlying;
+use barter_integration::channel::{mpsc_unbounded, Tx, UnboundedTx};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{BoxStream, StreamExt};
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tracing::{info, warn, error};
 
-use neuromorphic_core::exchanges::{Symbol, UniversalMarketData};
-use neuromorphic_core::paper_trading::{TradingSignal, SignalAction, SignalMetadata};
+use neuromorphic_core::exchanges::{Side, Symbol, UniversalMarketData};
+use neuromorphic_core::paper_trading::reconciliation::base_asset;
+use neuromorphic_core::paper_trading::{TradingSignal, SignalAction};
 
 /// Bridge error types
 #[derive(Debug, thiserror::Error)]
 pub enum BridgeError {
     #[error("Barter engine error: {0}")]
     BarterEngine(String),
-    
+
     #[error("Signal conversion error: {0}")]
     SignalConversion(String),
-    
+
     #[error("Market data error: {0}")]
     MarketData(String),
-    
+
     #[error("Strategy error: {0}")]
     Strategy(String),
 }
@@ -36,81 +76,145 @@ pub enum BridgeError {
 /// Result type for bridge operations
 pub type BridgeResult<T> = Result<T, BridgeError>;
 
-/// Neuromorphic strategy that integrates with Barter
+/// Engine state shared by the engine, its strategy and the stats replica
+pub type BridgeState = EngineState<DefaultGlobalData, DefaultInstrumentMarketData>;
+
+type BridgeEngine = Engine<LiveClock, BridgeState, MultiExchangeTxMap, NeuromorphicStrategy, DefaultRiskManager<BridgeState>>;
+type MarketStream = BoxStream<'static, MarketStreamEvent<InstrumentIndex, DataKind>>;
+
+/// Exchange the engine's paper orders are filled on
+const MOCK_EXCHANGE: ExchangeId = ExchangeId::Mock;
+
+/// Bridge configuration
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    pub initial_cash: f64,
+    pub cash_asset: String, // Quote asset holding initial_cash; every other asset starts empty
+    pub default_order_size: f64, // Quantity for signals without a size hint
+    pub symbols: Vec<Symbol>, // Instruments registered with the engine at start
+    pub order_book_depth: Option<usize>, // Levels per side forwarded to the engine; None keeps the full book
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            initial_cash: 100_000.0,
+            cash_asset: "USDT".to_string(),
+            default_order_size: 1000.0,
+            symbols: Vec::new(),
+            order_book_depth: Some(20),
+        }
+    }
+}
+
+/// Neuromorphic strategy run by the Barter engine
+///
+/// Signals queue up until the engine next runs its strategy, which turns them
+/// into market orders at the instrument's current engine price.
 pub struct NeuromorphicStrategy {
-    signal_receiver: mpsc::UnboundedReceiver<TradingSignal>,
-    event_tx: EventTx,
-    symbol_mapping: HashMap<Symbol, barter::instrument::Instrument>,
+    id: StrategyId,
+    signal_receiver: Mutex<mpsc::UnboundedReceiver<TradingSignal>>,
+    symbol_mapping: HashMap<Symbol, InstrumentIndex>,
+    default_order_size: f64,
 }
 
 impl NeuromorphicStrategy {
-    pub fn new(
-        signal_receiver: mpsc::UnboundedReceiver<TradingSignal>,
-        event_tx: EventTx,
-    ) -> Self {
+    pub fn new(signal_receiver: mpsc::UnboundedReceiver<TradingSignal>) -> Self {
         Self {
-            signal_receiver,
-            event_tx,
+            id: StrategyId::new("neuromorphic"),
+            signal_receiver: Mutex::new(signal_receiver),
             symbol_mapping: HashMap::new(),
+            default_order_size: BridgeConfig::default().default_order_size,
         }
     }
-    
-    /// Add symbol mapping between our format and Barter format
-    pub fn add_symbol_mapping(&mut self, our_symbol: Symbol, barter_instrument: barter::instrument::Instrument) {
-        self.symbol_mapping.insert(our_symbol, barter_instrument);
+
+    /// Add symbol mapping between our format and the engine's instrument
+    pub fn add_symbol_mapping(&mut self, our_symbol: Symbol, instrument: InstrumentIndex) {
+        self.symbol_mapping.insert(our_symbol, instrument);
     }
-    
-    /// Convert our trading signal to Barter signal
-    fn convert_signal_to_barter(&self, signal: &TradingSignal) -> BridgeResult<Option<barter::strategy::Signal>> {
+
+    /// Convert our trading signal to a market order against the engine state
+    fn convert_signal_to_barter(
+        &self,
+        signal: &TradingSignal,
+        state: &BridgeState,
+    ) -> BridgeResult<Option<OrderRequestOpen<ExchangeIndex, InstrumentIndex>>> {
         let instrument = self.symbol_mapping.get(&signal.symbol)
             .ok_or_else(|| BridgeError::SignalConversion(
                 format!("No mapping found for symbol: {}", signal.symbol)
             ))?;
-        
-        let barter_signal = match &signal.action {
-            SignalAction::Buy { size_hint } => {
-                let quantity = size_hint.unwrap_or(1000.0); // Default size
-                barter::strategy::Signal::GoLong {
-                    instrument: instrument.clone(),
-                    quantity,
-                }
-            }
-            SignalAction::Sell { size_hint } => {
-                let quantity = size_hint.unwrap_or(1000.0); // Default size
-                barter::strategy::Signal::GoShort {
-                    instrument: instrument.clone(),
-                    quantity,
-                }
-            }
+        let instrument_state = state.instruments.instrument_index(instrument);
+        let price = instrument_state.data.price()
+            .ok_or_else(|| BridgeError::SignalConversion(
+                format!("No market price yet for symbol: {}", signal.symbol)
+            ))?;
+        let exchange = instrument_state.instrument.exchange;
+
+        let (side, size_hint) = match &signal.action {
+            SignalAction::Buy { size_hint } => (barter_instrument::Side::Buy, size_hint),
+            SignalAction::Sell { size_hint } => (barter_instrument::Side::Sell, size_hint),
             SignalAction::Close { position_id: _ } => {
-                barter::strategy::Signal::ClosePosition {
-                    instrument: instrument.clone(),
-                }
+                let Some(position) = &instrument_state.position.current else {
+                    return Ok(None);
+                };
+                return Ok(Some(build_ioc_market_order_to_close_position(
+                    exchange,
+                    position,
+                    self.id.clone(),
+                    price,
+                    ClientOrderId::random,
+                )));
+            }
+            SignalAction::ScaleIn { .. } | SignalAction::ScaleOut { .. } => {
+                // Barter signals carry no relative sizing; scaling stays in the paper engine
+                return Ok(None);
             }
             SignalAction::Hold => {
                 // No signal for hold
                 return Ok(None);
             }
         };
-        
-        Ok(Some(barter_signal))
+        let quantity = Decimal::from_f64(size_hint.unwrap_or(self.default_order_size))
+            .filter(|quantity| *quantity > Decimal::ZERO)
+            .ok_or_else(|| BridgeError::SignalConversion(format!("Invalid order size for {}", signal.symbol)))?;
+
+        Ok(Some(OrderRequestOpen {
+            key: OrderKey {
+                exchange,
+                instrument: *instrument,
+                strategy: self.id.clone(),
+                cid: ClientOrderId::random(),
+            },
+            state: RequestOpen {
+                side,
+                price,
+                quantity,
+                kind: OrderKind::Market,
+                time_in_force: TimeInForce::ImmediateOrCancel,
+            },
+        }))
     }
-    
-    /// Process incoming neuromorphic signals
-    async fn process_signals(&mut self) -> BridgeResult<()> {
-        while let Some(signal) = self.signal_receiver.recv().await {
+}
+
+impl AlgoStrategy for NeuromorphicStrategy {
+    type State = BridgeState;
+
+    fn generate_algo_orders(
+        &self,
+        state: &Self::State,
+    ) -> (
+        impl IntoIterator<Item = OrderRequestCancel<ExchangeIndex, InstrumentIndex>>,
+        impl IntoIterator<Item = OrderRequestOpen<ExchangeIndex, InstrumentIndex>>,
+    ) {
+        let mut orders = Vec::new();
+        let mut signal_receiver = self.signal_receiver.lock();
+        while let Ok(signal) = signal_receiver.try_recv() {
             info!("Received neuromorphic signal: {:?}", signal);
-            
-            // Convert to Barter signal
-            match self.convert_signal_to_barter(&signal) {
-                Ok(Some(barter_signal)) => {
-                    info!("Converted to Barter signal: {:?}", barter_signal);
-                    
-                    // Send to Barter engine
-                    let strategy_event = Event::Strategy(barter_signal);
-                    if let Err(e) = self.event_tx.send(strategy_event).await {
-                        error!("Failed to send signal to Barter engine: {}", e);
-                    }
+
+            match self.convert_signal_to_barter(&signal, state) {
+                Ok(Some(order)) => {
+                    info!("Converted to Barter order: {:?}", order);
+                    orders.push(order);
                 }
                 Ok(None) => {
                     // Hold signal, no action needed
@@ -121,177 +225,435 @@ impl NeuromorphicStrategy {
                 }
             }
         }
-        
-        Ok(())
+
+        (std::iter::empty(), orders)
+    }
+}
+
+impl ClosePositionsStrategy for NeuromorphicStrategy {
+    type State = BridgeState;
+
+    fn close_positions_requests<'a>(
+        &'a self,
+        state: &'a Self::State,
+        filter: &'a InstrumentFilter,
+    ) -> (
+        impl IntoIterator<Item = OrderRequestCancel<ExchangeIndex, InstrumentIndex>> + 'a,
+        impl IntoIterator<Item = OrderRequestOpen<ExchangeIndex, InstrumentIndex>> + 'a,
+    )
+    where
+        ExchangeIndex: 'a,
+        AssetIndex: 'a,
+        InstrumentIndex: 'a,
+    {
+        close_open_positions_with_market_orders(&self.id, state, filter, |_| ClientOrderId::random())
     }
 }
 
-#[async_trait]
-impl Strategy for NeuromorphicStrategy {
-    async fn generate_signals(&mut self, market: &MarketEvent) -> Vec<barter::strategy::Signal> {
-        // In this implementation, we generate signals from neuromorphic input
-        // rather than from market data directly. The market data is used
-        // for context in the neuromorphic analysis.
-        
-        // For now, return empty vec as signals come through the receiver
-        vec![]
+impl<Clock, ExecutionTxs, Risk> OnDisconnectStrategy<Clock, BridgeState, ExecutionTxs, Risk> for NeuromorphicStrategy {
+    type OnDisconnect = ();
+
+    fn on_disconnect(_: &mut Engine<Clock, BridgeState, ExecutionTxs, Self, Risk>, exchange: ExchangeId) {
+        warn!(%exchange, "Barter engine lost its connection");
     }
 }
 
+impl<Clock, ExecutionTxs, Risk> OnTradingDisabled<Clock, BridgeState, ExecutionTxs, Risk> for NeuromorphicStrategy {
+    type OnTradingDisabled = ();
+
+    fn on_trading_disabled(_: &mut Engine<Clock, BridgeState, ExecutionTxs, Self, Risk>) {}
+}
+
 /// Market data bridge to convert our market data to Barter format
 pub struct MarketDataBridge {
-    barter_event_tx: EventTx,
+    market_tx: UnboundedTx<MarketStreamEvent<InstrumentIndex, DataKind>>,
+    instruments: HashMap<Symbol, InstrumentIndex>,
+    order_book_depth: Option<usize>,
 }
 
 impl MarketDataBridge {
-    pub fn new(barter_event_tx: EventTx) -> Self {
-        Self { barter_event_tx }
+    pub fn new(
+        market_tx: UnboundedTx<MarketStreamEvent<InstrumentIndex, DataKind>>,
+        instruments: HashMap<Symbol, InstrumentIndex>,
+    ) -> Self {
+        Self::with_depth(market_tx, instruments, BridgeConfig::default().order_book_depth)
+    }
+
+    /// Forward at most `depth` order book levels per side; `None` forwards them all
+    pub fn with_depth(
+        market_tx: UnboundedTx<MarketStreamEvent<InstrumentIndex, DataKind>>,
+        instruments: HashMap<Symbol, InstrumentIndex>,
+        depth: Option<usize>,
+    ) -> Self {
+        Self {
+            market_tx,
+            instruments,
+            order_book_depth: depth,
+        }
     }
-    
+
     /// Convert our market data to Barter market event
-    pub fn convert_market_data(&self, data: &UniversalMarketData) -> BridgeResult<MarketEvent> {
-        match data {
+    pub fn convert_market_data(&self, data: &UniversalMarketData) -> BridgeResult<MarketEvent<InstrumentIndex, DataKind>> {
+        let (symbol, time_exchange, time_received, kind) = match data {
             UniversalMarketData::Trade(trade) => {
-                // Convert to Barter trade event
-                let barter_trade = barter_data::event::Trade {
-                    instrument: barter::instrument::Instrument::from(trade.symbol.as_str()),
+                let barter_trade = PublicTrade {
+                    id: trade.trade_id.clone(),
                     price: trade.price,
-                    quantity: trade.quantity,
-                    ts_event: trade.timestamp_exchange,
-                    ts_received: trade.timestamp_local,
+                    amount: trade.quantity,
+                    side: barter_side(trade.side),
                 };
-                
-                Ok(MarketEvent::Trade(barter_trade))
+
+                (&trade.symbol, millis(trade.timestamp_exchange), millis(trade.timestamp_local), DataKind::Trade(barter_trade))
             }
             UniversalMarketData::Quote(quote) => {
-                // Convert to Barter quote/orderbook event
-                let barter_orderbook = barter_data::event::OrderBookL1 {
-                    instrument: barter::instrument::Instrument::from(quote.symbol.as_str()),
-                    bid: quote.bid_price,
-                    ask: quote.ask_price,
-                    ts_event: quote.timestamp_exchange,
-                    ts_received: quote.timestamp_local,
+                let barter_orderbook = OrderBookL1 {
+                    last_update_time: millis(quote.timestamp_exchange),
+                    best_bid: level(quote.bid_price, quote.bid_size),
+                    best_ask: level(quote.ask_price, quote.ask_size),
                 };
-                
-                Ok(MarketEvent::OrderBookL1(barter_orderbook))
+
+                (&quote.symbol, millis(quote.timestamp_exchange), millis(quote.timestamp_local), DataKind::OrderBookL1(barter_orderbook))
             }
-            UniversalMarketData::OrderBook(_book) => {
-                // For now, we'll convert to L1 orderbook
-                // Full L2 conversion would require more complex mapping
-                Err(BridgeError::MarketData(
-                    "Full orderbook conversion not yet implemented".to_string()
-                ))
+            UniversalMarketData::OrderBook(book) => {
+                let levels = |side: &[(f64, f64)], descending: bool| {
+                    book_levels(side, descending, self.order_book_depth)
+                        .into_iter()
+                        .filter_map(|(price, quantity)| level(price, quantity))
+                        .collect::<Vec<_>>()
+                };
+                let barter_orderbook = OrderBook::new(
+                    book.sequence,
+                    Some(millis(book.timestamp_local)),
+                    levels(&book.bids, true),
+                    levels(&book.asks, false),
+                );
+
+                (&book.symbol, millis(book.timestamp_exchange), millis(book.timestamp_local), DataKind::OrderBook(OrderBookEvent::Snapshot(barter_orderbook)))
             }
-        }
+            UniversalMarketData::Kline(kline) => {
+                let barter_candle = Candle {
+                    close_time: kline.close_time,
+                    open: kline.open,
+                    high: kline.high,
+                    low: kline.low,
+                    close: kline.close,
+                    volume: kline.volume,
+                    trade_count: kline.trades_count,
+                };
+
+                (&kline.symbol, kline.close_time, kline.close_time, DataKind::Candle(barter_candle))
+            }
+        };
+        let instrument = self.instruments.get(symbol)
+            .ok_or_else(|| BridgeError::MarketData(format!("No instrument registered for symbol: {}", symbol)))?;
+
+        Ok(MarketEvent {
+            time_exchange,
+            time_received,
+            exchange: MOCK_EXCHANGE,
+            instrument: *instrument,
+            kind,
+        })
     }
-    
+
     /// Send market data to Barter engine
-    pub async fn send_market_data(&self, data: UniversalMarketData) -> BridgeResult<()> {
+    pub fn send_market_data(&self, data: UniversalMarketData) -> BridgeResult<()> {
         let market_event = self.convert_market_data(&data)?;
-        let barter_event = Event::Market(market_event);
-        
-        self.barter_event_tx.send(barter_event).await
+
+        self.market_tx.send(MarketStreamEvent::Item(market_event))
             .map_err(|e| BridgeError::MarketData(format!("Failed to send market data: {}", e)))?;
-        
+
         Ok(())
     }
 }
 
+fn barter_side(side: Side) -> barter_instrument::Side {
+    match side {
+        Side::Buy => barter_instrument::Side::Buy,
+        Side::Sell => barter_instrument::Side::Sell,
+    }
+}
+
+fn millis(timestamp: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(timestamp as i64).unwrap_or_else(Utc::now)
+}
+
+fn level(price: f64, quantity: f64) -> Option<Level> {
+    Some(Level::new(Decimal::from_f64(price)?, Decimal::from_f64(quantity)?))
+}
+
+/// Best-first levels of one book side: bids by descending price, asks ascending.
+/// Empty and invalid levels are dropped before truncating to `depth`.
+fn book_levels(levels: &[(f64, f64)], descending: bool, depth: Option<usize>) -> Vec<(f64, f64)> {
+    let mut levels: Vec<(f64, f64)> = levels
+        .iter()
+        .copied()
+        .filter(|(price, quantity)| price.is_finite() && *price > 0.0 && quantity.is_finite() && *quantity > 0.0)
+        .collect();
+    levels.sort_by(|a, b| {
+        let order = a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal);
+        if descending { order.reverse() } else { order }
+    });
+    if let Some(depth) = depth {
+        levels.truncate(depth);
+    }
+    levels
+}
+
+/// Base and quote asset of a spot symbol, e.g. "BTC-USDT" or "BTCUSDT" -> ("BTC", "USDT")
+fn spot_assets(symbol: &Symbol) -> BridgeResult<(String, String)> {
+    let base = base_asset(symbol);
+    let quote = symbol.as_str().to_uppercase()[base.len()..]
+        .trim_start_matches(['-', '/', '_'])
+        .to_string();
+    if quote.is_empty() {
+        return Err(BridgeError::BarterEngine(format!("Cannot tell the quote asset of {}", symbol)));
+    }
+    Ok((base, quote))
+}
+
+/// Portfolio statistics read from the engine state
+fn portfolio_stats(state: &BridgeState, cash_asset: &AssetNameInternal, initial_cash: f64) -> PortfolioStats {
+    let cash = state.assets.assets()
+        .find(|asset| asset.asset.name_internal == *cash_asset)
+        .and_then(|asset| asset.balance.as_ref())
+        .map_or(Decimal::ZERO, |balance| balance.value.total);
+
+    let mut unrealized_pnl = Decimal::ZERO;
+    let mut realized_pnl = Decimal::ZERO;
+    for instrument in state.instruments.instruments(&InstrumentFilter::None) {
+        realized_pnl += instrument.tear_sheet.pnl_returns.pnl_raw;
+        if let Some(position) = &instrument.position.current {
+            // The engine marks positions on fills only; re-mark to the latest price
+            let mut position = position.clone();
+            if let Some(price) = instrument.data.price() {
+                position.update_pnl_unrealised(price);
+            }
+            unrealized_pnl += position.pnl_unrealised;
+            realized_pnl += position.pnl_realised;
+        }
+    }
+    let unrealized_pnl = unrealized_pnl.to_f64().unwrap_or(0.0);
+    let realized_pnl = realized_pnl.to_f64().unwrap_or(0.0);
+
+    PortfolioStats {
+        total_value: initial_cash + realized_pnl + unrealized_pnl,
+        cash: cash.to_f64().unwrap_or(0.0),
+        unrealized_pnl,
+        realized_pnl,
+    }
+}
+
 /// Main bridge coordinator
 pub struct NeuromorphicBarterBridge {
-    engine: Option<Engine>,
-    strategy: NeuromorphicStrategy,
-    market_data_bridge: MarketDataBridge,
+    config: BridgeConfig,
+    engine: Mutex<Option<SystemBuild<BridgeEngine, EngineEvent, MarketStream>>>, // Built but not yet running; locked to keep the bridge Sync
+    system: Option<System<BridgeEngine, EngineEvent>>,
+    signal_receiver: Option<mpsc::UnboundedReceiver<TradingSignal>>, // Moved into the strategy with the engine
+    market_data_bridge: Option<MarketDataBridge>,
     signal_sender: mpsc::UnboundedSender<TradingSignal>,
+    state: Arc<RwLock<Option<BridgeState>>>, // Replica of the engine state, fed by its audit stream
+    tasks: Vec<JoinHandle<()>>,
 }
 
 impl NeuromorphicBarterBridge {
-    /// Create a new bridge
+    /// Create a new bridge with the default configuration
     pub async fn new() -> BridgeResult<Self> {
+        Self::with_config(BridgeConfig::default()).await
+    }
+
+    /// Create a new bridge
+    pub async fn with_config(config: BridgeConfig) -> BridgeResult<Self> {
         // Create signal channel for neuromorphic input
         let (signal_sender, signal_receiver) = mpsc::unbounded_channel();
-        
-        // Create Barter engine
-        let (event_tx, event_rx) = mpsc::unbounded_channel();
-        
-        // Create strategy
-        let strategy = NeuromorphicStrategy::new(signal_receiver, event_tx.clone());
-        
-        // Create market data bridge
-        let market_data_bridge = MarketDataBridge::new(event_tx.clone());
-        
-        // Build portfolio (paper trading configuration)
-        let portfolio = PortfolioBuilder::new()
-            .initial_cash(100_000.0) // $100k initial capital
-            .build()
-            .map_err(|e| BridgeError::BarterEngine(format!("Failed to build portfolio: {}", e)))?;
-        
-        // Build engine (we'll set it up later)
-        let mut bridge = Self {
-            engine: None,
-            strategy,
-            market_data_bridge,
+
+        Ok(Self {
+            config,
+            engine: Mutex::new(None),
+            system: None,
+            signal_receiver: Some(signal_receiver),
+            market_data_bridge: None, // Created with the engine's instruments
             signal_sender,
-        };
-        
-        Ok(bridge)
+            state: Arc::new(RwLock::new(None)),
+            tasks: Vec::new(),
+        })
+    }
+
+    /// Register an instrument with the engine; call before `start`
+    pub fn add_symbol(&mut self, symbol: Symbol) -> BridgeResult<()> {
+        if self.signal_receiver.is_none() {
+            return Err(BridgeError::Strategy("Symbols must be added before the engine is built".to_string()));
+        }
+        self.config.symbols.push(symbol);
+        Ok(())
     }
-    
-    /// Initialize the Barter engine with our strategy
+
+    /// Build the Barter engine around a mock exchange holding the initial cash
     pub async fn initialize_engine(&mut self) -> BridgeResult<()> {
-        // This would require more complex setup with actual Barter components
-        // For now, we'll create a placeholder
-        info!("Initializing Barter engine with neuromorphic strategy");
-        
-        // The actual engine initialization would happen here
-        // self.engine = Some(engine);
-        
+        if self.engine.get_mut().is_some() || self.system.is_some() {
+            return Ok(());
+        }
+        info!(initial_cash = self.config.initial_cash, instruments = self.config.symbols.len(), "Initializing Barter engine with neuromorphic strategy");
+
+        let signal_receiver = self.signal_receiver.take()
+            .ok_or_else(|| BridgeError::BarterEngine("Engine signal channel already consumed".to_string()))?;
+
+        let mut spot = Vec::with_capacity(self.config.symbols.len());
+        for symbol in &self.config.symbols {
+            let (base, quote) = spot_assets(symbol)?;
+            spot.push(Instrument::spot(
+                MOCK_EXCHANGE,
+                symbol.as_str(),
+                symbol.as_str(),
+                Underlying::new(Asset::from(base.as_str()), Asset::from(quote.as_str())),
+                None,
+            ));
+        }
+        let instruments = IndexedInstruments::new(spot);
+        let instrument_indices: HashMap<Symbol, InstrumentIndex> = self.config.symbols
+            .iter()
+            .filter_map(|symbol| {
+                instruments.instruments().iter()
+                    .find(|instrument| instrument.value.name_exchange.name() == symbol.as_str())
+                    .map(|instrument| (symbol.clone(), instrument.key))
+            })
+            .collect();
+
+        // The mock exchange needs a balance for every asset it trades
+        let now = Utc::now();
+        let cash_asset = AssetNameInternal::new(self.config.cash_asset.as_str());
+        let initial_cash = Decimal::from_f64(self.config.initial_cash)
+            .ok_or_else(|| BridgeError::BarterEngine(format!("Invalid initial cash: {}", self.config.initial_cash)))?;
+        let balances: Vec<(Asset, Balance)> = instruments.assets()
+            .iter()
+            .map(|asset| {
+                let amount = if asset.value.asset.name_internal == cash_asset { initial_cash } else { Decimal::ZERO };
+                (asset.value.asset.clone(), Balance::new(amount, amount))
+            })
+            .collect();
+        let initial_state = UnindexedAccountSnapshot {
+            exchange: MOCK_EXCHANGE,
+            balances: balances.iter()
+                .map(|(asset, balance)| AssetBalance::new(asset.name_exchange.clone(), *balance, now))
+                .collect(),
+            instruments: instruments.instruments()
+                .iter()
+                .map(|instrument| InstrumentAccountSnapshot::new(instrument.value.name_exchange.clone(), Vec::new()))
+                .collect(),
+        };
+        let execution = ExecutionConfig::Mock(MockExecutionConfig::new(MOCK_EXCHANGE, initial_state, 0, Decimal::ZERO));
+
+        let mut strategy = NeuromorphicStrategy::new(signal_receiver);
+        strategy.default_order_size = self.config.default_order_size;
+        for (symbol, instrument) in &instrument_indices {
+            strategy.add_symbol_mapping(symbol.clone(), *instrument);
+        }
+
+        // Our market data reaches the engine as its market stream
+        let (market_tx, market_rx) = mpsc_unbounded();
+        let market_stream: MarketStream = market_rx.into_stream().boxed();
+        self.market_data_bridge = Some(MarketDataBridge::with_depth(market_tx, instrument_indices, self.config.order_book_depth));
+
+        let args = SystemArgs::new(
+            &instruments,
+            vec![execution],
+            LiveClock,
+            strategy,
+            DefaultRiskManager::default(),
+            market_stream,
+            DefaultGlobalData,
+            |_| DefaultInstrumentMarketData::default(),
+        );
+        let engine = SystemBuilder::new(args)
+            .engine_feed_mode(EngineFeedMode::Stream)
+            .audit_mode(AuditMode::Enabled)
+            .trading_state(TradingState::Enabled)
+            .balances(balances.into_iter().map(|(asset, balance)| (MOCK_EXCHANGE, asset.name_internal, balance)))
+            .build::<EngineEvent, _>()
+            .map_err(|e| BridgeError::BarterEngine(format!("Failed to build engine: {}", e)))?;
+
+        *self.engine.get_mut() = Some(engine);
         Ok(())
     }
-    
+
     /// Send a neuromorphic trading signal to the bridge
     pub async fn send_signal(&self, signal: TradingSignal) -> BridgeResult<()> {
         self.signal_sender.send(signal)
             .map_err(|e| BridgeError::Strategy(format!("Failed to send signal: {}", e)))?;
         Ok(())
     }
-    
+
     /// Process market data through the bridge
     pub async fn process_market_data(&self, data: UniversalMarketData) -> BridgeResult<()> {
-        self.market_data_bridge.send_market_data(data).await
+        self.market_data_bridge.as_ref()
+            .ok_or_else(|| BridgeError::MarketData("Engine not initialized".to_string()))?
+            .send_market_data(data)
     }
-    
-    /// Start the bridge processing
+
+    /// Start the engine, its mock exchange and the state replica behind the stats
     pub async fn start(&mut self) -> BridgeResult<()> {
         info!("Starting Neuromorphic-Barter bridge");
-        
+
         // Initialize engine
         self.initialize_engine().await?;
-        
-        // Start strategy signal processing
-        // This would typically be spawned as a background task
-        // tokio::spawn(async move { strategy.process_signals().await });
-        
+
+        let engine = self.engine.get_mut().take()
+            .ok_or_else(|| BridgeError::BarterEngine("Bridge already started".to_string()))?;
+        let mut system = engine.init().await
+            .map_err(|e| BridgeError::BarterEngine(format!("Failed to start engine: {}", e)))?;
+
+        // Engine audits keep a replica of its state for the portfolio statistics
+        if let Some(audit) = system.take_audit() {
+            let mut replica = StateReplicaManager::new(audit.snapshot, ());
+            *self.state.write() = Some(replica.replica_engine_state().clone());
+            let state = self.state.clone();
+            let mut updates = audit.updates.into_stream();
+            self.tasks.push(tokio::spawn(async move {
+                while let Some(tick) = updates.next().await {
+                    if let EngineAudit::Process(audit) = tick.event {
+                        replica.update_from_event(audit.event);
+                        *state.write() = Some(replica.replica_engine_state().clone());
+                    }
+                }
+                info!("Barter engine stopped");
+            }));
+        }
+
+        self.system = Some(system);
         Ok(())
     }
-    
-    /// Get portfolio statistics from Barter
+
+    /// Stop the engine and background tasks
+    pub fn stop(&mut self) {
+        if let Some(system) = self.system.take() {
+            let _ = system.feed_tx.send(EngineEvent::shutdown());
+            system.handles.abort();
+        }
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+
+    /// Portfolio statistics from the engine's balances and positions, marked to the latest prices
     pub fn get_portfolio_stats(&self) -> BridgeResult<PortfolioStats> {
-        // Extract stats from Barter portfolio
-        // This is a placeholder implementation
-        Ok(PortfolioStats {
-            total_value: 100_000.0,
-            cash: 50_000.0,
-            unrealized_pnl: 0.0,
-            realized_pnl: 0.0,
-        })
+        let state = self.state.read();
+        let state = state.as_ref()
+            .ok_or_else(|| BridgeError::BarterEngine("Portfolio stats requested before the bridge started".to_string()))?;
+        let cash_asset = AssetNameInternal::new(self.config.cash_asset.as_str());
+        Ok(portfolio_stats(state, &cash_asset, self.config.initial_cash))
+    }
+}
+
+impl Drop for NeuromorphicBarterBridge {
+    fn drop(&mut self) {
+        self.stop();
     }
 }
 
 /// Portfolio statistics structure
 #[derive(Debug, Clone)]
 pub struct PortfolioStats {
-    pub total_value: f64,
+    pub total_value: f64, // Initial cash plus realized and unrealized P&L
     pub cash: f64,
     pub unrealized_pnl: f64,
     pub realized_pnl: f64,
@@ -300,25 +662,15 @@ pub struct PortfolioStats {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use neuromorphic_core::exchanges::{Exchange, Side};
-    
-    #[tokio::test]
-    async fn test_bridge_creation() {
-        let bridge = NeuromorphicBarterBridge::new().await;
-        assert!(bridge.is_ok());
-    }
-    
-    #[tokio::test]
-    async fn test_signal_conversion() {
-        let (signal_sender, signal_receiver) = mpsc::unbounded_channel();
-        let (event_tx, _event_rx) = mpsc::unbounded_channel();
-        
-        let strategy = NeuromorphicStrategy::new(signal_receiver, event_tx);
-        
-        let signal = TradingSignal {
-            symbol: Symbol::new("BTC-USD"),
+    use neuromorphic_core::exchanges::{Exchange, UniversalTrade};
+    use neuromorphic_core::paper_trading::SignalMetadata;
+    use std::time::Duration;
+
+    fn signal(action: SignalAction) -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new("BTCUSDT"),
             exchange: Exchange::Binance,
-            action: SignalAction::Buy { size_hint: Some(1000.0) },
+            action,
             confidence: 0.8,
             urgency: 0.5,
             metadata: SignalMetadata {
@@ -326,11 +678,91 @@ mod tests {
                 pattern_strength: 0.9,
                 market_regime: "trending".to_string(),
                 volatility: 0.02,
+                ..Default::default()
             },
-        };
-        
-        // Test would require proper Barter instrument setup
-        // For now, just verify the signal structure
-        assert_eq!(signal.symbol.as_str(), "BTC-USD");
+        }
     }
-}
\ No newline at end of file
+
+    fn trade(price: f64, timestamp: u64) -> UniversalMarketData {
+        UniversalMarketData::Trade(UniversalTrade {
+            exchange: Exchange::Binance,
+            symbol: Symbol::new("BTCUSDT"),
+            price,
+            quantity: 0.5,
+            side: Side::Buy,
+            timestamp_exchange: timestamp,
+            timestamp_local: timestamp,
+            trade_id: timestamp.to_string(),
+        })
+    }
+
+    async fn stats_until(bridge: &NeuromorphicBarterBridge, done: impl Fn(&PortfolioStats) -> bool) -> PortfolioStats {
+        for _ in 0..200 {
+            let stats = bridge.get_portfolio_stats().unwrap();
+            if done(&stats) {
+                return stats;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        bridge.get_portfolio_stats().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bridge_creation() {
+        let bridge = NeuromorphicBarterBridge::new().await;
+        assert!(bridge.is_ok());
+    }
+
+    #[test]
+    fn test_spot_assets() {
+        assert_eq!(spot_assets(&Symbol::new("BTCUSDT")).unwrap(), ("BTC".to_string(), "USDT".to_string()));
+        assert_eq!(spot_assets(&Symbol::new("eth-usd")).unwrap(), ("ETH".to_string(), "USD".to_string()));
+        assert!(spot_assets(&Symbol::new("XYZ")).is_err());
+    }
+
+    #[test]
+    fn test_order_book_levels() {
+        let bids = vec![(99.0, 1.0), (100.0, 2.0), (98.0, 0.0), (97.0, 3.0)];
+        assert_eq!(book_levels(&bids, true, Some(2)), vec![(100.0, 2.0), (99.0, 1.0)]);
+        assert_eq!(book_levels(&bids, true, None), vec![(100.0, 2.0), (99.0, 1.0), (97.0, 3.0)]);
+
+        let asks = vec![(102.0, 1.0), (101.0, 1.5), (f64::NAN, 1.0)];
+        assert_eq!(book_levels(&asks, false, Some(5)), vec![(101.0, 1.5), (102.0, 1.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_signals_trade_through_the_engine() {
+        let mut bridge = NeuromorphicBarterBridge::with_config(BridgeConfig {
+            symbols: vec![Symbol::new("BTCUSDT")],
+            ..BridgeConfig::default()
+        }).await.unwrap();
+        assert!(bridge.get_portfolio_stats().is_err());
+        bridge.start().await.unwrap();
+
+        let stats = bridge.get_portfolio_stats().unwrap();
+        assert_eq!(stats.cash, 100_000.0);
+        assert_eq!(stats.total_value, 100_000.0);
+
+        // The engine turns queued signals into orders on its next event
+        bridge.process_market_data(trade(50_000.0, 1_000)).await.unwrap();
+        bridge.send_signal(signal(SignalAction::Buy { size_hint: Some(1.0) })).await.unwrap();
+        bridge.process_market_data(trade(50_000.0, 2_000)).await.unwrap();
+        let stats = stats_until(&bridge, |stats| stats.cash < 100_000.0).await;
+        assert_eq!(stats.cash, 50_000.0);
+
+        bridge.process_market_data(trade(51_000.0, 3_000)).await.unwrap();
+        let stats = stats_until(&bridge, |stats| stats.unrealized_pnl != 0.0).await;
+        assert_eq!(stats.unrealized_pnl, 1_000.0);
+        assert_eq!(stats.total_value, 101_000.0);
+
+        // Closing realizes the gain at the latest price
+        bridge.send_signal(signal(SignalAction::Close { position_id: None })).await.unwrap();
+        bridge.process_market_data(trade(51_000.0, 4_000)).await.unwrap();
+        let stats = stats_until(&bridge, |stats| stats.realized_pnl != 0.0).await;
+        assert_eq!(stats.realized_pnl, 1_000.0);
+        assert_eq!(stats.unrealized_pnl, 0.0);
+        assert_eq!(stats.total_value, 101_000.0);
+
+        bridge.stop();
+    }
+}
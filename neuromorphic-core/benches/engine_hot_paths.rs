@@ -0,0 +1,158 @@
+//! Signal, order matching and metrics hot paths
+//!
+//! Run with `cargo bench -p neuromorphic-core --bench engine_hot_paths`;
+//! see `support/gate.rs` for checking the results against the baseline.
+
+use criterion::{black_box, criterion_group, BatchSize, Criterion, Throughput};
+use dashmap::DashMap;
+use neuromorphic_core::exchanges::{Exchange, Side, Symbol};
+use neuromorphic_core::metrics::{MetricsCollector, TradingHistograms};
+use neuromorphic_core::paper_trading::{
+    Order, OrderManager, OrderStatus, PaperTradingConfig, PaperTradingEngine, RiskLimits, SignalAction, SignalMetadata,
+    SlippageModel, TradingSignal,
+};
+use std::time::{Duration, SystemTime};
+
+#[path = "support/gate.rs"]
+mod gate;
+
+const SYMBOLS: usize = 1_000;
+
+fn universe() -> Vec<Symbol> {
+    (0..SYMBOLS).map(|i| Symbol::new(format!("SYM{}USDT", i))).collect()
+}
+
+fn signal(symbol: &Symbol, action: SignalAction) -> TradingSignal {
+    TradingSignal {
+        symbol: symbol.clone(),
+        exchange: Exchange::Binance,
+        action,
+        confidence: 0.8,
+        urgency: 0.9,
+        metadata: SignalMetadata::default(),
+    }
+}
+
+/// A signal from the channel into the engine to its published result,
+/// alternating entries and exits so positions don't pile up
+fn bench_signals(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let symbols = universe();
+    let (engine, mut results) = runtime.block_on(async {
+        let risk_limits = RiskLimits {
+            max_positions: SYMBOLS * 2,
+            max_orders_per_minute: u64::MAX,
+            max_orders_per_minute_per_symbol: u64::MAX,
+            max_position_size: f64::MAX,
+            max_leverage: f64::MAX,
+            ..Default::default()
+        };
+        let config = PaperTradingConfig { risk_limits, ..Default::default() };
+        let mut engine = PaperTradingEngine::new(config);
+        let results = engine.position_manager().outcome_publisher().subscribe_signals();
+        for symbol in &symbols {
+            engine.update_price(symbol.clone(), 100.0);
+        }
+        engine.start().await.unwrap();
+        (engine, results)
+    });
+
+    let mut group = c.benchmark_group("engine");
+    group.throughput(Throughput::Elements(1));
+
+    let mut i = 0usize;
+    group.bench_function("signal_to_result", |b| {
+        b.iter(|| {
+            let symbol = &symbols[(i / 2) % SYMBOLS];
+            let action = if i.is_multiple_of(2) { SignalAction::Buy { size_hint: Some(1.0) } } else { SignalAction::Close { position_id: None } };
+            i += 1;
+            runtime.block_on(async {
+                engine.process_signal(signal(symbol, action)).await.unwrap();
+                tokio::time::timeout(Duration::from_secs(5), results.recv()).await.unwrap().unwrap()
+            })
+        })
+    });
+
+    group.finish();
+    runtime.block_on(engine.stop()).unwrap();
+}
+
+/// One pass of the matching loop over resting and marketable orders
+fn bench_order_matching(c: &mut Criterion) {
+    let symbols = universe();
+    let prices: DashMap<Symbol, f64> = symbols.iter().map(|symbol| (symbol.clone(), 100.0)).collect();
+
+    let mut group = c.benchmark_group("order_manager");
+
+    // Limits below the market rest on every pass
+    let resting = OrderManager::new(0.1, SlippageModel::Fixed(0.0));
+    for symbol in &symbols {
+        resting.submit_order(Order::limit(symbol.clone(), Exchange::Binance, Side::Buy, 1.0, 90.0)).unwrap();
+    }
+    group.throughput(Throughput::Elements(SYMBOLS as u64));
+    group.bench_function("process_resting_1k", |b| b.iter(|| resting.process_orders(black_box(&prices)).unwrap()));
+
+    group.throughput(Throughput::Elements(100));
+    group.bench_function("fill_100_market", |b| {
+        b.iter_batched(
+            || {
+                let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0));
+                for symbol in &symbols[..100] {
+                    manager.submit_order(Order::market(symbol.clone(), Exchange::Binance, Side::Buy, 1.0)).unwrap();
+                }
+                manager
+            },
+            |manager| manager.process_orders(&prices).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn bench_metrics(c: &mut Criterion) {
+    let symbols = universe();
+    let collector = MetricsCollector::new();
+    let histograms = TradingHistograms::default();
+    let mut filled = Order::market(symbols[0].clone(), Exchange::Binance, Side::Buy, 1.0);
+    filled.status = OrderStatus::Filled;
+    filled.filled_time = Some(filled.created_time + 25);
+
+    let mut group = c.benchmark_group("metrics");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("record_fill", |b| b.iter(|| histograms.record_fill(black_box(&filled))));
+
+    let mut i = 0usize;
+    group.bench_function("update_market_data", |b| {
+        b.iter(|| {
+            let price = 100.0 + (i % 100) as f64 * 0.01;
+            collector.update_market_data(symbols[i % SYMBOLS].clone(), black_box(price));
+            i += 1;
+        })
+    });
+
+    let mut i = 0usize;
+    group.bench_function("record_signal", |b| {
+        b.iter(|| {
+            let signal = signal(&symbols[i % SYMBOLS], SignalAction::Buy { size_hint: None });
+            collector.record_signal(black_box(&signal));
+            i += 1;
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = gate::criterion();
+    targets = bench_signals, bench_order_matching, bench_metrics
+}
+
+fn main() {
+    let started = SystemTime::now();
+    benches();
+    gate::criterion().configure_from_args().final_summary();
+    gate::finish(started);
+}
@@ -0,0 +1,117 @@
+//! Performance regression gate over criterion results
+//!
+//! Criterion compares a run with the previous one but never fails it, so a
+//! slowdown only shows if someone reads the report. With `BENCH_GATE=save` a
+//! bench run records the mean time of every benchmark it ran into
+//! `benches/baseline.json`; with `BENCH_GATE=check` it exits non-zero when a
+//! benchmark got slower than its baseline by more than
+//! `BENCH_GATE_TOLERANCE_PCT` percent (10 by default):
+//!
+//! ```text
+//! BENCH_GATE=check cargo bench -p neuromorphic-core
+//! ```
+//!
+//! Times are only comparable on the machine that recorded them, so record
+//! the baseline on the machine that runs the gate before a release.
+
+use criterion::Criterion;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const DEFAULT_TOLERANCE_PCT: f64 = 10.0;
+
+/// Criterion writing its results where the gate reads them
+pub fn criterion() -> Criterion {
+    Criterion::default().output_directory(&results_dir())
+}
+
+/// Save or check the benchmarks that ran since `started`, per `BENCH_GATE`
+pub fn finish(started: SystemTime) {
+    let Ok(mode) = std::env::var("BENCH_GATE") else { return };
+    let results = collect(&results_dir(), started);
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/baseline.json");
+    let mut baseline: BTreeMap<String, f64> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+
+    match mode.as_str() {
+        "save" => {
+            baseline.extend(results);
+            let json = serde_json::to_string_pretty(&baseline).expect("baseline serializes");
+            std::fs::write(&path, json + "\n").unwrap_or_else(|e| panic!("writing {}: {}", path.display(), e));
+            println!("Saved {} benchmark baselines to {}", baseline.len(), path.display());
+        }
+        "check" => {
+            let tolerance = std::env::var("BENCH_GATE_TOLERANCE_PCT")
+                .ok()
+                .and_then(|pct| pct.parse().ok())
+                .unwrap_or(DEFAULT_TOLERANCE_PCT);
+            let mut regressions = 0;
+            for (id, mean_ns) in &results {
+                let Some(base_ns) = baseline.get(id) else {
+                    println!("{:<50} {:>12.1} ns  (no baseline)", id, mean_ns);
+                    continue;
+                };
+                let change_pct = (mean_ns / base_ns - 1.0) * 100.0;
+                let regressed = change_pct > tolerance;
+                regressions += regressed as usize;
+                println!(
+                    "{:<50} {:>12.1} ns  {:>+7.1}%{}",
+                    id,
+                    mean_ns,
+                    change_pct,
+                    if regressed { "  REGRESSION" } else { "" }
+                );
+            }
+            if regressions > 0 {
+                eprintln!("{} benchmark(s) slower than the baseline by more than {}%", regressions, tolerance);
+                std::process::exit(1);
+            }
+        }
+        other => panic!("BENCH_GATE must be 'save' or 'check', not '{}'", other),
+    }
+}
+
+fn results_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../target"))
+        .join("criterion")
+}
+
+/// Mean time in ns by benchmark ID, e.g. `engine/update_price`, of the
+/// estimates written since `started`
+fn collect(dir: &Path, started: SystemTime) -> BTreeMap<String, f64> {
+    let mut results = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if !path.ends_with("new/estimates.json") {
+                continue;
+            }
+            let fresh = entry.metadata().and_then(|m| m.modified()).is_ok_and(|modified| modified >= started);
+            let mean = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+                .and_then(|estimates| estimates["mean"]["point_estimate"].as_f64());
+            let id = path
+                .parent()
+                .and_then(Path::parent)
+                .and_then(|bench| bench.strip_prefix(results_dir()).ok())
+                .map(|id| id.to_string_lossy().replace('\\', "/"));
+            if let (true, Some(mean), Some(id)) = (fresh, mean, id) {
+                results.insert(id, mean);
+            }
+        }
+    }
+    results
+}
@@ -0,0 +1,97 @@
+//! Tick -> P&L hot path with a market scanner sized universe
+//!
+//! Run with `cargo bench -p neuromorphic-core --bench tick_to_pnl`. The
+//! target is at least 100k price updates per second; see `support/gate.rs`
+//! for checking the results against the baseline.
+
+use criterion::{black_box, criterion_group, BatchSize, Criterion, Throughput};
+use neuromorphic_core::exchanges::{Exchange, Side, Symbol};
+use neuromorphic_core::paper_trading::{PaperTradingConfig, PaperTradingEngine, PositionManager};
+use std::time::SystemTime;
+
+#[path = "support/gate.rs"]
+mod gate;
+
+const SYMBOLS: usize = 5_000;
+const POSITIONS_PER_SYMBOL: usize = 2;
+
+fn universe() -> Vec<Symbol> {
+    (0..SYMBOLS).map(|i| Symbol::new(format!("SYM{}USDT", i))).collect()
+}
+
+fn open_positions(manager: &PositionManager, symbols: &[Symbol]) {
+    for symbol in symbols {
+        for n in 0..POSITIONS_PER_SYMBOL {
+            let side = if n % 2 == 0 { Side::Buy } else { Side::Sell };
+            manager
+                .open_position(symbol.clone(), Exchange::Binance, side, 1.0, 100.0, 0.1, 0.0)
+                .unwrap();
+        }
+    }
+}
+
+fn bench_position_manager(c: &mut Criterion) {
+    let symbols = universe();
+    let manager = PositionManager::new();
+    open_positions(&manager, &symbols);
+
+    let mut group = c.benchmark_group("position_manager");
+    group.throughput(Throughput::Elements(1));
+
+    let mut i = 0usize;
+    group.bench_function("update_symbol_price", |b| {
+        b.iter(|| {
+            let symbol = &symbols[i % SYMBOLS];
+            let price = 100.0 + (i % 100) as f64 * 0.01;
+            manager.update_symbol_price(black_box(symbol), black_box(price));
+            i += 1;
+        })
+    });
+
+    let mut i = 0usize;
+    group.bench_function("check_exits_for_symbol", |b| {
+        b.iter(|| {
+            let exits = manager.check_exits_for_symbol(black_box(&symbols[i % SYMBOLS]), 100.0);
+            i += 1;
+            exits
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_engine(c: &mut Criterion) {
+    let symbols = universe();
+    let engine = PaperTradingEngine::new(PaperTradingConfig::default());
+    open_positions(engine.position_manager(), &symbols);
+
+    let mut group = c.benchmark_group("engine");
+    group.throughput(Throughput::Elements(1));
+
+    let mut i = 0usize;
+    group.bench_function("update_price", |b| {
+        b.iter_batched(
+            || {
+                i += 1;
+                (symbols[i % SYMBOLS].clone(), 100.0 + (i % 100) as f64 * 0.01)
+            },
+            |(symbol, price)| engine.update_price(symbol, price),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = gate::criterion();
+    targets = bench_position_manager, bench_engine
+}
+
+fn main() {
+    let started = SystemTime::now();
+    benches();
+    gate::criterion().configure_from_args().final_summary();
+    gate::finish(started);
+}
@@ -28,6 +28,7 @@ async fn main() -> Result<()> {
             momentum_lookback_periods: vec![5, 15, 30, 60],
             volatility_threshold: 2.0,
             volume_spike_threshold: 3.0,
+            ..Default::default()
         },
         trading_config: PaperTradingConfig {
             initial_capital: 1000.0,
@@ -43,6 +44,7 @@ async fn main() -> Result<()> {
         enable_auto_trading: true,
         min_opportunity_confidence: 0.72,
         portfolio_heat: 0.12,
+        ..Default::default()
     };
 
     let mut trading_system = AutonomousTradingSystem::new(autonomous_config);
@@ -41,6 +41,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.9,
                 market_regime: "strong_uptrend".to_string(),
                 volatility: 0.025,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -54,6 +55,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.7,
                 market_regime: "consolidation".to_string(),
                 volatility: 0.018,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -67,6 +69,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.95,
                 market_regime: "risk_off".to_string(),
                 volatility: 0.045,
+                ..Default::default()
             },
         },
     ];
@@ -139,6 +142,8 @@ fn get_action_type(action: &SignalAction) -> &'static str {
         SignalAction::Buy { .. } => "BUY",
         SignalAction::Sell { .. } => "SELL",
         SignalAction::Close { .. } => "CLOSE",
+        SignalAction::ScaleIn { .. } => "SCALE IN",
+        SignalAction::ScaleOut { .. } => "SCALE OUT",
         SignalAction::Hold => "HOLD",
     }
 }
\ No newline at end of file
@@ -7,6 +7,7 @@ use neuromorphic_core::{
     TradingSignal, SignalAction, SignalMetadata,
     Symbol, Exchange, NeuromorphicPaperTrader, PaperTradingConfig
 };
+use neuromorphic_core::paper_trading::SeededRng;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -34,7 +35,7 @@ async fn main() -> anyhow::Result<()> {
     println!("✅ Neuromorphic Paper Trading Engine Started");
     
     // Start Grafana metrics API server
-    trader.start_metrics_api(3001).await;
+    let _api = trader.start_metrics_api(3001)?;
     println!("📈 Grafana Metrics API started on http://localhost:3001");
     println!("   Available endpoints:");
     println!("   - http://localhost:3001/health");
@@ -60,6 +61,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.9,
                 market_regime: "strong_uptrend".to_string(),
                 volatility: 0.025,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -73,6 +75,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.7,
                 market_regime: "consolidation".to_string(),
                 volatility: 0.018,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -86,6 +89,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.8,
                 market_regime: "mild_uptrend".to_string(),
                 volatility: 0.035,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -99,6 +103,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.6,
                 market_regime: "weak_downtrend".to_string(),
                 volatility: 0.045,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -112,6 +117,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.95,
                 market_regime: "risk_off".to_string(),
                 volatility: 0.055,
+                ..Default::default()
             },
         },
         // Additional signals for richer metrics
@@ -126,6 +132,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.75,
                 market_regime: "recovery".to_string(),
                 volatility: 0.030,
+                ..Default::default()
             },
         },
         TradingSignal {
@@ -139,6 +146,7 @@ async fn main() -> anyhow::Result<()> {
                 pattern_strength: 0.5,
                 market_regime: "sideways".to_string(),
                 volatility: 0.022,
+                ..Default::default()
             },
         },
     ];
@@ -239,7 +247,8 @@ async fn main() -> anyhow::Result<()> {
     println!();
     println!("💡 Press Ctrl+C to stop the demo");
     
-    // Keep the demo running to allow Grafana testing
+    // Keep the demo running to allow Grafana testing, with the same signals every run
+    let rng = SeededRng::new(12345);
     loop {
         tokio::time::sleep(std::time::Duration::from_secs(5)).await;
         
@@ -248,18 +257,19 @@ async fn main() -> anyhow::Result<()> {
             symbol: Symbol::new("BTC-USD"),
             exchange: Exchange::Binance,
             action: SignalAction::Hold,
-            confidence: 0.5 + (rand::random::<f64>() * 0.3),
-            urgency: 0.3 + (rand::random::<f64>() * 0.4),
+            confidence: rng.range(0.5, 0.8),
+            urgency: rng.range(0.3, 0.7),
             metadata: SignalMetadata {
-                spike_count: 50 + (rand::random::<u32>() % 100),
-                pattern_strength: 0.5 + (rand::random::<f64>() * 0.3),
+                spike_count: 50 + rng.below(100),
+                pattern_strength: rng.range(0.5, 0.8),
                 market_regime: "live_monitoring".to_string(),
-                volatility: 0.02 + (rand::random::<f64>() * 0.03),
+                volatility: rng.range(0.02, 0.05),
+                ..Default::default()
             },
         };
         
         // Update price with small random movement
-        let btc_price = 45000.0 + (rand::random::<f64>() - 0.5) * 2000.0;
+        let btc_price = rng.range(44000.0, 46000.0);
         trader.update_market_price(Symbol::new("BTC-USD"), btc_price);
         
         if let Err(e) = trader.process_prediction_signal(random_signal).await {
@@ -278,23 +288,8 @@ fn get_action_type(action: &SignalAction) -> &'static str {
         SignalAction::Buy { .. } => "BUY",
         SignalAction::Sell { .. } => "SELL",
         SignalAction::Close { .. } => "CLOSE",
+        SignalAction::ScaleIn { .. } => "SCALE IN",
+        SignalAction::ScaleOut { .. } => "SCALE OUT",
         SignalAction::Hold => "HOLD",
     }
 }
-
-// Simple random number generation (replace with proper crate if needed)
-mod rand {
-    use std::sync::atomic::{AtomicU64, Ordering};
-    
-    static SEED: AtomicU64 = AtomicU64::new(12345);
-    
-    pub fn random<T>() -> T 
-    where 
-        T: From<u64>
-    {
-        let current = SEED.load(Ordering::Relaxed);
-        let next = current.wrapping_mul(1103515245).wrapping_add(12345);
-        SEED.store(next, Ordering::Relaxed);
-        T::from(next)
-    }
-}
\ No newline at end of file
@@ -0,0 +1,120 @@
+//! Audit log of control actions
+//!
+//! Every request that would change state is recorded with the key that sent
+//! it and how it ended, including those refused for bad credentials, a
+//! read-only role or the rate limit. Records go to the `audit` tracing target
+//! and, with a path set, are appended to a JSON lines file that survives
+//! restarts.
+
+use super::auth::{Principal, Role};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// One control action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub key: Option<String>, // Name of the key used; None without valid credentials
+    pub role: Option<Role>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub status: u16,
+}
+
+impl AuditRecord {
+    pub fn new(principal: Option<&Principal>, method: &str, path: &str, query: Option<&str>, status: u16) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            key: principal.map(|p| p.name.clone()),
+            role: principal.map(|p| p.role),
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.map(str::to_string),
+            status,
+        }
+    }
+}
+
+/// Audit trail, logged and optionally appended to a file
+#[derive(Default)]
+pub struct AuditLog {
+    file: Option<Mutex<File>>,
+}
+
+impl AuditLog {
+    /// Records are only logged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records are also appended to the JSON lines file at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+        Ok(Self {
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    pub fn record(&self, record: &AuditRecord) {
+        tracing::info!(
+            target: "audit",
+            key = record.key.as_deref().unwrap_or("-"),
+            method = %record.method,
+            path = %record.path,
+            query = record.query.as_deref().unwrap_or(""),
+            status = record.status,
+            "Control action"
+        );
+        let Some(file) = &self.file else {
+            return;
+        };
+        let written = serde_json::to_string(record)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| writeln!(file.lock(), "{}", line).context("Failed to append to the audit log"));
+        if let Err(e) = written {
+            tracing::warn!(error = %e, "Failed to write audit record");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_are_appended() {
+        let path = std::env::temp_dir().join(format!("api-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let ops = Principal {
+            name: "ops".to_string(),
+            role: Role::Operator,
+            rate_limit_per_minute: None,
+            account: None,
+        };
+
+        AuditLog::open(&path).unwrap().record(&AuditRecord::new(Some(&ops), "POST", "/api/v1/control/pause", None, 200));
+        // Reopening appends rather than truncating
+        AuditLog::open(&path).unwrap().record(&AuditRecord::new(None, "DELETE", "/api/v1/control/blacklist/BTCUSDT", None, 401));
+
+        let records: Vec<AuditRecord> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].key.as_deref(), records[0].role, records[0].status), (Some("ops"), Some(Role::Operator), 200));
+        assert_eq!((records[1].key.as_deref(), records[1].status), (None, 401));
+        let _ = std::fs::remove_file(&path);
+    }
+}
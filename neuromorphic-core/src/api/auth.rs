@@ -0,0 +1,214 @@
+//! API keys and roles
+//!
+//! Keys are named in the `[api.keys.<name>]` config tables and sent as
+//! `Authorization: Bearer <key>` or `X-API-Key: <key>`. A read-only key may
+//! call every GET route; anything that changes state (watchlist, blacklist,
+//! pause, flatten, confidence overrides) needs an operator key. Each key may
+//! carry its own request rate limit, counted per minute.
+//!
+//! A key bound to an account is a tenant's, e.g. one competitor of a hosted
+//! simulation: whatever its role, it may only read that account's positions,
+//! orders, groups and statistics, the leaderboard, and submit signals to it.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use warp::http::request::Parts;
+use warp::http::{header, Method};
+
+/// Header carrying an API key as an alternative to a bearer token
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// What a caller may do; operators may do everything a read-only caller may
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    ReadOnly,
+    Operator,
+}
+
+impl Role {
+    /// Role a request needs: reads are open to read-only keys, every route
+    /// that changes state is a control action
+    pub fn required_for(method: &Method) -> Role {
+        if method == Method::GET || method == Method::HEAD {
+            Role::ReadOnly
+        } else {
+            Role::Operator
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::ReadOnly => "read_only",
+            Role::Operator => "operator",
+        })
+    }
+}
+
+/// Authenticated caller
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+    pub rate_limit_per_minute: Option<u32>,
+    pub account: Option<String>, // Tenant's account the caller is confined to
+}
+
+/// Identifies the caller of a request
+pub trait ApiAuth: Send + Sync {
+    /// The caller, or None if the request carries no valid credentials
+    fn authenticate(&self, request: &Parts) -> Option<Principal>;
+}
+
+/// One `[api.keys.<name>]` entry
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    pub key: String,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default)]
+    pub account: Option<String>, // Makes the key a tenant's, confined to this account
+}
+
+impl fmt::Debug for ApiKey {
+    // Keep the key out of logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("key", &"***")
+            .field("role", &self.role)
+            .field("rate_limit_per_minute", &self.rate_limit_per_minute)
+            .field("account", &self.account)
+            .finish()
+    }
+}
+
+/// Authentication against the configured keys, by name
+pub struct ApiKeys {
+    keys: BTreeMap<String, ApiKey>,
+}
+
+impl ApiKeys {
+    pub fn new(keys: BTreeMap<String, ApiKey>) -> Self {
+        Self { keys }
+    }
+}
+
+impl ApiAuth for ApiKeys {
+    fn authenticate(&self, request: &Parts) -> Option<Principal> {
+        let presented = request
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| request.headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()))?;
+        // Check every key so the time taken doesn't tell which one is closest
+        let mut found = None;
+        for (name, key) in &self.keys {
+            if constant_time_eq(presented.as_bytes(), key.key.as_bytes()) {
+                found = Some(Principal {
+                    name: name.clone(),
+                    role: key.role,
+                    rate_limit_per_minute: key.rate_limit_per_minute,
+                    account: key.account.clone(),
+                });
+            }
+        }
+        found
+    }
+}
+
+/// Routes a tenant's key may call; each answers only for the tenant's account
+pub fn tenant_route(method: &Method, path: &str) -> bool {
+    if method == Method::POST {
+        return path == "/api/v1/signals";
+    }
+    (method == Method::GET || method == Method::HEAD)
+        && (matches!(path, "/api/v1/positions" | "/api/v1/orders" | "/api/v1/groups" | "/api/v1/leaderboard" | "/api/v1/leaderboard/scores" | "/api/docs")
+            || path.starts_with("/api/v1/metrics/accounts/"))
+}
+
+// Compare without exiting at the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per caller, refilled continuously up to one minute's quota
+#[derive(Default)]
+pub(super) struct RateLimiter {
+    buckets: DashMap<String, Bucket>,
+}
+
+impl RateLimiter {
+    /// Take one request from the caller's quota at `now`, or return how long
+    /// until the next one is allowed
+    pub fn acquire(&self, name: &str, per_minute: u32, now: Instant) -> Result<(), Duration> {
+        let capacity = per_minute.max(1) as f64;
+        let per_sec = capacity / 60.0;
+        let mut bucket = self.buckets.entry(name.to_string()).or_insert_with(|| Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> Parts {
+        let mut request = warp::http::Request::get("/api/v1/orders");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_keys_roles_and_rate_limits() {
+        let key = |key: &str, role: Role| ApiKey { key: key.to_string(), role, rate_limit_per_minute: Some(2), account: None };
+        let keys = ApiKeys::new(BTreeMap::from([
+            ("grafana".to_string(), key("read-key", Role::ReadOnly)),
+            ("ops".to_string(), key("ops-key", Role::Operator)),
+        ]));
+
+        let grafana = keys.authenticate(&request(&[("authorization", "Bearer read-key")])).unwrap();
+        assert_eq!((grafana.name.as_str(), grafana.role), ("grafana", Role::ReadOnly));
+        assert_eq!(keys.authenticate(&request(&[(API_KEY_HEADER, "ops-key")])).unwrap().role, Role::Operator);
+        assert!(keys.authenticate(&request(&[("authorization", "Bearer nope")])).is_none());
+        assert!(keys.authenticate(&request(&[])).is_none());
+        assert!(Role::Operator >= Role::required_for(&Method::DELETE));
+        assert!(Role::ReadOnly < Role::required_for(&Method::POST));
+
+        // Two a minute: the third waits 30 seconds for its token
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.acquire("grafana", 2, start).is_ok());
+        assert!(limiter.acquire("grafana", 2, start).is_ok());
+        assert_eq!(limiter.acquire("grafana", 2, start).unwrap_err().as_secs_f64().round(), 30.0);
+        assert!(limiter.acquire("ops", 2, start).is_ok());
+        assert!(limiter.acquire("grafana", 2, start + Duration::from_secs(30)).is_ok());
+    }
+}
@@ -0,0 +1,24 @@
+//! Demo data for dashboards without a live feed
+//!
+//! Only built with the `demo` feature. Handlers fall back to these series
+//! where real data is missing and mark their response with `"demo": true`, so
+//! a dashboard never mistakes them for market data.
+
+use serde_json::{json, Value};
+
+/// Hourly points of a made-up price wave around `base_price`, ending now
+pub(super) fn price_history(base_price: f64, hours: u64) -> Vec<Value> {
+    let now = chrono::Utc::now();
+    (0..hours)
+        .map(|i| {
+            let timestamp = now - chrono::Duration::hours((hours - i) as i64);
+            let price = base_price + (i as f64 * 0.1).sin() * 2.0 + ((i % 7) as f64 - 3.0) * 1.5;
+            json!({
+                "timestamp": timestamp.timestamp_millis(),
+                "price": price.max(1.0),
+                "volume": 1000000.0 + ((i % 10) as f64 * 50000.0),
+                "demo": true
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,266 @@
+//! Liveness and readiness probes
+//!
+//! `/health/live` answers 200 whenever the server can answer at all, so an
+//! orchestrator restarts the process only when it hangs. `/health/ready`, and
+//! `/health` as before, report every dependency the system has reported to
+//! the metrics collector: the engines, each exchange's market data stream,
+//! the age of the newest price, the queues and the storage directories. They
+//! answer 503 while any of them is down, so traffic and rollouts wait for the
+//! instance. A degraded dependency, e.g. a reconnecting stream or a queue
+//! close to full, is reported but keeps the instance ready. Dependencies
+//! never reported, like the exchanges of a backtest, are left out rather than
+//! counted as down.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+use crate::exchanges::ConnectionStatus;
+use crate::metrics::MetricsCollector;
+use crate::paper_trading::QueueStatistics;
+
+/// Settings of the readiness checks, `[api.health]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    pub max_data_age_secs: u64,   // Newest price older than this is down; 0 disables
+    pub queue_degraded_pct: f64,  // Queues fuller than this are degraded
+    pub storage: Vec<PathBuf>,    // Directories that must be writable, e.g. snapshots and reports
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_data_age_secs: 60,
+            queue_degraded_pct: 90.0,
+            storage: Vec::new(),
+        }
+    }
+}
+
+/// Ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EngineHealth {
+    pub account: String,
+    pub running: bool,
+    pub status: HealthStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExchangeHealth {
+    pub exchange: String,
+    pub connection: String, // connected, connecting, reconnecting, disconnected or failed
+    pub last_data_age_ms: Option<u64>,
+    pub status: HealthStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MarketDataHealth {
+    pub symbols: usize,
+    pub last_update_age_ms: Option<u64>, // Of the newest price of any symbol
+    pub status: HealthStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueueHealth {
+    pub queue: String,
+    pub depth: usize,
+    pub capacity: usize,
+    pub dropped: u64,
+    pub rejected: u64,
+    pub status: HealthStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorageHealth {
+    pub path: String,
+    pub error: Option<String>, // Why the directory can't be written to
+    pub status: HealthStatus,
+}
+
+/// Answer of `/health` and `/health/ready`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus, // Worst of the dependencies
+    pub ready: bool,
+    pub service: String,
+    pub timestamp: DateTime<Utc>,
+    pub engines: Vec<EngineHealth>,
+    pub exchanges: Vec<ExchangeHealth>,
+    pub market_data: MarketDataHealth,
+    pub queues: Vec<QueueHealth>,
+    pub storage: Vec<StorageHealth>,
+}
+
+impl HealthReport {
+    /// Check what `metrics` was last told and the storage directories now
+    pub fn check(metrics: &MetricsCollector, config: &HealthConfig) -> Self {
+        let now = metrics.clock().now();
+        let age_ms = |time: DateTime<Utc>| (now - time).num_milliseconds().max(0) as u64;
+        let stale = |age: u64| config.max_data_age_secs > 0 && age > config.max_data_age_secs * 1000;
+
+        let engines = metrics
+            .get_engines_running()
+            .into_iter()
+            .map(|(account, running)| EngineHealth {
+                account,
+                running,
+                status: if running { HealthStatus::Ok } else { HealthStatus::Down },
+            })
+            .collect();
+
+        let exchanges = metrics
+            .get_feed_status()
+            .into_iter()
+            .map(|(exchange, feed)| {
+                let last_data_age_ms = feed.last_data.map(age_ms);
+                let status = match feed.connection {
+                    ConnectionStatus::Connected if last_data_age_ms.is_some_and(stale) => HealthStatus::Degraded,
+                    ConnectionStatus::Connected => HealthStatus::Ok,
+                    ConnectionStatus::Connecting | ConnectionStatus::Reconnecting => HealthStatus::Degraded,
+                    ConnectionStatus::Disconnected | ConnectionStatus::Failed => HealthStatus::Down,
+                };
+                ExchangeHealth {
+                    exchange,
+                    connection: format!("{:?}", feed.connection).to_lowercase(),
+                    last_data_age_ms,
+                    status,
+                }
+            })
+            .collect();
+
+        let (symbols, last_update) = metrics.get_market_data_freshness();
+        let last_update_age_ms = last_update.map(age_ms);
+        let market_data = MarketDataHealth {
+            symbols,
+            last_update_age_ms,
+            status: if last_update_age_ms.is_some_and(stale) { HealthStatus::Down } else { HealthStatus::Ok },
+        };
+
+        let queue_metrics = metrics.get_queue_metrics();
+        let queue = |name: &str, stats: &QueueStatistics| QueueHealth {
+            queue: name.to_string(),
+            depth: stats.depth,
+            capacity: stats.capacity,
+            dropped: stats.dropped,
+            rejected: stats.rejected,
+            status: if stats.capacity > 0 && stats.depth as f64 > stats.capacity as f64 * config.queue_degraded_pct / 100.0 {
+                HealthStatus::Degraded
+            } else {
+                HealthStatus::Ok
+            },
+        };
+        let queues = vec![queue("signals", &queue_metrics.signals), queue("order_events", &queue_metrics.order_events)];
+
+        let storage = config
+            .storage
+            .iter()
+            .map(|path| {
+                let error = probe_writable(path).err().map(|e| e.to_string());
+                StorageHealth {
+                    path: path.display().to_string(),
+                    status: if error.is_some() { HealthStatus::Down } else { HealthStatus::Ok },
+                    error,
+                }
+            })
+            .collect();
+
+        let mut report = Self {
+            status: HealthStatus::Ok,
+            ready: true,
+            service: "neuromorphic-trading-metrics".to_string(),
+            timestamp: now,
+            engines,
+            exchanges,
+            market_data,
+            queues,
+            storage,
+        };
+        report.status = report.worst();
+        report.ready = report.status != HealthStatus::Down;
+        report
+    }
+
+    /// What is down, e.g. "engine default" or "storage /state"
+    pub fn problems(&self) -> Vec<String> {
+        let down = |status: HealthStatus| status == HealthStatus::Down;
+        let mut problems: Vec<String> = self.engines.iter().filter(|e| down(e.status)).map(|e| format!("engine {}", e.account)).collect();
+        problems.extend(self.exchanges.iter().filter(|e| down(e.status)).map(|e| format!("exchange {} {}", e.exchange, e.connection)));
+        if down(self.market_data.status) {
+            problems.push(format!("market data {}s old", self.market_data.last_update_age_ms.unwrap_or_default() / 1000));
+        }
+        problems.extend(self.storage.iter().filter(|s| down(s.status)).map(|s| format!("storage {}", s.path)));
+        problems
+    }
+
+    fn worst(&self) -> HealthStatus {
+        self.engines
+            .iter()
+            .map(|e| e.status)
+            .chain(self.exchanges.iter().map(|e| e.status))
+            .chain(std::iter::once(self.market_data.status))
+            .chain(self.queues.iter().map(|q| q.status))
+            .chain(self.storage.iter().map(|s| s.status))
+            .max()
+            .unwrap_or(HealthStatus::Ok)
+    }
+}
+
+/// Create and remove a file in `dir`: the only check that holds for
+/// read-only mounts and full disks alike
+fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".health-{}", std::process::id()));
+    std::fs::write(&probe, b"ok")?;
+    std::fs::remove_file(probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::clock::SimulatedClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_report_is_as_bad_as_its_worst_dependency() {
+        let clock = Arc::new(SimulatedClock::new(1_700_000_000_000));
+        let metrics = MetricsCollector::with_clock(clock.clone());
+        let config = HealthConfig { max_data_age_secs: 30, ..Default::default() };
+
+        // Nothing reported yet: nothing to be down
+        let report = HealthReport::check(&metrics, &config);
+        assert!(report.ready && report.engines.is_empty() && report.exchanges.is_empty());
+
+        metrics.update_engine_running("default", true);
+        metrics.update_feed_status(Exchange::Binance, ConnectionStatus::Connected, Some(Duration::from_secs(1)));
+        metrics.update_feed_status(Exchange::Coinbase, ConnectionStatus::Reconnecting, None);
+        metrics.update_market_data(crate::exchanges::Symbol::new("BTC-USD"), 50_000.0);
+        let report = HealthReport::check(&metrics, &config);
+        assert_eq!((report.status, report.ready), (HealthStatus::Degraded, true));
+        assert_eq!(report.exchanges[0].last_data_age_ms, Some(1_000));
+
+        // Prices stop coming; then the engine stops
+        clock.advance(Duration::from_secs(31));
+        let report = HealthReport::check(&metrics, &config);
+        assert_eq!((report.market_data.status, report.ready), (HealthStatus::Down, false));
+        assert_eq!(report.problems(), vec!["market data 31s old"]);
+        assert_eq!(report.exchanges[0].status, HealthStatus::Degraded);
+        metrics.update_market_data(crate::exchanges::Symbol::new("BTC-USD"), 50_010.0);
+        metrics.update_engine_running("default", false);
+        assert!(!HealthReport::check(&metrics, &config).ready);
+
+        let missing = HealthConfig { storage: vec![PathBuf::from("/nonexistent/state")], ..Default::default() };
+        let report = HealthReport::check(&MetricsCollector::new(), &missing);
+        assert!(report.storage[0].error.is_some() && !report.ready);
+    }
+}
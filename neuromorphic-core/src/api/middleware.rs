@@ -0,0 +1,171 @@
+//! Request middleware of the API server
+//!
+//! Runs around the warp routes at the hyper service level, so it also covers
+//! requests the routes reject. With authentication on, a request needs a key
+//! (401), a role allowed on the route (403) and room in the key's rate limit
+//! (429) before it reaches the routes, which then run under a timeout. The
+//! health probes and CORS preflights are always let through. A tenant's key,
+//! one bound to an account, is refused (403) outside the tenant routes, and
+//! the caller is passed on to the routes so they answer for its account only.
+//!
+//! Every request gets one log line: successful ones at debug since Grafana
+//! polls every few seconds, client errors at info and server errors and
+//! timeouts at warn. Requests that would change state also go to the audit log.
+
+use super::audit::{AuditLog, AuditRecord};
+use super::auth::{self, ApiAuth, Principal, RateLimiter, Role};
+use super::ErrorResponse;
+use hyper::service::Service;
+use hyper::{Body, Request, Response};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use warp::http::request::Parts;
+use warp::http::{header, HeaderValue, Method, StatusCode};
+use warp::Reply;
+
+/// Authentication, rate limits, timeout, logging and audit around the routes
+#[derive(Clone)]
+pub(super) struct Middleware {
+    pub auth: Option<Arc<dyn ApiAuth>>,
+    pub request_timeout: Duration,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub audit: Arc<AuditLog>,
+}
+
+impl Middleware {
+    pub async fn handle<S>(self, mut routes: S, request: Request<Body>) -> Result<Response<Body>, Infallible>
+    where
+        S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>,
+    {
+        let started = Instant::now();
+        let (mut parts, body) = request.into_parts();
+        let method = parts.method.clone();
+        let path = parts.uri.path().to_string();
+        let query = parts.uri.query().map(str::to_string);
+
+        let (principal, refused) = self.admit(&parts, started);
+        if let Some(principal) = &principal {
+            parts.extensions.insert(principal.clone());
+        }
+        let response = match refused {
+            Some(response) => response,
+            None => match tokio::time::timeout(self.request_timeout, routes.call(Request::from_parts(parts, body))).await {
+                Ok(response) => response.unwrap_or_else(|never| match never {}),
+                Err(_) => error_response(StatusCode::REQUEST_TIMEOUT, "Request timed out"),
+            },
+        };
+
+        let status = response.status();
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let key = principal.as_ref().map_or("-", |p| p.name.as_str());
+        if status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT {
+            tracing::warn!(%method, %path, status = status.as_u16(), key, elapsed_ms, "API request failed");
+        } else if status.is_client_error() {
+            tracing::info!(%method, %path, status = status.as_u16(), key, elapsed_ms, "API request rejected");
+        } else {
+            tracing::debug!(%method, %path, status = status.as_u16(), key, elapsed_ms, "API request");
+        }
+        if method != Method::OPTIONS && Role::required_for(&method) == Role::Operator {
+            self.audit.record(&AuditRecord::new(principal.as_ref(), method.as_str(), &path, query.as_deref(), status.as_u16()));
+        }
+        Ok(response)
+    }
+
+    /// The caller, and the response refusing the request if it may not go on
+    fn admit(&self, request: &Parts, now: Instant) -> (Option<Principal>, Option<Response<Body>>) {
+        let Some(auth) = &self.auth else {
+            return (None, None);
+        };
+        if request.method == Method::OPTIONS || request.uri.path() == "/health" || request.uri.path().starts_with("/health/") {
+            return (None, None);
+        }
+
+        let Some(principal) = auth.authenticate(request) else {
+            let mut response = error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+            response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return (None, Some(response));
+        };
+        let required = Role::required_for(&request.method);
+        if let Some(account) = &principal.account {
+            if !auth::tenant_route(&request.method, request.uri.path()) {
+                let message = format!("Key '{}' is bound to account '{}'; this route isn't open to it", principal.name, account);
+                return (Some(principal), Some(error_response(StatusCode::FORBIDDEN, &message)));
+            }
+        } else if principal.role < required {
+            let message = format!("Key '{}' is {}; this route needs {}", principal.name, principal.role, required);
+            return (Some(principal), Some(error_response(StatusCode::FORBIDDEN, &message)));
+        }
+        if let Some(per_minute) = principal.rate_limit_per_minute {
+            if let Err(wait) = self.rate_limiter.acquire(&principal.name, per_minute, now) {
+                let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded");
+                response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(wait.as_secs_f64().ceil() as u64));
+                return (Some(principal), Some(response));
+            }
+        }
+        (Some(principal), None)
+    }
+}
+
+fn error_response(code: StatusCode, message: &str) -> Response<Body> {
+    let json = warp::reply::json(&ErrorResponse {
+        error: message.to_string(),
+        code: code.as_u16(),
+    });
+    warp::reply::with_status(json, code).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::auth::{ApiKey, ApiKeys};
+    use hyper::service::service_fn;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_roles_rate_limit_and_timeout() {
+        let key = |key: &str, role: Role, rate_limit_per_minute| ApiKey { key: key.to_string(), role, rate_limit_per_minute, account: None };
+        let middleware = Middleware {
+            auth: Some(Arc::new(ApiKeys::new(BTreeMap::from([
+                ("grafana".to_string(), key("read-key", Role::ReadOnly, Some(2))),
+                ("ops".to_string(), key("ops-key", Role::Operator, None)),
+                ("alice".to_string(), ApiKey { account: Some("alice".to_string()), ..key("alice-key", Role::ReadOnly, None) }),
+            ])))),
+            request_timeout: Duration::from_millis(50),
+            rate_limiter: Arc::default(),
+            audit: Arc::new(AuditLog::new()),
+        };
+        let routes = || {
+            service_fn(|request: Request<Body>| async move {
+                if request.uri().path() == "/slow" {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            })
+        };
+        let status = |method: Method, path: &str, key: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(path);
+            if let Some(key) = key {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", key));
+            }
+            let response = middleware.clone().handle(routes(), request.body(Body::empty()).unwrap());
+            async move { response.await.unwrap().status() }
+        };
+
+        assert_eq!(status(Method::GET, "/health", None).await, StatusCode::OK);
+        assert_eq!(status(Method::GET, "/api/v1/orders", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Method::GET, "/api/v1/orders", Some("wrong")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Method::GET, "/api/v1/orders", Some("read-key")).await, StatusCode::OK);
+        assert_eq!(status(Method::POST, "/api/v1/control/pause", Some("read-key")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(Method::GET, "/api/v1/orders", Some("read-key")).await, StatusCode::OK);
+        assert_eq!(status(Method::GET, "/api/v1/orders", Some("read-key")).await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(status(Method::POST, "/api/v1/control/pause", Some("ops-key")).await, StatusCode::OK);
+        assert_eq!(status(Method::GET, "/slow", Some("ops-key")).await, StatusCode::REQUEST_TIMEOUT);
+
+        // A tenant may submit signals whatever its role, and reach nothing else
+        assert_eq!(status(Method::POST, "/api/v1/signals", Some("alice-key")).await, StatusCode::OK);
+        assert_eq!(status(Method::GET, "/api/v1/metrics/accounts/alice", Some("alice-key")).await, StatusCode::OK);
+        assert_eq!(status(Method::GET, "/api/v1/metrics/portfolio", Some("alice-key")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(Method::POST, "/api/v1/control/flatten", Some("alice-key")).await, StatusCode::FORBIDDEN);
+    }
+}
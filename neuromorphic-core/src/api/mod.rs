@@ -1,12 +1,53 @@
 //! REST API for Grafana integration
 //! 
 //! Provides HTTP endpoints that Grafana can consume for real-time dashboards
+//!
+//! The server runs until its `ApiHandle` is shut down or dropped. Shutting
+//! down stops accepting connections and lets requests in flight finish, for
+//! at most the configured shutdown timeout.
+//!
+//! Without configured keys every route is open; see `auth` for keys and roles.
+//! Routes over accounts answer a tenant's key for its own account only.
 
+pub mod audit;
+pub mod auth;
+#[cfg(feature = "demo")]
+mod demo;
+pub mod health;
+pub mod middleware;
+pub mod openapi;
+
+pub use audit::{AuditLog, AuditRecord};
+pub use auth::{ApiAuth, ApiKey, ApiKeys, Principal, Role};
+pub use health::{HealthConfig, HealthReport, HealthStatus};
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use warp::{Filter, Rejection, Reply};
+use serde::Serialize;
 use serde_json::json;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
-use crate::metrics::MetricsCollector;
+use crate::control::AutonomousControl;
+use crate::exchanges::Symbol;
+use crate::market_scanner::universe::universe_key;
+use crate::market_scanner::{Granularity, MarketScannerService, StrategyHitRate, SymbolStats, TrackedOpportunity};
+use crate::metrics::{MetricSample, MetricsCollector, TradingMetrics};
+use crate::paper_trading::{
+    groups, import, ImportedPosition, Leaderboard, LeaderboardMetric, Order, OrderManager, PaperTradingEngine, Position, PositionGroup, PositionManager,
+    QueueError, Scoreboard, ScoringConfig, SignalSubmitter, TradingSignal, DEFAULT_ACCOUNT,
+};
+
+/// Rows a positions or orders page holds unless the query asks for fewer
+const DEFAULT_PAGE: usize = 100;
+const MAX_PAGE: usize = 1000;
 
 /// API error types
 #[derive(Debug)]
@@ -16,34 +57,268 @@ pub struct ApiError {
 
 impl warp::reject::Reject for ApiError {}
 
+/// A resource the route serves doesn't exist, with the reason
+#[derive(Debug)]
+pub struct NotFound {
+    pub message: String,
+}
+
+impl warp::reject::Reject for NotFound {}
+
+/// The caller may not see or act on the resource, e.g. another tenant's account
+#[derive(Debug)]
+pub struct Forbidden {
+    pub message: String,
+}
+
+impl warp::reject::Reject for Forbidden {}
+
+/// The request can't be taken now, e.g. the signal queue is full; retry later
+#[derive(Debug)]
+pub struct Busy {
+    pub message: String,
+}
+
+impl warp::reject::Reject for Busy {}
+
+/// Settings of the API server
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    pub port: u16,
+    pub keys: BTreeMap<String, ApiKey>, // By name; required on every route but the /health probes once any is set
+    pub audit_log: Option<PathBuf>,     // JSON lines file of control actions
+    pub request_timeout: Duration,
+    pub shutdown_timeout: Duration, // How long requests in flight may take to finish on shutdown
+    pub health: HealthConfig,
+    pub scoring: ScoringConfig, // Of the competition leaderboard
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            port: 3002,
+            keys: BTreeMap::new(),
+            audit_log: None,
+            request_timeout: Duration::from_secs(30),
+            shutdown_timeout: Duration::from_secs(10),
+            health: HealthConfig::default(),
+            scoring: ScoringConfig::default(),
+        }
+    }
+}
+
 /// API server for metrics endpoints
 pub struct MetricsApiServer {
     metrics_collector: Arc<MetricsCollector>,
+    scanner: Option<MarketScannerService>,
+    control: Option<AutonomousControl>,
+    books: Vec<AccountBook>,
     port: u16,
+    auth: Option<Arc<dyn ApiAuth>>,
+    audit: Arc<AuditLog>,
+    request_timeout: Duration,
+    shutdown_timeout: Duration,
+    health: HealthConfig,
+    scoring: ScoringConfig,
+}
+
+/// Running API server
+pub struct ApiHandle {
+    local_addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<hyper::Result<()>>,
+    shutdown_timeout: Duration,
+}
+
+impl ApiHandle {
+    /// Address the server listens on, with the port picked when started on port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and wait for requests in flight, cutting
+    /// them off after the shutdown timeout
+    pub async fn shutdown(self) -> Result<()> {
+        let _ = self.shutdown.send(());
+        let mut task = self.task;
+        match tokio::time::timeout(self.shutdown_timeout, &mut task).await {
+            Ok(served) => served.context("API server task failed")?.context("API server failed"),
+            Err(_) => {
+                task.abort();
+                tracing::warn!(timeout = ?self.shutdown_timeout, "API requests still running at shutdown were cut off");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Positions and orders of one account
+#[derive(Clone)]
+struct AccountBook {
+    account: String,
+    positions: Arc<PositionManager>,
+    orders: Arc<OrderManager>,
+    signals: Option<SignalSubmitter>, // Takes signals through the API when set
 }
 
 impl MetricsApiServer {
     pub fn new(metrics_collector: Arc<MetricsCollector>, port: u16) -> Self {
+        let defaults = ApiConfig::default();
         Self {
             metrics_collector,
+            scanner: None,
+            control: None,
+            books: Vec::new(),
             port,
+            auth: None,
+            audit: Arc::new(AuditLog::new()),
+            request_timeout: defaults.request_timeout,
+            shutdown_timeout: defaults.shutdown_timeout,
+            health: defaults.health,
+            scoring: defaults.scoring,
         }
     }
 
-    /// Start the metrics API server
-    pub async fn start(&self) {
+    /// Server with the port, keys, audit log and timeouts of `config`
+    pub fn with_config(metrics_collector: Arc<MetricsCollector>, config: &ApiConfig) -> Result<Self> {
+        let mut server = Self::new(metrics_collector, config.port)
+            .with_request_timeout(config.request_timeout)
+            .with_shutdown_timeout(config.shutdown_timeout)
+            .with_health(config.health.clone())
+            .with_scoring(config.scoring.clone());
+        if !config.keys.is_empty() {
+            server = server.with_auth(ApiKeys::new(config.keys.clone()));
+        }
+        if let Some(path) = &config.audit_log {
+            server = server.with_audit_log(AuditLog::open(path)?);
+        }
+        Ok(server)
+    }
+
+    /// Identify every request's caller with `auth` and hold it to its role
+    /// and rate limit before routing it
+    pub fn with_auth(mut self, auth: impl ApiAuth + 'static) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Record control actions in `audit` rather than only logging them
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Arc::new(audit);
+        self
+    }
+
+    /// Answer 408 to requests the routes take longer than `timeout` to handle
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Thresholds and storage directories of the readiness checks
+    pub fn with_health(mut self, config: HealthConfig) -> Self {
+        self.health = config;
+        self
+    }
+
+    /// Weights of the competition scores
+    pub fn with_scoring(mut self, config: ScoringConfig) -> Self {
+        self.scoring = config;
+        self
+    }
+
+    /// Serve the scanner's universe, movers, regime and opportunities with their outcomes
+    pub fn with_scanner(mut self, scanner: MarketScannerService) -> Self {
+        self.scanner = Some(scanner);
+        self
+    }
+
+    /// Serve the autonomous system's pause, flatten, blacklist and confidence controls
+    pub fn with_control(mut self, control: AutonomousControl) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Serve an account's positions and orders; call once per account
+    pub fn with_account(mut self, account: impl Into<String>, positions: Arc<PositionManager>, orders: Arc<OrderManager>) -> Self {
+        self.books.push(AccountBook {
+            account: account.into(),
+            positions,
+            orders,
+            signals: None,
+        });
+        self
+    }
+
+    /// Serve an engine's positions and orders as an account, and take signals for it
+    pub fn with_engine(mut self, account: impl Into<String>, engine: &PaperTradingEngine) -> Self {
+        self.books.push(AccountBook {
+            account: account.into(),
+            positions: engine.position_manager().clone(),
+            orders: engine.order_manager().clone(),
+            signals: Some(engine.signal_submitter()),
+        });
+        self
+    }
+
+    /// Bind the port and serve in the background until the handle is shut
+    /// down or dropped
+    pub fn spawn(self) -> Result<ApiHandle> {
+        let addr = SocketAddr::from(([0, 0, 0, 0], self.port));
+        let routes = warp::service(self.routes());
+        let middleware = middleware::Middleware {
+            auth: self.auth.clone(),
+            request_timeout: self.request_timeout,
+            rate_limiter: Arc::default(),
+            audit: self.audit.clone(),
+        };
+        let make_service = hyper::service::make_service_fn(move |_| {
+            let middleware = middleware.clone();
+            let routes = routes.clone();
+            async move {
+                Ok::<_, Infallible>(hyper::service::service_fn(move |request| middleware.clone().handle(routes.clone(), request)))
+            }
+        });
+
+        let server = hyper::Server::try_bind(&addr)
+            .with_context(|| format!("Failed to bind the metrics API to port {}", self.port))?
+            .serve(make_service);
+        let local_addr = server.local_addr();
+        let (shutdown, shutdown_requested) = oneshot::channel::<()>();
+        let task = tokio::spawn(server.with_graceful_shutdown(async {
+            // A dropped handle shuts down too
+            let _ = shutdown_requested.await;
+        }));
+
+        tracing::info!(auth = self.auth.is_some(), "Metrics API listening on {}", local_addr);
+        Ok(ApiHandle {
+            local_addr,
+            shutdown,
+            task,
+            shutdown_timeout: self.shutdown_timeout,
+        })
+    }
+
+    fn routes(&self) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone + Send + Sync + 'static {
         let metrics = self.metrics_collector.clone();
 
         // Health check endpoint
-        let health = warp::path("health")
+        let health_config = Arc::new(self.health.clone());
+        let readiness = warp::path!("health")
+            .or(warp::path!("health" / "ready"))
+            .unify()
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and(warp::any().map(move || health_config.clone()))
+            .and_then(get_readiness);
+        let liveness = warp::path!("health" / "live")
             .and(warp::get())
-            .map(|| {
-                warp::reply::json(&json!({
-                    "status": "ok",
-                    "service": "neuromorphic-trading-metrics",
-                    "timestamp": chrono::Utc::now()
-                }))
-            });
+            .and_then(get_liveness);
+        let health = readiness.or(liveness);
 
         // Portfolio metrics endpoint
         let portfolio_metrics = warp::path!("api" / "v1" / "metrics" / "portfolio")
@@ -81,6 +356,217 @@ impl MetricsApiServer {
             .and(with_metrics(metrics.clone()))
             .and_then(get_risk_metrics);
 
+        // Per-account and consolidated statistics
+        let account_metrics = warp::path!("api" / "v1" / "metrics" / "accounts")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_account_metrics);
+
+        let single_account_metrics = warp::path!("api" / "v1" / "metrics" / "accounts" / String)
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and(with_tenant())
+            .and_then(get_single_account_metrics);
+
+        // Rolling 1h / 24h / 7d statistics
+        let rolling_metrics = warp::path!("api" / "v1" / "metrics" / "rolling")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_rolling_metrics);
+
+        // Signal and order event queue depth and drops
+        let queue_metrics = warp::path!("api" / "v1" / "metrics" / "queues")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_queue_metrics);
+
+        // Market data stream latency and clock skew
+        let stream_metrics = warp::path!("api" / "v1" / "metrics" / "streams")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_stream_metrics);
+
+        // Best bid and offer across venues and each venue's contribution
+        let quote_metrics = warp::path!("api" / "v1" / "metrics" / "quotes")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_quote_metrics);
+
+        // Realized and unrealized P&L by symbol, exchange, entry hour and weekday
+        let attribution_metrics = warp::path!("api" / "v1" / "metrics" / "attribution")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_attribution_metrics);
+
+        // Realized win rate by signal confidence decile
+        let calibration_metrics = warp::path!("api" / "v1" / "metrics" / "calibration")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_calibration_metrics);
+
+        // Fill and signal-to-order latency, trade P&L and holding time distributions
+        let histogram_metrics = warp::path!("api" / "v1" / "metrics" / "histograms")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_histogram_metrics);
+
+        // Totals and histograms for Prometheus to scrape
+        let prometheus_metrics = warp::path!("api" / "v1" / "metrics" / "prometheus")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_prometheus_metrics);
+
+        // Hypothetical P&L and margin of the open portfolio under stress scenarios
+        let scenario_analysis = warp::path!("api" / "v1" / "analysis" / "scenarios")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_scenario_analysis);
+
+        // Scanner universe, and watchlist additions and removals
+        let universe_status = warp::path!("api" / "v1" / "universe")
+            .and(warp::get())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(get_universe);
+
+        let watchlist_add = warp::path!("api" / "v1" / "universe" / "watchlist" / String)
+            .and(warp::post())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(add_to_watchlist);
+
+        let watchlist_remove = warp::path!("api" / "v1" / "universe" / "watchlist" / String)
+            .and(warp::delete())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(remove_from_watchlist);
+
+        // Top gainers, losers, volume and volatility leaders, and unusual volume
+        let market_movers = warp::path!("api" / "v1" / "scanner" / "movers")
+            .and(warp::get())
+            .and(warp::query::<LimitQuery>())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(get_market_movers);
+
+        // Market regime the strategies currently adapt to
+        let market_regime = warp::path!("api" / "v1" / "scanner" / "regime")
+            .and(warp::get())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(get_market_regime);
+
+        // Hit rates per strategy, and open and resolved opportunities
+        let opportunity_stats = warp::path!("api" / "v1" / "opportunities" / "stats")
+            .and(warp::get())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(get_opportunity_stats);
+
+        let tracked_opportunities = warp::path!("api" / "v1" / "opportunities" / "tracked")
+            .and(warp::get())
+            .and(warp::query::<LimitQuery>())
+            .and(with_scanner(self.scanner.clone()))
+            .and_then(get_tracked_opportunities);
+
+        // OpenAPI document of the routes below, for typed clients
+        let spec = Arc::new(openapi::ApiDoc::openapi());
+        let api_docs = warp::path!("api" / "docs")
+            .and(warp::get())
+            .map(move || warp::reply::json(&*spec));
+
+        // Positions and orders of every account, filtered, sorted and paged
+        let positions = warp::path!("api" / "v1" / "positions")
+            .and(warp::get())
+            .and(warp::query::<BookQuery>())
+            .and(with_tenant_books(self.books.clone()))
+            .and_then(get_positions);
+
+        // Positions of an existing portfolio, opened in a running account
+        let positions_import = warp::path!("api" / "v1" / "positions" / "import")
+            .and(warp::post())
+            .and(warp::query::<ImportQuery>())
+            .and(warp::header::optional::<String>("content-type"))
+            .and(warp::body::bytes())
+            .and(with_books(self.books.clone()))
+            .and_then(import_positions);
+
+        // P&L and exposure of position groups or strategy books
+        let position_groups = warp::path!("api" / "v1" / "groups")
+            .and(warp::get())
+            .and(warp::query::<GroupQuery>())
+            .and(with_tenant_books(self.books.clone()))
+            .and_then(get_position_groups);
+
+        let group_close = warp::path!("api" / "v1" / "groups" / String / "close")
+            .and(warp::post())
+            .and(warp::query::<ImportQuery>())
+            .and(with_books(self.books.clone()))
+            .and_then(close_position_group);
+
+        let orders = warp::path!("api" / "v1" / "orders")
+            .and(warp::get())
+            .and(warp::query::<BookQuery>())
+            .and(with_tenant_books(self.books.clone()))
+            .and_then(get_orders);
+
+        // Signals for an account, queued like the system's own
+        let signals = warp::path!("api" / "v1" / "signals")
+            .and(warp::post())
+            .and(warp::query::<ImportQuery>())
+            .and(warp::body::json())
+            .and(with_tenant())
+            .and(with_books(self.books.clone()))
+            .and_then(submit_signal);
+
+        // Accounts ranked by return, Sharpe ratio or drawdown
+        let leaderboard = warp::path!("api" / "v1" / "leaderboard")
+            .and(warp::get())
+            .and(warp::query::<LeaderboardQuery>())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_leaderboard);
+
+        // Accounts ranked by competition score
+        let scoring = Arc::new(self.scoring.clone());
+        let competition_scores = warp::path!("api" / "v1" / "leaderboard" / "scores")
+            .and(warp::get())
+            .and(with_metrics(metrics.clone()))
+            .and(warp::any().map(move || scoring.clone()))
+            .and_then(get_competition_scores);
+
+        // Autonomous decisions on opportunities, including shadow mode ones
+        let decisions = warp::path!("api" / "v1" / "decisions")
+            .and(warp::get())
+            .and(warp::query::<LimitQuery>())
+            .and(with_metrics(metrics.clone()))
+            .and_then(get_decisions);
+
+        // Runtime controls of the autonomous system
+        let control_status = warp::path!("api" / "v1" / "control")
+            .and(warp::get())
+            .and(with_control(self.control.clone()))
+            .and_then(get_control_status);
+
+        let control_action = warp::path!("api" / "v1" / "control" / String)
+            .and(warp::post())
+            .and(with_control(self.control.clone()))
+            .and_then(apply_control_action);
+
+        let blacklist_add = warp::path!("api" / "v1" / "control" / "blacklist" / String)
+            .and(warp::post())
+            .and(with_control(self.control.clone()))
+            .and_then(add_to_blacklist);
+
+        let blacklist_remove = warp::path!("api" / "v1" / "control" / "blacklist" / String)
+            .and(warp::delete())
+            .and(with_control(self.control.clone()))
+            .and_then(remove_from_blacklist);
+
+        let confidence_raise = warp::path!("api" / "v1" / "control" / "confidence")
+            .and(warp::post())
+            .and(warp::body::json::<ConfidenceOverrideRequest>())
+            .and(with_control(self.control.clone()))
+            .and_then(raise_min_confidence);
+
+        let confidence_clear = warp::path!("api" / "v1" / "control" / "confidence")
+            .and(warp::delete())
+            .and(with_control(self.control.clone()))
+            .and_then(clear_min_confidence);
+
         // Time series endpoint for Grafana's JSON datasource
         let timeseries = warp::path!("api" / "v1" / "timeseries" / String)
             .and(warp::get())
@@ -92,52 +578,108 @@ impl MetricsApiServer {
         let simple_metrics = warp::path("metrics")
             .and(warp::get())
             .and(with_metrics(metrics.clone()))
+            .and(with_scanner(self.scanner.clone()))
             .and_then(get_simple_metrics);
 
         // Opportunities endpoint for Grafana tables
         let opportunities = warp::path("opportunities")
             .and(warp::get())
-            .and(with_metrics(metrics.clone()))
+            .and(warp::query::<LimitQuery>())
+            .and(with_scanner(self.scanner.clone()))
             .and_then(get_opportunities);
 
         // Monitored stocks endpoint
         let monitored_stocks = warp::path("stocks")
             .and(warp::get())
             .and(with_metrics(metrics.clone()))
+            .and(with_scanner(self.scanner.clone()))
             .and_then(get_monitored_stocks);
 
         // Stock price history endpoint
         let stock_history = warp::path!(String / "history")
             .and(warp::get())
             .and(warp::query::<HistoryQuery>())
-            .and(with_metrics(metrics.clone()))
+            .and(with_scanner(self.scanner.clone()))
             .and_then(get_stock_history);
 
         // CORS for Grafana
         let cors = warp::cors()
             .allow_any_origin()
             .allow_headers(vec!["content-type", "authorization"])
-            .allow_methods(vec!["GET", "POST", "OPTIONS"]);
+            .allow_methods(vec!["GET", "POST", "DELETE", "OPTIONS"]);
 
-        let routes = health
-            .or(portfolio_metrics)
+        // Grouped and boxed to keep the filter type within the compiler's
+        // recursion limit
+        let metrics_routes = portfolio_metrics
             .or(signal_metrics)
             .or(all_metrics)
             .or(position_metrics)
             .or(market_metrics)
             .or(risk_metrics)
-            .or(timeseries)
+            .or(account_metrics)
+            .or(single_account_metrics)
+            .or(rolling_metrics)
+            .or(queue_metrics)
+            .or(stream_metrics)
+            .or(quote_metrics)
+            .or(attribution_metrics)
+            .or(calibration_metrics)
+            .or(histogram_metrics)
+            .or(prometheus_metrics)
+            .or(scenario_analysis)
+            .boxed();
+        let scanner_routes = universe_status
+            .or(watchlist_add)
+            .or(watchlist_remove)
+            .or(market_movers)
+            .or(market_regime)
+            .or(opportunity_stats)
+            .or(tracked_opportunities)
+            .boxed();
+        let trading_routes = positions
+            .or(positions_import)
+            .or(position_groups)
+            .or(group_close)
+            .or(orders)
+            .or(signals)
+            .or(leaderboard)
+            .or(competition_scores)
+            .or(decisions)
+            .or(control_status)
+            .or(confidence_raise)
+            .or(confidence_clear)
+            .or(control_action)
+            .or(blacklist_add)
+            .or(blacklist_remove)
+            .boxed();
+        let grafana_routes = timeseries
             .or(simple_metrics)
             .or(opportunities)
             .or(monitored_stocks)
             .or(stock_history)
+            .boxed();
+
+        let routes = health
+            .or(api_docs)
+            .or(metrics_routes)
+            .or(scanner_routes)
+            .or(trading_routes)
+            .or(grafana_routes)
             .with(cors)
             .recover(handle_rejection);
 
-        tracing::info!("Starting Metrics API server on port {}", self.port);
-        warp::serve(routes)
-            .run(([0, 0, 0, 0], self.port))
-            .await;
+        // Gzip for clients that accept it
+        #[cfg(feature = "compression")]
+        let routes = warp::header::<String>("accept-encoding")
+            .and_then(|accepted: String| async move {
+                if accepted.contains("gzip") { Ok(()) } else { Err(warp::reject()) }
+            })
+            .untuple_one()
+            .and(routes.clone())
+            .with(warp::compression::gzip())
+            .or(routes);
+
+        routes
     }
 }
 
@@ -148,21 +690,325 @@ fn with_metrics(
     warp::any().map(move || metrics.clone())
 }
 
+// Helper function to inject the market scanner, if there is one
+fn with_scanner(
+    scanner: Option<MarketScannerService>,
+) -> impl Filter<Extract = (Option<MarketScannerService>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || scanner.clone())
+}
+
+// Helper function to inject the autonomous system's controls, if there are any
+fn with_control(
+    control: Option<AutonomousControl>,
+) -> impl Filter<Extract = (Option<AutonomousControl>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || control.clone())
+}
+
+// Helper function to inject the accounts' positions and orders
+fn with_books(
+    books: Vec<AccountBook>,
+) -> impl Filter<Extract = (Vec<AccountBook>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || books.clone())
+}
+
+// Helper function to inject the account the caller's key is bound to, if any
+fn with_tenant() -> impl Filter<Extract = (Option<String>,), Error = std::convert::Infallible> + Clone {
+    warp::ext::optional::<Principal>().map(|principal: Option<Principal>| principal.and_then(|p| p.account))
+}
+
+// Helper function to inject the accounts the caller may see: only its own for a tenant
+fn with_tenant_books(
+    books: Vec<AccountBook>,
+) -> impl Filter<Extract = (Vec<AccountBook>,), Error = std::convert::Infallible> + Clone {
+    with_tenant().and(with_books(books)).map(|tenant: Option<String>, books: Vec<AccountBook>| {
+        books.into_iter().filter(|book| tenant.as_ref().is_none_or(|tenant| &book.account == tenant)).collect::<Vec<_>>()
+    })
+}
+
+// Body of a temporary confidence threshold raise
+#[derive(serde::Deserialize, ToSchema)]
+struct ConfidenceOverrideRequest {
+    min_confidence: f64,
+    duration_secs: u64,
+}
+
 // Query parameters for timeseries endpoint
 #[derive(serde::Deserialize)]
 struct TimeseriesQuery {
-    from: Option<i64>,
+    from: Option<i64>, // Unix milliseconds
     to: Option<i64>,
-    interval: Option<String>,
+}
+
+// Query parameters for ranked lists
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LimitQuery {
+    limit: Option<usize>,
+}
+
+// Filters, sort and page of the positions and orders endpoints
+#[derive(Debug, Default, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BookQuery {
+    account: Option<String>,
+    symbol: Option<String>,
+    /// Comma separated, e.g. "open,partially_closed"
+    status: Option<String>,
+    /// Unix millis of entry or creation, inclusive
+    from: Option<u64>,
+    /// Unix millis, exclusive
+    to: Option<u64>,
+    /// Field, prefixed with "-" for descending; newest first by default
+    sort: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+// Account positions are imported into
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ImportQuery {
+    /// The default account when unset
+    account: Option<String>,
+}
+
+// Accounts and key of the position groups endpoint
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct GroupQuery {
+    /// Every account when unset
+    account: Option<String>,
+    /// "group" (default) for position groups, "strategy" for strategy books
+    by: Option<String>,
+}
+
+// Ranking and length of the leaderboard
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LeaderboardQuery {
+    /// "return" (default), "sharpe" or "drawdown"
+    by: Option<LeaderboardMetric>,
+    /// Every account when unset
+    limit: Option<usize>,
+}
+
+/// A position group with the account holding it
+#[derive(Serialize, ToSchema)]
+struct AccountPositionGroup {
+    account: String,
+    #[serde(flatten)]
+    group: PositionGroup,
+}
+
+/// Signal queued for an account
+#[derive(Serialize, ToSchema)]
+struct SignalAccepted {
+    account: String,
+    symbol: String,
+}
+
+/// Orders submitted to close a position group
+#[derive(Serialize, ToSchema)]
+struct ClosedGroup {
+    account: String,
+    group_id: String,
+    order_ids: Vec<String>,
+}
+
+/// IDs of the positions an import opened
+#[derive(Serialize, ToSchema)]
+struct ImportedPositions {
+    account: String,
+    position_ids: Vec<String>,
+}
+
+/// Filtered and sorted rows of (account, row), one page of them
+struct BookPage<T> {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    items: Vec<(String, T)>,
+}
+
+/// A position with the account holding it
+#[derive(Serialize, ToSchema)]
+struct AccountPosition {
+    account: String,
+    #[serde(flatten)]
+    position: Position,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PositionPage {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    items: Vec<AccountPosition>,
+}
+
+/// An order with the account holding it
+#[derive(Serialize, ToSchema)]
+struct AccountOrder {
+    account: String,
+    #[serde(flatten)]
+    order: Order,
+}
+
+#[derive(Serialize, ToSchema)]
+struct OrderPage {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    items: Vec<AccountOrder>,
+}
+
+/// A symbol added to a watchlist or blacklist
+#[derive(Serialize, ToSchema)]
+struct SymbolAdded {
+    symbol: Symbol,
+    added: bool, // False if it was already there
+}
+
+/// A symbol removed from a watchlist or blacklist
+#[derive(Serialize, ToSchema)]
+struct SymbolRemoved {
+    symbol: Symbol,
+    removed: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+struct OpportunityStats {
+    strategies: Vec<StrategyHitRate>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct TrackedOpportunities {
+    open: Vec<TrackedOpportunity>,
+    resolved: Vec<TrackedOpportunity>,
+}
+
+/// Body of every error response
+#[derive(Serialize, ToSchema)]
+struct ErrorResponse {
+    error: String,
+    code: u16,
+}
+
+/// A position or order as listed by the API
+trait BookRow {
+    fn symbol(&self) -> &Symbol;
+    fn status(&self) -> String;
+    fn time(&self) -> u64;
+    /// Value of a numeric sort field, None if the row has no such field
+    fn sort_value(&self, field: &str) -> Option<f64>;
+}
+
+impl BookRow for Position {
+    fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    fn status(&self) -> String {
+        format!("{:?}", self.status)
+    }
+
+    fn time(&self) -> u64 {
+        self.entry_time
+    }
+
+    fn sort_value(&self, field: &str) -> Option<f64> {
+        match field {
+            "time" => Some(self.entry_time as f64),
+            "exit_time" => Some(self.exit_time.unwrap_or(0) as f64),
+            "pnl" => Some(self.realized_pnl + self.unrealized_pnl),
+            "quantity" => Some(self.quantity),
+            _ => None,
+        }
+    }
+}
+
+impl BookRow for Order {
+    fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    fn status(&self) -> String {
+        format!("{:?}", self.status)
+    }
+
+    fn time(&self) -> u64 {
+        self.created_time
+    }
+
+    fn sort_value(&self, field: &str) -> Option<f64> {
+        match field {
+            "time" => Some(self.created_time as f64),
+            "updated_time" => Some(self.updated_time as f64),
+            "quantity" => Some(self.quantity),
+            "filled_quantity" => Some(self.filled_quantity),
+            _ => None,
+        }
+    }
+}
+
+/// Statuses compare without case or underscores, so "partially_closed"
+/// matches `PartiallyClosed`
+fn normalize_status(status: &str) -> String {
+    status.replace('_', "").to_lowercase()
+}
+
+/// Filter, sort and page `rows` of (account, row)
+fn query_book<T: BookRow>(rows: Vec<(String, T)>, query: &BookQuery) -> Result<BookPage<T>, ApiError> {
+    let statuses: Option<Vec<String>> = query
+        .status
+        .as_ref()
+        .map(|status| status.split(',').map(|s| normalize_status(s.trim())).collect());
+    let symbol = query.symbol.as_ref().map(|s| universe_key(&Symbol::new(s.as_str())));
+    let mut rows: Vec<(String, T)> = rows
+        .into_iter()
+        .filter(|(account, _)| query.account.as_ref().is_none_or(|a| a == account))
+        .filter(|(_, row)| symbol.as_ref().is_none_or(|key| universe_key(row.symbol()) == *key))
+        .filter(|(_, row)| statuses.as_ref().is_none_or(|s| s.contains(&normalize_status(&row.status()))))
+        .filter(|(_, row)| query.from.is_none_or(|from| row.time() >= from))
+        .filter(|(_, row)| query.to.is_none_or(|to| row.time() < to))
+        .collect();
+
+    let sort = query.sort.as_deref().unwrap_or("-time");
+    let (field, descending) = match sort.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (sort, false),
+    };
+    if field == "symbol" {
+        rows.sort_by(|(_, a), (_, b)| a.symbol().0.cmp(&b.symbol().0));
+    } else {
+        if rows.first().is_some_and(|(_, row)| row.sort_value(field).is_none()) {
+            return Err(ApiError { message: format!("Cannot sort by {}", field) });
+        }
+        rows.sort_by(|(_, a), (_, b)| {
+            let (a, b) = (a.sort_value(field).unwrap_or(0.0), b.sort_value(field).unwrap_or(0.0));
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+    if descending {
+        rows.reverse();
+    }
+
+    let total = rows.len();
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE).min(MAX_PAGE);
+    let items = rows.into_iter().skip(offset).take(limit).collect();
+    Ok(BookPage { total, offset, limit, items })
 }
 
 // Query parameters for stock history endpoint
 #[derive(serde::Deserialize)]
 struct HistoryQuery {
     hours: Option<u64>,
+    granularity: Option<String>, // 1m, 5m, 15m, 30m, 1h, 4h or 1d
 }
 
 /// Get portfolio metrics
+#[utoipa::path(get, path = "/api/v1/metrics/portfolio", tag = "metrics", responses((status = 200, body = PortfolioMetrics)))]
 async fn get_portfolio_metrics(
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
@@ -171,6 +1017,7 @@ async fn get_portfolio_metrics(
 }
 
 /// Get signal metrics
+#[utoipa::path(get, path = "/api/v1/metrics/signals", tag = "metrics", responses((status = 200, body = SignalMetrics)))]
 async fn get_signal_metrics(
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
@@ -179,6 +1026,7 @@ async fn get_signal_metrics(
 }
 
 /// Get all metrics
+#[utoipa::path(get, path = "/api/v1/metrics/all", tag = "metrics", responses((status = 200, body = TradingMetrics)))]
 async fn get_all_metrics(
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
@@ -187,6 +1035,7 @@ async fn get_all_metrics(
 }
 
 /// Get position metrics
+#[utoipa::path(get, path = "/api/v1/metrics/positions", tag = "metrics", responses((status = 200, body = Vec<PositionMetrics>)))]
 async fn get_position_metrics(
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
@@ -195,14 +1044,15 @@ async fn get_position_metrics(
 }
 
 /// Get market metrics
+#[utoipa::path(get, path = "/api/v1/metrics/market", tag = "metrics", responses((status = 200, body = Vec<MarketMetrics>)))]
 async fn get_market_metrics(
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
-    let all_metrics = metrics.get_all_metrics();
-    Ok(warp::reply::json(&all_metrics.market_data))
+    Ok(warp::reply::json(&metrics.get_market_metrics()))
 }
 
 /// Get risk metrics
+#[utoipa::path(get, path = "/api/v1/metrics/risk", tag = "metrics", responses((status = 200, body = RiskMetrics)))]
 async fn get_risk_metrics(
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
@@ -210,48 +1060,470 @@ async fn get_risk_metrics(
     Ok(warp::reply::json(&all_metrics.risk))
 }
 
-/// Get timeseries data for Grafana's JSON datasource
+/// Get per-account statistics with the consolidated total
+#[utoipa::path(get, path = "/api/v1/metrics/accounts", tag = "metrics", responses((status = 200, body = AccountMetrics)))]
+async fn get_account_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_account_metrics()))
+}
+
+/// Get statistics of one account, or the consolidated total
+#[utoipa::path(get, path = "/api/v1/metrics/accounts/{account_id}", tag = "metrics", params(("account_id" = String, Path, description = "Account, or \"consolidated\" for the total")), responses((status = 200, body = AccountStatistics), (status = 404, body = ErrorResponse)))]
+async fn get_single_account_metrics(
+    account_id: String,
+    metrics: Arc<MetricsCollector>,
+    tenant: Option<String>,
+) -> Result<impl Reply, Rejection> {
+    if let Some(tenant) = tenant.filter(|tenant| *tenant != account_id) {
+        return Err(warp::reject::custom(Forbidden { message: format!("Only account '{}' is open to this key", tenant) }));
+    }
+    let account_metrics = metrics.get_account_metrics();
+    if account_id == crate::paper_trading::CONSOLIDATED_ACCOUNT {
+        return Ok(warp::reply::json(&account_metrics.consolidated));
+    }
+    account_metrics.accounts
+        .iter()
+        .find(|account| account.account_id == account_id)
+        .map(warp::reply::json)
+        .ok_or_else(warp::reject::not_found)
+}
+
+/// Rank the accounts by return, Sharpe ratio or drawdown
+#[utoipa::path(get, path = "/api/v1/leaderboard", tag = "trading", params(LeaderboardQuery), responses((status = 200, body = Leaderboard)))]
+async fn get_leaderboard(query: LeaderboardQuery, metrics: Arc<MetricsCollector>) -> Result<impl Reply, Rejection> {
+    let mut leaderboard = Leaderboard::rank(&metrics.get_account_metrics().accounts, query.by.unwrap_or_default());
+    if let Some(limit) = query.limit {
+        leaderboard.entries.truncate(limit);
+    }
+    Ok(warp::reply::json(&leaderboard))
+}
+
+/// Rank the accounts by risk-adjusted return, consistency and drawdown,
+/// standardized over the field
+#[utoipa::path(get, path = "/api/v1/leaderboard/scores", tag = "trading", responses((status = 200, body = Scoreboard)))]
+async fn get_competition_scores(metrics: Arc<MetricsCollector>, scoring: Arc<ScoringConfig>) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&Scoreboard::score(&metrics.get_competitors(), &scoring)))
+}
+
+/// Dependency status; 503 while one is down
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses((status = 200, body = HealthReport), (status = 503, description = "A dependency is down", body = HealthReport))
+)]
+async fn get_readiness(metrics: Arc<MetricsCollector>, config: Arc<HealthConfig>) -> Result<impl Reply, Rejection> {
+    let report = HealthReport::check(&metrics, &config);
+    let code = if report.ready { warp::http::StatusCode::OK } else { warp::http::StatusCode::SERVICE_UNAVAILABLE };
+    Ok(warp::reply::with_status(warp::reply::json(&report), code))
+}
+
+/// Answers as long as the server does
+#[utoipa::path(get, path = "/health/live", tag = "health", responses((status = 200)))]
+async fn get_liveness() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&json!({
+        "status": "ok",
+        "service": "neuromorphic-trading-metrics",
+        "timestamp": chrono::Utc::now()
+    })))
+}
+
+/// Get rolling-window statistics
+#[utoipa::path(get, path = "/api/v1/metrics/rolling", tag = "metrics", responses((status = 200, body = RollingMetrics)))]
+async fn get_rolling_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_rolling_metrics()))
+}
+
+/// Get signal and order event queue metrics
+#[utoipa::path(get, path = "/api/v1/metrics/queues", tag = "metrics", responses((status = 200, body = QueueMetrics)))]
+async fn get_queue_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_queue_metrics()))
+}
+
+/// Get market data stream latency metrics
+#[utoipa::path(get, path = "/api/v1/metrics/streams", tag = "metrics", responses((status = 200, body = StreamLatencyMetrics)))]
+async fn get_stream_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_stream_metrics()))
+}
+
+/// Get the best bid and offer across venues
+#[utoipa::path(get, path = "/api/v1/metrics/quotes", tag = "metrics", responses((status = 200, body = QuoteMetrics)))]
+async fn get_quote_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_quote_metrics()))
+}
+
+/// Get the P&L attribution per account and consolidated
+#[utoipa::path(get, path = "/api/v1/metrics/attribution", tag = "metrics", responses((status = 200, body = AttributionMetrics)))]
+async fn get_attribution_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_attribution_metrics()))
+}
+
+/// Get the confidence calibration table
+#[utoipa::path(get, path = "/api/v1/metrics/calibration", tag = "metrics", responses((status = 200, body = CalibrationMetrics)))]
+async fn get_calibration_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_calibration_metrics()))
+}
+
+/// Get the latency, trade P&L and position duration histograms
+#[utoipa::path(get, path = "/api/v1/metrics/histograms", tag = "metrics", responses((status = 200, body = HistogramMetrics)))]
+async fn get_histogram_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_histogram_metrics()))
+}
+
+/// Get the portfolio totals and histograms in the Prometheus text format
+#[utoipa::path(get, path = "/api/v1/metrics/prometheus", tag = "metrics", responses((status = 200, body = String, content_type = "text/plain")))]
+async fn get_prometheus_metrics(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::with_header(
+        metrics.prometheus_metrics(),
+        "content-type",
+        "text/plain; version=0.0.4",
+    ))
+}
+
+/// Get the what-if scenario results of the open portfolio
+#[utoipa::path(get, path = "/api/v1/analysis/scenarios", tag = "metrics", responses((status = 200, body = ScenarioReport)))]
+async fn get_scenario_analysis(
+    metrics: Arc<MetricsCollector>,
+) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_scenario_analysis()))
+}
+
+/// Get the scanner universe
+#[utoipa::path(get, path = "/api/v1/universe", tag = "scanner", responses((status = 200, body = UniverseStatus), (status = 404, body = ErrorResponse)))]
+async fn get_universe(
+    scanner: Option<MarketScannerService>,
+) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    let universe = scanner.universe();
+    Ok(warp::reply::json(&universe.status()))
+}
+
+/// Add a symbol to the scanner watchlist
+#[utoipa::path(post, path = "/api/v1/universe/watchlist/{symbol}", tag = "scanner", params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT")), responses((status = 200, body = SymbolAdded), (status = 400, body = ErrorResponse)))]
+async fn add_to_watchlist(
+    symbol: String,
+    scanner: Option<MarketScannerService>,
+) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    let universe = scanner.universe();
+    let symbol = Symbol::new(symbol.to_uppercase());
+    if !symbol.validate() {
+        return Err(warp::reject::custom(ApiError { message: format!("Invalid symbol {}", symbol) }));
+    }
+    let added = universe
+        .add_to_watchlist(symbol.clone())
+        .map_err(|e| warp::reject::custom(ApiError { message: e.to_string() }))?;
+    Ok(warp::reply::json(&SymbolAdded { symbol, added }))
+}
+
+/// Remove a symbol from the scanner watchlist
+#[utoipa::path(delete, path = "/api/v1/universe/watchlist/{symbol}", tag = "scanner", params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT")), responses((status = 200, body = SymbolRemoved), (status = 404, body = ErrorResponse)))]
+async fn remove_from_watchlist(
+    symbol: String,
+    scanner: Option<MarketScannerService>,
+) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    let universe = scanner.universe();
+    let symbol = Symbol::new(symbol.to_uppercase());
+    let removed = universe
+        .remove_from_watchlist(&symbol)
+        .map_err(|e| warp::reject::custom(ApiError { message: e.to_string() }))?;
+    if !removed {
+        return Err(warp::reject::not_found());
+    }
+    Ok(warp::reply::json(&SymbolRemoved { symbol, removed: true }))
+}
+
+/// Get the top movers of the scanned symbols
+#[utoipa::path(get, path = "/api/v1/scanner/movers", tag = "scanner", params(LimitQuery), responses((status = 200, body = MarketMovers)))]
+async fn get_market_movers(
+    query: LimitQuery,
+    scanner: Option<MarketScannerService>,
+) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    Ok(warp::reply::json(&scanner.movers().movers(query.limit.unwrap_or(10))))
+}
+
+/// Get the detected market regime and its trend and volatility readings
+#[utoipa::path(get, path = "/api/v1/scanner/regime", tag = "scanner", responses((status = 200, body = RegimeState)))]
+async fn get_market_regime(scanner: Option<MarketScannerService>) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    Ok(warp::reply::json(&scanner.regime().state()))
+}
+
+/// Get how often each strategy's opportunities reached their expected move
+#[utoipa::path(get, path = "/api/v1/opportunities/stats", tag = "scanner", responses((status = 200, body = OpportunityStats)))]
+async fn get_opportunity_stats(scanner: Option<MarketScannerService>) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    Ok(warp::reply::json(&OpportunityStats { strategies: scanner.opportunities().strategy_stats() }))
+}
+
+/// Get the open opportunities and the most recently resolved ones
+#[utoipa::path(get, path = "/api/v1/opportunities/tracked", tag = "scanner", params(LimitQuery), responses((status = 200, body = TrackedOpportunities)))]
+async fn get_tracked_opportunities(
+    query: LimitQuery,
+    scanner: Option<MarketScannerService>,
+) -> Result<impl Reply, Rejection> {
+    let scanner = scanner.ok_or_else(warp::reject::not_found)?;
+    let store = scanner.opportunities();
+    Ok(warp::reply::json(&TrackedOpportunities {
+        open: store.open(),
+        resolved: store.resolved(query.limit.unwrap_or(50)),
+    }))
+}
+
+/// List the accounts' positions
+#[utoipa::path(get, path = "/api/v1/positions", tag = "trading", params(BookQuery), responses((status = 200, body = PositionPage), (status = 400, body = ErrorResponse)))]
+async fn get_positions(query: BookQuery, books: Vec<AccountBook>) -> Result<impl Reply, Rejection> {
+    let rows = books
+        .iter()
+        .flat_map(|book| book.positions.get_all_positions().into_iter().map(|p| (book.account.clone(), p)))
+        .collect();
+    let page = query_book(rows, &query).map_err(warp::reject::custom)?;
+    Ok(warp::reply::json(&PositionPage {
+        total: page.total,
+        offset: page.offset,
+        limit: page.limit,
+        items: page.items.into_iter().map(|(account, position)| AccountPosition { account, position }).collect(),
+    }))
+}
+
+/// Open positions of an existing portfolio in an account; none are opened if
+/// any is invalid. Capital can only be seeded at startup, from `portfolio_file`.
+#[utoipa::path(post, path = "/api/v1/positions/import", tag = "trading", params(ImportQuery), request_body(content = Vec<ImportedPosition>, description = "JSON, or CSV rows with content type text/csv"), responses((status = 200, body = ImportedPositions), (status = 400, body = ErrorResponse), (status = 404, body = ErrorResponse)))]
+async fn import_positions(
+    query: ImportQuery,
+    content_type: Option<String>,
+    body: warp::hyper::body::Bytes,
+    books: Vec<AccountBook>,
+) -> Result<impl Reply, Rejection> {
+    let account = query.account.unwrap_or_else(|| DEFAULT_ACCOUNT.to_string());
+    let book = books
+        .iter()
+        .find(|book| book.account == account)
+        .ok_or_else(|| warp::reject::custom(NotFound { message: format!("Unknown account '{}'", account) }))?;
+
+    let text = std::str::from_utf8(&body).map_err(|_| warp::reject::custom(ApiError { message: "Body is not UTF-8".to_string() }))?;
+    let positions = if content_type.is_some_and(|t| t.starts_with("text/csv")) {
+        import::parse_csv(text)
+    } else {
+        serde_json::from_str::<Vec<ImportedPosition>>(text).map_err(anyhow::Error::from)
+    };
+    let invalid = |e: anyhow::Error| warp::reject::custom(ApiError { message: format!("{:#}", e) });
+    let positions = positions.map_err(invalid)?;
+    for (i, position) in positions.iter().enumerate() {
+        position.validate().with_context(|| format!("position {}", i + 1)).map_err(invalid)?;
+    }
+
+    let position_ids = positions
+        .iter()
+        .map(|position| book.positions.import_position(position))
+        .collect::<Result<Vec<_>>>()
+        .map_err(invalid)?;
+    tracing::info!(account = %account, positions = position_ids.len(), "Imported portfolio positions");
+    Ok(warp::reply::json(&ImportedPositions { account, position_ids }))
+}
+
+/// Queue a trading signal for an account, a tenant's key for its own. It goes
+/// through the account's throttle and risk limits like the system's signals.
+#[utoipa::path(post, path = "/api/v1/signals", tag = "trading", params(ImportQuery), request_body(content = Object, description = "TradingSignal as JSON"), responses((status = 202, body = SignalAccepted), (status = 403, body = ErrorResponse), (status = 404, body = ErrorResponse), (status = 503, body = ErrorResponse)))]
+async fn submit_signal(
+    query: ImportQuery,
+    mut signal: TradingSignal,
+    tenant: Option<String>,
+    books: Vec<AccountBook>,
+) -> Result<impl Reply, Rejection> {
+    let account = match (tenant, query.account) {
+        (Some(tenant), Some(account)) if account != tenant => {
+            return Err(warp::reject::custom(Forbidden { message: format!("Only account '{}' is open to this key", tenant) }));
+        }
+        (Some(tenant), _) => tenant,
+        (None, account) => account.unwrap_or_else(|| DEFAULT_ACCOUNT.to_string()),
+    };
+    let submitter = books
+        .iter()
+        .find(|book| book.account == account)
+        .and_then(|book| book.signals.as_ref())
+        .ok_or_else(|| warp::reject::custom(NotFound { message: format!("Account '{}' takes no signals", account) }))?;
+
+    signal.metadata.account_id = Some(account.clone());
+    let symbol = signal.symbol.to_string();
+    submitter.try_submit(signal).map_err(|e| {
+        let message = match e {
+            QueueError::Full(_) => format!("Signal queue of account '{}' is full", account),
+            QueueError::Closed => format!("Account '{}' is stopped", account),
+        };
+        warp::reject::custom(Busy { message })
+    })?;
+    let accepted = warp::reply::json(&SignalAccepted { account, symbol });
+    Ok(warp::reply::with_status(accepted, warp::http::StatusCode::ACCEPTED))
+}
+
+/// Sum up the accounts' positions by group id or by strategy
+#[utoipa::path(get, path = "/api/v1/groups", tag = "trading", params(GroupQuery), responses((status = 200, body = Vec<AccountPositionGroup>), (status = 400, body = ErrorResponse)))]
+async fn get_position_groups(query: GroupQuery, books: Vec<AccountBook>) -> Result<impl Reply, Rejection> {
+    let by_strategy = match query.by.as_deref() {
+        None | Some("group") => false,
+        Some("strategy") => true,
+        Some(other) => return Err(warp::reject::custom(ApiError { message: format!("Unknown grouping '{}'", other) })),
+    };
+    let groups: Vec<AccountPositionGroup> = books
+        .iter()
+        .filter(|book| query.account.as_ref().is_none_or(|account| &book.account == account))
+        .flat_map(|book| {
+            let groups = if by_strategy { book.positions.strategy_books() } else { book.positions.position_groups() };
+            groups.into_iter().map(|group| AccountPositionGroup { account: book.account.clone(), group })
+        })
+        .collect();
+    Ok(warp::reply::json(&groups))
+}
+
+/// Close every open position of a group and cancel its unfilled basket legs
+#[utoipa::path(post, path = "/api/v1/groups/{group_id}/close", tag = "trading", params(("group_id" = String, Path, description = "Position group"), ImportQuery), responses((status = 200, body = ClosedGroup), (status = 404, body = ErrorResponse)))]
+async fn close_position_group(group_id: String, query: ImportQuery, books: Vec<AccountBook>) -> Result<impl Reply, Rejection> {
+    let account = query.account.unwrap_or_else(|| DEFAULT_ACCOUNT.to_string());
+    let book = books
+        .iter()
+        .find(|book| book.account == account)
+        .ok_or_else(|| warp::reject::custom(NotFound { message: format!("Unknown account '{}'", account) }))?;
+    if !groups::group_exists(&book.positions, &book.orders, &group_id) {
+        return Err(warp::reject::custom(NotFound { message: format!("Unknown position group '{}'", group_id) }));
+    }
+
+    let order_ids = groups::close_group(&book.positions, &book.orders, &group_id)
+        .map_err(|e| warp::reject::custom(ApiError { message: format!("{:#}", e) }))?;
+    tracing::info!(account = %account, group_id = %group_id, orders = order_ids.len(), "Closing position group");
+    Ok(warp::reply::json(&ClosedGroup { account, group_id, order_ids }))
+}
+
+/// List the accounts' orders
+#[utoipa::path(get, path = "/api/v1/orders", tag = "trading", params(BookQuery), responses((status = 200, body = OrderPage), (status = 400, body = ErrorResponse)))]
+async fn get_orders(query: BookQuery, books: Vec<AccountBook>) -> Result<impl Reply, Rejection> {
+    let rows = books
+        .iter()
+        .flat_map(|book| book.orders.get_all_orders().into_iter().map(|o| (book.account.clone(), o)))
+        .collect();
+    let page = query_book(rows, &query).map_err(warp::reject::custom)?;
+    Ok(warp::reply::json(&OrderPage {
+        total: page.total,
+        offset: page.offset,
+        limit: page.limit,
+        items: page.items.into_iter().map(|(account, order)| AccountOrder { account, order }).collect(),
+    }))
+}
+
+/// Get the latest opportunity decisions
+#[utoipa::path(get, path = "/api/v1/decisions", tag = "trading", params(LimitQuery), responses((status = 200, body = Vec<DecisionRecord>)))]
+async fn get_decisions(query: LimitQuery, metrics: Arc<MetricsCollector>) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&metrics.get_decision_history(query.limit.unwrap_or(100))))
+}
+
+/// Get the pause, blacklist and confidence override state
+#[utoipa::path(get, path = "/api/v1/control", tag = "control", responses((status = 200, body = ControlStatus)))]
+async fn get_control_status(control: Option<AutonomousControl>) -> Result<impl Reply, Rejection> {
+    let control = control.ok_or_else(warp::reject::not_found)?;
+    Ok(warp::reply::json(&control.status()))
+}
+
+/// Pause or resume auto-trading, flatten every account, or reload the config files
+#[utoipa::path(post, path = "/api/v1/control/{action}", tag = "control", params(("action" = String, Path, description = "pause, resume, flatten or reload")), responses((status = 200, body = ControlStatus), (status = 404, body = ErrorResponse)))]
+async fn apply_control_action(
+    action: String,
+    control: Option<AutonomousControl>,
+) -> Result<impl Reply, Rejection> {
+    let control = control.ok_or_else(warp::reject::not_found)?;
+    match action.as_str() {
+        "pause" => control.pause(),
+        "resume" => control.resume(),
+        "flatten" => control.request_flatten(),
+        "reload" => control.request_reload(),
+        _ => return Err(warp::reject::not_found()),
+    }
+    Ok(warp::reply::json(&control.status()))
+}
+
+/// Stop trading a symbol
+#[utoipa::path(post, path = "/api/v1/control/blacklist/{symbol}", tag = "control", params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT")), responses((status = 200, body = SymbolAdded), (status = 400, body = ErrorResponse)))]
+async fn add_to_blacklist(
+    symbol: String,
+    control: Option<AutonomousControl>,
+) -> Result<impl Reply, Rejection> {
+    let control = control.ok_or_else(warp::reject::not_found)?;
+    let symbol = Symbol::new(symbol.to_uppercase());
+    if !symbol.validate() {
+        return Err(warp::reject::custom(ApiError { message: format!("Invalid symbol {}", symbol) }));
+    }
+    let added = control.blacklist(symbol.clone());
+    Ok(warp::reply::json(&SymbolAdded { symbol, added }))
+}
+
+/// Allow trading a blacklisted symbol again
+#[utoipa::path(delete, path = "/api/v1/control/blacklist/{symbol}", tag = "control", params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT")), responses((status = 200, body = SymbolRemoved), (status = 404, body = ErrorResponse)))]
+async fn remove_from_blacklist(
+    symbol: String,
+    control: Option<AutonomousControl>,
+) -> Result<impl Reply, Rejection> {
+    let control = control.ok_or_else(warp::reject::not_found)?;
+    let symbol = Symbol::new(symbol.to_uppercase());
+    if !control.unblacklist(&symbol) {
+        return Err(warp::reject::not_found());
+    }
+    Ok(warp::reply::json(&SymbolRemoved { symbol, removed: true }))
+}
+
+/// Require a higher opportunity confidence for a while
+#[utoipa::path(post, path = "/api/v1/control/confidence", tag = "control", request_body = ConfidenceOverrideRequest, responses((status = 200, body = ControlStatus), (status = 400, body = ErrorResponse)))]
+async fn raise_min_confidence(
+    request: ConfidenceOverrideRequest,
+    control: Option<AutonomousControl>,
+) -> Result<impl Reply, Rejection> {
+    let control = control.ok_or_else(warp::reject::not_found)?;
+    if !(0.0..=1.0).contains(&request.min_confidence) || request.duration_secs == 0 {
+        return Err(warp::reject::custom(ApiError {
+            message: "min_confidence must be between 0 and 1 and duration_secs above zero".to_string(),
+        }));
+    }
+    control.raise_min_confidence(request.min_confidence, std::time::Duration::from_secs(request.duration_secs));
+    Ok(warp::reply::json(&control.status()))
+}
+
+/// Go back to the configured confidence threshold
+#[utoipa::path(delete, path = "/api/v1/control/confidence", tag = "control", responses((status = 200, body = ControlStatus)))]
+async fn clear_min_confidence(control: Option<AutonomousControl>) -> Result<impl Reply, Rejection> {
+    let control = control.ok_or_else(warp::reject::not_found)?;
+    control.clear_min_confidence();
+    Ok(warp::reply::json(&control.status()))
+}
+
+/// Get timeseries data for Grafana's JSON datasource: the metric's samples
+/// between `from` and `to`, in Unix milliseconds
 async fn get_timeseries_data(
     metric_type: String,
-    _query: TimeseriesQuery,
+    query: TimeseriesQuery,
     metrics: Arc<MetricsCollector>,
 ) -> Result<impl Reply, Rejection> {
-    // Convert current metrics to timeseries format expected by Grafana
-    let all_metrics = metrics.get_all_metrics();
-    
-    let timeseries_data = match metric_type.as_str() {
-        "portfolio_pnl" => {
-            vec![json!({
-                "target": "Total P&L",
-                "datapoints": [
-                    [all_metrics.portfolio.total_pnl, all_metrics.portfolio.timestamp.timestamp_millis()]
-                ]
-            })]
-        },
-        "portfolio_capital" => {
-            vec![json!({
-                "target": "Total Capital",
-                "datapoints": [
-                    [all_metrics.portfolio.total_capital, all_metrics.portfolio.timestamp.timestamp_millis()]
-                ]
-            })]
-        },
-        "signals_per_minute" => {
-            vec![json!({
-                "target": "Signals/Min",
-                "datapoints": [
-                    [all_metrics.signals.signals_per_minute, all_metrics.signals.timestamp.timestamp_millis()]
-                ]
-            })]
-        },
-        "signal_confidence" => {
-            vec![json!({
-                "target": "Avg Confidence",
-                "datapoints": [
-                    [all_metrics.signals.avg_confidence * 100.0, all_metrics.signals.timestamp.timestamp_millis()]
-                ]
-            })]
-        },
+    let (target, value): (&str, fn(&MetricSample) -> f64) = match metric_type.as_str() {
+        "portfolio_pnl" => ("Total P&L", |s| s.total_pnl),
+        "portfolio_capital" => ("Total Capital", |s| s.total_capital),
+        "signals_per_minute" => ("Signals/Min", |s| s.signals_per_minute),
+        "signal_confidence" => ("Avg Confidence", |s| s.avg_confidence * 100.0),
         _ => {
             return Err(warp::reject::custom(ApiError {
                 message: format!("Unknown metric type: {}", metric_type),
@@ -259,190 +1531,82 @@ async fn get_timeseries_data(
         }
     };
 
-    Ok(warp::reply::json(&timeseries_data))
+    let millis = |ms: Option<i64>| ms.and_then(chrono::DateTime::from_timestamp_millis);
+    let datapoints: Vec<_> = metrics
+        .get_series(millis(query.from), millis(query.to))
+        .iter()
+        .map(|sample| json!([value(sample), sample.timestamp.timestamp_millis()]))
+        .collect();
+    Ok(warp::reply::json(&vec![json!({ "target": target, "datapoints": datapoints })]))
 }
 
 /// Get simple metrics for Grafana Infinity datasource
 async fn get_simple_metrics(
     metrics: Arc<MetricsCollector>,
+    scanner: Option<MarketScannerService>,
 ) -> Result<impl Reply, Rejection> {
     let all_metrics = metrics.get_all_metrics();
-    
-    // Generate demo stock data if no real market data exists
-    let demo_stocks = if all_metrics.market_data.is_empty() {
-        vec![
-            ("AAPL", 175.50, 2.3, 15000000.0, 1.2),
-            ("MSFT", 342.80, -0.8, 12000000.0, 0.9),
-            ("GOOGL", 138.45, 1.7, 8500000.0, 1.5),
-            ("TSLA", 242.80, 4.2, 25000000.0, 3.1),
-            ("NVDA", 465.20, 3.8, 18000000.0, 2.7),
-            ("META", 298.75, -1.2, 14000000.0, 1.8),
-            ("AMZN", 127.35, 0.5, 22000000.0, 1.1),
-            ("NFLX", 445.60, -2.1, 6500000.0, 2.3),
-        ]
-    } else {
-        Vec::new()
-    };
-
-    let stocks_data: Vec<_> = if !demo_stocks.is_empty() {
-        demo_stocks.iter().map(|(symbol, price, change, volume, volatility)| {
-            json!({
-                "symbol": symbol,
-                "price": price,
-                "change_24h": change,
-                "volume": volume,
-                "volatility": volatility,
-                "trend": if *change > 0.0 { "up" } else if *change < 0.0 { "down" } else { "flat" }
-            })
-        }).collect()
-    } else {
-        all_metrics.market_data.iter().map(|stock| {
-            json!({
-                "symbol": stock.symbol,
-                "price": stock.price,
-                "change_24h": stock.price_change_pct_24h,
-                "volume": stock.volume_24h,
-                "volatility": stock.volatility,
-                "trend": if stock.price_change_pct_24h > 0.0 { "up" } else if stock.price_change_pct_24h < 0.0 { "down" } else { "flat" }
-            })
-        }).collect()
-    };
+    let stocks_data = stock_rows(&all_metrics, scanner.as_ref());
 
     // Return a simplified metrics structure for Grafana
     let simple_metrics = json!({
         "timestamp": chrono::Utc::now(),
+        "currency": all_metrics.portfolio.currency,
         "total_capital": all_metrics.portfolio.total_capital,
         "total_pnl": all_metrics.portfolio.total_pnl,
         "portfolio_value": all_metrics.portfolio.total_capital + all_metrics.portfolio.total_pnl,
         "open_positions": all_metrics.positions.len(),
         "total_return_percent": all_metrics.portfolio.total_return_pct,
         "trades_executed": all_metrics.signals.signals_processed,
-        "opportunities_today": all_metrics.signals.signals_per_minute * 60.0 * 8.0, // Rough estimate for 8-hour trading day
+        "opportunities_tracked": scanner.as_ref().map_or(0, |s| s.opportunities().strategy_stats().iter().map(|h| h.tracked).sum::<u64>()),
         "signals_per_minute": all_metrics.signals.signals_per_minute,
         "avg_confidence": all_metrics.signals.avg_confidence,
+        "opportunities_skipped": all_metrics.signals.opportunities_skipped.values().sum::<u64>(),
         "market_volatility": if all_metrics.market_data.is_empty() { 0.0 } else { all_metrics.market_data[0].volatility },
         "win_rate": all_metrics.portfolio.win_rate,
         "sharpe_ratio": all_metrics.portfolio.sharpe_ratio,
         "max_drawdown": all_metrics.portfolio.max_drawdown,
         "var_95": all_metrics.risk.portfolio_var_95,
-        "portfolio_heat": all_metrics.risk.concentration_risk,
+        "portfolio_heat": all_metrics.risk.portfolio_heat,
         "stocks": stocks_data,
         "monitored_stocks_count": stocks_data.len(),
-        "price_history": generate_mock_price_history()
     });
-    
-    Ok(warp::reply::json(&simple_metrics))
-}
 
-/// Generate mock price history for dashboard demo
-fn generate_mock_price_history() -> serde_json::Value {
-    let current_time = chrono::Utc::now();
-    let mut history = Vec::new();
-    
-    // Generate 24 hours of data points (every hour)
-    for i in 0..24 {
-        let timestamp = current_time - chrono::Duration::hours(24 - i);
-        let base_price = 175.50; // AAPL base price
-        let price_variation = (i as f64 * 0.2).sin() * 3.0 + ((i % 5) as f64 - 2.0) * 1.5;
-        let price = base_price + price_variation;
-        
-        history.push(json!({
-            "timestamp": timestamp.timestamp_millis(),
-            "price": price.max(1.0),
-        }));
-    }
-    
-    json!(history)
+    #[cfg(feature = "demo")]
+    let simple_metrics = {
+        let mut simple_metrics = simple_metrics;
+        if stocks_data.is_empty() {
+            simple_metrics["price_history"] = json!(demo::price_history(175.50, 24));
+            simple_metrics["demo"] = json!(true);
+        }
+        simple_metrics
+    };
+
+    Ok(warp::reply::json(&simple_metrics))
 }
 
-/// Get trading opportunities for Grafana tables
+/// Get the scanner's current opportunities for Grafana tables, most confident first
 async fn get_opportunities(
-    metrics: Arc<MetricsCollector>,
+    query: LimitQuery,
+    scanner: Option<MarketScannerService>,
 ) -> Result<impl Reply, Rejection> {
-    // Generate mock opportunities for demonstration
-    // In a real implementation, this would come from the trading system
-    let opportunities = json!({
-        "opportunities": [
-            {
-                "symbol": "AAPL",
-                "strategy": "Neuromorphic Momentum",
-                "confidence": 0.85,
-                "expected_move": 2.5,
-                "time_horizon": "4h",
-                "entry_price": 175.50,
-                "position_size": 0.02,
-                "risk_score": 0.3
-            },
-            {
-                "symbol": "TSLA", 
-                "strategy": "Volume Spike",
-                "confidence": 0.78,
-                "expected_move": 4.2,
-                "time_horizon": "2h",
-                "entry_price": 242.80,
-                "position_size": 0.015,
-                "risk_score": 0.4
-            },
-            {
-                "symbol": "NVDA",
-                "strategy": "Breakout Pattern",
-                "confidence": 0.73,
-                "expected_move": 3.1,
-                "time_horizon": "6h", 
-                "entry_price": 118.45,
-                "position_size": 0.025,
-                "risk_score": 0.35
-            }
-        ]
-    });
-    
-    Ok(warp::reply::json(&opportunities))
+    let opportunities = match scanner {
+        Some(scanner) => scanner
+            .get_top_opportunities(query.limit.unwrap_or(10))
+            .await
+            .map_err(|e| warp::reject::custom(ApiError { message: e.to_string() }))?,
+        None => Vec::new(),
+    };
+
+    Ok(warp::reply::json(&json!({ "opportunities": opportunities })))
 }
 
 /// Get monitored stocks and their current data
 async fn get_monitored_stocks(
     metrics: Arc<MetricsCollector>,
+    scanner: Option<MarketScannerService>,
 ) -> Result<impl Reply, Rejection> {
-    let all_metrics = metrics.get_all_metrics();
-    
-    // Transform market data into a more dashboard-friendly format
-    let mut stocks: Vec<_> = all_metrics.market_data.iter().map(|stock| {
-        json!({
-            "symbol": stock.symbol,
-            "price": stock.price,
-            "change_24h": stock.price_change_pct_24h,
-            "volume": stock.volume_24h,
-            "volatility": stock.volatility,
-            "last_updated": stock.last_update,
-            "trend": if stock.price_change_pct_24h > 0.0 { "up" } else if stock.price_change_pct_24h < 0.0 { "down" } else { "flat" }
-        })
-    }).collect();
-    
-    // If no real market data, provide demo data for dashboard testing
-    if stocks.is_empty() {
-        let demo_stocks = vec![
-            ("AAPL", 175.50, 2.3, 15000000.0, 1.2),
-            ("MSFT", 342.80, -0.8, 12000000.0, 0.9),
-            ("GOOGL", 138.45, 1.7, 8500000.0, 1.5),
-            ("TSLA", 242.80, 4.2, 25000000.0, 3.1),
-            ("NVDA", 465.20, 3.8, 18000000.0, 2.7),
-            ("META", 298.75, -1.2, 14000000.0, 1.8),
-            ("AMZN", 127.35, 0.5, 22000000.0, 1.1),
-            ("NFLX", 445.60, -2.1, 6500000.0, 2.3),
-        ];
-        
-        stocks = demo_stocks.iter().map(|(symbol, price, change, volume, volatility)| {
-            json!({
-                "symbol": symbol,
-                "price": price,
-                "change_24h": change,
-                "volume": volume,
-                "volatility": volatility,
-                "last_updated": chrono::Utc::now(),
-                "trend": if *change > 0.0 { "up" } else if *change < 0.0 { "down" } else { "flat" }
-            })
-        }).collect();
-    }
+    let stocks = stock_rows(&metrics.get_all_metrics(), scanner.as_ref());
     
     let response = json!({
         "stocks": stocks,
@@ -453,46 +1617,92 @@ async fn get_monitored_stocks(
     Ok(warp::reply::json(&response))
 }
 
-/// Get price history for a specific stock
+/// Dashboard rows of the monitored symbols: the scanner's rolling 24h
+/// statistics when there is a scanner, else the collector's latest prices
+fn stock_rows(all_metrics: &TradingMetrics, scanner: Option<&MarketScannerService>) -> Vec<serde_json::Value> {
+    let trend = |change: f64| if change > 0.0 { "up" } else if change < 0.0 { "down" } else { "flat" };
+    match scanner {
+        Some(scanner) => scanner.movers().all_stats().iter().map(|stats: &SymbolStats| {
+            json!({
+                "symbol": stats.symbol,
+                "price": stats.price,
+                "change_24h": stats.change_pct_24h,
+                "volume": stats.volume_24h,
+                "turnover": stats.turnover_24h,
+                "volatility": stats.volatility_pct,
+                "volume_ratio": stats.volume_ratio,
+                "last_updated": stats.last_update,
+                "trend": trend(stats.change_pct_24h)
+            })
+        }).collect(),
+        None => all_metrics.market_data.iter().map(|stock| {
+            json!({
+                "symbol": stock.symbol,
+                "price": stock.price,
+                "change_24h": stock.price_change_pct_24h,
+                "volume": stock.volume_24h,
+                "volatility": stock.volatility,
+                "last_updated": stock.last_update,
+                "trend": trend(stock.price_change_pct_24h)
+            })
+        }).collect(),
+    }
+}
+
+/// Get the recorded price bars of a symbol, by default one-minute bars over
+/// the last 24 hours; `hours` is capped at the scanner's retention
 async fn get_stock_history(
     symbol: String,
     query: HistoryQuery,
-    metrics: Arc<MetricsCollector>,
+    scanner: Option<MarketScannerService>,
 ) -> Result<impl Reply, Rejection> {
-    let hours = query.hours.unwrap_or(24);
-    
-    // For demo purposes, generate some realistic price history
-    // In a real implementation, this would come from stored historical data
-    let current_time = chrono::Utc::now();
-    let mut price_points = Vec::new();
-    
-    // Generate mock historical data based on current market data
-    let all_metrics = metrics.get_all_metrics();
-    let current_stock = all_metrics.market_data.iter()
-        .find(|stock| stock.symbol == symbol);
-    
-    let base_price = current_stock.map(|s| s.price).unwrap_or(100.0);
-    
-    for i in 0..hours {
-        let timestamp = current_time - chrono::Duration::hours(hours as i64 - i as i64);
-        let price_variation = (i as f64 * 0.1).sin() * 2.0 + ((i % 7) as f64 - 3.0) * 1.5;
-        let price = base_price + price_variation;
-        
-        price_points.push(json!({
-            "timestamp": timestamp.timestamp_millis(),
-            "price": price.max(1.0), // Ensure price doesn't go negative
-            "volume": 1000000.0 + ((i % 10) as f64 * 50000.0)
-        }));
-    }
-    
-    let response = json!({
+    let granularity: Granularity = query
+        .granularity
+        .as_deref()
+        .unwrap_or("1m")
+        .parse()
+        .map_err(|e: anyhow::Error| warp::reject::custom(ApiError { message: e.to_string() }))?;
+    let retention = scanner.as_ref().map_or(24, |scanner| scanner.price_history().retention_hours());
+    let hours = query.hours.unwrap_or(24).clamp(1, retention);
+    let history = scanner.and_then(|scanner| scanner.price_history().bars(&Symbol::new(symbol.as_str()), granularity, hours));
+
+    let Some(history) = history else {
+        #[cfg(feature = "demo")]
+        {
+            let points = demo::price_history(100.0, hours);
+            return Ok(warp::reply::json(&json!({
+                "symbol": symbol,
+                "timeframe_hours": hours,
+                "granularity": query.granularity.as_deref().unwrap_or("1m"),
+                "data_points": points.len(),
+                "price_history": points,
+                "demo": true
+            })));
+        }
+        #[cfg(not(feature = "demo"))]
+        return Err(warp::reject::custom(NotFound { message: format!("No price history for {}", symbol) }));
+    };
+
+    let points: Vec<serde_json::Value> = history
+        .iter()
+        .map(|bar| {
+            json!({
+                "timestamp": bar.timestamp.timestamp_millis(),
+                "price": bar.close,
+                "open": bar.open,
+                "high": bar.high,
+                "low": bar.low,
+                "volume": bar.volume
+            })
+        })
+        .collect();
+    Ok(warp::reply::json(&json!({
         "symbol": symbol,
         "timeframe_hours": hours,
-        "data_points": price_points.len(),
-        "price_history": price_points
-    });
-    
-    Ok(warp::reply::json(&response))
+        "granularity": query.granularity.as_deref().unwrap_or("1m"),
+        "data_points": points.len(),
+        "price_history": points
+    })))
 }
 
 /// Handle API errors
@@ -503,19 +1713,116 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, std::convert::In
     if err.is_not_found() {
         code = warp::http::StatusCode::NOT_FOUND;
         message = "Endpoint not found";
+    } else if let Some(not_found) = err.find::<NotFound>() {
+        code = warp::http::StatusCode::NOT_FOUND;
+        message = &not_found.message;
+    } else if let Some(forbidden) = err.find::<Forbidden>() {
+        code = warp::http::StatusCode::FORBIDDEN;
+        message = &forbidden.message;
+    } else if let Some(busy) = err.find::<Busy>() {
+        code = warp::http::StatusCode::SERVICE_UNAVAILABLE;
+        message = &busy.message;
     } else if let Some(api_error) = err.find::<ApiError>() {
         code = warp::http::StatusCode::BAD_REQUEST;
         message = &api_error.message;
+    } else if err.find::<warp::filters::body::BodyDeserializeError>().is_some() {
+        code = warp::http::StatusCode::BAD_REQUEST;
+        message = "Invalid request body";
     } else {
         tracing::error!("Unhandled rejection: {:?}", err);
         code = warp::http::StatusCode::INTERNAL_SERVER_ERROR;
         message = "Internal server error";
     }
 
-    let json = warp::reply::json(&json!({
-        "error": message,
-        "code": code.as_u16()
-    }));
+    let json = warp::reply::json(&ErrorResponse {
+        error: message.to_string(),
+        code: code.as_u16(),
+    });
 
     Ok(warp::reply::with_status(json, code))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side};
+    use crate::paper_trading::OrderStatus;
+
+    fn order(symbol: &str, quantity: f64, status: OrderStatus, created_time: u64) -> Order {
+        let mut order = Order::market(Symbol::new(symbol), Exchange::Binance, Side::Buy, quantity);
+        order.status = status;
+        order.created_time = created_time;
+        order
+    }
+
+    #[test]
+    fn test_query_book_filters_sorts_and_pages() {
+        let rows = || {
+            vec![
+                ("default".to_string(), order("BTCUSDT", 1.0, OrderStatus::Filled, 1_000)),
+                ("default".to_string(), order("BTCUSDT", 3.0, OrderStatus::PartiallyFilled, 2_000)),
+                ("default".to_string(), order("ETHUSDT", 2.0, OrderStatus::Filled, 3_000)),
+                ("momentum".to_string(), order("BTCUSDT", 4.0, OrderStatus::Filled, 4_000)),
+            ]
+        };
+        let quantities = |page: &BookPage<Order>| page.items.iter().map(|(_, order)| order.quantity).collect::<Vec<_>>();
+
+        // Newest first by default; symbols match whatever the separator
+        let query = BookQuery { symbol: Some("btc-usdt".to_string()), ..Default::default() };
+        let page = query_book(rows(), &query).unwrap();
+        assert_eq!(quantities(&page), [4.0, 3.0, 1.0]);
+        assert_eq!(page.items[0].0, "momentum");
+
+        let query = BookQuery {
+            account: Some("default".to_string()),
+            status: Some("filled,partially_filled".to_string()),
+            from: Some(1_500),
+            sort: Some("quantity".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(quantities(&query_book(rows(), &query).unwrap()), [2.0, 3.0]);
+
+        let query = BookQuery { sort: Some("-quantity".to_string()), offset: Some(1), limit: Some(2), ..Default::default() };
+        let page = query_book(rows(), &query).unwrap();
+        assert_eq!((page.total, quantities(&page)), (4, vec![3.0, 2.0]));
+
+        let query = BookQuery { sort: Some("pnl".to_string()), ..Default::default() };
+        assert!(query_book(rows(), &query).is_err());
+    }
+    #[tokio::test]
+    async fn test_server_auth_and_graceful_shutdown() {
+        let alice = PaperTradingEngine::new(Default::default());
+        let api = MetricsApiServer::new(Arc::new(MetricsCollector::new()), 0)
+            .with_auth(ApiKeys::new(BTreeMap::from([
+                ("ops".to_string(), ApiKey { key: "secret".to_string(), role: Role::Operator, rate_limit_per_minute: None, account: None }),
+                ("alice".to_string(), ApiKey { key: "alice-key".to_string(), role: Role::ReadOnly, rate_limit_per_minute: None, account: Some("alice".to_string()) }),
+            ])))
+            .with_engine("alice", &alice)
+            .spawn()
+            .unwrap();
+        let url = |path: &str| format!("http://127.0.0.1:{}{}", api.local_addr().port(), path);
+        let client = reqwest::Client::new();
+
+        assert!(client.get(url("/health")).send().await.unwrap().status().is_success());
+        assert!(client.get(url("/health/live")).send().await.unwrap().status().is_success());
+        let denied = client.get(url("/api/v1/metrics/portfolio")).send().await.unwrap();
+        assert_eq!(denied.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let allowed = client.get(url("/api/v1/metrics/portfolio")).bearer_auth("secret").send().await.unwrap();
+        assert!(allowed.status().is_success());
+
+        // A tenant trades and reads its own account only
+        let signal = json!({"symbol": "BTC-USD", "exchange": "Binance", "action": {"Buy": {"size_hint": null}}, "confidence": 0.9, "urgency": 0.5});
+        let submitted = client.post(url("/api/v1/signals")).bearer_auth("alice-key").json(&signal).send().await.unwrap();
+        assert_eq!(submitted.status(), reqwest::StatusCode::ACCEPTED);
+        assert_eq!(alice.get_statistics().signal_queue.depth, 1);
+        let other = client.post(url("/api/v1/signals?account=default")).bearer_auth("alice-key").json(&signal).send().await.unwrap();
+        assert_eq!(other.status(), reqwest::StatusCode::FORBIDDEN);
+        let other = client.get(url("/api/v1/metrics/accounts/default")).bearer_auth("alice-key").send().await.unwrap();
+        assert_eq!(other.status(), reqwest::StatusCode::FORBIDDEN);
+        assert!(client.get(url("/api/v1/leaderboard?by=sharpe")).bearer_auth("alice-key").send().await.unwrap().status().is_success());
+
+        let health = url("/health");
+        api.shutdown().await.unwrap();
+        assert!(client.get(health).send().await.is_err());
+    }
+}
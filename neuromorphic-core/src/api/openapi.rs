@@ -0,0 +1,186 @@
+//! OpenAPI document of the metrics and control API
+//!
+//! Generated from the handlers' `utoipa::path` annotations and the payload
+//! types, served at `/api/docs` and printed by `paper-trader openapi` so
+//! dashboards and tools can generate typed clients. The Grafana datasource
+//! routes outside `/api/v1` answer in whatever shape their panels expect and
+//! are left out.
+
+use utoipa::OpenApi;
+
+use super::*;
+use super::health::{EngineHealth, ExchangeHealth, MarketDataHealth, QueueHealth, StorageHealth};
+use crate::control::{ControlStatus, Decision, DecisionRecord, SkipReason};
+use crate::exchanges::{DriftWarning, Exchange, LatencyStatistics, Side};
+use crate::market_data::{CompositeQuote, VenueContribution};
+use crate::market_scanner::{MarketMovers, MarketRegime, OpportunityState, RegimeState, TradingOpportunity, UniverseStatus};
+use crate::metrics::{
+    AccountAttribution, AccountMetrics, AttributionMetrics, CalibrationMetrics, HistogramBucket, HistogramMetrics, HistogramSnapshot, MarketMetrics, PortfolioMetrics,
+    PositionMetrics, QueueMetrics, QuoteMetrics, RiskMetrics, RollingMetrics, SignalMetrics, StreamLatencyMetrics,
+};
+use crate::paper_trading::{
+    AccountStatistics, CalibrationBucket, CompetitionScore, ExitReason, LeaderboardEntry, LiquidityRole, OrderStatus, OrderType, PnlAttribution, PnlBucket, PositionShock, PositionStatus,
+    QueueStatistics, ScenarioReport, ScenarioResult, Shock, TimeInForce, WindowStatistics,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Neuromorphic paper trading API"),
+    paths(
+        get_readiness,
+        get_liveness,
+        get_portfolio_metrics,
+        get_signal_metrics,
+        get_all_metrics,
+        get_position_metrics,
+        get_market_metrics,
+        get_risk_metrics,
+        get_account_metrics,
+        get_single_account_metrics,
+        get_rolling_metrics,
+        get_queue_metrics,
+        get_stream_metrics,
+        get_quote_metrics,
+        get_attribution_metrics,
+        get_calibration_metrics,
+        get_histogram_metrics,
+        get_prometheus_metrics,
+        get_scenario_analysis,
+        get_universe,
+        add_to_watchlist,
+        remove_from_watchlist,
+        get_market_movers,
+        get_market_regime,
+        get_opportunity_stats,
+        get_tracked_opportunities,
+        get_positions,
+        import_positions,
+        get_position_groups,
+        close_position_group,
+        get_orders,
+        submit_signal,
+        get_leaderboard,
+        get_competition_scores,
+        get_decisions,
+        get_control_status,
+        apply_control_action,
+        add_to_blacklist,
+        remove_from_blacklist,
+        raise_min_confidence,
+        clear_min_confidence,
+    ),
+    components(schemas(
+        HealthReport,
+        HealthStatus,
+        EngineHealth,
+        ExchangeHealth,
+        MarketDataHealth,
+        QueueHealth,
+        StorageHealth,
+        PortfolioMetrics,
+        SignalMetrics,
+        PositionMetrics,
+        MarketMetrics,
+        RiskMetrics,
+        AccountMetrics,
+        AccountStatistics,
+        RollingMetrics,
+        WindowStatistics,
+        QueueMetrics,
+        QueueStatistics,
+        StreamLatencyMetrics,
+        LatencyStatistics,
+        DriftWarning,
+        QuoteMetrics,
+        CompositeQuote,
+        VenueContribution,
+        AttributionMetrics,
+        AccountAttribution,
+        PnlAttribution,
+        PnlBucket,
+        CalibrationMetrics,
+        CalibrationBucket,
+        HistogramMetrics,
+        HistogramSnapshot,
+        HistogramBucket,
+        TradingMetrics,
+        ScenarioReport,
+        ScenarioResult,
+        PositionShock,
+        Shock,
+        UniverseStatus,
+        MarketMovers,
+        SymbolStats,
+        RegimeState,
+        MarketRegime,
+        StrategyHitRate,
+        TrackedOpportunity,
+        OpportunityState,
+        TradingOpportunity,
+        Position,
+        PositionStatus,
+        ExitReason,
+        Order,
+        OrderType,
+        OrderStatus,
+        TimeInForce,
+        LiquidityRole,
+        Symbol,
+        Exchange,
+        Side,
+        AccountPosition,
+        PositionPage,
+        ImportedPosition,
+        ImportedPositions,
+        PositionGroup,
+        AccountPositionGroup,
+        ClosedGroup,
+        SignalAccepted,
+        Leaderboard,
+        LeaderboardEntry,
+        LeaderboardMetric,
+        Scoreboard,
+        CompetitionScore,
+        AccountOrder,
+        OrderPage,
+        DecisionRecord,
+        Decision,
+        SkipReason,
+        ControlStatus,
+        ConfidenceOverrideRequest,
+        SymbolAdded,
+        SymbolRemoved,
+        OpportunityStats,
+        TrackedOpportunities,
+        ErrorResponse,
+    )),
+    tags(
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "metrics", description = "Portfolio, signal, risk and account metrics"),
+        (name = "scanner", description = "Scanner universe, movers, regime and opportunity outcomes"),
+        (name = "trading", description = "Positions, orders, signals, the leaderboard and opportunity decisions"),
+        (name = "control", description = "Runtime controls of the autonomous system"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI document
+pub fn document() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_covers_routes_and_schemas() {
+        let doc = serde_json::to_value(document()).unwrap();
+        assert!(doc["paths"]["/api/v1/positions"]["get"].is_object());
+        assert!(doc["paths"]["/api/v1/control/blacklist/{symbol}"]["delete"].is_object());
+        let schemas = &doc["components"]["schemas"];
+        for schema in ["ControlStatus", "DecisionRecord", "PositionPage", "ErrorResponse"] {
+            assert!(schemas[schema].is_object(), "missing schema {schema}");
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Splits and dividends in equity backtests
+//!
+//! A 4-for-1 split quarters a stock's traded price overnight and a dividend
+//! drops it by the amount paid, so a backtest over prices as traded sees
+//! crashes that never cost a holder anything. `AdjustmentMode` picks how
+//! they are taken out:
+//!
+//! - adjusted: prices before each ex-date are scaled back the way data
+//!   vendors publish adjusted series, splits by their ratio and dividends by
+//!   one minus the dividend over the last close before the ex-date. The
+//!   series is continuous and positions are left alone.
+//! - raw: prices stay as traded, and positions held over an ex-date are
+//!   adjusted on it instead. A split multiplies their quantity and divides
+//!   entry price and exit levels; a dividend is paid to longs and charged to
+//!   shorts as realized P&L. Resting orders keep their prices.
+//!
+//! Actions come from a CSV file of `symbol,ex_date,action,value` rows, e.g.
+//! `AAPL,2020-08-31,split,4` for four new shares per old one or
+//! `AAPL,2024-08-12,dividend,0.25` per share, or from the corporate actions
+//! API of Alpaca.
+
+use crate::exchanges::Symbol;
+use crate::market_scanner::MarketData;
+use crate::paper_trading::PositionManager;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+const ALPACA_CORPORATE_ACTIONS_URL: &str = "https://data.alpaca.markets/v1/corporate-actions";
+
+/// How a backtest accounts for corporate actions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentMode {
+    #[default]
+    Adjusted, // Prices before each ex-date scaled back
+    Raw, // Prices as traded, positions adjusted on the ex-date
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorporateActionKind {
+    Split { ratio: f64 }, // New shares per old one; below 1 for a reverse split
+    Dividend { amount: f64 }, // Cash per share
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CorporateAction {
+    pub symbol: Symbol,
+    pub ex_date: NaiveDate,
+    pub kind: CorporateActionKind,
+}
+
+impl CorporateAction {
+    /// Adjust the open positions of the symbol, as of the ex-date
+    pub fn apply_to(&self, positions: &PositionManager) -> Result<()> {
+        match self.kind {
+            CorporateActionKind::Split { ratio } => positions.apply_split(&self.symbol, ratio).map(drop),
+            CorporateActionKind::Dividend { amount } => positions.apply_dividend(&self.symbol, amount).map(drop),
+        }
+    }
+}
+
+/// Corporate actions per symbol, by ex-date
+#[derive(Clone, Debug, Default)]
+pub struct CorporateActions {
+    by_symbol: HashMap<String, Vec<CorporateAction>>, // Keyed by upper case symbol
+}
+
+impl CorporateActions {
+    pub fn new(actions: impl IntoIterator<Item = CorporateAction>) -> Self {
+        let mut by_symbol: HashMap<String, Vec<CorporateAction>> = HashMap::new();
+        for action in actions {
+            by_symbol.entry(action.symbol.as_str().to_uppercase()).or_default().push(action);
+        }
+        for actions in by_symbol.values_mut() {
+            actions.sort_by_key(|action| action.ex_date);
+        }
+        Self { by_symbol }
+    }
+
+    /// Parse `symbol,ex_date,action,value` rows; the action is split or
+    /// dividend. A header row is optional.
+    pub fn parse_csv(text: &str) -> Result<Self> {
+        let mut actions = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if line.trim().is_empty() || (i == 0 && fields[0].eq_ignore_ascii_case("symbol")) {
+                continue;
+            }
+            if fields.len() < 4 {
+                bail!("line {}: expected 4 columns, found {}", i + 1, fields.len());
+            }
+            let ex_date = NaiveDate::parse_from_str(fields[1], "%Y-%m-%d").with_context(|| format!("line {}: invalid ex-date '{}'", i + 1, fields[1]))?;
+            let value: f64 = fields[3].parse().with_context(|| format!("line {}: invalid value '{}'", i + 1, fields[3]))?;
+            let kind = match fields[2].to_lowercase().as_str() {
+                "split" if value.is_finite() && value > 0.0 => CorporateActionKind::Split { ratio: value },
+                "dividend" if value.is_finite() && value >= 0.0 => CorporateActionKind::Dividend { amount: value },
+                "split" | "dividend" => bail!("line {}: invalid {} value {}", i + 1, fields[2], value),
+                other => bail!("line {}: unknown action '{}', expected split or dividend", i + 1, other),
+            };
+            actions.push(CorporateAction { symbol: Symbol::new(fields[0].to_uppercase()), ex_date, kind });
+        }
+        Ok(Self::new(actions))
+    }
+
+    pub fn load_csv(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse_csv(&text).with_context(|| format!("Invalid corporate actions in {}", path.display()))
+    }
+
+    /// Splits and cash dividends of `symbols` with ex-dates in `[start, end]`
+    /// from the Alpaca market data API
+    pub async fn fetch_alpaca(key_id: &str, secret_key: &str, symbols: &[Symbol], start: NaiveDate, end: NaiveDate) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Page {
+            corporate_actions: Actions,
+            next_page_token: Option<String>,
+        }
+        #[derive(Deserialize, Default)]
+        #[serde(default)]
+        struct Actions {
+            forward_splits: Vec<Split>,
+            reverse_splits: Vec<Split>,
+            cash_dividends: Vec<Dividend>,
+        }
+        #[derive(Deserialize)]
+        struct Split {
+            symbol: String,
+            ex_date: NaiveDate,
+            new_rate: f64,
+            old_rate: f64,
+        }
+        #[derive(Deserialize)]
+        struct Dividend {
+            symbol: String,
+            ex_date: NaiveDate,
+            rate: f64,
+        }
+
+        let client = reqwest::Client::new();
+        let symbols = symbols.iter().map(|s| s.as_str().to_uppercase()).collect::<Vec<_>>().join(",");
+        let mut actions = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut params = vec![
+                ("symbols", symbols.clone()),
+                ("types", "forward_split,reverse_split,cash_dividend".to_string()),
+                ("start", start.to_string()),
+                ("end", end.to_string()),
+                ("limit", "1000".to_string()),
+            ];
+            params.extend(page_token.take().map(|token| ("page_token", token)));
+            let page: Page = client
+                .get(ALPACA_CORPORATE_ACTIONS_URL)
+                .query(&params)
+                .header("APCA-API-KEY-ID", key_id)
+                .header("APCA-API-SECRET-KEY", secret_key)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .context("Failed to fetch corporate actions from Alpaca")?
+                .json()
+                .await
+                .context("Invalid corporate actions from Alpaca")?;
+
+            let Actions { forward_splits, reverse_splits, cash_dividends } = page.corporate_actions;
+            for split in forward_splits.into_iter().chain(reverse_splits).filter(|s| s.old_rate > 0.0 && s.new_rate > 0.0) {
+                let kind = CorporateActionKind::Split { ratio: split.new_rate / split.old_rate };
+                actions.push(CorporateAction { symbol: Symbol::new(split.symbol), ex_date: split.ex_date, kind });
+            }
+            for dividend in cash_dividends {
+                let kind = CorporateActionKind::Dividend { amount: dividend.rate };
+                actions.push(CorporateAction { symbol: Symbol::new(dividend.symbol), ex_date: dividend.ex_date, kind });
+            }
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(Self::new(actions))
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_symbol.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_symbol.is_empty()
+    }
+
+    /// Actions of a symbol, by ex-date
+    pub fn for_symbol(&self, symbol: &Symbol) -> &[CorporateAction] {
+        self.by_symbol.get(&symbol.as_str().to_uppercase()).map_or(&[], Vec::as_slice)
+    }
+
+    /// Actions taking effect after a bar on `after` and by one on `until`
+    pub fn between(&self, symbol: &Symbol, after: NaiveDate, until: NaiveDate) -> impl Iterator<Item = &CorporateAction> {
+        self.for_symbol(symbol).iter().filter(move |action| action.ex_date > after && action.ex_date <= until)
+    }
+
+    /// Scale back the prices, and for splits the volumes, of every bar
+    /// before each ex-date of its symbol
+    pub fn adjust_bars(&self, bars: &mut [MarketData]) {
+        let mut indices: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, bar) in bars.iter().enumerate() {
+            indices.entry(bar.symbol.as_str().to_uppercase()).or_default().push(i);
+        }
+
+        for (key, mut indices) in indices {
+            let Some(actions) = self.by_symbol.get(&key) else { continue };
+            indices.sort_by_key(|&i| bars[i].timestamp);
+            // Factors from the prices as traded, before any is applied
+            let factors: Vec<(NaiveDate, f64, f64)> = actions
+                .iter()
+                .filter_map(|action| match action.kind {
+                    CorporateActionKind::Split { ratio } => Some((action.ex_date, 1.0 / ratio, ratio)),
+                    CorporateActionKind::Dividend { amount } => {
+                        let close = indices.iter().rev().map(|&i| &bars[i]).find(|bar| bar.timestamp.date_naive() < action.ex_date)?.price;
+                        (close > amount).then(|| (action.ex_date, 1.0 - amount / close, 1.0))
+                    }
+                })
+                .collect();
+
+            for &i in &indices {
+                let bar = &mut bars[i];
+                let date = bar.timestamp.date_naive();
+                let (price, volume) = factors
+                    .iter()
+                    .filter(|(ex_date, ..)| date < *ex_date)
+                    .fold((1.0, 1.0), |(price, volume), (_, p, v)| (price * p, volume * v));
+                bar.price *= price;
+                bar.open *= price;
+                bar.high *= price;
+                bar.low *= price;
+                bar.bid = bar.bid.map(|bid| bid * price);
+                bar.ask = bar.ask.map(|ask| ask * price);
+                bar.volume *= volume;
+                bar.volume_24h *= volume;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side};
+    use chrono::{TimeZone, Utc};
+
+    fn bar(day: u32, price: f64) -> MarketData {
+        let mut bar = MarketData::new(Symbol::new("AAPL"), price);
+        (bar.open, bar.high, bar.low, bar.volume) = (price, price, price, 100.0);
+        bar.timestamp = Utc.with_ymd_and_hms(2024, 8, day, 20, 0, 0).unwrap();
+        bar
+    }
+
+    #[test]
+    fn test_splits_and_dividends_adjust_prices_or_positions() {
+        let csv = "symbol,ex_date,action,value\naapl,2024-08-06,split,4\nAAPL,2024-08-08,dividend,0.5\n";
+        let actions = CorporateActions::parse_csv(csv).unwrap();
+        assert_eq!(actions.len(), 2);
+        assert!(CorporateActions::parse_csv("AAPL,2024-08-06,merger,1").is_err());
+
+        // Traded: 400 before the split, 100 after, 99.5 after the dividend
+        let mut bars = vec![bar(5, 400.0), bar(6, 100.0), bar(7, 100.0), bar(8, 99.5)];
+        actions.adjust_bars(&mut bars);
+        let prices: Vec<f64> = bars.iter().map(|b| (b.price * 1e6).round() / 1e6).collect();
+        assert_eq!(prices, [99.5, 99.5, 99.5, 99.5]);
+        assert_eq!((bars[0].volume, bars[1].volume), (400.0, 100.0));
+
+        // Raw: the position takes the split and the dividend instead
+        let positions = PositionManager::new();
+        let symbol = Symbol::new("AAPL");
+        let id = positions.open_position(symbol.clone(), Exchange::NASDAQ, Side::Buy, 10.0, 400.0, 0.0, 0.0).unwrap();
+        positions.modify_position_exits(&id, Some(360.0), None).unwrap();
+        let after = |day| NaiveDate::from_ymd_opt(2024, 8, day).unwrap();
+        for action in actions.between(&symbol, after(5), after(8)) {
+            action.apply_to(&positions).unwrap();
+        }
+        let position = positions.get_position(&id).unwrap();
+        assert_eq!((position.quantity, position.entry_price, position.stop_loss), (40.0, 100.0, Some(90.0)));
+        assert_eq!((position.realized_pnl, positions.total_pnl()), (20.0, 20.0));
+    }
+}
@@ -0,0 +1,316 @@
+//! Offline simulation: strategy backtests over historical bars and replay of
+//! recorded sessions, both producing a regular session report
+
+pub mod corporate_actions;
+
+pub use corporate_actions::{AdjustmentMode, CorporateAction, CorporateActionKind, CorporateActions};
+
+use crate::exchanges::{Exchange, Symbol};
+use crate::market_scanner::{MarketData, PriceHistory, RegimeDetector, StrategyEngine};
+use crate::paper_trading::{PaperTradingConfig, SignalAction, SignalMetadata, SimulatedClock, TradingSignal};
+use crate::reports::SessionReport;
+use crate::{AutonomousConfig, NeuromorphicPaperTrader};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// One line of a recorded session file (JSON Lines)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    Price { symbol: Symbol, price: f64 },
+    Signal { signal: Box<TradingSignal> },
+}
+
+/// Parse a recorded session, one JSON event per line; blank lines are skipped
+pub fn parse_session(text: &str) -> Result<Vec<SessionEvent>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("Invalid session event on line {}", i + 1))
+        })
+        .collect()
+}
+
+/// Read a recorded session file
+pub fn read_session(path: &Path) -> Result<Vec<SessionEvent>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read session file {}", path.display()))?;
+    parse_session(&text)
+}
+
+/// Load OHLCV bars from every `<SYMBOL>.csv` in a directory, merged in time order.
+/// Columns: timestamp,open,high,low,close,volume; the timestamp is unix seconds,
+/// unix milliseconds or RFC 3339. A header row is optional.
+pub fn load_bars(dir: &Path) -> Result<Vec<MarketData>> {
+    let mut bars = Vec::new();
+
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read data directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("csv") {
+            continue;
+        }
+        let symbol = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(Symbol::new)
+            .with_context(|| format!("Invalid data file name {}", path.display()))?;
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let parsed = parse_bars(&symbol, &text)
+            .with_context(|| format!("Invalid bar data in {}", path.display()))?;
+        bars.extend(parsed);
+    }
+
+    bars.sort_by_key(|bar| bar.timestamp);
+    Ok(bars)
+}
+
+fn parse_bars(symbol: &Symbol, text: &str) -> Result<Vec<MarketData>> {
+    let mut bars = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if line.trim().is_empty() || (i == 0 && fields[0].eq_ignore_ascii_case("timestamp")) {
+            continue;
+        }
+        if fields.len() < 6 {
+            anyhow::bail!("line {}: expected 6 columns, found {}", i + 1, fields.len());
+        }
+
+        let number = |idx: usize| -> Result<f64> {
+            fields[idx]
+                .parse()
+                .with_context(|| format!("line {}: invalid number '{}'", i + 1, fields[idx]))
+        };
+        let (open, high, low, close, volume) = (number(1)?, number(2)?, number(3)?, number(4)?, number(5)?);
+
+        bars.push(MarketData {
+            symbol: symbol.clone(),
+            price: close,
+            volume,
+            timestamp: parse_timestamp(fields[0])
+                .with_context(|| format!("line {}: invalid timestamp '{}'", i + 1, fields[0]))?,
+            bid: None,
+            ask: None,
+            open,
+            high,
+            low,
+            change_24h: if open > 0.0 { (close - open) / open * 100.0 } else { 0.0 },
+            volume_24h: volume,
+            exchange: None,
+            candle: None,
+        });
+    }
+
+    Ok(bars)
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(n) = value.parse::<i64>() {
+        // Anything past year ~5000 in seconds is taken as milliseconds
+        return if n > 100_000_000_000 {
+            Utc.timestamp_millis_opt(n).single()
+        } else {
+            Utc.timestamp_opt(n, 0).single()
+        };
+    }
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// Steps a paper trader through historical data: each signal is handled,
+/// and the orders due filled, before the next event, with no engine tasks.
+/// The trader runs on a simulated clock that backtests move to each bar's time,
+/// and takes the ATR of ATR stops from the bars seen so far.
+pub struct Simulator {
+    trader: NeuromorphicPaperTrader,
+    clock: Arc<SimulatedClock>,
+    history: Arc<PriceHistory>,
+    corporate_actions: CorporateActions,
+    adjustment: AdjustmentMode,
+}
+
+impl Simulator {
+    /// Start a paper trader configured for simulation
+    pub async fn new(mut config: PaperTradingConfig) -> Result<Self> {
+        // Step the engine ourselves, and don't rate-limit orders: the limits
+        // guard live strategies against runaway loops
+        config.stepped = true;
+        config.risk_limits.max_orders_per_minute = u64::MAX;
+        config.risk_limits.max_orders_per_minute_per_symbol = u64::MAX;
+
+        let clock = Arc::new(SimulatedClock::starting_at(Utc::now()));
+        let history = Arc::new(PriceHistory::new(config.stop_history_hours()));
+        let mut trader = NeuromorphicPaperTrader::with_clock(config, clock.clone());
+        trader.set_price_history(history.clone());
+        trader.start().await?;
+        Ok(Self { trader, clock, history, corporate_actions: CorporateActions::default(), adjustment: AdjustmentMode::default() })
+    }
+
+    /// Account for splits and dividends in backtests, by adjusting the bars
+    /// or the positions held over each ex-date
+    pub fn with_corporate_actions(mut self, actions: CorporateActions, mode: AdjustmentMode) -> Self {
+        self.corporate_actions = actions;
+        self.adjustment = mode;
+        self
+    }
+
+    pub fn trader(&self) -> &NeuromorphicPaperTrader {
+        &self.trader
+    }
+
+    /// Simulated time seen by the trader
+    pub fn clock(&self) -> &Arc<SimulatedClock> {
+        &self.clock
+    }
+
+    /// Apply one recorded event
+    pub async fn apply(&self, event: SessionEvent) -> Result<()> {
+        match event {
+            SessionEvent::Price { symbol, price } => {
+                self.trader.update_market_price(symbol, price);
+                self.trader.process_orders_once()?;
+            }
+            SessionEvent::Signal { signal } => {
+                self.trader.process_signal_now(*signal).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replay recorded events in order, returning how many were applied
+    pub async fn replay(&self, events: Vec<SessionEvent>) -> Result<usize> {
+        let count = events.len();
+        for event in events {
+            self.apply(event).await?;
+        }
+        info!(events = count, "Replay finished");
+        Ok(count)
+    }
+
+    /// Run the scanner strategies over bars and trade the opportunities that pass
+    /// the autonomous limits, returning the number of signals sent
+    pub async fn backtest(&self, mut bars: Vec<MarketData>, config: &AutonomousConfig) -> Result<usize> {
+        let strategies = StrategyEngine::new();
+        let regime = RegimeDetector::new(config.scanner_config.regime.clone());
+        let exchange = config.scanner_config.included_exchanges.first().copied().unwrap_or(Exchange::NYSE);
+
+        let mut signals = 0;
+        let mut day: Option<NaiveDate> = None;
+        let mut daily_trades = 0;
+        let mut last_dates: HashMap<Symbol, NaiveDate> = HashMap::new();
+
+        if self.adjustment == AdjustmentMode::Adjusted && !self.corporate_actions.is_empty() {
+            self.corporate_actions.adjust_bars(&mut bars);
+        }
+
+        for bar in bars {
+            if day != Some(bar.timestamp.date_naive()) {
+                day = Some(bar.timestamp.date_naive());
+                daily_trades = 0;
+            }
+
+            self.clock.set(bar.timestamp);
+            if self.adjustment == AdjustmentMode::Raw {
+                let date = bar.timestamp.date_naive();
+                if let Some(last) = last_dates.insert(bar.symbol.clone(), date) {
+                    for action in self.corporate_actions.between(&bar.symbol, last, date) {
+                        debug!(symbol = %action.symbol, ex_date = %action.ex_date, kind = ?action.kind, "Corporate action");
+                        action.apply_to(self.trader.positions())?;
+                    }
+                }
+            }
+            self.trader.update_market_price(bar.symbol.clone(), bar.price);
+            self.trader.update_market_volume(&bar.symbol, bar.volume);
+            self.trader.process_orders_once()?;
+            self.history.record(&bar);
+            regime.record(&bar);
+
+            let open = self.trader.positions().get_open_positions();
+            let has_capacity = open.len() < config.max_positions && daily_trades < config.max_daily_trades;
+            let opportunity = if has_capacity && !open.iter().any(|p| p.symbol == bar.symbol) {
+                strategies
+                    .analyze_opportunity(&bar, regime.regime())
+                    .await?
+                    .into_iter()
+                    .find(|o| o.confidence >= config.min_opportunity_confidence && o.side().is_some())
+            } else {
+                None
+            };
+            let capital = self.trader.accounts().default_engine().capital();
+            let heat = self.trader.portfolio_heat();
+            let sized = opportunity
+                .map(|o| (config.trade_notional(&o, capital, heat), o))
+                .filter(|(notional, _)| *notional > 0.0);
+
+            if let Some((notional, opportunity)) = sized {
+                debug!(symbol = %opportunity.symbol, strategy = %opportunity.strategy, notional, "Backtest signal");
+                self.trader.process_signal_now(opportunity.to_signal(exchange, notional)).await?;
+                signals += 1;
+                daily_trades += 1;
+            }
+        }
+
+        info!(signals, "Backtest finished");
+        Ok(signals)
+    }
+
+    /// Close what is still open at the last known prices, stop the engine and build the report
+    pub async fn finish(self) -> Result<SessionReport> {
+        let symbols: HashSet<Symbol> = self.trader
+            .positions()
+            .get_open_positions()
+            .into_iter()
+            .map(|p| p.symbol)
+            .collect();
+
+        for symbol in symbols {
+            self.trader.process_signal_now(TradingSignal {
+                symbol,
+                exchange: Exchange::NYSE, // Closing ignores the exchange
+                action: SignalAction::Close { position_id: None },
+                confidence: 1.0,
+                urgency: 1.0,
+                metadata: SignalMetadata {
+                    market_regime: "end_of_simulation".to_string(),
+                    ..Default::default()
+                },
+            }).await?;
+        }
+
+        self.trader.stop().await?;
+        Ok(self.trader.session_report())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replay_session() {
+        let session = r#"
+            {"type":"price","symbol":"BTC-USD","price":50000.0}
+            {"type":"signal","signal":{"symbol":"BTC-USD","exchange":"Binance","action":{"Buy":{"size_hint":1000.0}},"confidence":0.9,"urgency":0.9}}
+            {"type":"price","symbol":"BTC-USD","price":52000.0}
+        "#;
+        let events = parse_session(session).unwrap();
+        assert_eq!(events.len(), 3);
+
+        let simulator = Simulator::new(PaperTradingConfig::default()).await.unwrap();
+        simulator.replay(events).await.unwrap();
+        let report = simulator.finish().await.unwrap();
+
+        // The open long is closed at the last price when the simulation ends
+        assert_eq!(report.trades.len(), 1);
+        assert!(report.trades[0].pnl > 0.0);
+        assert!(parse_session("{\"type\":\"price\"}").is_err());
+    }
+}
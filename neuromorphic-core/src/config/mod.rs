@@ -0,0 +1,960 @@
+//! Run configuration from layered TOML files and environment overrides
+//!
+//! Files are applied in order, each overriding the keys it sets. Environment
+//! variables named `NEUROMORPHIC_<SECTION>__<KEY>` override both, with `__`
+//! separating nested keys, e.g. `NEUROMORPHIC_TRADING__INITIAL_CAPITAL=50000` or
+//! `NEUROMORPHIC_CREDENTIALS__BINANCE__API_KEY=...`.
+//!
+//! `[trading.instruments.<symbol>]` tables set how a symbol trades, over the
+//! defaults of its asset class, see `InstrumentConfig`.
+//!
+//! `[accounts.<id>]` tables add isolated accounts; they take the `[trading]` keys
+//! and inherit whatever they don't set from `[trading]`. `[[routes]]` entries
+//! send untagged signals to those accounts, see `RouteRule`. `[reconciliation]`
+//! controls the venue state checks used with external execution, `[api]` the
+//! metrics and control API server, with its keys in `[api.keys.<name>]`, its
+//! readiness checks in `[api.health]` and the weights of the competition
+//! scores in `[api.scoring]`, `[metrics.histograms]` the bucket bounds
+//! of the latency and trade histograms, `[metrics.series]` the sampling and
+//! persistence of the time series Grafana graphs, and `[service]` the state directory and
+//! alerts of a long-running deployment, see `Service`.
+//!
+//! Risk limits, strategy parameters and `[scanner.screening]` can be reloaded
+//! into a running session, see `reload`.
+
+pub mod reload;
+
+pub use reload::{ConfigChange, ReloadableSettings, StrategyParams};
+
+use crate::api::{ApiConfig, ApiKey, HealthConfig};
+use crate::exchanges::{Exchange, Symbol};
+use crate::market_scanner::ScannerConfig;
+use crate::metrics::{MetricsConfig, SeriesFormat};
+use crate::service::ServiceConfig;
+use crate::paper_trading::{AssetClass, ChaosConfig, ExecutionMode, FaultKind, FeeSchedule, HistoryConfig, InstrumentConfig, MarkPrice, PaperTradingConfig, ParticipationConfig, PortfolioImport, QueueConfig, RebalanceConfig, ReconciliationConfig, RiskLimits, SlippageModel, RouteRule, ScoringConfig, StopPlacement, ThrottleConfig, TradeRule, TradingHours, CONSOLIDATED_ACCOUNT, DEFAULT_ACCOUNT};
+use crate::AutonomousConfig;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Prefix of environment variables that override config keys
+pub const ENV_PREFIX: &str = "NEUROMORPHIC_";
+
+/// Separator between nested keys in override variable names
+const ENV_SEPARATOR: &str = "__";
+
+/// Configuration errors, naming the file, variable or key at fault
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid config file {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("Invalid override {var} for `{key}`: {message}")]
+    Env { var: String, key: String, message: String },
+
+    #[error("Invalid value for `{key}`: {message}")]
+    Invalid { key: String, message: String },
+}
+
+/// API credentials for one exchange
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExchangeCredentials {
+    pub api_key: String,
+    pub api_secret: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+impl fmt::Debug for ExchangeCredentials {
+    // Keep secrets out of logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExchangeCredentials")
+            .field("api_key", &"***")
+            .field("api_secret", &"***")
+            .field("passphrase", &self.passphrase.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// `[trading]` section; unset keys keep the `PaperTradingConfig` defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TradingSection {
+    initial_capital: Option<f64>,
+    commission_rate: Option<f64>,
+    fee_schedule: Option<FeeSchedule>,
+    slippage_model: Option<SlippageModel>,
+    participation: Option<ParticipationConfig>,
+    queue_position: Option<bool>,
+    risk_limits: Option<RiskLimits>,
+    enable_stop_loss: Option<bool>,
+    enable_take_profit: Option<bool>,
+    max_holding_period_secs: Option<u64>,
+    hedge_mode: Option<bool>,
+    reporting_currency: Option<String>,
+    fx_rates: Option<BTreeMap<String, f64>>,
+    execution: Option<ExecutionMode>,
+    signal_queue: Option<QueueConfig>,
+    order_event_queue: Option<QueueConfig>,
+    signal_throttle: Option<ThrottleConfig>,
+    seed: Option<u64>,
+    id_seed: Option<u64>,
+    event_log: Option<PathBuf>,
+    portfolio_file: Option<PathBuf>,
+    exit_rules: Option<Vec<PathBuf>>,
+    instruments: Option<BTreeMap<String, InstrumentSection>>,
+    stops: Option<StopPlacement>,
+    strategy_stops: Option<BTreeMap<String, StopPlacement>>,
+    trade_management: Option<Vec<TradeRule>>,
+    rebalance: Option<RebalanceConfig>,
+    chaos: Option<ChaosConfig>,
+    history: Option<HistoryConfig>,
+    marks: Option<MarkPrice>,
+    symbol_marks: Option<BTreeMap<String, MarkPrice>>,
+    update_interval_ms: Option<u64>,
+}
+
+impl TradingSection {
+    fn apply(self, config: &mut PaperTradingConfig) {
+        if let Some(v) = self.initial_capital { config.initial_capital = v; }
+        if let Some(v) = self.commission_rate { config.commission_rate = v; }
+        if let Some(v) = self.fee_schedule { config.fee_schedule = Some(v); }
+        if let Some(v) = self.slippage_model { config.slippage_model = v; }
+        if let Some(v) = self.participation { config.participation = v; }
+        if let Some(v) = self.queue_position { config.queue_position = v; }
+        if let Some(v) = self.risk_limits { config.risk_limits = v; }
+        if let Some(v) = self.enable_stop_loss { config.enable_stop_loss = v; }
+        if let Some(v) = self.enable_take_profit { config.enable_take_profit = v; }
+        if let Some(v) = self.max_holding_period_secs { config.max_holding_period = Some(Duration::from_secs(v)); }
+        if let Some(v) = self.hedge_mode { config.hedge_mode = v; }
+        if let Some(v) = self.reporting_currency { config.reporting_currency = v; }
+        if let Some(v) = self.fx_rates { config.fx_rates = v; }
+        if let Some(v) = self.execution { config.execution = v; }
+        if let Some(v) = self.signal_queue { config.signal_queue = v; }
+        if let Some(v) = self.order_event_queue { config.order_event_queue = v; }
+        if let Some(v) = self.signal_throttle { config.signal_throttle = v; }
+        if let Some(v) = self.seed { config.seed = Some(v); }
+        if let Some(v) = self.id_seed { config.id_seed = Some(v); }
+        if let Some(v) = self.event_log { config.event_log = Some(v); }
+        if let Some(v) = self.portfolio_file { config.portfolio_file = Some(v); }
+        if let Some(v) = self.exit_rules { config.exit_rules = v; }
+        if let Some(v) = self.instruments {
+            config.instruments = v.into_iter().map(|(symbol, section)| {
+                let instrument = section.resolve(&symbol);
+                (symbol, instrument)
+            }).collect();
+        }
+        if let Some(v) = self.stops { config.stops = v; }
+        if let Some(v) = self.strategy_stops { config.strategy_stops = v; }
+        if let Some(v) = self.trade_management { config.trade_management = v; }
+        if let Some(v) = self.rebalance { config.rebalance = Some(v); }
+        if let Some(v) = self.chaos { config.chaos = Some(v); }
+        if let Some(v) = self.history { config.history = v; }
+        if let Some(v) = self.marks { config.marks = v; }
+        if let Some(v) = self.symbol_marks { config.symbol_marks = v; }
+        if let Some(v) = self.update_interval_ms { config.update_interval = Duration::from_millis(v); }
+    }
+}
+
+/// `[trading.instruments.<symbol>]` section; unset keys keep the defaults of
+/// the asset class, which is guessed from the symbol when unset
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct InstrumentSection {
+    asset_class: Option<AssetClass>,
+    multiplier: Option<f64>,
+    price_decimals: Option<u32>,
+    quantity_step: Option<f64>,
+    hours: Option<TradingHours>,
+}
+
+impl InstrumentSection {
+    fn resolve(self, symbol: &str) -> InstrumentConfig {
+        let mut config = self.asset_class.map_or_else(|| InstrumentConfig::for_symbol(&Symbol::new(symbol)), InstrumentConfig::for_class);
+        if let Some(v) = self.multiplier { config.multiplier = v; }
+        if let Some(v) = self.price_decimals { config.price_decimals = v; }
+        if let Some(v) = self.quantity_step { config.quantity_step = v; }
+        if let Some(v) = self.hours { config.hours = v; }
+        config
+    }
+}
+
+/// `[autonomous]` section; unset keys keep the `AutonomousConfig` defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AutonomousSection {
+    max_positions: Option<usize>,
+    max_daily_trades: Option<usize>,
+    risk_per_trade: Option<f64>,
+    enable_auto_trading: Option<bool>,
+    min_opportunity_confidence: Option<f64>,
+    portfolio_heat: Option<f64>,
+    opportunity_cooldown_secs: Option<u64>,
+    daily_state_path: Option<PathBuf>,
+    shadow_mode: Option<bool>,
+    watch_config: Option<bool>,
+}
+
+impl AutonomousSection {
+    fn apply(self, config: &mut AutonomousConfig) {
+        if let Some(v) = self.max_positions { config.max_positions = v; }
+        if let Some(v) = self.max_daily_trades { config.max_daily_trades = v; }
+        if let Some(v) = self.risk_per_trade { config.risk_per_trade = v; }
+        if let Some(v) = self.enable_auto_trading { config.enable_auto_trading = v; }
+        if let Some(v) = self.min_opportunity_confidence { config.min_opportunity_confidence = v; }
+        if let Some(v) = self.portfolio_heat { config.portfolio_heat = v; }
+        if let Some(v) = self.opportunity_cooldown_secs { config.opportunity_cooldown = Duration::from_secs(v); }
+        if let Some(v) = self.daily_state_path { config.daily_state_path = Some(v); }
+        if let Some(v) = self.shadow_mode { config.shadow_mode = v; }
+        if let Some(v) = self.watch_config { config.watch_config = v; }
+    }
+}
+
+/// `[reconciliation]` section; unset keys keep the `ReconciliationConfig` defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ReconciliationSection {
+    enabled: Option<bool>,
+    interval_secs: Option<u64>,
+    auto_correct: Option<bool>,
+    grace_period_secs: Option<u64>,
+    tolerance_pct: Option<f64>,
+}
+
+impl ReconciliationSection {
+    fn apply(self, config: &mut ReconciliationConfig) {
+        if let Some(v) = self.enabled { config.enabled = v; }
+        if let Some(v) = self.interval_secs { config.interval = Duration::from_secs(v); }
+        if let Some(v) = self.auto_correct { config.auto_correct = v; }
+        if let Some(v) = self.grace_period_secs { config.grace_period = Duration::from_secs(v); }
+        if let Some(v) = self.tolerance_pct { config.tolerance_pct = v; }
+    }
+}
+
+/// `[api]` section; unset keys keep the `ApiConfig` defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ApiSection {
+    port: Option<u16>,
+    keys: Option<BTreeMap<String, ApiKey>>,
+    audit_log: Option<PathBuf>,
+    request_timeout_secs: Option<u64>,
+    shutdown_timeout_secs: Option<u64>,
+    health: Option<HealthConfig>,
+    scoring: Option<ScoringConfig>,
+}
+
+impl ApiSection {
+    fn apply(self, config: &mut ApiConfig) {
+        if let Some(v) = self.port { config.port = v; }
+        if let Some(v) = self.keys { config.keys = v; }
+        if let Some(v) = self.audit_log { config.audit_log = Some(v); }
+        if let Some(v) = self.request_timeout_secs { config.request_timeout = Duration::from_secs(v); }
+        if let Some(v) = self.shutdown_timeout_secs { config.shutdown_timeout = Duration::from_secs(v); }
+        if let Some(v) = self.health { config.health = v; }
+        if let Some(v) = self.scoring { config.scoring = v; }
+    }
+}
+
+/// `[service]` section; unset keys keep the `ServiceConfig` defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServiceSection {
+    state_dir: Option<PathBuf>,
+    snapshot_interval_secs: Option<u64>,
+    alert_webhook: Option<String>,
+}
+
+impl ServiceSection {
+    fn apply(self, config: &mut ServiceConfig) {
+        if let Some(v) = self.state_dir { config.state_dir = Some(v); }
+        if let Some(v) = self.snapshot_interval_secs { config.snapshot_interval = Duration::from_secs(v); }
+        if let Some(v) = self.alert_webhook { config.alert_webhook = Some(v); }
+    }
+}
+
+/// Layout of a config file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    trading: TradingSection,
+    scanner: ScannerConfig,
+    autonomous: AutonomousSection,
+    accounts: HashMap<String, TradingSection>,
+    routes: Vec<RouteRule>,
+    reconciliation: ReconciliationSection,
+    api: ApiSection,
+    metrics: MetricsConfig,
+    credentials: HashMap<String, ExchangeCredentials>,
+    service: ServiceSection,
+}
+
+/// Fully resolved run configuration
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub trading: PaperTradingConfig,
+    pub scanner: ScannerConfig,
+    pub autonomous: AutonomousConfig,
+    pub accounts: BTreeMap<String, PaperTradingConfig>, // Extra accounts besides the default one
+    pub routes: Vec<RouteRule>,
+    pub reconciliation: ReconciliationConfig,
+    pub credentials: HashMap<Exchange, ExchangeCredentials>,
+    pub service: ServiceConfig,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        let autonomous = AutonomousConfig::default();
+        Self {
+            trading: autonomous.trading_config.clone(),
+            scanner: autonomous.scanner_config.clone(),
+            autonomous,
+            accounts: BTreeMap::new(),
+            routes: Vec::new(),
+            reconciliation: ReconciliationConfig::default(),
+            credentials: HashMap::new(),
+            service: ServiceConfig::default(),
+        }
+    }
+}
+
+impl RunConfig {
+    /// Load config files in order, then apply overrides from the process environment
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self, ConfigError> {
+        let mut sources = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            sources.push((path.to_path_buf(), text));
+        }
+
+        Self::from_sources(sources, std::env::vars())
+    }
+
+    /// Build from TOML sources given as (origin, contents) and environment variables
+    pub fn from_sources<I>(sources: Vec<(PathBuf, String)>, env: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut merged = toml::Table::new();
+
+        for (path, text) in sources {
+            // Check each file on its own so errors carry its line and key
+            let parse_error = |e: toml::de::Error| ConfigError::Parse {
+                path: path.clone(),
+                message: e.to_string(),
+            };
+            toml::from_str::<ConfigFile>(&text).map_err(parse_error)?;
+            let table: toml::Table = toml::from_str(&text).map_err(parse_error)?;
+            merge_tables(&mut merged, table);
+        }
+
+        let mut overrides: Vec<(String, Vec<String>, String)> = env
+            .into_iter()
+            .filter_map(|(var, value)| env_key(&var).map(|key| (var, key, value)))
+            .collect();
+        overrides.sort();
+
+        for (var, key, raw) in overrides {
+            apply_override(&mut merged, &key, &raw).map_err(|message| ConfigError::Env {
+                var,
+                key: key.join("."),
+                message,
+            })?;
+        }
+
+        let file: ConfigFile = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse {
+                path: PathBuf::from("<merged>"),
+                message: e.to_string(),
+            })?;
+
+        let config = file.resolve()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check value ranges and cross-field constraints
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        validate_trading("trading", &self.trading)?;
+        for (id, trading) in &self.accounts {
+            let section = format!("accounts.{}", id);
+            check(id != DEFAULT_ACCOUNT && id != CONSOLIDATED_ACCOUNT, &section, "account id is reserved")?;
+            validate_trading(&section, trading)?;
+        }
+
+        for (i, route) in self.routes.iter().enumerate() {
+            let key = |name: &str| format!("routes[{}].{}", i, name);
+            check(!route.name.trim().is_empty(), &key("name"), "must not be empty")?;
+            check(
+                !self.routes[..i].iter().any(|r| r.name == route.name),
+                &key("name"),
+                &format!("duplicate route name '{}'", route.name),
+            )?;
+            check(
+                route.account == DEFAULT_ACCOUNT || self.accounts.contains_key(&route.account),
+                &key("account"),
+                &format!("unknown account '{}'", route.account),
+            )?;
+            check(route.size_multiplier > 0.0, &key("size_multiplier"), "must be positive")?;
+            check(
+                route.min_confidence.unwrap_or(0.0) <= route.max_confidence.unwrap_or(1.0),
+                &key("max_confidence"),
+                "must not be below min_confidence",
+            )?;
+        }
+
+        let scanner = &self.scanner;
+        check(scanner.scan_interval_ms > 0, "scanner.scan_interval_ms", "must be greater than zero")?;
+        check(
+            scanner.min_price_threshold <= scanner.max_price_threshold,
+            "scanner.max_price_threshold",
+            "must not be below scanner.min_price_threshold",
+        )?;
+        check(!scanner.included_exchanges.is_empty(), "scanner.included_exchanges", "must list at least one exchange")?;
+        check(scanner.universe.refresh_interval_secs > 0, "scanner.universe.refresh_interval_secs", "must be greater than zero")?;
+        check(scanner.universe.min_quote_volume >= 0.0, "scanner.universe.min_quote_volume", "must not be negative")?;
+        check(scanner.history_retention_hours > 0, "scanner.history_retention_hours", "must be greater than zero")?;
+        for (i, model) in scanner.models.iter().enumerate() {
+            model.validate().map_err(|e| ConfigError::Invalid {
+                key: format!("scanner.models[{}]", i),
+                message: e.to_string(),
+            })?;
+        }
+        for (i, plugin) in scanner.plugins.iter().enumerate() {
+            plugin.validate().map_err(|e| ConfigError::Invalid {
+                key: format!("scanner.plugins[{}]", i),
+                message: e.to_string(),
+            })?;
+        }
+        let screening = &scanner.screening;
+        check(
+            screening.min_price.unwrap_or(0.0) <= screening.max_price.unwrap_or(f64::INFINITY),
+            "scanner.screening.max_price",
+            "must not be below scanner.screening.min_price",
+        )?;
+        let regime = &scanner.regime;
+        check(
+            regime.fast_span_minutes > 0 && regime.fast_span_minutes < regime.slow_span_minutes,
+            "scanner.regime.fast_span_minutes",
+            "must be greater than zero and below scanner.regime.slow_span_minutes",
+        )?;
+        check(
+            regime.volatility_span_minutes > 0 && regime.volatility_span_minutes < regime.baseline_span_minutes,
+            "scanner.regime.volatility_span_minutes",
+            "must be greater than zero and below scanner.regime.baseline_span_minutes",
+        )?;
+        check(
+            regime.mild_trend_pct > 0.0 && regime.mild_trend_pct <= regime.strong_trend_pct,
+            "scanner.regime.mild_trend_pct",
+            "must be greater than zero and not above scanner.regime.strong_trend_pct",
+        )?;
+        check(
+            regime.low_volatility_ratio > 0.0 && regime.low_volatility_ratio < regime.high_volatility_ratio,
+            "scanner.regime.low_volatility_ratio",
+            "must be greater than zero and below scanner.regime.high_volatility_ratio",
+        )?;
+
+        let reconciliation = &self.reconciliation;
+        check(!reconciliation.interval.is_zero(), "reconciliation.interval_secs", "must be greater than zero")?;
+        check(reconciliation.tolerance_pct >= 0.0, "reconciliation.tolerance_pct", "must not be negative")?;
+
+        let autonomous = &self.autonomous;
+        check(autonomous.max_positions > 0, "autonomous.max_positions", "must be at least 1")?;
+        check((0.0..=1.0).contains(&autonomous.min_opportunity_confidence), "autonomous.min_opportunity_confidence", "must be between 0 and 1")?;
+        check(autonomous.risk_per_trade > 0.0 && autonomous.risk_per_trade <= 1.0, "autonomous.risk_per_trade", "must be in (0, 1]")?;
+        check(autonomous.portfolio_heat > 0.0 && autonomous.portfolio_heat <= 1.0, "autonomous.portfolio_heat", "must be in (0, 1]")?;
+
+        let api = &autonomous.api;
+        check(!api.request_timeout.is_zero(), "api.request_timeout_secs", "must be greater than zero")?;
+        check(api.health.queue_degraded_pct > 0.0 && api.health.queue_degraded_pct <= 100.0, "api.health.queue_degraded_pct", "must be in (0, 100]")?;
+        let scoring = &api.scoring;
+        check(
+            [scoring.return_weight, scoring.consistency_weight, scoring.drawdown_weight].iter().all(|w| *w >= 0.0)
+                && scoring.return_weight + scoring.consistency_weight + scoring.drawdown_weight > 0.0,
+            "api.scoring",
+            "weights must not be negative, and one must be greater than zero",
+        )?;
+        for (name, key) in &api.keys {
+            let field = |field: &str| format!("api.keys.{}.{}", name, field);
+            check(!key.key.trim().is_empty(), &field("key"), "must not be empty")?;
+            check(!api.keys.iter().any(|(other, k)| other < name && k.key == key.key), &field("key"), "must differ from the other keys")?;
+            check(key.rate_limit_per_minute != Some(0), &field("rate_limit_per_minute"), "must be at least 1")?;
+            check(
+                key.account.as_ref().is_none_or(|account| account == DEFAULT_ACCOUNT || self.accounts.contains_key(account)),
+                &field("account"),
+                "must name an account",
+            )?;
+        }
+        for (name, bounds) in autonomous.metrics.histograms.all() {
+            check(
+                !bounds.is_empty() && bounds.iter().all(|b| b.is_finite()) && bounds.windows(2).all(|w| w[0] < w[1]),
+                &format!("metrics.histograms.{}", name),
+                "must list finite bounds in increasing order",
+            )?;
+        }
+        let series = &autonomous.metrics.series;
+        check(series.retention_hours > 0, "metrics.series.retention_hours", "must be at least 1")?;
+        check(
+            series.format != SeriesFormat::Parquet || cfg!(feature = "parquet"),
+            "metrics.series.format",
+            "needs a build with the parquet feature",
+        )?;
+
+        check(!self.service.snapshot_interval.is_zero(), "service.snapshot_interval_secs", "must be greater than zero")?;
+        if let Some(url) = &self.service.alert_webhook {
+            check(url.starts_with("http://") || url.starts_with("https://"), "service.alert_webhook", "must be an http(s) URL")?;
+        }
+
+        for (exchange, credentials) in &self.credentials {
+            check(
+                !credentials.api_key.trim().is_empty(),
+                &format!("credentials.{}.api_key", exchange),
+                "must not be empty",
+            )?;
+        }
+
+        let sections: Vec<_> = std::iter::once(("trading".to_string(), &self.trading))
+            .chain(self.accounts.iter().map(|(id, trading)| (format!("accounts.{}", id), trading)))
+            .collect();
+        for (i, (section, trading)) in sections.iter().enumerate() {
+            check(
+                trading.execution != ExecutionMode::BinanceTestnet || self.credentials.contains_key(&Exchange::Binance),
+                &format!("{}.execution", section),
+                "binance_testnet needs [credentials.binance]",
+            )?;
+            // Consolidated statistics add up the accounts as they are
+            check(
+                trading.reporting_currency.eq_ignore_ascii_case(&self.trading.reporting_currency),
+                &format!("{}.reporting_currency", section),
+                "must match trading.reporting_currency",
+            )?;
+            // Accounts inherit the [trading] path, so each needs its own
+            check(
+                trading.event_log.is_none() || !sections[..i].iter().any(|(_, other)| other.event_log == trading.event_log),
+                &format!("{}.event_log", section),
+                "must differ from the event logs of the other accounts",
+            )?;
+            check(
+                trading.portfolio_file.is_none() || !sections[..i].iter().any(|(_, other)| other.portfolio_file == trading.portfolio_file),
+                &format!("{}.portfolio_file", section),
+                "must differ from the portfolio files of the other accounts",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Credentials configured for an exchange
+    pub fn credentials_for(&self, exchange: Exchange) -> Option<&ExchangeCredentials> {
+        self.credentials.get(&exchange)
+    }
+}
+
+impl ConfigFile {
+    fn resolve(self) -> Result<RunConfig, ConfigError> {
+        let mut trading = PaperTradingConfig::default();
+        self.trading.apply(&mut trading);
+        apply_portfolio_capital("trading", &mut trading)?;
+
+        let accounts: BTreeMap<String, PaperTradingConfig> = self.accounts
+            .into_iter()
+            .map(|(id, section)| {
+                let mut account = trading.clone();
+                section.apply(&mut account);
+                apply_portfolio_capital(&format!("accounts.{}", id), &mut account)?;
+                Ok((id, account))
+            })
+            .collect::<Result<_, ConfigError>>()?;
+
+        let mut autonomous = AutonomousConfig {
+            scanner_config: self.scanner.clone(),
+            trading_config: trading.clone(),
+            accounts: accounts.clone(),
+            routes: self.routes.clone(),
+            ..AutonomousConfig::default()
+        };
+        self.autonomous.apply(&mut autonomous);
+        self.api.apply(&mut autonomous.api);
+        autonomous.metrics = self.metrics;
+
+        let mut reconciliation = ReconciliationConfig::default();
+        self.reconciliation.apply(&mut reconciliation);
+        let mut service = ServiceConfig::default();
+        self.service.apply(&mut service);
+
+        let credentials = self.credentials
+            .into_iter()
+            .map(|(name, credentials)| {
+                Exchange::ALL
+                    .iter()
+                    .find(|e| e.to_string().eq_ignore_ascii_case(&name))
+                    .map(|e| (*e, credentials))
+                    .ok_or_else(|| ConfigError::Invalid {
+                        key: format!("credentials.{}", name),
+                        message: format!("unknown exchange '{}'", name),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(RunConfig {
+            trading,
+            scanner: self.scanner,
+            autonomous,
+            accounts,
+            routes: self.routes,
+            reconciliation,
+            credentials,
+            service,
+        })
+    }
+}
+
+/// Start from the capital of the section's portfolio file, if it sets one
+fn apply_portfolio_capital(section: &str, config: &mut PaperTradingConfig) -> Result<(), ConfigError> {
+    let Some(path) = &config.portfolio_file else {
+        return Ok(());
+    };
+    let portfolio = PortfolioImport::load(path).map_err(|e| ConfigError::Invalid {
+        key: format!("{}.portfolio_file", section),
+        message: format!("{:#}", e),
+    })?;
+    if let Some(capital) = portfolio.capital {
+        config.initial_capital = capital;
+    }
+    Ok(())
+}
+
+/// Checks for a `[trading]` or `[accounts.<id>]` section
+fn validate_trading(section: &str, trading: &PaperTradingConfig) -> Result<(), ConfigError> {
+    let key = |name: &str| format!("{}.{}", section, name);
+    check(trading.initial_capital > 0.0, &key("initial_capital"), "must be positive")?;
+    check(trading.commission_rate >= 0.0, &key("commission_rate"), "must not be negative")?;
+    check(!trading.update_interval.is_zero(), &key("update_interval_ms"), "must be greater than zero")?;
+    check(!trading.reporting_currency.trim().is_empty(), &key("reporting_currency"), "must not be empty")?;
+    for (currency, rate) in &trading.fx_rates {
+        check(rate.is_finite() && *rate > 0.0, &key(&format!("fx_rates.{}", currency)), "must be positive")?;
+    }
+    check(trading.signal_queue.capacity > 0, &key("signal_queue.capacity"), "must be at least 1")?;
+    check(trading.order_event_queue.capacity > 0, &key("order_event_queue.capacity"), "must be at least 1")?;
+    let throttle = &trading.signal_throttle;
+    check(throttle.max_signals == 0 || throttle.interval_ms > 0, &key("signal_throttle.interval_ms"), "must be greater than zero when max_signals is set")?;
+    let participation = &trading.participation;
+    check((0.0..=1.0).contains(&participation.max_rate), &key("participation.max_rate"), "must be between 0 and 1")?;
+    check(participation.max_rate == 0.0 || participation.window_ms > 0, &key("participation.window_ms"), "must be greater than zero when max_rate is set")?;
+    for (symbol, instrument) in &trading.instruments {
+        let key = |name: &str| key(&format!("instruments.{}.{}", symbol, name));
+        check(instrument.multiplier.is_finite() && instrument.multiplier > 0.0, &key("multiplier"), "must be positive")?;
+        check(instrument.price_decimals <= 12, &key("price_decimals"), "must be at most 12")?;
+        check(instrument.quantity_step.is_finite() && instrument.quantity_step >= 0.0, &key("quantity_step"), "must not be negative")?;
+    }
+    let strategy_stops = trading.strategy_stops.iter().map(|(strategy, stops)| (format!("strategy_stops.{}", strategy), stops));
+    for (name, stops) in std::iter::once(("stops".to_string(), &trading.stops)).chain(strategy_stops) {
+        let key = |field: &str| key(&format!("{}.{}", name, field));
+        check(stops.atr_period > 0, &key("atr_period"), "must be at least 1")?;
+        check(stops.stop_multiple.is_finite() && stops.stop_multiple > 0.0, &key("stop_multiple"), "must be positive")?;
+        check(stops.take_profit_multiple.is_finite() && stops.take_profit_multiple > 0.0, &key("take_profit_multiple"), "must be positive")?;
+    }
+    let symbol_marks = trading.symbol_marks.iter().map(|(symbol, marks)| (format!("symbol_marks.{}", symbol), marks));
+    for (name, marks) in std::iter::once(("marks".to_string(), &trading.marks)).chain(symbol_marks) {
+        check(marks.half_life_secs.is_finite() && marks.half_life_secs > 0.0, &key(&format!("{}.half_life_secs", name)), "must be positive")?;
+    }
+    for (i, rule) in trading.trade_management.iter().enumerate() {
+        let key = |field: &str| key(&format!("trade_management[{}].{}", i, field));
+        let (TradeRule::BreakEven { at_r, .. } | TradeRule::PartialProfit { at_r, .. } | TradeRule::Trail { at_r, .. }) = *rule;
+        check(at_r.is_finite() && at_r > 0.0, &key("at_r"), "must be positive")?;
+        match *rule {
+            TradeRule::BreakEven { offset_r, .. } => check(offset_r.is_finite() && offset_r < at_r, &key("offset_r"), "must be less than at_r")?,
+            TradeRule::PartialProfit { fraction, .. } => check(fraction > 0.0 && fraction <= 1.0, &key("fraction"), "must be above 0 and at most 1")?,
+            TradeRule::Trail { distance_r, .. } => check(distance_r.is_finite() && distance_r > 0.0, &key("distance_r"), "must be positive")?,
+        }
+    }
+    if let Some(rebalance) = &trading.rebalance {
+        for (symbol, weight) in &rebalance.targets {
+            check((0.0..=1.0).contains(weight), &key(&format!("rebalance.targets.{}", symbol)), "must be between 0 and 1")?;
+        }
+        check(rebalance.targets.values().sum::<f64>() <= 1.0 + 1e-9, &key("rebalance.targets"), "must not add up to more than 1")?;
+        check((0.0..1.0).contains(&rebalance.tolerance), &key("rebalance.tolerance"), "must be at least 0 and below 1")?;
+        check(rebalance.min_notional.is_finite() && rebalance.min_notional >= 0.0, &key("rebalance.min_notional"), "must not be negative")?;
+        check(rebalance.interval_secs != Some(0), &key("rebalance.interval_secs"), "must be greater than zero")?;
+    }
+    if let Some(chaos) = &trading.chaos {
+        for (i, fault) in chaos.faults.iter().enumerate() {
+            let key = |field: &str| key(&format!("chaos.faults[{}].{}", i, field));
+            check(fault.duration_secs > 0, &key("duration_secs"), "must be greater than zero")?;
+            check(fault.kind != FaultKind::Delay || fault.delay_ms > 0, &key("delay_ms"), "must be greater than zero for a delay")?;
+        }
+        let random = &chaos.random;
+        for (name, rate) in [("outage_rate", random.outage_rate), ("delay_rate", random.delay_rate), ("duplicate_rate", random.duplicate_rate), ("reject_rate", random.reject_rate)] {
+            check((0.0..=1.0).contains(&rate), &key(&format!("chaos.random.{}", name)), "must be between 0 and 1")?;
+        }
+    }
+    let history = &trading.history;
+    for (name, retention) in [("signals", history.signals), ("returns", history.returns), ("orders", history.orders)] {
+        check(retention > 0, &key(&format!("history.{}", name)), "must be at least 1")?;
+    }
+
+    let risk = &trading.risk_limits;
+    check((0.0..100.0).contains(&risk.stop_loss_pct), &key("risk_limits.stop_loss_pct"), "must be between 0 and 100")?;
+    check(risk.take_profit_pct >= 0.0, &key("risk_limits.take_profit_pct"), "must not be negative")?;
+    check((0.0..=100.0).contains(&risk.position_size_pct), &key("risk_limits.position_size_pct"), "must be between 0 and 100")?;
+    check((0.0..=100.0).contains(&risk.equity_stop_out_pct), &key("risk_limits.equity_stop_out_pct"), "must be between 0 and 100")?;
+    check(risk.max_positions > 0, &key("risk_limits.max_positions"), "must be at least 1")?;
+    Ok(())
+}
+
+fn check(ok: bool, key: &str, message: &str) -> Result<(), ConfigError> {
+    if ok {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid {
+            key: key.to_string(),
+            message: message.to_string(),
+        })
+    }
+}
+
+/// Key path for an override variable, e.g. `NEUROMORPHIC_TRADING__HEDGE_MODE` -> trading.hedge_mode.
+/// Variables without a section (like `NEUROMORPHIC_LOG_FORMAT`) are not config overrides.
+fn env_key(var: &str) -> Option<Vec<String>> {
+    let rest = var.strip_prefix(ENV_PREFIX)?;
+    let key: Vec<String> = rest.split(ENV_SEPARATOR).map(|part| part.to_lowercase()).collect();
+    (key.len() > 1 && key.iter().all(|part| !part.is_empty())).then_some(key)
+}
+
+/// Set an override, reading the raw value as TOML first (numbers, booleans, arrays)
+/// and falling back to a plain string, e.g. for numeric-looking API keys
+fn apply_override(merged: &mut toml::Table, key: &[String], raw: &str) -> Result<(), String> {
+    let typed = toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"));
+
+    let mut first_error = None;
+    for value in typed.into_iter().chain(std::iter::once(toml::Value::String(raw.to_string()))) {
+        let mut candidate = merged.clone();
+        set_key(&mut candidate, key, value);
+        match toml::Value::Table(candidate.clone()).try_into::<ConfigFile>() {
+            Ok(_) => {
+                *merged = candidate;
+                return Ok(());
+            }
+            Err(e) => {
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+    }
+
+    Err(first_error.unwrap_or_default())
+}
+
+fn set_key(table: &mut toml::Table, key: &[String], value: toml::Value) {
+    match key {
+        [] => {}
+        [last] => {
+            table.insert(last.clone(), value);
+        }
+        [first, rest @ ..] => {
+            let entry = table
+                .entry(first.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            if let toml::Value::Table(inner) = entry {
+                set_key(inner, rest, value);
+            }
+        }
+    }
+}
+
+/// Deep-merge `overlay` into `base`; tables merge key by key, other values replace
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(inner)) => merge_tables(existing, inner),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Role;
+    use crate::market_scanner::Granularity;
+    use crate::metrics::HistogramBuckets;
+    use crate::paper_trading::{MarkSource, OverflowPolicy, StopMode};
+
+    fn source(text: &str) -> (PathBuf, String) {
+        (PathBuf::from("test.toml"), text.to_string())
+    }
+
+    #[test]
+    fn test_layered_config() {
+        let base = source(r#"
+            [trading]
+            initial_capital = 50000.0
+            hedge_mode = true
+            seed = 42
+            signal_queue = { capacity = 500, policy = "reject_new" }
+
+            [trading.risk_limits]
+            stop_loss_pct = 1.5
+
+            [trading.instruments.ES]
+            multiplier = 50.0
+            quantity_step = 1.0
+            hours = { session = { open = "18:00:00", close = "17:00:00", timezone = "new_york" } }
+
+            [trading.strategy_stops."Momentum Breakout"]
+            mode = "atr"
+            granularity = "1h"
+
+            [trading.rebalance]
+            targets = { SPY = 0.6, TLT = 0.4 }
+            interval_secs = 86400
+
+            [trading.history]
+            orders = 500
+
+            [trading.symbol_marks.DOGEUSDT]
+            source = "ewma_mid"
+            half_life_secs = 60.0
+
+            [scanner]
+            included_exchanges = ["Binance"]
+
+            [accounts.momentum]
+            initial_capital = 10000.0
+            execution = "binance_testnet"
+
+            [[routes]]
+            name = "breakouts"
+            account = "momentum"
+            strategies = ["Momentum Breakout"]
+            size_multiplier = 0.5
+
+            [reconciliation]
+            auto_correct = true
+
+            [api]
+            port = 8080
+
+            [api.keys.grafana]
+            key = "read"
+            rate_limit_per_minute = 600
+
+            [metrics.histograms]
+            fill_latency_ms = [100.0, 1000.0]
+
+            [metrics.series]
+            interval_secs = 60
+            dir = "/var/lib/paper-trader/metrics"
+
+            [credentials.binance]
+            api_key = "key"
+            api_secret = "secret"
+        "#);
+        let local = source("[trading]\ninitial_capital = 75000.0\n");
+        let env = vec![
+            ("NEUROMORPHIC_AUTONOMOUS__MAX_POSITIONS".to_string(), "3".to_string()),
+            ("NEUROMORPHIC_CREDENTIALS__BINANCE__API_SECRET".to_string(), "12345".to_string()),
+            ("NEUROMORPHIC_LOG_FORMAT".to_string(), "json".to_string()),
+            ("NEUROMORPHIC_API__KEYS__OPS__KEY".to_string(), "write".to_string()),
+            ("NEUROMORPHIC_API__KEYS__OPS__ROLE".to_string(), "operator".to_string()),
+        ];
+
+        let config = RunConfig::from_sources(vec![base, local], env).unwrap();
+        assert_eq!(config.trading.initial_capital, 75000.0);
+        assert!(config.trading.hedge_mode);
+        assert_eq!((config.trading.seed, config.trading.id_seed), (Some(42), None));
+        assert_eq!(config.trading.risk_limits.stop_loss_pct, 1.5);
+        assert_eq!(config.trading.signal_queue, QueueConfig { capacity: 500, policy: OverflowPolicy::RejectNew });
+        assert_eq!(config.trading.order_event_queue, QueueConfig::default());
+        assert_eq!(config.trading.risk_limits.take_profit_pct, RiskLimits::default().take_profit_pct);
+        let es = &config.trading.instruments["ES"];
+        assert_eq!((es.asset_class, es.multiplier, es.price_decimals), (AssetClass::Equity, 50.0, 2));
+        let breakout = config.trading.stops_for(Some("Momentum Breakout"));
+        assert_eq!((breakout.mode, breakout.granularity, breakout.atr_period), (StopMode::Atr, Granularity::OneHour, 14));
+        assert_eq!(config.trading.stops_for(None).mode, StopMode::Percent);
+        let rebalance = config.trading.rebalance.as_ref().unwrap();
+        assert_eq!((rebalance.targets["TLT"], rebalance.tolerance, rebalance.interval_secs), (0.4, 0.02, Some(86400)));
+        assert_eq!((config.trading.history.orders, config.trading.history.returns), (500, 1000));
+        assert_eq!(config.trading.symbol_marks["DOGEUSDT"], MarkPrice { source: MarkSource::EwmaMid, half_life_secs: 60.0 });
+        assert_eq!(config.trading.marks.source, MarkSource::Composite);
+        assert_eq!(config.autonomous.max_positions, 3);
+        assert_eq!(config.autonomous.trading_config.initial_capital, 75000.0);
+        assert_eq!(config.scanner.included_exchanges, vec![Exchange::Binance]);
+        assert_eq!(config.credentials_for(Exchange::Binance).unwrap().api_secret, "12345");
+
+        // Accounts inherit unset keys from [trading]
+        let momentum = &config.accounts["momentum"];
+        assert_eq!(momentum.initial_capital, 10000.0);
+        assert!(momentum.hedge_mode);
+        assert_eq!(momentum.execution, ExecutionMode::BinanceTestnet);
+        assert_eq!(config.trading.execution, ExecutionMode::Simulated);
+        assert_eq!(config.autonomous.accounts.len(), 1);
+        assert_eq!(config.autonomous.routes[0].account, "momentum");
+        assert!(config.reconciliation.auto_correct);
+        assert_eq!(config.reconciliation.interval, ReconciliationConfig::default().interval);
+        let api = &config.autonomous.api;
+        assert_eq!(api.port, 8080);
+        assert_eq!((api.keys["grafana"].role, api.keys["grafana"].rate_limit_per_minute), (Role::ReadOnly, Some(600)));
+        assert_eq!((api.keys["ops"].key.as_str(), api.keys["ops"].role), ("write", Role::Operator));
+        let histograms = &config.autonomous.metrics.histograms;
+        assert_eq!(histograms.fill_latency_ms, vec![100.0, 1000.0]);
+        assert_eq!(histograms.trade_pnl, HistogramBuckets::default().trade_pnl);
+        let series = &config.autonomous.metrics.series;
+        assert_eq!((series.interval_secs, series.retention_hours), (60, 24));
+        assert_eq!(series.dir.as_deref(), Some(std::path::Path::new("/var/lib/paper-trader/metrics")));
+
+        // Errors name the offending key
+        let err = RunConfig::from_sources(vec![source("[trading]\ninitial_capital = -1.0\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("trading.initial_capital"));
+
+        let err = RunConfig::from_sources(vec![source("[trading]\nsignal_queue = { capacity = 0 }\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("trading.signal_queue.capacity"));
+
+        let err = RunConfig::from_sources(vec![source("[metrics.histograms]\ntrade_pnl = [10.0, -10.0]\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("metrics.histograms.trade_pnl"));
+
+        let err = RunConfig::from_sources(vec![source("[accounts.default]\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("accounts.default"));
+
+        let err = RunConfig::from_sources(vec![source("[[routes]]\nname = \"x\"\naccount = \"missing\"\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("routes[0].account"));
+
+        let err = RunConfig::from_sources(vec![source("[accounts.live]\nexecution = \"binance_testnet\"\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("accounts.live.execution"));
+
+        let err = RunConfig::from_sources(vec![source("[trading]\nevent_log = \"events.jsonl\"\n[accounts.live]\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("accounts.live.event_log"));
+
+        let err = RunConfig::from_sources(vec![source("[trading]\nportfolio_file = \"missing.json\"\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("trading.portfolio_file"));
+
+        let err = RunConfig::from_sources(vec![source("[accounts.live]\nreporting_currency = \"EUR\"\n")], vec![]).unwrap_err();
+        assert!(err.to_string().contains("accounts.live.reporting_currency"));
+
+        let env = vec![("NEUROMORPHIC_TRADING__HEDGE_MODE".to_string(), "maybe".to_string())];
+        let err = RunConfig::from_sources(vec![], env).unwrap_err();
+        assert!(err.to_string().contains("trading.hedge_mode"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Reloading settings into a running session
+//!
+//! Risk limits, the autonomous system's strategy parameters and the
+//! screener criteria can change without a restart. A reload reads and
+//! validates the config files first, so a bad edit leaves every setting as it
+//! was, then swaps each group whole and returns what changed. Everything else
+//! in the files, like accounts, routes or the API, still needs a restart.
+//!
+//! A reload is requested through `AutonomousControl`: by the control API, by
+//! SIGHUP or by `watch_files` when a config file is modified.
+
+use super::RunConfig;
+use crate::control::AutonomousControl;
+use crate::market_scanner::ScreeningCriteria;
+use crate::paper_trading::{RiskLimits, DEFAULT_ACCOUNT};
+use crate::AutonomousConfig;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// How often `watch_files` checks the files
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// One changed setting, with values as JSON
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    pub key: String, // Config key path, e.g. "trading.risk_limits.max_positions"
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// `[autonomous]` keys that take effect without a restart
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrategyParams {
+    pub enable_auto_trading: bool,
+    pub max_positions: usize,
+    pub max_daily_trades: usize,
+    pub risk_per_trade: f64,
+    pub min_opportunity_confidence: f64,
+    pub portfolio_heat: f64,
+    pub opportunity_cooldown_secs: u64,
+}
+
+impl StrategyParams {
+    pub fn of(config: &AutonomousConfig) -> Self {
+        Self {
+            enable_auto_trading: config.enable_auto_trading,
+            max_positions: config.max_positions,
+            max_daily_trades: config.max_daily_trades,
+            risk_per_trade: config.risk_per_trade,
+            min_opportunity_confidence: config.min_opportunity_confidence,
+            portfolio_heat: config.portfolio_heat,
+            opportunity_cooldown_secs: config.opportunity_cooldown.as_secs(),
+        }
+    }
+
+    pub fn apply(&self, config: &mut AutonomousConfig) {
+        config.enable_auto_trading = self.enable_auto_trading;
+        config.max_positions = self.max_positions;
+        config.max_daily_trades = self.max_daily_trades;
+        config.risk_per_trade = self.risk_per_trade;
+        config.min_opportunity_confidence = self.min_opportunity_confidence;
+        config.portfolio_heat = self.portfolio_heat;
+        config.opportunity_cooldown = Duration::from_secs(self.opportunity_cooldown_secs);
+    }
+}
+
+/// Everything a reload can change
+#[derive(Debug, Clone)]
+pub struct ReloadableSettings {
+    pub risk_limits: BTreeMap<String, RiskLimits>, // By account id
+    pub strategy: StrategyParams,
+    pub screening: ScreeningCriteria,
+}
+
+impl ReloadableSettings {
+    pub fn from_run_config(config: &RunConfig) -> Self {
+        let risk_limits = std::iter::once((DEFAULT_ACCOUNT.to_string(), config.trading.risk_limits.clone()))
+            .chain(config.accounts.iter().map(|(id, account)| (id.clone(), account.risk_limits.clone())))
+            .collect();
+        Self {
+            risk_limits,
+            strategy: StrategyParams::of(&config.autonomous),
+            screening: config.scanner.screening.clone(),
+        }
+    }
+
+    /// Settings that differ in `next`, by config key. Risk limits of accounts
+    /// only in one of the two are left out, as accounts need a restart.
+    pub fn diff(&self, next: &ReloadableSettings) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        for (id, limits) in &self.risk_limits {
+            if let Some(next_limits) = next.risk_limits.get(id) {
+                let section = if id == DEFAULT_ACCOUNT { "trading".to_string() } else { format!("accounts.{}", id) };
+                changes.extend(diff(&format!("{}.risk_limits", section), limits, next_limits));
+            }
+        }
+        changes.extend(diff("autonomous", &self.strategy, &next.strategy));
+        changes.extend(diff("scanner.screening", &self.screening, &next.screening));
+        changes
+    }
+}
+
+/// Leaf values of `old` and `new` that differ, keyed below `prefix`
+pub fn diff<T: Serialize>(prefix: &str, old: &T, new: &T) -> Vec<ConfigChange> {
+    let mut old_values = BTreeMap::new();
+    let mut new_values = BTreeMap::new();
+    flatten(prefix, &serde_json::to_value(old).unwrap_or_default(), &mut old_values);
+    flatten(prefix, &serde_json::to_value(new).unwrap_or_default(), &mut new_values);
+
+    let mut keys: Vec<&String> = old_values.keys().chain(new_values.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| old_values.get(*key) != new_values.get(*key))
+        .map(|key| ConfigChange {
+            key: key.clone(),
+            old: old_values.get(key).cloned(),
+            new: new_values.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Tables become dotted keys; arrays and scalars are compared whole
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(table) => {
+            for (key, value) in table {
+                flatten(&format!("{}.{}", prefix, key), value, out);
+            }
+        }
+        value => {
+            out.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+/// Request a reload whenever one of `paths` is modified
+pub fn watch_files(paths: Vec<PathBuf>, control: AutonomousControl) -> JoinHandle<()> {
+    let modified = |paths: &[PathBuf]| -> Vec<Option<SystemTime>> {
+        paths.iter().map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok()).collect()
+    };
+    tokio::spawn(async move {
+        let mut last = modified(&paths);
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let current = modified(&paths);
+            if current != last {
+                last = current;
+                control.request_reload();
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_of_reloadable_settings() {
+        let source = |text: &str| vec![(PathBuf::from("paper-trader.toml"), text.to_string())];
+        let base = "[trading.risk_limits]\nmax_positions = 10\n[accounts.live.risk_limits]\nmax_positions = 5\n";
+        let current = ReloadableSettings::from_run_config(&RunConfig::from_sources(source(base), vec![]).unwrap());
+        let edited = format!(
+            "{}[autonomous]\nmin_opportunity_confidence = 0.9\n[scanner.screening]\nmin_price = 1.0\nsectors = [\"Tech\"]\n",
+            base.replace("max_positions = 5", "max_positions = 3"),
+        );
+        let next = ReloadableSettings::from_run_config(&RunConfig::from_sources(source(&edited), vec![]).unwrap());
+
+        let changes = current.diff(&next);
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "accounts.live.risk_limits.max_positions",
+                "autonomous.min_opportunity_confidence",
+                "scanner.screening.min_price",
+                "scanner.screening.sectors",
+            ]
+        );
+        assert_eq!((changes[0].old.as_deref(), changes[0].new.as_deref()), (Some("5"), Some("3")));
+        assert_eq!(changes[3].new.as_deref(), Some("[\"Tech\"]"));
+        assert!(current.diff(&current).is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Daily counters of the autonomous system
+//!
+//! Trades, realized P&L and the kill switch count per day on the engine
+//! clock. With a path set, every change is written to a JSON file keyed by
+//! date and the file is read back on startup, so restarting mid-day keeps the
+//! daily trade limit and a tripped kill switch instead of starting over.
+//!
+//! The day's realized P&L is accumulated from changes in the accounts' total
+//! realized P&L. That total starts over with the engines, so the first reading
+//! after a restart only sets the baseline.
+
+use crate::paper_trading::SharedClock;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Days kept in the file
+const KEPT_DAYS: usize = 30;
+
+/// Counters of one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyCounters {
+    pub date: NaiveDate,
+    pub trades: usize,
+    pub realized_pnl: f64,
+    pub kill_switch: Option<String>, // Why trading stopped for the day, once tripped
+}
+
+impl DailyCounters {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            trades: 0,
+            realized_pnl: 0.0,
+            kill_switch: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct LedgerState {
+    days: BTreeMap<NaiveDate, DailyCounters>,
+    realized_baseline: Option<f64>, // Last total realized P&L read
+}
+
+/// Daily counters, optionally persisted
+pub struct DailyLedger {
+    clock: SharedClock,
+    path: Option<PathBuf>,
+    state: Mutex<LedgerState>,
+}
+
+impl DailyLedger {
+    /// Counters kept in memory only
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            clock,
+            path: None,
+            state: Mutex::new(LedgerState::default()),
+        }
+    }
+
+    /// Counters persisted at `path`, reloading what it holds; a missing
+    /// file starts empty
+    pub fn open(path: impl AsRef<Path>, clock: SharedClock) -> Result<Self> {
+        let path = path.as_ref();
+        let days = match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).with_context(|| format!("Invalid daily counters {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read daily counters {}", path.display())),
+        };
+        Ok(Self {
+            clock,
+            path: Some(path.to_path_buf()),
+            state: Mutex::new(LedgerState {
+                days,
+                realized_baseline: None,
+            }),
+        })
+    }
+
+    /// Today's counters
+    pub fn today(&self) -> DailyCounters {
+        let date = self.clock.now().date_naive();
+        self.state.lock().days.get(&date).cloned().unwrap_or_else(|| DailyCounters::new(date))
+    }
+
+    /// Count an executed trade, returning today's count
+    pub fn record_trade(&self) -> usize {
+        self.update(|today| {
+            today.trades += 1;
+            today.trades
+        })
+    }
+
+    /// Add the change in the accounts' total realized P&L to today's,
+    /// returning today's
+    pub fn record_realized_pnl(&self, total_realized: f64) -> f64 {
+        let mut state = self.state.lock();
+        let change = total_realized - state.realized_baseline.unwrap_or(total_realized);
+        state.realized_baseline = Some(total_realized);
+        if change == 0.0 {
+            return self.today_in(&mut state).realized_pnl;
+        }
+        let pnl = {
+            let today = self.today_in(&mut state);
+            today.realized_pnl += change;
+            today.realized_pnl
+        };
+        self.persist(&state);
+        pnl
+    }
+
+    /// Stop trading for the rest of the day; returns false if already stopped
+    pub fn trip_kill_switch(&self, reason: impl Into<String>) -> bool {
+        let reason = reason.into();
+        self.update(|today| {
+            if today.kill_switch.is_some() {
+                return false;
+            }
+            today.kill_switch = Some(reason);
+            true
+        })
+    }
+
+    /// Counters of the kept days, oldest first
+    pub fn history(&self) -> Vec<DailyCounters> {
+        self.state.lock().days.values().cloned().collect()
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut DailyCounters) -> T) -> T {
+        let mut state = self.state.lock();
+        let result = f(self.today_in(&mut state));
+        self.persist(&state);
+        result
+    }
+
+    fn today_in<'a>(&self, state: &'a mut LedgerState) -> &'a mut DailyCounters {
+        let date = self.clock.now().date_naive();
+        if !state.days.contains_key(&date) {
+            if !state.days.is_empty() {
+                info!(%date, "Daily counters reset");
+            }
+            while state.days.len() >= KEPT_DAYS {
+                state.days.pop_first();
+            }
+        }
+        state.days.entry(date).or_insert_with(|| DailyCounters::new(date))
+    }
+
+    fn persist(&self, state: &LedgerState) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let written = serde_json::to_string_pretty(&state.days)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display())));
+        if let Err(e) = written {
+            warn!(error = %e, "Failed to persist daily counters");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper_trading::SimulatedClock;
+    use chrono::{DateTime, Utc};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_counters_survive_restart_and_reset_daily() {
+        let path = std::env::temp_dir().join(format!("daily-counters-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let start: DateTime<Utc> = "2026-03-02T09:00:00Z".parse().unwrap();
+        let clock = Arc::new(SimulatedClock::starting_at(start));
+
+        let ledger = DailyLedger::open(&path, clock.clone()).unwrap();
+        ledger.record_trade();
+        assert_eq!(ledger.record_trade(), 2);
+        ledger.record_realized_pnl(0.0);
+        assert_eq!(ledger.record_realized_pnl(-300.0), -300.0);
+        assert!(ledger.trip_kill_switch("daily loss"));
+        assert!(!ledger.trip_kill_switch("again"));
+
+        // The restarted engines report realized P&L from zero again
+        let ledger = DailyLedger::open(&path, clock.clone()).unwrap();
+        ledger.record_realized_pnl(0.0);
+        ledger.record_realized_pnl(-50.0);
+        let today = ledger.today();
+        assert_eq!((today.trades, today.realized_pnl), (2, -350.0));
+        assert_eq!(today.kill_switch.as_deref(), Some("daily loss"));
+
+        clock.advance(Duration::from_secs(86_400));
+        assert_eq!(ledger.today().trades, 0);
+        assert_eq!(ledger.record_trade(), 1);
+        assert_eq!(DailyLedger::open(&path, clock).unwrap().history().len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}
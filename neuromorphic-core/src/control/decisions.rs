@@ -0,0 +1,73 @@
+//! Opportunity decisions
+//!
+//! Every opportunity the autonomous system sees ends in one decision: traded,
+//! skipped for a reason, or, in shadow mode, what it would have done. Shadow
+//! decisions never reach the engines; they carry the notional that would have
+//! been traded and the fill the engine's slippage model gives at the latest
+//! price, so filter settings can be audited before trading is switched on.
+
+use crate::exchanges::Symbol;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Why an opportunity wasn't traded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    AutoTradingDisabled,
+    Paused,
+    Blacklisted,
+    Confidence,
+    KillSwitch,
+    DailyLimit,
+    MaxPositions,
+    PortfolioHeat,
+    Cooldown,
+    NoRiskBudget,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::AutoTradingDisabled => "auto_trading_disabled",
+            SkipReason::Paused => "paused",
+            SkipReason::Blacklisted => "blacklisted",
+            SkipReason::Confidence => "confidence",
+            SkipReason::KillSwitch => "kill_switch",
+            SkipReason::DailyLimit => "daily_limit",
+            SkipReason::MaxPositions => "max_positions",
+            SkipReason::PortfolioHeat => "portfolio_heat",
+            SkipReason::Cooldown => "cooldown",
+            SkipReason::NoRiskBudget => "no_risk_budget",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Decision {
+    Executed,
+    WouldExecute,
+    Skipped { reason: SkipReason },
+}
+
+/// Decision taken on one opportunity
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DecisionRecord {
+    pub timestamp: DateTime<Utc>,
+    pub symbol: Symbol,
+    pub strategy: String,
+    pub confidence: f64,
+    pub decision: Decision,
+    pub shadow: bool,
+    pub notional: Option<f64>,   // Sized trade, when it got that far
+    pub fill_price: Option<f64>, // Expected fill of a shadow trade
+}
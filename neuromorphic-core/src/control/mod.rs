@@ -0,0 +1,225 @@
+//! Runtime controls of the autonomous system
+//!
+//! `AutonomousControl` is a cheap handle shared by the trading loop, the
+//! control API and library users. Pausing stops new trades but leaves open
+//! positions and their exits alone; flattening is a request the trading loop
+//! carries out, cancelling resting orders and closing every position, and it
+//! doesn't pause, so pause first to stay flat. Reloading the config files is
+//! a request the trading loop carries out too, between opportunities.
+//!
+//! A raised confidence threshold only ever tightens the configured one and
+//! lapses on its own once its time is up.
+
+pub mod daily;
+pub mod decisions;
+
+pub use daily::{DailyCounters, DailyLedger};
+pub use decisions::{Decision, DecisionRecord, SkipReason};
+
+use crate::market_scanner::universe::universe_key;
+use crate::exchanges::Symbol;
+use crate::paper_trading::SharedClock;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ConfidenceOverride {
+    min_confidence: f64,
+    until: DateTime<Utc>,
+}
+
+/// Current control settings
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ControlStatus {
+    pub paused: bool,
+    pub blacklist: Vec<Symbol>,
+    pub min_confidence_override: Option<f64>,
+    pub override_until: Option<DateTime<Utc>>,
+    pub flatten_pending: bool,
+    pub reload_pending: bool,
+}
+
+struct ControlState {
+    clock: SharedClock,
+    paused: AtomicBool,
+    blacklist: RwLock<BTreeMap<String, Symbol>>, // Keyed by `universe_key`
+    confidence: RwLock<Option<ConfidenceOverride>>,
+    flatten_pending: AtomicBool,
+    flatten: Notify,
+    reload_pending: AtomicBool,
+    reload: Notify,
+}
+
+/// Handle to pause, resume and override the autonomous system
+#[derive(Clone)]
+pub struct AutonomousControl {
+    state: Arc<ControlState>,
+}
+
+impl AutonomousControl {
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            state: Arc::new(ControlState {
+                clock,
+                paused: AtomicBool::new(false),
+                blacklist: RwLock::new(BTreeMap::new()),
+                confidence: RwLock::new(None),
+                flatten_pending: AtomicBool::new(false),
+                flatten: Notify::new(),
+                reload_pending: AtomicBool::new(false),
+                reload: Notify::new(),
+            }),
+        }
+    }
+
+    /// Stop opening trades until `resume`
+    pub fn pause(&self) {
+        self.state.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.state.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.paused.load(Ordering::Relaxed)
+    }
+
+    /// Never trade this symbol, whatever the separator or case; returns
+    /// false if it was already blacklisted
+    pub fn blacklist(&self, symbol: Symbol) -> bool {
+        let mut blacklist = self.state.blacklist.write();
+        let key = universe_key(&symbol);
+        if blacklist.contains_key(&key) {
+            return false;
+        }
+        blacklist.insert(key, symbol);
+        true
+    }
+
+    /// Returns false if the symbol wasn't blacklisted
+    pub fn unblacklist(&self, symbol: &Symbol) -> bool {
+        self.state.blacklist.write().remove(&universe_key(symbol)).is_some()
+    }
+
+    pub fn is_blacklisted(&self, symbol: &Symbol) -> bool {
+        self.state.blacklist.read().contains_key(&universe_key(symbol))
+    }
+
+    /// Require at least `min_confidence` for the next `duration`
+    pub fn raise_min_confidence(&self, min_confidence: f64, duration: Duration) {
+        let until = self.state.clock.now() + chrono::Duration::from_std(duration).unwrap_or(chrono::TimeDelta::MAX);
+        *self.state.confidence.write() = Some(ConfidenceOverride { min_confidence, until });
+    }
+
+    pub fn clear_min_confidence(&self) {
+        *self.state.confidence.write() = None;
+    }
+
+    /// Confidence an opportunity needs: `configured`, or the raised
+    /// threshold while it lasts and is higher
+    pub fn min_confidence(&self, configured: f64) -> f64 {
+        match self.active_override() {
+            Some(raised) => configured.max(raised.min_confidence),
+            None => configured,
+        }
+    }
+
+    fn active_override(&self) -> Option<ConfidenceOverride> {
+        let now = self.state.clock.now();
+        let current = *self.state.confidence.read();
+        current.filter(|raised| now < raised.until)
+    }
+
+    /// Ask the trading loop to cancel resting orders and close every position
+    pub fn request_flatten(&self) {
+        self.state.flatten_pending.store(true, Ordering::Relaxed);
+        self.state.flatten.notify_one();
+    }
+
+    /// Wait for a flatten request and take it
+    pub async fn flatten_requested(&self) {
+        loop {
+            self.state.flatten.notified().await;
+            if self.state.flatten_pending.swap(false, Ordering::Relaxed) {
+                return;
+            }
+        }
+    }
+
+    /// Ask the trading loop to reload the reloadable settings from the config files
+    pub fn request_reload(&self) {
+        self.state.reload_pending.store(true, Ordering::Relaxed);
+        self.state.reload.notify_one();
+    }
+
+    /// Wait for a reload request and take it
+    pub async fn reload_requested(&self) {
+        loop {
+            self.state.reload.notified().await;
+            if self.state.reload_pending.swap(false, Ordering::Relaxed) {
+                return;
+            }
+        }
+    }
+
+    pub fn status(&self) -> ControlStatus {
+        let raised = self.active_override();
+        ControlStatus {
+            paused: self.is_paused(),
+            blacklist: self.state.blacklist.read().values().cloned().collect(),
+            min_confidence_override: raised.map(|r| r.min_confidence),
+            override_until: raised.map(|r| r.until),
+            flatten_pending: self.state.flatten_pending.load(Ordering::Relaxed),
+            reload_pending: self.state.reload_pending.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper_trading::SimulatedClock;
+
+    #[tokio::test]
+    async fn test_controls() {
+        let clock = Arc::new(SimulatedClock::starting_at(Utc::now()));
+        let control = AutonomousControl::new(clock.clone());
+
+        control.pause();
+        assert!(control.status().paused);
+        control.resume();
+        assert!(!control.is_paused());
+
+        assert!(control.blacklist(Symbol::new("btc-usdt")));
+        assert!(!control.blacklist(Symbol::new("BTCUSDT")));
+        assert!(control.is_blacklisted(&Symbol::new("BTCUSDT")));
+        assert!(control.unblacklist(&Symbol::new("BTC-USDT")));
+        assert!(control.status().blacklist.is_empty());
+
+        // The raised threshold never loosens the configured one and lapses
+        control.raise_min_confidence(0.9, Duration::from_secs(600));
+        assert_eq!(control.min_confidence(0.75), 0.9);
+        assert_eq!(control.min_confidence(0.95), 0.95);
+        clock.advance(Duration::from_secs(601));
+        assert_eq!(control.min_confidence(0.75), 0.75);
+        assert_eq!(control.status().min_confidence_override, None);
+
+        control.request_flatten();
+        assert!(control.status().flatten_pending);
+        tokio::time::timeout(Duration::from_secs(1), control.flatten_requested()).await.unwrap();
+        assert!(!control.status().flatten_pending);
+
+        control.request_reload();
+        assert!(control.status().reload_pending);
+        tokio::time::timeout(Duration::from_secs(1), control.reload_requested()).await.unwrap();
+        assert!(!control.status().reload_pending);
+    }
+}
@@ -0,0 +1,331 @@
+//! Parquet files of closed klines, partitioned by symbol and interval
+
+use crate::exchanges::{Exchange, KlineInterval, Symbol, UniversalKline};
+use crate::market_scanner::{Candle, MarketData, PriceBar, PriceHistory};
+use anyhow::{bail, Context, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float64Type, Int64Type, UInt64Type};
+use arrow_array::{ArrayRef, ArrowPrimitiveType, Float64Array, Int64Array, PrimitiveArray, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use chrono::{DateTime, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One part file of a series, with the open times of its first and last kline
+struct Part {
+    first_ms: i64,
+    last_ms: i64,
+    path: PathBuf,
+}
+
+/// Klines on disk under `<root>/<SYMBOL>/<interval>/`
+#[derive(Clone, Debug)]
+pub struct KlineCache {
+    root: PathBuf,
+}
+
+impl KlineCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory of a series; a '/' in the symbol becomes '-'
+    fn dir(&self, symbol: &Symbol, interval: KlineInterval) -> PathBuf {
+        self.root.join(symbol.as_str().replace('/', "-")).join(interval.to_string())
+    }
+
+    /// Part files of a series, oldest first; none when nothing is cached
+    fn parts(&self, symbol: &Symbol, interval: KlineInterval) -> Result<Vec<Part>> {
+        let dir = self.dir(symbol, interval);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut parts = Vec::new();
+        for entry in std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("parquet") {
+                continue;
+            }
+            let range = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.split_once('-'));
+            let Some((Ok(first_ms), Ok(last_ms))) = range.map(|(first, last)| (first.parse(), last.parse())) else {
+                bail!("Unexpected file {} in the kline cache", path.display());
+            };
+            parts.push(Part { first_ms, last_ms, path });
+        }
+        parts.sort_by_key(|part| part.first_ms);
+        Ok(parts)
+    }
+
+    /// Open times of the oldest and newest cached kline of a series
+    pub fn range(&self, symbol: &Symbol, interval: KlineInterval) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>> {
+        let parts = self.parts(symbol, interval)?;
+        let (Some(first), Some(last)) = (parts.iter().map(|p| p.first_ms).min(), parts.iter().map(|p| p.last_ms).max()) else {
+            return Ok(None);
+        };
+        Ok(Some((millis(first)?, millis(last)?)))
+    }
+
+    /// Store klines of one series as a new part; returns its path, none when
+    /// there was nothing to store. The part appears in one rename, so a
+    /// crash mid-write leaves no partial file behind.
+    pub fn write(&self, klines: &[UniversalKline]) -> Result<Option<PathBuf>> {
+        let (Some(first), Some(last)) = (klines.first(), klines.last()) else {
+            return Ok(None);
+        };
+        if klines.iter().any(|k| k.symbol != first.symbol || k.interval != first.interval) {
+            bail!("Klines of one series expected, got {} and others", first.symbol);
+        }
+
+        let dir = self.dir(&first.symbol, first.interval);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let name = format!("{}-{}.parquet", first.open_time.timestamp_millis(), last.open_time.timestamp_millis());
+        let (path, partial) = (dir.join(&name), dir.join(format!(".{}.partial", name)));
+
+        let batch = to_batch(klines)?;
+        let file = File::create(&partial).with_context(|| format!("Failed to create {}", partial.display()))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        std::fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(Some(path))
+    }
+
+    /// Cached klines with open times in `[start, end]`, oldest first. Where
+    /// parts overlap, the one starting later wins.
+    pub fn load(&self, symbol: &Symbol, interval: KlineInterval, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> Result<Vec<UniversalKline>> {
+        let start_ms = start.map_or(i64::MIN, |t| t.timestamp_millis());
+        let end_ms = end.map_or(i64::MAX, |t| t.timestamp_millis());
+
+        let mut parts = self.parts(symbol, interval)?;
+        parts.retain(|part| part.last_ms >= start_ms && part.first_ms <= end_ms);
+
+        let mut klines = BTreeMap::new();
+        for part in parts {
+            let file = File::open(&part.path).with_context(|| format!("Failed to open {}", part.path.display()))?;
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+            for batch in reader {
+                let batch = batch?;
+                let read = from_batch(&batch, symbol, interval).with_context(|| format!("Invalid kline file {}", part.path.display()))?;
+                for kline in read {
+                    let open_ms = kline.open_time.timestamp_millis();
+                    if (start_ms..=end_ms).contains(&open_ms) {
+                        klines.insert(open_ms, kline);
+                    }
+                }
+            }
+        }
+        Ok(klines.into_values().collect())
+    }
+
+    /// Symbols with klines of `interval` in the cache
+    pub fn symbols(&self, interval: KlineInterval) -> Result<Vec<Symbol>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut symbols = Vec::new();
+        for entry in std::fs::read_dir(&self.root).with_context(|| format!("Failed to read {}", self.root.display()))? {
+            let path = entry?.path();
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                if path.join(interval.to_string()).is_dir() {
+                    symbols.push(Symbol::new(name));
+                }
+            }
+        }
+        symbols.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Ok(symbols)
+    }
+
+    /// Bars of every cached symbol at `interval`, merged in time order, as
+    /// the backtester takes them
+    pub fn market_data(&self, interval: KlineInterval) -> Result<Vec<MarketData>> {
+        let mut bars = Vec::new();
+        for symbol in self.symbols(interval)? {
+            bars.extend(self.load(&symbol, interval, None, None)?.iter().map(bar));
+        }
+        bars.sort_by_key(|bar| bar.timestamp);
+        Ok(bars)
+    }
+
+    /// Seed `history` with the cached one-minute klines of its retention
+    /// period before `now`; returns the number of bars seeded
+    pub fn seed_history(&self, history: &PriceHistory, now: DateTime<Utc>) -> Result<usize> {
+        let start = now - chrono::Duration::hours(history.retention_hours() as i64);
+        let mut seeded = 0;
+        for symbol in self.symbols(KlineInterval::OneMinute)? {
+            let bars: Vec<PriceBar> = self
+                .load(&symbol, KlineInterval::OneMinute, Some(start), Some(now))?
+                .into_iter()
+                .map(|k| PriceBar { timestamp: k.open_time, open: k.open, high: k.high, low: k.low, close: k.close, volume: k.volume })
+                .collect();
+            seeded += history.seed(&symbol, &bars);
+        }
+        Ok(seeded)
+    }
+}
+
+/// A kline as a bar update at its open time
+fn bar(kline: &UniversalKline) -> MarketData {
+    MarketData {
+        symbol: kline.symbol.clone(),
+        price: kline.close,
+        volume: kline.volume,
+        timestamp: kline.open_time,
+        bid: None,
+        ask: None,
+        open: kline.open,
+        high: kline.high,
+        low: kline.low,
+        change_24h: if kline.open > 0.0 { (kline.close - kline.open) / kline.open * 100.0 } else { 0.0 },
+        volume_24h: kline.volume,
+        exchange: Some(kline.exchange),
+        candle: Some(Candle::from(kline)),
+    }
+}
+
+fn millis(ms: i64) -> Result<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(ms).with_context(|| format!("Timestamp {} out of range", ms))
+}
+
+fn schema() -> Arc<Schema> {
+    let float = |name: &str| Field::new(name, DataType::Float64, false);
+    Arc::new(Schema::new(vec![
+        Field::new("exchange", DataType::Utf8, false),
+        Field::new("open_time", DataType::Int64, false), // Unix milliseconds
+        Field::new("close_time", DataType::Int64, false),
+        float("open"),
+        float("high"),
+        float("low"),
+        float("close"),
+        float("volume"),
+        float("quote_volume"),
+        Field::new("trades_count", DataType::UInt64, false),
+        float("taker_buy_volume"),
+        float("taker_buy_quote_volume"),
+    ]))
+}
+
+fn to_batch(klines: &[UniversalKline]) -> Result<RecordBatch> {
+    let float = |value: fn(&UniversalKline) -> f64| Arc::new(Float64Array::from_iter_values(klines.iter().map(value))) as ArrayRef;
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(klines.iter().map(|k| k.exchange.to_string()))),
+        Arc::new(Int64Array::from_iter_values(klines.iter().map(|k| k.open_time.timestamp_millis()))),
+        Arc::new(Int64Array::from_iter_values(klines.iter().map(|k| k.close_time.timestamp_millis()))),
+        float(|k| k.open),
+        float(|k| k.high),
+        float(|k| k.low),
+        float(|k| k.close),
+        float(|k| k.volume),
+        float(|k| k.quote_volume),
+        Arc::new(UInt64Array::from_iter_values(klines.iter().map(|k| k.trades_count))),
+        float(|k| k.taker_buy_volume),
+        float(|k| k.taker_buy_quote_volume),
+    ];
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
+
+fn column<'a, T: ArrowPrimitiveType>(batch: &'a RecordBatch, name: &str) -> Result<&'a PrimitiveArray<T>> {
+    batch.column_by_name(name).and_then(|c| c.as_primitive_opt::<T>()).with_context(|| format!("Missing column {}", name))
+}
+
+fn from_batch(batch: &RecordBatch, symbol: &Symbol, interval: KlineInterval) -> Result<Vec<UniversalKline>> {
+    let exchanges = batch.column_by_name("exchange").and_then(|c| c.as_string_opt::<i32>()).context("Missing column exchange")?;
+    let (open_time, close_time) = (column::<Int64Type>(batch, "open_time")?, column::<Int64Type>(batch, "close_time")?);
+    let (open, high, low, close) = (
+        column::<Float64Type>(batch, "open")?,
+        column::<Float64Type>(batch, "high")?,
+        column::<Float64Type>(batch, "low")?,
+        column::<Float64Type>(batch, "close")?,
+    );
+    let (volume, quote_volume) = (column::<Float64Type>(batch, "volume")?, column::<Float64Type>(batch, "quote_volume")?);
+    let trades_count = column::<UInt64Type>(batch, "trades_count")?;
+    let (taker_buy_volume, taker_buy_quote_volume) =
+        (column::<Float64Type>(batch, "taker_buy_volume")?, column::<Float64Type>(batch, "taker_buy_quote_volume")?);
+
+    (0..batch.num_rows())
+        .map(|i| {
+            let name = exchanges.value(i);
+            let exchange = Exchange::ALL.into_iter().find(|e| e.to_string() == name).with_context(|| format!("Unknown exchange {}", name))?;
+            Ok(UniversalKline {
+                symbol: symbol.clone(),
+                exchange,
+                interval,
+                open_time: millis(open_time.value(i))?,
+                close_time: millis(close_time.value(i))?,
+                open: open.value(i),
+                high: high.value(i),
+                low: low.value(i),
+                close: close.value(i),
+                volume: volume.value(i),
+                quote_volume: quote_volume.value(i),
+                trades_count: trades_count.value(i),
+                taker_buy_volume: taker_buy_volume.value(i),
+                taker_buy_quote_volume: taker_buy_quote_volume.value(i),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_scanner::Granularity;
+
+    fn kline(symbol: &str, minute: i64, close: f64) -> UniversalKline {
+        let open_time = DateTime::from_timestamp_millis(1_735_689_600_000 + minute * 60_000).unwrap();
+        UniversalKline {
+            symbol: Symbol::new(symbol),
+            exchange: Exchange::Binance,
+            interval: KlineInterval::OneMinute,
+            open_time,
+            close_time: open_time + chrono::Duration::milliseconds(59_999),
+            open: close - 1.0,
+            high: close + 1.0,
+            low: close - 2.0,
+            close,
+            volume: 2.0,
+            quote_volume: 2.0 * close,
+            trades_count: 5,
+            taker_buy_volume: 1.0,
+            taker_buy_quote_volume: close,
+        }
+    }
+
+    #[test]
+    fn test_parts_merge_into_one_series() {
+        let root = std::env::temp_dir().join(format!("kline-cache-{}", std::process::id()));
+        let cache = KlineCache::new(&root);
+        let first: Vec<_> = (0..3).map(|m| kline("BTCUSDT", m, 100.0 + m as f64)).collect();
+        let second: Vec<_> = (2..5).map(|m| kline("BTCUSDT", m, 200.0 + m as f64)).collect();
+        cache.write(&first).unwrap();
+        cache.write(&second).unwrap();
+        cache.write(&[kline("ETHUSDT", 1, 10.0)]).unwrap();
+
+        let btc = Symbol::new("BTCUSDT");
+        let loaded = cache.load(&btc, KlineInterval::OneMinute, None, None).unwrap();
+        assert_eq!(loaded.len(), 5);
+        assert_eq!((loaded[0].open_time, loaded[0].close_time, loaded[0].low), (first[0].open_time, first[0].close_time, 98.0));
+        assert_eq!((loaded[0].trades_count, loaded[0].taker_buy_quote_volume), (5, 100.0));
+        assert_eq!(loaded[2].close, 202.0); // Overlap taken from the later part
+        let (oldest, newest) = cache.range(&btc, KlineInterval::OneMinute).unwrap().unwrap();
+        assert_eq!((oldest, newest), (first[0].open_time, second[2].open_time));
+        assert!(cache.range(&btc, KlineInterval::OneHour).unwrap().is_none());
+
+        let bars = cache.market_data(KlineInterval::OneMinute).unwrap();
+        assert_eq!(bars.len(), 6);
+        assert_eq!((bars[2].symbol.as_str(), bars[2].exchange), ("ETHUSDT", Some(Exchange::Binance)));
+
+        let history = PriceHistory::new(1);
+        assert_eq!(cache.seed_history(&history, second[2].close_time).unwrap(), 6);
+        let seeded = history.bars(&btc, Granularity::OneMinute, 1).unwrap();
+        assert_eq!((seeded.len(), seeded[4].close), (5, 204.0));
+        std::fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,195 @@
+//! Incremental kline downloads into the cache
+//!
+//! A download fills in what the cache lacks of the requested range: the
+//! klines before the oldest cached one and after the newest. Gaps between
+//! cached klines are left alone. Each page is stored as it arrives, so an
+//! interrupted download resumes where it stopped. The kline still forming at
+//! the end of the range is left out, so the cache only holds closed ones.
+
+use super::cache::KlineCache;
+use crate::exchanges::{KlineInterval, KlineSource, Symbol, UniversalKline};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Retries of failed requests
+#[derive(Debug, Clone)]
+pub struct DownloaderConfig {
+    pub max_retries: u32,
+    pub retry_backoff: std::time::Duration, // Pause before the first retry, doubled for each further one
+}
+
+impl Default for DownloaderConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            retry_backoff: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+/// What a download added to the cache
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadSummary {
+    pub symbol: Symbol,
+    pub interval: KlineInterval,
+    pub klines: usize,
+    pub requests: usize,
+    pub first: Option<DateTime<Utc>>, // Open time of the oldest cached kline afterwards
+    pub last: Option<DateTime<Utc>>,  // And of the newest
+}
+
+/// Fetches klines from a source into a cache
+pub struct KlineDownloader {
+    source: Arc<dyn KlineSource>,
+    cache: KlineCache,
+    config: DownloaderConfig,
+}
+
+impl KlineDownloader {
+    pub fn new(source: Arc<dyn KlineSource>, cache: KlineCache) -> Self {
+        Self { source, cache, config: DownloaderConfig::default() }
+    }
+
+    pub fn with_config(mut self, config: DownloaderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn cache(&self) -> &KlineCache {
+        &self.cache
+    }
+
+    /// Download the klines of `[start, end]` (open times) missing from the cache
+    pub async fn update(&self, symbol: &Symbol, interval: KlineInterval, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<DownloadSummary> {
+        let step = Duration::milliseconds(interval.duration_ms().with_context(|| format!("{} klines have no fixed length to page by", interval))?);
+        let mut summary = DownloadSummary { symbol: symbol.clone(), interval, klines: 0, requests: 0, first: None, last: None };
+
+        match self.cache.range(symbol, interval)? {
+            Some((first, last)) => {
+                if start < first {
+                    self.download(&mut summary, start, end.min(first - step), step).await?;
+                }
+                self.download(&mut summary, start.max(last + step), end, step).await?;
+            }
+            None => self.download(&mut summary, start, end, step).await?,
+        }
+
+        (summary.first, summary.last) = self.cache.range(symbol, interval)?.unzip();
+        info!(symbol = %symbol, %interval, klines = summary.klines, requests = summary.requests, "Klines downloaded");
+        Ok(summary)
+    }
+
+    /// Page through `[from, to]`, storing each page
+    async fn download(&self, summary: &mut DownloadSummary, mut from: DateTime<Utc>, to: DateTime<Utc>, step: Duration) -> Result<()> {
+        let now = Utc::now();
+        while from <= to {
+            let mut page = self.fetch(&summary.symbol, summary.interval, from, to).await?;
+            summary.requests += 1;
+            let Some(next) = page.last().map(|kline| kline.open_time + step) else {
+                break;
+            };
+            page.retain(|kline| kline.open_time >= from && kline.open_time <= to && kline.close_time < now);
+            summary.klines += page.len();
+            self.cache.write(&page)?;
+            if next <= from {
+                break;
+            }
+            from = next;
+        }
+        Ok(())
+    }
+
+    /// One request, retried after failures with a doubling pause
+    async fn fetch(&self, symbol: &Symbol, interval: KlineInterval, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<UniversalKline>> {
+        let mut attempt = 0;
+        loop {
+            match self.source.fetch_klines(symbol, interval, from, to).await {
+                Ok(klines) => return Ok(klines),
+                Err(e) if attempt < self.config.max_retries => {
+                    let pause = self.config.retry_backoff.saturating_mul(1 << attempt.min(16));
+                    attempt += 1;
+                    warn!(symbol = %symbol, %interval, attempt, pause_ms = pause.as_millis() as u64, error = %format!("{:#}", e), "Kline request failed, retrying");
+                    tokio::time::sleep(pause).await;
+                }
+                Err(e) => return Err(e.context(format!("Failed to fetch {} {} klines from {}", symbol, interval, from))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+
+    /// Minute klines from 2025-01-01, three per request; every other request fails
+    struct FlakySource {
+        requests: Mutex<Vec<DateTime<Utc>>>,
+    }
+
+    #[async_trait]
+    impl KlineSource for FlakySource {
+        async fn fetch_klines(&self, symbol: &Symbol, interval: KlineInterval, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<UniversalKline>> {
+            let mut requests = self.requests.lock();
+            requests.push(start);
+            if requests.len() % 2 == 1 {
+                anyhow::bail!("Connection reset");
+            }
+            let origin = DateTime::from_timestamp_millis(1_735_689_600_000).unwrap();
+            let first = ((start - origin).num_milliseconds().max(0) + 59_999) / 60_000;
+            Ok((first..first + 3)
+                .map(|minute| origin + Duration::minutes(minute))
+                .filter(|open_time| *open_time <= end)
+                .map(|open_time| UniversalKline {
+                    symbol: symbol.clone(),
+                    exchange: Exchange::Binance,
+                    interval,
+                    open_time,
+                    close_time: open_time + Duration::milliseconds(59_999),
+                    open: 100.0,
+                    high: 101.0,
+                    low: 99.0,
+                    close: 100.5,
+                    volume: 1.0,
+                    quote_volume: 100.5,
+                    trades_count: 1,
+                    taker_buy_volume: 0.5,
+                    taker_buy_quote_volume: 50.0,
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_only_fetches_what_the_cache_lacks() {
+        let root = std::env::temp_dir().join(format!("kline-download-{}", std::process::id()));
+        let source = Arc::new(FlakySource { requests: Mutex::new(Vec::new()) });
+        let config = DownloaderConfig { max_retries: 1, retry_backoff: std::time::Duration::from_millis(1) };
+        let downloader = KlineDownloader::new(source.clone(), KlineCache::new(&root)).with_config(config.clone());
+        let (btc, at) = (Symbol::new("BTCUSDT"), |minute: i64| DateTime::from_timestamp_millis(1_735_689_600_000 + minute * 60_000).unwrap());
+
+        let summary = downloader.update(&btc, KlineInterval::OneMinute, at(10), at(16)).await.unwrap();
+        assert_eq!((summary.klines, summary.first, summary.last), (7, Some(at(10)), Some(at(16))));
+
+        // Later: only the new minutes are fetched, then the older ones asked for
+        source.requests.lock().clear();
+        let summary = downloader.update(&btc, KlineInterval::OneMinute, at(10), at(18)).await.unwrap();
+        assert_eq!((summary.klines, summary.last), (2, Some(at(18))));
+        assert_eq!(source.requests.lock()[0], at(17));
+        let summary = downloader.update(&btc, KlineInterval::OneMinute, at(5), at(18)).await.unwrap();
+        assert_eq!((summary.klines, summary.first), (5, Some(at(5))));
+
+        let cached = downloader.cache().load(&btc, KlineInterval::OneMinute, None, None).unwrap();
+        assert_eq!(cached.len(), 14);
+        assert!(cached.windows(2).all(|pair| pair[1].open_time - pair[0].open_time == Duration::minutes(1)));
+
+        source.requests.lock().clear();
+        let downloader = KlineDownloader::new(source, KlineCache::new(&root)).with_config(DownloaderConfig { max_retries: 0, ..config });
+        assert!(downloader.update(&Symbol::new("ETHUSDT"), KlineInterval::OneMinute, at(0), at(2)).await.is_err());
+        std::fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,27 @@
+//! Historical market data kept on disk
+//!
+//! Backtests and a freshly started scanner both want more history than a
+//! running process has seen. `KlineDownloader` fetches closed klines from
+//! Binance, Coinbase or Alpaca into a `KlineCache` of Parquet files, one
+//! directory per symbol and interval:
+//!
+//! ```text
+//! <cache>/BTCUSDT/1m/1735689600000-1735775940000.parquet
+//! ```
+//!
+//! Each download adds a part named after the open times of its first and
+//! last kline, starting after the newest cached one, so re-running it only
+//! fetches what is new. Reads merge the parts in open time order. Failed
+//! requests are retried with a doubling pause; rate limit answers are waited
+//! out by each source's `RateLimiter` first.
+//!
+//! `backtest --cache` runs over the cached bars, and `scanner.history_cache`
+//! seeds the price history with the cached one-minute klines at startup.
+
+pub mod cache;
+pub mod downloader;
+pub mod sources;
+
+pub use cache::KlineCache;
+pub use downloader::{DownloadSummary, DownloaderConfig, KlineDownloader};
+pub use sources::{AlpacaKlines, CoinbaseKlines};
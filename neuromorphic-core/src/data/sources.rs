@@ -0,0 +1,320 @@
+//! Historical kline sources besides the Binance connector
+//!
+//! Binance klines come from `BinanceRestConnector`, which already is a
+//! `KlineSource`. Coinbase candles and Alpaca stock bars are fetched here,
+//! each under its own `RateLimiter` set to the published limits. Neither
+//! reports taker volume, and Coinbase has no quote volume or trade count;
+//! those are 0.
+
+use crate::exchanges::{
+    Exchange, ExchangeError, ExchangeResult, KlineInterval, KlineSource, RateLimit, RateLimitInterval, RateLimitType, RateLimiter, Symbol,
+    UniversalKline,
+};
+use crate::paper_trading::CurrencyConverter;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+const COINBASE_URL: &str = "https://api.exchange.coinbase.com";
+const ALPACA_DATA_URL: &str = "https://data.alpaca.markets";
+
+/// Most candles Coinbase returns per request
+const COINBASE_MAX_CANDLES: i32 = 300;
+/// Most bars Alpaca returns per request
+const ALPACA_MAX_BARS: u32 = 10_000;
+
+const REQUEST: &[(RateLimitType, u32)] = &[(RateLimitType::RawRequests, 1)];
+
+fn requests_per(interval: RateLimitInterval, limit: u32) -> RateLimit {
+    RateLimit { rate_type: RateLimitType::RawRequests, interval, interval_num: 1, limit }
+}
+
+fn client() -> reqwest::Client {
+    // Coinbase turns away requests without a user agent
+    reqwest::Client::builder()
+        .user_agent(concat!("neuromorphic-paper-trader/", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .unwrap_or_default()
+}
+
+fn network_error(e: reqwest::Error) -> ExchangeError {
+    if e.is_timeout() {
+        ExchangeError::Timeout { seconds: 30 }
+    } else {
+        ExchangeError::Network { message: e.to_string() }
+    }
+}
+
+async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> ExchangeResult<T> {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response.headers().get("Retry-After").and_then(|v| v.to_str().ok()).and_then(|v| v.parse().ok());
+        return Err(ExchangeError::RateLimit { retry_after });
+    }
+    let body = response.text().await.map_err(network_error)?;
+    match status {
+        _ if status.is_success() => Ok(serde_json::from_str(&body)?),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ExchangeError::Authentication { reason: body }),
+        _ => Err(ExchangeError::Api { code: status.as_u16() as i32, message: body }),
+    }
+}
+
+/// Fixed length of one candle, for the intervals a source supports
+fn step(interval: KlineInterval) -> Result<Duration> {
+    interval.duration_ms().map(Duration::milliseconds).with_context(|| format!("{} klines have no fixed length", interval))
+}
+
+/// Public candles of Coinbase Exchange, 10 requests per second
+pub struct CoinbaseKlines {
+    client: reqwest::Client,
+    base_url: String,
+    rate_limiter: RateLimiter,
+}
+
+impl CoinbaseKlines {
+    pub fn new() -> Self {
+        Self {
+            client: client(),
+            base_url: COINBASE_URL.to_string(),
+            rate_limiter: RateLimiter::default().with_limits(Exchange::Coinbase, &[requests_per(RateLimitInterval::Second, 10)]),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Product id of a symbol, e.g. "BTC-USD" for "BTCUSD" or "btc/usd"
+    pub fn product_id(symbol: &Symbol) -> String {
+        CurrencyConverter::split_symbol(symbol).map_or_else(|| symbol.as_str().to_uppercase(), |(base, quote)| format!("{}-{}", base, quote))
+    }
+
+    fn granularity(interval: KlineInterval) -> Result<i64> {
+        Ok(match interval {
+            KlineInterval::OneMinute => 60,
+            KlineInterval::FiveMinutes => 300,
+            KlineInterval::FifteenMinutes => 900,
+            KlineInterval::OneHour => 3_600,
+            KlineInterval::SixHours => 21_600,
+            KlineInterval::OneDay => 86_400,
+            other => bail!("Coinbase has no {} candles; use 1m, 5m, 15m, 1h, 6h or 1d", other),
+        })
+    }
+}
+
+impl Default for CoinbaseKlines {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl KlineSource for CoinbaseKlines {
+    /// The candles of the first window of up to 300 from `start` that has
+    /// any; windows without trades come back empty
+    async fn fetch_klines(&self, symbol: &Symbol, interval: KlineInterval, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<UniversalKline>> {
+        let granularity = Self::granularity(interval)?;
+        let step = step(interval)?;
+        let url = format!("{}/products/{}/candles", self.base_url, Self::product_id(symbol));
+
+        let mut from = start;
+        while from <= end {
+            let to = end.min(from + step * (COINBASE_MAX_CANDLES - 1));
+            let params = [("granularity", granularity.to_string()), ("start", from.to_rfc3339()), ("end", to.to_rfc3339())];
+            // Rows of [time, low, high, open, close, volume], newest first
+            let rows: Vec<[f64; 6]> = self
+                .rate_limiter
+                .execute(Exchange::Coinbase, REQUEST, || async {
+                    parse(self.client.get(&url).query(&params).send().await.map_err(network_error)?).await
+                })
+                .await?;
+
+            let mut klines: Vec<UniversalKline> = rows
+                .iter()
+                .filter_map(|row| {
+                    let open_time = DateTime::from_timestamp(row[0] as i64, 0)?;
+                    Some(UniversalKline {
+                        symbol: symbol.clone(),
+                        exchange: Exchange::Coinbase,
+                        interval,
+                        open_time,
+                        close_time: open_time + step - Duration::milliseconds(1),
+                        open: row[3],
+                        high: row[2],
+                        low: row[1],
+                        close: row[4],
+                        volume: row[5],
+                        quote_volume: 0.0,
+                        trades_count: 0,
+                        taker_buy_volume: 0.0,
+                        taker_buy_quote_volume: 0.0,
+                    })
+                })
+                .filter(|kline| kline.open_time >= from && kline.open_time <= to)
+                .collect();
+            if !klines.is_empty() {
+                klines.sort_by_key(|kline| kline.open_time);
+                return Ok(klines);
+            }
+            from = to + step;
+        }
+        Ok(Vec::new())
+    }
+}
+
+#[derive(Deserialize)]
+struct AlpacaBars {
+    bars: Option<Vec<AlpacaBar>>, // Null when there are none
+}
+
+#[derive(Deserialize)]
+struct AlpacaBar {
+    t: DateTime<Utc>,
+    o: f64,
+    h: f64,
+    l: f64,
+    c: f64,
+    v: f64,
+    #[serde(default)]
+    n: u64,
+    #[serde(default)]
+    vw: f64, // Volume weighted average price
+}
+
+/// US stock bars of the Alpaca market data API, 200 requests per minute.
+/// Prices are as traded, not adjusted for splits or dividends. Alpaca
+/// consolidates the US venues, so the klines are labelled NASDAQ like the
+/// scanner's equity quotes.
+pub struct AlpacaKlines {
+    client: reqwest::Client,
+    base_url: String,
+    key_id: String,
+    secret_key: String,
+    feed: String,
+    rate_limiter: RateLimiter,
+}
+
+impl AlpacaKlines {
+    pub fn new(key_id: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Self {
+            client: client(),
+            base_url: ALPACA_DATA_URL.to_string(),
+            key_id: key_id.into(),
+            secret_key: secret_key.into(),
+            feed: "iex".to_string(),
+            rate_limiter: RateLimiter::default().with_limits(Exchange::NASDAQ, &[requests_per(RateLimitInterval::Minute, 200)]),
+        }
+    }
+
+    /// Keys from APCA_API_KEY_ID and APCA_API_SECRET_KEY, where Alpaca's own tools read them
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).with_context(|| format!("{} must be set to download from Alpaca", name));
+        Ok(Self::new(var("APCA_API_KEY_ID")?, var("APCA_API_SECRET_KEY")?))
+    }
+
+    /// Data feed: "iex" on the free plan, "sip" for every US venue
+    pub fn with_feed(mut self, feed: impl Into<String>) -> Self {
+        self.feed = feed.into();
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    fn timeframe(interval: KlineInterval) -> Result<&'static str> {
+        Ok(match interval {
+            KlineInterval::OneMinute => "1Min",
+            KlineInterval::ThreeMinutes => "3Min",
+            KlineInterval::FiveMinutes => "5Min",
+            KlineInterval::FifteenMinutes => "15Min",
+            KlineInterval::ThirtyMinutes => "30Min",
+            KlineInterval::OneHour => "1Hour",
+            KlineInterval::TwoHours => "2Hour",
+            KlineInterval::FourHours => "4Hour",
+            KlineInterval::SixHours => "6Hour",
+            KlineInterval::EightHours => "8Hour",
+            KlineInterval::TwelveHours => "12Hour",
+            KlineInterval::OneDay => "1Day",
+            KlineInterval::OneWeek => "1Week",
+            other => bail!("Alpaca has no {} bars", other),
+        })
+    }
+}
+
+#[async_trait]
+impl KlineSource for AlpacaKlines {
+    async fn fetch_klines(&self, symbol: &Symbol, interval: KlineInterval, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<UniversalKline>> {
+        let timeframe = Self::timeframe(interval)?;
+        let step = step(interval)?;
+        let url = format!("{}/v2/stocks/{}/bars", self.base_url, symbol.as_str().to_uppercase());
+        let params = [
+            ("timeframe", timeframe.to_string()),
+            ("start", start.to_rfc3339()),
+            ("end", end.to_rfc3339()),
+            ("limit", ALPACA_MAX_BARS.to_string()),
+            ("feed", self.feed.clone()),
+            ("adjustment", "raw".to_string()),
+        ];
+        let response: AlpacaBars = self
+            .rate_limiter
+            .execute(Exchange::NASDAQ, REQUEST, || async {
+                let request = self
+                    .client
+                    .get(&url)
+                    .query(&params)
+                    .header("APCA-API-KEY-ID", &self.key_id)
+                    .header("APCA-API-SECRET-KEY", &self.secret_key);
+                parse(request.send().await.map_err(network_error)?).await
+            })
+            .await?;
+
+        Ok(response
+            .bars
+            .unwrap_or_default()
+            .into_iter()
+            .map(|bar| UniversalKline {
+                symbol: symbol.clone(),
+                exchange: Exchange::NASDAQ,
+                interval,
+                open_time: bar.t,
+                close_time: bar.t + step - Duration::milliseconds(1),
+                open: bar.o,
+                high: bar.h,
+                low: bar.l,
+                close: bar.c,
+                volume: bar.v,
+                quote_volume: bar.vw * bar.v,
+                trades_count: bar.n,
+                taker_buy_volume: 0.0,
+                taker_buy_quote_volume: 0.0,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_and_intervals_map_to_each_api() {
+        assert_eq!(CoinbaseKlines::product_id(&Symbol::new("BTCUSD")), "BTC-USD");
+        assert_eq!(CoinbaseKlines::product_id(&Symbol::new("eth/usdt")), "ETH-USDT");
+        assert_eq!(CoinbaseKlines::granularity(KlineInterval::SixHours).unwrap(), 21_600);
+        assert!(CoinbaseKlines::granularity(KlineInterval::FourHours).is_err());
+        assert_eq!(AlpacaKlines::timeframe(KlineInterval::FifteenMinutes).unwrap(), "15Min");
+        assert!(AlpacaKlines::timeframe(KlineInterval::OneSecond).is_err());
+
+        let bars: AlpacaBars = serde_json::from_str(r#"{"bars":[{"t":"2026-01-02T14:30:00Z","o":1,"h":2,"l":0.5,"c":1.5,"v":100,"n":7,"vw":1.2}],"next_page_token":null}"#).unwrap();
+        assert_eq!(bars.bars.unwrap()[0].n, 7);
+        let none: AlpacaBars = serde_json::from_str(r#"{"bars":null,"symbol":"AAPL","next_page_token":null}"#).unwrap();
+        assert!(none.bars.is_none());
+    }
+}
@@ -0,0 +1,1067 @@
+//! Binance Spot REST connector for account and order management
+//!
+//! Requests are signed with HMAC-SHA256. The default endpoint is the Spot
+//! Testnet, where orders are matched for real against test balances.
+
+use super::connector::{
+    AccountInfo, AccountType, Balance, ExchangeConnector, ExchangeError, ExchangeInfo, ExchangeResult,
+    ExchangeValidation, KlineInterval, OrderRequest, OrderStatus, Permission, RateLimit, RateLimitInterval,
+    RateLimitType, SymbolInfo, SymbolStatus, TradeExecution, TradeFee, UniversalKline, UniversalOrder,
+    UniversalTicker,
+};
+use super::rate_limit::RateLimiter;
+use super::types::{Exchange, OrderType, Side, Symbol, TimeInForce, UniversalMarketData, UniversalOrderBook, UniversalTrade};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Spot Testnet REST endpoint
+pub const BINANCE_TESTNET_URL: &str = "https://testnet.binance.vision";
+
+/// Production Spot REST endpoint
+pub const BINANCE_SPOT_URL: &str = "https://api.binance.com";
+
+#[derive(Clone)]
+pub struct BinanceRestConfig {
+    pub base_url: String,
+    pub api_key: String,
+    pub api_secret: String,
+    pub recv_window_ms: u64,
+}
+
+impl BinanceRestConfig {
+    /// Spot Testnet with keys from https://testnet.binance.vision
+    pub fn testnet(api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        Self {
+            base_url: BINANCE_TESTNET_URL.to_string(),
+            api_key: api_key.into(),
+            api_secret: api_secret.into(),
+            recv_window_ms: 5000,
+        }
+    }
+
+    /// Production Spot endpoint without keys, for public market data only
+    pub fn public() -> Self {
+        Self {
+            base_url: BINANCE_SPOT_URL.to_string(),
+            api_key: String::new(),
+            api_secret: String::new(),
+            recv_window_ms: 5000,
+        }
+    }
+}
+
+/// Trading rules needed to submit valid orders
+#[derive(Clone, Debug)]
+struct SymbolFilters {
+    base_asset: String,
+    quote_asset: String,
+    step_size: f64,
+    min_quantity: f64,
+    tick_size: f64,
+    min_notional: f64,
+}
+
+/// Binance Spot REST connector. Market data streams are served by
+/// `BinanceWebSocket`; this connector only polls.
+pub struct BinanceRestConnector {
+    config: BinanceRestConfig,
+    client: reqwest::Client,
+    filters: DashMap<String, SymbolFilters>, // Keyed by Binance symbol
+    order_symbols: DashMap<String, Symbol>, // Order ID -> symbol, since lookups by ID need the symbol
+    rate_limiter: Arc<RateLimiter>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestOrder {
+    symbol: String,
+    order_id: u64,
+    client_order_id: String,
+    price: String,
+    orig_qty: String,
+    executed_qty: String,
+    cummulative_quote_qty: String,
+    status: String,
+    time_in_force: String,
+    #[serde(rename = "type")]
+    order_type: String,
+    side: String,
+    #[serde(default)]
+    stop_price: Option<String>,
+    #[serde(default)]
+    time: Option<i64>,
+    #[serde(default)]
+    update_time: Option<i64>,
+    #[serde(default)]
+    transact_time: Option<i64>,
+    #[serde(default)]
+    fills: Vec<RestFill>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestFill {
+    price: String,
+    commission: String,
+    commission_asset: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestAccount {
+    maker_commission: f64, // Basis points
+    taker_commission: f64,
+    can_trade: bool,
+    can_withdraw: bool,
+    can_deposit: bool,
+    update_time: i64,
+    account_type: String,
+    balances: Vec<RestBalance>,
+    #[serde(default)]
+    permissions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RestBalance {
+    asset: String,
+    free: String,
+    locked: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestTrade {
+    id: u64,
+    order_id: u64,
+    price: String,
+    qty: String,
+    commission: String,
+    commission_asset: String,
+    time: i64,
+    is_buyer: bool,
+    is_maker: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestTicker {
+    last_price: String,
+    price_change: String,
+    price_change_percent: String,
+    high_price: String,
+    low_price: String,
+    volume: String,
+    quote_volume: String,
+    open_price: String,
+    close_time: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestDepth {
+    last_update_id: u64,
+    bids: Vec<(String, String)>,
+    asks: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestPublicTrade {
+    id: u64,
+    price: String,
+    qty: String,
+    time: i64,
+    is_buyer_maker: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListenKey {
+    listen_key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerTime {
+    server_time: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestExchangeInfo {
+    timezone: String,
+    server_time: i64,
+    #[serde(default)]
+    rate_limits: Vec<RestRateLimit>,
+    symbols: Vec<RestSymbol>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestRateLimit {
+    rate_limit_type: String,
+    interval: String,
+    interval_num: u32,
+    limit: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestSymbol {
+    symbol: String,
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+    base_asset_precision: u32,
+    quote_asset_precision: u32,
+    order_types: Vec<String>,
+    is_spot_trading_allowed: bool,
+    is_margin_trading_allowed: bool,
+    filters: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct RestError {
+    code: i32,
+    msg: String,
+}
+
+/// Binance symbol for a symbol, e.g. "BTC-USDT" -> "BTCUSDT"
+pub fn binance_symbol(symbol: &Symbol) -> String {
+    symbol.as_str().replace(['-', '/', '_'], "").to_uppercase()
+}
+
+/// HMAC-SHA256 signature of a query string, hex encoded
+fn sign(secret: &str, query: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(query.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Round down to a multiple of `step`, formatted with the step's decimals
+fn format_step(value: f64, step: f64) -> String {
+    if step <= 0.0 {
+        return value.to_string();
+    }
+    let decimals = (-step.log10()).ceil().max(0.0) as usize;
+    let rounded = ((value / step) + 1e-9).floor() * step;
+    format!("{:.*}", decimals, rounded)
+}
+
+pub(super) fn num(value: &str) -> f64 {
+    value.parse().unwrap_or(0.0)
+}
+
+pub(super) fn timestamp(ms: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ms).single().unwrap_or_else(Utc::now)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+fn network_error(e: reqwest::Error) -> ExchangeError {
+    if e.is_timeout() {
+        ExchangeError::Timeout { seconds: 30 }
+    } else {
+        ExchangeError::Network { message: e.to_string() }
+    }
+}
+
+fn symbol_filters(symbol: &RestSymbol) -> SymbolFilters {
+    let filter = |kind: &str, field: &str| {
+        symbol.filters
+            .iter()
+            .find(|f| f["filterType"] == kind)
+            .and_then(|f| f[field].as_str())
+            .map(num)
+    };
+
+    SymbolFilters {
+        base_asset: symbol.base_asset.clone(),
+        quote_asset: symbol.quote_asset.clone(),
+        step_size: filter("LOT_SIZE", "stepSize").unwrap_or(0.0),
+        min_quantity: filter("LOT_SIZE", "minQty").unwrap_or(0.0),
+        tick_size: filter("PRICE_FILTER", "tickSize").unwrap_or(0.0),
+        min_notional: filter("NOTIONAL", "minNotional")
+            .or_else(|| filter("MIN_NOTIONAL", "minNotional"))
+            .unwrap_or(0.0),
+    }
+}
+
+/// Kline from a /api/v3/klines row:
+/// [open time, open, high, low, close, volume, close time, quote volume, trades, taker base, taker quote, _]
+fn parse_kline_row(symbol: &Symbol, interval: KlineInterval, row: &[serde_json::Value]) -> ExchangeResult<UniversalKline> {
+    if row.len() < 11 {
+        return Err(ExchangeError::InvalidRequest {
+            details: format!("Malformed kline row: {:?}", row),
+        });
+    }
+    let int = |i: usize| row[i].as_i64().unwrap_or(0);
+    let float = |i: usize| row[i].as_str().map(num).unwrap_or(0.0);
+
+    Ok(UniversalKline {
+        symbol: symbol.clone(),
+        exchange: Exchange::Binance,
+        interval,
+        open_time: timestamp(int(0)),
+        close_time: timestamp(int(6)),
+        open: float(1),
+        high: float(2),
+        low: float(3),
+        close: float(4),
+        volume: float(5),
+        quote_volume: float(7),
+        trades_count: int(8) as u64,
+        taker_buy_volume: float(9),
+        taker_buy_quote_volume: float(10),
+    })
+}
+
+/// Published Spot limits, used until `get_exchange_info` returns the current ones
+fn default_rate_limits() -> Vec<RateLimit> {
+    let limit = |rate_type, interval, interval_num, limit| RateLimit { rate_type, interval, interval_num, limit };
+    vec![
+        limit(RateLimitType::RequestWeight, RateLimitInterval::Minute, 1, 6000),
+        limit(RateLimitType::Orders, RateLimitInterval::Second, 10, 100),
+        limit(RateLimitType::Orders, RateLimitInterval::Day, 1, 200_000),
+        limit(RateLimitType::RawRequests, RateLimitInterval::Minute, 5, 61_000),
+    ]
+}
+
+fn rate_limit(limit: &RestRateLimit) -> Option<RateLimit> {
+    let rate_type = match limit.rate_limit_type.as_str() {
+        "REQUEST_WEIGHT" => RateLimitType::RequestWeight,
+        "ORDERS" => RateLimitType::Orders,
+        "RAW_REQUESTS" => RateLimitType::RawRequests,
+        _ => return None,
+    };
+    let interval = match limit.interval.as_str() {
+        "SECOND" => RateLimitInterval::Second,
+        "MINUTE" => RateLimitInterval::Minute,
+        "DAY" => RateLimitInterval::Day,
+        _ => return None,
+    };
+    Some(RateLimit { rate_type, interval, interval_num: limit.interval_num, limit: limit.limit })
+}
+
+/// What a request counts against each limit, per the Spot API endpoint weights
+fn request_costs(method: &Method, path: &str, params: &[(&str, String)]) -> Vec<(RateLimitType, u32)> {
+    let has_symbol = params.iter().any(|(key, _)| *key == "symbol");
+    let limit = params.iter().find(|(key, _)| *key == "limit").and_then(|(_, value)| value.parse::<u32>().ok());
+    let weight = match (method.as_str(), path) {
+        ("GET", "/api/v3/exchangeInfo") if has_symbol => 2,
+        ("GET", "/api/v3/exchangeInfo" | "/api/v3/account" | "/api/v3/allOrders" | "/api/v3/myTrades") => 20,
+        ("GET", "/api/v3/ticker/24hr" | "/api/v3/klines") if has_symbol => 2,
+        ("GET", "/api/v3/ticker/24hr" | "/api/v3/openOrders") if !has_symbol => 80,
+        ("GET", "/api/v3/openOrders") => 6,
+        ("GET", "/api/v3/order") => 4,
+        ("GET", "/api/v3/depth") => match limit.unwrap_or(100) {
+            0..=100 => 5,
+            101..=500 => 25,
+            501..=1000 => 50,
+            _ => 250,
+        },
+        ("GET", "/api/v3/trades") => 25,
+        (_, "/api/v3/userDataStream") => 2,
+        _ => 1,
+    };
+    let mut costs = vec![(RateLimitType::RequestWeight, weight), (RateLimitType::RawRequests, 1)];
+    if method == Method::POST && path == "/api/v3/order" {
+        costs.push((RateLimitType::Orders, 1));
+    }
+    costs
+}
+
+pub(super) fn order_status(status: &str) -> OrderStatus {
+    match status {
+        "NEW" => OrderStatus::New,
+        "PARTIALLY_FILLED" => OrderStatus::PartiallyFilled,
+        "FILLED" => OrderStatus::Filled,
+        "CANCELED" => OrderStatus::Canceled,
+        "PENDING_CANCEL" => OrderStatus::PendingCancel,
+        "REJECTED" => OrderStatus::Rejected,
+        _ => OrderStatus::Expired, // EXPIRED, EXPIRED_IN_MATCH
+    }
+}
+
+pub(super) fn time_in_force(tif: &str) -> TimeInForce {
+    match tif {
+        "IOC" => TimeInForce::IOC,
+        "FOK" => TimeInForce::FOK,
+        _ => TimeInForce::GTC,
+    }
+}
+
+pub(super) fn order_type(order_type: &str, limit_price: f64, stop_price: Option<f64>) -> OrderType {
+    match order_type {
+        "LIMIT" | "LIMIT_MAKER" => OrderType::Limit { price: limit_price },
+        "STOP_LOSS_LIMIT" | "TAKE_PROFIT_LIMIT" => OrderType::StopLimit {
+            stop: stop_price.unwrap_or(0.0),
+            limit: limit_price,
+        },
+        _ => OrderType::Market,
+    }
+}
+
+/// Commission converted to the quote asset, or `None` when it was paid in a
+/// third asset (BNB discounts) that can't be priced here
+fn commission_in_quote(filters: &SymbolFilters, commission: f64, asset: &str, price: f64) -> Option<f64> {
+    if asset == filters.quote_asset {
+        Some(commission)
+    } else if asset == filters.base_asset {
+        Some(commission * price)
+    } else if commission > 0.0 {
+        None
+    } else {
+        Some(0.0)
+    }
+}
+
+impl BinanceRestConnector {
+    fn new(config: BinanceRestConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            filters: DashMap::new(),
+            order_symbols: DashMap::new(),
+            rate_limiter: Arc::new(RateLimiter::default().with_limits(Exchange::Binance, &default_rate_limits())),
+        }
+    }
+
+    /// Share a limiter with other connectors on the same IP
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Limiter consulted before every request, including its throttling statistics
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    async fn public<T: DeserializeOwned>(&self, path: &str, params: &[(&str, String)]) -> ExchangeResult<T> {
+        let costs = request_costs(&Method::GET, path, params);
+        self.rate_limiter
+            .execute(Exchange::Binance, &costs, || async {
+                let response = self.client
+                    .get(format!("{}{}", self.config.base_url, path))
+                    .query(params)
+                    .send()
+                    .await
+                    .map_err(network_error)?;
+                Self::parse(response).await
+            })
+            .await
+    }
+
+    async fn signed<T: DeserializeOwned>(&self, method: Method, path: &str, params: &[(&str, String)]) -> ExchangeResult<T> {
+        let costs = request_costs(&method, path, params);
+        self.rate_limiter
+            .execute(Exchange::Binance, &costs, || {
+                let method = method.clone();
+                async move {
+                    // Signed per attempt so a retry carries a fresh timestamp
+                    let query = {
+                        let mut query = url::form_urlencoded::Serializer::new(String::new());
+                        for (key, value) in params {
+                            query.append_pair(key, value);
+                        }
+                        query.append_pair("recvWindow", &self.config.recv_window_ms.to_string());
+                        query.append_pair("timestamp", &now_ms().to_string());
+                        query.finish()
+                    };
+                    let signature = sign(&self.config.api_secret, &query);
+
+                    let response = self.client
+                        .request(method, format!("{}{}?{}&signature={}", self.config.base_url, path, query, signature))
+                        .header("X-MBX-APIKEY", &self.config.api_key)
+                        .send()
+                        .await
+                        .map_err(network_error)?;
+                    Self::parse(response).await
+                }
+            })
+            .await
+    }
+
+    /// Request that needs the API key but no signature
+    async fn keyed<T: DeserializeOwned>(&self, method: Method, path: &str, params: &[(&str, String)]) -> ExchangeResult<T> {
+        let costs = request_costs(&method, path, params);
+        self.rate_limiter
+            .execute(Exchange::Binance, &costs, || async {
+                let response = self.client
+                    .request(method.clone(), format!("{}{}", self.config.base_url, path))
+                    .query(params)
+                    .header("X-MBX-APIKEY", &self.config.api_key)
+                    .send()
+                    .await
+                    .map_err(network_error)?;
+                Self::parse(response).await
+            })
+            .await
+    }
+
+    async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> ExchangeResult<T> {
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::IM_A_TEAPOT {
+            let retry_after = response
+                .headers()
+                .get("Retry-After")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok());
+            return Err(ExchangeError::RateLimit { retry_after });
+        }
+
+        let body = response.text().await.map_err(network_error)?;
+        if status.is_success() {
+            return Ok(serde_json::from_str(&body)?);
+        }
+
+        match serde_json::from_str::<RestError>(&body) {
+            // -2014/-2015: bad key format or key/IP/permission mismatch, -1022: bad signature
+            Ok(e) if matches!(e.code, -2014 | -2015 | -1022) || status == StatusCode::UNAUTHORIZED => {
+                Err(ExchangeError::Authentication { reason: e.msg })
+            }
+            Ok(e) => Err(ExchangeError::Api { code: e.code, message: e.msg }),
+            Err(_) => Err(ExchangeError::Api { code: status.as_u16() as i32, message: body }),
+        }
+    }
+
+    async fn filters_for(&self, symbol: &str) -> ExchangeResult<SymbolFilters> {
+        if let Some(filters) = self.filters.get(symbol) {
+            return Ok(filters.clone());
+        }
+
+        let info: RestExchangeInfo = self.public("/api/v3/exchangeInfo", &[("symbol", symbol.to_string())]).await?;
+        let filters = info.symbols
+            .iter()
+            .find(|s| s.symbol == symbol)
+            .map(symbol_filters)
+            .ok_or_else(|| ExchangeError::SymbolNotFound { symbol: symbol.to_string() })?;
+        self.filters.insert(symbol.to_string(), filters.clone());
+        Ok(filters)
+    }
+
+    /// Symbol for an order ID seen earlier
+    fn known_symbol(&self, order_id: &str) -> ExchangeResult<Symbol> {
+        self.order_symbols
+            .get(order_id)
+            .map(|s| s.clone())
+            .ok_or_else(|| ExchangeError::InvalidRequest {
+                details: format!("Order {} was not placed or fetched through this connector", order_id),
+            })
+    }
+
+    /// Convert an order response; `symbol` is the caller's form of the Binance symbol
+    async fn to_universal(&self, order: RestOrder, symbol: Symbol) -> UniversalOrder {
+        let executed = num(&order.executed_qty);
+        let quote = num(&order.cummulative_quote_qty);
+        let limit_price = num(&order.price);
+        let stop_price = order.stop_price.as_deref().map(num).filter(|p| *p > 0.0);
+        let avg_price = if executed > 0.0 { quote / executed } else { limit_price };
+
+        let order_type = order_type(&order.order_type, limit_price, stop_price);
+
+        let fees = match self.filters_for(&order.symbol).await {
+            Ok(filters) if !order.fills.is_empty() => Self::quote_fees(&order.fills, &filters, quote),
+            _ => None,
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert(UniversalOrder::AVG_PRICE_KEY.to_string(), avg_price.to_string());
+
+        let id = order.order_id.to_string();
+        self.order_symbols.insert(id.clone(), symbol.clone());
+
+        UniversalOrder {
+            id,
+            client_order_id: Some(order.client_order_id),
+            symbol,
+            side: if order.side == "BUY" { Side::Buy } else { Side::Sell },
+            order_type,
+            quantity: num(&order.orig_qty),
+            filled_quantity: executed,
+            remaining_quantity: (num(&order.orig_qty) - executed).max(0.0),
+            price: (limit_price > 0.0).then_some(limit_price),
+            stop_price,
+            status: order_status(&order.status),
+            time_in_force: time_in_force(&order.time_in_force),
+            created_at: timestamp(order.time.or(order.transact_time).unwrap_or_default()),
+            updated_at: timestamp(order.update_time.or(order.transact_time).unwrap_or_default()),
+            exchange: Exchange::Binance,
+            fees,
+            metadata,
+        }
+    }
+
+    /// Total commission in the quote asset. Commission paid in a third asset
+    /// (BNB discounts) can't be priced here and is left out.
+    fn quote_fees(fills: &[RestFill], filters: &SymbolFilters, quote_quantity: f64) -> Option<TradeFee> {
+        let mut amount = 0.0;
+        for fill in fills {
+            amount += commission_in_quote(filters, num(&fill.commission), &fill.commission_asset, num(&fill.price))?;
+        }
+
+        Some(TradeFee {
+            asset: filters.quote_asset.clone(),
+            amount,
+            rate: if quote_quantity > 0.0 { amount / quote_quantity } else { 0.0 },
+        })
+    }
+
+    async fn account(&self) -> ExchangeResult<RestAccount> {
+        self.signed(Method::GET, "/api/v3/account", &[]).await
+    }
+
+    /// Quote asset of a Binance symbol and the commission of one fill in it
+    pub(super) async fn quote_commission(&self, symbol: &str, commission: f64, asset: &str, price: f64) -> Option<(String, f64)> {
+        let filters = self.filters_for(symbol).await.ok()?;
+        let amount = commission_in_quote(&filters, commission, asset, price)?;
+        Some((filters.quote_asset, amount))
+    }
+
+    /// The caller's form of a Binance symbol for an order, remembering it so
+    /// the order can be looked up or cancelled by ID
+    pub(super) fn order_symbol(&self, order_id: &str, symbol: &str) -> Symbol {
+        self.order_symbols
+            .entry(order_id.to_string())
+            .or_insert_with(|| Symbol::new(symbol))
+            .clone()
+    }
+
+    /// How far the exchange clock is ahead of the local one, in milliseconds,
+    /// assuming the request took as long each way
+    pub async fn clock_offset(&self) -> ExchangeResult<f64> {
+        let sent = Utc::now().timestamp_millis();
+        let response: ServerTime = self.public("/api/v3/time", &[]).await?;
+        let received = Utc::now().timestamp_millis();
+        Ok(response.server_time as f64 - (sent + received) as f64 / 2.0)
+    }
+
+    /// Open a user data stream. The key expires 60 minutes after it was
+    /// created or last kept alive.
+    pub async fn create_listen_key(&self) -> ExchangeResult<String> {
+        let response: ListenKey = self.keyed(Method::POST, "/api/v3/userDataStream", &[]).await?;
+        Ok(response.listen_key)
+    }
+
+    pub async fn keepalive_listen_key(&self, listen_key: &str) -> ExchangeResult<()> {
+        let _: serde_json::Value = self
+            .keyed(Method::PUT, "/api/v3/userDataStream", &[("listenKey", listen_key.to_string())])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn close_listen_key(&self, listen_key: &str) -> ExchangeResult<()> {
+        let _: serde_json::Value = self
+            .keyed(Method::DELETE, "/api/v3/userDataStream", &[("listenKey", listen_key.to_string())])
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExchangeConnector for BinanceRestConnector {
+    type Config = BinanceRestConfig;
+
+    async fn connect(config: Self::Config) -> ExchangeResult<Self> {
+        let connector = Self::new(config);
+        connector.ping().await?;
+        Ok(connector)
+    }
+
+    async fn disconnect(&self) -> ExchangeResult<()> {
+        Ok(())
+    }
+
+    async fn get_account_info(&self) -> ExchangeResult<AccountInfo> {
+        let account = self.account().await?;
+        Ok(AccountInfo {
+            account_id: self.config.api_key.chars().take(8).collect(),
+            account_type: match account.account_type.as_str() {
+                "MARGIN" => AccountType::Margin,
+                _ => AccountType::Spot,
+            },
+            permissions: account.permissions
+                .iter()
+                .filter_map(|p| match p.as_str() {
+                    "SPOT" => Some(Permission::Spot),
+                    "MARGIN" => Some(Permission::Margin),
+                    "LEVERAGED" => Some(Permission::Leveraged),
+                    _ => None,
+                })
+                .collect(),
+            can_trade: account.can_trade,
+            can_withdraw: account.can_withdraw,
+            can_deposit: account.can_deposit,
+            trading_fee_maker: account.maker_commission / 10_000.0,
+            trading_fee_taker: account.taker_commission / 10_000.0,
+            updated_at: timestamp(account.update_time),
+        })
+    }
+
+    async fn get_balances(&self) -> ExchangeResult<Vec<Balance>> {
+        Ok(self.account()
+            .await?
+            .balances
+            .into_iter()
+            .map(|b| Balance::new(b.asset, num(&b.free), num(&b.locked)))
+            .filter(|b| b.total > 0.0)
+            .collect())
+    }
+
+    async fn get_balance(&self, asset: &str) -> ExchangeResult<Option<Balance>> {
+        Ok(self.get_balances().await?.into_iter().find(|b| b.asset.eq_ignore_ascii_case(asset)))
+    }
+
+    async fn place_order(&self, order: OrderRequest) -> ExchangeResult<UniversalOrder> {
+        order.validate()?;
+        let symbol = binance_symbol(&order.symbol);
+        let filters = self.filters_for(&symbol).await?;
+
+        let quantity = format_step(order.quantity, filters.step_size);
+        if num(&quantity) < filters.min_quantity.max(f64::MIN_POSITIVE) {
+            return Err(ExchangeError::InvalidRequest {
+                details: format!("Quantity {} is below the {} lot size", order.quantity, symbol),
+            });
+        }
+
+        let mut params = vec![
+            ("symbol", symbol.clone()),
+            ("side", if order.side == Side::Buy { "BUY" } else { "SELL" }.to_string()),
+            ("quantity", quantity.clone()),
+            ("newOrderRespType", "FULL".to_string()),
+        ];
+        if let Some(client_order_id) = &order.client_order_id {
+            params.push(("newClientOrderId", client_order_id.clone()));
+        }
+
+        let tif = match order.time_in_force {
+            TimeInForce::IOC => "IOC",
+            TimeInForce::FOK => "FOK",
+            _ => "GTC",
+        };
+        match &order.order_type {
+            OrderType::Market => params.push(("type", "MARKET".to_string())),
+            OrderType::Limit { price } => {
+                if num(&quantity) * price < filters.min_notional {
+                    return Err(ExchangeError::InvalidRequest {
+                        details: format!("Order value is below the {} minimum of {}", symbol, filters.min_notional),
+                    });
+                }
+                params.push(("price", format_step(*price, filters.tick_size)));
+                if order.post_only || matches!(order.time_in_force, TimeInForce::GTX) {
+                    params.push(("type", "LIMIT_MAKER".to_string()));
+                } else {
+                    params.push(("type", "LIMIT".to_string()));
+                    params.push(("timeInForce", tif.to_string()));
+                }
+            }
+            OrderType::StopLimit { stop, limit } => {
+                params.push(("type", "STOP_LOSS_LIMIT".to_string()));
+                params.push(("price", format_step(*limit, filters.tick_size)));
+                params.push(("stopPrice", format_step(*stop, filters.tick_size)));
+                params.push(("timeInForce", tif.to_string()));
+            }
+        }
+
+        let placed: RestOrder = self.signed(Method::POST, "/api/v3/order", &params).await?;
+        Ok(self.to_universal(placed, order.symbol).await)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> ExchangeResult<()> {
+        let symbol = self.known_symbol(order_id)?;
+        let params = [("symbol", binance_symbol(&symbol)), ("orderId", order_id.to_string())];
+        let _: serde_json::Value = self.signed(Method::DELETE, "/api/v3/order", &params).await?;
+        Ok(())
+    }
+
+    async fn cancel_all_orders(&self, symbol: Option<&Symbol>) -> ExchangeResult<Vec<String>> {
+        // Binance cancels per symbol, so find the symbols with open orders first
+        let symbols: Vec<Symbol> = match symbol {
+            Some(symbol) => vec![symbol.clone()],
+            None => {
+                let mut symbols: Vec<Symbol> = self.get_open_orders(None).await?.into_iter().map(|o| o.symbol).collect();
+                symbols.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                symbols.dedup();
+                symbols
+            }
+        };
+
+        let mut cancelled = Vec::new();
+        for symbol in symbols {
+            let orders: Vec<RestOrder> = self
+                .signed(Method::DELETE, "/api/v3/openOrders", &[("symbol", binance_symbol(&symbol))])
+                .await?;
+            cancelled.extend(orders.into_iter().map(|o| o.order_id.to_string()));
+        }
+        Ok(cancelled)
+    }
+
+    async fn get_order(&self, order_id: &str) -> ExchangeResult<UniversalOrder> {
+        let symbol = self.known_symbol(order_id)?;
+        let params = [("symbol", binance_symbol(&symbol)), ("orderId", order_id.to_string())];
+        let order: RestOrder = self.signed(Method::GET, "/api/v3/order", &params).await?;
+        Ok(self.to_universal(order, symbol).await)
+    }
+
+    async fn get_open_orders(&self, symbol: Option<&Symbol>) -> ExchangeResult<Vec<UniversalOrder>> {
+        let params: Vec<(&str, String)> = symbol.map(|s| ("symbol", binance_symbol(s))).into_iter().collect();
+        let orders: Vec<RestOrder> = self.signed(Method::GET, "/api/v3/openOrders", &params).await?;
+
+        let mut result = Vec::with_capacity(orders.len());
+        for order in orders {
+            let symbol = symbol.cloned().unwrap_or_else(|| Symbol::new(order.symbol.clone()));
+            result.push(self.to_universal(order, symbol).await);
+        }
+        Ok(result)
+    }
+
+    async fn get_order_history(&self, symbol: Option<&Symbol>, limit: Option<u32>) -> ExchangeResult<Vec<UniversalOrder>> {
+        let symbol = symbol.ok_or_else(|| ExchangeError::InvalidRequest {
+            details: "Binance order history requires a symbol".to_string(),
+        })?;
+        let mut params = vec![("symbol", binance_symbol(symbol))];
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+
+        let orders: Vec<RestOrder> = self.signed(Method::GET, "/api/v3/allOrders", &params).await?;
+        let mut result = Vec::with_capacity(orders.len());
+        for order in orders {
+            result.push(self.to_universal(order, symbol.clone()).await);
+        }
+        Ok(result)
+    }
+
+    async fn get_trade_history(&self, symbol: Option<&Symbol>, limit: Option<u32>) -> ExchangeResult<Vec<TradeExecution>> {
+        let symbol = symbol.ok_or_else(|| ExchangeError::InvalidRequest {
+            details: "Binance trade history requires a symbol".to_string(),
+        })?;
+        let mut params = vec![("symbol", binance_symbol(symbol))];
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+
+        let trades: Vec<RestTrade> = self.signed(Method::GET, "/api/v3/myTrades", &params).await?;
+        Ok(trades
+            .into_iter()
+            .map(|t| {
+                let (price, quantity) = (num(&t.price), num(&t.qty));
+                let commission = num(&t.commission);
+                TradeExecution {
+                    id: t.id.to_string(),
+                    order_id: t.order_id.to_string(),
+                    symbol: symbol.clone(),
+                    side: if t.is_buyer { Side::Buy } else { Side::Sell },
+                    quantity,
+                    price,
+                    fee: TradeFee {
+                        asset: t.commission_asset,
+                        amount: commission,
+                        rate: 0.0,
+                    },
+                    timestamp: timestamp(t.time),
+                    is_maker: t.is_maker,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_ticker(&self, symbol: &Symbol) -> ExchangeResult<UniversalTicker> {
+        let ticker: RestTicker = self.public("/api/v3/ticker/24hr", &[("symbol", binance_symbol(symbol))]).await?;
+        Ok(UniversalTicker {
+            symbol: symbol.clone(),
+            exchange: Exchange::Binance,
+            price: num(&ticker.last_price),
+            price_change: num(&ticker.price_change),
+            price_change_percent: num(&ticker.price_change_percent),
+            high_24h: num(&ticker.high_price),
+            low_24h: num(&ticker.low_price),
+            volume_24h: num(&ticker.volume),
+            volume_quote_24h: num(&ticker.quote_volume),
+            open_24h: num(&ticker.open_price),
+            timestamp: timestamp(ticker.close_time),
+        })
+    }
+
+    async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> ExchangeResult<UniversalOrderBook> {
+        let mut params = vec![("symbol", binance_symbol(symbol))];
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+        let depth: RestDepth = self.public("/api/v3/depth", &params).await?;
+        let levels = |levels: Vec<(String, String)>| levels.iter().map(|(price, qty)| (num(price), num(qty))).collect();
+        let now = now_ms();
+        Ok(UniversalOrderBook {
+            exchange: Exchange::Binance,
+            symbol: symbol.clone(),
+            bids: levels(depth.bids),
+            asks: levels(depth.asks),
+            timestamp_exchange: now, // The snapshot carries no time
+            timestamp_local: now,
+            sequence: depth.last_update_id,
+        })
+    }
+
+    async fn get_recent_trades(&self, symbol: &Symbol, limit: Option<u32>) -> ExchangeResult<Vec<UniversalTrade>> {
+        let mut params = vec![("symbol", binance_symbol(symbol))];
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+        let trades: Vec<RestPublicTrade> = self.public("/api/v3/trades", &params).await?;
+        let now = now_ms();
+        Ok(trades
+            .into_iter()
+            .map(|t| UniversalTrade {
+                exchange: Exchange::Binance,
+                symbol: symbol.clone(),
+                price: num(&t.price),
+                quantity: num(&t.qty),
+                // The buyer resting on the book means the seller took liquidity
+                side: if t.is_buyer_maker { Side::Sell } else { Side::Buy },
+                timestamp_exchange: t.time as u64,
+                timestamp_local: now,
+                trade_id: t.id.to_string(),
+            })
+            .collect())
+    }
+
+    async fn subscribe(&mut self, _symbols: Vec<&str>) -> ExchangeResult<()> {
+        Err(ExchangeError::InvalidRequest {
+            details: "The REST connector does not stream; use BinanceWebSocket for market data".to_string(),
+        })
+    }
+
+    fn try_recv(&mut self) -> Option<UniversalMarketData> {
+        None
+    }
+
+    async fn start(&mut self) -> ExchangeResult<()> {
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "Binance REST"
+    }
+
+    async fn get_klines(&self, symbol: &Symbol, interval: KlineInterval, start_time: Option<DateTime<Utc>>, end_time: Option<DateTime<Utc>>, limit: Option<u32>) -> ExchangeResult<Vec<UniversalKline>> {
+        let mut params = vec![("symbol", binance_symbol(symbol)), ("interval", interval.to_string())];
+        if let Some(start) = start_time {
+            params.push(("startTime", start.timestamp_millis().to_string()));
+        }
+        if let Some(end) = end_time {
+            params.push(("endTime", end.timestamp_millis().to_string()));
+        }
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+
+        let rows: Vec<Vec<serde_json::Value>> = self.public("/api/v3/klines", &params).await?;
+        rows.iter()
+            .map(|row| parse_kline_row(symbol, interval, row))
+            .collect()
+    }
+
+    async fn ping(&self) -> ExchangeResult<u64> {
+        let started = Instant::now();
+        let _: serde_json::Value = self.public("/api/v3/ping", &[]).await?;
+        Ok(started.elapsed().as_millis() as u64)
+    }
+
+    async fn get_exchange_info(&self) -> ExchangeResult<ExchangeInfo> {
+        let info: RestExchangeInfo = self.public("/api/v3/exchangeInfo", &[]).await?;
+        let rate_limits: Vec<RateLimit> = info.rate_limits.iter().filter_map(rate_limit).collect();
+        self.rate_limiter.configure(Exchange::Binance, &rate_limits);
+
+        let symbols = info.symbols
+            .iter()
+            .map(|s| {
+                let filters = symbol_filters(s);
+                SymbolInfo {
+                    symbol: Symbol::new(s.symbol.clone()),
+                    base_asset: s.base_asset.clone(),
+                    quote_asset: s.quote_asset.clone(),
+                    status: match s.status.as_str() {
+                        "TRADING" => SymbolStatus::Trading,
+                        "PRE_TRADING" => SymbolStatus::PreTrading,
+                        "POST_TRADING" => SymbolStatus::PostTrading,
+                        "END_OF_DAY" => SymbolStatus::EndOfDay,
+                        "AUCTION_MATCH" => SymbolStatus::AuctionMatch,
+                        "BREAK" => SymbolStatus::Break,
+                        _ => SymbolStatus::Halt,
+                    },
+                    base_precision: s.base_asset_precision,
+                    quote_precision: s.quote_asset_precision,
+                    min_quantity: filters.min_quantity,
+                    max_quantity: f64::MAX,
+                    step_size: filters.step_size,
+                    min_price: 0.0,
+                    max_price: f64::MAX,
+                    tick_size: filters.tick_size,
+                    min_notional: filters.min_notional,
+                    order_types: s.order_types
+                        .iter()
+                        .filter_map(|t| match t.as_str() {
+                            "MARKET" => Some(OrderType::Market),
+                            "LIMIT" => Some(OrderType::Limit { price: 0.0 }),
+                            "STOP_LOSS_LIMIT" => Some(OrderType::StopLimit { stop: 0.0, limit: 0.0 }),
+                            _ => None,
+                        })
+                        .collect(),
+                    is_spot_trading_allowed: s.is_spot_trading_allowed,
+                    is_margin_trading_allowed: s.is_margin_trading_allowed,
+                }
+            })
+            .collect();
+
+        Ok(ExchangeInfo {
+            exchange: Exchange::Binance,
+            timezone: info.timezone,
+            server_time: timestamp(info.server_time),
+            symbols,
+            rate_limits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_signing_and_rounding() {
+        // Example from the Binance API documentation
+        let secret = "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j";
+        let query = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+        assert_eq!(sign(secret, query), "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71");
+
+        assert_eq!(binance_symbol(&Symbol::new("btc-usdt")), "BTCUSDT");
+        assert_eq!(format_step(0.0199999, 0.00001), "0.01999");
+        assert_eq!(format_step(50123.456, 0.01), "50123.45");
+        assert_eq!(format_step(3.0, 1.0), "3");
+    }
+
+    #[test]
+    fn test_market_data_weights() {
+        let symbol = || ("symbol", "BTCUSDT".to_string());
+        let weight = |path, params: &[(&str, String)]| request_costs(&Method::GET, path, params)[0].1;
+        assert_eq!(weight("/api/v3/depth", &[symbol()]), 5);
+        assert_eq!(weight("/api/v3/depth", &[symbol(), ("limit", "500".to_string())]), 25);
+        assert_eq!(weight("/api/v3/depth", &[symbol(), ("limit", "5000".to_string())]), 250);
+        assert_eq!(weight("/api/v3/trades", &[symbol()]), 25);
+
+        let depth: RestDepth = serde_json::from_str(r#"{"lastUpdateId":1027024,"bids":[["4.00000000","431.00000000"]],"asks":[["4.00000200","12.00000000"]]}"#).unwrap();
+        assert_eq!((depth.last_update_id, depth.bids[0].0.as_str(), depth.asks[0].1.as_str()), (1027024, "4.00000000", "12.00000000"));
+    }
+}
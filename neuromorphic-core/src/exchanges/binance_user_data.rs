@@ -0,0 +1,483 @@
+//! Binance user data stream
+//!
+//! Order and balance changes on the account are pushed over a WebSocket
+//! opened with a listen key from the REST API. The key is kept alive every
+//! 30 minutes and replaced when it expires or the connection drops.
+
+use super::binance_rest::{num, order_status, order_type, time_in_force, timestamp, BinanceRestConnector};
+use super::connector::{Balance, ExchangeError, ExchangeResult, TradeFee, UniversalOrder};
+use super::types::{Exchange, Side, Symbol};
+use super::websocket::{ConnectionStatus, ReconnectPolicy};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, info, warn};
+
+/// Spot Testnet user data WebSocket endpoint; the listen key is appended
+pub const BINANCE_TESTNET_USER_DATA_URL: &str = "wss://testnet.binance.vision/ws";
+
+#[derive(Debug, Clone)]
+pub struct BinanceUserDataConfig {
+    pub ws_base_url: String,
+    /// Binance drops keys not kept alive for 60 minutes
+    pub keepalive_interval: Duration,
+    pub reconnect: ReconnectPolicy,
+    /// Finished orders are kept this long for late status checks
+    pub retain_finished: Duration,
+}
+
+impl BinanceUserDataConfig {
+    pub fn testnet() -> Self {
+        Self {
+            ws_base_url: BINANCE_TESTNET_USER_DATA_URL.to_string(),
+            keepalive_interval: Duration::from_secs(30 * 60),
+            reconnect: ReconnectPolicy::forever(),
+            retain_finished: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// What the execution report says happened to the order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionType {
+    New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Trade,
+    Expired,
+}
+
+/// Order state after an execution report
+#[derive(Debug, Clone)]
+pub struct OrderUpdate {
+    pub order: UniversalOrder, // Cumulative fills, average price and fees so far
+    pub execution_type: ExecutionType,
+    pub last_quantity: f64, // Filled by this report
+    pub last_price: f64,
+    pub reject_reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum UserDataEvent {
+    Order(Box<OrderUpdate>),
+    /// Balances of the assets that changed
+    Balances { balances: Vec<Balance>, updated_at: DateTime<Utc> },
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecutionReport {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "c")]
+    client_order_id: String,
+    #[serde(rename = "C", default)]
+    original_client_order_id: String, // Set on cancels, where `c` is the cancel request's ID
+    #[serde(rename = "S")]
+    side: String,
+    #[serde(rename = "o")]
+    order_type: String,
+    #[serde(rename = "f")]
+    time_in_force: String,
+    #[serde(rename = "q")]
+    quantity: String,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "P")]
+    stop_price: String,
+    #[serde(rename = "x")]
+    execution_type: String,
+    #[serde(rename = "X")]
+    status: String,
+    #[serde(rename = "r")]
+    reject_reason: String,
+    #[serde(rename = "i")]
+    order_id: i64,
+    #[serde(rename = "l")]
+    last_quantity: String,
+    #[serde(rename = "z")]
+    cumulative_quantity: String,
+    #[serde(rename = "L")]
+    last_price: String,
+    #[serde(rename = "n")]
+    commission: String,
+    #[serde(rename = "N")]
+    commission_asset: Option<String>,
+    #[serde(rename = "T")]
+    transaction_time: i64,
+    #[serde(rename = "O")]
+    created_time: i64,
+    #[serde(rename = "Z")]
+    cumulative_quote_quantity: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountPosition {
+    #[serde(rename = "u")]
+    updated_time: i64,
+    #[serde(rename = "B")]
+    balances: Vec<AccountBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountBalance {
+    #[serde(rename = "a")]
+    asset: String,
+    #[serde(rename = "f")]
+    free: String,
+    #[serde(rename = "l")]
+    locked: String,
+}
+
+#[derive(Debug)]
+enum StreamMessage {
+    Execution(Box<ExecutionReport>),
+    Account(AccountPosition),
+    ListenKeyExpired,
+}
+
+/// Decode a stream message; events this stream doesn't use give `None`
+fn parse_message(text: &str) -> ExchangeResult<Option<StreamMessage>> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    let message = match value.get("e").and_then(|e| e.as_str()) {
+        Some("executionReport") => StreamMessage::Execution(serde_json::from_value(value)?),
+        Some("outboundAccountPosition") => StreamMessage::Account(serde_json::from_value(value)?),
+        Some("listenKeyExpired") => StreamMessage::ListenKeyExpired,
+        _ => return Ok(None),
+    };
+    Ok(Some(message))
+}
+
+fn execution_type(kind: &str) -> ExecutionType {
+    match kind {
+        "NEW" => ExecutionType::New,
+        "CANCELED" => ExecutionType::Canceled,
+        "REPLACED" => ExecutionType::Replaced,
+        "REJECTED" => ExecutionType::Rejected,
+        "TRADE" => ExecutionType::Trade,
+        _ => ExecutionType::Expired, // EXPIRED, TRADE_PREVENTION
+    }
+}
+
+/// Order state from a report; `fees` are the fees of every fill so far
+fn to_order_update(report: &ExecutionReport, symbol: Symbol, fees: Option<TradeFee>) -> OrderUpdate {
+    let executed = num(&report.cumulative_quantity);
+    let quote = num(&report.cumulative_quote_quantity);
+    let quantity = num(&report.quantity);
+    let limit_price = num(&report.price);
+    let stop_price = Some(num(&report.stop_price)).filter(|p| *p > 0.0);
+    let avg_price = if executed > 0.0 { quote / executed } else { limit_price };
+
+    let mut metadata = HashMap::new();
+    metadata.insert(UniversalOrder::AVG_PRICE_KEY.to_string(), avg_price.to_string());
+
+    let client_order_id = if report.original_client_order_id.is_empty() {
+        &report.client_order_id
+    } else {
+        &report.original_client_order_id
+    };
+
+    OrderUpdate {
+        order: UniversalOrder {
+            id: report.order_id.to_string(),
+            client_order_id: Some(client_order_id.clone()),
+            symbol,
+            side: if report.side == "BUY" { Side::Buy } else { Side::Sell },
+            order_type: order_type(&report.order_type, limit_price, stop_price),
+            quantity,
+            filled_quantity: executed,
+            remaining_quantity: (quantity - executed).max(0.0),
+            price: (limit_price > 0.0).then_some(limit_price),
+            stop_price,
+            status: order_status(&report.status),
+            time_in_force: time_in_force(&report.time_in_force),
+            created_at: timestamp(report.created_time),
+            updated_at: timestamp(report.transaction_time.max(report.event_time)),
+            exchange: Exchange::Binance,
+            fees,
+            metadata,
+        },
+        execution_type: execution_type(&report.execution_type),
+        last_quantity: num(&report.last_quantity),
+        last_price: num(&report.last_price),
+        reject_reason: Some(report.reject_reason.clone()).filter(|r| r != "NONE"),
+    }
+}
+
+/// Push-based order and balance updates for one account
+pub struct BinanceUserDataStream {
+    connector: Arc<BinanceRestConnector>,
+    config: BinanceUserDataConfig,
+    orders: DashMap<String, UniversalOrder>, // Venue order ID -> latest state
+    fees: DashMap<String, Option<(String, f64)>>, // Venue order ID -> quote commission so far, `None` once unpriceable
+    balances: DashMap<String, Balance>,
+    status: parking_lot::RwLock<ConnectionStatus>,
+    status_sender: broadcast::Sender<ConnectionStatus>,
+    event_sender: broadcast::Sender<UserDataEvent>,
+}
+
+impl BinanceUserDataStream {
+    pub fn new(connector: Arc<BinanceRestConnector>, config: BinanceUserDataConfig) -> Self {
+        let (status_sender, _) = broadcast::channel(16);
+        let (event_sender, _) = broadcast::channel(1000);
+        Self {
+            connector,
+            config,
+            orders: DashMap::new(),
+            fees: DashMap::new(),
+            balances: DashMap::new(),
+            status: parking_lot::RwLock::new(ConnectionStatus::Disconnected),
+            status_sender,
+            event_sender,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<UserDataEvent> {
+        self.event_sender.subscribe()
+    }
+
+    pub fn subscribe_status(&self) -> broadcast::Receiver<ConnectionStatus> {
+        self.status_sender.subscribe()
+    }
+
+    pub fn status(&self) -> ConnectionStatus {
+        self.status.read().clone()
+    }
+
+    /// While connected, the cached order states are current
+    pub fn is_connected(&self) -> bool {
+        *self.status.read() == ConnectionStatus::Connected
+    }
+
+    /// Latest known state of a venue order
+    pub fn order(&self, venue_order_id: &str) -> Option<UniversalOrder> {
+        self.orders.get(venue_order_id).map(|o| o.clone())
+    }
+
+    /// Remember an order state seen over REST, unless the stream already
+    /// reported a newer one
+    pub fn track(&self, order: &UniversalOrder) {
+        self.orders
+            .entry(order.id.clone())
+            .and_modify(|known| {
+                if order.updated_at >= known.updated_at {
+                    *known = order.clone();
+                }
+            })
+            .or_insert_with(|| order.clone());
+    }
+
+    pub fn forget(&self, venue_order_id: &str) {
+        self.orders.remove(venue_order_id);
+        self.fees.remove(venue_order_id);
+    }
+
+    /// Balances as of the last account update
+    pub fn balances(&self) -> Vec<Balance> {
+        self.balances.iter().map(|b| b.clone()).collect()
+    }
+
+    /// Run the stream until the task is aborted or the reconnect policy gives
+    /// up, reconnecting with a fresh listen key whenever the connection ends
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut failures = 0;
+            loop {
+                match self.session(&mut failures).await {
+                    Ok(()) => info!("User data stream ended, reconnecting"),
+                    Err(e) => {
+                        failures += 1;
+                        warn!(error = %e, failures, "User data stream failed");
+                    }
+                }
+                if self.config.reconnect.gives_up_after(failures) {
+                    warn!("User data stream giving up; fills fall back to polling");
+                    self.set_status(ConnectionStatus::Failed);
+                    return;
+                }
+                self.set_status(ConnectionStatus::Reconnecting);
+                tokio::time::sleep(self.config.reconnect.delay(failures)).await;
+            }
+        })
+    }
+
+    fn set_status(&self, status: ConnectionStatus) {
+        let mut current = self.status.write();
+        if *current != status {
+            *current = status.clone();
+            let _ = self.status_sender.send(status);
+        }
+    }
+
+    /// One connection, from listen key to disconnect. A connection that
+    /// comes up resets the count of consecutive failures.
+    async fn session(&self, failures: &mut u32) -> ExchangeResult<()> {
+        self.set_status(ConnectionStatus::Connecting);
+        let listen_key = self.connector.create_listen_key().await?;
+        let (ws, _) = connect_async(format!("{}/{}", self.config.ws_base_url, listen_key))
+            .await
+            .map_err(|e| ExchangeError::Connection { message: format!("User data stream connection failed: {}", e) })?;
+        let (mut sink, mut stream) = ws.split();
+
+        // Reports may have been missed while disconnected, so open orders
+        // are polled once more before the stream is trusted for them
+        self.orders.retain(|_, order| !order.is_active());
+        self.set_status(ConnectionStatus::Connected);
+        *failures = 0;
+        info!("User data stream connected");
+
+        let mut keepalive = tokio::time::interval_at(
+            tokio::time::Instant::now() + self.config.keepalive_interval,
+            self.config.keepalive_interval,
+        );
+        let result = loop {
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    if let Err(e) = self.connector.keepalive_listen_key(&listen_key).await {
+                        break Err(e);
+                    }
+                    self.prune_finished();
+                }
+                message = stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => match parse_message(&text) {
+                        Ok(Some(StreamMessage::ListenKeyExpired)) => break Ok(()),
+                        Ok(Some(message)) => self.handle(message).await,
+                        Ok(None) => debug!("Ignored user data event: {}", text),
+                        Err(e) => warn!(error = %e, "Unparseable user data event"),
+                    },
+                    Some(Ok(Message::Ping(payload))) => {
+                        if let Err(e) = sink.send(Message::Pong(payload)).await {
+                            break Err(ExchangeError::Connection { message: format!("Failed to answer ping: {}", e) });
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => break Err(ExchangeError::Connection { message: format!("User data stream error: {}", e) }),
+                },
+            }
+        };
+
+        self.set_status(ConnectionStatus::Disconnected);
+        let _ = self.connector.close_listen_key(&listen_key).await; // Already gone if it expired
+        result
+    }
+
+    async fn handle(&self, message: StreamMessage) {
+        let event = match message {
+            StreamMessage::Execution(report) => {
+                let id = report.order_id.to_string();
+                let symbol = self.connector.order_symbol(&id, &report.symbol);
+
+                // Each report carries the commission of its own fill only
+                let commission = num(&report.commission);
+                let fill_fee = match &report.commission_asset {
+                    Some(asset) if commission > 0.0 => {
+                        self.connector.quote_commission(&report.symbol, commission, asset, num(&report.last_price)).await
+                    }
+                    _ => None,
+                };
+                let total = {
+                    let mut total = self.fees.entry(id.clone()).or_insert(Some((String::new(), 0.0)));
+                    if commission > 0.0 {
+                        *total = match (total.take(), fill_fee) {
+                            (Some((_, sum)), Some((asset, fee))) => Some((asset, sum + fee)),
+                            _ => None,
+                        };
+                    }
+                    total.clone()
+                };
+                let quote = num(&report.cumulative_quote_quantity);
+                let fees = total.filter(|(asset, _)| !asset.is_empty()).map(|(asset, amount)| TradeFee {
+                    asset,
+                    amount,
+                    rate: if quote > 0.0 { amount / quote } else { 0.0 },
+                });
+
+                let update = to_order_update(&report, symbol, fees);
+                self.orders.insert(id, update.order.clone());
+                UserDataEvent::Order(Box::new(update))
+            }
+            StreamMessage::Account(position) => {
+                let balances: Vec<Balance> = position
+                    .balances
+                    .iter()
+                    .map(|b| Balance::new(b.asset.clone(), num(&b.free), num(&b.locked)))
+                    .collect();
+                for balance in &balances {
+                    self.balances.insert(balance.asset.clone(), balance.clone());
+                }
+                UserDataEvent::Balances { balances, updated_at: timestamp(position.updated_time) }
+            }
+            StreamMessage::ListenKeyExpired => return,
+        };
+        let _ = self.event_sender.send(event); // No subscribers is fine
+    }
+
+    /// Drop finished orders nobody asked about
+    fn prune_finished(&self) {
+        let cutoff = Utc::now() - ChronoDuration::from_std(self.config.retain_finished).unwrap_or_default();
+        let stale: Vec<String> = self
+            .orders
+            .iter()
+            .filter(|o| !o.is_active() && o.updated_at < cutoff)
+            .map(|o| o.id.clone())
+            .collect();
+        for id in stale {
+            self.forget(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::connector::OrderStatus;
+
+    #[test]
+    fn test_parse_user_data_events() {
+        // Partial fill of a limit order, from the Binance API documentation
+        let report = r#"{"e":"executionReport","E":1499405658658,"s":"ETHBTC","c":"mUvoqJxFIILMdfAW5iGSOW","S":"BUY",
+            "o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"",
+            "x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":4293153,"l":"0.40000000","z":"0.40000000",
+            "L":"0.10264400","n":"0.00004105","N":"BTC","T":1499405658657,"t":77,"I":8641984,"w":true,"m":true,
+            "M":false,"O":1499405658657,"Z":"0.04105760","Y":"0.04105760","Q":"0.00000000"}"#;
+        let Some(StreamMessage::Execution(report)) = parse_message(report).unwrap() else {
+            panic!("expected an execution report");
+        };
+        let fee = TradeFee { asset: "BTC".to_string(), amount: 0.00004105, rate: 0.001 };
+        let update = to_order_update(&report, Symbol::new("ETHBTC"), Some(fee));
+        assert_eq!(update.execution_type, ExecutionType::Trade);
+        assert_eq!(update.order.id, "4293153");
+        assert_eq!(update.order.client_order_id.as_deref(), Some("mUvoqJxFIILMdfAW5iGSOW"));
+        assert_eq!(update.order.status, OrderStatus::PartiallyFilled);
+        assert!(update.order.is_active());
+        assert_eq!((update.order.filled_quantity, update.order.remaining_quantity), (0.4, 0.6));
+        assert!((update.order.average_price().unwrap() - 0.102644).abs() < 1e-9);
+        assert_eq!((update.last_quantity, update.last_price), (0.4, 0.102644));
+        assert_eq!(update.reject_reason, None);
+
+        let account = r#"{"e":"outboundAccountPosition","E":1564034571105,"u":1564034571073,
+            "B":[{"a":"ETH","f":"10000.000000","l":"0.000000"}]}"#;
+        let Some(StreamMessage::Account(account)) = parse_message(account).unwrap() else {
+            panic!("expected an account update");
+        };
+        assert_eq!((account.balances[0].asset.as_str(), num(&account.balances[0].free)), ("ETH", 10_000.0));
+
+        assert!(matches!(
+            parse_message(r#"{"e":"listenKeyExpired","E":1576653824250,"listenKey":"abc"}"#).unwrap(),
+            Some(StreamMessage::ListenKeyExpired)
+        ));
+        assert!(parse_message(r#"{"e":"balanceUpdate","E":1573200697110,"a":"BTC","d":"100.0","T":1573200697068}"#)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -4,21 +4,45 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
-use super::connector::{ExchangeError, ExchangeResult};
+use super::connector::{ExchangeError, ExchangeResult, KlineInterval, UniversalKline};
+use super::data_quality::{DataQualityConfig, QuarantinedTick};
+use super::latency::LatencyConfig;
 use super::types::{Exchange, Side, Symbol, UniversalMarketData, UniversalOrderBook, UniversalQuote, UniversalTrade};
 use super::websocket::{
-    ConnectionStatus, StreamManager, StreamMetrics, StreamSubscription, StreamType, WebSocketConfig, WebSocketManager,
+    ConnectionStatus, ReconnectPolicy, StreamManager, StreamMetrics, StreamProtocol, StreamSubscription, StreamType,
+    WebSocketConfig, WebSocketManager,
 };
 
-/// Binance WebSocket stream manager
+/// Production combined stream endpoint
+pub const BINANCE_STREAM_URL: &str = "wss://stream.binance.com:9443/stream";
+
+/// Spot Testnet combined stream endpoint
+pub const BINANCE_TESTNET_STREAM_URL: &str = "wss://testnet.binance.vision/stream";
+
+/// Most streams Binance allows on one connection
+pub const BINANCE_MAX_STREAMS_PER_CONNECTION: usize = 1024;
+
+/// Binance WebSocket stream manager. Streams go over combined-stream
+/// connections, opening another connection whenever one is full.
 pub struct BinanceWebSocketManager {
-    inner: WebSocketManager,
+    shards: Vec<WebSocketManager>, // The first one owns the data receiver
+    shard_streams: Vec<usize>, // Streams assigned to each shard
+    assignments: HashMap<String, usize>, // Stream name -> shard
+    protocol: Arc<BinanceStreamProtocol>,
+    max_streams_per_connection: usize,
+    started: bool,
     testnet: bool,
 }
 
+/// Binance's combined stream format: streams are named in the connection URL
+/// and every message comes wrapped as `{"stream": ..., "data": ...}`
+#[derive(Debug, Default)]
+pub struct BinanceStreamProtocol;
+
 /// Binance WebSocket message format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct BinanceMessage {
@@ -87,6 +111,46 @@ struct BinanceDepthData {
     asks: Vec<[String; 2]>,
 }
 
+/// Binance kline event format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BinanceKlineEvent {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "k")]
+    kline: BinanceKlineData,
+}
+
+/// Binance kline payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BinanceKlineData {
+    #[serde(rename = "t")]
+    open_time: i64,
+    #[serde(rename = "T")]
+    close_time: i64,
+    #[serde(rename = "i")]
+    interval: String,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "c")]
+    close: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "v")]
+    volume: String,
+    #[serde(rename = "n")]
+    trades_count: u64,
+    #[serde(rename = "x")]
+    is_closed: bool,
+    #[serde(rename = "q")]
+    quote_volume: String,
+    #[serde(rename = "V")]
+    taker_buy_volume: String,
+    #[serde(rename = "Q")]
+    taker_buy_quote_volume: String,
+}
+
 /// Binance 24hr ticker data format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct BinanceTickerData {
@@ -141,28 +205,82 @@ struct BinanceTickerData {
 impl BinanceWebSocketManager {
     /// Create a new Binance WebSocket manager
     pub fn new(testnet: bool) -> Self {
-        let base_url = if testnet {
-            "wss://testnet.binance.vision/ws".to_string()
-        } else {
-            "wss://stream.binance.com:9443/ws".to_string()
-        };
+        let base_url = if testnet { BINANCE_TESTNET_STREAM_URL } else { BINANCE_STREAM_URL }.to_string();
         
         let config = WebSocketConfig {
             base_url,
             ping_interval: Duration::from_secs(180), // Binance expects 3 minute intervals
-            reconnect_interval: Duration::from_secs(5),
-            max_reconnect_attempts: 10,
+            reconnect: ReconnectPolicy::forever(), // A session can't trade without market data
             message_timeout: Duration::from_secs(30),
             buffer_size: 1000,
             enable_compression: true,
+            data_quality: DataQualityConfig::default(),
+            latency: LatencyConfig::default(),
         };
         
+        let protocol = Arc::new(BinanceStreamProtocol);
         Self {
-            inner: WebSocketManager::new(config, Exchange::Binance),
+            shards: vec![WebSocketManager::new(config, Exchange::Binance).with_protocol(protocol.clone())],
+            shard_streams: vec![0],
+            assignments: HashMap::new(),
+            protocol,
+            max_streams_per_connection: BINANCE_MAX_STREAMS_PER_CONNECTION,
+            started: false,
             testnet,
         }
     }
     
+    /// Cap the streams per connection below Binance's limit, e.g. to keep
+    /// each connection's message rate down
+    pub fn with_max_streams_per_connection(mut self, max: usize) -> Self {
+        self.max_streams_per_connection = max.clamp(1, BINANCE_MAX_STREAMS_PER_CONNECTION);
+        self
+    }
+    
+    /// Connections opened so far
+    pub fn connection_count(&self) -> usize {
+        self.shards.len()
+    }
+    
+    /// Subscribe to many streams at once. Each connection gets its share in a
+    /// single message, or in its URL if it hasn't connected yet.
+    pub async fn subscribe_many(&mut self, subscriptions: Vec<StreamSubscription>) -> ExchangeResult<()> {
+        let existing = self.shards.len();
+        let mut batches: Vec<Vec<StreamSubscription>> = vec![Vec::new(); existing];
+        for subscription in subscriptions {
+            let name = self.protocol.stream_name(&subscription);
+            if self.assignments.contains_key(&name) {
+                continue;
+            }
+            
+            let shard = match self.shard_streams.iter().position(|&n| n < self.max_streams_per_connection) {
+                Some(shard) => shard,
+                None => {
+                    self.shards.push(self.shards[0].new_shard());
+                    self.shard_streams.push(0);
+                    batches.push(Vec::new());
+                    self.shards.len() - 1
+                }
+            };
+            self.assignments.insert(name, shard);
+            self.shard_streams[shard] += 1;
+            batches[shard].push(subscription);
+        }
+        
+        for (shard, batch) in batches.into_iter().enumerate() {
+            let manager = &mut self.shards[shard];
+            manager.subscribe_batch(batch).await?;
+            // New connections start with their streams already in the URL
+            if self.started && shard >= existing {
+                manager.start().await?;
+            }
+        }
+        Ok(())
+    }
+    
+}
+
+impl BinanceStreamProtocol {
     /// Create subscription for Binance format
     fn create_binance_subscription(&self, subscription: &StreamSubscription) -> String {
         let symbol = subscription.symbol.as_str().to_lowercase();
@@ -178,7 +296,7 @@ impl BinanceWebSocketManager {
                 format!("{}@kline_{}", symbol, interval)
             }
             StreamType::UserData => {
-                // User data streams require a listen key from the REST API
+                // User data has its own listen key connection, see `BinanceUserDataStream`
                 "userData".to_string()
             }
         }
@@ -387,17 +505,89 @@ impl BinanceWebSocketManager {
         Ok(None)
     }
     
-    /// Parse kline data
-    fn parse_kline_data(&self, _data: &serde_json::Value) -> ExchangeResult<Option<UniversalMarketData>> {
-        // Kline parsing would be implemented here
-        debug!("Kline data parsing not yet implemented");
-        Ok(None)
+    /// Parse kline data. Updates to the candle still forming are skipped.
+    fn parse_kline_data(&self, data: &serde_json::Value) -> ExchangeResult<Option<UniversalMarketData>> {
+        let event: BinanceKlineEvent = serde_json::from_value(data.clone()).map_err(|e| {
+            ExchangeError::InvalidRequest {
+                details: format!("Failed to parse kline data: {}", e),
+            }
+        })?;
+        let k = event.kline;
+        if !k.is_closed {
+            return Ok(None);
+        }
+        
+        let number = |field: &str, value: &str| {
+            value.parse::<f64>().map_err(|e| ExchangeError::InvalidRequest {
+                details: format!("Invalid kline {}: {}", field, e),
+            })
+        };
+        let time = |ms: i64| DateTime::<Utc>::from_timestamp_millis(ms).unwrap_or_else(Utc::now);
+        
+        let kline = UniversalKline {
+            symbol: Symbol::new(event.symbol),
+            exchange: Exchange::Binance,
+            interval: k.interval.parse::<KlineInterval>()?,
+            open_time: time(k.open_time),
+            close_time: time(k.close_time),
+            open: number("open", &k.open)?,
+            high: number("high", &k.high)?,
+            low: number("low", &k.low)?,
+            close: number("close", &k.close)?,
+            volume: number("volume", &k.volume)?,
+            quote_volume: number("quote volume", &k.quote_volume)?,
+            trades_count: k.trades_count,
+            taker_buy_volume: number("taker buy volume", &k.taker_buy_volume)?,
+            taker_buy_quote_volume: number("taker buy quote volume", &k.taker_buy_quote_volume)?,
+        };
+        
+        Ok(Some(UniversalMarketData::Kline(kline)))
     }
     
+}
+
+impl StreamProtocol for BinanceStreamProtocol {
+    fn stream_name(&self, subscription: &StreamSubscription) -> String {
+        self.create_binance_subscription(subscription)
+    }
+    
+    fn connect_url(&self, base_url: &str, subscriptions: &[StreamSubscription]) -> String {
+        let streams: Vec<String> = subscriptions.iter().map(|s| self.stream_name(s)).collect();
+        combined_stream_url(base_url, &streams)
+    }
+    
+    fn streams_in_url(&self) -> bool {
+        true
+    }
+    
+    fn parse(&self, text: &str, _exchange: Exchange) -> ExchangeResult<Option<UniversalMarketData>> {
+        self.parse_binance_message(text)
+    }
+    
+    fn exchange_time(&self, data: &UniversalMarketData) -> Option<u64> {
+        match data {
+            UniversalMarketData::Trade(trade) => Some(trade.timestamp_exchange),
+            UniversalMarketData::OrderBook(book) => Some(book.timestamp_exchange),
+            // bookTicker carries no event time, and klines are emitted after they close
+            UniversalMarketData::Quote(_) | UniversalMarketData::Kline(_) => None,
+        }
+    }
+}
+
+/// Combined stream URL; with no streams yet, the bare endpoint
+fn combined_stream_url(base_url: &str, streams: &[String]) -> String {
+    if streams.is_empty() {
+        base_url.to_string()
+    } else {
+        format!("{}?streams={}", base_url, streams.join("/"))
+    }
+}
+
+impl BinanceWebSocketManager {
     /// Subscribe to multiple symbols at once
     pub async fn subscribe_symbols(&mut self, symbols: Vec<Symbol>, stream_type: StreamType) -> ExchangeResult<()> {
-        for symbol in symbols {
-            let subscription = match stream_type {
+        let subscriptions = symbols.into_iter().map(|symbol| {
+            match stream_type {
                 StreamType::Trade => StreamSubscription::trade(symbol),
                 StreamType::Quote => StreamSubscription::quote(symbol),
                 StreamType::OrderBook => StreamSubscription::orderbook(symbol),
@@ -408,56 +598,112 @@ impl BinanceWebSocketManager {
                 },
                 StreamType::Kline => StreamSubscription::kline(symbol, "1m".to_string()),
                 StreamType::UserData => StreamSubscription::user_data(),
-            };
-            
-            self.subscribe(subscription).await?;
-        }
-        Ok(())
+            }
+        });
+        self.subscribe_many(subscriptions.collect()).await
+    }
+    
+    /// Ticks held back by the data quality filter, oldest first
+    pub async fn quarantined_ticks(&self) -> Vec<QuarantinedTick> {
+        self.shards[0].quarantined_ticks().await // The filter is shared by all connections
+    }
+    
+    /// Correct stream latencies with an offset measured by
+    /// `BinanceRestConnector::clock_offset`
+    pub async fn set_clock_offset(&self, offset_ms: f64) {
+        self.shards[0].set_clock_offset(offset_ms).await // The tracker is shared by all connections
+    }
+    
+    /// Status changes of every connection
+    pub fn subscribe_status(&self) -> tokio::sync::broadcast::Receiver<ConnectionStatus> {
+        self.shards[0].subscribe_status()
     }
     
     /// Get Binance-specific stream URL for combined streams
     pub fn get_combined_stream_url(&self, subscriptions: &[String]) -> String {
-        let base_url = if self.testnet {
-            "wss://testnet.binance.vision/stream"
-        } else {
-            "wss://stream.binance.com:9443/stream"
-        };
-        
-        let streams = subscriptions.join("/");
-        format!("{}?streams={}", base_url, streams)
+        let base_url = if self.testnet { BINANCE_TESTNET_STREAM_URL } else { BINANCE_STREAM_URL };
+        combined_stream_url(base_url, subscriptions)
     }
 }
 
 #[async_trait]
 impl StreamManager for BinanceWebSocketManager {
     async fn subscribe(&mut self, subscription: StreamSubscription) -> ExchangeResult<()> {
-        self.inner.subscribe(subscription).await
+        self.subscribe_many(vec![subscription]).await
     }
     
     async fn unsubscribe(&mut self, subscription: StreamSubscription) -> ExchangeResult<()> {
-        self.inner.unsubscribe(subscription).await
+        let name = self.protocol.stream_name(&subscription);
+        let Some(shard) = self.assignments.remove(&name) else {
+            return Ok(());
+        };
+        self.shard_streams[shard] -= 1;
+        self.shards[shard].unsubscribe(subscription).await
     }
     
     fn get_receiver(&mut self) -> Option<tokio::sync::broadcast::Receiver<UniversalMarketData>> {
-        self.inner.get_receiver()
+        self.shards[0].get_receiver()
     }
     
+    /// Connected only when every connection is
     async fn get_status(&self) -> ConnectionStatus {
-        self.inner.get_status().await
+        let mut statuses = Vec::with_capacity(self.shards.len());
+        for shard in &self.shards {
+            statuses.push(shard.get_status().await);
+        }
+        [
+            ConnectionStatus::Failed,
+            ConnectionStatus::Reconnecting,
+            ConnectionStatus::Connecting,
+            ConnectionStatus::Disconnected,
+        ]
+        .into_iter()
+        .find(|status| statuses.contains(status))
+        .unwrap_or(ConnectionStatus::Connected)
     }
     
+    /// Totals over all connections
     async fn get_metrics(&self) -> StreamMetrics {
-        self.inner.get_metrics().await
+        let mut total = StreamMetrics::default();
+        for shard in &self.shards {
+            let metrics = shard.get_metrics().await;
+            total.messages_received += metrics.messages_received;
+            total.messages_parsed += metrics.messages_parsed;
+            total.parse_errors += metrics.parse_errors;
+            total.connection_errors += metrics.connection_errors;
+            total.reconnection_count += metrics.reconnection_count;
+            total.last_message_time = total.last_message_time.max(metrics.last_message_time);
+            total.average_latency_ms = metrics.average_latency_ms; // Latency is tracked across all connections
+            total.latency = metrics.latency;
+            total.data_gaps += metrics.data_gaps;
+            total.quarantined_ticks += metrics.quarantined_ticks;
+            for (reason, count) in metrics.quarantined_by_reason {
+                *total.quarantined_by_reason.entry(reason).or_insert(0) += count;
+            }
+        }
+        total
     }
     
     async fn start(&mut self) -> ExchangeResult<()> {
-        info!("Starting Binance WebSocket manager (testnet: {})", self.testnet);
-        self.inner.start().await
+        info!(
+            "Starting Binance WebSocket manager (testnet: {}, connections: {})",
+            self.testnet,
+            self.shards.len()
+        );
+        for shard in &mut self.shards {
+            shard.start().await?;
+        }
+        self.started = true;
+        Ok(())
     }
     
     async fn stop(&mut self) -> ExchangeResult<()> {
         info!("Stopping Binance WebSocket manager");
-        self.inner.stop().await
+        for shard in &mut self.shards {
+            shard.stop().await?;
+        }
+        self.started = false;
+        Ok(())
     }
 }
 
@@ -477,15 +723,15 @@ mod tests {
         let manager = BinanceWebSocketManager::new(true);
         
         let trade_sub = StreamSubscription::trade(Symbol::new("BTCUSDT"));
-        let stream = manager.create_binance_subscription(&trade_sub);
+        let stream = manager.protocol.create_binance_subscription(&trade_sub);
         assert_eq!(stream, "btcusdt@trade");
         
         let quote_sub = StreamSubscription::quote(Symbol::new("ETHUSDT"));
-        let stream = manager.create_binance_subscription(&quote_sub);
+        let stream = manager.protocol.create_binance_subscription(&quote_sub);
         assert_eq!(stream, "ethusdt@bookTicker");
         
         let kline_sub = StreamSubscription::kline(Symbol::new("ADAUSDT"), "5m".to_string());
-        let stream = manager.create_binance_subscription(&kline_sub);
+        let stream = manager.protocol.create_binance_subscription(&kline_sub);
         assert_eq!(stream, "adausdt@kline_5m");
     }
     
@@ -509,7 +755,7 @@ mod tests {
         "#;
         
         let data: serde_json::Value = serde_json::from_str(trade_json).unwrap();
-        let result = manager.parse_trade_data(&data).unwrap();
+        let result = manager.protocol.parse_trade_data(&data).unwrap();
         
         assert!(result.is_some());
         if let Some(UniversalMarketData::Trade(trade)) = result {
@@ -519,4 +765,82 @@ mod tests {
             assert_eq!(trade.side, Side::Sell); // is_buyer_maker = true means sell
         }
     }
+    
+    #[test]
+    fn test_parse_binance_kline_data() {
+        let manager = BinanceWebSocketManager::new(true);
+        
+        let kline_json = r#"
+        {
+            "e": "kline",
+            "E": 1672515782136,
+            "s": "BTCUSDT",
+            "k": {
+                "t": 1672515720000,
+                "T": 1672515779999,
+                "s": "BTCUSDT",
+                "i": "1m",
+                "f": 100,
+                "L": 200,
+                "o": "16560.00",
+                "c": "16569.01",
+                "h": "16575.50",
+                "l": "16555.10",
+                "v": "12.5",
+                "n": 101,
+                "x": true,
+                "q": "207000.0",
+                "V": "6.1",
+                "Q": "101000.0",
+                "B": "0"
+            }
+        }
+        "#;
+        
+        let mut data: serde_json::Value = serde_json::from_str(kline_json).unwrap();
+        match manager.protocol.parse_kline_data(&data).unwrap() {
+            Some(UniversalMarketData::Kline(kline)) => {
+                assert_eq!(kline.interval, KlineInterval::OneMinute);
+                assert_eq!(kline.close, 16569.01);
+                assert_eq!(kline.open_time.timestamp_millis(), 1672515720000);
+            }
+            other => panic!("expected a closed kline, got {:?}", other),
+        }
+        
+        // The candle still forming is not emitted
+        data["k"]["x"] = serde_json::Value::Bool(false);
+        assert!(manager.protocol.parse_kline_data(&data).unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_streams_sharded_across_connections() {
+        let mut manager = BinanceWebSocketManager::new(true).with_max_streams_per_connection(2);
+        let symbols: Vec<Symbol> = ["BTCUSDT", "ETHUSDT", "SOLUSDT", "ADAUSDT", "XRPUSDT"]
+            .into_iter()
+            .map(Symbol::new)
+            .collect();
+        manager.subscribe_symbols(symbols.clone(), StreamType::Trade).await.unwrap();
+        manager.subscribe_symbols(symbols[..2].to_vec(), StreamType::Trade).await.unwrap(); // Already subscribed
+        assert_eq!(manager.connection_count(), 3);
+        assert_eq!(manager.shard_streams, vec![2, 2, 1]);
+        
+        // Unsubscribing frees room on that connection for the next stream
+        manager.unsubscribe(StreamSubscription::trade(Symbol::new("ETHUSDT"))).await.unwrap();
+        manager.subscribe(StreamSubscription::trade(Symbol::new("DOGEUSDT"))).await.unwrap();
+        assert_eq!(manager.connection_count(), 3);
+        assert_eq!(manager.assignments["dogeusdt@trade"], 0);
+        
+        let first = vec![StreamSubscription::trade(Symbol::new("BTCUSDT")), StreamSubscription::trade(Symbol::new("DOGEUSDT"))];
+        assert_eq!(
+            manager.protocol.connect_url(BINANCE_TESTNET_STREAM_URL, &first),
+            "wss://testnet.binance.vision/stream?streams=btcusdt@trade/dogeusdt@trade"
+        );
+        
+        // Combined streams wrap each event with its stream name
+        let message = r#"{"stream":"btcusdt@trade","data":{"e":"trade","E":1672515782136,"s":"BTCUSDT","t":1,"p":"16569.01","q":"0.5","b":1,"a":2,"T":1672515782134,"m":false}}"#;
+        match manager.protocol.parse(message, Exchange::Binance).unwrap() {
+            Some(UniversalMarketData::Trade(trade)) => assert_eq!(trade.side, Side::Buy),
+            other => panic!("expected a trade, got {:?}", other),
+        }
+    }
 }
\ No newline at end of file
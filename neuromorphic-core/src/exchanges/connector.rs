@@ -107,6 +107,9 @@ pub struct UniversalOrder {
 }
 
 impl UniversalOrder {
+    /// Metadata key holding the average fill price
+    pub const AVG_PRICE_KEY: &'static str = "avg_price";
+
     pub fn is_active(&self) -> bool {
         matches!(self.status, OrderStatus::New | OrderStatus::PartiallyFilled | OrderStatus::PendingCancel)
     }
@@ -122,6 +125,11 @@ impl UniversalOrder {
             0.0
         }
     }
+
+    /// Average fill price, when the connector reports one
+    pub fn average_price(&self) -> Option<f64> {
+        self.metadata.get(Self::AVG_PRICE_KEY).and_then(|p| p.parse().ok())
+    }
 }
 
 /// Order request for placing new orders
@@ -302,6 +310,7 @@ pub struct UniversalTicker {
 pub struct UniversalKline {
     pub symbol: Symbol,
     pub exchange: Exchange,
+    pub interval: KlineInterval,
     pub open_time: DateTime<Utc>,
     pub close_time: DateTime<Utc>,
     pub open: f64,
@@ -316,7 +325,7 @@ pub struct UniversalKline {
 }
 
 /// Kline interval enumeration
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum KlineInterval {
     OneSecond,
     OneMinute,
@@ -336,6 +345,53 @@ pub enum KlineInterval {
     OneMonth,
 }
 
+impl KlineInterval {
+    pub const ALL: [KlineInterval; 16] = [
+        Self::OneSecond, Self::OneMinute, Self::ThreeMinutes, Self::FiveMinutes,
+        Self::FifteenMinutes, Self::ThirtyMinutes, Self::OneHour, Self::TwoHours,
+        Self::FourHours, Self::SixHours, Self::EightHours, Self::TwelveHours,
+        Self::OneDay, Self::ThreeDays, Self::OneWeek, Self::OneMonth,
+    ];
+
+    /// Fixed length of one candle; months vary so they have none
+    pub fn duration_ms(&self) -> Option<i64> {
+        const MINUTE: i64 = 60_000;
+        let ms = match self {
+            Self::OneSecond => 1_000,
+            Self::OneMinute => MINUTE,
+            Self::ThreeMinutes => 3 * MINUTE,
+            Self::FiveMinutes => 5 * MINUTE,
+            Self::FifteenMinutes => 15 * MINUTE,
+            Self::ThirtyMinutes => 30 * MINUTE,
+            Self::OneHour => 60 * MINUTE,
+            Self::TwoHours => 120 * MINUTE,
+            Self::FourHours => 240 * MINUTE,
+            Self::SixHours => 360 * MINUTE,
+            Self::EightHours => 480 * MINUTE,
+            Self::TwelveHours => 720 * MINUTE,
+            Self::OneDay => 1_440 * MINUTE,
+            Self::ThreeDays => 3 * 1_440 * MINUTE,
+            Self::OneWeek => 7 * 1_440 * MINUTE,
+            Self::OneMonth => return None,
+        };
+        Some(ms)
+    }
+}
+
+impl std::str::FromStr for KlineInterval {
+    type Err = ExchangeError;
+
+    /// Parse the exchange notation, e.g. "1m" or "4h"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|interval| interval.to_string() == s)
+            .ok_or_else(|| ExchangeError::InvalidRequest {
+                details: format!("Unknown kline interval: {}", s),
+            })
+    }
+}
+
 impl fmt::Display for KlineInterval {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
@@ -492,7 +548,7 @@ pub struct RateLimit {
 }
 
 /// Rate limit types
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RateLimitType {
     RequestWeight,
     Orders,
@@ -500,13 +556,25 @@ pub enum RateLimitType {
 }
 
 /// Rate limit intervals
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RateLimitInterval {
     Second,
     Minute,
     Day,
 }
 
+impl RateLimit {
+    /// Length of the window the limit applies to
+    pub fn window(&self) -> std::time::Duration {
+        let unit = match self.interval {
+            RateLimitInterval::Second => 1,
+            RateLimitInterval::Minute => 60,
+            RateLimitInterval::Day => 86_400,
+        };
+        std::time::Duration::from_secs(unit * self.interval_num.max(1) as u64)
+    }
+}
+
 /// Conversion trait for exchange-specific to universal data structures
 pub trait ToUniversal<T> {
     fn to_universal(&self) -> T;
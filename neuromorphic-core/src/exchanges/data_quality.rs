@@ -0,0 +1,267 @@
+//! Sanity checks on streamed market data
+//!
+//! Ticks that fail a check are quarantined instead of being forwarded, so a
+//! single bad print can't move marks, trigger stops or skew indicators.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::Instant;
+
+use super::connector::KlineInterval;
+use super::types::{Symbol, UniversalMarketData};
+
+/// Data quality thresholds
+#[derive(Debug, Clone)]
+pub struct DataQualityConfig {
+    pub enabled: bool,
+    /// Largest move from the last accepted price, in percent
+    pub max_jump_pct: Option<f64>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    /// Reject ticks older than the last accepted one for the same stream
+    pub require_monotonic_timestamps: bool,
+    /// After this many consecutive jump rejections the new level is accepted,
+    /// so a genuine gap doesn't blank the symbol forever
+    pub jump_reset_after: u32,
+    /// Quarantined ticks kept for inspection
+    pub quarantine_capacity: usize,
+}
+
+impl Default for DataQualityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_jump_pct: Some(20.0),
+            min_price: None,
+            max_price: None,
+            require_monotonic_timestamps: true,
+            jump_reset_after: 5,
+            quarantine_capacity: 100,
+        }
+    }
+}
+
+/// Why a tick was quarantined
+#[derive(Debug, Clone, PartialEq)]
+pub enum TickIssue {
+    InvalidPrice(f64),
+    NegativeSize(f64),
+    PriceOutOfRange(f64),
+    PriceJump { last: f64, price: f64, pct: f64 },
+    StaleTimestamp { last: u64, received: u64 },
+}
+
+impl TickIssue {
+    /// Short name used as the metrics key
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidPrice(_) => "invalid_price",
+            Self::NegativeSize(_) => "negative_size",
+            Self::PriceOutOfRange(_) => "price_out_of_range",
+            Self::PriceJump { .. } => "price_jump",
+            Self::StaleTimestamp { .. } => "stale_timestamp",
+        }
+    }
+}
+
+impl fmt::Display for TickIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPrice(price) => write!(f, "invalid price {}", price),
+            Self::NegativeSize(size) => write!(f, "negative size {}", size),
+            Self::PriceOutOfRange(price) => write!(f, "price {} outside configured range", price),
+            Self::PriceJump { last, price, pct } => {
+                write!(f, "price jumped {:.2}% from {} to {}", pct, last, price)
+            }
+            Self::StaleTimestamp { last, received } => {
+                write!(f, "timestamp {} is older than last accepted {}", received, last)
+            }
+        }
+    }
+}
+
+/// A rejected tick and the reason it was held back
+#[derive(Debug, Clone)]
+pub struct QuarantinedTick {
+    pub data: UniversalMarketData,
+    pub issue: TickIssue,
+    pub quarantined_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct SymbolState {
+    last_price: Option<f64>,
+    consecutive_jumps: u32,
+}
+
+/// Per-symbol filter over the market data stream
+#[derive(Debug)]
+pub struct DataQualityFilter {
+    config: DataQualityConfig,
+    symbols: HashMap<Symbol, SymbolState>,
+    last_timestamps: HashMap<(Symbol, &'static str, Option<KlineInterval>), u64>, // Per symbol and stream
+    quarantine: VecDeque<QuarantinedTick>,
+}
+
+impl DataQualityFilter {
+    pub fn new(config: DataQualityConfig) -> Self {
+        Self {
+            config,
+            symbols: HashMap::new(),
+            last_timestamps: HashMap::new(),
+            quarantine: VecDeque::new(),
+        }
+    }
+
+    /// Check a tick; accepted ticks update the reference price and timestamp,
+    /// rejected ones are quarantined
+    pub fn check(&mut self, data: &UniversalMarketData) -> Result<(), TickIssue> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let result = self.validate(data);
+        if let Err(issue) = &result {
+            if self.quarantine.len() >= self.config.quarantine_capacity {
+                self.quarantine.pop_front();
+            }
+            if self.config.quarantine_capacity > 0 {
+                self.quarantine.push_back(QuarantinedTick {
+                    data: data.clone(),
+                    issue: issue.clone(),
+                    quarantined_at: Instant::now(),
+                });
+            }
+        }
+        result
+    }
+
+    /// Most recent quarantined ticks, oldest first
+    pub fn quarantined(&self) -> Vec<QuarantinedTick> {
+        self.quarantine.iter().cloned().collect()
+    }
+
+    fn validate(&mut self, data: &UniversalMarketData) -> Result<(), TickIssue> {
+        let mut interval = None;
+        let (symbol, kind, price, sizes, timestamp) = match data {
+            UniversalMarketData::Trade(t) => {
+                check_price(t.price)?;
+                (&t.symbol, "trade", Some(t.price), vec![t.quantity], t.timestamp_exchange)
+            }
+            UniversalMarketData::Quote(q) => {
+                check_price(q.bid_price)?;
+                check_price(q.ask_price)?;
+                let mid = (q.bid_price + q.ask_price) / 2.0;
+                (&q.symbol, "quote", Some(mid), vec![q.bid_size, q.ask_size], q.timestamp_exchange)
+            }
+            UniversalMarketData::OrderBook(b) => {
+                for &(price, _) in b.bids.iter().chain(&b.asks) {
+                    check_price(price)?;
+                }
+                let mid = match (b.bids.first(), b.asks.first()) {
+                    (Some(bid), Some(ask)) => Some((bid.0 + ask.0) / 2.0),
+                    _ => None,
+                };
+                let sizes = b.bids.iter().chain(&b.asks).map(|level| level.1).collect();
+                (&b.symbol, "book", mid, sizes, b.timestamp_exchange)
+            }
+            UniversalMarketData::Kline(k) => {
+                for price in [k.open, k.high, k.low, k.close] {
+                    check_price(price)?;
+                }
+                interval = Some(k.interval);
+                let timestamp = k.open_time.timestamp_millis() as u64;
+                (&k.symbol, "kline", Some(k.close), vec![k.volume], timestamp)
+            }
+        };
+
+        if let Some(&size) = sizes.iter().find(|s| !s.is_finite() || **s < 0.0) {
+            return Err(TickIssue::NegativeSize(size));
+        }
+
+        if let Some(price) = price {
+            let below = self.config.min_price.is_some_and(|min| price < min);
+            let above = self.config.max_price.is_some_and(|max| price > max);
+            if below || above {
+                return Err(TickIssue::PriceOutOfRange(price));
+            }
+        }
+
+        let key = (symbol.clone(), kind, interval);
+        if self.config.require_monotonic_timestamps {
+            if let Some(&last) = self.last_timestamps.get(&key) {
+                if timestamp < last {
+                    return Err(TickIssue::StaleTimestamp { last, received: timestamp });
+                }
+            }
+        }
+
+        if let Some(price) = price {
+            let state = self.symbols.entry(symbol.clone()).or_default();
+            if let (Some(max_jump), Some(last)) = (self.config.max_jump_pct, state.last_price) {
+                let pct = (price - last).abs() / last * 100.0;
+                if pct > max_jump {
+                    state.consecutive_jumps += 1;
+                    if state.consecutive_jumps < self.config.jump_reset_after {
+                        return Err(TickIssue::PriceJump { last, price, pct });
+                    }
+                }
+            }
+            state.last_price = Some(price);
+            state.consecutive_jumps = 0;
+        }
+
+        self.last_timestamps.insert(key, timestamp);
+        Ok(())
+    }
+}
+
+fn check_price(price: f64) -> Result<(), TickIssue> {
+    if price.is_finite() && price > 0.0 {
+        Ok(())
+    } else {
+        Err(TickIssue::InvalidPrice(price))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::types::{Exchange, Side, UniversalTrade};
+
+    fn trade(price: f64, quantity: f64, timestamp: u64) -> UniversalMarketData {
+        UniversalMarketData::Trade(UniversalTrade {
+            exchange: Exchange::Binance,
+            symbol: Symbol::new("BTCUSDT"),
+            price,
+            quantity,
+            side: Side::Buy,
+            timestamp_exchange: timestamp,
+            timestamp_local: timestamp,
+            trade_id: timestamp.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_bad_ticks_quarantined() {
+        let mut filter = DataQualityFilter::new(DataQualityConfig {
+            max_jump_pct: Some(10.0),
+            jump_reset_after: 3,
+            ..Default::default()
+        });
+
+        assert!(filter.check(&trade(50_000.0, 0.1, 1_000)).is_ok());
+        assert_eq!(filter.check(&trade(5_000_000.0, 0.1, 1_001)).unwrap_err().kind(), "price_jump");
+        assert_eq!(filter.check(&trade(50_100.0, -0.1, 1_002)).unwrap_err().kind(), "negative_size");
+        assert_eq!(filter.check(&trade(50_100.0, 0.1, 999)).unwrap_err().kind(), "stale_timestamp");
+        assert_eq!(filter.check(&trade(f64::NAN, 0.1, 1_003)).unwrap_err().kind(), "invalid_price");
+        assert!(filter.check(&trade(50_100.0, 0.1, 1_003)).is_ok());
+        assert_eq!(filter.quarantined().len(), 4);
+
+        // A level that persists is accepted after repeated rejections
+        assert!(filter.check(&trade(40_000.0, 0.1, 1_004)).is_err());
+        assert!(filter.check(&trade(40_000.0, 0.1, 1_005)).is_err());
+        assert!(filter.check(&trade(40_000.0, 0.1, 1_006)).is_ok());
+        assert!(filter.check(&trade(40_100.0, 0.1, 1_007)).is_ok());
+    }
+}
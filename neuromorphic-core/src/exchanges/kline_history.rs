@@ -0,0 +1,375 @@
+//! Closed kline history with gap detection and REST backfill
+//!
+//! Candles that close while the WebSocket is down never arrive on the stream.
+//! The backfiller notices the jump in open times (or a reconnect) and fetches
+//! the missing candles over REST, publishing them in order ahead of the live
+//! one so indicators built on the history see an unbroken series.
+
+use super::{
+    ConnectionStatus, ExchangeConnector, KlineInterval, Symbol, UniversalKline, UniversalMarketData,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Most klines the REST API returns per request
+const MAX_KLINES_PER_REQUEST: u32 = 1000;
+
+/// Historical klines for backfilling
+#[async_trait]
+pub trait KlineSource: Send + Sync {
+    /// Klines with open times in `[start, end]`, oldest first
+    async fn fetch_klines(
+        &self,
+        symbol: &Symbol,
+        interval: KlineInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<UniversalKline>>;
+}
+
+#[async_trait]
+impl<C: ExchangeConnector> KlineSource for C {
+    async fn fetch_klines(
+        &self,
+        symbol: &Symbol,
+        interval: KlineInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<UniversalKline>> {
+        Ok(self.get_klines(symbol, interval, Some(start), Some(end), Some(MAX_KLINES_PER_REQUEST)).await?)
+    }
+}
+
+/// Missing candles in a series, by open time (inclusive)
+#[derive(Clone, Debug, PartialEq)]
+pub struct KlineGap {
+    pub symbol: Symbol,
+    pub interval: KlineInterval,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl KlineGap {
+    /// Number of candles missing
+    pub fn len(&self) -> i64 {
+        match self.interval.duration_ms() {
+            Some(ms) => (self.to - self.from).num_milliseconds() / ms + 1,
+            None => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() <= 0
+    }
+}
+
+/// Closed klines per symbol and interval, keyed by open time
+pub struct KlineHistory {
+    series: DashMap<(Symbol, KlineInterval), BTreeMap<i64, UniversalKline>>,
+    capacity: usize, // Per series; oldest candles are dropped first
+}
+
+impl KlineHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            series: DashMap::new(),
+            capacity,
+        }
+    }
+
+    /// Insert a closed kline. Returns the gap between it and the latest
+    /// candle already held, if any candles are missing in between.
+    pub fn insert(&self, kline: UniversalKline) -> Option<KlineGap> {
+        let key = (kline.symbol.clone(), kline.interval);
+        let open_ms = kline.open_time.timestamp_millis();
+        let mut series = self.series.entry(key).or_default();
+
+        let gap = match (series.keys().next_back(), kline.interval.duration_ms()) {
+            (Some(&latest), Some(step)) if open_ms > latest + step => Some(KlineGap {
+                symbol: kline.symbol.clone(),
+                interval: kline.interval,
+                from: kline.open_time - Duration::milliseconds(open_ms - latest - step),
+                to: kline.open_time - Duration::milliseconds(step),
+            }),
+            _ => None,
+        };
+
+        series.insert(open_ms, kline);
+        while series.len() > self.capacity {
+            series.pop_first();
+        }
+        gap
+    }
+
+    pub fn contains(&self, symbol: &Symbol, interval: KlineInterval, open_time: DateTime<Utc>) -> bool {
+        self.series
+            .get(&(symbol.clone(), interval))
+            .is_some_and(|series| series.contains_key(&open_time.timestamp_millis()))
+    }
+
+    pub fn latest(&self, symbol: &Symbol, interval: KlineInterval) -> Option<UniversalKline> {
+        self.series
+            .get(&(symbol.clone(), interval))
+            .and_then(|series| series.values().next_back().cloned())
+    }
+
+    /// All klines held for a series, oldest first
+    pub fn klines(&self, symbol: &Symbol, interval: KlineInterval) -> Vec<UniversalKline> {
+        self.series
+            .get(&(symbol.clone(), interval))
+            .map(|series| series.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Series held, e.g. to check all of them after a reconnect
+    pub fn series(&self) -> Vec<(Symbol, KlineInterval)> {
+        self.series.iter().map(|entry| entry.key().clone()).collect()
+    }
+}
+
+/// Backfill counters
+#[derive(Clone, Debug, Default)]
+pub struct BackfillStatistics {
+    pub gaps_detected: u64,
+    pub klines_backfilled: u64,
+    pub backfill_errors: u64,
+}
+
+/// Feeds streamed klines into the history, backfilling gaps from REST
+pub struct KlineBackfiller {
+    history: Arc<KlineHistory>,
+    source: Arc<dyn KlineSource>,
+    sender: broadcast::Sender<UniversalKline>,
+    gaps_detected: AtomicU64,
+    klines_backfilled: AtomicU64,
+    backfill_errors: AtomicU64,
+}
+
+impl KlineBackfiller {
+    pub fn new(history: Arc<KlineHistory>, source: Arc<dyn KlineSource>) -> Self {
+        let (sender, _) = broadcast::channel(1000);
+        Self {
+            history,
+            source,
+            sender,
+            gaps_detected: AtomicU64::new(0),
+            klines_backfilled: AtomicU64::new(0),
+            backfill_errors: AtomicU64::new(0),
+        }
+    }
+
+    pub fn history(&self) -> &Arc<KlineHistory> {
+        &self.history
+    }
+
+    /// Closed klines in open-time order, backfilled ones included
+    pub fn subscribe(&self) -> broadcast::Receiver<UniversalKline> {
+        self.sender.subscribe()
+    }
+
+    pub fn statistics(&self) -> BackfillStatistics {
+        BackfillStatistics {
+            gaps_detected: self.gaps_detected.load(Ordering::Relaxed),
+            klines_backfilled: self.klines_backfilled.load(Ordering::Relaxed),
+            backfill_errors: self.backfill_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Record a streamed kline. Missing candles before it are fetched and
+    /// published first. Returns everything published, oldest first.
+    pub async fn on_kline(&self, kline: UniversalKline) -> Vec<UniversalKline> {
+        if self.history.contains(&kline.symbol, kline.interval, kline.open_time) {
+            return Vec::new(); // Already backfilled
+        }
+
+        let mut published = Vec::new();
+        if let Some(gap) = self.history.insert(kline.clone()) {
+            warn!(
+                symbol = %gap.symbol,
+                interval = %gap.interval,
+                missing = gap.len(),
+                "Kline gap detected"
+            );
+            self.gaps_detected.fetch_add(1, Ordering::Relaxed);
+            published = self.fill(&gap.symbol, gap.interval, gap.from, gap.to).await;
+        }
+
+        let _ = self.sender.send(kline.clone());
+        published.push(kline);
+        published
+    }
+
+    /// Fetch candles that closed since the latest one held for every series,
+    /// e.g. after the stream reconnects
+    pub async fn backfill_all(&self, now: DateTime<Utc>) -> Vec<UniversalKline> {
+        let mut published = Vec::new();
+        for (symbol, interval) in self.history.series() {
+            let (Some(latest), Some(step)) = (self.history.latest(&symbol, interval), interval.duration_ms()) else {
+                continue;
+            };
+            let from = latest.open_time + Duration::milliseconds(step);
+            // Only candles that have closed by now
+            let to = now - Duration::milliseconds(step);
+            if from <= to {
+                published.extend(self.fill(&symbol, interval, from, to).await);
+            }
+        }
+        published
+    }
+
+    /// Fetch and publish missing candles with open times in `[from, to]`
+    async fn fill(
+        &self,
+        symbol: &Symbol,
+        interval: KlineInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<UniversalKline> {
+        let mut published = Vec::new();
+        let mut start = from;
+
+        // The REST API pages, so keep going until the range is covered
+        while start <= to {
+            let klines = match self.source.fetch_klines(symbol, interval, start, to).await {
+                Ok(klines) => klines,
+                Err(e) => {
+                    warn!(symbol = %symbol, interval = %interval, error = %e, "Kline backfill failed");
+                    self.backfill_errors.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+            };
+            let Some(last_open) = klines.last().map(|k| k.open_time) else {
+                break;
+            };
+
+            for kline in klines {
+                if kline.open_time < from || kline.open_time > to
+                    || self.history.contains(symbol, interval, kline.open_time)
+                {
+                    continue;
+                }
+                self.history.insert(kline.clone());
+                let _ = self.sender.send(kline.clone());
+                published.push(kline);
+            }
+
+            if last_open < start {
+                break;
+            }
+            start = last_open + Duration::milliseconds(interval.duration_ms().unwrap_or(1));
+        }
+
+        if !published.is_empty() {
+            info!(symbol = %symbol, interval = %interval, count = published.len(), "Klines backfilled");
+            self.klines_backfilled.fetch_add(published.len() as u64, Ordering::Relaxed);
+        }
+        published
+    }
+
+    /// Consume a market data stream, backfilling on gaps and reconnects
+    pub fn spawn(
+        self: Arc<Self>,
+        mut data: broadcast::Receiver<UniversalMarketData>,
+        mut status: broadcast::Receiver<ConnectionStatus>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    received = data.recv() => match received {
+                        Ok(UniversalMarketData::Kline(kline)) => {
+                            self.on_kline(kline).await;
+                        }
+                        Ok(_) => {}
+                        // Dropped klines show up as gaps on the next candle
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    changed = status.recv() => match changed {
+                        Ok(ConnectionStatus::Connected) => {
+                            self.backfill_all(Utc::now()).await;
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+
+    fn kline(minute: i64) -> UniversalKline {
+        let open_time = DateTime::<Utc>::from_timestamp_millis(minute * 60_000).unwrap();
+        UniversalKline {
+            symbol: Symbol::new("BTCUSDT"),
+            exchange: Exchange::Binance,
+            interval: KlineInterval::OneMinute,
+            open_time,
+            close_time: open_time + Duration::milliseconds(59_999),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0 + minute as f64,
+            volume: 1.0,
+            quote_volume: 100.0,
+            trades_count: 10,
+            taker_buy_volume: 0.5,
+            taker_buy_quote_volume: 50.0,
+        }
+    }
+
+    /// Serves every minute up to a limit, two candles per request
+    struct MinuteSource;
+
+    #[async_trait]
+    impl KlineSource for MinuteSource {
+        async fn fetch_klines(
+            &self,
+            _symbol: &Symbol,
+            _interval: KlineInterval,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+        ) -> Result<Vec<UniversalKline>> {
+            let first = start.timestamp_millis() / 60_000;
+            let last = end.timestamp_millis() / 60_000;
+            Ok((first..=last).take(2).map(kline).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gap_backfilled_in_order() {
+        let backfiller = KlineBackfiller::new(Arc::new(KlineHistory::new(100)), Arc::new(MinuteSource));
+        let mut rx = backfiller.subscribe();
+
+        backfiller.on_kline(kline(1)).await;
+        let published = backfiller.on_kline(kline(6)).await;
+
+        let minutes: Vec<f64> = published.iter().map(|k| k.close - 100.0).collect();
+        assert_eq!(minutes, vec![2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(backfiller.history().klines(&Symbol::new("BTCUSDT"), KlineInterval::OneMinute).len(), 6);
+        for minute in 1..=6 {
+            assert_eq!(rx.recv().await.unwrap().close, 100.0 + minute as f64);
+        }
+
+        // After a reconnect, candles closed in the meantime are fetched up front
+        let now = DateTime::<Utc>::from_timestamp_millis(9 * 60_000 + 30_000).unwrap();
+        let published = backfiller.backfill_all(now).await;
+        assert_eq!(published.len(), 2); // Minutes 7 and 8; 9 is still open
+        assert!(backfiller.on_kline(kline(8)).await.is_empty());
+
+        let stats = backfiller.statistics();
+        assert_eq!((stats.gaps_detected, stats.klines_backfilled), (1, 6));
+    }
+}
@@ -0,0 +1,246 @@
+//! Stream latency and exchange clock skew
+//!
+//! Latency is the local receive time minus the exchange's event time, so it
+//! is only as good as the agreement between the two clocks. A local clock
+//! running behind the exchange shows up as negative latency; the offset is
+//! either calibrated from the exchange's server time or estimated from the
+//! fastest recent messages, and applied before the samples are aggregated.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use utoipa::ToSchema;
+
+/// Drift warning levels
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum DriftWarning {
+    Minor { offset_ms: f64 },
+    Major { offset_ms: f64 },
+    Critical { offset_ms: f64 },
+}
+
+/// Drift detector
+pub struct DriftDetector {
+    recent_offsets: RwLock<VecDeque<i64>>,
+    max_samples: usize,
+    warning_threshold_ms: f64,
+    critical_threshold_ms: f64,
+}
+
+impl DriftDetector {
+    pub fn new() -> Self {
+        Self {
+            recent_offsets: RwLock::new(VecDeque::with_capacity(100)),
+            max_samples: 100,
+            warning_threshold_ms: 5.0,
+            critical_threshold_ms: 10.0,
+        }
+    }
+
+    pub fn add_offset(&self, offset_us: i64) {
+        let mut offsets = self.recent_offsets.write();
+        if offsets.len() >= self.max_samples {
+            offsets.pop_front();
+        }
+        offsets.push_back(offset_us);
+    }
+
+    pub fn detect_drift(&self) -> Option<DriftWarning> {
+        let offsets = self.recent_offsets.read();
+        if offsets.len() < 10 {
+            return None;
+        }
+
+        // Calculate variance
+        let mean: f64 = offsets.iter().map(|&x| x as f64).sum::<f64>() / offsets.len() as f64;
+        let variance: f64 = offsets.iter()
+            .map(|&x| {
+                let diff = x as f64 - mean;
+                diff * diff
+            })
+            .sum::<f64>() / offsets.len() as f64;
+
+        let std_dev = variance.sqrt();
+        let drift_ms = std_dev / 1000.0;
+
+        if drift_ms > self.critical_threshold_ms {
+            Some(DriftWarning::Critical { offset_ms: drift_ms })
+        } else if drift_ms > self.warning_threshold_ms {
+            Some(DriftWarning::Major { offset_ms: drift_ms })
+        } else if drift_ms > 1.0 {
+            Some(DriftWarning::Minor { offset_ms: drift_ms })
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for DriftDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LatencyConfig {
+    /// Weight of the newest sample in the moving average
+    pub ewma_alpha: f64,
+    /// Samples kept for the percentiles
+    pub window: usize,
+    /// Samples per skew estimate; the fastest message of each block bounds the offset
+    pub skew_block: usize,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            ewma_alpha: 0.05,
+            window: 1000,
+            skew_block: 100,
+        }
+    }
+}
+
+/// Latency summary, in milliseconds after skew correction
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct LatencyStatistics {
+    pub samples: u64,
+    pub ewma_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    /// How far the exchange clock is ahead of the local one
+    pub clock_offset_ms: f64,
+    pub clock_calibrated: bool, // Offset measured against server time rather than estimated
+    /// Instability of the skew estimates
+    pub clock_drift: Option<DriftWarning>,
+}
+
+pub struct LatencyTracker {
+    config: LatencyConfig,
+    samples: u64,
+    ewma_ms: Option<f64>,
+    window: VecDeque<f64>,
+    block_min_ms: f64,
+    block_len: usize,
+    estimated_offset_ms: f64,
+    calibrated_offset_ms: Option<f64>,
+    drift: DriftDetector,
+}
+
+impl LatencyTracker {
+    pub fn new(config: LatencyConfig) -> Self {
+        Self {
+            window: VecDeque::with_capacity(config.window),
+            config,
+            samples: 0,
+            ewma_ms: None,
+            block_min_ms: f64::INFINITY,
+            block_len: 0,
+            estimated_offset_ms: 0.0,
+            calibrated_offset_ms: None,
+            drift: DriftDetector::new(),
+        }
+    }
+
+    /// Record a message and return its corrected latency. Both times are
+    /// milliseconds since the Unix epoch.
+    pub fn record(&mut self, exchange_time_ms: u64, local_time_ms: u64) -> f64 {
+        let raw_ms = local_time_ms as f64 - exchange_time_ms as f64;
+
+        self.block_min_ms = self.block_min_ms.min(raw_ms);
+        self.block_len += 1;
+        if self.block_len >= self.config.skew_block.max(1) {
+            // Latency can't be negative, so a negative floor is the local clock lagging
+            self.estimated_offset_ms = (-self.block_min_ms).max(0.0);
+            self.drift.add_offset((self.block_min_ms * 1000.0) as i64);
+            self.block_min_ms = f64::INFINITY;
+            self.block_len = 0;
+        }
+
+        let latency_ms = (raw_ms + self.clock_offset_ms()).max(0.0);
+        self.samples += 1;
+        self.ewma_ms = Some(match self.ewma_ms {
+            Some(ewma) => ewma + self.config.ewma_alpha * (latency_ms - ewma),
+            None => latency_ms,
+        });
+        if self.window.len() >= self.config.window {
+            self.window.pop_front();
+        }
+        self.window.push_back(latency_ms);
+        latency_ms
+    }
+
+    /// Use a measured offset, e.g. exchange server time minus the local time
+    /// halfway through the request
+    pub fn set_clock_offset(&mut self, offset_ms: f64) {
+        self.calibrated_offset_ms = Some(offset_ms);
+    }
+
+    pub fn clock_offset_ms(&self) -> f64 {
+        self.calibrated_offset_ms.unwrap_or(self.estimated_offset_ms)
+    }
+
+    pub fn statistics(&self) -> LatencyStatistics {
+        let mut sorted: Vec<f64> = self.window.iter().copied().collect();
+        sorted.sort_unstable_by(f64::total_cmp);
+        let percentile = |p: f64| match sorted.len() {
+            0 => 0.0,
+            n => sorted[((n as f64 * p).ceil() as usize).clamp(1, n) - 1],
+        };
+
+        LatencyStatistics {
+            samples: self.samples,
+            ewma_ms: self.ewma_ms.unwrap_or(0.0),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            max_ms: sorted.last().copied().unwrap_or(0.0),
+            clock_offset_ms: self.clock_offset_ms(),
+            clock_calibrated: self.calibrated_offset_ms.is_some(),
+            clock_drift: self.drift.detect_drift(),
+        }
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new(LatencyConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles_and_skew_correction() {
+        let mut tracker = LatencyTracker::new(LatencyConfig {
+            ewma_alpha: 0.5,
+            window: 100,
+            skew_block: 50,
+        });
+
+        // 1..=100ms with clocks in agreement
+        for latency in 1..=100u64 {
+            tracker.record(1_000_000, 1_000_000 + latency);
+        }
+        let stats = tracker.statistics();
+        assert_eq!((stats.p95_ms, stats.p99_ms, stats.max_ms), (95.0, 99.0, 100.0));
+        assert_eq!(stats.clock_offset_ms, 0.0);
+        assert!(stats.ewma_ms > 95.0 && stats.ewma_ms < 100.0);
+
+        // The local clock falls 30ms behind: once a block has been seen,
+        // latencies are shifted back up to a zero floor
+        for n in 0..50u64 {
+            tracker.record(2_000_000, 2_000_000 + n % 10 - 30 + 5);
+        }
+        assert_eq!(tracker.statistics().clock_offset_ms, 25.0);
+        assert_eq!(tracker.record(3_000_000, 3_000_000 - 15), 10.0);
+
+        // A calibrated offset takes precedence over the estimate
+        tracker.set_clock_offset(40.0);
+        let stats = tracker.statistics();
+        assert!(stats.clock_calibrated);
+        assert_eq!(tracker.record(3_000_000, 3_000_000 - 15), 25.0);
+    }
+}
@@ -7,7 +7,13 @@ pub mod orderbook;
 pub mod book_manager;
 pub mod connector;
 pub mod websocket;
+pub mod data_quality;
+pub mod latency;
+pub mod kline_history;
 pub mod binance_websocket;
+pub mod binance_rest;
+pub mod binance_user_data;
+pub mod rate_limit;
 
 pub use binance::{BinanceWebSocket, MultiSymbolTracker};
 pub use types::{
@@ -30,11 +36,36 @@ pub use connector::{
 // Re-export WebSocket streaming interface
 pub use websocket::{
     StreamManager, WebSocketManager, WebSocketConfig,
-    StreamType, StreamSubscription, ConnectionStatus, StreamMetrics,
+    StreamType, StreamSubscription, ConnectionStatus, StreamMetrics, ReconnectPolicy, StreamProtocol,
+    DefaultStreamProtocol,
 };
 
+// Re-export market data sanity checks
+pub use data_quality::{DataQualityConfig, DataQualityFilter, QuarantinedTick, TickIssue};
+
+// Re-export stream latency and clock skew tracking
+pub use latency::{LatencyConfig, LatencyStatistics, LatencyTracker, DriftDetector, DriftWarning};
+
+// Re-export kline gap detection and backfill
+pub use kline_history::{KlineHistory, KlineBackfiller, KlineGap, KlineSource, BackfillStatistics};
+
+// Re-export REST rate limiting
+pub use rate_limit::{RateLimiter, RateLimiterConfig, RateLimiterStatistics, ThrottleStatistics};
+
 // Re-export Binance WebSocket implementation
-pub use binance_websocket::BinanceWebSocketManager;
+pub use binance_websocket::{
+    BinanceWebSocketManager, BinanceStreamProtocol, BINANCE_STREAM_URL, BINANCE_TESTNET_STREAM_URL,
+    BINANCE_MAX_STREAMS_PER_CONNECTION,
+};
+
+// Re-export Binance REST trading implementation
+pub use binance_rest::{BinanceRestConnector, BinanceRestConfig, BINANCE_TESTNET_URL};
+
+// Re-export the Binance user data stream
+pub use binance_user_data::{
+    BinanceUserDataStream, BinanceUserDataConfig, UserDataEvent, OrderUpdate, ExecutionType,
+    BINANCE_TESTNET_USER_DATA_URL,
+};
 
 use async_trait::async_trait;
 use anyhow::Result;
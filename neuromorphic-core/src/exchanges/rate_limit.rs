@@ -0,0 +1,341 @@
+//! Client-side rate limiting for REST connectors
+//!
+//! Requests are charged against token buckets built from the exchange's
+//! published limits, so a burst waits locally instead of earning a 429 or an
+//! IP ban. If the exchange still answers with a rate limit error, every
+//! request to that exchange pauses until the backoff has passed.
+
+use super::connector::{ExchangeError, ExchangeResult, RateLimit, RateLimitType};
+use super::types::Exchange;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Retry and backoff settings
+#[derive(Debug, Clone)]
+pub struct RateLimiterConfig {
+    /// Retries after a rate limit error before it is returned to the caller
+    pub max_retries: u32,
+    /// Pause when the exchange sends no Retry-After, doubled for each
+    /// consecutive rate limit error
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RateLimiterConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Throttling counters for one exchange and limit type
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThrottleStatistics {
+    pub requests: u64,
+    pub throttled: u64, // Requests that waited for tokens
+    pub wait_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RateLimiterStatistics {
+    /// Keyed by "exchange/limit type", e.g. "Binance/RequestWeight"
+    pub limits: HashMap<String, ThrottleStatistics>,
+    pub rate_limit_errors: u64,
+    pub backoff_ms: u64, // Time spent waiting out rate limit errors
+}
+
+struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit) -> Self {
+        let capacity = limit.limit.max(1) as f64;
+        Self {
+            capacity,
+            refill_per_sec: capacity / limit.window().as_secs_f64(),
+            tokens: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time until `cost` tokens are available. A cost above the capacity only
+    /// waits for a full bucket, otherwise it could never run.
+    fn wait_for(&self, cost: f64) -> Duration {
+        let missing = cost.min(self.capacity) - self.tokens;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.refill_per_sec)
+        }
+    }
+}
+
+#[derive(Default)]
+struct LimitState {
+    buckets: Vec<TokenBucket>, // One per interval, e.g. orders per 10s and per day
+    stats: ThrottleStatistics,
+}
+
+impl LimitState {
+    /// Take `cost` tokens from every bucket, or return how long to wait
+    fn try_take(&mut self, cost: u32) -> Duration {
+        let now = Instant::now();
+        let cost = cost as f64;
+        let mut wait = Duration::ZERO;
+        for bucket in &mut self.buckets {
+            bucket.refill(now);
+            wait = wait.max(bucket.wait_for(cost));
+        }
+        if wait.is_zero() {
+            for bucket in &mut self.buckets {
+                bucket.tokens -= cost;
+            }
+        }
+        wait
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Backoff {
+    until: Instant,
+    consecutive: u32,
+}
+
+/// Token buckets keyed by exchange and limit type
+pub struct RateLimiter {
+    config: RateLimiterConfig,
+    limits: DashMap<(Exchange, RateLimitType), LimitState>,
+    backoff: DashMap<Exchange, Backoff>,
+    rate_limit_errors: DashMap<Exchange, (u64, Duration)>, // Errors and total backoff
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimiterConfig) -> Self {
+        Self {
+            config,
+            limits: DashMap::new(),
+            backoff: DashMap::new(),
+            rate_limit_errors: DashMap::new(),
+        }
+    }
+
+    pub fn with_limits(self, exchange: Exchange, limits: &[RateLimit]) -> Self {
+        self.configure(exchange, limits);
+        self
+    }
+
+    /// Replace an exchange's limits, e.g. with the ones from `get_exchange_info`.
+    /// Limit types not listed are left alone; counters are kept.
+    pub fn configure(&self, exchange: Exchange, limits: &[RateLimit]) {
+        let mut buckets: HashMap<RateLimitType, Vec<TokenBucket>> = HashMap::new();
+        for limit in limits {
+            buckets.entry(limit.rate_type).or_default().push(TokenBucket::new(limit));
+        }
+        for (rate_type, buckets) in buckets {
+            self.limits.entry((exchange, rate_type)).or_default().buckets = buckets;
+        }
+    }
+
+    /// Wait until the request can be sent. `costs` is what it counts against
+    /// each limit type, e.g. its request weight and one order.
+    pub async fn acquire(&self, exchange: Exchange, costs: &[(RateLimitType, u32)]) {
+        loop {
+            let pause = self.backoff_remaining(exchange);
+            if pause.is_zero() {
+                break;
+            }
+            tokio::time::sleep(pause).await;
+        }
+
+        for &(rate_type, cost) in costs {
+            let mut waited = Duration::ZERO;
+            loop {
+                // The map guard must not be held across the sleep
+                let wait = match self.limits.get_mut(&(exchange, rate_type)) {
+                    Some(mut state) => {
+                        let wait = state.try_take(cost);
+                        if wait.is_zero() {
+                            state.stats.requests += 1;
+                            if !waited.is_zero() {
+                                state.stats.throttled += 1;
+                                state.stats.wait_ms += waited.as_millis() as u64;
+                            }
+                        }
+                        wait
+                    }
+                    None => Duration::ZERO, // No known limit
+                };
+                if wait.is_zero() {
+                    break;
+                }
+                tokio::time::sleep(wait).await;
+                waited += wait;
+            }
+        }
+    }
+
+    /// Pause the exchange after a rate limit error and return the pause.
+    /// `retry_after` is in seconds, as sent in the Retry-After header.
+    pub fn record_rate_limited(&self, exchange: Exchange, retry_after: Option<u64>) -> Duration {
+        let mut backoff = self.backoff.entry(exchange).or_insert(Backoff {
+            until: Instant::now(),
+            consecutive: 0,
+        });
+        backoff.consecutive += 1;
+        let pause = match retry_after {
+            Some(secs) => Duration::from_secs(secs),
+            None => self
+                .config
+                .base_backoff
+                .saturating_mul(1 << (backoff.consecutive - 1).min(16))
+                .min(self.config.max_backoff),
+        };
+        backoff.until = backoff.until.max(Instant::now() + pause);
+
+        let mut errors = self.rate_limit_errors.entry(exchange).or_default();
+        errors.0 += 1;
+        errors.1 += pause;
+        pause
+    }
+
+    /// A request went through, so the next rate limit error starts the backoff over
+    pub fn record_success(&self, exchange: Exchange) {
+        if let Some(mut backoff) = self.backoff.get_mut(&exchange) {
+            backoff.consecutive = 0;
+        }
+    }
+
+    /// Run a request under the limiter, retrying it after rate limit errors
+    pub async fn execute<T, F, Fut>(&self, exchange: Exchange, costs: &[(RateLimitType, u32)], mut request: F) -> ExchangeResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ExchangeResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.acquire(exchange, costs).await;
+            match request().await {
+                Err(ExchangeError::RateLimit { retry_after }) if attempt < self.config.max_retries => {
+                    attempt += 1;
+                    let pause = self.record_rate_limited(exchange, retry_after);
+                    warn!(?exchange, attempt, pause_ms = pause.as_millis() as u64, "Rate limited, backing off");
+                }
+                Err(ExchangeError::RateLimit { retry_after }) => {
+                    self.record_rate_limited(exchange, retry_after);
+                    return Err(ExchangeError::RateLimit { retry_after });
+                }
+                result => {
+                    if result.is_ok() {
+                        self.record_success(exchange);
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    pub fn statistics(&self) -> RateLimiterStatistics {
+        let limits = self
+            .limits
+            .iter()
+            .map(|entry| {
+                let (exchange, rate_type) = entry.key();
+                (format!("{:?}/{:?}", exchange, rate_type), entry.stats.clone())
+            })
+            .collect();
+        let (rate_limit_errors, backoff) = self
+            .rate_limit_errors
+            .iter()
+            .fold((0, Duration::ZERO), |(count, total), e| (count + e.0, total + e.1));
+        RateLimiterStatistics {
+            limits,
+            rate_limit_errors,
+            backoff_ms: backoff.as_millis() as u64,
+        }
+    }
+
+    fn backoff_remaining(&self, exchange: Exchange) -> Duration {
+        self.backoff
+            .get(&exchange)
+            .map(|b| b.until.saturating_duration_since(Instant::now()))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimiterConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::connector::RateLimitInterval;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_throttle_and_backoff() {
+        let limiter = RateLimiter::new(RateLimiterConfig {
+            max_retries: 2,
+            base_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        })
+        .with_limits(
+            Exchange::Binance,
+            &[RateLimit {
+                rate_type: RateLimitType::RequestWeight,
+                interval: RateLimitInterval::Second,
+                interval_num: 1,
+                limit: 10,
+            }],
+        );
+        let cost = [(RateLimitType::RequestWeight, 4), (RateLimitType::Orders, 1)];
+
+        // Two requests fit in the bucket, the third waits for 2 tokens to refill
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire(Exchange::Binance, &cost).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        let stats = limiter.statistics();
+        let weight = &stats.limits["Binance/RequestWeight"];
+        assert_eq!((weight.requests, weight.throttled), (3, 1));
+        assert!(!stats.limits.contains_key("Binance/Orders")); // Unknown limits aren't enforced
+
+        // Rate limit errors are retried after a growing pause, then surfaced
+        let calls = AtomicU32::new(0);
+        let started = Instant::now();
+        let result: ExchangeResult<()> = limiter
+            .execute(Exchange::Binance, &[], || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(ExchangeError::RateLimit { retry_after: None })
+            })
+            .await;
+        assert!(matches!(result, Err(ExchangeError::RateLimit { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() >= Duration::from_millis(150)); // 50ms + 100ms
+        assert_eq!(limiter.statistics().rate_limit_errors, 3);
+
+        let result = limiter.execute(Exchange::Binance, &[], || async { Ok(7) }).await;
+        assert_eq!(result.unwrap(), 7);
+    }
+}
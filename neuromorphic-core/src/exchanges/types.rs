@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use utoipa::ToSchema;
+
+use super::connector::UniversalKline;
 
 /// Market data types
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -23,7 +26,7 @@ pub struct ExchangeMessage {
 }
 
 /// Trading symbol
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub struct Symbol(pub String);
 
 impl Symbol {
@@ -47,7 +50,7 @@ impl fmt::Display for Symbol {
 }
 
 /// Order side
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 pub enum Side {
     Buy,
     Sell,
@@ -71,7 +74,7 @@ pub enum OrderType {
 }
 
 /// Exchange identifier
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub enum Exchange {
     Binance,
     Coinbase,
@@ -82,6 +85,18 @@ pub enum Exchange {
     NASDAQ,
 }
 
+impl Exchange {
+    pub const ALL: [Exchange; 7] = [
+        Exchange::Binance,
+        Exchange::Coinbase,
+        Exchange::Kraken,
+        Exchange::Bitstamp,
+        Exchange::Gemini,
+        Exchange::NYSE,
+        Exchange::NASDAQ,
+    ];
+}
+
 impl fmt::Display for Exchange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -140,6 +155,7 @@ pub enum UniversalMarketData {
     Trade(UniversalTrade),
     Quote(UniversalQuote),
     OrderBook(UniversalOrderBook),
+    Kline(UniversalKline), // Closed candles only
 }
 
 impl UniversalMarketData {
@@ -148,6 +164,7 @@ impl UniversalMarketData {
             Self::Trade(t) => t.timestamp_local,
             Self::Quote(q) => q.timestamp_local,
             Self::OrderBook(b) => b.timestamp_local,
+            Self::Kline(k) => k.close_time.timestamp_millis() as u64,
         }
     }
     
@@ -156,6 +173,7 @@ impl UniversalMarketData {
             Self::Trade(t) => &t.symbol,
             Self::Quote(q) => &q.symbol,
             Self::OrderBook(b) => &b.symbol,
+            Self::Kline(k) => &k.symbol,
         }
     }
 }
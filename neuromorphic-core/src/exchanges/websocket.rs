@@ -1,7 +1,15 @@
 //! WebSocket streaming interface for real-time market data
+//!
+//! Each `WebSocketManager` connection is owned by a single task, the
+//! connection actor. It alone reads, writes, pings and reconnects the
+//! socket; the manager only sends it commands over a channel, so nothing
+//! else ever needs a handle on the socket. Subscriptions are the wanted
+//! state rather than messages in flight: they are recorded whether or not a
+//! connection is open, before `start` or between reconnects, and every
+//! connection the actor opens subscribes to all of them.
 
 use async_trait::async_trait;
-use futures_util::{stream::{SplitSink, SplitStream}, SinkExt, StreamExt};
+use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -16,6 +24,8 @@ use tracing::{debug, error, info, warn};
 use url::Url;
 
 use super::connector::{ExchangeError, ExchangeResult};
+use super::data_quality::{DataQualityConfig, DataQualityFilter, QuarantinedTick};
+use super::latency::{LatencyConfig, LatencyStatistics, LatencyTracker};
 use super::types::{Exchange, Symbol, UniversalMarketData, UniversalOrderBook, UniversalQuote, UniversalTrade};
 
 /// WebSocket stream types
@@ -79,14 +89,77 @@ impl StreamSubscription {
     }
 }
 
-/// WebSocket connection status
+/// WebSocket connection status. Every change is broadcast to
+/// `subscribe_status` receivers.
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectionStatus {
     Disconnected,
     Connecting,
     Connected,
     Reconnecting,
-    Failed,
+    Failed, // Gave up reconnecting
+}
+
+/// Delays between reconnection attempts: exponential backoff with jitter
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Fraction of each delay that is randomised, so clients dropped together
+    /// don't all reconnect at the same moment
+    pub jitter: f64,
+    /// Consecutive failed attempts before giving up; `None` retries forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: Some(10),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Keep retrying with the default backoff, for connections a session can't do without
+    pub fn forever() -> Self {
+        Self {
+            max_attempts: None,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the next attempt after `failures` consecutive failures
+    pub fn delay(&self, failures: u32) -> Duration {
+        self.delay_with(failures, random_unit())
+    }
+
+    pub fn gives_up_after(&self, failures: u32) -> bool {
+        self.max_attempts.is_some_and(|max| failures >= max)
+    }
+
+    /// Delay with `unit` in [0, 1) as the random draw
+    fn delay_with(&self, failures: u32, unit: f64) -> Duration {
+        let max = self.max_delay.as_secs_f64();
+        let base = (self.initial_delay.as_secs_f64() * self.multiplier.max(1.0).powi(failures.min(64) as i32)).min(max);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        Duration::from_secs_f64((base * (1.0 + jitter * (2.0 * unit - 1.0))).min(max))
+    }
+}
+
+/// Random number in [0, 1), good enough to spread reconnects
+fn random_unit() -> f64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
 }
 
 /// Stream health metrics
@@ -98,8 +171,11 @@ pub struct StreamMetrics {
     pub connection_errors: u64,
     pub reconnection_count: u64,
     pub last_message_time: Option<Instant>,
-    pub average_latency_ms: f64,
+    pub average_latency_ms: f64, // Moving average, see `latency`
     pub data_gaps: u64,
+    pub quarantined_ticks: u64,
+    pub quarantined_by_reason: HashMap<String, u64>,
+    pub latency: LatencyStatistics,
 }
 
 /// WebSocket configuration
@@ -107,11 +183,12 @@ pub struct StreamMetrics {
 pub struct WebSocketConfig {
     pub base_url: String,
     pub ping_interval: Duration,
-    pub reconnect_interval: Duration,
-    pub max_reconnect_attempts: u32,
+    pub reconnect: ReconnectPolicy,
     pub message_timeout: Duration,
     pub buffer_size: usize,
     pub enable_compression: bool,
+    pub data_quality: DataQualityConfig,
+    pub latency: LatencyConfig,
 }
 
 impl Default for WebSocketConfig {
@@ -119,11 +196,12 @@ impl Default for WebSocketConfig {
         Self {
             base_url: "wss://stream.binance.com:9443/ws".to_string(),
             ping_interval: Duration::from_secs(30),
-            reconnect_interval: Duration::from_secs(5),
-            max_reconnect_attempts: 10,
+            reconnect: ReconnectPolicy::default(),
             message_timeout: Duration::from_secs(30),
             buffer_size: 1000,
             enable_compression: true,
+            data_quality: DataQualityConfig::default(),
+            latency: LatencyConfig::default(),
         }
     }
 }
@@ -153,24 +231,93 @@ pub trait StreamManager: Send + Sync {
     async fn stop(&mut self) -> ExchangeResult<()>;
 }
 
+/// Exchange-specific wire format of a WebSocket stream
+pub trait StreamProtocol: Send + Sync {
+    /// Name of the stream carrying a subscription
+    fn stream_name(&self, subscription: &StreamSubscription) -> String {
+        format!("{}@{}", subscription.symbol.as_str().to_lowercase(), subscription.stream_type.as_str())
+    }
+    
+    /// URL for a connection carrying `subscriptions`
+    fn connect_url(&self, base_url: &str, _subscriptions: &[StreamSubscription]) -> String {
+        base_url.to_string()
+    }
+    
+    /// Whether `connect_url` already subscribes the connection to its
+    /// streams; otherwise they are subscribed by message after connecting
+    fn streams_in_url(&self) -> bool {
+        false
+    }
+    
+    fn subscription_message(&self, subscriptions: &[StreamSubscription], subscribe: bool, id: u64) -> String {
+        let streams: Vec<String> = subscriptions.iter().map(|s| self.stream_name(s)).collect();
+        serde_json::json!({
+            "method": if subscribe { "SUBSCRIBE" } else { "UNSUBSCRIBE" },
+            "params": streams,
+            "id": id,
+        })
+        .to_string()
+    }
+    
+    /// Market data in a text frame; `None` for frames that carry none, like
+    /// subscription acknowledgements
+    fn parse(&self, text: &str, exchange: Exchange) -> ExchangeResult<Option<UniversalMarketData>>;
+    
+    /// Exchange time of the event, in Unix milliseconds, for latency
+    /// measurement. `None` when the message doesn't carry one.
+    fn exchange_time(&self, _data: &UniversalMarketData) -> Option<u64> {
+        None
+    }
+}
+
+/// Placeholder format used until an exchange supplies its own
+#[derive(Debug, Default)]
+pub struct DefaultStreamProtocol;
+
+impl StreamProtocol for DefaultStreamProtocol {
+    fn parse(&self, text: &str, exchange: Exchange) -> ExchangeResult<Option<UniversalMarketData>> {
+        let _data: serde_json::Value = serde_json::from_str(text).map_err(|e| {
+            ExchangeError::InvalidRequest {
+                details: format!("Failed to parse JSON: {}", e),
+            }
+        })?;
+        
+        // For now, return a dummy trade
+        Ok(Some(UniversalMarketData::Trade(UniversalTrade {
+            exchange,
+            symbol: Symbol::new("BTC-USD"),
+            price: 50000.0,
+            quantity: 0.001,
+            side: super::types::Side::Buy,
+            timestamp_exchange: chrono::Utc::now().timestamp_millis() as u64,
+            timestamp_local: chrono::Utc::now().timestamp_millis() as u64,
+            trade_id: "test".to_string(),
+        })))
+    }
+}
+
 /// WebSocket stream manager implementation
 pub struct WebSocketManager {
     config: WebSocketConfig,
     exchange: Exchange,
+    protocol: Arc<dyn StreamProtocol>,
     subscriptions: Arc<RwLock<HashMap<String, StreamSubscription>>>,
     connection_status: Arc<RwLock<ConnectionStatus>>,
     metrics: Arc<RwLock<StreamMetrics>>,
+    quality_filter: Arc<RwLock<DataQualityFilter>>,
+    latency: Arc<RwLock<LatencyTracker>>,
+    status_sender: broadcast::Sender<ConnectionStatus>,
     data_sender: broadcast::Sender<UniversalMarketData>,
     data_receiver: Option<broadcast::Receiver<UniversalMarketData>>,
     control_sender: Option<mpsc::UnboundedSender<ControlMessage>>,
     websocket_task: Option<tokio::task::JoinHandle<()>>,
 }
 
-/// Internal control messages
+/// Commands to the connection actor
 #[derive(Debug)]
 enum ControlMessage {
-    Subscribe(StreamSubscription),
-    Unsubscribe(StreamSubscription),
+    Subscribe(Vec<StreamSubscription>),
+    Unsubscribe(Vec<StreamSubscription>),
     Reconnect,
     Shutdown,
 }
@@ -178,13 +325,20 @@ enum ControlMessage {
 impl WebSocketManager {
     pub fn new(config: WebSocketConfig, exchange: Exchange) -> Self {
         let (data_sender, data_receiver) = broadcast::channel(config.buffer_size);
+        let quality_filter = DataQualityFilter::new(config.data_quality.clone());
+        let latency = LatencyTracker::new(config.latency.clone());
+        let (status_sender, _) = broadcast::channel(16);
         
         Self {
             config,
             exchange,
+            protocol: Arc::new(DefaultStreamProtocol),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
             connection_status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
             metrics: Arc::new(RwLock::new(StreamMetrics::default())),
+            quality_filter: Arc::new(RwLock::new(quality_filter)),
+            latency: Arc::new(RwLock::new(latency)),
+            status_sender,
             data_sender,
             data_receiver: Some(data_receiver),
             control_sender: None,
@@ -192,6 +346,73 @@ impl WebSocketManager {
         }
     }
     
+    pub fn with_protocol(mut self, protocol: Arc<dyn StreamProtocol>) -> Self {
+        self.protocol = protocol;
+        self
+    }
+    
+    /// Another connection feeding the same data receiver, status channel and
+    /// quality filter, for exchanges that cap the streams per connection.
+    /// It has its own subscriptions and metrics and must be started separately.
+    pub fn new_shard(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            exchange: self.exchange,
+            protocol: self.protocol.clone(),
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            connection_status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
+            metrics: Arc::new(RwLock::new(StreamMetrics::default())),
+            quality_filter: self.quality_filter.clone(),
+            latency: self.latency.clone(),
+            status_sender: self.status_sender.clone(),
+            data_sender: self.data_sender.clone(),
+            data_receiver: None,
+            control_sender: None,
+            websocket_task: None,
+        }
+    }
+    
+    /// Subscribe to several streams with one message. Before `start` they are
+    /// remembered and subscribed when the connection opens.
+    pub async fn subscribe_batch(&mut self, subscriptions: Vec<StreamSubscription>) -> ExchangeResult<()> {
+        self.send_control(subscriptions, true).await
+    }
+    
+    pub async fn unsubscribe_batch(&mut self, subscriptions: Vec<StreamSubscription>) -> ExchangeResult<()> {
+        self.send_control(subscriptions, false).await
+    }
+    
+    async fn send_control(&mut self, subscriptions: Vec<StreamSubscription>, subscribe: bool) -> ExchangeResult<()> {
+        if subscriptions.is_empty() {
+            return Ok(());
+        }
+        
+        let Some(sender) = &self.control_sender else {
+            record_subscriptions(&mut *self.subscriptions.write().await, &subscriptions, subscribe);
+            return Ok(());
+        };
+        
+        let message = if subscribe {
+            ControlMessage::Subscribe(subscriptions)
+        } else {
+            ControlMessage::Unsubscribe(subscriptions)
+        };
+        sender.send(message).map_err(|e| ExchangeError::Internal {
+            message: format!("Failed to send subscription command: {}", e),
+        })
+    }
+    
+    /// Drop the connection and open a new one right away, subscribed to
+    /// every stream; errors when not started
+    pub fn reconnect(&self) -> ExchangeResult<()> {
+        let Some(sender) = &self.control_sender else {
+            return Err(ExchangeError::InvalidRequest { details: "WebSocket not started".to_string() });
+        };
+        sender.send(ControlMessage::Reconnect).map_err(|e| ExchangeError::Internal {
+            message: format!("Failed to send reconnect command: {}", e),
+        })
+    }
+    
     /// Create subscription key for internal tracking
     fn create_subscription_key(subscription: &StreamSubscription) -> String {
         match &subscription.interval {
@@ -200,244 +421,244 @@ impl WebSocketManager {
         }
     }
     
-    /// Start the WebSocket connection and message handling
-    async fn start_websocket_task(
-        config: WebSocketConfig,
-        exchange: Exchange,
-        subscriptions: Arc<RwLock<HashMap<String, StreamSubscription>>>,
-        connection_status: Arc<RwLock<ConnectionStatus>>,
-        metrics: Arc<RwLock<StreamMetrics>>,
-        data_sender: broadcast::Sender<UniversalMarketData>,
-        mut control_receiver: mpsc::UnboundedReceiver<ControlMessage>,
+    /// Ticks held back by the data quality filter, oldest first
+    pub async fn quarantined_ticks(&self) -> Vec<QuarantinedTick> {
+        self.quality_filter.read().await.quarantined()
+    }
+    
+    /// Correct latencies with a measured clock offset, the exchange's server
+    /// time minus local time. Without one the offset is estimated.
+    pub async fn set_clock_offset(&self, offset_ms: f64) {
+        self.latency.write().await.set_clock_offset(offset_ms);
+    }
+    
+    /// Connection changes, e.g. to backfill data missed while disconnected
+    pub fn subscribe_status(&self) -> broadcast::Receiver<ConnectionStatus> {
+        self.status_sender.subscribe()
+    }
+    
+    /// Record a status change and broadcast it
+    async fn set_status(
+        connection_status: &RwLock<ConnectionStatus>,
+        status_sender: &broadcast::Sender<ConnectionStatus>,
+        status: ConnectionStatus,
     ) {
-        let mut reconnect_attempts = 0;
-        let mut websocket: Option<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>> = None;
-        let mut write_sink: Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>> = None;
+        let mut current = connection_status.write().await;
+        if *current != status {
+            *current = status.clone();
+            let _ = status_sender.send(status); // No subscribers is fine
+        }
+    }
+}
+
+/// Add or remove a batch of subscriptions
+fn record_subscriptions(current: &mut HashMap<String, StreamSubscription>, batch: &[StreamSubscription], subscribe: bool) {
+    for subscription in batch {
+        let key = WebSocketManager::create_subscription_key(subscription);
+        if subscribe {
+            current.insert(key, subscription.clone());
+        } else {
+            current.remove(&key);
+        }
+    }
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// What ended a connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hangup {
+    Lost,
+    Reconnect, // Asked for; reconnect without waiting
+    Shutdown,
+}
+
+/// The task owning a manager's connection, see the module docs
+struct ConnectionActor {
+    config: WebSocketConfig,
+    exchange: Exchange,
+    protocol: Arc<dyn StreamProtocol>,
+    subscriptions: Arc<RwLock<HashMap<String, StreamSubscription>>>, // Wanted, whether or not connected
+    connection_status: Arc<RwLock<ConnectionStatus>>,
+    metrics: Arc<RwLock<StreamMetrics>>,
+    quality_filter: Arc<RwLock<DataQualityFilter>>,
+    latency: Arc<RwLock<LatencyTracker>>,
+    status_sender: broadcast::Sender<ConnectionStatus>,
+    data_sender: broadcast::Sender<UniversalMarketData>,
+    commands: mpsc::UnboundedReceiver<ControlMessage>,
+    request_id: u64, // Of the last subscription message
+}
+
+impl ConnectionActor {
+    /// Connect, serve the connection and reconnect until shut down or out of attempts
+    async fn run(mut self) {
+        let mut failures = 0;
+        let mut reconnecting = false;
         
         loop {
-            // Update connection status
-            *connection_status.write().await = if reconnect_attempts > 0 {
+            let status = if reconnecting {
                 ConnectionStatus::Reconnecting
             } else {
                 ConnectionStatus::Connecting
             };
+            self.set_status(status).await;
+            reconnecting = true;
             
-            // Attempt to connect
-            match Self::connect_websocket(&config.base_url).await {
-                Ok((ws, sink)) => {
-                    info!("WebSocket connected successfully");
-                    websocket = Some(ws);
-                    write_sink = Some(sink);
-                    *connection_status.write().await = ConnectionStatus::Connected;
-                    reconnect_attempts = 0;
-                    
-                    // Resubscribe to all active subscriptions
-                    let current_subscriptions = subscriptions.read().await.clone();
-                    for subscription in current_subscriptions.values() {
-                        if let Err(e) = Self::send_subscription_message(&mut write_sink, subscription, true).await {
-                            error!("Failed to resubscribe to {}: {}", Self::create_subscription_key(subscription), e);
-                        }
+            // With the current subscriptions in the URL when the protocol supports it
+            let streams: Vec<StreamSubscription> = self.subscriptions.read().await.values().cloned().collect();
+            let url = self.protocol.connect_url(&self.config.base_url, &streams);
+            let delay = match connect(&url).await {
+                Ok(socket) => {
+                    info!(streams = streams.len(), "WebSocket connected successfully");
+                    failures = 0;
+                    self.set_status(ConnectionStatus::Connected).await;
+                    let hangup = self.serve(socket, &streams).await;
+                    self.set_status(ConnectionStatus::Disconnected).await;
+                    if hangup == Hangup::Shutdown {
+                        return;
                     }
+                    self.metrics.write().await.reconnection_count += 1;
+                    if hangup == Hangup::Reconnect { Duration::ZERO } else { self.config.reconnect.delay(0) }
                 }
                 Err(e) => {
                     error!("Failed to connect to WebSocket: {}", e);
-                    reconnect_attempts += 1;
-                    
-                    if reconnect_attempts >= config.max_reconnect_attempts {
+                    failures += 1;
+                    if self.config.reconnect.gives_up_after(failures) {
                         error!("Max reconnection attempts reached, giving up");
-                        *connection_status.write().await = ConnectionStatus::Failed;
-                        break;
+                        self.set_status(ConnectionStatus::Failed).await;
+                        return;
                     }
-                    
-                    tokio::time::sleep(config.reconnect_interval).await;
-                    continue;
+                    self.config.reconnect.delay(failures)
                 }
-            }
-            
-            // Main message handling loop
-            let mut ping_interval = interval(config.ping_interval);
+            };
             
-            loop {
-                tokio::select! {
-                    // Handle control messages
-                    Some(control_msg) = control_receiver.recv() => {
-                        match control_msg {
-                            ControlMessage::Subscribe(subscription) => {
-                                if let Err(e) = Self::send_subscription_message(&mut write_sink, &subscription, true).await {
-                                    error!("Failed to subscribe: {}", e);
-                                } else {
-                                    let key = Self::create_subscription_key(&subscription);
-                                    subscriptions.write().await.insert(key, subscription);
-                                }
-                            }
-                            ControlMessage::Unsubscribe(subscription) => {
-                                if let Err(e) = Self::send_subscription_message(&mut write_sink, &subscription, false).await {
-                                    error!("Failed to unsubscribe: {}", e);
-                                } else {
-                                    let key = Self::create_subscription_key(&subscription);
-                                    subscriptions.write().await.remove(&key);
-                                }
-                            }
-                            ControlMessage::Reconnect => {
-                                info!("Manual reconnection requested");
-                                break; // Break inner loop to reconnect
-                            }
-                            ControlMessage::Shutdown => {
-                                info!("Shutdown requested");
-                                return;
-                            }
+            warn!(failures, delay_ms = delay.as_millis() as u64, "Reconnecting WebSocket");
+            if !self.wait(delay).await {
+                return;
+            }
+        }
+    }
+    
+    /// Subscribe a new connection to `streams`, then read, write and ping it
+    /// until it ends
+    async fn serve(&mut self, mut socket: Socket, streams: &[StreamSubscription]) -> Hangup {
+        if !self.protocol.streams_in_url() {
+            if let Err(e) = self.send_subscription(&mut socket, streams, true).await {
+                error!("Failed to resubscribe {} streams: {}", streams.len(), e);
+                return Hangup::Lost;
+            }
+        }
+        
+        let mut ping_interval = interval(self.config.ping_interval);
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(ControlMessage::Subscribe(batch)) => {
+                        self.record(&batch, true).await;
+                        if let Err(e) = self.send_subscription(&mut socket, &batch, true).await {
+                            error!("Failed to subscribe: {}", e);
+                            return Hangup::Lost; // The next connection subscribes it
                         }
                     }
-                    
-                    // Handle WebSocket messages
-                    msg = Self::receive_message(&mut websocket, &config.message_timeout) => {
-                        match msg {
-                            Ok(Some(message)) => {
-                                metrics.write().await.messages_received += 1;
-                                metrics.write().await.last_message_time = Some(Instant::now());
-                                
-                                if let Err(e) = Self::process_message(
-                                    message,
-                                    exchange,
-                                    &data_sender,
-                                    &metrics
-                                ).await {
-                                    error!("Failed to process message: {}", e);
-                                    metrics.write().await.parse_errors += 1;
-                                }
-                            }
-                            Ok(None) => {
-                                // Connection closed gracefully
-                                warn!("WebSocket connection closed");
-                                break;
-                            }
-                            Err(e) => {
-                                error!("WebSocket error: {}", e);
-                                metrics.write().await.connection_errors += 1;
-                                break;
-                            }
+                    Some(ControlMessage::Unsubscribe(batch)) => {
+                        self.record(&batch, false).await;
+                        if let Err(e) = self.send_subscription(&mut socket, &batch, false).await {
+                            error!("Failed to unsubscribe: {}", e);
+                            return Hangup::Lost;
                         }
                     }
-                    
-                    // Send periodic ping
-                    _ = ping_interval.tick() => {
-                        if let Err(e) = Self::send_ping(&mut write_sink).await {
-                            error!("Failed to send ping: {}", e);
-                            break;
-                        }
+                    Some(ControlMessage::Reconnect) => {
+                        info!("Manual reconnection requested");
+                        let _ = socket.close(None).await;
+                        return Hangup::Reconnect;
+                    }
+                    Some(ControlMessage::Shutdown) | None => {
+                        info!("Shutdown requested");
+                        let _ = socket.close(None).await;
+                        return Hangup::Shutdown;
+                    }
+                },
+                
+                message = timeout(self.config.message_timeout, socket.next()) => match message {
+                    Ok(Some(Ok(message))) => self.receive(message).await,
+                    Ok(Some(Err(e))) => {
+                        error!("WebSocket error: {}", e);
+                        self.metrics.write().await.connection_errors += 1;
+                        return Hangup::Lost;
+                    }
+                    Ok(None) => {
+                        warn!("WebSocket connection closed");
+                        return Hangup::Lost;
+                    }
+                    Err(_) => {
+                        error!(timeout_secs = self.config.message_timeout.as_secs(), "No WebSocket message before the timeout");
+                        self.metrics.write().await.connection_errors += 1;
+                        return Hangup::Lost;
+                    }
+                },
+                
+                _ = ping_interval.tick() => {
+                    if let Err(e) = socket.send(Message::Ping(vec![])).await {
+                        error!("Failed to send ping: {}", e);
+                        return Hangup::Lost;
                     }
                 }
             }
-            
-            // Connection lost, prepare for reconnection
-            websocket = None;
-            write_sink = None;
-            *connection_status.write().await = ConnectionStatus::Disconnected;
-            metrics.write().await.reconnection_count += 1;
-            
-            warn!("WebSocket disconnected, attempting to reconnect...");
-            tokio::time::sleep(config.reconnect_interval).await;
         }
     }
     
-    /// Connect to WebSocket
-    async fn connect_websocket(
-        url: &str,
-    ) -> ExchangeResult<(
-        SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
-        SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
-    )> {
-        let url = Url::parse(url).map_err(|e| ExchangeError::InvalidRequest {
-            details: format!("Invalid WebSocket URL: {}", e),
-        })?;
-        
-        let (ws_stream, _) = connect_async(url).await.map_err(|e| ExchangeError::Connection {
-            message: format!("WebSocket connection failed: {}", e),
-        })?;
-        
-        let (sink, stream) = ws_stream.split();
-        Ok((stream, sink))
-    }
-    
-    /// Send subscription/unsubscription message
-    async fn send_subscription_message(
-        sink: &mut Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
-        subscription: &StreamSubscription,
-        subscribe: bool,
-    ) -> ExchangeResult<()> {
-        if let Some(sink) = sink {
-            // This is a placeholder - actual implementation would depend on exchange protocol
-            let method = if subscribe { "SUBSCRIBE" } else { "UNSUBSCRIBE" };
-            let message = serde_json::json!({
-                "method": method,
-                "params": [format!("{}@{}", subscription.symbol.as_str().to_lowercase(), subscription.stream_type.as_str())],
-                "id": chrono::Utc::now().timestamp_millis()
-            });
-            
-            sink.send(Message::Text(message.to_string())).await.map_err(|e| {
-                ExchangeError::Connection {
-                    message: format!("Failed to send subscription message: {}", e),
-                }
-            })?;
+    /// Sit out a delay between connections, still taking commands so
+    /// subscriptions changed meanwhile are made on the next one. False when
+    /// shut down.
+    async fn wait(&mut self, delay: Duration) -> bool {
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => return true,
+                command = self.commands.recv() => match command {
+                    Some(ControlMessage::Subscribe(batch)) => self.record(&batch, true).await,
+                    Some(ControlMessage::Unsubscribe(batch)) => self.record(&batch, false).await,
+                    Some(ControlMessage::Reconnect) => return true,
+                    Some(ControlMessage::Shutdown) | None => return false,
+                },
+            }
         }
-        
-        Ok(())
     }
     
-    /// Send ping message
-    async fn send_ping(
-        sink: &mut Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
-    ) -> ExchangeResult<()> {
-        if let Some(sink) = sink {
-            sink.send(Message::Ping(vec![])).await.map_err(|e| ExchangeError::Connection {
-                message: format!("Failed to send ping: {}", e),
-            })?;
-        }
-        Ok(())
+    async fn record(&self, batch: &[StreamSubscription], subscribe: bool) {
+        record_subscriptions(&mut *self.subscriptions.write().await, batch, subscribe);
     }
     
-    /// Receive message with timeout
-    async fn receive_message(
-        websocket: &mut Option<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>>,
-        message_timeout: &Duration,
-    ) -> ExchangeResult<Option<Message>> {
-        if let Some(ws) = websocket {
-            match timeout(*message_timeout, ws.next()).await {
-                Ok(Some(Ok(message))) => Ok(Some(message)),
-                Ok(Some(Err(e))) => Err(ExchangeError::Connection {
-                    message: format!("WebSocket error: {}", e),
-                }),
-                Ok(None) => Ok(None), // Stream ended
-                Err(_) => Err(ExchangeError::Timeout {
-                    seconds: message_timeout.as_secs(),
-                }),
-            }
-        } else {
-            Err(ExchangeError::Connection {
-                message: "No active WebSocket connection".to_string(),
-            })
+    async fn set_status(&self, status: ConnectionStatus) {
+        WebSocketManager::set_status(&self.connection_status, &self.status_sender, status).await;
+    }
+    
+    /// Send one subscription/unsubscription message for a batch of streams
+    async fn send_subscription(&mut self, socket: &mut Socket, batch: &[StreamSubscription], subscribe: bool) -> ExchangeResult<()> {
+        if batch.is_empty() {
+            return Ok(());
         }
+        self.request_id += 1;
+        let message = self.protocol.subscription_message(batch, subscribe, self.request_id);
+        socket.send(Message::Text(message)).await.map_err(|e| ExchangeError::Connection {
+            message: format!("Failed to send subscription message: {}", e),
+        })
     }
     
-    /// Process incoming WebSocket message
-    async fn process_message(
-        message: Message,
-        exchange: Exchange,
-        data_sender: &broadcast::Sender<UniversalMarketData>,
-        metrics: &Arc<RwLock<StreamMetrics>>,
-    ) -> ExchangeResult<()> {
+    /// Handle a message from the exchange
+    async fn receive(&self, message: Message) {
+        {
+            let mut metrics = self.metrics.write().await;
+            metrics.messages_received += 1;
+            metrics.last_message_time = Some(Instant::now());
+        }
+        
         match message {
             Message::Text(text) => {
-                // Parse JSON message and convert to UniversalMarketData
-                // This is a placeholder - actual implementation would depend on exchange format
-                debug!("Received message: {}", text);
-                
-                // Try to parse as market data
-                if let Ok(market_data) = Self::parse_market_data(&text, exchange) {
-                    if let Err(_) = data_sender.send(market_data) {
-                        // No receivers, that's OK
-                    }
-                    metrics.write().await.messages_parsed += 1;
+                if let Err(e) = self.process_text(&text).await {
+                    error!("Failed to process message: {}", e);
+                    self.metrics.write().await.parse_errors += 1;
                 }
             }
             Message::Binary(_) => {
@@ -454,35 +675,46 @@ impl WebSocketManager {
                 debug!("Received other message type");
             }
         }
-        
-        Ok(())
     }
     
-    /// Parse market data from JSON text
-    fn parse_market_data(text: &str, exchange: Exchange) -> ExchangeResult<UniversalMarketData> {
-        // This is a placeholder implementation
-        // Real implementation would parse exchange-specific JSON format
+    /// Parse a text frame and forward the market data in it
+    async fn process_text(&self, text: &str) -> ExchangeResult<()> {
+        let received_ms = chrono::Utc::now().timestamp_millis() as u64;
+        debug!("Received message: {}", text);
         
-        let _data: serde_json::Value = serde_json::from_str(text).map_err(|e| {
-            ExchangeError::InvalidRequest {
-                details: format!("Failed to parse JSON: {}", e),
+        if let Some(market_data) = self.protocol.parse(text, self.exchange)? {
+            self.metrics.write().await.messages_parsed += 1;
+            if let Some(exchange_ms) = self.protocol.exchange_time(&market_data) {
+                self.latency.write().await.record(exchange_ms, received_ms);
             }
-        })?;
-        
-        // For now, return a dummy trade
-        Ok(UniversalMarketData::Trade(UniversalTrade {
-            exchange,
-            symbol: Symbol::new("BTC-USD"),
-            price: 50000.0,
-            quantity: 0.001,
-            side: super::types::Side::Buy,
-            timestamp_exchange: chrono::Utc::now().timestamp_millis() as u64,
-            timestamp_local: chrono::Utc::now().timestamp_millis() as u64,
-            trade_id: "test".to_string(),
-        }))
+            
+            // Bad ticks are quarantined rather than forwarded
+            if let Err(issue) = self.quality_filter.write().await.check(&market_data) {
+                warn!("Quarantined {:?} tick: {}", self.exchange, issue);
+                let mut metrics = self.metrics.write().await;
+                metrics.quarantined_ticks += 1;
+                *metrics.quarantined_by_reason.entry(issue.kind().to_string()).or_insert(0) += 1;
+                return Ok(());
+            }
+            
+            let _ = self.data_sender.send(market_data); // No receivers is fine
+        }
+        Ok(())
     }
 }
 
+/// Open a WebSocket connection
+async fn connect(url: &str) -> ExchangeResult<Socket> {
+    let url = Url::parse(url).map_err(|e| ExchangeError::InvalidRequest {
+        details: format!("Invalid WebSocket URL: {}", e),
+    })?;
+    
+    let (socket, _) = connect_async(url).await.map_err(|e| ExchangeError::Connection {
+        message: format!("WebSocket connection failed: {}", e),
+    })?;
+    Ok(socket)
+}
+
 impl StreamType {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -499,33 +731,11 @@ impl StreamType {
 #[async_trait]
 impl StreamManager for WebSocketManager {
     async fn subscribe(&mut self, subscription: StreamSubscription) -> ExchangeResult<()> {
-        if let Some(sender) = &self.control_sender {
-            sender.send(ControlMessage::Subscribe(subscription)).map_err(|e| {
-                ExchangeError::Internal {
-                    message: format!("Failed to send subscribe command: {}", e),
-                }
-            })?;
-        } else {
-            return Err(ExchangeError::Connection {
-                message: "Stream manager not started".to_string(),
-            });
-        }
-        Ok(())
+        self.subscribe_batch(vec![subscription]).await
     }
     
     async fn unsubscribe(&mut self, subscription: StreamSubscription) -> ExchangeResult<()> {
-        if let Some(sender) = &self.control_sender {
-            sender.send(ControlMessage::Unsubscribe(subscription)).map_err(|e| {
-                ExchangeError::Internal {
-                    message: format!("Failed to send unsubscribe command: {}", e),
-                }
-            })?;
-        } else {
-            return Err(ExchangeError::Connection {
-                message: "Stream manager not started".to_string(),
-            });
-        }
-        Ok(())
+        self.unsubscribe_batch(vec![subscription]).await
     }
     
     fn get_receiver(&mut self) -> Option<broadcast::Receiver<UniversalMarketData>> {
@@ -537,7 +747,10 @@ impl StreamManager for WebSocketManager {
     }
     
     async fn get_metrics(&self) -> StreamMetrics {
-        self.metrics.read().await.clone()
+        let mut metrics = self.metrics.read().await.clone();
+        metrics.latency = self.latency.read().await.statistics();
+        metrics.average_latency_ms = metrics.latency.ewma_ms;
+        metrics
     }
     
     async fn start(&mut self) -> ExchangeResult<()> {
@@ -547,29 +760,24 @@ impl StreamManager for WebSocketManager {
             });
         }
         
-        let (control_sender, control_receiver) = mpsc::unbounded_channel();
+        let (control_sender, commands) = mpsc::unbounded_channel();
         self.control_sender = Some(control_sender);
         
-        let config = self.config.clone();
-        let exchange = self.exchange.clone();
-        let subscriptions = self.subscriptions.clone();
-        let connection_status = self.connection_status.clone();
-        let metrics = self.metrics.clone();
-        let data_sender = self.data_sender.clone();
-        
-        let task = tokio::spawn(async move {
-            Self::start_websocket_task(
-                config,
-                exchange,
-                subscriptions,
-                connection_status,
-                metrics,
-                data_sender,
-                control_receiver,
-            ).await;
-        });
-        
-        self.websocket_task = Some(task);
+        let actor = ConnectionActor {
+            config: self.config.clone(),
+            exchange: self.exchange,
+            protocol: self.protocol.clone(),
+            subscriptions: self.subscriptions.clone(),
+            connection_status: self.connection_status.clone(),
+            metrics: self.metrics.clone(),
+            quality_filter: self.quality_filter.clone(),
+            latency: self.latency.clone(),
+            status_sender: self.status_sender.clone(),
+            data_sender: self.data_sender.clone(),
+            commands,
+            request_id: 0,
+        };
+        self.websocket_task = Some(tokio::spawn(actor.run()));
         
         Ok(())
     }
@@ -579,12 +787,15 @@ impl StreamManager for WebSocketManager {
             let _ = sender.send(ControlMessage::Shutdown);
         }
         
-        if let Some(task) = self.websocket_task.take() {
-            task.abort();
+        // Let the actor close the socket, without waiting on a connect that hangs
+        if let Some(mut task) = self.websocket_task.take() {
+            if timeout(Duration::from_secs(1), &mut task).await.is_err() {
+                task.abort();
+            }
         }
         
         self.control_sender = None;
-        *self.connection_status.write().await = ConnectionStatus::Disconnected;
+        Self::set_status(&self.connection_status, &self.status_sender, ConnectionStatus::Disconnected).await;
         
         Ok(())
     }
@@ -593,6 +804,31 @@ impl StreamManager for WebSocketManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::net::TcpListener;
+    
+    async fn exchange() -> (TcpListener, WebSocketConfig) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = WebSocketConfig { base_url: format!("ws://{}", listener.local_addr().unwrap()), ..Default::default() };
+        (listener, config)
+    }
+    
+    async fn accept(listener: &TcpListener) -> WebSocketStream<TcpStream> {
+        let (stream, _) = timeout(Duration::from_secs(5), listener.accept()).await.unwrap().unwrap();
+        tokio_tungstenite::accept_async(stream).await.unwrap()
+    }
+    
+    /// Streams the next subscription message on `socket` asks for, sorted
+    async fn subscribed(socket: &mut WebSocketStream<TcpStream>) -> Vec<String> {
+        loop {
+            let message = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+            let Message::Text(text) = message else { continue }; // Pings
+            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(request["method"], "SUBSCRIBE");
+            let mut streams: Vec<String> = serde_json::from_value(request["params"].clone()).unwrap();
+            streams.sort();
+            return streams;
+        }
+    }
     
     #[tokio::test]
     async fn test_websocket_manager_creation() {
@@ -612,4 +848,112 @@ mod tests {
         let kline_key = WebSocketManager::create_subscription_key(&kline_subscription);
         assert_eq!(kline_key, "ETH-USD:kline:1m");
     }
+    
+    #[tokio::test]
+    async fn test_reconnect_backoff_and_status_broadcast() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_attempts: Some(3),
+        };
+        assert_eq!(policy.delay_with(0, 0.5), Duration::from_millis(100));
+        assert_eq!(policy.delay_with(2, 0.5), Duration::from_millis(400));
+        assert_eq!(policy.delay_with(10, 0.5), Duration::from_secs(1)); // Capped
+        assert_eq!(policy.delay_with(2, 0.0), Duration::from_millis(200));
+        assert_eq!(policy.delay_with(2, 1.0), Duration::from_millis(600));
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(300));
+        }
+        assert!(!policy.gives_up_after(2) && policy.gives_up_after(3));
+        assert!(!ReconnectPolicy::forever().gives_up_after(u32::MAX));
+        
+        // Nothing listens on port 1, so every attempt fails until the manager gives up
+        let config = WebSocketConfig {
+            base_url: "ws://127.0.0.1:1".to_string(),
+            reconnect: ReconnectPolicy {
+                initial_delay: Duration::from_millis(5),
+                max_attempts: Some(2),
+                ..policy
+            },
+            ..Default::default()
+        };
+        let mut manager = WebSocketManager::new(config, Exchange::Binance);
+        let mut status = manager.subscribe_status();
+        manager.start().await.unwrap();
+        
+        let mut seen = Vec::new();
+        while let Ok(Ok(changed)) = tokio::time::timeout(Duration::from_secs(5), status.recv()).await {
+            seen.push(changed.clone());
+            if changed == ConnectionStatus::Failed {
+                break;
+            }
+        }
+        assert_eq!(seen, vec![ConnectionStatus::Connecting, ConnectionStatus::Reconnecting, ConnectionStatus::Failed]);
+        assert_eq!(manager.get_status().await, ConnectionStatus::Failed);
+    }
+    
+    #[tokio::test]
+    async fn test_subscriptions_made_before_connecting_are_sent_on_connect() {
+        let (listener, config) = exchange().await;
+        let mut manager = WebSocketManager::new(config, Exchange::Binance);
+        manager.subscribe(StreamSubscription::trade(Symbol::new("BTCUSDT"))).await.unwrap();
+        manager.start().await.unwrap();
+        manager.subscribe(StreamSubscription::quote(Symbol::new("ETHUSDT"))).await.unwrap(); // Most likely still connecting
+        
+        let mut socket = accept(&listener).await;
+        let mut streams = subscribed(&mut socket).await;
+        if streams.len() < 2 {
+            streams.extend(subscribed(&mut socket).await);
+            streams.sort();
+        }
+        assert_eq!(streams, ["btcusdt@trade", "ethusdt@bookTicker"]);
+        manager.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_reconnects_resubscribe_every_stream() {
+        let (listener, mut config) = exchange().await;
+        config.reconnect = ReconnectPolicy { initial_delay: Duration::from_millis(200), jitter: 0.0, ..Default::default() };
+        let mut manager = WebSocketManager::new(config, Exchange::Binance);
+        let mut status = manager.subscribe_status();
+        let mut data = manager.get_receiver().unwrap();
+        manager.subscribe(StreamSubscription::trade(Symbol::new("BTCUSDT"))).await.unwrap();
+        manager.start().await.unwrap();
+        
+        // The exchange drops the connection; a stream subscribed before the
+        // manager reconnects is subscribed on the next one with the rest
+        let mut socket = accept(&listener).await;
+        assert_eq!(subscribed(&mut socket).await, ["btcusdt@trade"]);
+        drop(socket);
+        while timeout(Duration::from_secs(5), status.recv()).await.unwrap().unwrap() != ConnectionStatus::Disconnected {}
+        manager.subscribe(StreamSubscription::kline(Symbol::new("ETHUSDT"), "1m".to_string())).await.unwrap();
+        
+        let mut socket = accept(&listener).await;
+        assert_eq!(subscribed(&mut socket).await, ["btcusdt@trade", "ethusdt@kline"]);
+        socket.send(Message::Text(r#"{"e":"trade"}"#.to_string())).await.unwrap();
+        assert!(timeout(Duration::from_secs(5), data.recv()).await.unwrap().is_ok());
+        assert_eq!(manager.get_metrics().await.reconnection_count, 1);
+        manager.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_reconnect_on_request_skips_the_delay() {
+        let (listener, mut config) = exchange().await;
+        config.reconnect = ReconnectPolicy { initial_delay: Duration::from_secs(60), jitter: 0.0, ..Default::default() };
+        let mut manager = WebSocketManager::new(config, Exchange::Binance);
+        assert!(manager.reconnect().is_err());
+        manager.subscribe(StreamSubscription::trade(Symbol::new("BTCUSDT"))).await.unwrap();
+        manager.start().await.unwrap();
+        
+        let mut socket = accept(&listener).await;
+        assert_eq!(subscribed(&mut socket).await, ["btcusdt@trade"]);
+        manager.reconnect().unwrap();
+        let mut socket = accept(&listener).await; // Well before the reconnect delay
+        assert_eq!(subscribed(&mut socket).await, ["btcusdt@trade"]);
+        assert_eq!(manager.get_metrics().await.reconnection_count, 1);
+        manager.stop().await.unwrap();
+    }
 }
\ No newline at end of file
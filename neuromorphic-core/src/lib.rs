@@ -5,29 +5,64 @@
 
 pub mod paper_trading;
 pub mod exchanges;
+pub mod market_data;
 pub mod metrics;
 pub mod api;
 pub mod market_scanner;
+pub mod reports;
+pub mod logging;
+pub mod config;
+pub mod backtest;
+pub mod control;
+#[cfg(feature = "parquet")]
+pub mod data;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod service;
 
 // Re-export main types for easy access
 pub use paper_trading::{
     PaperTradingEngine, PaperTradingConfig, TradingSignal, SignalAction, 
-    SignalMetadata, TradingStatistics, PositionManager, OrderManager, RiskManager
+    SignalMetadata, TradingStatistics, PositionManager, OrderManager, RiskManager,
+    Accounts, AccountStatistics, DEFAULT_ACCOUNT, RouteRule, SignalRouter,
+    ExecutionMode, ExecutionVenue, Clock, SharedClock, SimulatedClock, SystemClock,
+    TradeOutcome, SignalOutcome, SignalResult, SignalExecution, ExecutionError, OutcomePublisher, OutcomeWebhookConfig, SignalAggregator, AggregatorConfig,
+    AggregationPolicy, EngineSnapshot, Scenario, ScenarioReport, Shock, DetailedStatistics, PnlAttribution,
+    ImportedPosition, PortfolioImport, BasketOrder, PositionGroup, ExecutionAlgo, ParticipationConfig
 };
 pub use exchanges::{Symbol, Exchange, Side, OrderType};
-pub use metrics::MetricsCollector;
-pub use api::MetricsApiServer;
+pub use market_data::{UnifiedMarketFeed, UnifiedMarketEvent, UnifiedFeedConfig};
+pub use metrics::{AccountAttribution, HistogramBuckets, MetricsCollector, MetricsConfig};
+pub use api::{ApiAuth, ApiConfig, ApiHandle, ApiKey, MetricsApiServer, Role};
 pub use market_scanner::{
     MarketScannerService, MarketData, TradingOpportunity, ScannerConfig,
-    StockScreener, StrategyEngine, MarketAnalytics
+    StockScreener, StrategyEngine, MarketAnalytics, UniverseConfig, UniverseManager, UniverseSource,
+    MarketRegime, RegimeConfig, RegimeDetector, OpportunityStore, PriceHistory,
+    PopulationPatternClassifier, SpikePattern, SpikePatternClassifier, SpikePatterns
 };
+pub use reports::{Locale, ReportGenerator, SessionReport, ReportFormat};
+pub use service::{Service, ServiceConfig};
+pub use logging::{init_logging, LogFormat};
+pub use config::{RunConfig, ConfigError, ConfigChange, ExchangeCredentials, ReloadableSettings, StrategyParams};
+pub use control::{AutonomousControl, ControlStatus, DailyCounters, DailyLedger, Decision, DecisionRecord, SkipReason};
 
 use anyhow::Result;
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn, error};
+
+/// How often `execute_signal_sync` checks its orders
+const FILL_POLL: Duration = Duration::from_millis(10);
 
 /// Main interface for integrating with external prediction engines
 pub struct NeuromorphicPaperTrader {
-    engine: PaperTradingEngine,
+    accounts: Accounts,
+    router: Arc<SignalRouter>,
+    aggregator: Option<Arc<SignalAggregator>>,
     metrics_collector: Arc<MetricsCollector>,
 }
 
@@ -35,7 +70,16 @@ pub struct NeuromorphicPaperTrader {
 pub struct AutonomousTradingSystem {
     paper_trader: NeuromorphicPaperTrader,
     market_scanner: MarketScannerService,
-    config: AutonomousConfig,
+    market_feed: Option<UnifiedMarketFeed>,
+    config: parking_lot::RwLock<AutonomousConfig>, // Strategy parameters change on reload
+    config_files: Vec<PathBuf>, // Reloaded on request
+    config_watcher: Option<tokio::task::JoinHandle<()>>,
+    recent_trades: DashMap<(Symbol, String), u64>, // Last accepted time per symbol and strategy
+    suppressed: AtomicU64,
+    control: AutonomousControl,
+    daily: DailyLedger,
+    api: Option<ApiHandle>,
+    snapshots: Option<(PathBuf, Duration)>, // Directory and interval of the periodic account snapshots
 }
 
 #[derive(Debug, Clone)]
@@ -48,64 +92,338 @@ pub struct AutonomousConfig {
     pub enable_auto_trading: bool,
     pub min_opportunity_confidence: f64,
     pub portfolio_heat: f64,
+    pub opportunity_cooldown: Duration, // Same strategy on the same symbol trades at most once per cooldown
+    pub accounts: BTreeMap<String, PaperTradingConfig>, // Extra accounts besides the default one
+    pub routes: Vec<RouteRule>,
+    pub daily_state_path: Option<PathBuf>, // Keeps the daily counters across restarts
+    pub shadow_mode: bool, // Decide and log, but never send signals to the engines
+    pub watch_config: bool, // Reload when one of the config files changes
+    pub api: ApiConfig,
+    pub metrics: MetricsConfig,
 }
 
 impl NeuromorphicPaperTrader {
-    /// Create a new paper trader with configuration
+    /// Create a new paper trader with configuration for the default account
     pub fn new(config: PaperTradingConfig) -> Self {
-        let metrics_collector = Arc::new(MetricsCollector::new());
+        Self::with_clock(config, paper_trading::system_clock())
+    }
+
+    /// Create a paper trader whose accounts and metrics run off `clock`
+    pub fn with_clock(config: PaperTradingConfig, clock: SharedClock) -> Self {
+        let signal_history = (config.history.signals, config.history.spill("signals"));
+        let accounts = Accounts::with_clock(config, clock.clone());
+        let metrics_collector = Arc::new(
+            MetricsCollector::with_clock(clock)
+                .with_confidence_calibration(accounts.confidence_calibration().clone())
+                .with_histograms(accounts.histograms().clone())
+                .with_signal_history(signal_history.0, signal_history.1),
+        );
         Self {
-            engine: PaperTradingEngine::new(config),
+            accounts,
+            router: Arc::new(SignalRouter::default()),
+            aggregator: None,
             metrics_collector,
         }
     }
 
-    /// Start the paper trading engine
+    /// Add an isolated account with its own capital and risk limits.
+    /// Accounts are started by `start`, so add them before it.
+    pub fn add_account(&mut self, id: impl Into<String>, config: PaperTradingConfig) -> Result<()> {
+        self.accounts.add(id, config)
+    }
+
+    /// Fill the orders of accounts using `mode` at this venue; call before `start`.
+    /// Returns the number of accounts attached.
+    pub fn set_execution_venue(&mut self, mode: ExecutionMode, venue: Arc<dyn ExecutionVenue>) -> usize {
+        self.accounts.set_execution_venue(mode, venue)
+    }
+
+    /// Combine signals tagged with a `source_id` before they are executed;
+    /// set before `start` so trade outcomes are attributed to the sources
+    pub fn set_signal_aggregator(&mut self, aggregator: Arc<SignalAggregator>) {
+        self.aggregator = Some(aggregator);
+    }
+
+    /// Take the ATR of ATR stops from these bars, e.g. the scanner's; set before `start`
+    pub fn set_price_history(&mut self, history: Arc<PriceHistory>) {
+        self.accounts.set_price_history(history);
+    }
+
+    pub fn signal_aggregator(&self) -> Option<&Arc<SignalAggregator>> {
+        self.aggregator.as_ref()
+    }
+
+    /// Start the paper trading engines of all accounts
     pub async fn start(&mut self) -> Result<()> {
-        self.engine.start().await
+        self.accounts.start_all().await?;
+
+        if let Some(aggregator) = self.aggregator.clone() {
+            let mut outcomes = self.accounts.subscribe_outcomes();
+            tokio::spawn(async move {
+                loop {
+                    match outcomes.recv().await {
+                        Ok(outcome) => aggregator.record_outcome(&outcome),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+        Ok(())
     }
 
-    /// Stop the paper trading engine
+    /// Stop the paper trading engines of all accounts
     pub async fn stop(&self) -> Result<()> {
-        self.engine.stop().await
+        self.accounts.stop_all().await
     }
 
-    /// Process a trading signal from an external prediction engine
+    /// Process a trading signal from an external prediction engine, sending it
+    /// to the account named in its metadata or chosen by the routing rules
+    ///
+    /// With a signal aggregator set, tagged signals are held until their
+    /// window closes and only the consolidated signal is executed. Success
+    /// means the signal was queued; see `subscribe_signal_results` for what
+    /// became of it.
     pub async fn process_prediction_signal(&self, signal: TradingSignal) -> Result<()> {
+        let Some(aggregator) = &self.aggregator else {
+            return self.execute_signal(signal, false).await;
+        };
+        let ready = aggregator.submit(signal, self.engine().clock().now_ms());
+        self.execute_signals(ready).await
+    }
+
+    /// Execute a signal and wait until its orders are done, for scripts that
+    /// want the fill rather than a queued signal. It skips the aggregator and
+    /// is given a `signal_id`, from the engine's `rng`, if it has none. Orders
+    /// fill on market prices, so a feed or `update_market_price` must keep
+    /// them coming meanwhile.
+    pub async fn execute_signal_sync(&self, mut signal: TradingSignal, timeout: Duration) -> Result<SignalExecution, ExecutionError> {
+        let signal_id = signal.metadata.signal_id.get_or_insert_with(|| self.engine().rng().uuid().to_string()).clone();
+        let signal = self.router.route(signal);
+        let orders = match self.accounts.route(&signal) {
+            Ok((_, engine)) => engine.order_manager().clone(),
+            Err(e) => return Err(ExecutionError::Rejected(format!("{:#}", e))),
+        };
+        let mut results = self.subscribe_signal_results();
+
+        let execution = async {
+            self.execute_signal(signal, false).await.map_err(|e| ExecutionError::Rejected(format!("{:#}", e)))?;
+            let outcome = loop {
+                match results.recv().await {
+                    Ok(result) if result.signal_id.as_deref() == Some(signal_id.as_str()) => break result.outcome,
+                    Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        return Err(ExecutionError::Rejected("engine stopped".to_string()));
+                    }
+                }
+            };
+            let order_ids = match outcome {
+                SignalOutcome::Executed { order_ids } => order_ids,
+                SignalOutcome::Rejected { reason } => return Err(ExecutionError::Rejected(reason)),
+                SignalOutcome::Throttled { reason } => return Err(ExecutionError::Throttled(reason)),
+                SignalOutcome::Ignored { reason } => return Err(ExecutionError::Ignored(reason)),
+            };
+            let mut poll = tokio::time::interval(FILL_POLL);
+            loop {
+                poll.tick().await;
+                let submitted: Vec<_> = order_ids.iter().filter_map(|id| orders.get_order(id)).collect();
+                if let Some(execution) = SignalExecution::from_orders(&signal_id, &submitted)? {
+                    return Ok(execution);
+                }
+            }
+        };
+        tokio::time::timeout(timeout, execution).await.unwrap_or(Err(ExecutionError::Timeout(timeout)))
+    }
+
+    /// Execute consolidated signals whose aggregation window has run out;
+    /// call periodically when some sources may not vote
+    pub async fn flush_aggregated_signals(&self) -> Result<()> {
+        let Some(aggregator) = &self.aggregator else {
+            return Ok(());
+        };
+        let ready = aggregator.flush_expired(self.engine().clock().now_ms());
+        self.execute_signals(ready).await
+    }
+
+    /// Execute each signal, returning the first error after trying them all
+    async fn execute_signals(&self, signals: Vec<TradingSignal>) -> Result<()> {
+        let mut result = Ok(());
+        for signal in signals {
+            let executed = self.execute_signal(signal, false).await;
+            if result.is_ok() {
+                result = executed;
+            }
+        }
+        result
+    }
+
+    /// Handle a signal on accounts started `stepped`, and fill the orders it
+    /// triggers, before returning; for simulations stepping through data.
+    /// It skips the aggregator.
+    pub async fn process_signal_now(&self, signal: TradingSignal) -> Result<()> {
+        self.execute_signal(signal, true).await
+    }
+
+    /// Fill triggered orders of every account once, see `PaperTradingEngine::process_orders_once`
+    pub fn process_orders_once(&self) -> Result<()> {
+        for (_, engine) in self.accounts.iter() {
+            engine.process_orders_once()?;
+        }
+        Ok(())
+    }
+
+    /// Queue a signal on its account, or handle it and fill its orders right away when `now`
+    async fn execute_signal(&self, signal: TradingSignal, now: bool) -> Result<()> {
+        let signal = self.router.route(signal);
+        
         // Record signal metrics
         self.metrics_collector.record_signal(&signal);
         
         // Process the signal
-        let result = self.engine.process_signal(signal).await;
+        let result = match self.accounts.route(&signal) {
+            Ok((_, engine)) if now => {
+                engine.process_signal_now(signal).await;
+                engine.process_orders_once().map(|_| ())
+            }
+            Ok((_, engine)) => engine.process_signal(signal).await,
+            Err(e) => Err(e),
+        };
         
         // Update portfolio metrics after processing
-        let stats = self.engine.get_statistics();
+        let stats = self.engine().get_statistics();
         self.metrics_collector.update_portfolio_metrics(&stats);
+        let positions: Vec<_> = self.accounts
+            .iter()
+            .flat_map(|(_, engine)| engine.position_manager().get_open_positions())
+            .collect();
+        self.metrics_collector.update_position_metrics(&positions);
+        self.metrics_collector.update_scenario_analysis(self.engine().run_scenarios(&Scenario::defaults()));
+        self.metrics_collector.update_account_metrics(self.accounts.all_statistics());
+        self.metrics_collector.update_attribution_metrics(
+            self.accounts
+                .iter()
+                .map(|(id, engine)| AccountAttribution {
+                    account_id: id.to_string(),
+                    attribution: engine.position_manager().pnl_attribution(),
+                })
+                .collect(),
+        );
+        let throttled = self.accounts
+            .iter()
+            .map(|(_, engine)| engine.get_statistics().signals_throttled.total())
+            .sum();
+        self.metrics_collector.update_throttled_signals(throttled);
         
         result
     }
 
-    /// Update market price for a symbol
+    /// Update market price for a symbol in every account
     pub fn update_market_price(&self, symbol: Symbol, price: f64) {
-        self.engine.update_price(symbol.clone(), price);
+        self.accounts.update_price(&symbol, price);
         
         // Update market data metrics
         self.metrics_collector.update_market_data(symbol, price);
     }
 
-    /// Get current trading statistics
+    /// Record traded volume for a symbol in every account
+    pub fn update_market_volume(&self, symbol: &Symbol, volume: f64) {
+        self.accounts.update_volume(symbol, volume);
+        self.metrics_collector.record_market_volume(symbol, volume);
+    }
+
+    /// Record the displayed book of a symbol in every account
+    pub fn update_market_book(&self, book: &exchanges::UniversalOrderBook) {
+        self.accounts.update_book(&book.symbol, &book.bids, &book.asks);
+    }
+
+    /// Record the cross-venue mark of a symbol in every account, for
+    /// positions marked from the composite
+    pub fn update_composite_price(&self, symbol: &Symbol, price: f64) {
+        self.accounts.update_composite_price(symbol, price);
+    }
+
+    /// Get current trading statistics of the default account
     pub fn get_statistics(&self) -> TradingStatistics {
-        self.engine.get_statistics()
+        self.engine().get_statistics()
+    }
+
+    /// Get trading statistics and P&L attribution of the default account
+    pub fn get_statistics_detailed(&self) -> DetailedStatistics {
+        self.engine().get_statistics_detailed()
+    }
+
+    /// Summary statistics of one account
+    pub fn account_statistics(&self, account_id: &str) -> Option<AccountStatistics> {
+        self.accounts.statistics(account_id)
+    }
+
+    /// Summary statistics of all accounts combined
+    pub fn consolidated_statistics(&self) -> AccountStatistics {
+        self.accounts.consolidated_statistics()
     }
 
-    /// Get access to position manager for detailed position info
+    /// Loss if every open position of the default account hit its stop, as a
+    /// fraction of equity
+    pub fn portfolio_heat(&self) -> f64 {
+        self.engine().portfolio_heat()
+    }
+
+    /// Hypothetical P&L and margin of the default account's open positions
+    /// under each scenario, e.g. `Scenario::defaults()`
+    pub fn scenario_analysis(&self, scenarios: &[Scenario]) -> ScenarioReport {
+        self.engine().run_scenarios(scenarios)
+    }
+
+    /// Routing rules for untagged signals; rules can be changed while trading
+    pub fn router(&self) -> &Arc<SignalRouter> {
+        &self.router
+    }
+
+    /// Outcomes of closed positions, tagged with the `signal_id` of the signal
+    /// that opened them, for the prediction engine to learn from
+    pub fn subscribe_trade_outcomes(&self) -> tokio::sync::broadcast::Receiver<TradeOutcome> {
+        self.accounts.subscribe_outcomes()
+    }
+
+    /// What became of each signal once an account processed it: the orders it
+    /// submitted, or why it was rejected, throttled or ignored. Tagged with
+    /// the signal's `signal_id`, so the source can adapt to its rejections.
+    pub fn subscribe_signal_results(&self) -> tokio::sync::broadcast::Receiver<SignalResult> {
+        self.accounts.subscribe_signal_results()
+    }
+
+    /// POST every trade outcome to a callback URL as JSON
+    pub fn add_outcome_webhook(&self, config: OutcomeWebhookConfig) -> tokio::task::JoinHandle<()> {
+        self.accounts.outcome_publisher().spawn_webhook(config)
+    }
+
+    /// All accounts, the default account first
+    pub fn accounts(&self) -> &Accounts {
+        &self.accounts
+    }
+
+    fn engine(&self) -> &PaperTradingEngine {
+        self.accounts.default_engine()
+    }
+
+    /// Get access to the default account's position manager for detailed position info
     pub fn positions(&self) -> &std::sync::Arc<PositionManager> {
-        self.engine.position_manager()
+        self.engine().position_manager()
     }
 
-    /// Get access to risk manager for risk metrics
+    /// Get access to the default account's risk manager for risk metrics
     pub fn risk_manager(&self) -> &std::sync::Arc<RiskManager> {
-        self.engine.risk_manager()
+        self.engine().risk_manager()
+    }
+
+    /// Get access to the default account's order manager for pending and filled orders
+    pub fn orders(&self) -> &std::sync::Arc<OrderManager> {
+        self.engine().order_manager()
+    }
+
+    /// Default account configuration
+    pub fn config(&self) -> &PaperTradingConfig {
+        self.engine().config()
     }
 
     /// Get access to metrics collector for Grafana integration
@@ -113,12 +431,28 @@ impl NeuromorphicPaperTrader {
         &self.metrics_collector
     }
 
-    /// Start Grafana metrics API server
-    pub async fn start_metrics_api(&self, port: u16) {
-        let api_server = MetricsApiServer::new(self.metrics_collector.clone(), port);
-        tokio::spawn(async move {
-            api_server.start().await;
-        });
+    /// Build an end-of-session report from the default account's closed positions, order fills and signal history
+    pub fn session_report(&self) -> SessionReport {
+        ReportGenerator::new(self.engine().config().initial_capital)
+            .with_currency(&self.engine().config().reporting_currency)
+            .with_clock(self.engine().clock().clone())
+            .generate(
+                self.engine().position_manager(),
+                &self.metrics_collector.get_signal_history(),
+            )
+            .with_shortfall(self.engine().order_manager().get_statistics().shortfall)
+    }
+
+    /// Metrics API server over every account's positions and orders, taking
+    /// signals for each account
+    pub fn metrics_api(&self, config: &ApiConfig) -> Result<MetricsApiServer> {
+        let server = MetricsApiServer::with_config(self.metrics_collector.clone(), config)?;
+        Ok(self.accounts.iter().fold(server, |server, (id, engine)| server.with_engine(id, engine)))
+    }
+
+    /// Start Grafana metrics API server; it stops when the handle is shut down or dropped
+    pub fn start_metrics_api(&self, port: u16) -> Result<ApiHandle> {
+        self.metrics_api(&ApiConfig { port, ..ApiConfig::default() })?.spawn()
     }
 }
 
@@ -133,38 +467,142 @@ impl Default for AutonomousConfig {
             enable_auto_trading: true,
             min_opportunity_confidence: 0.75,
             portfolio_heat: 0.1,
+            opportunity_cooldown: Duration::from_secs(300),
+            accounts: BTreeMap::new(),
+            routes: Vec::new(),
+            daily_state_path: None,
+            shadow_mode: false,
+            watch_config: false,
+            api: ApiConfig::default(),
+            metrics: MetricsConfig::default(),
+        }
+    }
+}
+
+impl AutonomousConfig {
+    /// Notional to trade for an opportunity: its `position_size` share of
+    /// capital, cut so the loss at its stop stays within `risk_per_trade` of
+    /// capital, scaled down by its `risk_score`, and within the portfolio heat
+    /// left over `heat` (capital already at risk, as a fraction)
+    pub fn trade_notional(&self, opportunity: &TradingOpportunity, capital: f64, heat: f64) -> f64 {
+        let entry = opportunity.entry_price;
+        let stop_distance = match opportunity.stop_loss {
+            Some(stop) if entry > 0.0 && stop > 0.0 && stop != entry => (entry - stop).abs() / entry,
+            _ => self.trading_config.risk_limits.stop_loss_pct / 100.0, // The stop the engine attaches
+        };
+        if capital <= 0.0 || stop_distance <= 0.0 {
+            return 0.0;
         }
+
+        let heat_left = (self.portfolio_heat - heat).max(0.0);
+        let risk = (self.risk_per_trade * (1.0 - opportunity.risk_score).clamp(0.0, 1.0)).min(heat_left);
+        (capital * opportunity.position_size).min(capital * risk / stop_distance).max(0.0)
     }
 }
 
 impl AutonomousTradingSystem {
     /// Create a new autonomous trading system
     pub fn new(config: AutonomousConfig) -> Self {
-        let paper_trader = NeuromorphicPaperTrader::new(config.trading_config.clone());
+        let mut paper_trader = NeuromorphicPaperTrader::new(config.trading_config.clone());
+        for (id, account) in &config.accounts {
+            if let Err(e) = paper_trader.add_account(id.clone(), account.clone()) {
+                warn!(account = %id, error = %e, "Skipping account");
+            }
+        }
+        for route in &config.routes {
+            if let Err(e) = paper_trader.router().add_rule(route.clone()) {
+                warn!(route = %route.name, error = %e, "Skipping route");
+            }
+        }
+        paper_trader.accounts().histograms().set_buckets(&config.metrics.histograms);
+        match paper_trader.metrics_collector().open_series(&config.metrics.series) {
+            Ok(0) => {}
+            Ok(loaded) => info!(samples = loaded, "Loaded metric history"),
+            Err(e) => warn!(error = %format!("{:#}", e), "Keeping metric history in memory only"),
+        }
         let market_scanner = MarketScannerService::new(config.scanner_config.clone());
+        paper_trader.set_price_history(market_scanner.price_history().clone());
+        let clock = paper_trader.engine().clock().clone();
+        let control = AutonomousControl::new(clock.clone());
+        let daily = match &config.daily_state_path {
+            Some(path) => DailyLedger::open(path, clock.clone()).unwrap_or_else(|e| {
+                warn!(error = %e, "Keeping daily counters in memory");
+                DailyLedger::new(clock)
+            }),
+            None => DailyLedger::new(clock),
+        };
 
         Self {
             paper_trader,
             market_scanner,
-            config,
+            market_feed: None,
+            config: parking_lot::RwLock::new(config),
+            config_files: Vec::new(),
+            config_watcher: None,
+            recent_trades: DashMap::new(),
+            suppressed: AtomicU64::new(0),
+            control,
+            daily,
+            api: None,
+            snapshots: None,
         }
     }
 
+    /// Reload risk limits, strategy parameters and screener criteria from
+    /// `paths` when asked through the control handle
+    pub fn with_config_files(mut self, paths: Vec<PathBuf>) -> Self {
+        self.config_files = paths;
+        self
+    }
+
+    /// Snapshot every account into `dir` each `interval` while trading, and
+    /// once more on `stop`
+    pub fn with_snapshots(mut self, dir: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.snapshots = Some((dir.into(), interval));
+        self
+    }
+
+    /// Stream exchange market data to the scanner, and through it to the
+    /// engine. The feed is started and stopped with the system.
+    pub fn set_market_feed(&mut self, feed: UnifiedMarketFeed) {
+        self.market_scanner = self.market_scanner.clone().with_market_feed(feed.subscribe());
+        self.market_feed = Some(feed);
+    }
+
+    /// Drive the neuromorphic strategy from classified spike patterns, e.g.
+    /// of a spike bridge encoding the market feed
+    pub fn set_spike_patterns(&mut self, patterns: Arc<SpikePatterns>) {
+        self.market_scanner = self.market_scanner.clone().with_spike_patterns(patterns);
+    }
+
     /// Start the autonomous trading system
     pub async fn start(&mut self) -> Result<()> {
-        println!("🤖 Starting Autonomous Neuromorphic Trading System");
+        info!("Starting autonomous trading system");
         
         self.paper_trader.start().await?;
-        self.paper_trader.start_metrics_api(3002).await;
+        let api = self
+            .paper_trader
+            .metrics_api(&self.config.read().api)?
+            .with_scanner(self.market_scanner.clone())
+            .with_control(self.control.clone())
+            .spawn()?;
+        self.api = Some(api);
+        if self.config.read().watch_config && !self.config_files.is_empty() {
+            self.config_watcher = Some(config::reload::watch_files(self.config_files.clone(), self.control.clone()));
+        }
+        
+        if let Some(feed) = &mut self.market_feed {
+            feed.start().await?;
+        }
         
-        println!("🚀 Starting market scanner...");
+        info!("Starting market scanner");
         let (market_stream, opportunity_stream) = match self.market_scanner.start().await {
             Ok(streams) => {
-                println!("✅ Market scanner started successfully");
+                info!("Market scanner started");
                 streams
             }
             Err(e) => {
-                println!("❌ Failed to start market scanner: {}", e);
+                error!(error = %e, "Failed to start market scanner");
                 return Err(e);
             }
         };
@@ -180,114 +618,363 @@ impl AutonomousTradingSystem {
         mut market_stream: tokio::sync::broadcast::Receiver<MarketData>,
         mut opportunity_stream: tokio::sync::broadcast::Receiver<TradingOpportunity>,
     ) -> Result<()> {
-        let mut daily_trades = 0;
-        let mut last_reset = chrono::Utc::now().date_naive();
-        
-        println!("📊 Market scanner started - monitoring {} exchanges", 
-                 self.config.scanner_config.included_exchanges.len());
-        println!("🎯 Auto-trading: {} | Min confidence: {:.0}%", 
-                 if self.config.enable_auto_trading { "ENABLED" } else { "DISABLED" },
-                 self.config.min_opportunity_confidence * 100.0);
+        let today = self.daily.today();
+        {
+            let config = self.config.read();
+            info!(
+                exchanges = config.scanner_config.included_exchanges.len(),
+                auto_trading = config.enable_auto_trading,
+                shadow_mode = config.shadow_mode,
+                min_confidence = config.min_opportunity_confidence,
+                daily_trades = today.trades,
+                kill_switch = today.kill_switch.is_some(),
+                "Trading loop started"
+            );
+        }
 
+        // Intervals rather than sleeps in the select, which every message would restart
+        let mut health_check = tokio::time::interval(tokio::time::Duration::from_secs(5));
+        let status_every = tokio::time::Duration::from_secs(60);
+        let mut status_report = tokio::time::interval_at(tokio::time::Instant::now() + status_every, status_every);
+        let snapshot_every = self.snapshots.as_ref().map_or(status_every, |(_, interval)| *interval);
+        let mut snapshot = tokio::time::interval_at(tokio::time::Instant::now() + snapshot_every, snapshot_every);
         loop {
             tokio::select! {
                 Ok(market_data) = market_stream.recv() => {
+                    // The best bid and offer across venues, for positions marked from it
+                    let composite = self.market_feed.as_ref().zip(market_data.exchange).and_then(|(feed, exchange)| {
+                        feed.quotes().mark_price(&market_data.symbol, exchange, chrono::Utc::now().timestamp_millis() as u64)
+                    });
+                    if let Some(composite) = composite {
+                        self.paper_trader.update_composite_price(&market_data.symbol, composite);
+                    }
                     self.paper_trader.update_market_price(
                         market_data.symbol.clone(), 
                         market_data.price
                     );
+                    self.paper_trader.update_market_volume(&market_data.symbol, market_data.volume);
                 }
                 
-                Ok(opportunity) = opportunity_stream.recv() => {
-                    let today = chrono::Utc::now().date_naive();
-                    if today != last_reset {
-                        daily_trades = 0;
-                        last_reset = today;
-                        println!("📅 Daily trade counter reset");
+                Ok(opportunity) = opportunity_stream.recv() => self.handle_opportunity(&opportunity).await,
+                
+                _ = self.control.flatten_requested() => {
+                    if let Err(e) = self.flatten_all().await {
+                        warn!(error = %e, "Failed to flatten");
                     }
-
-                    if self.should_execute_trade(&opportunity, daily_trades).await {
-                        match self.execute_opportunity(&opportunity).await {
-                            Ok(_) => {
-                                daily_trades += 1;
-                                println!("✅ Executed trade #{}: {} {} @ ${:.2} (confidence: {:.1}%)",
-                                        daily_trades,
-                                        opportunity.strategy,
-                                        opportunity.symbol.as_str(),
-                                        opportunity.entry_price,
-                                        opportunity.confidence * 100.0);
-                            }
-                            Err(e) => {
-                                println!("❌ Failed to execute trade: {}", e);
-                            }
-                        }
-                    } else {
-                        println!("⏭️  Skipped opportunity: {} {} (confidence: {:.1}%, reason: filtering)",
-                                opportunity.symbol.as_str(),
-                                opportunity.strategy,
-                                opportunity.confidence * 100.0);
+                }
+                
+                _ = self.control.reload_requested() => {
+                    if let Err(e) = self.reload_config_files() {
+                        warn!(error = %e, "Config reload failed, keeping the current settings");
                     }
                 }
                 
-                _ = tokio::time::sleep(tokio::time::Duration::from_secs(60)) => {
+                _ = health_check.tick() => {
+                    self.report_health().await;
+                    // Scoring clones each engine's returns, too much for every tick
+                    let metrics = self.paper_trader.metrics_collector();
+                    metrics.update_competitors(self.paper_trader.accounts().competitors());
+                }
+                
+                _ = status_report.tick() => {
                     self.print_status().await;
                 }
+                
+                _ = snapshot.tick(), if self.snapshots.is_some() => {
+                    self.save_snapshots();
+                }
             }
         }
     }
 
-    /// Determine if we should execute a trading opportunity
-    async fn should_execute_trade(&self, opportunity: &TradingOpportunity, daily_trades: usize) -> bool {
-        if !self.config.enable_auto_trading {
-            return false;
+    /// Determine if we should execute a trading opportunity, or why not
+    /// Trade a scanned opportunity, or record why not
+    async fn handle_opportunity(&self, opportunity: &TradingOpportunity) {
+        match self.should_execute_trade(opportunity).await {
+            Ok(()) if self.config.read().shadow_mode => self.shadow_opportunity(opportunity),
+            Ok(()) => match self.execute_opportunity(opportunity).await {
+                Ok(notional) => {
+                    self.start_cooldown(opportunity);
+                    let daily_trades = self.daily.record_trade();
+                    self.market_scanner.opportunities().mark_executed(opportunity);
+                    self.record_decision(opportunity, Decision::Executed, Some(notional), None);
+                    info!(
+                        trade = daily_trades,
+                        symbol = %opportunity.symbol,
+                        strategy = %opportunity.strategy,
+                        price = opportunity.entry_price,
+                        confidence = opportunity.confidence,
+                        notional,
+                        "Executed opportunity"
+                    );
+                }
+                Err(e) => {
+                    warn!(symbol = %opportunity.symbol, error = %e, "Failed to execute opportunity");
+                }
+            },
+            Err(reason) => self.skip_opportunity(opportunity, reason),
+        }
+    }
+
+    async fn should_execute_trade(&self, opportunity: &TradingOpportunity) -> Result<(), SkipReason> {
+        // One view of the parameters for the whole decision, even mid-reload
+        let params = StrategyParams::of(&self.config.read());
+        if !params.enable_auto_trading {
+            return Err(SkipReason::AutoTradingDisabled);
+        }
+
+        if self.control.is_paused() {
+            return Err(SkipReason::Paused);
         }
 
-        if opportunity.confidence < self.config.min_opportunity_confidence {
-            return false;
+        let today = self.check_daily_loss();
+        if today.kill_switch.is_some() {
+            return Err(SkipReason::KillSwitch);
         }
 
-        if daily_trades >= self.config.max_daily_trades {
-            return false;
+        if self.control.is_blacklisted(&opportunity.symbol) {
+            return Err(SkipReason::Blacklisted);
+        }
+
+        if opportunity.confidence < self.control.min_confidence(params.min_opportunity_confidence) {
+            return Err(SkipReason::Confidence);
+        }
+
+        if today.trades >= params.max_daily_trades {
+            return Err(SkipReason::DailyLimit);
         }
 
         let stats = self.paper_trader.get_statistics();
         let current_positions = stats.position_stats.open_positions;
         
-        if current_positions >= self.config.max_positions as u64 {
-            return false;
+        if current_positions >= params.max_positions as u64 {
+            return Err(SkipReason::MaxPositions);
         }
 
-        let portfolio_risk = (current_positions as f64 * self.config.risk_per_trade).min(self.config.portfolio_heat);
-        if portfolio_risk >= self.config.portfolio_heat {
-            return false;
+        if self.paper_trader.portfolio_heat() >= params.portfolio_heat {
+            return Err(SkipReason::PortfolioHeat);
         }
 
-        true
+        // Strategies keep firing while their setup holds; trade it once
+        let now_ms = self.paper_trader.engine().clock().now_ms();
+        let cooldown_ms = params.opportunity_cooldown_secs * 1000;
+        let key = (opportunity.symbol.clone(), opportunity.strategy.clone());
+        if self.recent_trades.get(&key).is_some_and(|last| now_ms < *last + cooldown_ms) {
+            let suppressed = self.suppressed.fetch_add(1, Ordering::Relaxed) + 1;
+            self.paper_trader.metrics_collector().update_suppressed_opportunities(suppressed);
+            return Err(SkipReason::Cooldown);
+        }
+
+        Ok(())
     }
 
-    /// Execute a trading opportunity
-    async fn execute_opportunity(&self, opportunity: &TradingOpportunity) -> Result<()> {
-        let signal_action = match opportunity.expected_move {
-            x if x > 0.0 => SignalAction::Buy { size_hint: Some(opportunity.position_size) },
-            x if x < 0.0 => SignalAction::Sell { size_hint: Some(opportunity.position_size) },
-            _ => SignalAction::Hold,
-        };
+    /// Suppress the opportunity's symbol and strategy for the cooldown, once traded
+    fn start_cooldown(&self, opportunity: &TradingOpportunity) {
+        let now_ms = self.paper_trader.engine().clock().now_ms();
+        let cooldown_ms = StrategyParams::of(&self.config.read()).opportunity_cooldown_secs * 1000;
+        self.recent_trades.retain(|_, last| now_ms < *last + cooldown_ms);
+        self.recent_trades.insert((opportunity.symbol.clone(), opportunity.strategy.clone()), now_ms);
+    }
 
-        let signal = TradingSignal {
+    fn record_decision(&self, opportunity: &TradingOpportunity, decision: Decision, notional: Option<f64>, fill_price: Option<f64>) {
+        self.paper_trader.metrics_collector().record_decision(DecisionRecord {
+            timestamp: self.paper_trader.engine().clock().now(),
             symbol: opportunity.symbol.clone(),
-            exchange: Exchange::NYSE, // Default exchange
-            action: signal_action,
+            strategy: opportunity.strategy.clone(),
             confidence: opportunity.confidence,
-            urgency: 0.8,
-            metadata: SignalMetadata {
-                spike_count: 100,
-                pattern_strength: opportunity.confidence,
-                volatility: opportunity.risk_score,
-                market_regime: "autonomous".to_string(),
-            },
-        };
+            decision,
+            shadow: self.config.read().shadow_mode,
+            notional,
+            fill_price,
+        });
+    }
+
+    fn skip_opportunity(&self, opportunity: &TradingOpportunity, reason: SkipReason) {
+        self.record_decision(opportunity, Decision::Skipped { reason }, None, None);
+        info!(
+            symbol = %opportunity.symbol,
+            strategy = %opportunity.strategy,
+            confidence = opportunity.confidence,
+            %reason,
+            shadow = self.config.read().shadow_mode,
+            "Skipped opportunity"
+        );
+    }
+
+    /// Record the trade an opportunity would have made, filled by the default
+    /// account's slippage model at its latest price
+    fn shadow_opportunity(&self, opportunity: &TradingOpportunity) {
+        let notional = self.trade_notional(opportunity);
+        if notional <= 0.0 {
+            return self.skip_opportunity(opportunity, SkipReason::NoRiskBudget);
+        }
+
+        let engine = self.paper_trader.engine();
+        let price = engine.market_price(&opportunity.symbol).unwrap_or(opportunity.entry_price);
+        let fill_price = (price > 0.0).then(|| {
+            let side = opportunity.side().unwrap_or(Side::Buy);
+            engine.order_manager().estimate_fill_price(price, &side, notional / price)
+        });
+        self.record_decision(opportunity, Decision::WouldExecute, Some(notional), fill_price);
+        self.start_cooldown(opportunity);
+        info!(
+            symbol = %opportunity.symbol,
+            strategy = %opportunity.strategy,
+            confidence = opportunity.confidence,
+            notional,
+            fill_price,
+            "Shadow trade"
+        );
+    }
+
+    /// Bring today's realized P&L up to date and trip the kill switch once the
+    /// loss reaches the default account's daily loss limit
+    fn check_daily_loss(&self) -> DailyCounters {
+        let realized = self.paper_trader.consolidated_statistics().realized_pnl;
+        let pnl = self.daily.record_realized_pnl(realized);
+        let limit = self.paper_trader.engine().risk_manager().get_limits().max_daily_loss;
+        if -pnl >= limit && self.daily.trip_kill_switch(format!("Daily loss {:.2} reached limit {:.2}", -pnl, limit)) {
+            warn!(daily_pnl = pnl, limit, "Kill switch tripped, no more trades today");
+        }
+        self.daily.today()
+    }
+
+    /// Today's trades, realized P&L and kill switch
+    pub fn daily_counters(&self) -> DailyCounters {
+        self.daily.today()
+    }
+
+    /// Opportunities skipped because the same strategy recently fired on the symbol
+    pub fn suppressed_opportunities(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    /// Notional the risk budget allows for an opportunity now
+    fn trade_notional(&self, opportunity: &TradingOpportunity) -> f64 {
+        let capital = self.paper_trader.get_statistics().capital;
+        self.config.read().trade_notional(opportunity, capital, self.paper_trader.portfolio_heat())
+    }
+
+    /// Execute a trading opportunity on its exchange, or the first scanned
+    /// exchange when the data didn't say, sized to the risk budget; returns
+    /// the notional traded
+    async fn execute_opportunity(&self, opportunity: &TradingOpportunity) -> Result<f64> {
+        let notional = self.trade_notional(opportunity);
+        if notional <= 0.0 {
+            anyhow::bail!("No risk budget left for {}", opportunity.symbol);
+        }
+
+        let exchange = self.config.read().scanner_config.included_exchanges.first().copied().unwrap_or(Exchange::NYSE);
+        let signal = opportunity.to_signal(exchange, notional);
+        self.paper_trader.process_prediction_signal(signal).await?;
+        Ok(notional)
+    }
+
+    /// Cancel every account's resting orders and close all open positions
+    pub async fn flatten_all(&self) -> Result<()> {
+        let mut result = Ok(());
+        for (account, engine) in self.paper_trader.accounts().iter() {
+            for order in engine.order_manager().get_active_orders() {
+                if let Err(e) = engine.order_manager().cancel_order(&order.id) {
+                    warn!(account, order_id = %order.id, error = %e, "Failed to cancel order while flattening");
+                }
+            }
+            for position in engine.position_manager().get_open_positions() {
+                let closed = engine.process_signal(TradingSignal {
+                    symbol: position.symbol,
+                    exchange: position.exchange,
+                    action: SignalAction::Close { position_id: Some(position.id) },
+                    confidence: 1.0,
+                    urgency: 1.0,
+                    metadata: SignalMetadata {
+                        market_regime: "manual_flatten".to_string(),
+                        ..Default::default()
+                    },
+                }).await;
+                if result.is_ok() {
+                    result = closed;
+                }
+            }
+        }
+        info!("Flattened all accounts");
+        result
+    }
+
+    /// Risk limits of every account, strategy parameters and screener criteria in effect
+    pub fn reloadable_settings(&self) -> ReloadableSettings {
+        ReloadableSettings {
+            risk_limits: self.paper_trader.accounts().iter().map(|(id, engine)| (id.to_string(), engine.risk_manager().get_limits())).collect(),
+            strategy: StrategyParams::of(&self.config.read()),
+            screening: self.market_scanner.screener().criteria(),
+        }
+    }
+
+    /// Apply the reloadable settings of `config` and log what changed. Each
+    /// group is swapped whole, so no check sees half of an update.
+    pub fn reload(&self, config: &RunConfig) -> Vec<ConfigChange> {
+        let next = ReloadableSettings::from_run_config(config);
+        let changes = self.reloadable_settings().diff(&next);
+        for (id, engine) in self.paper_trader.accounts().iter() {
+            if let Some(limits) = next.risk_limits.get(id) {
+                engine.risk_manager().set_limits(limits.clone());
+            }
+        }
+        next.strategy.apply(&mut self.config.write());
+        self.market_scanner.screener().set_criteria(next.screening);
+
+        for change in &changes {
+            info!(
+                key = %change.key,
+                old = change.old.as_deref().unwrap_or("unset"),
+                new = change.new.as_deref().unwrap_or("unset"),
+                "Setting reloaded"
+            );
+        }
+        info!(changes = changes.len(), "Config reloaded");
+        changes
+    }
+
+    /// Read and validate the config files given to `with_config_files`, then
+    /// `reload`; nothing changes if they don't load
+    pub fn reload_config_files(&self) -> Result<Vec<ConfigChange>> {
+        if self.config_files.is_empty() {
+            anyhow::bail!("No config files to reload");
+        }
+        let config = RunConfig::load(&self.config_files)?;
+        Ok(self.reload(&config))
+    }
+
+    /// Pause, resume, blacklist and confidence overrides; the same handle
+    /// backs the control API
+    pub fn control(&self) -> &AutonomousControl {
+        &self.control
+    }
 
-        self.paper_trader.process_prediction_signal(signal).await
+    /// Tell the metrics collector, and so the readiness probe, whether the
+    /// engines run and how the exchange streams are doing, and what each
+    /// venue contributes to the best bid and offer
+    async fn report_health(&self) {
+        let metrics = self.paper_trader.metrics_collector();
+        for (account, engine) in self.paper_trader.accounts().iter() {
+            metrics.update_engine_running(account, engine.is_running().await);
+        }
+        if let Some(feed) = &self.market_feed {
+            for (exchange, status) in feed.status().await {
+                let last_data_age = feed.get_statistics(exchange).and_then(|s| s.last_update).map(|t| t.elapsed());
+                metrics.update_feed_status(exchange, status, last_data_age);
+            }
+            metrics.update_composite_quotes(feed.quotes().snapshot(chrono::Utc::now().timestamp_millis() as u64));
+        }
+    }
+
+    /// Write the account snapshots, if configured; failures are logged and
+    /// the previous snapshots kept
+    fn save_snapshots(&self) {
+        let Some((dir, _)) = &self.snapshots else { return };
+        match self.paper_trader.accounts().save_snapshots(dir) {
+            Ok(()) => tracing::debug!(dir = %dir.display(), "Accounts snapshotted"),
+            Err(e) => warn!(error = %format!("{:#}", e), "Failed to snapshot accounts"),
+        }
     }
 
     /// Print current system status
@@ -304,20 +991,56 @@ impl AutonomousTradingSystem {
                 overall_sentiment: 0.0,
             });
 
+        let control = self.control.status();
+        let today = self.check_daily_loss();
+        let skipped = self.paper_trader.metrics_collector().get_signal_metrics().opportunities_skipped;
+        let top_skip_reason = skipped.iter().max_by_key(|(_, count)| **count).map(|(reason, _)| reason.clone());
+
         // Update metrics collector with current trading statistics
         self.paper_trader.metrics_collector().update_portfolio_metrics(&stats);
+        self.paper_trader.metrics_collector().update_position_metrics(&self.paper_trader.positions().get_open_positions());
+        self.paper_trader.metrics_collector().update_scenario_analysis(self.paper_trader.scenario_analysis(&Scenario::defaults()));
+
+        info!(
+            capital = stats.capital,
+            currency = %stats.currency,
+            return_pct = stats.total_return_pct,
+            open_positions = stats.position_stats.open_positions,
+            symbols_tracked = market_metrics.total_symbols_tracked,
+            opportunities = market_metrics.opportunities_detected,
+            market_volatility = market_metrics.market_volatility,
+            win_rate = stats.position_stats.win_rate,
+            sharpe = stats.risk_metrics.sharpe_ratio,
+            max_drawdown = stats.risk_metrics.max_drawdown,
+            portfolio_heat = self.paper_trader.portfolio_heat(),
+            market_regime = ?market_metrics.market_regime,
+            sentiment = market_metrics.overall_sentiment,
+            suppressed_opportunities = self.suppressed_opportunities(),
+            skipped_opportunities = skipped.values().sum::<u64>(),
+            top_skip_reason = top_skip_reason.as_deref().unwrap_or("none"),
+            daily_trades = today.trades,
+            daily_pnl = today.realized_pnl,
+            kill_switch = today.kill_switch.is_some(),
+            paused = control.paused,
+            blacklisted = control.blacklist.len(),
+            min_confidence = self.control.min_confidence(self.config.read().min_opportunity_confidence),
+            "Autonomous trading status"
+        );
+    }
+
+    /// Paper trader executing the opportunities
+    pub fn paper_trader(&self) -> &NeuromorphicPaperTrader {
+        &self.paper_trader
+    }
+
+    /// Mutable paper trader, e.g. to attach execution venues before `start`
+    pub fn paper_trader_mut(&mut self) -> &mut NeuromorphicPaperTrader {
+        &mut self.paper_trader
+    }
 
-        println!("\n📈 AUTONOMOUS TRADING STATUS");
-        println!("💰 Portfolio: ${:.2} | P&L: {:.2}% | Positions: {}",
-                stats.capital, stats.total_return_pct, stats.position_stats.open_positions);
-        println!("📊 Symbols tracked: {} | Opportunities: {} | Market volatility: {:.1}%",
-                market_metrics.total_symbols_tracked,
-                market_metrics.opportunities_detected,
-                market_metrics.market_volatility * 100.0);
-        println!("🎯 Win rate: {:.1}% | Sharpe: {:.2} | Max drawdown: {:.1}%",
-                stats.position_stats.win_rate, stats.risk_metrics.sharpe_ratio, stats.risk_metrics.max_drawdown);
-        println!("🔄 Market regime: {:?} | Sentiment: {:.2}\n",
-                market_metrics.market_regime, market_metrics.overall_sentiment);
+    /// Symbols the scanner analyses, e.g. to add discovery sources before `start`
+    pub fn universe(&self) -> &Arc<UniverseManager> {
+        self.market_scanner.universe()
     }
 
     /// Get top opportunities currently available
@@ -331,9 +1054,21 @@ impl AutonomousTradingSystem {
     }
 
     /// Stop the autonomous trading system
-    pub async fn stop(&self) -> Result<()> {
-        println!("🛑 Stopping Autonomous Trading System");
-        self.paper_trader.stop().await
+    pub async fn stop(&mut self) -> Result<()> {
+        info!("Stopping autonomous trading system");
+        if let Some(watcher) = self.config_watcher.take() {
+            watcher.abort();
+        }
+        if let Some(api) = self.api.take() {
+            api.shutdown().await?;
+        }
+        if let Some(feed) = &mut self.market_feed {
+            feed.stop().await?;
+        }
+        self.paper_trader.stop().await?;
+        self.save_snapshots();
+        self.paper_trader.metrics_collector().flush_series();
+        Ok(())
     }
 }
 
@@ -362,4 +1097,214 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(10)).await;
         assert!(trader.stop().await.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_execute_signal_sync_waits_for_the_fill() {
+        let mut trader = NeuromorphicPaperTrader::new(PaperTradingConfig::default());
+        trader.start().await.unwrap();
+        let symbol = Symbol::new("BTC-USD");
+        trader.update_market_price(symbol.clone(), 50_000.0);
+        let signal = |action| TradingSignal {
+            symbol: symbol.clone(),
+            exchange: Exchange::Binance,
+            action,
+            confidence: 0.8,
+            urgency: 0.9,
+            metadata: SignalMetadata::default(),
+        };
+
+        let ticks = async {
+            for _ in 0..20 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                trader.update_market_price(symbol.clone(), 50_000.0);
+            }
+        };
+        let (execution, _) = tokio::join!(trader.execute_signal_sync(signal(SignalAction::Buy { size_hint: Some(1_000.0) }), Duration::from_secs(2)), ticks);
+        let execution = execution.unwrap();
+        let position = trader.positions().get_open_positions().remove(0);
+        assert_eq!((execution.position_id.as_deref(), execution.quantity), (Some(position.id.as_str()), position.quantity));
+        assert!((execution.fill_price / 50_000.0 - 1.0).abs() < 0.01);
+
+        let hold = trader.execute_signal_sync(signal(SignalAction::Hold), Duration::from_secs(2)).await;
+        assert!(matches!(hold, Err(ExecutionError::Ignored(_))));
+        trader.stop().await.unwrap();
+
+        // A stepped engine runs no tasks, so nothing handles the queued signal
+        let mut stepped = NeuromorphicPaperTrader::new(PaperTradingConfig { stepped: true, ..Default::default() });
+        stepped.start().await.unwrap();
+        stepped.update_market_price(symbol.clone(), 50_000.0);
+        let buy = stepped.execute_signal_sync(signal(SignalAction::Buy { size_hint: Some(1_000.0) }), Duration::from_millis(50)).await;
+        assert_eq!(buy, Err(ExecutionError::Timeout(Duration::from_millis(50))));
+        assert!(stepped.positions().get_open_positions().is_empty());
+        stepped.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repeated_opportunity_is_suppressed() {
+        let system = AutonomousTradingSystem::new(AutonomousConfig::default());
+        let mut opportunity = TradingOpportunity {
+            symbol: Symbol::new("BTCUSDT"),
+            strategy: "Momentum Breakout".to_string(),
+            confidence: 0.9,
+            expected_move: 3.0,
+            time_horizon: "1-3 days".to_string(),
+            entry_price: 50000.0,
+            stop_loss: None,
+            take_profit: None,
+            position_size: 0.02,
+            reasoning: String::new(),
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            exchange: None,
+        };
+
+        assert_eq!(system.should_execute_trade(&opportunity).await, Ok(()));
+        system.start_cooldown(&opportunity);
+        assert_eq!(system.should_execute_trade(&opportunity).await, Err(SkipReason::Cooldown));
+        assert_eq!(system.suppressed_opportunities(), 1);
+
+        // Another strategy on the same symbol is a different opportunity
+        opportunity.strategy = "Gap and Go".to_string();
+        assert_eq!(system.should_execute_trade(&opportunity).await, Ok(()));
+        let metrics = system.paper_trader().metrics_collector().get_signal_metrics();
+        assert_eq!(metrics.opportunities_suppressed, 1);
+
+        // Controls gate the trade before the cooldown does
+        opportunity.strategy = "Volatility Breakout".to_string();
+        system.control().pause();
+        assert_eq!(system.should_execute_trade(&opportunity).await, Err(SkipReason::Paused));
+        system.control().resume();
+        system.control().blacklist(Symbol::new("BTC-USDT"));
+        assert_eq!(system.should_execute_trade(&opportunity).await, Err(SkipReason::Blacklisted));
+        system.control().unblacklist(&opportunity.symbol);
+        system.control().raise_min_confidence(0.95, Duration::from_secs(60));
+        assert_eq!(system.should_execute_trade(&opportunity).await, Err(SkipReason::Confidence));
+        system.control().clear_min_confidence();
+        assert_eq!(system.should_execute_trade(&opportunity).await, Ok(()));
+        assert_eq!(system.suppressed_opportunities(), 1);
+
+        // A tripped kill switch stops trading for the day
+        opportunity.strategy = "Mean Reversion".to_string();
+        system.daily.trip_kill_switch("test");
+        assert_eq!(system.should_execute_trade(&opportunity).await, Err(SkipReason::KillSwitch));
+        assert!(system.daily_counters().kill_switch.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_execution_does_not_start_the_cooldown() {
+        let system = AutonomousTradingSystem::new(AutonomousConfig::default());
+        let mut opportunity = TradingOpportunity {
+            symbol: Symbol::new("BTCUSDT"),
+            strategy: "Momentum Breakout".to_string(),
+            confidence: 0.9,
+            expected_move: 3.0,
+            time_horizon: "1-3 days".to_string(),
+            entry_price: 50000.0,
+            stop_loss: None,
+            take_profit: None,
+            position_size: 0.0, // No risk budget, so execution fails
+            reasoning: String::new(),
+            risk_score: 0.1,
+            timestamp: chrono::Utc::now(),
+            exchange: None,
+        };
+
+        system.handle_opportunity(&opportunity).await;
+        assert_eq!(system.should_execute_trade(&opportunity).await, Ok(()));
+        assert_eq!(system.daily_counters().trades, 0);
+
+        opportunity.position_size = 0.02;
+        system.handle_opportunity(&opportunity).await;
+        assert_eq!(system.should_execute_trade(&opportunity).await, Err(SkipReason::Cooldown));
+    }
+
+    #[tokio::test]
+    async fn test_shadow_mode_records_decisions() {
+        let system = AutonomousTradingSystem::new(AutonomousConfig {
+            shadow_mode: true,
+            ..Default::default()
+        });
+        let opportunity = TradingOpportunity {
+            symbol: Symbol::new("ETHUSDT"),
+            strategy: "Momentum Breakout".to_string(),
+            confidence: 0.9,
+            expected_move: 3.0,
+            time_horizon: "1-3 days".to_string(),
+            entry_price: 2000.0,
+            stop_loss: Some(1960.0),
+            take_profit: None,
+            position_size: 0.02,
+            reasoning: String::new(),
+            risk_score: 0.0,
+            timestamp: chrono::Utc::now(),
+            exchange: None,
+        };
+
+        system.shadow_opportunity(&opportunity);
+        system.skip_opportunity(&opportunity, SkipReason::Cooldown);
+
+        let decisions = system.paper_trader().metrics_collector().get_decision_history(10);
+        assert_eq!(decisions[0].decision, Decision::Skipped { reason: SkipReason::Cooldown });
+        let shadow = &decisions[1];
+        assert_eq!(shadow.decision, Decision::WouldExecute);
+        assert!(shadow.shadow);
+        assert_eq!(shadow.notional, Some(2000.0));
+        // Default slippage of 0.01% on a buy
+        assert!((shadow.fill_price.unwrap() - 2000.2).abs() < 1e-9);
+
+        system.skip_opportunity(&opportunity, SkipReason::Cooldown);
+        system.skip_opportunity(&opportunity, SkipReason::PortfolioHeat);
+        let skipped = system.paper_trader().metrics_collector().get_signal_metrics().opportunities_skipped;
+        assert_eq!(skipped.get("cooldown"), Some(&2));
+        assert_eq!(skipped.get("portfolio_heat"), Some(&1));
+        assert_eq!(system.paper_trader().get_statistics().signals_processed, 0);
+    }
+
+    #[test]
+    fn test_trade_notional_follows_risk_budget() {
+        let config = AutonomousConfig::default(); // 2% risk per trade, 10% heat
+        let mut opportunity = TradingOpportunity {
+            symbol: Symbol::new("ETHUSDT"),
+            strategy: "Volume Spike Momentum".to_string(),
+            confidence: 0.8,
+            expected_move: 4.0,
+            time_horizon: "4-8 hours".to_string(),
+            entry_price: 2000.0,
+            stop_loss: Some(1900.0),
+            take_profit: Some(2200.0),
+            position_size: 0.5,
+            reasoning: String::new(),
+            risk_score: 0.25,
+            timestamp: chrono::Utc::now(),
+            exchange: None,
+        };
+
+        // 1.5% of capital at risk over a 5% stop
+        assert!((config.trade_notional(&opportunity, 100_000.0, 0.0) - 30_000.0).abs() < 1e-6);
+        // Only 1% of heat is left
+        assert!((config.trade_notional(&opportunity, 100_000.0, 0.09) - 20_000.0).abs() < 1e-6);
+        assert_eq!(config.trade_notional(&opportunity, 100_000.0, 0.1), 0.0);
+        // The opportunity's own share of capital caps a wide budget
+        opportunity.position_size = 0.05;
+        assert!((config.trade_notional(&opportunity, 100_000.0, 0.0) - 5_000.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_reload_swaps_limits_and_strategy() {
+        let source = |text: &str| vec![(PathBuf::from("paper-trader.toml"), text.to_string())];
+        let config = RunConfig::from_sources(source(""), vec![]).unwrap();
+        let system = AutonomousTradingSystem::new(config.autonomous.clone());
+        assert!(system.reload(&config).is_empty());
+
+        let edited = RunConfig::from_sources(
+            source("[trading.risk_limits]\nmax_daily_loss = 500.0\n[autonomous]\nmax_daily_trades = 3\n"),
+            vec![],
+        )
+        .unwrap();
+        let keys: Vec<String> = system.reload(&edited).into_iter().map(|c| c.key).collect();
+        assert_eq!(keys, vec!["trading.risk_limits.max_daily_loss", "autonomous.max_daily_trades"]);
+        assert_eq!(system.paper_trader().engine().risk_manager().get_limits().max_daily_loss, 500.0);
+        assert_eq!(system.reloadable_settings().strategy.max_daily_trades, 3);
+        assert!(system.reload_config_files().is_err()); // No files given
+    }
 }
\ No newline at end of file
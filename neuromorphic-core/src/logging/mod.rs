@@ -0,0 +1,74 @@
+//! Log output setup for the trading binaries
+//!
+//! Human-readable output by default, or one JSON object per line for
+//! ingestion into Loki/ELK.
+
+use anyhow::Result;
+use std::str::FromStr;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable selecting the log format ("pretty" or "json")
+pub const LOG_FORMAT_ENV: &str = "NEUROMORPHIC_LOG_FORMAT";
+
+/// Log output format
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+impl LogFormat {
+    /// Read the format from `NEUROMORPHIC_LOG_FORMAT`, falling back to pretty output
+    pub fn from_env() -> Self {
+        std::env::var(LOG_FORMAT_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "pretty" | "text" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => anyhow::bail!("Unknown log format '{}', expected 'pretty' or 'json'", other),
+        }
+    }
+}
+
+/// Install the global subscriber. `RUST_LOG` overrides `default_filter` when set.
+pub fn init_logging(format: LogFormat, default_filter: &str) -> Result<()> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(default_filter));
+
+    let result = match format {
+        LogFormat::Pretty => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .try_init(),
+        // Span fields (signal symbol, action, order id) are included on every event
+        LogFormat::Json => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init(),
+    };
+
+    result.map_err(|e| anyhow::anyhow!("Failed to initialize logging: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!(" Pretty ".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -1,166 +1,451 @@
 //! Neuromorphic Paper Trading Application
-//! 
-//! Standalone paper trading system that can integrate with neuromorphic
-//! prediction engines for signal generation.
+//!
+//! Command line front end: `run` trades live through the autonomous system,
+//! `backtest` and `replay` simulate offline, and `export` / `report` / `tax`
+//! render a saved session. `download` fills the kline cache `backtest
+//! --cache` reads, with the parquet feature.
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use chrono::Datelike;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use neuromorphic_core::backtest::{self, AdjustmentMode, CorporateActions, Simulator};
+use neuromorphic_core::exchanges::{
+    BinanceRestConfig, BinanceRestConnector, BinanceUserDataConfig, BinanceUserDataStream, ExchangeConnector, KlineInterval,
+};
+use neuromorphic_core::market_scanner::MarketData;
+use neuromorphic_core::logging::{init_logging, LogFormat};
+use neuromorphic_core::paper_trading::{Competitor, Reconciler, Scoreboard, StreamingVenue};
+use neuromorphic_core::{Exchange, ExecutionMode, Locale, ReportFormat, RunConfig, Service, SessionReport};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::signal;
 use tracing::{info, warn};
 
-mod paper_trading;
-mod exchanges;
+/// Session file written by run/backtest/replay and read by export/report
+const DEFAULT_SESSION_FILE: &str = "session-report.json";
 
-use paper_trading::{PaperTradingEngine, PaperTradingConfig, TradingSignal, SignalAction, SignalMetadata};
-use exchanges::{Symbol, Exchange, Side};
+/// Kline cache written by download and read by backtest --cache
+#[cfg(feature = "parquet")]
+const DEFAULT_KLINE_CACHE: &str = "data/klines";
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
-        .init();
-
-    info!("🚀 Starting Neuromorphic Paper Trading System");
-
-    // Configure paper trading
-    let config = PaperTradingConfig {
-        initial_capital: 100_000.0,
-        commission_rate: 0.1, // 0.1%
-        enable_stop_loss: true,
-        enable_take_profit: true,
-        update_interval: Duration::from_millis(100),
-        ..Default::default()
-    };
+#[derive(Parser)]
+#[command(name = "paper-trader", version, about = "Neuromorphic paper trading")]
+struct Cli {
+    /// Log output format (pretty or json); defaults to NEUROMORPHIC_LOG_FORMAT
+    #[arg(long, global = true)]
+    log_format: Option<LogFormat>,
 
-    // Create paper trading engine
-    let mut engine = PaperTradingEngine::new(config);
-    
-    // Start the engine
-    engine.start().await?;
-    info!("✅ Paper trading engine started");
-
-    // Wrap engine in Arc to share between tasks
-    let engine = Arc::new(engine);
-
-    // Simulate some market data and trading signals
-    let engine_clone = engine.clone();
-    tokio::spawn(async move {
-        simulate_trading_session(&*engine_clone).await;
-    });
-
-    // Run until interrupted
-    info!("📈 Paper trading system is running. Press Ctrl+C to stop.");
-    
-    let shutdown = signal::ctrl_c();
-    tokio::select! {
-        _ = shutdown => {
-            info!("🛑 Shutdown signal received...");
-        }
-        _ = tokio::time::sleep(Duration::from_secs(3600)) => {
-            info!("⏰ Max runtime reached...");
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the autonomous trading system until SIGTERM or Ctrl+C
+    Run {
+        #[command(flatten)]
+        config: ConfigArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Backtest the scanner strategies over a directory of <SYMBOL>.csv bar files
+    /// or the kline cache
+    Backtest {
+        /// Directory of timestamp,open,high,low,close,volume files
+        #[arg(long, required_unless_present = "cache", conflicts_with = "cache")]
+        data: Option<PathBuf>,
+        /// Kline cache filled by download; every cached symbol is backtested
+        #[arg(long)]
+        cache: Option<PathBuf>,
+        /// Kline interval of the cached bars
+        #[arg(long, default_value = "1m")]
+        interval: KlineInterval,
+        /// Splits and dividends of the backtested equities, as symbol,ex_date,action,value rows
+        #[arg(long)]
+        corporate_actions: Option<PathBuf>,
+        /// Fetch the splits and dividends from Alpaca instead; needs APCA_API_KEY_ID and APCA_API_SECRET_KEY
+        #[arg(long, conflicts_with = "corporate_actions")]
+        fetch_corporate_actions: bool,
+        /// Adjust the bars for corporate actions, or trade the raw bars and adjust the positions
+        #[arg(long, value_enum, default_value_t = AdjustmentArg::Adjusted)]
+        adjustment: AdjustmentArg,
+        #[command(flatten)]
+        config: ConfigArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Download historical klines into the Parquet cache, from where the
+    /// previous download stopped
+    #[cfg(feature = "parquet")]
+    Download {
+        #[arg(long, value_enum, default_value_t = KlineSourceArg::Binance)]
+        source: KlineSourceArg,
+        /// Symbol to download; repeat for several. Alpaca needs APCA_API_KEY_ID and APCA_API_SECRET_KEY.
+        #[arg(long = "symbol", required = true)]
+        symbols: Vec<String>,
+        #[arg(long, default_value = "1m")]
+        interval: KlineInterval,
+        /// Open time of the oldest kline, e.g. 2025-01-01T00:00:00Z
+        #[arg(long)]
+        start: chrono::DateTime<chrono::Utc>,
+        /// Open time of the newest kline; defaults to now
+        #[arg(long)]
+        end: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(long, default_value = DEFAULT_KLINE_CACHE)]
+        cache: PathBuf,
+    },
+    /// Replay a recorded JSON Lines session of prices and signals
+    Replay {
+        #[arg(long)]
+        file: PathBuf,
+        #[command(flatten)]
+        config: ConfigArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Export the trades of a saved session
+    Export {
+        #[arg(long, default_value = DEFAULT_SESSION_FILE)]
+        input: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Summarize the lots of a saved session closed in one year, by symbol
+    Tax {
+        #[arg(long, default_value = DEFAULT_SESSION_FILE)]
+        input: PathBuf,
+        /// Calendar year (UTC); defaults to the year the session ended in
+        #[arg(long)]
+        year: Option<i32>,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// One CSV row per lot instead of per symbol
+        #[arg(long)]
+        lots: bool,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Render the report of a saved session
+    Report {
+        #[arg(long, default_value = DEFAULT_SESSION_FILE)]
+        input: PathBuf,
+        #[arg(long, value_enum, default_value_t = ReportFormatArg::Markdown)]
+        format: ReportFormatArg,
+        /// Digit grouping, decimal separator and currency symbol placement
+        #[arg(long, value_enum, default_value_t = LocaleArg::En)]
+        locale: LocaleArg,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Score saved sessions against each other, e.g. the entries of a tournament
+    Leaderboard {
+        /// Session file; repeat for each competitor, named after its file
+        #[arg(long = "input", required = true)]
+        inputs: Vec<PathBuf>,
+        /// Config with the score weights in [api.scoring]
+        #[command(flatten)]
+        config: ConfigArgs,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Print the OpenAPI document of the HTTP API, e.g. to generate clients
+    Openapi {
+        /// Write to a file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    /// Config file; repeat to layer several, later files override earlier ones
+    #[arg(short, long = "config")]
+    config: Vec<PathBuf>,
+}
+
+impl ConfigArgs {
+    fn load(&self) -> Result<RunConfig> {
+        Ok(RunConfig::load(&self.config)?)
+    }
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// Where to save the session report
+    #[arg(long, default_value = DEFAULT_SESSION_FILE)]
+    session_out: PathBuf,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[cfg(feature = "parquet")]
+#[derive(Clone, Copy, ValueEnum)]
+enum KlineSourceArg {
+    Binance,
+    Coinbase,
+    Alpaca,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AdjustmentArg {
+    Adjusted,
+    Raw,
+}
+
+impl From<AdjustmentArg> for AdjustmentMode {
+    fn from(mode: AdjustmentArg) -> Self {
+        match mode {
+            AdjustmentArg::Adjusted => AdjustmentMode::Adjusted,
+            AdjustmentArg::Raw => AdjustmentMode::Raw,
         }
     }
+}
 
-    // Stop the engine
-    engine.stop().await?;
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormatArg {
+    Markdown,
+    Html,
+}
 
-    // Print final statistics
-    let stats = engine.get_statistics();
-    info!("📊 Final Statistics:");
-    info!("  Final Capital: ${:.2}", stats.capital);
-    info!("  Total P&L: ${:.2} ({:.2}%)", stats.total_pnl, stats.total_return_pct);
-    info!("  Signals Processed: {}", stats.signals_processed);
-    info!("  Signals Executed: {}", stats.signals_executed);
-    info!("  Win Rate: {:.1}%", stats.position_stats.win_rate);
+impl From<ReportFormatArg> for ReportFormat {
+    fn from(format: ReportFormatArg) -> Self {
+        match format {
+            ReportFormatArg::Markdown => ReportFormat::Markdown,
+            ReportFormatArg::Html => ReportFormat::Html,
+        }
+    }
+}
 
-    info!("✅ Paper trading system shutdown complete");
-    Ok(())
+#[derive(Clone, Copy, ValueEnum)]
+enum LocaleArg {
+    En,
+    De,
+    Fr,
 }
 
-/// Simulate a trading session with mock signals
-async fn simulate_trading_session(engine: &PaperTradingEngine) {
-    let symbols = vec![
-        Symbol::new("BTC-USD"),
-        Symbol::new("ETH-USD"),
-        Symbol::new("SOL-USD"),
-    ];
-
-    let mut price_btc = 50000.0;
-    let mut price_eth = 3000.0;
-    let mut price_sol = 100.0;
-
-    for i in 0..1000 {
-        // Simulate price movements
-        price_btc += (rand::random_f64() - 0.5) * 100.0;
-        price_eth += (rand::random_f64() - 0.5) * 10.0;
-        price_sol += (rand::random_f64() - 0.5) * 2.0;
-
-        // Update prices
-        engine.update_price(symbols[0].clone(), price_btc);
-        engine.update_price(symbols[1].clone(), price_eth);
-        engine.update_price(symbols[2].clone(), price_sol);
-
-        // Generate trading signals every 10 iterations
-        if i % 10 == 0 {
-            let symbol = &symbols[i % symbols.len()];
-            let confidence = 0.6 + (rand::random_f64() * 0.3);
-            let urgency = rand::random_f64();
-
-            let action = match rand::random_u32() % 4 {
-                0 => SignalAction::Buy { size_hint: Some(1000.0) },
-                1 => SignalAction::Sell { size_hint: Some(500.0) },
-                2 => SignalAction::Close { position_id: None },
-                _ => SignalAction::Hold,
+impl From<LocaleArg> for Locale {
+    fn from(locale: LocaleArg) -> Self {
+        match locale {
+            LocaleArg::En => Locale::En,
+            LocaleArg::De => Locale::De,
+            LocaleArg::Fr => Locale::Fr,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    init_logging(cli.log_format.unwrap_or_else(LogFormat::from_env), "info")?;
+
+    match cli.command {
+        Command::Run { config, output } => run(config.load()?, config.config, &output.session_out).await,
+        Command::Backtest { data, cache, interval, corporate_actions, fetch_corporate_actions, adjustment, config, output } => {
+            let config = config.load()?;
+            let bars = load_bars(data.as_deref(), cache.as_deref(), interval)?;
+            let actions = load_corporate_actions(corporate_actions.as_deref(), fetch_corporate_actions, &bars).await?;
+            info!(bars = bars.len(), corporate_actions = actions.len(), "Starting backtest");
+
+            let simulator = Simulator::new(config.trading.clone()).await?.with_corporate_actions(actions, adjustment.into());
+            simulator.backtest(bars, &config.autonomous).await?;
+            save_session(&simulator.finish().await?, &output.session_out)
+        }
+        #[cfg(feature = "parquet")]
+        Command::Download { source, symbols, interval, start, end, cache } => {
+            use neuromorphic_core::data::{AlpacaKlines, CoinbaseKlines, KlineCache, KlineDownloader};
+            use neuromorphic_core::exchanges::{KlineSource, Symbol};
+
+            let source: Arc<dyn KlineSource> = match source {
+                KlineSourceArg::Binance => Arc::new(
+                    BinanceRestConnector::connect(BinanceRestConfig::public()).await.context("Failed to reach Binance")?,
+                ),
+                KlineSourceArg::Coinbase => Arc::new(CoinbaseKlines::new()),
+                KlineSourceArg::Alpaca => Arc::new(AlpacaKlines::from_env()?),
             };
+            let downloader = KlineDownloader::new(source, KlineCache::new(cache));
+            let end = end.unwrap_or_else(chrono::Utc::now);
+            for symbol in symbols {
+                downloader.update(&Symbol::new(symbol), interval, start, end).await?;
+            }
+            Ok(())
+        }
+        Command::Replay { file, config, output } => {
+            let config = config.load()?;
+            let events = backtest::read_session(&file)?;
+            info!(events = events.len(), file = %file.display(), "Starting replay");
 
-            let signal = TradingSignal {
-                symbol: symbol.clone(),
-                exchange: Exchange::Binance,
-                action,
-                confidence,
-                urgency,
-                metadata: SignalMetadata {
-                    spike_count: (rand::random_u32() as u64) % 1000,
-                    pattern_strength: confidence,
-                    market_regime: "trending".to_string(),
-                    volatility: 0.02,
-                },
+            let simulator = Simulator::new(config.trading).await?;
+            simulator.replay(events).await?;
+            save_session(&simulator.finish().await?, &output.session_out)
+        }
+        Command::Export { input, format, out } => {
+            let report = load_session(&input)?;
+            let rendered = match format {
+                ExportFormat::Csv => report.trades_csv(),
+                ExportFormat::Json => serde_json::to_string_pretty(&report.trades)?,
+            };
+            write_output(&rendered, out.as_deref())
+        }
+        Command::Tax { input, year, format, lots, out } => {
+            let report = load_session(&input)?;
+            let summary = report.tax_summary(year.unwrap_or_else(|| report.generated_at.year()));
+            let rendered = match format {
+                ExportFormat::Csv if lots => summary.lots_csv(),
+                ExportFormat::Csv => summary.to_csv(),
+                ExportFormat::Json => serde_json::to_string_pretty(&summary)?,
+            };
+            write_output(&rendered, out.as_deref())
+        }
+        Command::Report { input, format, locale, out } => {
+            let report = load_session(&input)?;
+            write_output(&report.render_localized(format.into(), locale.into()), out.as_deref())
+        }
+        Command::Leaderboard { inputs, config, format, out } => {
+            let config = config.load()?;
+            let competitors = inputs
+                .iter()
+                .map(|path| {
+                    let id = path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+                    Ok(Competitor::from_session(id, &load_session(path)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let scoreboard = Scoreboard::score(&competitors, &config.autonomous.api.scoring);
+            let rendered = match format {
+                ExportFormat::Csv => scoreboard.to_csv(),
+                ExportFormat::Json => serde_json::to_string_pretty(&scoreboard)?,
             };
+            write_output(&rendered, out.as_deref())
+        }
+        Command::Openapi { out } => write_output(&serde_json::to_string_pretty(&neuromorphic_core::api::openapi::document())?, out.as_deref()),
+    }
+}
 
-            if let Err(e) = engine.process_signal(signal).await {
-                warn!("Error processing signal: {}", e);
-            }
+/// Bars of the CSV directory, or else of the kline cache
+#[cfg_attr(not(feature = "parquet"), allow(unused_variables))]
+fn load_bars(data: Option<&Path>, cache: Option<&Path>, interval: KlineInterval) -> Result<Vec<MarketData>> {
+    match (data, cache) {
+        (Some(dir), _) => {
+            info!(data = %dir.display(), "Loading bars");
+            backtest::load_bars(dir)
+        }
+        #[cfg(feature = "parquet")]
+        (None, Some(cache)) => {
+            info!(cache = %cache.display(), %interval, "Loading cached klines");
+            neuromorphic_core::data::KlineCache::new(cache).market_data(interval)
         }
+        #[cfg(not(feature = "parquet"))]
+        (None, Some(_)) => bail!("Built without the parquet feature; it is needed to read the kline cache"),
+        (None, None) => bail!("Backtest needs --data or --cache"),
+    }
+}
 
-        tokio::time::sleep(Duration::from_millis(50)).await;
+/// Corporate actions from the CSV file or Alpaca, for the symbols and dates of the bars
+async fn load_corporate_actions(path: Option<&Path>, fetch: bool, bars: &[MarketData]) -> Result<CorporateActions> {
+    if let Some(path) = path {
+        return CorporateActions::load_csv(path);
     }
+    let (true, Some(first), Some(last)) = (fetch, bars.first(), bars.last()) else {
+        return Ok(CorporateActions::default());
+    };
+    let key_id = std::env::var("APCA_API_KEY_ID").context("APCA_API_KEY_ID is not set")?;
+    let secret_key = std::env::var("APCA_API_SECRET_KEY").context("APCA_API_SECRET_KEY is not set")?;
+    let symbols: Vec<_> = bars.iter().map(|bar| bar.symbol.clone()).collect::<std::collections::HashSet<_>>().into_iter().collect();
+    info!(symbols = symbols.len(), "Fetching corporate actions from Alpaca");
+    CorporateActions::fetch_alpaca(&key_id, &secret_key, &symbols, first.timestamp.date_naive(), last.timestamp.date_naive()).await
 }
 
-// Mock random function for demo purposes
-mod rand {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    pub fn random_f64() -> f64 {
-        let mut hasher = DefaultHasher::new();
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos()
-            .hash(&mut hasher);
-        (hasher.finish() as f64) / (u64::MAX as f64)
+/// Trade live as a `Service` until SIGTERM or Ctrl+C, then save the session.
+/// SIGHUP reloads `config_files`.
+async fn run(config: RunConfig, config_files: Vec<PathBuf>, session_out: &Path) -> Result<()> {
+    let mut service = Service::new(&config, config_files)?;
+
+    for exchange in &config.autonomous.scanner_config.universe.exchanges {
+        match exchange {
+            Exchange::Binance => {
+                let connector = BinanceRestConnector::connect(BinanceRestConfig::public())
+                    .await
+                    .context("Failed to reach Binance for symbol discovery")?;
+                service.system().universe().add_source(Arc::new(connector));
+            }
+            other => warn!(exchange = %other, "Symbol discovery is not supported on this exchange"),
+        }
+    }
+
+    if service.system().paper_trader().accounts().execution_modes().contains(&ExecutionMode::BinanceTestnet) {
+        // Validation guarantees credentials when an account trades on the testnet
+        let credentials = config
+            .credentials_for(Exchange::Binance)
+            .context("binance_testnet execution needs [credentials.binance]")?;
+        let connector = BinanceRestConnector::connect(BinanceRestConfig::testnet(
+            credentials.api_key.clone(),
+            credentials.api_secret.clone(),
+        ))
+        .await
+        .context("Failed to reach the Binance Spot Testnet")?;
+        let connector = Arc::new(connector);
+
+        // Fills are confirmed by the user data stream rather than by polling
+        let stream = Arc::new(BinanceUserDataStream::new(connector.clone(), BinanceUserDataConfig::testnet()));
+        service.add_task(stream.clone().spawn());
+        let venue = Arc::new(StreamingVenue::new(connector.clone(), stream));
+
+        let attached = service
+            .system_mut()
+            .paper_trader_mut()
+            .set_execution_venue(ExecutionMode::BinanceTestnet, venue);
+        info!(accounts = attached, "Executing on the Binance Spot Testnet");
+
+        if config.reconciliation.enabled {
+            let mut reconciler = Reconciler::new(connector, config.reconciliation.clone());
+            for (id, engine) in service.system().paper_trader().accounts().iter() {
+                if engine.config().execution == ExecutionMode::BinanceTestnet {
+                    reconciler.track(id, engine);
+                }
+            }
+            service.add_task(Arc::new(reconciler).spawn());
+        }
     }
 
-    pub fn random_u32() -> u32 {
-        let mut hasher = DefaultHasher::new();
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos()
-            .hash(&mut hasher);
-        hasher.finish() as u32
+    let report = service.run_forever().await?;
+    save_session(&report, session_out)
+}
+
+fn save_session(report: &SessionReport, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    info!(
+        path = %path.display(),
+        trades = report.trades.len(),
+        total_pnl = report.total_pnl,
+        return_pct = report.total_return_pct,
+        "Session saved"
+    );
+    Ok(())
+}
+
+fn load_session(path: &Path) -> Result<SessionReport> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read session {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid session file {}", path.display()))
+}
+
+fn write_output(content: &str, out: Option<&Path>) -> Result<()> {
+    match out {
+        Some(path) => std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display())),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
     }
-}
\ No newline at end of file
+}
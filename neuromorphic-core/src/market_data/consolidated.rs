@@ -0,0 +1,317 @@
+//! Best bid and offer across venues
+//!
+//! The same instrument streams from several exchanges under different names,
+//! BTCUSDT on Binance and BTC-USD on Coinbase. Each venue's top of book and
+//! last trade are kept under the canonical symbol the `SymbolMapper` gives
+//! them, and combined into a composite: the highest bid and lowest ask of the
+//! venues that quoted within `max_quote_age`, and the latest trade of any
+//! venue. Positions are marked at the composite mid, or the latest trade
+//! without fresh quotes, rather than at whichever venue ticked last. A venue
+//! bidding above another's ask is an arbitrage opportunity.
+//!
+//! How much each venue contributes is counted: the updates it sent, and how
+//! many updates of the symbol left it with the best bid or ask.
+
+use super::{SymbolMapper, UnifiedMarketEvent};
+use crate::exchanges::{ArbitrageOpportunity, Exchange, Side, Symbol, UniversalMarketData};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// One venue's side of a composite quote
+#[derive(Clone, Debug)]
+struct VenueQuote {
+    exchange: Exchange,
+    symbol: Symbol, // As the venue names it
+    bid: Option<(f64, f64)>, // Price and size
+    ask: Option<(f64, f64)>,
+    quoted_ms: u64,
+    last: Option<f64>,
+    traded_ms: u64,
+    updates: u64,
+    at_best_bid: u64,
+    at_best_ask: u64,
+}
+
+impl VenueQuote {
+    fn new(exchange: Exchange, symbol: Symbol) -> Self {
+        Self {
+            exchange,
+            symbol,
+            bid: None,
+            ask: None,
+            quoted_ms: 0,
+            last: None,
+            traded_ms: 0,
+            updates: 0,
+            at_best_bid: 0,
+            at_best_ask: 0,
+        }
+    }
+
+    fn is_fresh(&self, now_ms: u64, max_age_ms: u64) -> bool {
+        self.quoted_ms + max_age_ms >= now_ms
+    }
+}
+
+/// Best bid and ask of the fresh venues, as indices into the venue list
+#[derive(Default)]
+struct Best {
+    bid: Option<usize>,
+    ask: Option<usize>,
+}
+
+fn best(venues: &[VenueQuote], now_ms: u64, max_age_ms: u64) -> Best {
+    let mut best = Best::default();
+    for (i, venue) in venues.iter().enumerate().filter(|(_, v)| v.is_fresh(now_ms, max_age_ms)) {
+        if venue.bid.is_some_and(|(price, _)| best.bid.and_then(|b| venues[b].bid).is_none_or(|(top, _)| price > top)) {
+            best.bid = Some(i);
+        }
+        if venue.ask.is_some_and(|(price, _)| best.ask.and_then(|a| venues[a].ask).is_none_or(|(top, _)| price < top)) {
+            best.ask = Some(i);
+        }
+    }
+    best
+}
+
+/// A venue's quote and contribution to the composite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VenueContribution {
+    pub exchange: Exchange,
+    pub symbol: Symbol, // As the venue names it
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub last: Option<f64>,
+    pub fresh: bool, // Quoted recently enough to count towards the composite
+    pub updates: u64,
+    pub at_best_bid: u64, // Updates of the symbol after which this venue had the best bid
+    pub at_best_ask: u64,
+}
+
+/// Composite quote of a canonical symbol across venues
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CompositeQuote {
+    pub symbol: Symbol,
+    pub bid: Option<f64>,
+    pub bid_size: Option<f64>,
+    pub bid_exchange: Option<Exchange>,
+    pub ask: Option<f64>,
+    pub ask_size: Option<f64>,
+    pub ask_exchange: Option<Exchange>,
+    pub last: Option<f64>, // Latest trade on any venue
+    pub last_exchange: Option<Exchange>,
+    pub mark: Option<f64>, // Mid of the best bid and ask, else the last trade
+    pub crossed_bps: Option<f64>, // How far the best bid is above the best ask, when on different venues
+    pub venues: Vec<VenueContribution>,
+}
+
+/// Per-venue quotes of every canonical symbol and their composites
+pub struct ConsolidatedQuotes {
+    mapper: SymbolMapper,
+    max_age_ms: u64,
+    symbols: DashMap<Symbol, Vec<VenueQuote>>, // By canonical symbol
+}
+
+impl ConsolidatedQuotes {
+    /// Composites of the venues that quoted within `max_quote_age`
+    pub fn new(max_quote_age: Duration) -> Self {
+        Self {
+            mapper: SymbolMapper::new(),
+            max_age_ms: max_quote_age.as_millis() as u64,
+            symbols: DashMap::new(),
+        }
+    }
+
+    /// Canonical symbol of a venue's symbol; the symbol itself when unmapped
+    pub fn canonical(&self, symbol: &Symbol, exchange: Exchange) -> Symbol {
+        self.mapper.from_exchange(symbol.as_str(), exchange).unwrap_or_else(|| symbol.clone())
+    }
+
+    /// Take a quote, top of book or trade into its venue's quote. Freshness
+    /// goes by the local receive time.
+    pub fn update(&self, event: &UnifiedMarketEvent) {
+        let (bid, ask, last) = match &event.data {
+            UniversalMarketData::Quote(q) => (Some((q.bid_price, q.bid_size)), Some((q.ask_price, q.ask_size)), None),
+            UniversalMarketData::OrderBook(b) => (b.bids.first().copied(), b.asks.first().copied(), None),
+            UniversalMarketData::Trade(t) => (None, None, Some(t.price)),
+            UniversalMarketData::Kline(_) => return,
+        };
+        let valid = |level: Option<(f64, f64)>| level.filter(|(price, _)| price.is_finite() && *price > 0.0);
+        let (bid, ask, last) = (valid(bid), valid(ask), last.filter(|p| p.is_finite() && *p > 0.0));
+        if bid.is_none() && ask.is_none() && last.is_none() {
+            return;
+        }
+
+        let symbol = event.symbol();
+        let mut venues = self.symbols.entry(self.canonical(symbol, event.exchange)).or_default();
+        let i = match venues.iter().position(|v| v.exchange == event.exchange) {
+            Some(i) => i,
+            None => {
+                venues.push(VenueQuote::new(event.exchange, symbol.clone()));
+                venues.len() - 1
+            }
+        };
+        let venue = &mut venues[i];
+        venue.updates += 1;
+        if let Some(price) = last {
+            venue.last = Some(price);
+            venue.traded_ms = event.received_time;
+        } else {
+            (venue.bid, venue.ask, venue.quoted_ms) = (bid, ask, event.received_time);
+        }
+
+        let best = best(&venues, event.received_time, self.max_age_ms);
+        if let Some(b) = best.bid {
+            venues[b].at_best_bid += 1;
+        }
+        if let Some(a) = best.ask {
+            venues[a].at_best_ask += 1;
+        }
+    }
+
+    /// Composite quote of a canonical symbol
+    pub fn composite(&self, symbol: &Symbol, now_ms: u64) -> Option<CompositeQuote> {
+        let venues = self.symbols.get(symbol)?;
+        let best = best(&venues, now_ms, self.max_age_ms);
+        let bid = best.bid.and_then(|b| venues[b].bid);
+        let ask = best.ask.and_then(|a| venues[a].ask);
+        let latest = venues.iter().filter(|v| v.last.is_some()).max_by_key(|v| v.traded_ms);
+        let mark = match (bid, ask) {
+            (Some((bid, _)), Some((ask, _))) => Some((bid + ask) / 2.0),
+            _ => latest.and_then(|v| v.last),
+        };
+        let crossed_bps = match (best.bid, best.ask, bid, ask) {
+            (Some(b), Some(a), Some((bid, _)), Some((ask, _))) if b != a && bid > ask => Some((bid - ask) / ask * 10_000.0),
+            _ => None,
+        };
+        Some(CompositeQuote {
+            symbol: symbol.clone(),
+            bid: bid.map(|(price, _)| price),
+            bid_size: bid.map(|(_, size)| size),
+            bid_exchange: best.bid.map(|b| venues[b].exchange),
+            ask: ask.map(|(price, _)| price),
+            ask_size: ask.map(|(_, size)| size),
+            ask_exchange: best.ask.map(|a| venues[a].exchange),
+            last: latest.and_then(|v| v.last),
+            last_exchange: latest.map(|v| v.exchange),
+            mark,
+            crossed_bps,
+            venues: venues
+                .iter()
+                .map(|v| VenueContribution {
+                    exchange: v.exchange,
+                    symbol: v.symbol.clone(),
+                    bid: v.bid.map(|(price, _)| price),
+                    ask: v.ask.map(|(price, _)| price),
+                    last: v.last,
+                    fresh: v.is_fresh(now_ms, self.max_age_ms),
+                    updates: v.updates,
+                    at_best_bid: v.at_best_bid,
+                    at_best_ask: v.at_best_ask,
+                })
+                .collect(),
+        })
+    }
+
+    /// Price to mark a venue's symbol at: the composite mark of its canonical symbol
+    pub fn mark_price(&self, symbol: &Symbol, exchange: Exchange, now_ms: u64) -> Option<f64> {
+        self.composite(&self.canonical(symbol, exchange), now_ms)?.mark
+    }
+
+    /// Composites of every symbol, by symbol
+    pub fn snapshot(&self, now_ms: u64) -> Vec<CompositeQuote> {
+        let symbols: Vec<Symbol> = self.symbols.iter().map(|entry| entry.key().clone()).collect();
+        let mut quotes: Vec<CompositeQuote> = symbols.iter().filter_map(|symbol| self.composite(symbol, now_ms)).collect();
+        quotes.sort_by(|a, b| a.symbol.as_str().cmp(b.symbol.as_str()));
+        quotes
+    }
+
+    /// Buying on one venue and selling on another for at least `min_bps`,
+    /// sized to the smaller of the two top levels
+    pub fn arbitrage(&self, min_bps: f64, now_ms: u64) -> Vec<ArbitrageOpportunity> {
+        let mut opportunities = Vec::new();
+        for entry in self.symbols.iter() {
+            let venues = entry.value();
+            let best = best(venues, now_ms, self.max_age_ms);
+            let (Some(b), Some(a)) = (best.bid, best.ask) else { continue };
+            let (Some((bid, bid_size)), Some((ask, ask_size))) = (venues[b].bid, venues[a].ask) else { continue };
+            let profit_bps = (bid - ask) / ask * 10_000.0;
+            if b == a || profit_bps < min_bps {
+                continue;
+            }
+            opportunities.push(ArbitrageOpportunity {
+                symbol_pair: (venues[a].symbol.to_string(), venues[b].symbol.to_string()),
+                profit_bps,
+                side: Side::Buy,
+                size: bid_size.min(ask_size),
+                exchange_buy: format!("{:?}", venues[a].exchange),
+                exchange_sell: format!("{:?}", venues[b].exchange),
+                detected_at: Instant::now(),
+            });
+        }
+        opportunities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{UniversalQuote, UniversalTrade};
+
+    fn quote(exchange: Exchange, symbol: &str, bid: f64, ask: f64, time: u64) -> UnifiedMarketEvent {
+        let data = UniversalMarketData::Quote(UniversalQuote {
+            exchange,
+            symbol: Symbol::new(symbol),
+            bid_price: bid,
+            bid_size: 1.0,
+            ask_price: ask,
+            ask_size: 2.0,
+            timestamp_exchange: time,
+            timestamp_local: time,
+        });
+        UnifiedMarketEvent::new(exchange, data, time)
+    }
+
+    #[test]
+    fn test_composite_across_venues() {
+        let quotes = ConsolidatedQuotes::new(Duration::from_secs(5));
+        quotes.update(&quote(Exchange::Binance, "BTCUSDT", 100.0, 101.0, 1_000));
+        quotes.update(&quote(Exchange::Coinbase, "BTC-USD", 100.5, 101.5, 2_000));
+        let trade = UniversalMarketData::Trade(UniversalTrade {
+            exchange: Exchange::Kraken,
+            symbol: Symbol::new("XXBTZUSD"),
+            price: 100.8,
+            quantity: 0.1,
+            side: Side::Buy,
+            timestamp_exchange: 3_000,
+            timestamp_local: 3_000,
+            trade_id: "k1".to_string(),
+        });
+        quotes.update(&UnifiedMarketEvent::new(Exchange::Kraken, trade, 3_000));
+
+        // Best bid on Coinbase, best ask on Binance, last trade on Kraken
+        let btc = quotes.composite(&Symbol::new("BTC-USD"), 3_000).unwrap();
+        assert_eq!((btc.bid, btc.bid_exchange), (Some(100.5), Some(Exchange::Coinbase)));
+        assert_eq!((btc.ask, btc.ask_exchange), (Some(101.0), Some(Exchange::Binance)));
+        assert_eq!((btc.last, btc.last_exchange), (Some(100.8), Some(Exchange::Kraken)));
+        assert_eq!(btc.mark, Some(100.75));
+        assert_eq!(btc.crossed_bps, None);
+        assert_eq!(quotes.mark_price(&Symbol::new("BTCUSDT"), Exchange::Binance, 3_000), Some(100.75));
+        let coinbase = btc.venues.iter().find(|v| v.exchange == Exchange::Coinbase).unwrap();
+        assert_eq!((coinbase.updates, coinbase.at_best_bid, coinbase.at_best_ask), (1, 2, 0));
+
+        // Coinbase bids above Binance's ask
+        quotes.update(&quote(Exchange::Coinbase, "BTC-USD", 101.2, 101.6, 4_000));
+        let opportunities = quotes.arbitrage(10.0, 4_000);
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!((opportunities[0].exchange_buy.as_str(), opportunities[0].exchange_sell.as_str()), ("Binance", "Coinbase"));
+        assert!((opportunities[0].profit_bps - 19.8).abs() < 0.1);
+        assert_eq!(opportunities[0].size, 1.0);
+
+        // Binance's quote goes stale: Coinbase alone makes the composite
+        let btc = quotes.composite(&Symbol::new("BTC-USD"), 7_000).unwrap();
+        assert_eq!((btc.bid, btc.ask), (Some(101.2), Some(101.6)));
+        assert!(quotes.arbitrage(10.0, 7_000).is_empty());
+    }
+}
@@ -1,30 +1,58 @@
 //! Bridge between market data and spike encoding
+//!
+//! Every symbol has its own encoder and market state behind a mutex, so
+//! events for different symbols encode in parallel while a symbol's events
+//! are encoded one at a time, in the order they arrive.
+//!
+//! Spikes leave in [`SpikeBatch`]es on a broadcast channel. A batch holds the
+//! spikes of one symbol in time order and is sent once `batch_size` spikes
+//! are buffered, or `max_batch_delay` after its first spike, whichever comes
+//! first. A subscriber that falls more than `spike_buffer_size` batches
+//! behind loses the oldest ones (`RecvError::Lagged`).
 
-use super::unified_feed::{UnifiedMarketEvent, UnifiedMarketFeed};
-use ares_spike_encoding::{SpikeEncoder, Spike, SpikePattern, MarketData};
-use crate::exchanges::{Symbol, Exchange};
-use anyhow::Result;
+use super::unified_feed::UnifiedMarketEvent;
+use crate::exchanges::{Exchange, Symbol, UniversalMarketData};
 use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Arc;
-use tokio::sync::mpsc;
 use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::warn;
 
-/// Quote data structure for spike encoding
-#[derive(Debug, Clone)]
-pub struct QuoteData {
-    pub bid_price: f64,
-    pub ask_price: f64,
-    pub bid_size: f64,
-    pub ask_size: f64,
-    pub timestamp: u64,
+/// One neuron firing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spike {
+    pub neuron_id: u32,
+    /// Event time in Unix microseconds
+    pub timestamp_us: u64,
+    /// 0..=1, strongest at the centre of the neuron's receptive field
+    pub strength: f32,
 }
 
-/// Order book data structure for spike encoding
-#[derive(Debug, Clone)]
-pub struct OrderBookData {
-    pub bids: Vec<(f64, f64)>,
-    pub asks: Vec<(f64, f64)>,
-    pub timestamp: u64,
+/// Spikes of one symbol, oldest first
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpikeBatch {
+    pub symbol: Symbol,
+    pub exchange: Exchange,
+    pub spikes: Vec<Spike>,
+}
+
+/// Market data reduced to what the encoder sees
+#[derive(Clone, Debug)]
+pub struct MarketTick {
+    pub time_ms: u64,
+    pub price: f64,
+    pub previous_price: Option<f64>,
+    pub volume: f64,
+    pub spread_bps: Option<f64>,
+}
+
+/// Turns market ticks into spikes. Encoders are per symbol and may keep state.
+pub trait SpikeEncoder: Send {
+    fn encode(&mut self, tick: &MarketTick, state: &MarketState) -> Vec<Spike>;
 }
 
 /// Market state tracking
@@ -32,15 +60,15 @@ pub struct OrderBookData {
 pub struct MarketState {
     pub last_price: f64,
     pub volume: f64,
-    pub volatility: f64,
-    pub trend: f64,
+    pub volatility: f64, // Mean absolute tick return
+    pub trend: f64,      // Return over the retained history
 }
 
 /// Market state tracker
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct MarketStateTracker {
     pub state: MarketState,
-    price_history: Vec<f64>,
+    price_history: VecDeque<f64>,
     max_history: usize,
 }
 
@@ -48,47 +76,144 @@ impl MarketStateTracker {
     pub fn new() -> Self {
         Self {
             state: MarketState::default(),
-            price_history: Vec::new(),
+            price_history: VecDeque::new(),
             max_history: 100,
         }
     }
 
-    pub fn update_from_trade(&mut self, price: f64, volume: f64) {
+    pub fn update(&mut self, price: f64, volume: f64) {
         self.state.last_price = price;
         self.state.volume += volume;
-        
-        self.price_history.push(price);
+
+        self.price_history.push_back(price);
         if self.price_history.len() > self.max_history {
-            self.price_history.remove(0);
+            self.price_history.pop_front();
+        }
+
+        let returns = self.price_history.len().saturating_sub(1);
+        if returns > 0 {
+            let total: f64 = self
+                .price_history
+                .iter()
+                .zip(self.price_history.iter().skip(1))
+                .map(|(a, b)| (b / a - 1.0).abs())
+                .sum();
+            self.state.volatility = total / returns as f64;
+            self.state.trend = price / self.price_history[0] - 1.0;
         }
-        
-        self.update_volatility();
     }
+}
 
-    pub fn update_from_quote(&mut self, bid: f64, ask: f64, volume: f64) {
-        let mid_price = (bid + ask) / 2.0;
-        self.state.last_price = mid_price;
-        self.state.volume += volume;
-        
-        self.price_history.push(mid_price);
-        if self.price_history.len() > self.max_history {
-            self.price_history.remove(0);
+impl Default for MarketStateTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Population coding: the neurons are split into four populations, for the
+/// tick return, volume, spread and volatility. Each value fires the neuron
+/// whose receptive field it falls in, and more weakly its neighbours.
+pub struct PopulationEncoder {
+    population_size: u32,
+    adaptive: bool,
+}
+
+impl PopulationEncoder {
+    const POPULATIONS: u32 = 4;
+
+    pub fn new(neuron_count: usize, adaptive: bool) -> Self {
+        Self {
+            population_size: (neuron_count as u32 / Self::POPULATIONS).max(1),
+            adaptive,
         }
-        
-        self.update_volatility();
     }
 
-    fn update_volatility(&mut self) {
-        if self.price_history.len() < 2 {
+    /// Fire the neuron for `value` in `[min, max]` of a population, plus its neighbours
+    fn fire(&self, spikes: &mut Vec<Spike>, population: u32, value: f64, min: f64, max: f64, timestamp_us: u64) {
+        if !value.is_finite() {
             return;
         }
-        
-        let returns: Vec<f64> = self.price_history.windows(2)
-            .map(|w| (w[1] / w[0] - 1.0).abs())
-            .collect();
-        
-        if !returns.is_empty() {
-            self.state.volatility = returns.iter().sum::<f64>() / returns.len() as f64;
+        let last = self.population_size - 1;
+        let position = (value.clamp(min, max) - min) / (max - min) * last as f64;
+        let centre = position.round() as u32;
+        for neuron in centre.saturating_sub(1)..=(centre + 1).min(last) {
+            let distance = neuron as f64 - position;
+            spikes.push(Spike {
+                neuron_id: population * self.population_size + neuron,
+                timestamp_us,
+                strength: (-0.5 * distance * distance).exp() as f32,
+            });
+        }
+    }
+}
+
+impl SpikeEncoder for PopulationEncoder {
+    fn encode(&mut self, tick: &MarketTick, state: &MarketState) -> Vec<Spike> {
+        let timestamp_us = tick.time_ms * 1000;
+        let mut spikes = Vec::with_capacity(12);
+
+        if let Some(previous) = tick.previous_price.filter(|p| *p > 0.0) {
+            let return_bps = (tick.price / previous).ln() * 10_000.0;
+            // In calm markets a small move is as significant as a large one in a volatile market
+            let scale_bps = match self.adaptive && state.volatility > 0.0 {
+                true => state.volatility * 10_000.0,
+                false => 10.0,
+            };
+            self.fire(&mut spikes, 0, return_bps / scale_bps, -3.0, 3.0, timestamp_us);
+        }
+        if tick.volume > 0.0 {
+            self.fire(&mut spikes, 1, (tick.volume + 1.0).log10(), 0.0, 6.0, timestamp_us);
+        }
+        if let Some(spread_bps) = tick.spread_bps {
+            self.fire(&mut spikes, 2, spread_bps, 0.0, 50.0, timestamp_us);
+        }
+        if state.volatility > 0.0 {
+            self.fire(&mut spikes, 3, state.volatility * 10_000.0, 0.0, 100.0, timestamp_us);
+        }
+        spikes
+    }
+}
+
+#[cfg(feature = "ares")]
+pub use ares::AresEncoder;
+
+#[cfg(feature = "ares")]
+mod ares {
+    use super::*;
+
+    /// The ARES-51 spike encoder. It sees each tick's price and volume, and
+    /// its spikes are stamped with the tick's time.
+    pub struct AresEncoder {
+        symbol: Symbol,
+        encoder: ares_spike_encoding::SpikeEncoder,
+    }
+
+    impl AresEncoder {
+        pub fn new(symbol: &Symbol, neuron_count: usize) -> anyhow::Result<Self> {
+            let encoder = ares_spike_encoding::SpikeEncoder::new(neuron_count, 1000.0)
+                .map_err(|e| anyhow::anyhow!("Failed to create ARES spike encoder: {:?}", e))?;
+            Ok(Self { symbol: symbol.clone(), encoder })
+        }
+    }
+
+    impl SpikeEncoder for AresEncoder {
+        fn encode(&mut self, tick: &MarketTick, _state: &MarketState) -> Vec<Spike> {
+            let data = ares_spike_encoding::MarketData::new(self.symbol.as_str(), tick.price, tick.volume);
+            match self.encoder.encode(&data) {
+                Ok(pattern) => pattern
+                    .spikes
+                    .iter()
+                    .map(|spike| Spike {
+                        neuron_id: spike.neuron_id as u32,
+                        timestamp_us: tick.time_ms * 1000,
+                        strength: (spike.strength as f32).clamp(0.0, 1.0),
+                    })
+                    .collect(),
+                Err(e) => {
+                    warn!(symbol = %self.symbol, "ARES encoding failed: {:?}", e);
+                    Vec::new()
+                }
+            }
         }
     }
 }
@@ -98,17 +223,23 @@ impl MarketStateTracker {
 pub struct SpikeStatistics {
     pub events_processed: u64,
     pub spikes_generated: u64,
-    pub encoding_latency_us: f64,
+    pub batches_sent: u64,
+    pub encoding_latency_us: f64, // Moving average
     pub last_spike_time: Option<Instant>,
-    pub spike_rate_hz: f64,
+    pub spike_rate_hz: f64, // Moving average
 }
 
 /// Configuration for spike bridge
+#[derive(Debug, Clone)]
 pub struct SpikeBridgeConfig {
     pub neuron_count: usize,
+    /// Batches a subscriber may fall behind before losing the oldest
     pub spike_buffer_size: usize,
+    /// Spikes per batch
     pub batch_size: usize,
-    pub encoding_timeout: Duration,
+    /// Longest a spike waits for its batch to fill
+    pub max_batch_delay: Duration,
+    /// Scale return sensitivity by the symbol's volatility
     pub enable_adaptive_encoding: bool,
 }
 
@@ -118,380 +249,297 @@ impl Default for SpikeBridgeConfig {
             neuron_count: 10000,
             spike_buffer_size: 100000,
             batch_size: 100,
-            encoding_timeout: Duration::from_millis(10),
+            max_batch_delay: Duration::from_millis(10),
             enable_adaptive_encoding: true,
         }
     }
 }
 
+type EncoderFactory = dyn Fn(&Symbol) -> Box<dyn SpikeEncoder> + Send + Sync;
+
+/// The ARES encoder when built with the `ares` feature, else population coding
+#[cfg(feature = "ares")]
+fn default_encoder(symbol: &Symbol, neuron_count: usize, adaptive: bool) -> Box<dyn SpikeEncoder> {
+    match AresEncoder::new(symbol, neuron_count) {
+        Ok(encoder) => Box::new(encoder),
+        Err(e) => {
+            warn!(%symbol, "{:#}; falling back to population coding", e);
+            Box::new(PopulationEncoder::new(neuron_count, adaptive))
+        }
+    }
+}
+
+#[cfg(not(feature = "ares"))]
+fn default_encoder(_symbol: &Symbol, neuron_count: usize, adaptive: bool) -> Box<dyn SpikeEncoder> {
+    Box::new(PopulationEncoder::new(neuron_count, adaptive))
+}
+
+struct SymbolEncoder {
+    encoder: Box<dyn SpikeEncoder>,
+    tracker: MarketStateTracker,
+    exchange: Exchange,
+    pending: Vec<Spike>,
+    pending_since: Option<Instant>,
+    statistics: SpikeStatistics,
+}
+
 /// Bridge between market data and spike encoding
 pub struct MarketDataSpikeBridge {
-    encoders: DashMap<Symbol, Arc<SpikeEncoder>>,
-    state_trackers: DashMap<Symbol, Arc<MarketStateTracker>>,
-    spike_sender: mpsc::UnboundedSender<Vec<Spike>>,
-    spike_receiver: Option<mpsc::UnboundedReceiver<Vec<Spike>>>,
-    statistics: DashMap<Symbol, SpikeStatistics>,
     config: SpikeBridgeConfig,
-    spike_buffer: DashMap<Symbol, Vec<Spike>>,
+    symbols: DashMap<Symbol, Arc<Mutex<SymbolEncoder>>>,
+    encoder_factory: Box<EncoderFactory>,
+    spike_sender: broadcast::Sender<SpikeBatch>,
 }
 
 impl MarketDataSpikeBridge {
     pub fn new(config: SpikeBridgeConfig) -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
-        
+        let (spike_sender, _) = broadcast::channel(config.spike_buffer_size.max(1));
+        let (neuron_count, adaptive) = (config.neuron_count, config.enable_adaptive_encoding);
         Self {
-            encoders: DashMap::new(),
-            state_trackers: DashMap::new(),
-            spike_sender: tx,
-            spike_receiver: Some(rx),
-            statistics: DashMap::new(),
             config,
-            spike_buffer: DashMap::new(),
+            symbols: DashMap::new(),
+            encoder_factory: Box::new(move |symbol| default_encoder(symbol, neuron_count, adaptive)),
+            spike_sender,
         }
     }
-    
-    /// Initialize encoder for a symbol
-    pub fn initialize_symbol(&self, symbol: Symbol) {
-        if !self.encoders.contains_key(&symbol) {
-            let encoder = SpikeEncoder::new(self.config.neuron_count, 1000.0)
-                .expect("Failed to create spike encoder");
-            
-            self.encoders.insert(symbol.clone(), Arc::new(encoder));
-            self.state_trackers.insert(
-                symbol.clone(), 
-                Arc::new(MarketStateTracker::new())
-            );
-            self.statistics.insert(symbol.clone(), SpikeStatistics::default());
-            self.spike_buffer.insert(symbol, Vec::new());
-        }
-    }
-    
-    /// Process unified market event
-    pub async fn process_event(&self, event: UnifiedMarketEvent) -> Result<()> {
-        let start = Instant::now();
-        
-        match event {
-            UnifiedMarketEvent::Trade(trade) => {
-                self.process_trade(trade).await?;
-            }
-            UnifiedMarketEvent::Quote(quote) => {
-                self.process_quote(quote).await?;
-            }
-            UnifiedMarketEvent::OrderBook(book) => {
-                self.process_orderbook(book).await?;
-            }
-            UnifiedMarketEvent::Heartbeat { .. } => {
-                // Heartbeats don't generate spikes
-            }
-            UnifiedMarketEvent::Error { exchange, error } => {
-                println!("Exchange {} error: {}", exchange, error);
-            }
-        }
-        
-        Ok(())
-    }
-    
-    /// Process trade and generate spikes
-    async fn process_trade(&self, trade: crate::exchanges::UniversalTrade) -> Result<()> {
-        self.initialize_symbol(trade.symbol.clone());
-        
-        // Update market state
-        if let Some(tracker) = self.state_trackers.get(&trade.symbol) {
-            let mut tracker = Arc::clone(&tracker.value());
-            let tracker_mut = Arc::get_mut(&mut tracker).unwrap();
-            tracker_mut.update_from_trade(trade.price, trade.quantity);
-        }
-        
-        // Create market data for encoding
-        let market_data = MarketData::new(
-            trade.symbol.as_str(),
-            trade.price,
-            trade.quantity
-        );
-        
-        // Get encoder and encode the data
-        if let Some(encoder_ref) = self.encoders.get(&trade.symbol) {
-            // Note: This is a simplified approach since the ARES encoder requires mutable access
-            // In a real implementation, you'd want to use interior mutability or a different pattern
-            let spikes = vec![]; // Placeholder - proper encoding would require encoder modification
-            self.send_spikes(trade.symbol, spikes).await?;
-        }
-        
-        Ok(())
+
+    /// Encode with other encoders, e.g. one wrapping a model's own encoding
+    pub fn with_encoder(mut self, factory: impl Fn(&Symbol) -> Box<dyn SpikeEncoder> + Send + Sync + 'static) -> Self {
+        self.encoder_factory = Box::new(factory);
+        self
     }
-    
-    /// Process quote and generate spikes
-    async fn process_quote(&self, quote: crate::exchanges::UniversalQuote) -> Result<()> {
-        self.initialize_symbol(quote.symbol.clone());
-        
-        // Update market state
-        if let Some(tracker) = self.state_trackers.get(&quote.symbol) {
-            let mut tracker = Arc::clone(&tracker.value());
-            let tracker_mut = Arc::get_mut(&mut tracker).unwrap();
-            tracker_mut.update_from_quote(
-                quote.bid_price,
-                quote.ask_price,
-                quote.bid_size + quote.ask_size
-            );
-        }
-        
-        // Create market data for encoding (using mid price and total size)
-        let mid_price = (quote.bid_price + quote.ask_price) / 2.0;
-        let total_size = quote.bid_size + quote.ask_size;
-        let market_data = MarketData::new(
-            quote.symbol.as_str(),
-            mid_price,
-            total_size
-        );
-        
-        // Get encoder and encode the data (placeholder implementation)
-        if let Some(_encoder_ref) = self.encoders.get(&quote.symbol) {
-            let spikes = vec![]; // Placeholder - proper encoding would require encoder modification
-            self.send_spikes(quote.symbol, spikes).await?;
-        }
-        
-        Ok(())
+
+    /// Spike batches from every symbol
+    pub fn subscribe(&self) -> broadcast::Receiver<SpikeBatch> {
+        self.spike_sender.subscribe()
     }
-    
-    /// Process order book and generate spikes
-    async fn process_orderbook(&self, book: crate::exchanges::UniversalOrderBook) -> Result<()> {
-        self.initialize_symbol(book.symbol.clone());
-        
-        // Update market state
-        if let Some(tracker) = self.state_trackers.get(&book.symbol) {
-            if !book.bids.is_empty() && !book.asks.is_empty() {
-                let mut tracker = Arc::clone(&tracker.value());
-                let tracker_mut = Arc::get_mut(&mut tracker).unwrap();
-                
-                let best_bid = book.bids[0].0;
-                let best_ask = book.asks[0].0;
-                let total_volume = book.bids.iter().map(|(_, q)| q).sum::<f64>() +
-                                 book.asks.iter().map(|(_, q)| q).sum::<f64>();
-                
-                tracker_mut.update_from_quote(best_bid, best_ask, total_volume);
-            }
-        }
-        
-        // Create market data for encoding (using best bid/ask)
-        if !book.bids.is_empty() && !book.asks.is_empty() {
-            let mid_price = (book.bids[0].0 + book.asks[0].0) / 2.0;
-            let total_volume = book.bids.iter().map(|(_, q)| q).sum::<f64>() +
-                             book.asks.iter().map(|(_, q)| q).sum::<f64>();
-            
-            let market_data = MarketData::new(
-                book.symbol.as_str(),
-                mid_price,
-                total_volume
-            );
-            
-            // Get encoder and encode the data (placeholder implementation)
-            if let Some(_encoder_ref) = self.encoders.get(&book.symbol) {
-                let spikes = vec![]; // Placeholder - proper encoding would require encoder modification
-                self.send_spikes(book.symbol, spikes).await?;
+
+    /// Encode one event. Safe to call from many tasks at once.
+    pub fn process_event(&self, event: &UnifiedMarketEvent) {
+        let Some(price) = event.price() else {
+            return;
+        };
+        let (volume, spread_bps) = match &event.data {
+            UniversalMarketData::Trade(trade) => (trade.quantity, None),
+            UniversalMarketData::Quote(quote) => (
+                quote.bid_size + quote.ask_size,
+                Some((quote.ask_price - quote.bid_price) / price * 10_000.0),
+            ),
+            UniversalMarketData::OrderBook(book) => {
+                let depth = book.bids.iter().chain(&book.asks).map(|(_, quantity)| quantity).sum();
+                let spread = book.asks[0].0 - book.bids[0].0; // Both sides exist, or there'd be no price
+                (depth, Some(spread / price * 10_000.0))
             }
-        }
-        
-        Ok(())
-    }
-    
-    /// Send spikes and update statistics
-    async fn send_spikes(&self, symbol: Symbol, spikes: Vec<Spike>) -> Result<()> {
+            UniversalMarketData::Kline(kline) => (kline.volume, None),
+        };
+
+        let entry = self.symbol_encoder(event.symbol(), event.exchange);
+        let mut symbol = entry.lock();
+        let started = Instant::now();
+
+        let tick = MarketTick {
+            time_ms: event.event_time,
+            price,
+            previous_price: (symbol.tracker.state.last_price > 0.0).then_some(symbol.tracker.state.last_price),
+            volume,
+            spread_bps,
+        };
+        symbol.tracker.update(price, volume);
+        let state = symbol.tracker.state.clone();
+        let spikes = symbol.encoder.encode(&tick, &state);
+
+        let stats = &mut symbol.statistics;
+        let latency_us = started.elapsed().as_secs_f64() * 1_000_000.0;
+        stats.encoding_latency_us = match stats.events_processed {
+            0 => latency_us,
+            _ => stats.encoding_latency_us * 0.9 + latency_us * 0.1,
+        };
+        stats.events_processed += 1;
         if spikes.is_empty() {
-            return Ok(());
+            return;
         }
-        
-        // Buffer spikes if batching is enabled
-        if self.config.batch_size > 1 {
-            let mut buffer = self.spike_buffer.get_mut(&symbol).unwrap();
-            buffer.extend(spikes.clone());
-            
-            if buffer.len() >= self.config.batch_size {
-                let batch = buffer.drain(..).collect::<Vec<_>>();
-                self.spike_sender.send(batch)?;
-            }
-        } else {
-            self.spike_sender.send(spikes.clone())?;
+
+        let now = Instant::now();
+        if let Some(last) = stats.last_spike_time {
+            let elapsed = now.duration_since(last).as_secs_f64().max(1e-6);
+            stats.spike_rate_hz = stats.spike_rate_hz * 0.9 + spikes.len() as f64 / elapsed * 0.1;
         }
-        
-        // Update statistics
-        if let Some(mut stats) = self.statistics.get_mut(&symbol) {
-            stats.events_processed += 1;
-            stats.spikes_generated += spikes.len() as u64;
-            
-            if let Some(last_time) = stats.last_spike_time {
-                let elapsed = last_time.elapsed().as_secs_f64();
-                if elapsed > 0.0 {
-                    stats.spike_rate_hz = spikes.len() as f64 / elapsed;
-                }
-            }
-            stats.last_spike_time = Some(Instant::now());
+        stats.last_spike_time = Some(now);
+        stats.spikes_generated += spikes.len() as u64;
+
+        symbol.exchange = event.exchange;
+        symbol.pending_since.get_or_insert(now);
+        symbol.pending.extend(spikes);
+        if symbol.pending.len() >= self.config.batch_size {
+            self.send_batch(event.symbol(), &mut symbol);
         }
-        
-        Ok(())
     }
-    
-    /// Flush buffered spikes for a symbol
-    pub async fn flush_symbol(&self, symbol: &Symbol) -> Result<()> {
-        if let Some(mut buffer) = self.spike_buffer.get_mut(symbol) {
-            if !buffer.is_empty() {
-                let batch = buffer.drain(..).collect::<Vec<_>>();
-                self.spike_sender.send(batch)?;
-            }
-        }
-        Ok(())
+
+    /// Send batches whose first spike has waited `max_batch_delay`
+    pub fn flush_due(&self) {
+        self.flush(|since| since.elapsed() >= self.config.max_batch_delay);
     }
-    
-    /// Flush all buffered spikes
-    pub async fn flush_all(&self) -> Result<()> {
-        for entry in self.spike_buffer.iter() {
-            let symbol = entry.key().clone();
-            drop(entry); // Release the lock
-            self.flush_symbol(&symbol).await?;
-        }
-        Ok(())
+
+    /// Send every partial batch
+    pub fn flush_all(&self) {
+        self.flush(|_| true);
     }
-    
-    /// Subscribe to spike stream
-    pub fn subscribe(&mut self) -> Option<mpsc::UnboundedReceiver<Vec<Spike>>> {
-        self.spike_receiver.take()
+
+    /// Encode the events of a unified feed until it closes
+    pub fn spawn(self: Arc<Self>, mut events: broadcast::Receiver<UnifiedMarketEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval((self.config.max_batch_delay / 2).max(Duration::from_millis(1)));
+            tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => self.process_event(&event),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!(skipped, "Spike bridge fell behind the market feed");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = tick.tick() => self.flush_due(),
+                }
+            }
+            self.flush_all();
+        })
     }
-    
+
     /// Get statistics for a symbol
     pub fn get_statistics(&self, symbol: &Symbol) -> Option<SpikeStatistics> {
-        self.statistics.get(symbol).map(|s| s.clone())
+        self.symbols.get(symbol).map(|entry| entry.lock().statistics.clone())
     }
-    
+
     /// Get market state for a symbol
     pub fn get_market_state(&self, symbol: &Symbol) -> Option<MarketState> {
-        self.state_trackers.get(symbol).map(|tracker| {
-            let tracker = Arc::clone(&tracker.value());
-            // This would need proper accessor methods in real implementation
-            MarketState::default()
-        })
-    }
-    
-    /// Adaptive encoding adjustment based on market conditions
-    pub fn adjust_encoding(&self, symbol: &Symbol) {
-        if !self.config.enable_adaptive_encoding {
-            return;
-        }
-        
-        // Note: The ARES encoder doesn't have a set_sensitivity method
-        // This would need to be implemented differently, possibly by creating
-        // new encoders with different configurations based on market conditions
-        if let Some(_tracker) = self.state_trackers.get(symbol) {
-            if let Some(_encoder) = self.encoders.get(symbol) {
-                let state = self.get_market_state(symbol).unwrap_or_default();
-                
-                // Placeholder for adaptive encoding logic
-                // In a real implementation, you might:
-                // 1. Create new encoder configurations based on volatility
-                // 2. Replace the encoder in the map
-                // 3. Or adjust encoding parameters through configuration
-                let _volatility_adjustment = if state.volatility > 0.02 {
-                    2.0 // High volatility
-                } else if state.volatility > 0.01 {
-                    1.5 // Medium volatility
-                } else {
-                    1.0 // Low volatility
-                };
-            }
-        }
+        self.symbols.get(symbol).map(|entry| entry.lock().tracker.state.clone())
     }
-}
-
-/// Integration handler for connecting unified feed to spike bridge
-pub struct MarketSpikeIntegration {
-    feed: Arc<UnifiedMarketFeed>,
-    bridge: Arc<MarketDataSpikeBridge>,
-    running: Arc<tokio::sync::RwLock<bool>>,
-}
 
-impl MarketSpikeIntegration {
-    pub fn new(
-        mut feed: UnifiedMarketFeed,
-        bridge: MarketDataSpikeBridge
-    ) -> Self {
-        Self {
-            feed: Arc::new(feed),
-            bridge: Arc::new(bridge),
-            running: Arc::new(tokio::sync::RwLock::new(false)),
+    fn symbol_encoder(&self, symbol: &Symbol, exchange: Exchange) -> Arc<Mutex<SymbolEncoder>> {
+        if let Some(entry) = self.symbols.get(symbol) {
+            return entry.clone();
         }
+        // The map guard is released before the symbol is locked
+        self.symbols
+            .entry(symbol.clone())
+            .or_insert_with(|| {
+                Arc::new(Mutex::new(SymbolEncoder {
+                    encoder: (self.encoder_factory)(symbol),
+                    tracker: MarketStateTracker::new(),
+                    exchange,
+                    pending: Vec::new(),
+                    pending_since: None,
+                    statistics: SpikeStatistics::default(),
+                }))
+            })
+            .clone()
     }
-    
-    /// Start processing market events
-    pub async fn start(&mut self) -> Result<()> {
-        let mut running = self.running.write().await;
-        *running = true;
-        drop(running);
-        
-        // Get event receiver from feed
-        let mut feed = Arc::get_mut(&mut self.feed).unwrap();
-        let mut receiver = feed.subscribe()
-            .ok_or_else(|| anyhow::anyhow!("Failed to subscribe to feed"))?;
-        
-        let bridge = self.bridge.clone();
-        let running = self.running.clone();
-        
-        // Spawn processing task
-        tokio::spawn(async move {
-            while *running.read().await {
-                tokio::select! {
-                    Some(event) = receiver.recv() => {
-                        if let Err(e) = bridge.process_event(event).await {
-                            eprintln!("Error processing event: {}", e);
-                        }
-                    }
-                    _ = tokio::time::sleep(Duration::from_secs(1)) => {
-                        // Periodic flush
-                        if let Err(e) = bridge.flush_all().await {
-                            eprintln!("Error flushing spikes: {}", e);
-                        }
-                    }
-                }
+
+    fn flush(&self, due: impl Fn(Instant) -> bool) {
+        let entries: Vec<(Symbol, Arc<Mutex<SymbolEncoder>>)> = self
+            .symbols
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        for (symbol, entry) in entries {
+            let mut encoder = entry.lock();
+            if encoder.pending_since.is_some_and(&due) {
+                self.send_batch(&symbol, &mut encoder);
             }
-        });
-        
-        Ok(())
+        }
     }
-    
-    /// Stop processing
-    pub async fn stop(&self) -> Result<()> {
-        let mut running = self.running.write().await;
-        *running = false;
-        
-        // Final flush
-        self.bridge.flush_all().await?;
-        
-        Ok(())
+
+    fn send_batch(&self, symbol: &Symbol, encoder: &mut SymbolEncoder) {
+        encoder.pending_since = None;
+        encoder.statistics.batches_sent += 1;
+        let batch = SpikeBatch {
+            symbol: symbol.clone(),
+            exchange: encoder.exchange,
+            spikes: std::mem::take(&mut encoder.pending),
+        };
+        let _ = self.spike_sender.send(batch); // No subscribers is fine
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::exchanges::{Side, UniversalQuote, UniversalTrade};
+
+    fn trade(symbol: &str, price: f64, time: u64) -> UnifiedMarketEvent {
+        let data = UniversalMarketData::Trade(UniversalTrade {
+            exchange: Exchange::Binance,
+            symbol: Symbol::new(symbol),
+            price,
+            quantity: 2.0,
+            side: Side::Buy,
+            timestamp_exchange: time,
+            timestamp_local: time,
+            trade_id: time.to_string(),
+        });
+        UnifiedMarketEvent::new(Exchange::Binance, data, time)
+    }
+
     #[tokio::test]
-    async fn test_spike_bridge() {
-        let config = SpikeBridgeConfig::default();
-        let mut bridge = MarketDataSpikeBridge::new(config);
-        
-        let quote = crate::exchanges::UniversalQuote {
+    async fn test_spike_bridge_encodes_and_batches_per_symbol() {
+        let bridge = Arc::new(MarketDataSpikeBridge::new(SpikeBridgeConfig {
+            neuron_count: 400,
+            batch_size: 50,
+            enable_adaptive_encoding: false,
+            ..Default::default()
+        }));
+        let mut batches = bridge.subscribe();
+
+        // Symbols encode from several tasks at once
+        let mut tasks = Vec::new();
+        for symbol in ["BTC-USD", "ETH-USD", "SOL-USD", "ADA-USD"] {
+            let bridge = bridge.clone();
+            tasks.push(tokio::task::spawn_blocking(move || {
+                for n in 0..100u64 {
+                    bridge.process_event(&trade(symbol, 100.0 + (n % 5) as f64 * 0.01, 1_000 + n));
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        bridge.flush_all();
+
+        let stats = bridge.get_statistics(&Symbol::new("ETH-USD")).unwrap();
+        assert_eq!(stats.events_processed, 100);
+        assert!(stats.batches_sent >= 2);
+        assert!((bridge.get_market_state(&Symbol::new("ETH-USD")).unwrap().volume - 200.0).abs() < 1e-9);
+
+        let mut spikes = 0;
+        while let Ok(batch) = batches.try_recv() {
+            assert!(batch.spikes.windows(2).all(|w| w[0].timestamp_us <= w[1].timestamp_us));
+            assert!(batch.spikes.iter().all(|s| s.neuron_id < 400 && s.strength > 0.0 && s.strength <= 1.0));
+            spikes += batch.spikes.len() as u64;
+        }
+        assert_eq!(spikes, stats.spikes_generated * 4);
+
+        // A 0.5% jump saturates the return population (neurons 0..100); the
+        // quote's 10bps spread lands 20% into the spread population
+        bridge.process_event(&trade("BTC-USD", 100.5, 2_000));
+        let quote = UniversalMarketData::Quote(UniversalQuote {
             exchange: Exchange::Binance,
-            symbol: Symbol::new("BTC-USD"),
-            bid_price: 50000.0,
-            bid_size: 1.0,
-            ask_price: 50001.0,
-            ask_size: 1.0,
-            timestamp_exchange: 0,
-            timestamp_local: 0,
-        };
-        
-        let event = UnifiedMarketEvent::Quote(quote);
-        bridge.process_event(event).await.unwrap();
-        
-        let stats = bridge.get_statistics(&Symbol::new("BTC-USD"));
-        assert!(stats.is_some());
-        assert_eq!(stats.unwrap().events_processed, 1);
+            symbol: Symbol::new("XRP-USD"),
+            bid_price: 0.9995,
+            bid_size: 10.0,
+            ask_price: 1.0005,
+            ask_size: 10.0,
+            timestamp_exchange: 2_000,
+            timestamp_local: 2_000,
+        });
+        bridge.process_event(&UnifiedMarketEvent::new(Exchange::Binance, quote, 2_000));
+        bridge.flush_all();
+
+        let mut fired = Vec::new();
+        while let Ok(batch) = batches.try_recv() {
+            fired.extend(batch.spikes.iter().filter(|s| s.strength > 0.9).map(|s| s.neuron_id));
+        }
+        assert!(fired.contains(&99));
+        assert!(fired.contains(&(200 + 20)));
     }
-}
\ No newline at end of file
+}
@@ -108,18 +108,13 @@ impl SymbolMapper {
         match exchange {
             Exchange::Binance => {
                 // Binance uses BASEQOUTE format (e.g., BTCUSDT)
-                if exchange_symbol.ends_with("USDT") {
-                    let base = &exchange_symbol[..exchange_symbol.len() - 4];
-                    Some(Symbol::new(format!("{}-USD", base)))
-                } else if exchange_symbol.ends_with("BUSD") {
-                    let base = &exchange_symbol[..exchange_symbol.len() - 4];
-                    Some(Symbol::new(format!("{}-BUSD", base)))
-                } else if exchange_symbol.ends_with("BTC") {
-                    let base = &exchange_symbol[..exchange_symbol.len() - 3];
-                    Some(Symbol::new(format!("{}-BTC", base)))
-                } else {
-                    None
-                }
+                [("USDT", "USD"), ("BUSD", "BUSD"), ("BTC", "BTC")]
+                    .iter()
+                    .find_map(|(suffix, quote)| {
+                        exchange_symbol
+                            .strip_suffix(suffix)
+                            .map(|base| Symbol::new(format!("{}-{}", base, quote)))
+                    })
             }
             Exchange::Coinbase => {
                 // Coinbase uses BASE-QUOTE format
@@ -153,4 +148,10 @@ impl SymbolMapper {
         self.cache_hits.store(0, Ordering::Relaxed);
         self.cache_misses.store(0, Ordering::Relaxed);
     }
-}
\ No newline at end of file
+}
+
+impl Default for SymbolMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,379 +1,511 @@
 //! Unified market data feed combining all exchanges
+//!
+//! Every exchange's `StreamManager` feeds one merged channel. Events are held
+//! for a short reordering window and released in exchange time order, so the
+//! scanner and the engine see a single timeline even though each connection
+//! delivers on its own schedule.
+//!
+//! Each released event also updates the feed's `ConsolidatedQuotes`, the
+//! best bid and offer of every instrument across the venues quoting it.
 
-use super::{MarketDataNormalizer, SymbolMapper, TimeSynchronizer};
-use super::normalizers::{BinanceNormalizer, CoinbaseNormalizer};
+use super::ConsolidatedQuotes;
 use crate::exchanges::{
-    Exchange, Symbol, UniversalTrade, UniversalQuote, 
-    UniversalOrderBook, MarketDataType
+    ConnectionStatus, Exchange, ExchangeError, ExchangeResult, StreamManager, Symbol, UniversalMarketData,
 };
-use anyhow::Result;
 use dashmap::DashMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::mpsc;
 use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
-/// Unified market data event
+/// Trade IDs remembered per feed for deduplication
+const DEDUP_CAPACITY: usize = 10_000;
+
+/// Market data from one exchange, tagged with its source
 #[derive(Clone, Debug)]
-pub enum UnifiedMarketEvent {
-    Trade(UniversalTrade),
-    Quote(UniversalQuote),
-    OrderBook(UniversalOrderBook),
-    Heartbeat { exchange: Exchange, timestamp: u64 },
-    Error { exchange: Exchange, error: String },
+pub struct UnifiedMarketEvent {
+    pub exchange: Exchange,
+    pub data: UniversalMarketData,
+    /// Exchange time in Unix milliseconds, or the local receive time when the
+    /// exchange doesn't send one
+    pub event_time: u64,
+    pub received_time: u64,
 }
 
-/// Feed statistics
-#[derive(Default, Clone, Debug)]
-pub struct FeedStatistics {
-    pub messages_received: u64,
-    pub messages_processed: u64,
-    pub errors_count: u64,
-    pub last_update: Option<Instant>,
-    pub latency_ms: f64,
+impl UnifiedMarketEvent {
+    pub fn new(exchange: Exchange, data: UniversalMarketData, received_time: u64) -> Self {
+        let event_time = match &data {
+            UniversalMarketData::Trade(t) => t.timestamp_exchange,
+            UniversalMarketData::Quote(q) => q.timestamp_exchange,
+            UniversalMarketData::OrderBook(b) => b.timestamp_exchange,
+            UniversalMarketData::Kline(k) => k.close_time.timestamp_millis().max(0) as u64,
+        };
+        Self {
+            exchange,
+            data,
+            event_time: if event_time == 0 { received_time } else { event_time },
+            received_time,
+        }
+    }
+
+    pub fn symbol(&self) -> &Symbol {
+        self.data.symbol()
+    }
+
+    /// Last trade, quote or book mid, or kline close
+    pub fn price(&self) -> Option<f64> {
+        let price = match &self.data {
+            UniversalMarketData::Trade(t) => t.price,
+            UniversalMarketData::Quote(q) => (q.bid_price + q.ask_price) / 2.0,
+            UniversalMarketData::OrderBook(b) => {
+                let (bid, ask) = (b.bids.first()?, b.asks.first()?);
+                (bid.0 + ask.0) / 2.0
+            }
+            UniversalMarketData::Kline(k) => k.close,
+        };
+        (price.is_finite() && price > 0.0).then_some(price)
+    }
 }
 
 /// Unified feed configuration
+#[derive(Debug, Clone)]
 pub struct UnifiedFeedConfig {
     pub buffer_size: usize,
-    pub heartbeat_interval: Duration,
-    pub max_latency_ms: f64,
+    /// How long an event is held so slower connections can catch up before
+    /// it is released in time order. Zero passes events straight through.
+    pub reorder_window: Duration,
+    /// Drop trades already seen, e.g. delivered again after a reconnect
     pub enable_deduplication: bool,
+    /// A source with no data for this long is reported unhealthy
+    pub stale_after: Duration,
+    /// A venue's quote older than this is left out of the composite best bid and offer
+    pub max_quote_age: Duration,
 }
 
 impl Default for UnifiedFeedConfig {
     fn default() -> Self {
         Self {
-            buffer_size: 10000,
-            heartbeat_interval: Duration::from_secs(30),
-            max_latency_ms: 100.0,
+            buffer_size: 10_000,
+            reorder_window: Duration::from_millis(50),
             enable_deduplication: true,
+            stale_after: Duration::from_secs(30),
+            max_quote_age: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Per-exchange feed counters
+#[derive(Default, Clone, Debug)]
+pub struct FeedStatistics {
+    pub messages_received: u64,
+    pub messages_published: u64,
+    pub duplicates: u64,
+    pub late: u64,   // Arrived after newer events were released, published out of order
+    pub lagged: u64, // Dropped because the feed fell behind the source
+    pub last_update: Option<Instant>,
+}
+
+struct Pending(UnifiedMarketEvent, u64); // Event and arrival sequence
+
+impl Pending {
+    fn key(&self) -> (u64, u64) {
+        (self.0.event_time, self.1)
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Holds events until they've waited out the window, then releases them
+/// oldest first
+struct ReorderBuffer {
+    window_ms: u64,
+    pending: BinaryHeap<Reverse<Pending>>,
+    sequence: u64,
+    watermark: u64, // Event time of the last release
+}
+
+impl ReorderBuffer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window_ms: window.as_millis() as u64,
+            pending: BinaryHeap::new(),
+            sequence: 0,
+            watermark: 0,
+        }
+    }
+
+    /// Queue an event. Returns it back if it's older than what was already
+    /// released, since it can no longer be put in order.
+    fn push(&mut self, event: UnifiedMarketEvent) -> Option<UnifiedMarketEvent> {
+        if event.event_time < self.watermark {
+            return Some(event);
+        }
+        self.sequence += 1;
+        self.pending.push(Reverse(Pending(event, self.sequence)));
+        None
+    }
+
+    fn release(&mut self, now_ms: u64) -> Vec<UnifiedMarketEvent> {
+        let mut released = Vec::new();
+        while let Some(Reverse(oldest)) = self.pending.peek() {
+            if oldest.0.received_time + self.window_ms > now_ms {
+                break;
+            }
+            let Reverse(Pending(event, _)) = self.pending.pop().unwrap();
+            self.watermark = event.event_time;
+            released.push(event);
         }
+        released
     }
 }
 
+/// Recently seen trade IDs
+#[derive(Default)]
+struct SeenTrades {
+    ids: HashSet<(Exchange, String)>,
+    order: VecDeque<(Exchange, String)>,
+}
+
+impl SeenTrades {
+    /// False if the trade was already seen
+    fn insert(&mut self, exchange: Exchange, trade_id: &str) -> bool {
+        let key = (exchange, trade_id.to_string());
+        if !self.ids.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > DEDUP_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+struct Source {
+    exchange: Exchange,
+    manager: Box<dyn StreamManager>,
+}
+
 /// Unified market data feed
 pub struct UnifiedMarketFeed {
-    normalizers: DashMap<Exchange, Arc<dyn MarketDataNormalizer>>,
-    symbol_mapper: Arc<SymbolMapper>,
-    time_sync: Arc<TimeSynchronizer>,
-    event_sender: mpsc::UnboundedSender<UnifiedMarketEvent>,
-    event_receiver: Option<mpsc::UnboundedReceiver<UnifiedMarketEvent>>,
-    statistics: DashMap<Exchange, FeedStatistics>,
     config: UnifiedFeedConfig,
-    dedup_cache: DashMap<String, Instant>,
+    sources: Vec<Source>,
+    event_sender: broadcast::Sender<UnifiedMarketEvent>,
+    statistics: Arc<DashMap<Exchange, FeedStatistics>>,
+    quotes: Arc<ConsolidatedQuotes>,
+    tasks: Vec<JoinHandle<()>>,
 }
 
 impl UnifiedMarketFeed {
     pub fn new(config: UnifiedFeedConfig) -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
-        
-        let symbol_mapper = Arc::new(SymbolMapper::new());
-        let time_sync = Arc::new(TimeSynchronizer::new());
-        
-        let mut feed = Self {
-            normalizers: DashMap::new(),
-            symbol_mapper: symbol_mapper.clone(),
-            time_sync: time_sync.clone(),
-            event_sender: tx,
-            event_receiver: Some(rx),
-            statistics: DashMap::new(),
+        let (event_sender, _) = broadcast::channel(config.buffer_size.max(1));
+        Self {
+            quotes: Arc::new(ConsolidatedQuotes::new(config.max_quote_age)),
             config,
-            dedup_cache: DashMap::new(),
-        };
-        
-        // Register default normalizers
-        feed.register_normalizer(
-            Exchange::Binance,
-            Arc::new(BinanceNormalizer::new(
-                symbol_mapper.clone(),
-                time_sync.clone()
-            ))
-        );
-        
-        feed.register_normalizer(
-            Exchange::Coinbase,
-            Arc::new(CoinbaseNormalizer::new(
-                symbol_mapper.clone(),
-                time_sync.clone()
-            ))
-        );
-        
-        feed
+            sources: Vec::new(),
+            event_sender,
+            statistics: Arc::new(DashMap::new()),
+            tasks: Vec::new(),
+        }
     }
-    
-    /// Register a normalizer for an exchange
-    pub fn register_normalizer(
-        &mut self,
-        exchange: Exchange,
-        normalizer: Arc<dyn MarketDataNormalizer>
-    ) {
-        self.normalizers.insert(exchange, normalizer);
-        self.statistics.insert(exchange, FeedStatistics::default());
+
+    /// Merge an exchange's streams into the feed. Its events are tagged with
+    /// `exchange` whatever the stream reports.
+    pub fn with_source(mut self, exchange: Exchange, manager: Box<dyn StreamManager>) -> Self {
+        self.add_source(exchange, manager);
+        self
     }
-    
-    /// Process raw market data from an exchange
-    pub async fn process_raw_data(
-        &self,
-        exchange: Exchange,
-        data_type: MarketDataType,
-        raw_data: &[u8]
-    ) -> Result<()> {
-        let normalizer = self.normalizers
-            .get(&exchange)
-            .ok_or_else(|| anyhow::anyhow!("No normalizer for {:?}", exchange))?;
-        
-        let start = Instant::now();
-        
-        // Normalize based on data type
-        let event = match data_type {
-            MarketDataType::Trade => {
-                let trade = normalizer.normalize_trade(raw_data)?;
-                
-                // Deduplicate if enabled
-                if self.config.enable_deduplication {
-                    let key = format!("{}:{}:{}", exchange, trade.symbol, trade.trade_id);
-                    if let Some(last_seen) = self.dedup_cache.get(&key) {
-                        if last_seen.elapsed() < Duration::from_secs(1) {
-                            return Ok(()); // Skip duplicate
-                        }
-                    }
-                    self.dedup_cache.insert(key, Instant::now());
-                }
-                
-                UnifiedMarketEvent::Trade(trade)
-            }
-            MarketDataType::Quote => {
-                let quote = normalizer.normalize_quote(raw_data)?;
-                UnifiedMarketEvent::Quote(quote)
-            }
-            MarketDataType::OrderBook => {
-                let book = normalizer.normalize_book(raw_data)?;
-                UnifiedMarketEvent::OrderBook(book)
-            }
-        };
-        
-        // Send normalized event
-        self.event_sender.send(event)?;
-        
-        // Update statistics
-        if let Some(mut stats) = self.statistics.get_mut(&exchange) {
-            stats.messages_received += 1;
-            stats.messages_processed += 1;
-            stats.last_update = Some(Instant::now());
-            stats.latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    pub fn add_source(&mut self, exchange: Exchange, manager: Box<dyn StreamManager>) {
+        self.statistics.entry(exchange).or_default();
+        self.sources.push(Source { exchange, manager });
+    }
+
+    /// Merged events, in exchange time order within the reorder window
+    pub fn subscribe(&self) -> broadcast::Receiver<UnifiedMarketEvent> {
+        self.event_sender.subscribe()
+    }
+
+    /// Best bid and offer of every instrument across the sources, as of the
+    /// events released so far
+    pub fn quotes(&self) -> Arc<ConsolidatedQuotes> {
+        self.quotes.clone()
+    }
+
+    /// Start every source and the merge task
+    pub async fn start(&mut self) -> ExchangeResult<()> {
+        if !self.tasks.is_empty() {
+            return Err(ExchangeError::InvalidRequest {
+                details: "Unified feed already started".to_string(),
+            });
         }
-        
-        // Clean old dedup entries periodically
-        if self.dedup_cache.len() > 10000 {
-            self.clean_dedup_cache();
+
+        let (merged_sender, merged_receiver) = mpsc::unbounded_channel();
+        for source in &mut self.sources {
+            let receiver = source.manager.get_receiver().ok_or_else(|| ExchangeError::InvalidRequest {
+                details: format!("{:?} stream receiver was already taken", source.exchange),
+            })?;
+            source.manager.start().await?;
+            self.tasks.push(tokio::spawn(Self::forward(
+                source.exchange,
+                receiver,
+                merged_sender.clone(),
+                self.statistics.clone(),
+            )));
         }
-        
+
+        self.tasks.push(tokio::spawn(Self::merge(
+            self.config.clone(),
+            merged_receiver,
+            self.event_sender.clone(),
+            self.statistics.clone(),
+            self.quotes.clone(),
+        )));
+        info!(sources = self.sources.len(), "Unified market feed started");
         Ok(())
     }
-    
-    /// Clean old entries from deduplication cache
-    fn clean_dedup_cache(&self) {
-        let cutoff = Instant::now() - Duration::from_secs(60);
-        self.dedup_cache.retain(|_, instant| *instant > cutoff);
+
+    pub async fn stop(&mut self) -> ExchangeResult<()> {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        for source in &mut self.sources {
+            source.manager.stop().await?;
+        }
+        info!("Unified market feed stopped");
+        Ok(())
     }
-    
-    /// Subscribe to unified market events
-    pub fn subscribe(&mut self) -> Option<mpsc::UnboundedReceiver<UnifiedMarketEvent>> {
-        self.event_receiver.take()
+
+    /// Connection status of every source
+    pub async fn status(&self) -> Vec<(Exchange, ConnectionStatus)> {
+        let mut statuses = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            statuses.push((source.exchange, source.manager.get_status().await));
+        }
+        statuses
     }
-    
-    /// Get feed statistics
+
     pub fn get_statistics(&self, exchange: Exchange) -> Option<FeedStatistics> {
         self.statistics.get(&exchange).map(|s| s.clone())
     }
-    
-    /// Get all statistics
+
     pub fn get_all_statistics(&self) -> Vec<(Exchange, FeedStatistics)> {
         self.statistics
             .iter()
             .map(|entry| (*entry.key(), entry.value().clone()))
             .collect()
     }
-    
-    /// Check feed health
+
+    /// Whether the exchange delivered data within `stale_after`
     pub fn is_healthy(&self, exchange: Exchange) -> bool {
         self.statistics
             .get(&exchange)
-            .map(|stats| {
-                if let Some(last_update) = stats.last_update {
-                    last_update.elapsed() < self.config.heartbeat_interval * 2
-                        && stats.latency_ms < self.config.max_latency_ms
-                } else {
-                    false
-                }
-            })
-            .unwrap_or(false)
+            .and_then(|stats| stats.last_update)
+            .is_some_and(|last| last.elapsed() < self.config.stale_after)
     }
-    
-    /// Send heartbeat for an exchange
-    pub async fn send_heartbeat(&self, exchange: Exchange) -> Result<()> {
-        let timestamp = self.time_sync.get_local_time();
-        let event = UnifiedMarketEvent::Heartbeat { exchange, timestamp };
-        self.event_sender.send(event)?;
-        Ok(())
+
+    /// Move one source's data into the merge task
+    async fn forward(
+        exchange: Exchange,
+        mut receiver: broadcast::Receiver<UniversalMarketData>,
+        merged: mpsc::UnboundedSender<UnifiedMarketEvent>,
+        statistics: Arc<DashMap<Exchange, FeedStatistics>>,
+    ) {
+        loop {
+            match receiver.recv().await {
+                Ok(data) => {
+                    if let Some(mut stats) = statistics.get_mut(&exchange) {
+                        stats.messages_received += 1;
+                        stats.last_update = Some(Instant::now());
+                    }
+                    if merged.send(UnifiedMarketEvent::new(exchange, data, now_ms())).is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(?exchange, skipped, "Unified feed fell behind the stream");
+                    if let Some(mut stats) = statistics.get_mut(&exchange) {
+                        stats.lagged += skipped;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
     }
-    
-    /// Handle error from exchange
-    pub async fn handle_error(&self, exchange: Exchange, error: String) -> Result<()> {
-        let event = UnifiedMarketEvent::Error { exchange, error: error.clone() };
-        self.event_sender.send(event)?;
-        
-        // Update error count
-        if let Some(mut stats) = self.statistics.get_mut(&exchange) {
-            stats.errors_count += 1;
+
+    async fn merge(
+        config: UnifiedFeedConfig,
+        mut merged: mpsc::UnboundedReceiver<UnifiedMarketEvent>,
+        sender: broadcast::Sender<UnifiedMarketEvent>,
+        statistics: Arc<DashMap<Exchange, FeedStatistics>>,
+        quotes: Arc<ConsolidatedQuotes>,
+    ) {
+        let mut buffer = ReorderBuffer::new(config.reorder_window);
+        let mut seen = SeenTrades::default();
+        let mut tick = tokio::time::interval((config.reorder_window / 2).max(Duration::from_millis(1)));
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        let publish = |event: UnifiedMarketEvent| {
+            quotes.update(&event);
+            if let Some(mut stats) = statistics.get_mut(&event.exchange) {
+                stats.messages_published += 1;
+            }
+            let _ = sender.send(event); // No subscribers is fine
+        };
+
+        loop {
+            tokio::select! {
+                event = merged.recv() => {
+                    let Some(event) = event else { break };
+                    if config.enable_deduplication {
+                        if let UniversalMarketData::Trade(trade) = &event.data {
+                            if !seen.insert(event.exchange, &trade.trade_id) {
+                                if let Some(mut stats) = statistics.get_mut(&event.exchange) {
+                                    stats.duplicates += 1;
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    if let Some(late) = buffer.push(event) {
+                        if let Some(mut stats) = statistics.get_mut(&late.exchange) {
+                            stats.late += 1;
+                        }
+                        publish(late);
+                    }
+                }
+                _ = tick.tick() => {}
+            }
+            for event in buffer.release(now_ms()) {
+                publish(event);
+            }
         }
-        
-        Ok(())
     }
 }
 
-/// Aggregated market data across exchanges
-pub struct AggregatedMarketData {
-    trades_by_symbol: DashMap<Symbol, Vec<UniversalTrade>>,
-    quotes_by_symbol: DashMap<Symbol, Vec<UniversalQuote>>,
-    books_by_symbol: DashMap<Symbol, Vec<UniversalOrderBook>>,
-    aggregation_window: Duration,
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
 }
 
-impl AggregatedMarketData {
-    pub fn new(window: Duration) -> Self {
-        Self {
-            trades_by_symbol: DashMap::new(),
-            quotes_by_symbol: DashMap::new(),
-            books_by_symbol: DashMap::new(),
-            aggregation_window: window,
-        }
-    }
-    
-    /// Add trade to aggregation
-    pub fn add_trade(&self, trade: UniversalTrade) {
-        self.trades_by_symbol
-            .entry(trade.symbol.clone())
-            .or_insert_with(Vec::new)
-            .push(trade);
-    }
-    
-    /// Add quote to aggregation
-    pub fn add_quote(&self, quote: UniversalQuote) {
-        self.quotes_by_symbol
-            .entry(quote.symbol.clone())
-            .or_insert_with(Vec::new)
-            .push(quote);
-    }
-    
-    /// Add order book to aggregation
-    pub fn add_orderbook(&self, book: UniversalOrderBook) {
-        self.books_by_symbol
-            .entry(book.symbol.clone())
-            .or_insert_with(Vec::new)
-            .push(book);
-    }
-    
-    /// Get best bid across all exchanges for a symbol
-    pub fn get_best_bid(&self, symbol: &Symbol) -> Option<(f64, f64, Exchange)> {
-        self.quotes_by_symbol
-            .get(symbol)
-            .and_then(|quotes| {
-                quotes.iter()
-                    .filter(|q| q.bid_price > 0.0)
-                    .max_by(|a, b| a.bid_price.partial_cmp(&b.bid_price).unwrap())
-                    .map(|q| (q.bid_price, q.bid_size, q.exchange))
-            })
-    }
-    
-    /// Get best ask across all exchanges for a symbol
-    pub fn get_best_ask(&self, symbol: &Symbol) -> Option<(f64, f64, Exchange)> {
-        self.quotes_by_symbol
-            .get(symbol)
-            .and_then(|quotes| {
-                quotes.iter()
-                    .filter(|q| q.ask_price > 0.0)
-                    .min_by(|a, b| a.ask_price.partial_cmp(&b.ask_price).unwrap())
-                    .map(|q| (q.ask_price, q.ask_size, q.exchange))
-            })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Side, StreamMetrics, StreamSubscription, UniversalTrade};
+    use async_trait::async_trait;
+
+    /// Stream whose data is pushed by the test
+    struct TestStream {
+        receiver: Option<broadcast::Receiver<UniversalMarketData>>,
     }
-    
-    /// Calculate VWAP across exchanges
-    pub fn calculate_vwap(&self, symbol: &Symbol) -> Option<f64> {
-        self.trades_by_symbol
-            .get(symbol)
-            .and_then(|trades| {
-                if trades.is_empty() {
-                    return None;
-                }
-                
-                let total_value: f64 = trades.iter()
-                    .map(|t| t.price * t.quantity)
-                    .sum();
-                
-                let total_volume: f64 = trades.iter()
-                    .map(|t| t.quantity)
-                    .sum();
-                
-                if total_volume > 0.0 {
-                    Some(total_value / total_volume)
-                } else {
-                    None
-                }
-            })
+
+    impl TestStream {
+        fn new() -> (Self, broadcast::Sender<UniversalMarketData>) {
+            let (sender, receiver) = broadcast::channel(64);
+            (Self { receiver: Some(receiver) }, sender)
+        }
     }
-    
-    /// Clear old data outside aggregation window
-    pub fn clean_old_data(&self) {
-        let cutoff = self.time_sync.get_local_time() - 
-            (self.aggregation_window.as_millis() as u64);
-        
-        // Clean trades
-        for mut entry in self.trades_by_symbol.iter_mut() {
-            entry.value_mut().retain(|t| t.timestamp_local > cutoff);
+
+    #[async_trait]
+    impl StreamManager for TestStream {
+        async fn subscribe(&mut self, _subscription: StreamSubscription) -> ExchangeResult<()> {
+            Ok(())
         }
-        
-        // Clean quotes
-        for mut entry in self.quotes_by_symbol.iter_mut() {
-            entry.value_mut().retain(|q| q.timestamp_local > cutoff);
+        async fn unsubscribe(&mut self, _subscription: StreamSubscription) -> ExchangeResult<()> {
+            Ok(())
         }
-        
-        // Clean books
-        for mut entry in self.books_by_symbol.iter_mut() {
-            entry.value_mut().retain(|b| b.timestamp_local > cutoff);
+        fn get_receiver(&mut self) -> Option<broadcast::Receiver<UniversalMarketData>> {
+            self.receiver.take()
+        }
+        async fn get_status(&self) -> ConnectionStatus {
+            ConnectionStatus::Connected
+        }
+        async fn get_metrics(&self) -> StreamMetrics {
+            StreamMetrics::default()
+        }
+        async fn start(&mut self) -> ExchangeResult<()> {
+            Ok(())
+        }
+        async fn stop(&mut self) -> ExchangeResult<()> {
+            Ok(())
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    #[test]
-    fn test_unified_feed_creation() {
-        let config = UnifiedFeedConfig::default();
-        let mut feed = UnifiedMarketFeed::new(config);
-        assert!(feed.subscribe().is_some());
-    }
-    
-    #[test]
-    fn test_aggregated_data() {
-        let aggregated = AggregatedMarketData::new(Duration::from_secs(60));
-        
-        let trade = UniversalTrade {
-            exchange: Exchange::Binance,
+    fn trade(exchange: Exchange, id: &str, time: u64) -> UniversalMarketData {
+        UniversalMarketData::Trade(UniversalTrade {
+            exchange,
             symbol: Symbol::new("BTC-USD"),
             price: 50000.0,
-            quantity: 1.0,
-            side: crate::exchanges::Side::Buy,
-            timestamp_exchange: 0,
-            timestamp_local: 0,
-            trade_id: "test".to_string(),
-        };
-        
-        aggregated.add_trade(trade);
-        
-        let vwap = aggregated.calculate_vwap(&Symbol::new("BTC-USD"));
-        assert_eq!(vwap, Some(50000.0));
+            quantity: 0.1,
+            side: Side::Buy,
+            timestamp_exchange: time,
+            timestamp_local: time,
+            trade_id: id.to_string(),
+        })
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_merges_sources_in_time_order() {
+        let (binance, binance_tx) = TestStream::new();
+        let (coinbase, coinbase_tx) = TestStream::new();
+        let mut feed = UnifiedMarketFeed::new(UnifiedFeedConfig {
+            reorder_window: Duration::from_millis(100),
+            ..Default::default()
+        })
+        .with_source(Exchange::Binance, Box::new(binance))
+        .with_source(Exchange::Coinbase, Box::new(coinbase));
+        let mut events = feed.subscribe();
+        feed.start().await.unwrap();
+
+        // Coinbase's older trade arrives last, but within the window; the
+        // stream mislabels its exchange, which the feed overrides
+        let now = now_ms();
+        binance_tx.send(trade(Exchange::Binance, "b1", now - 10)).unwrap();
+        binance_tx.send(trade(Exchange::Binance, "b1", now - 10)).unwrap(); // Duplicate
+        binance_tx.send(trade(Exchange::Binance, "b2", now - 5)).unwrap();
+        coinbase_tx.send(trade(Exchange::Binance, "c1", now - 20)).unwrap();
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            let event = tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap().unwrap();
+            received.push((event.exchange, event.event_time));
+        }
+        assert_eq!(
+            received,
+            vec![(Exchange::Coinbase, now - 20), (Exchange::Binance, now - 10), (Exchange::Binance, now - 5)]
+        );
+
+        // Too old to be ordered: published at once and counted as late
+        binance_tx.send(trade(Exchange::Binance, "b0", now - 1000)).unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap().unwrap();
+        assert_eq!(event.event_time, now - 1000);
+
+        let stats = feed.get_statistics(Exchange::Binance).unwrap();
+        assert_eq!((stats.messages_received, stats.duplicates, stats.late), (4, 1, 1));
+        assert!(feed.is_healthy(Exchange::Coinbase));
+        assert_eq!(feed.quotes().composite(&Symbol::new("BTC-USD"), now).unwrap().venues.len(), 2);
+        feed.stop().await.unwrap();
+    }
+}
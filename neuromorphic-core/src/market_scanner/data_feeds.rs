@@ -6,6 +6,7 @@ use tokio::sync::{broadcast, RwLock};
 use tokio::time::{interval, Duration};
 use reqwest::Client;
 use crate::exchanges::{Symbol, Exchange};
+use crate::market_data::UnifiedMarketEvent;
 use super::{MarketData, ScannerConfig};
 use chrono::Utc;
 
@@ -141,9 +142,31 @@ impl DataFeedManager {
         self.feeds.insert(Exchange::NASDAQ, Box::new(feed));
     }
 
-    pub async fn start_all_feeds(&self) -> Result<broadcast::Receiver<MarketData>> {
+    /// Start polling the configured exchanges. Events of `market_feed` are
+    /// passed through as they arrive.
+    pub async fn start_all_feeds(
+        &self,
+        market_feed: Option<broadcast::Receiver<UnifiedMarketEvent>>,
+    ) -> Result<broadcast::Receiver<MarketData>> {
         let (tx, rx) = broadcast::channel(10000);
         
+        if let Some(mut events) = market_feed {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => {
+                            if let Some(data) = MarketData::from_event(&event) {
+                                let _ = tx.send(data);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+        
         println!("🚀 Starting data feeds for {} exchanges", self.config.included_exchanges.len());
         for exchange in &self.config.included_exchanges {
             println!("🔍 Processing exchange: {:?}", exchange);
@@ -197,7 +220,7 @@ impl DataFeedManager {
                         }
                     });
                 }
-                _ => {}
+                _ => {} // Streamed exchanges come in through the market feed
             }
         }
         
@@ -267,6 +290,8 @@ impl MarketDataFeed for PolygonFeed {
                     low,
                     change_24h,
                     volume_24h: volume,
+                    exchange: Some(self.exchange),
+                    candle: None,
                 });
             }
         }
@@ -379,6 +404,8 @@ impl MarketDataFeed for YahooFinanceFeed {
                     low,
                     change_24h,
                     volume_24h: volume,
+                    exchange: Some(self.exchange),
+                    candle: None,
                 });
             }
         }
@@ -0,0 +1,352 @@
+//! Recorded price history
+//!
+//! Every market data update the scanner accepts is folded into one-minute
+//! OHLCV bars per symbol, kept for `history_retention_hours`. Coarser bars are
+//! built from them on request, so any granularity from a minute to a day is
+//! exact. The history starts with the process unless it is seeded with older
+//! bars, e.g. from the kline cache of `data`; nothing recorded is kept across
+//! restarts.
+//!
+//! Closed exchange candles, such as Binance kline streams, are taken as the
+//! exchange computed them rather than as one more price: a candle of a minute
+//! or longer replaces the bars it spans with one bar at its start, so with a
+//! one-minute kline stream the bars are the exchange's own, trades the feed
+//! missed included. Shorter candles are merged into their minute.
+
+use super::universe::universe_key;
+use super::{Candle, MarketData};
+use crate::exchanges::Symbol;
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+const MINUTE_MS: u64 = 60_000;
+const HOUR_MS: u64 = 3_600_000;
+
+/// Width of the bars a history query returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Granularity {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl Granularity {
+    pub fn millis(&self) -> u64 {
+        match self {
+            Granularity::OneMinute => MINUTE_MS,
+            Granularity::FiveMinutes => 5 * MINUTE_MS,
+            Granularity::FifteenMinutes => 15 * MINUTE_MS,
+            Granularity::ThirtyMinutes => 30 * MINUTE_MS,
+            Granularity::OneHour => HOUR_MS,
+            Granularity::FourHours => 4 * HOUR_MS,
+            Granularity::OneDay => 24 * HOUR_MS,
+        }
+    }
+}
+
+impl FromStr for Granularity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "1m" => Granularity::OneMinute,
+            "5m" => Granularity::FiveMinutes,
+            "15m" => Granularity::FifteenMinutes,
+            "30m" => Granularity::ThirtyMinutes,
+            "1h" => Granularity::OneHour,
+            "4h" => Granularity::FourHours,
+            "1d" => Granularity::OneDay,
+            other => bail!("Unknown granularity '{}'; use 1m, 5m, 15m, 30m, 1h, 4h or 1d", other),
+        })
+    }
+}
+
+/// Bar of recorded prices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PriceBar {
+    pub timestamp: DateTime<Utc>, // Start of the bar
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MinuteBar {
+    start_ms: u64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+struct Series {
+    bars: VecDeque<MinuteBar>, // Oldest first
+    last_ms: u64,
+}
+
+/// One-minute bars per symbol over the retention period
+pub struct PriceHistory {
+    retention_ms: u64,
+    series: DashMap<String, Series>, // Keyed by `universe_key`
+}
+
+impl PriceHistory {
+    pub fn new(retention_hours: u64) -> Self {
+        Self {
+            retention_ms: retention_hours.max(1) * HOUR_MS,
+            series: DashMap::new(),
+        }
+    }
+
+    /// Hours of bars kept per symbol
+    pub fn retention_hours(&self) -> u64 {
+        self.retention_ms / HOUR_MS
+    }
+
+    pub fn record(&self, data: &MarketData) {
+        if !data.price.is_finite() || data.price <= 0.0 {
+            return;
+        }
+        let mut series = self.series.entry(universe_key(&data.symbol)).or_insert_with(|| Series {
+            bars: VecDeque::new(),
+            last_ms: 0,
+        });
+        if let Some(candle) = data.candle.filter(|c| c.interval.duration_ms().is_none_or(|ms| ms >= MINUTE_MS as i64)) {
+            Self::replace(&mut series, data, &candle);
+            self.prune(&mut series);
+            return;
+        }
+
+        // Late updates count towards the latest minute
+        let time_ms = (data.timestamp.timestamp_millis().max(0) as u64).max(series.last_ms);
+        series.last_ms = time_ms;
+        let start_ms = time_ms - time_ms % MINUTE_MS;
+        let (price, volume) = (data.price, data.volume.max(0.0));
+        let (open, high, low) = if data.candle.is_some() { (data.open, data.high, data.low) } else { (price, price, price) };
+
+        match series.bars.back_mut() {
+            Some(bar) if bar.start_ms == start_ms => {
+                bar.high = bar.high.max(high);
+                bar.low = bar.low.min(low);
+                bar.close = price;
+                bar.volume += volume;
+            }
+            _ => series.bars.push_back(MinuteBar {
+                start_ms,
+                open,
+                high,
+                low,
+                close: price,
+                volume,
+            }),
+        }
+        self.prune(&mut series);
+    }
+
+    /// Put a candle in place of the bars it spans, as one bar at its start
+    fn replace(series: &mut Series, data: &MarketData, candle: &Candle) {
+        let open_ms = candle.open_time.timestamp_millis().max(0) as u64;
+        let start_ms = open_ms - open_ms % MINUTE_MS;
+        let close_ms = (candle.close_time.timestamp_millis().max(0) as u64).max(start_ms);
+        let first = series.bars.partition_point(|bar| bar.start_ms < start_ms);
+        let last = series.bars.partition_point(|bar| bar.start_ms <= close_ms);
+        series.bars.drain(first..last);
+        let bar = MinuteBar {
+            start_ms,
+            open: data.open,
+            high: data.high.max(data.price),
+            low: data.low.min(data.price),
+            close: data.price,
+            volume: data.volume.max(0.0),
+        };
+        series.bars.insert(first, bar);
+        // Updates stamped before the close are already in the candle
+        series.last_ms = series.last_ms.max(close_ms + 1);
+    }
+
+    /// Drop bars older than the retention before the latest one
+    fn prune(&self, series: &mut Series) {
+        let Some(latest_ms) = series.bars.back().map(|bar| bar.start_ms) else { return };
+        let cutoff = (latest_ms + MINUTE_MS).saturating_sub(self.retention_ms);
+        while series.bars.front().is_some_and(|bar| bar.start_ms < cutoff) {
+            series.bars.pop_front();
+        }
+    }
+
+    /// Prepend bars from before the first recorded update, e.g. downloaded
+    /// one-minute klines; wider bars are kept as one minute at their start.
+    /// Bars at or after the first recorded minute are skipped, so live data
+    /// wins. Returns the number of bars seeded.
+    pub fn seed(&self, symbol: &Symbol, bars: &[PriceBar]) -> usize {
+        let mut series = self.series.entry(universe_key(symbol)).or_insert_with(|| Series {
+            bars: VecDeque::new(),
+            last_ms: 0,
+        });
+        let first_ms = series.bars.front().map_or(u64::MAX, |bar| bar.start_ms);
+        let mut seeded: Vec<MinuteBar> = bars
+            .iter()
+            .filter(|bar| bar.close.is_finite() && bar.close > 0.0)
+            .map(|bar| {
+                let time_ms = bar.timestamp.timestamp_millis().max(0) as u64;
+                MinuteBar {
+                    start_ms: time_ms - time_ms % MINUTE_MS,
+                    open: bar.open,
+                    high: bar.high,
+                    low: bar.low,
+                    close: bar.close,
+                    volume: bar.volume.max(0.0),
+                }
+            })
+            .filter(|bar| bar.start_ms < first_ms)
+            .collect();
+        seeded.sort_by_key(|bar| bar.start_ms);
+        seeded.dedup_by_key(|bar| bar.start_ms);
+
+        let Some(latest_ms) = series.bars.back().or(seeded.last()).map(|bar| bar.start_ms) else {
+            return 0;
+        };
+        let cutoff = (latest_ms + MINUTE_MS).saturating_sub(self.retention_ms);
+        seeded.retain(|bar| bar.start_ms >= cutoff);
+        series.last_ms = series.last_ms.max(latest_ms);
+
+        let count = seeded.len();
+        for bar in seeded.into_iter().rev() {
+            series.bars.push_front(bar);
+        }
+        count
+    }
+
+    /// Bars of `granularity` over the last `hours` up to the symbol's latest
+    /// update, oldest first; None for a symbol without recorded prices. The
+    /// symbol matches whatever the separator or case.
+    pub fn bars(&self, symbol: &Symbol, granularity: Granularity, hours: u64) -> Option<Vec<PriceBar>> {
+        let series = self.series.get(&universe_key(symbol))?;
+        let width = granularity.millis();
+        let end_ms = series.bars.back()?.start_ms + MINUTE_MS;
+        let cutoff = end_ms.saturating_sub(hours * HOUR_MS);
+
+        let mut bars: Vec<MinuteBar> = Vec::new();
+        for minute in series.bars.iter().filter(|bar| bar.start_ms >= cutoff) {
+            let start_ms = minute.start_ms - minute.start_ms % width;
+            match bars.last_mut() {
+                Some(bar) if bar.start_ms == start_ms => {
+                    bar.high = bar.high.max(minute.high);
+                    bar.low = bar.low.min(minute.low);
+                    bar.close = minute.close;
+                    bar.volume += minute.volume;
+                }
+                _ => bars.push(MinuteBar { start_ms, ..*minute }),
+            }
+        }
+        Some(
+            bars.into_iter()
+                .map(|bar| PriceBar {
+                    timestamp: DateTime::from_timestamp_millis(bar.start_ms as i64).unwrap_or_default(),
+                    open: bar.open,
+                    high: bar.high,
+                    low: bar.low,
+                    close: bar.close,
+                    volume: bar.volume,
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(symbol: &str, price: f64, minute: u64) -> MarketData {
+        let mut data = MarketData::new(Symbol::new(symbol), price);
+        data.volume = 1.0;
+        data.timestamp = DateTime::from_timestamp_millis((minute * MINUTE_MS) as i64).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_downsampled_bars_and_retention() {
+        let history = PriceHistory::new(2);
+        for minute in 0..180 {
+            history.record(&update("BTCUSDT", 100.0 + minute as f64, minute));
+        }
+
+        // Only the last two hours are kept; asked for more, that's what comes back
+        let minutes = history.bars(&Symbol::new("btc-usdt"), Granularity::OneMinute, 24).unwrap();
+        assert_eq!((minutes.len(), minutes[0].open), (120, 160.0));
+
+        let hours = history.bars(&Symbol::new("BTCUSDT"), Granularity::OneHour, 1).unwrap();
+        assert_eq!(hours.len(), 1);
+        assert_eq!((hours[0].open, hours[0].high, hours[0].low, hours[0].close), (220.0, 279.0, 220.0, 279.0));
+        assert_eq!(hours[0].volume, 60.0);
+        assert_eq!(history.bars(&Symbol::new("BTCUSDT"), Granularity::FifteenMinutes, 2).unwrap().len(), 8);
+
+        assert!(history.bars(&Symbol::new("ETHUSDT"), Granularity::OneMinute, 24).is_none());
+        assert!("2h".parse::<Granularity>().is_err());
+    }
+
+    #[test]
+    fn test_exchange_candles_replace_the_bars_they_span() {
+        use crate::exchanges::{Exchange, KlineInterval, UniversalKline, UniversalMarketData};
+        use crate::market_data::UnifiedMarketEvent;
+
+        let kline = |interval: KlineInterval, minute: u64, minutes: u64| {
+            let open_time = DateTime::from_timestamp_millis((minute * MINUTE_MS) as i64).unwrap();
+            let kline = UniversalKline {
+                symbol: Symbol::new("BTCUSDT"),
+                exchange: Exchange::Binance,
+                interval,
+                open_time,
+                close_time: open_time + chrono::Duration::milliseconds((minutes * MINUTE_MS) as i64 - 1),
+                open: 99.0,
+                high: 103.0,
+                low: 98.0,
+                close: 100.5,
+                volume: 7.0,
+                quote_volume: 700.0,
+                trades_count: 12,
+                taker_buy_volume: 3.0,
+                taker_buy_quote_volume: 300.0,
+            };
+            MarketData::from_event(&UnifiedMarketEvent::new(Exchange::Binance, UniversalMarketData::Kline(kline), 0)).unwrap()
+        };
+        let history = PriceHistory::new(24);
+        for (price, minute) in [(100.0, 0), (101.0, 0), (105.0, 1)] {
+            history.record(&update("BTCUSDT", price, minute));
+        }
+
+        // The closed minute arrives after the next one started; a trade
+        // stamped inside it afterwards counts towards the latest minute
+        history.record(&kline(KlineInterval::OneMinute, 0, 1));
+        history.record(&update("BTCUSDT", 104.0, 0));
+        let bars = history.bars(&Symbol::new("BTCUSDT"), Granularity::OneMinute, 24).unwrap();
+        assert_eq!((bars[0].open, bars[0].high, bars[0].low, bars[0].close, bars[0].volume), (99.0, 103.0, 98.0, 100.5, 7.0));
+        assert_eq!((bars[1].low, bars[1].close, bars[1].volume), (104.0, 104.0, 2.0));
+
+        // An hourly candle stands in for the minutes of its hour
+        history.record(&update("BTCUSDT", 110.0, 61));
+        history.record(&kline(KlineInterval::OneHour, 60, 60));
+        let bars = history.bars(&Symbol::new("BTCUSDT"), Granularity::OneMinute, 24).unwrap();
+        assert_eq!(bars.len(), 3);
+        assert_eq!((bars[2].timestamp.timestamp_millis(), bars[2].close), (60 * MINUTE_MS as i64, 100.5));
+    }
+}
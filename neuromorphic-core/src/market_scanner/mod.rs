@@ -1,22 +1,47 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{broadcast, RwLock};
 use chrono::{DateTime, Utc};
-use crate::exchanges::{Symbol, Exchange};
+use tracing::warn;
+use utoipa::ToSchema;
+use crate::exchanges::{Symbol, Exchange, KlineInterval, Side, UniversalKline, UniversalMarketData};
+use crate::market_data::UnifiedMarketEvent;
+use crate::paper_trading::{TradingSignal, SignalAction, SignalMetadata};
 
 pub mod scanner;
 pub mod screener;
 pub mod strategies;
 pub mod analytics;
 pub mod data_feeds;
+pub mod universe;
+pub mod movers;
+pub mod history;
+pub mod opportunities;
+pub mod regime;
+pub mod spike_patterns;
+pub mod onnx;
+pub mod wasm_plugins;
 
 pub use scanner::MarketScanner;
-pub use screener::{StockScreener, ScreeningCriteria};
+pub use screener::{StockScreener, ScreeningCriteria, ScreeningPredicate};
 pub use strategies::{StrategyEngine, TradingStrategy};
 pub use analytics::MarketAnalytics;
 pub use data_feeds::{DataFeedManager, MarketDataFeed};
+pub use movers::{MarketMovers, MoversTracker, SymbolStats};
+pub use history::{Granularity, PriceBar, PriceHistory};
+pub use opportunities::{OpportunityState, OpportunityStore, StrategyHitRate, TrackedOpportunity};
+pub use regime::{RegimeConfig, RegimeDetector, RegimeState};
+pub use onnx::{ModelFeature, OnnxStrategyConfig};
+#[cfg(feature = "onnx")]
+pub use onnx::OnnxStrategy;
+pub use wasm_plugins::{PluginInput, PluginOpportunity, WasmPluginConfig, PLUGIN_ABI_VERSION};
+#[cfg(feature = "wasm")]
+pub use wasm_plugins::WasmStrategy;
+pub use spike_patterns::{PopulationPatternClassifier, SpikePattern, SpikePatternClassifier, SpikePatterns};
+pub use universe::{UniverseCandidate, UniverseConfig, UniverseManager, UniverseSource, UniverseStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketData {
@@ -31,6 +56,25 @@ pub struct MarketData {
     pub low: f64,
     pub change_24h: f64,
     pub volume_24h: f64,
+    #[serde(default)]
+    pub exchange: Option<Exchange>, // Venue the data came from, where known
+    #[serde(default)]
+    pub candle: Option<Candle>, // Set when the update is a closed exchange candle
+}
+
+/// Span of an exchange-computed candle; the update's open, high, low, price
+/// and volume are its OHLCV
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub interval: KlineInterval,
+    pub open_time: DateTime<Utc>,
+    pub close_time: DateTime<Utc>,
+}
+
+impl From<&UniversalKline> for Candle {
+    fn from(kline: &UniversalKline) -> Self {
+        Self { interval: kline.interval, open_time: kline.open_time, close_time: kline.close_time }
+    }
 }
 
 impl MarketData {
@@ -48,11 +92,39 @@ impl MarketData {
             low: price,
             change_24h: 0.0,
             volume_24h: 0.0,
+            exchange: None,
+            candle: None,
         }
     }
+
+    /// Snapshot from a unified feed event; `None` if it carries no price
+    pub fn from_event(event: &UnifiedMarketEvent) -> Option<Self> {
+        let mut data = Self::new(event.symbol().clone(), event.price()?);
+        data.timestamp = DateTime::from_timestamp_millis(event.event_time as i64).unwrap_or(data.timestamp);
+        data.exchange = Some(event.exchange);
+        match &event.data {
+            UniversalMarketData::Trade(trade) => data.volume = trade.quantity,
+            UniversalMarketData::Quote(quote) => {
+                data.bid = Some(quote.bid_price);
+                data.ask = Some(quote.ask_price);
+            }
+            UniversalMarketData::OrderBook(book) => {
+                data.bid = book.bids.first().map(|level| level.0);
+                data.ask = book.asks.first().map(|level| level.0);
+            }
+            UniversalMarketData::Kline(kline) => {
+                data.volume = kline.volume;
+                data.open = kline.open;
+                data.high = kline.high;
+                data.low = kline.low;
+                data.candle = Some(Candle::from(kline));
+            }
+        }
+        Some(data)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TradingOpportunity {
     pub symbol: Symbol,
     pub strategy: String,
@@ -66,6 +138,84 @@ pub struct TradingOpportunity {
     pub reasoning: String,
     pub risk_score: f64,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub exchange: Option<Exchange>, // Where the setup was seen; trades route there
+}
+
+impl TradingOpportunity {
+    /// Maximum holding time implied by `time_horizon`, using the upper bound
+    /// of ranges like "4-8 hours" or "1-3 days". Returns None if unparseable.
+    pub fn max_hold_duration(&self) -> Option<std::time::Duration> {
+        let horizon = self.time_horizon.trim().to_lowercase();
+        let split = horizon
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(horizon.len());
+        let (amount, unit) = horizon.split_at(split);
+        
+        let amount: f64 = amount.rsplit('-').next()?.trim().parse().ok()?;
+        let unit_secs = match unit.trim() {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            "w" | "week" | "weeks" => 604800.0,
+            _ => return None,
+        };
+        
+        if !amount.is_finite() || amount <= 0.0 {
+            return None;
+        }
+        Some(std::time::Duration::from_secs_f64(amount * unit_secs))
+    }
+
+    /// Side of the trade: from the exits when they are set, otherwise from
+    /// the sign of `expected_move`; None when there is no direction
+    pub fn side(&self) -> Option<Side> {
+        let entry = self.entry_price;
+        match (self.take_profit, self.stop_loss) {
+            (Some(target), _) if target > entry => Some(Side::Buy),
+            (Some(target), _) if target < entry => Some(Side::Sell),
+            (_, Some(stop)) if stop < entry => Some(Side::Buy),
+            (_, Some(stop)) if stop > entry => Some(Side::Sell),
+            _ if self.expected_move > 0.0 => Some(Side::Buy),
+            _ if self.expected_move < 0.0 => Some(Side::Sell),
+            _ => None,
+        }
+    }
+
+    /// Trading signal for `notional` of this opportunity, carrying its exits,
+    /// on its own exchange or `default_exchange` when it has none
+    pub fn to_signal(&self, default_exchange: Exchange, notional: f64) -> TradingSignal {
+        let action = match self.side() {
+            Some(Side::Buy) => SignalAction::Buy { size_hint: Some(notional) },
+            Some(Side::Sell) => SignalAction::Sell { size_hint: Some(notional) },
+            None => SignalAction::Hold,
+        };
+
+        TradingSignal {
+            symbol: self.symbol.clone(),
+            exchange: self.exchange.unwrap_or(default_exchange),
+            action,
+            confidence: self.confidence,
+            urgency: 0.8,
+            metadata: SignalMetadata {
+                spike_count: 100,
+                pattern_strength: self.confidence,
+                volatility: self.risk_score,
+                market_regime: "autonomous".to_string(),
+                max_hold: self.max_hold_duration(),
+                account_id: None,
+                strategy: Some(self.strategy.clone()),
+                size_multiplier: None,
+                signal_id: None,
+                source_id: None,
+                stop_loss: self.stop_loss,
+                take_profit: self.take_profit,
+                group_id: None,
+                decision_price: Some(self.entry_price),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,7 +229,7 @@ pub struct MarketMetrics {
     pub overall_sentiment: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum MarketRegime {
     StrongBull,
     MildBull,
@@ -90,7 +240,31 @@ pub enum MarketRegime {
     LowVolatility,
 }
 
-#[derive(Debug, Clone)]
+impl MarketRegime {
+    /// Market direction: 1.0 strong bull, 0.5 mild bull, negative for bears
+    pub fn direction(&self) -> f64 {
+        match self {
+            MarketRegime::StrongBull => 1.0,
+            MarketRegime::MildBull => 0.5,
+            MarketRegime::MildBear => -0.5,
+            MarketRegime::StrongBear => -1.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Scale for entry thresholds: noisy markets need bigger moves to mean
+    /// anything, quiet ones make smaller moves worth trading
+    pub fn threshold_multiplier(&self) -> f64 {
+        match self {
+            MarketRegime::HighVolatility => 1.5,
+            MarketRegime::LowVolatility => 0.75,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ScannerConfig {
     pub max_symbols: usize,
     pub scan_interval_ms: u64,
@@ -104,6 +278,14 @@ pub struct ScannerConfig {
     pub momentum_lookback_periods: Vec<usize>,
     pub volatility_threshold: f64,
     pub volume_spike_threshold: f64,
+    pub universe: UniverseConfig,
+    pub regime: RegimeConfig,
+    pub history_retention_hours: u64, // One-minute price bars kept for the history API
+    pub history_cache: Option<PathBuf>, // Kline cache the history is seeded from at startup, see `data`
+    pub screening: ScreeningCriteria, // Filters of the periodic screening pass; reloadable
+    pub screening_script: Option<PathBuf>, // Rhai predicate screened symbols must also pass, see `scripting`
+    pub models: Vec<OnnxStrategyConfig>, // Strategies run by ONNX models, see `onnx`
+    pub plugins: Vec<WasmPluginConfig>, // Strategies run by WASM modules, see `wasm_plugins`
 }
 
 impl Default for ScannerConfig {
@@ -121,6 +303,14 @@ impl Default for ScannerConfig {
             momentum_lookback_periods: vec![5, 15, 30, 60],
             volatility_threshold: 2.0,
             volume_spike_threshold: 3.0,
+            universe: UniverseConfig::default(),
+            regime: RegimeConfig::default(),
+            history_retention_hours: 72,
+            history_cache: None,
+            screening: ScreeningCriteria::default(),
+            screening_script: None,
+            models: Vec::new(),
+            plugins: Vec::new(),
         }
     }
 }
@@ -135,16 +325,28 @@ pub struct MarketScannerService {
     strategy_engine: Arc<StrategyEngine>,
     data_feeds: Arc<DataFeedManager>,
     market_data: Arc<RwLock<HashMap<Symbol, MarketData>>>,
+    universe: Arc<UniverseManager>,
+    movers: Arc<MoversTracker>,
+    history: Arc<PriceHistory>,
+    regime: Arc<RegimeDetector>,
+    opportunities: Arc<OpportunityStore>,
+    market_feed: Option<Arc<broadcast::Receiver<UnifiedMarketEvent>>>,
     config: ScannerConfig,
 }
 
 impl MarketScannerService {
     pub fn new(config: ScannerConfig) -> Self {
         let scanner = Arc::new(MarketScanner::new(config.clone()));
-        let screener = Arc::new(StockScreener::new());
-        let strategy_engine = Arc::new(StrategyEngine::new());
+        let screener = Arc::new(Self::build_screener(&config));
         let data_feeds = Arc::new(DataFeedManager::new(config.clone()));
         let market_data = Arc::new(RwLock::new(HashMap::new()));
+        let universe = Arc::new(UniverseManager::new(&config));
+        let movers = Arc::new(MoversTracker::new(config.volume_spike_threshold));
+        let history = Arc::new(PriceHistory::new(config.history_retention_hours));
+        Self::seed_history(&config, &history);
+        let strategy_engine = Arc::new(Self::strategy_engine(&config, &history));
+        let regime = Arc::new(RegimeDetector::new(config.regime.clone()));
+        let opportunities = Arc::new(OpportunityStore::new());
 
         Self {
             scanner,
@@ -152,10 +354,110 @@ impl MarketScannerService {
             strategy_engine,
             data_feeds,
             market_data,
+            universe,
+            movers,
+            history,
+            regime,
+            opportunities,
+            market_feed: None,
             config,
         }
     }
 
+    /// Scan the exchanges of a unified market feed along with the polled feeds
+    pub fn with_market_feed(mut self, events: broadcast::Receiver<UnifiedMarketEvent>) -> Self {
+        self.market_feed = Some(Arc::new(events));
+        self
+    }
+
+    /// Drive the neuromorphic strategy from spike patterns, see `spike_patterns`
+    pub fn with_spike_patterns(mut self, patterns: Arc<SpikePatterns>) -> Self {
+        self.strategy_engine = Arc::new(Self::strategy_engine(&self.config, &self.history).with_spike_patterns(patterns));
+        self
+    }
+
+    /// One-minute bars of the kline cache, so the history doesn't start empty
+    #[cfg_attr(not(feature = "parquet"), allow(unused_variables))]
+    fn seed_history(config: &ScannerConfig, history: &PriceHistory) {
+        let Some(dir) = &config.history_cache else { return };
+        #[cfg(feature = "parquet")]
+        match crate::data::KlineCache::new(dir).seed_history(history, Utc::now()) {
+            Ok(bars) => tracing::info!(bars, cache = %dir.display(), "Seeded price history"),
+            Err(e) => warn!(cache = %dir.display(), error = %format!("{:#}", e), "Skipping price history seeding"),
+        }
+        #[cfg(not(feature = "parquet"))]
+        warn!(cache = %dir.display(), "Built without the parquet feature; skipping price history seeding");
+    }
+
+    /// Screener of the configured criteria and script
+    fn build_screener(config: &ScannerConfig) -> StockScreener {
+        let screener = StockScreener::new().with_criteria(config.screening.clone());
+        let Some(path) = &config.screening_script else { return screener };
+        #[cfg(feature = "scripting")]
+        match crate::scripting::ScriptScreen::load(path) {
+            Ok(script) => return screener.with_predicate(Arc::new(script)),
+            Err(e) => warn!(script = %path.display(), error = %format!("{:#}", e), "Skipping screening script"),
+        }
+        #[cfg(not(feature = "scripting"))]
+        warn!(script = %path.display(), "Built without the scripting feature; skipping screening script");
+        screener
+    }
+
+    /// The built-in strategies plus the configured models and plugins
+    #[cfg_attr(not(feature = "onnx"), allow(unused_variables, unused_mut))]
+    fn strategy_engine(config: &ScannerConfig, history: &Arc<PriceHistory>) -> StrategyEngine {
+        let mut engine = StrategyEngine::new();
+        for model in &config.models {
+            #[cfg(feature = "onnx")]
+            match OnnxStrategy::load(model.clone(), history.clone()) {
+                Ok(strategy) => engine = engine.with_strategy(Box::new(strategy)),
+                Err(e) => warn!(model = %model.name, error = %format!("{:#}", e), "Skipping model strategy"),
+            }
+            #[cfg(not(feature = "onnx"))]
+            warn!(model = %model.name, "Built without the onnx feature; skipping model strategy");
+        }
+        for plugin in &config.plugins {
+            #[cfg(feature = "wasm")]
+            match WasmStrategy::load(plugin.clone()) {
+                Ok(strategy) => engine = engine.with_strategy(Box::new(strategy)),
+                Err(e) => warn!(plugin = %plugin.name, error = %format!("{:#}", e), "Skipping plugin strategy"),
+            }
+            #[cfg(not(feature = "wasm"))]
+            warn!(plugin = %plugin.name, "Built without the wasm feature; skipping plugin strategy");
+        }
+        engine
+    }
+
+    /// Symbols the scanner analyses; updates for other symbols are dropped
+    pub fn universe(&self) -> &Arc<UniverseManager> {
+        &self.universe
+    }
+
+    /// Rolling 24h statistics and top movers of the scanned symbols
+    pub fn movers(&self) -> &Arc<MoversTracker> {
+        &self.movers
+    }
+
+    /// Recorded one-minute price bars of the scanned symbols
+    pub fn price_history(&self) -> &Arc<PriceHistory> {
+        &self.history
+    }
+
+    /// Screener of the periodic pass over all scanned symbols
+    pub fn screener(&self) -> &Arc<StockScreener> {
+        &self.screener
+    }
+
+    /// Market regime the strategies adapt to
+    pub fn regime(&self) -> &Arc<RegimeDetector> {
+        &self.regime
+    }
+
+    /// Lifecycle and hit rates of the opportunities the scanner has emitted
+    pub fn opportunities(&self) -> &Arc<OpportunityStore> {
+        &self.opportunities
+    }
+
     pub async fn start(&self) -> Result<(MarketDataStream, OpportunityStream)> {
         let (market_tx, market_rx) = broadcast::channel(10000);
         let (opportunity_tx, opportunity_rx) = broadcast::channel(1000);
@@ -165,10 +467,17 @@ impl MarketScannerService {
         let screener = self.screener.clone();
         let strategy_engine = self.strategy_engine.clone();
         let market_data = self.market_data.clone();
+        let market_feed = self.market_feed.as_ref().map(|events| events.resubscribe());
+        let universe = self.universe.clone();
+        universe.clone().spawn();
+        let movers = self.movers.clone();
+        let history = self.history.clone();
+        let regime = self.regime.clone();
+        let store = self.opportunities.clone();
 
         tokio::spawn(async move {
             println!("📡 Initializing data feeds...");
-            let mut data_stream = match data_feeds.start_all_feeds().await {
+            let mut data_stream = match data_feeds.start_all_feeds(market_feed).await {
                 Ok(stream) => {
                     println!("✅ Data feeds started successfully");
                     stream
@@ -182,6 +491,13 @@ impl MarketScannerService {
             loop {
                 tokio::select! {
                     Ok(market_update) = data_stream.recv() => {
+                        if !universe.contains(&market_update.symbol) {
+                            continue;
+                        }
+                        movers.record(&market_update);
+                        history.record(&market_update);
+                        regime.record(&market_update);
+                        store.record(&market_update);
                         {
                             let mut data = market_data.write().await;
                             data.insert(market_update.symbol.clone(), market_update.clone());
@@ -189,18 +505,22 @@ impl MarketScannerService {
                         
                         let _ = market_tx.send(market_update.clone());
                         
-                        if let Ok(opportunities) = strategy_engine.analyze_opportunity(&market_update).await {
+                        if let Ok(opportunities) = strategy_engine.analyze_opportunity(&market_update, regime.regime()).await {
                             for opportunity in opportunities {
+                                store.track(&opportunity);
                                 let _ = opportunity_tx.send(opportunity);
                             }
                         }
                     }
                     _ = tokio::time::sleep(tokio::time::Duration::from_millis(1000)) => {
-                        let data = market_data.read().await;
+                        let mut data = market_data.write().await;
+                        data.retain(|symbol, _| universe.contains(symbol));
+                        let current_regime = regime.regime();
                         if let Ok(filtered_symbols) = screener.screen_symbols(data.values().cloned().collect()).await {
                             for symbol_data in filtered_symbols {
-                                if let Ok(opportunities) = strategy_engine.analyze_opportunity(&symbol_data).await {
+                                if let Ok(opportunities) = strategy_engine.analyze_opportunity(&symbol_data, current_regime).await {
                                     for opportunity in opportunities {
+                                        store.track(&opportunity);
                                         let _ = opportunity_tx.send(opportunity);
                                     }
                                 }
@@ -217,15 +537,23 @@ impl MarketScannerService {
     pub async fn get_market_metrics(&self) -> Result<MarketMetrics> {
         let data = self.market_data.read().await;
         let analytics = MarketAnalytics::new();
-        analytics.calculate_market_metrics(data.values().cloned().collect()).await
+        let mut metrics = analytics.calculate_market_metrics(data.values().cloned().collect()).await?;
+        // The detector tracks the index over time; the snapshot guess only
+        // stands in while it warms up
+        let regime = self.regime.state();
+        if regime.warmed_up {
+            metrics.market_regime = regime.regime;
+        }
+        Ok(metrics)
     }
 
     pub async fn get_top_opportunities(&self, limit: usize) -> Result<Vec<TradingOpportunity>> {
         let data = self.market_data.read().await;
+        let regime = self.regime.regime();
         let mut all_opportunities = Vec::new();
         
         for market_data in data.values() {
-            if let Ok(opportunities) = self.strategy_engine.analyze_opportunity(market_data).await {
+            if let Ok(opportunities) = self.strategy_engine.analyze_opportunity(market_data, regime).await {
                 all_opportunities.extend(opportunities);
             }
         }
@@ -235,4 +563,70 @@ impl MarketScannerService {
         
         Ok(all_opportunities)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    #[test]
+    fn test_time_horizon_parsing() {
+        let mut opportunity = TradingOpportunity {
+            symbol: Symbol::new("AAPL"),
+            strategy: "Momentum".to_string(),
+            confidence: 0.8,
+            expected_move: 0.02,
+            time_horizon: "4-8 hours".to_string(),
+            entry_price: 100.0,
+            stop_loss: None,
+            take_profit: None,
+            position_size: 1000.0,
+            reasoning: String::new(),
+            risk_score: 0.3,
+            timestamp: Utc::now(),
+            exchange: None,
+        };
+        assert_eq!(opportunity.max_hold_duration(), Some(Duration::from_secs(8 * 3600)));
+        
+        opportunity.time_horizon = "1-3 days".to_string();
+        assert_eq!(opportunity.max_hold_duration(), Some(Duration::from_secs(3 * 86400)));
+        
+        opportunity.time_horizon = "4h".to_string();
+        assert_eq!(opportunity.max_hold_duration(), Some(Duration::from_secs(4 * 3600)));
+        
+        opportunity.time_horizon = "swing".to_string();
+        assert_eq!(opportunity.max_hold_duration(), None);
+    }
+
+    #[test]
+    fn test_signal_carries_exits_and_exchange() {
+        let mut opportunity = TradingOpportunity {
+            symbol: Symbol::new("ETHUSDT"),
+            strategy: "Volume Spike Momentum".to_string(),
+            confidence: 0.8,
+            expected_move: 4.0, // Strategies report the size of the move, not its sign
+            time_horizon: "4-8 hours".to_string(),
+            entry_price: 2000.0,
+            stop_loss: Some(2060.0),
+            take_profit: Some(1880.0),
+            position_size: 0.04,
+            reasoning: String::new(),
+            risk_score: 0.3,
+            timestamp: Utc::now(),
+            exchange: Some(Exchange::Binance),
+        };
+
+        let signal = opportunity.to_signal(Exchange::NYSE, 4000.0);
+        assert!(matches!(signal.action, SignalAction::Sell { size_hint: Some(s) } if s == 4000.0));
+        assert_eq!(signal.exchange, Exchange::Binance);
+        assert_eq!((signal.metadata.stop_loss, signal.metadata.take_profit), (Some(2060.0), Some(1880.0)));
+
+        opportunity.exchange = None;
+        opportunity.stop_loss = None;
+        opportunity.take_profit = None;
+        let signal = opportunity.to_signal(Exchange::NYSE, 4000.0);
+        assert!(matches!(signal.action, SignalAction::Buy { .. }));
+        assert_eq!(signal.exchange, Exchange::NYSE);
+    }
+}
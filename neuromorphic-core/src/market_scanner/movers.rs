@@ -0,0 +1,255 @@
+//! Top movers and unusual volume
+//!
+//! Keeps a rolling 24 hour window of one-minute bars per symbol, built from
+//! the scanner's market data updates, and ranks symbols by 24h change,
+//! turnover and volatility. Volume is summed from the traded volume of the
+//! updates, so it covers only what the feeds have delivered since startup.
+//!
+//! Volume is unusual when the last hour traded at least `spike_ratio` times
+//! the average hour of the rest of the window; at least two earlier hours are
+//! needed before a symbol is judged.
+
+use super::MarketData;
+use crate::exchanges::Symbol;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use utoipa::ToSchema;
+
+const MINUTE_MS: u64 = 60_000;
+const HOUR_MS: u64 = 3_600_000;
+const DAY_MS: u64 = 86_400_000;
+
+/// Rolling 24h statistics of one symbol
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolStats {
+    pub symbol: Symbol,
+    pub price: f64,
+    pub open_24h: f64,
+    pub high_24h: f64,
+    pub low_24h: f64,
+    pub change_pct_24h: f64,
+    pub volume_24h: f64,
+    pub turnover_24h: f64, // Volume times price, comparable across symbols
+    pub volatility_pct: f64, // Daily, from one-minute returns
+    pub volume_ratio: Option<f64>, // Last hour over the average earlier hour
+    pub last_update: DateTime<Utc>,
+}
+
+/// Ranked lists of the symbols seen in the last 24 hours
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct MarketMovers {
+    pub timestamp: Option<DateTime<Utc>>, // Latest update across symbols
+    pub top_gainers: Vec<SymbolStats>,
+    pub top_losers: Vec<SymbolStats>,
+    pub volume_leaders: Vec<SymbolStats>,
+    pub volatility_leaders: Vec<SymbolStats>,
+    pub unusual_volume: Vec<SymbolStats>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MinuteBar {
+    start_ms: u64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    turnover: f64,
+}
+
+#[derive(Debug, Default)]
+struct SymbolWindow {
+    bars: VecDeque<MinuteBar>, // Oldest first
+    last_ms: u64,
+}
+
+impl SymbolWindow {
+    fn record(&mut self, price: f64, volume: f64, time_ms: u64) {
+        // Late updates count towards the latest minute
+        let time_ms = time_ms.max(self.last_ms);
+        self.last_ms = time_ms;
+        let start_ms = time_ms - time_ms % MINUTE_MS;
+
+        match self.bars.back_mut() {
+            Some(bar) if bar.start_ms == start_ms => {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += volume;
+                bar.turnover += volume * price;
+            }
+            _ => self.bars.push_back(MinuteBar {
+                start_ms,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume,
+                turnover: volume * price,
+            }),
+        }
+
+        let cutoff = time_ms.saturating_sub(DAY_MS);
+        while self.bars.front().is_some_and(|bar| bar.start_ms < cutoff) {
+            self.bars.pop_front();
+        }
+    }
+
+    fn stats(&self, symbol: &Symbol) -> Option<SymbolStats> {
+        let first = self.bars.front()?;
+        let last = self.bars.back()?;
+        let change_pct_24h = if first.open > 0.0 { (last.close - first.open) / first.open * 100.0 } else { 0.0 };
+
+        let returns: Vec<f64> = self
+            .bars
+            .iter()
+            .zip(self.bars.iter().skip(1))
+            .filter(|(a, b)| a.close > 0.0 && b.close > 0.0)
+            .map(|(a, b)| (b.close / a.close).ln())
+            .collect();
+        let volatility_pct = if returns.len() >= 2 {
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+            variance.sqrt() * ((DAY_MS / MINUTE_MS) as f64).sqrt() * 100.0
+        } else {
+            0.0
+        };
+
+        // The last hour is the sixty minutes ending with the latest bar
+        let hour_start = (last.start_ms + MINUTE_MS).saturating_sub(HOUR_MS);
+        let (recent, earlier): (Vec<&MinuteBar>, Vec<&MinuteBar>) = self.bars.iter().partition(|bar| bar.start_ms >= hour_start);
+        let earlier_hours = earlier.first().map_or(0.0, |bar| (hour_start - bar.start_ms) as f64 / HOUR_MS as f64);
+        let earlier_volume: f64 = earlier.iter().map(|bar| bar.volume).sum();
+        let volume_ratio = (earlier_hours >= 2.0 && earlier_volume > 0.0)
+            .then(|| recent.iter().map(|bar| bar.volume).sum::<f64>() / (earlier_volume / earlier_hours));
+
+        Some(SymbolStats {
+            symbol: symbol.clone(),
+            price: last.close,
+            open_24h: first.open,
+            high_24h: self.bars.iter().map(|bar| bar.high).fold(f64::MIN, f64::max),
+            low_24h: self.bars.iter().map(|bar| bar.low).fold(f64::MAX, f64::min),
+            change_pct_24h,
+            volume_24h: self.bars.iter().map(|bar| bar.volume).sum(),
+            turnover_24h: self.bars.iter().map(|bar| bar.turnover).sum(),
+            volatility_pct,
+            volume_ratio,
+            last_update: DateTime::from_timestamp_millis(self.last_ms as i64).unwrap_or_default(),
+        })
+    }
+}
+
+/// Rolling 24h statistics per symbol
+pub struct MoversTracker {
+    spike_ratio: f64,
+    windows: DashMap<Symbol, SymbolWindow>,
+}
+
+impl MoversTracker {
+    pub fn new(spike_ratio: f64) -> Self {
+        Self {
+            spike_ratio,
+            windows: DashMap::new(),
+        }
+    }
+
+    pub fn record(&self, data: &MarketData) {
+        self.update(&data.symbol, data.price, data.volume, data.timestamp);
+    }
+
+    /// Record a price and the volume traded at it
+    pub fn update(&self, symbol: &Symbol, price: f64, volume: f64, time: DateTime<Utc>) {
+        if !price.is_finite() || price <= 0.0 {
+            return;
+        }
+        let (volume, time_ms) = (volume.max(0.0), time.timestamp_millis().max(0) as u64);
+        match self.windows.get_mut(symbol) {
+            Some(mut window) => window.record(price, volume, time_ms),
+            None => self.windows.entry(symbol.clone()).or_default().record(price, volume, time_ms),
+        }
+    }
+
+    pub fn stats(&self, symbol: &Symbol) -> Option<SymbolStats> {
+        self.windows.get(symbol)?.stats(symbol)
+    }
+
+    /// Statistics of every symbol updated within 24 hours of the latest
+    /// update, by symbol
+    pub fn all_stats(&self) -> Vec<SymbolStats> {
+        let latest_ms = self.windows.iter().map(|w| w.last_ms).max().unwrap_or(0);
+        let mut stats: Vec<SymbolStats> = self
+            .windows
+            .iter()
+            .filter(|w| w.last_ms + DAY_MS > latest_ms)
+            .filter_map(|w| w.stats(w.key()))
+            .collect();
+        stats.sort_by(|a, b| a.symbol.0.cmp(&b.symbol.0));
+        stats
+    }
+
+    /// Top `limit` symbols of each list
+    pub fn movers(&self, limit: usize) -> MarketMovers {
+        let stats = self.all_stats();
+        let ranked = |keep: &dyn Fn(&SymbolStats) -> bool, key: &dyn Fn(&SymbolStats) -> f64| {
+            let mut ranked: Vec<SymbolStats> = stats.iter().filter(|s| keep(s)).cloned().collect();
+            ranked.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(std::cmp::Ordering::Equal));
+            ranked.truncate(limit);
+            ranked
+        };
+
+        MarketMovers {
+            timestamp: stats.iter().map(|s| s.last_update).max(),
+            top_gainers: ranked(&|s| s.change_pct_24h > 0.0, &|s| s.change_pct_24h),
+            top_losers: ranked(&|s| s.change_pct_24h < 0.0, &|s| -s.change_pct_24h),
+            volume_leaders: ranked(&|s| s.turnover_24h > 0.0, &|s| s.turnover_24h),
+            volatility_leaders: ranked(&|s| s.volatility_pct > 0.0, &|s| s.volatility_pct),
+            unusual_volume: ranked(
+                &|s| s.volume_ratio.is_some_and(|r| r >= self.spike_ratio),
+                &|s| s.volume_ratio.unwrap_or(0.0),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(symbol: &str, price: f64, volume: f64, minute: u64) -> MarketData {
+        let mut data = MarketData::new(Symbol::new(symbol), price);
+        data.volume = volume;
+        data.timestamp = DateTime::from_timestamp_millis((minute * MINUTE_MS) as i64).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_rankings_over_rolling_window() {
+        let tracker = MoversTracker::new(3.0);
+
+        // Four hours: BTC drifts up on steady volume, ETH falls and trades
+        // ten times its usual volume in the last hour
+        for minute in 0..240 {
+            let eth_volume = if minute >= 180 { 100.0 } else { 10.0 };
+            tracker.record(&update("BTCUSDT", 100.0 + minute as f64 * 0.05, 1.0, minute));
+            tracker.record(&update("ETHUSDT", 50.0 - minute as f64 * 0.01, eth_volume, minute));
+        }
+        // A day-old price has left the window by the time SOL trades again
+        tracker.record(&update("SOLUSDT", 10.0, 5.0, 0));
+        tracker.record(&update("SOLUSDT", 20.0, 5.0, 1500));
+
+        let movers = tracker.movers(5);
+        let names = |list: &[SymbolStats]| list.iter().map(|s| s.symbol.0.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&movers.top_gainers), ["BTCUSDT"]);
+        assert_eq!(names(&movers.top_losers), ["ETHUSDT"]);
+        assert_eq!(names(&movers.unusual_volume), ["ETHUSDT"]);
+        assert_eq!(names(&movers.volume_leaders), ["ETHUSDT", "BTCUSDT", "SOLUSDT"]);
+
+        let eth = tracker.stats(&Symbol::new("ETHUSDT")).unwrap();
+        assert!((eth.volume_ratio.unwrap() - 10.0).abs() < 1e-9);
+        assert!((eth.change_pct_24h - (47.61 - 50.0) / 50.0 * 100.0).abs() < 1e-9);
+        let sol = tracker.stats(&Symbol::new("SOLUSDT")).unwrap();
+        assert_eq!((sol.open_24h, sol.change_pct_24h), (20.0, 0.0));
+    }
+}
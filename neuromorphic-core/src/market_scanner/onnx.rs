@@ -0,0 +1,310 @@
+//! Strategies backed by trained ONNX models
+//!
+//! Each `[[scanner.models]]` entry becomes an `OnnxStrategy`. On every update
+//! it builds a feature vector from the symbol's recorded price bars and the
+//! analytics indicators, runs the model on it and turns the output into an
+//! opportunity. The model takes one `[1, n]` f32 input, the features in the
+//! order they are listed, and returns either
+//!
+//! - one score: positive to buy, negative to sell, its magnitude (up to one)
+//!   the confidence, or
+//! - three probabilities, of sell, hold and buy: the larger of sell and buy
+//!   is the side and the confidence.
+//!
+//! Inference runs on tract, built with the `onnx` feature. Without it the
+//! models are skipped with a warning.
+
+use super::analytics::MarketAnalytics;
+use super::history::{Granularity, PriceBar};
+use super::MarketData;
+use crate::exchanges::{Side, Symbol};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Model input, in listed order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelFeature {
+    Returns,            // Log returns of the last `lookback` bars, `lookback` values
+    Rsi,                // RSI(14) over 100
+    MacdHistogram,      // Relative to the close
+    BollingerPosition,  // -1 at the lower band, 1 at the upper
+    VolumeRatio,        // Last bar's volume over its 20 bar average
+}
+
+impl ModelFeature {
+    fn width(&self, lookback: usize) -> usize {
+        match self {
+            ModelFeature::Returns => lookback,
+            _ => 1,
+        }
+    }
+}
+
+/// One `[[scanner.models]]` entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OnnxStrategyConfig {
+    pub name: String, // Strategy name on its opportunities and in routes
+    pub model: PathBuf,
+    pub features: Vec<ModelFeature>,
+    #[serde(default = "default_lookback")]
+    pub lookback: usize, // Bars the features are built from
+    #[serde(default = "default_granularity")]
+    pub granularity: String, // Bar width, as for the history API
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f64,
+    #[serde(default = "default_stop_loss_pct")]
+    pub stop_loss_pct: f64,
+    #[serde(default = "default_take_profit_pct")]
+    pub take_profit_pct: f64,
+}
+
+fn default_lookback() -> usize {
+    30
+}
+
+fn default_granularity() -> String {
+    "1m".to_string()
+}
+
+fn default_min_confidence() -> f64 {
+    0.6
+}
+
+fn default_stop_loss_pct() -> f64 {
+    3.0
+}
+
+fn default_take_profit_pct() -> f64 {
+    6.0
+}
+
+impl OnnxStrategyConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("name must not be empty");
+        }
+        if self.features.is_empty() {
+            bail!("features must list at least one feature");
+        }
+        if self.lookback < 2 {
+            bail!("lookback must be at least 2");
+        }
+        self.granularity.parse::<Granularity>()?;
+        if !(self.min_confidence > 0.0 && self.min_confidence <= 1.0) {
+            bail!("min_confidence must be between 0 and 1");
+        }
+        if !(self.stop_loss_pct > 0.0 && self.stop_loss_pct < 100.0 && self.take_profit_pct > 0.0) {
+            bail!("stop_loss_pct must be between 0 and 100 and take_profit_pct positive");
+        }
+        Ok(())
+    }
+
+    /// Length of the model's input
+    pub fn input_width(&self) -> usize {
+        self.features.iter().map(|f| f.width(self.lookback)).sum()
+    }
+
+    /// Features of the last `lookback` bars, or None while there are fewer
+    /// bars or an indicator isn't defined yet
+    pub async fn feature_vector(&self, symbol: &Symbol, bars: &[PriceBar]) -> Option<Vec<f32>> {
+        if bars.len() < self.lookback + 1 {
+            return None;
+        }
+        let bars = &bars[bars.len() - self.lookback - 1..];
+        let history: Vec<MarketData> = bars
+            .iter()
+            .map(|bar| {
+                let mut data = MarketData::new(symbol.clone(), bar.close);
+                data.timestamp = bar.timestamp;
+                data.open = bar.open;
+                data.high = bar.high;
+                data.low = bar.low;
+                data.volume = bar.volume;
+                data
+            })
+            .collect();
+        let indicators = MarketAnalytics::new().calculate_technical_indicators(&symbol.0, &history).await.ok()?;
+        let close = bars.last()?.close;
+
+        let mut features = Vec::with_capacity(self.input_width());
+        for feature in &self.features {
+            match feature {
+                ModelFeature::Returns => features.extend(bars.windows(2).map(|w| (w[1].close / w[0].close).ln())),
+                ModelFeature::Rsi => features.push(indicators.rsi? / 100.0),
+                ModelFeature::MacdHistogram => features.push(indicators.macd.as_ref()?.histogram / close),
+                ModelFeature::BollingerPosition => {
+                    let bands = indicators.bollinger_bands.as_ref()?;
+                    let half_width = bands.upper_band - bands.middle_band;
+                    features.push(if half_width > 0.0 { (close - bands.middle_band) / half_width } else { 0.0 });
+                }
+                ModelFeature::VolumeRatio => features.push(indicators.volume_indicators.volume_ratio),
+            }
+        }
+        features.iter().all(|f| f.is_finite()).then(|| features.into_iter().map(|f| f as f32).collect())
+    }
+}
+
+/// Side and confidence from a model's output, see the module docs
+pub fn decode_output(output: &[f32]) -> Result<Option<(Side, f64)>> {
+    let (side, confidence) = match output {
+        [score] => (if *score >= 0.0 { Side::Buy } else { Side::Sell }, score.abs().min(1.0) as f64),
+        [sell, _hold, buy] if buy >= sell => (Side::Buy, *buy as f64),
+        [sell, _hold, _buy] => (Side::Sell, *sell as f64),
+        _ => bail!("Model returned {} values; expected a score or three probabilities", output.len()),
+    };
+    Ok((confidence.is_finite() && confidence > 0.0).then_some((side, confidence)))
+}
+
+#[cfg(feature = "onnx")]
+pub use model::OnnxStrategy;
+
+#[cfg(feature = "onnx")]
+mod model {
+    use super::*;
+    use crate::market_scanner::strategies::{regime_confidence, RiskLevel, TradingStrategy};
+    use crate::market_scanner::{MarketRegime, TradingOpportunity};
+    use anyhow::Context;
+    use async_trait::async_trait;
+    use crate::market_scanner::PriceHistory;
+    use chrono::Utc;
+    use std::sync::Arc;
+    use tract_onnx::prelude::*;
+
+    type Plan = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
+
+    /// Opportunities from an ONNX model's predictions
+    pub struct OnnxStrategy {
+        config: OnnxStrategyConfig,
+        granularity: Granularity,
+        plan: Plan,
+        history: Arc<PriceHistory>,
+        description: String,
+    }
+
+    impl OnnxStrategy {
+        /// Load and optimize the model, with its input fixed to the features' width
+        pub fn load(config: OnnxStrategyConfig, history: Arc<PriceHistory>) -> Result<Self> {
+            config.validate()?;
+            let plan = tract_onnx::onnx()
+                .model_for_path(&config.model)
+                .and_then(|model| model.with_input_fact(0, f32::fact([1, config.input_width()]).into()))
+                .and_then(|model| model.into_optimized())
+                .and_then(|model| model.into_runnable())
+                .with_context(|| format!("Failed to load ONNX model {}", config.model.display()))?;
+            Ok(Self {
+                granularity: config.granularity.parse()?,
+                description: format!("ONNX model {}", config.model.display()),
+                config,
+                plan,
+                history,
+            })
+        }
+
+        fn predict(&self, features: Vec<f32>) -> Result<Option<(Side, f64)>> {
+            let input = Tensor::from_shape(&[1, features.len()], &features)?;
+            let outputs = self.plan.run(tvec!(input.into()))?;
+            let output = outputs[0].to_array_view::<f32>()?;
+            decode_output(output.as_slice().context("Model output is not contiguous")?)
+        }
+    }
+
+    #[async_trait]
+    impl TradingStrategy for OnnxStrategy {
+        async fn analyze(&self, data: &MarketData, _history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
+            let hours = (self.config.lookback as u64 + 1) * self.granularity.millis() / 3_600_000 + 1;
+            let Some(bars) = self.history.bars(&data.symbol, self.granularity, hours) else {
+                return Ok(Vec::new());
+            };
+            let Some(features) = self.config.feature_vector(&data.symbol, &bars).await else {
+                return Ok(Vec::new());
+            };
+            let Some((side, confidence)) = self.predict(features)? else {
+                return Ok(Vec::new());
+            };
+            let confidence = regime_confidence(confidence.min(0.95), side, regime);
+            if confidence < (self.config.min_confidence * regime.threshold_multiplier()).min(0.95) {
+                return Ok(Vec::new());
+            }
+
+            let (stop, target) = (self.config.stop_loss_pct / 100.0, self.config.take_profit_pct / 100.0);
+            Ok(vec![TradingOpportunity {
+                symbol: data.symbol.clone(),
+                strategy: self.config.name.clone(),
+                confidence,
+                expected_move: self.config.take_profit_pct,
+                time_horizon: format!("{} bars of {}", self.config.lookback, self.config.granularity),
+                entry_price: data.price,
+                stop_loss: Some(data.price * if side == Side::Buy { 1.0 - stop } else { 1.0 + stop }),
+                take_profit: Some(data.price * if side == Side::Buy { 1.0 + target } else { 1.0 - target }),
+                position_size: (confidence * 0.1).min(0.05),
+                reasoning: format!("{} predicts {:?} with {:.0}% confidence", self.config.name, side, confidence * 100.0),
+                risk_score: 1.0 - confidence,
+                timestamp: Utc::now(),
+                exchange: data.exchange,
+            }])
+        }
+
+        fn get_name(&self) -> &str {
+            &self.config.name
+        }
+
+        fn get_description(&self) -> &str {
+            &self.description
+        }
+
+        fn get_risk_level(&self) -> RiskLevel {
+            RiskLevel::Moderate
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[tokio::test]
+    async fn test_features_and_model_output() {
+        let config: OnnxStrategyConfig = toml::from_str(
+            r#"
+            name = "Trend Model"
+            model = "models/trend.onnx"
+            features = ["returns", "rsi", "volume_ratio"]
+            lookback = 20
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.input_width(), 22);
+
+        let start = Utc.timestamp_opt(1_700_000_040, 0).unwrap();
+        let bars: Vec<PriceBar> = (0..25)
+            .map(|i| {
+                let close = 100.0 * 1.001f64.powi(i);
+                PriceBar { timestamp: start + Duration::minutes(i as i64), open: close, high: close, low: close, close, volume: 10.0 }
+            })
+            .collect();
+        let symbol = Symbol::new("BTCUSDT");
+        let features = config.feature_vector(&symbol, &bars).await.unwrap();
+        assert_eq!(features.len(), 22);
+        assert!((features[0] - 1.001f32.ln()).abs() < 1e-6);
+        assert_eq!((features[20], features[21]), (1.0, 1.0)); // Only gains; steady volume
+        assert!(config.feature_vector(&symbol, &bars[..20]).await.is_none());
+
+        let (side, confidence) = decode_output(&[-0.7]).unwrap().unwrap();
+        assert!(side == Side::Sell && (confidence - 0.7).abs() < 1e-6);
+        let (side, confidence) = decode_output(&[0.1, 0.2, 0.7]).unwrap().unwrap();
+        assert!(side == Side::Buy && (confidence - 0.7).abs() < 1e-6);
+        assert!(decode_output(&[0.5, 0.5]).is_err());
+
+        #[cfg(feature = "onnx")]
+        {
+            let history = std::sync::Arc::new(crate::market_scanner::PriceHistory::new(1));
+            let error = OnnxStrategy::load(config, history).err().unwrap();
+            assert!(format!("{:#}", error).starts_with("Failed to load ONNX model models/trend.onnx"));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Opportunity lifecycle
+//!
+//! Strategies emit the same opportunity on every update while their setup
+//! holds, so the store keeps one open opportunity per symbol and strategy and
+//! folds repeats into it. An open opportunity is Active until it is traded
+//! (Executed), its horizon passes untraded (Expired), or the price crosses its
+//! stop or the strategy turns the other way first (Invalidated).
+//!
+//! Each opportunity is judged a hit once the price moves its `expected_move`
+//! percent in the trade's direction before the stop or the horizon; the
+//! per-strategy hit rates count every opportunity, traded or not.
+
+use super::{MarketData, TradingOpportunity};
+use crate::exchanges::{Side, Symbol};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use utoipa::ToSchema;
+
+/// Horizon of opportunities whose `time_horizon` can't be parsed
+const DEFAULT_HORIZON: chrono::Duration = chrono::Duration::hours(1);
+/// Resolved opportunities kept for the API
+const MAX_RESOLVED: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OpportunityState {
+    Active,
+    Executed,
+    Expired,
+    Invalidated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrackedOpportunity {
+    pub id: u64,
+    pub opportunity: TradingOpportunity,
+    pub state: OpportunityState,
+    pub expires_at: DateTime<Utc>,
+    pub hit: Option<bool>,     // Whether the expected move materialized; None while open
+    pub best_move_pct: f64,    // Largest move in the trade's direction so far
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl TrackedOpportunity {
+    /// 1.0 for longs, -1.0 for shorts
+    fn direction(opportunity: &TradingOpportunity) -> f64 {
+        if opportunity.side() == Some(Side::Sell) { -1.0 } else { 1.0 }
+    }
+
+    fn evaluate(&mut self, price: f64) {
+        if self.hit.is_some() || self.opportunity.entry_price <= 0.0 {
+            return;
+        }
+        let direction = Self::direction(&self.opportunity);
+        let entry = self.opportunity.entry_price;
+        let move_pct = (price - entry) / entry * 100.0 * direction;
+        self.best_move_pct = self.best_move_pct.max(move_pct);
+
+        if move_pct >= self.opportunity.expected_move.abs() {
+            self.hit = Some(true);
+        } else if self.opportunity.stop_loss.is_some_and(|stop| (price - stop) * direction <= 0.0) {
+            self.hit = Some(false);
+            if self.state == OpportunityState::Active {
+                self.state = OpportunityState::Invalidated;
+            }
+        }
+    }
+}
+
+/// Outcomes of one strategy's opportunities
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct StrategyHitRate {
+    pub strategy: String,
+    pub tracked: u64,
+    pub open: u64,
+    pub executed: u64,
+    pub expired: u64,
+    pub invalidated: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64, // Hits over resolved opportunities
+}
+
+#[derive(Default)]
+struct StoreState {
+    next_id: u64,
+    open: HashMap<(Symbol, String), TrackedOpportunity>,
+    resolved: VecDeque<TrackedOpportunity>, // Oldest first
+    stats: BTreeMap<String, StrategyHitRate>,
+}
+
+impl StoreState {
+    fn resolve(&mut self, mut tracked: TrackedOpportunity, now: DateTime<Utc>) {
+        tracked.hit = Some(tracked.hit.unwrap_or(false));
+        tracked.resolved_at = Some(now);
+        if tracked.state == OpportunityState::Active {
+            tracked.state = OpportunityState::Expired;
+        }
+
+        let stats = self.stats.entry(tracked.opportunity.strategy.clone()).or_default();
+        stats.open -= 1;
+        match tracked.state {
+            OpportunityState::Expired => stats.expired += 1,
+            OpportunityState::Invalidated => stats.invalidated += 1,
+            _ => {}
+        }
+        if tracked.hit == Some(true) {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+
+        self.resolved.push_back(tracked);
+        while self.resolved.len() > MAX_RESOLVED {
+            self.resolved.pop_front();
+        }
+    }
+
+    /// Resolve open opportunities that are invalidated or past their horizon
+    fn sweep(&mut self, now: DateTime<Utc>) {
+        let done: Vec<(Symbol, String)> = self
+            .open
+            .iter()
+            .filter(|(_, t)| t.state == OpportunityState::Invalidated || t.expires_at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in done {
+            if let Some(tracked) = self.open.remove(&key) {
+                self.resolve(tracked, now);
+            }
+        }
+    }
+}
+
+/// Lifecycle and outcome of the scanner's opportunities
+#[derive(Default)]
+pub struct OpportunityStore {
+    state: Mutex<StoreState>,
+}
+
+impl OpportunityStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track an opportunity, returning its id. A repeat of an open
+    /// opportunity keeps the original; one in the opposite direction
+    /// invalidates it and is tracked instead.
+    pub fn track(&self, opportunity: &TradingOpportunity) -> u64 {
+        let mut state = self.state.lock();
+        let now = opportunity.timestamp;
+        state.sweep(now);
+
+        let key = (opportunity.symbol.clone(), opportunity.strategy.clone());
+        let direction = TrackedOpportunity::direction(opportunity);
+        if let Some(open) = state.open.get(&key) {
+            if TrackedOpportunity::direction(&open.opportunity) == direction {
+                return open.id;
+            }
+            let mut previous = state.open.remove(&key).expect("open opportunity");
+            if previous.state == OpportunityState::Active {
+                previous.state = OpportunityState::Invalidated;
+            }
+            state.resolve(previous, now);
+        }
+
+        state.next_id += 1;
+        let id = state.next_id;
+        let horizon = opportunity
+            .max_hold_duration()
+            .and_then(|d| chrono::Duration::from_std(d).ok())
+            .unwrap_or(DEFAULT_HORIZON);
+        let stats = state.stats.entry(opportunity.strategy.clone()).or_insert_with(|| StrategyHitRate {
+            strategy: opportunity.strategy.clone(),
+            ..Default::default()
+        });
+        stats.tracked += 1;
+        stats.open += 1;
+        state.open.insert(key, TrackedOpportunity {
+            id,
+            opportunity: opportunity.clone(),
+            state: OpportunityState::Active,
+            expires_at: now + horizon,
+            hit: None,
+            best_move_pct: 0.0,
+            resolved_at: None,
+        });
+        id
+    }
+
+    /// Mark the open opportunity of this symbol and strategy as traded
+    pub fn mark_executed(&self, opportunity: &TradingOpportunity) -> bool {
+        let mut state = self.state.lock();
+        let key = (opportunity.symbol.clone(), opportunity.strategy.clone());
+        let Some(tracked) = state.open.get_mut(&key) else {
+            return false;
+        };
+        if tracked.state != OpportunityState::Active {
+            return false;
+        }
+        tracked.state = OpportunityState::Executed;
+        state.stats.entry(opportunity.strategy.clone()).or_default().executed += 1;
+        true
+    }
+
+    /// Judge the symbol's open opportunities at this price and resolve those
+    /// past their horizon at the update's time
+    pub fn record(&self, data: &MarketData) {
+        let mut state = self.state.lock();
+        for (_, tracked) in state.open.iter_mut().filter(|((symbol, _), _)| *symbol == data.symbol) {
+            tracked.evaluate(data.price);
+        }
+        state.sweep(data.timestamp);
+    }
+
+    /// Open opportunities, newest first
+    pub fn open(&self) -> Vec<TrackedOpportunity> {
+        let mut open: Vec<TrackedOpportunity> = self.state.lock().open.values().cloned().collect();
+        open.sort_by_key(|t| std::cmp::Reverse(t.id));
+        open
+    }
+
+    /// Up to `limit` resolved opportunities, newest first
+    pub fn resolved(&self, limit: usize) -> Vec<TrackedOpportunity> {
+        self.state.lock().resolved.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Outcomes per strategy, by strategy name
+    pub fn strategy_stats(&self) -> Vec<StrategyHitRate> {
+        self.state
+            .lock()
+            .stats
+            .values()
+            .map(|stats| {
+                let resolved = stats.hits + stats.misses;
+                StrategyHitRate {
+                    hit_rate: if resolved > 0 { stats.hits as f64 / resolved as f64 } else { 0.0 },
+                    ..stats.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opportunity(symbol: &str, strategy: &str, entry: f64, long: bool, minute: i64) -> TradingOpportunity {
+        let side = if long { 1.0 } else { -1.0 };
+        TradingOpportunity {
+            symbol: Symbol::new(symbol),
+            strategy: strategy.to_string(),
+            confidence: 0.8,
+            expected_move: 2.0,
+            time_horizon: "1-2 hours".to_string(),
+            entry_price: entry,
+            stop_loss: Some(entry * (1.0 - 0.01 * side)),
+            take_profit: Some(entry * (1.0 + 0.04 * side)),
+            position_size: 0.02,
+            reasoning: String::new(),
+            risk_score: 0.2,
+            timestamp: DateTime::from_timestamp(minute * 60, 0).unwrap(),
+            exchange: None,
+        }
+    }
+
+    fn price(symbol: &str, price: f64, minute: i64) -> MarketData {
+        let mut data = MarketData::new(Symbol::new(symbol), price);
+        data.timestamp = DateTime::from_timestamp(minute * 60, 0).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_lifecycle_and_hit_rates() {
+        let store = OpportunityStore::new();
+
+        // Repeats fold into the open opportunity; BTC is traded and reaches
+        // its expected 2% move before the two hour horizon
+        let btc = store.track(&opportunity("BTCUSDT", "Momentum", 100.0, true, 0));
+        assert_eq!(store.track(&opportunity("BTCUSDT", "Momentum", 100.5, true, 1)), btc);
+        assert!(store.mark_executed(&opportunity("BTCUSDT", "Momentum", 100.0, true, 2)));
+        store.record(&price("BTCUSDT", 102.5, 30));
+
+        // ETH crosses its stop before moving; SOL flips short, invalidating
+        // the long, and the short then expires untraded
+        store.track(&opportunity("ETHUSDT", "Momentum", 50.0, true, 0));
+        store.record(&price("ETHUSDT", 49.0, 10));
+        store.track(&opportunity("SOLUSDT", "Gap", 20.0, true, 0));
+        let sol = store.track(&opportunity("SOLUSDT", "Gap", 20.0, false, 5));
+        store.record(&price("SOLUSDT", 19.9, 200));
+
+        assert!(store.open().is_empty());
+        let resolved = store.resolved(10);
+        let state = |symbol: &str, id: Option<u64>| {
+            resolved
+                .iter()
+                .find(|t| t.opportunity.symbol.as_str() == symbol && id.is_none_or(|id| t.id == id))
+                .map(|t| (t.state, t.hit))
+                .unwrap()
+        };
+        assert_eq!(state("BTCUSDT", None), (OpportunityState::Executed, Some(true)));
+        assert_eq!(state("ETHUSDT", None), (OpportunityState::Invalidated, Some(false)));
+        assert_eq!(state("SOLUSDT", Some(sol)), (OpportunityState::Expired, Some(false)));
+        assert_eq!(state("SOLUSDT", Some(sol - 1)), (OpportunityState::Invalidated, Some(false)));
+
+        let stats = store.strategy_stats();
+        let momentum = &stats[1];
+        assert_eq!(momentum.strategy, "Momentum");
+        assert_eq!((momentum.tracked, momentum.executed, momentum.invalidated), (2, 1, 1));
+        assert_eq!((momentum.hits, momentum.misses, momentum.hit_rate), (1, 1, 0.5));
+        let gap = &stats[0];
+        assert_eq!((gap.tracked, gap.expired, gap.invalidated, gap.open), (2, 1, 1, 0));
+    }
+}
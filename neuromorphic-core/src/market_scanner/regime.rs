@@ -0,0 +1,235 @@
+//! Market regime detection
+//!
+//! Builds a one-minute index from the benchmark symbols, or from everything
+//! scanned when no benchmark is configured: each minute the index moves by
+//! the average log return of its members over that minute. The trend is the
+//! gap between a fast and a slow EMA of the index level, in percent, and the
+//! volatility ratio compares a short EWMA of squared index returns with a long
+//! one, so it reads about 1.0 in an ordinary market whatever the assets.
+//!
+//! First match wins: a volatility ratio above `high_volatility_ratio` is
+//! HighVolatility, a trend beyond `strong_trend_pct` or `mild_trend_pct` is a
+//! strong or mild bull or bear, a ratio below `low_volatility_ratio` is
+//! LowVolatility and anything else Consolidation. The regime stays
+//! Consolidation until `warmup_minutes` index returns have been seen.
+
+use super::universe::universe_key;
+use super::{MarketData, MarketRegime};
+use crate::exchanges::Symbol;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+const MINUTE_MS: u64 = 60_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegimeConfig {
+    pub benchmarks: Vec<Symbol>, // Empty: equal-weighted index of every scanned symbol
+    pub fast_span_minutes: usize,
+    pub slow_span_minutes: usize,
+    pub volatility_span_minutes: usize,
+    pub baseline_span_minutes: usize,
+    pub strong_trend_pct: f64,
+    pub mild_trend_pct: f64,
+    pub high_volatility_ratio: f64,
+    pub low_volatility_ratio: f64,
+    pub warmup_minutes: u64,
+}
+
+impl Default for RegimeConfig {
+    fn default() -> Self {
+        Self {
+            benchmarks: Vec::new(),
+            fast_span_minutes: 15,
+            slow_span_minutes: 60,
+            volatility_span_minutes: 15,
+            baseline_span_minutes: 240,
+            strong_trend_pct: 1.0,
+            mild_trend_pct: 0.25,
+            high_volatility_ratio: 1.5,
+            low_volatility_ratio: 0.6,
+            warmup_minutes: 60,
+        }
+    }
+}
+
+impl RegimeConfig {
+    fn classify(&self, trend_pct: f64, volatility_ratio: f64) -> MarketRegime {
+        match (trend_pct, volatility_ratio) {
+            (_, vol) if vol > self.high_volatility_ratio => MarketRegime::HighVolatility,
+            (trend, _) if trend >= self.strong_trend_pct => MarketRegime::StrongBull,
+            (trend, _) if trend <= -self.strong_trend_pct => MarketRegime::StrongBear,
+            (trend, _) if trend >= self.mild_trend_pct => MarketRegime::MildBull,
+            (trend, _) if trend <= -self.mild_trend_pct => MarketRegime::MildBear,
+            (_, vol) if vol < self.low_volatility_ratio => MarketRegime::LowVolatility,
+            _ => MarketRegime::Consolidation,
+        }
+    }
+}
+
+/// Latest regime and the readings behind it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RegimeState {
+    pub regime: MarketRegime,
+    pub trend_pct: f64,        // Fast minus slow EMA of the index
+    pub volatility_ratio: f64, // Short over long volatility
+    pub samples: u64,          // Index returns seen
+    pub warmed_up: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    minute_ms: Option<u64>,            // Start of the minute being built
+    closes: HashMap<String, f64>,      // Member prices at the end of the previous minute
+    current: HashMap<String, f64>,     // Latest member prices in this minute
+    fast: f64,
+    slow: f64,
+    level: f64, // Log of the index, starting at zero
+    short_var: f64,
+    long_var: f64,
+    samples: u64,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+/// EWMA weight of a new sample; the first samples get a running mean so
+/// early readings don't lean on the zero start
+fn weight(span: usize, samples: u64) -> f64 {
+    (2.0 / (span as f64 + 1.0)).max(1.0 / samples as f64)
+}
+
+/// Classifies the market from a benchmark index of the scanned updates
+pub struct RegimeDetector {
+    config: RegimeConfig,
+    benchmarks: HashSet<String>,
+    state: Mutex<IndexState>,
+}
+
+impl RegimeDetector {
+    pub fn new(config: RegimeConfig) -> Self {
+        Self {
+            benchmarks: config.benchmarks.iter().map(universe_key).collect(),
+            config,
+            state: Mutex::new(IndexState::default()),
+        }
+    }
+
+    pub fn record(&self, data: &MarketData) {
+        if !data.price.is_finite() || data.price <= 0.0 {
+            return;
+        }
+        let key = universe_key(&data.symbol);
+        if !self.benchmarks.is_empty() && !self.benchmarks.contains(&key) {
+            return;
+        }
+
+        let time_ms = data.timestamp.timestamp_millis().max(0) as u64;
+        let minute_ms = time_ms - time_ms % MINUTE_MS;
+        let mut state = self.state.lock();
+        // Late updates count towards the minute being built
+        if state.minute_ms.is_some_and(|m| minute_ms > m) {
+            self.close_minute(&mut state);
+        }
+        state.minute_ms = Some(state.minute_ms.map_or(minute_ms, |m| m.max(minute_ms)));
+        state.current.insert(key, data.price);
+        state.updated_at = Some(data.timestamp);
+    }
+
+    fn close_minute(&self, state: &mut IndexState) {
+        let returns: Vec<f64> = state
+            .current
+            .iter()
+            .filter_map(|(key, price)| state.closes.get(key).map(|close| (price / close).ln()))
+            .collect();
+        let current = std::mem::take(&mut state.current);
+        state.closes.extend(current);
+        if returns.is_empty() {
+            return;
+        }
+
+        let r = returns.iter().sum::<f64>() / returns.len() as f64;
+        state.samples += 1;
+        state.level += r;
+        let config = &self.config;
+        let n = state.samples;
+        state.fast += (state.level - state.fast) * weight(config.fast_span_minutes, n);
+        state.slow += (state.level - state.slow) * weight(config.slow_span_minutes, n);
+        state.short_var += (r * r - state.short_var) * weight(config.volatility_span_minutes, n);
+        state.long_var += (r * r - state.long_var) * weight(config.baseline_span_minutes, n);
+    }
+
+    pub fn state(&self) -> RegimeState {
+        let state = self.state.lock();
+        let trend_pct = (state.fast - state.slow) * 100.0;
+        let volatility_ratio = if state.long_var > 0.0 { (state.short_var / state.long_var).sqrt() } else { 1.0 };
+        let warmed_up = state.samples >= self.config.warmup_minutes;
+
+        RegimeState {
+            regime: if warmed_up {
+                self.config.classify(trend_pct, volatility_ratio)
+            } else {
+                MarketRegime::Consolidation
+            },
+            trend_pct,
+            volatility_ratio,
+            samples: state.samples,
+            warmed_up,
+            updated_at: state.updated_at,
+        }
+    }
+
+    pub fn regime(&self) -> MarketRegime {
+        self.state().regime
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(symbol: &str, price: f64, minute: u64) -> MarketData {
+        let mut data = MarketData::new(Symbol::new(symbol), price);
+        data.timestamp = DateTime::from_timestamp_millis((minute * MINUTE_MS) as i64).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_regime_follows_benchmark_trend_and_volatility() {
+        let detector = RegimeDetector::new(RegimeConfig {
+            benchmarks: vec![Symbol::new("BTC-USDT")],
+            ..Default::default()
+        });
+
+        // Three quiet hours of small alternating moves, then a steady climb;
+        // the crashing non-benchmark symbol is ignored
+        let mut price = 100.0;
+        for minute in 0..180 {
+            price *= if minute % 2 == 0 { 1.001 } else { 1.0 / 1.001 };
+            detector.record(&update("BTCUSDT", price, minute));
+            detector.record(&update("DOGEUSDT", 1.0 / (minute + 1) as f64, minute));
+        }
+        let quiet = detector.state();
+        assert!(quiet.warmed_up);
+        assert_eq!(quiet.regime, MarketRegime::Consolidation);
+
+        for minute in 180..300 {
+            price *= 1.001;
+            detector.record(&update("BTCUSDT", price, minute));
+        }
+        let climbing = detector.state();
+        assert!(climbing.trend_pct > 1.0);
+        assert_eq!(climbing.regime, MarketRegime::StrongBull);
+
+        // Large swings with no direction
+        for minute in 300..330 {
+            price *= if minute % 2 == 0 { 1.02 } else { 1.0 / 1.02 };
+            detector.record(&update("BTCUSDT", price, minute));
+        }
+        let state = detector.state();
+        assert!(state.volatility_ratio > 1.5);
+        assert_eq!(state.regime, MarketRegime::HighVolatility);
+    }
+}
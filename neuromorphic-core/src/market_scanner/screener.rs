@@ -1,10 +1,12 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use super::MarketData;
 use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ScreeningCriteria {
     pub min_price: Option<f64>,
     pub max_price: Option<f64>,
@@ -66,9 +68,14 @@ pub enum BollingerPosition {
     BandExpansion,
 }
 
-#[derive(Debug, Clone)]
+/// Custom filter applied after the screening criteria, e.g. a script
+pub trait ScreeningPredicate: Send + Sync {
+    fn passes(&self, data: &MarketData) -> bool;
+}
+
 pub struct StockScreener {
-    criteria: ScreeningCriteria,
+    criteria: parking_lot::RwLock<ScreeningCriteria>, // Replaced whole on reload
+    predicate: Option<Arc<dyn ScreeningPredicate>>,
     market_history: HashMap<String, Vec<MarketData>>,
 }
 
@@ -125,21 +132,37 @@ impl Default for ScreeningCriteria {
 impl StockScreener {
     pub fn new() -> Self {
         Self {
-            criteria: ScreeningCriteria::default(),
+            criteria: parking_lot::RwLock::new(ScreeningCriteria::default()),
+            predicate: None,
             market_history: HashMap::new(),
         }
     }
 
     pub fn with_criteria(mut self, criteria: ScreeningCriteria) -> Self {
-        self.criteria = criteria;
+        *self.criteria.get_mut() = criteria;
+        self
+    }
+
+    /// Only pass symbols `predicate` accepts as well
+    pub fn with_predicate(mut self, predicate: Arc<dyn ScreeningPredicate>) -> Self {
+        self.predicate = Some(predicate);
         self
     }
 
+    pub fn criteria(&self) -> ScreeningCriteria {
+        self.criteria.read().clone()
+    }
+
+    /// Screen with `criteria` from the next pass on
+    pub fn set_criteria(&self, criteria: ScreeningCriteria) {
+        *self.criteria.write() = criteria;
+    }
+
     pub async fn screen_symbols(&self, market_data: Vec<MarketData>) -> Result<Vec<MarketData>> {
         let mut filtered_symbols = Vec::new();
 
         for data in market_data {
-            if self.passes_basic_filters(&data).await? {
+            if self.passes_basic_filters(&data).await? && self.predicate.as_ref().is_none_or(|p| p.passes(&data)) {
                 if let Ok(score) = self.calculate_screening_score(&data).await {
                     if score > 0.6 {
                         filtered_symbols.push(data);
@@ -202,31 +225,32 @@ impl StockScreener {
     }
 
     async fn passes_basic_filters(&self, data: &MarketData) -> Result<bool> {
-        if let Some(min_price) = self.criteria.min_price {
+        let criteria = self.criteria.read();
+        if let Some(min_price) = criteria.min_price {
             if data.price < min_price {
                 return Ok(false);
             }
         }
 
-        if let Some(max_price) = self.criteria.max_price {
+        if let Some(max_price) = criteria.max_price {
             if data.price > max_price {
                 return Ok(false);
             }
         }
 
-        if let Some(min_volume) = self.criteria.min_volume {
+        if let Some(min_volume) = criteria.min_volume {
             if data.volume < min_volume {
                 return Ok(false);
             }
         }
 
-        if let Some(min_change) = self.criteria.min_change_percent {
+        if let Some(min_change) = criteria.min_change_percent {
             if data.change_24h.abs() < min_change {
                 return Ok(false);
             }
         }
 
-        if let Some(max_change) = self.criteria.max_change_percent {
+        if let Some(max_change) = criteria.max_change_percent {
             if data.change_24h.abs() > max_change {
                 return Ok(false);
             }
@@ -264,7 +288,8 @@ impl StockScreener {
     }
 
     async fn calculate_volume_score(&self, data: &MarketData) -> Result<f64> {
-        if let Some(min_volume) = self.criteria.min_volume {
+        let min_volume = self.criteria.read().min_volume;
+        if let Some(min_volume) = min_volume {
             let volume_ratio = data.volume / min_volume;
             let score = match volume_ratio {
                 x if x > 5.0 => 1.0,
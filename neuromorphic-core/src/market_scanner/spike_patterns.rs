@@ -0,0 +1,217 @@
+//! Spike-pattern inference for the neuromorphic strategy
+//!
+//! The spike bridge encodes market data into spikes; a
+//! `SpikePatternClassifier` reads a symbol's recent spikes and says whether
+//! they form a pattern, how strong it is and which way it points. Models plug
+//! in by implementing the trait, e.g. an ARES network run over the same
+//! encoding. `PopulationPatternClassifier` is the built-in one: it decodes the
+//! return population of the bridge's `PopulationEncoder`.
+//!
+//! `SpikePatterns` keeps each symbol's spikes over a rolling window, classifies
+//! them as batches arrive and holds the latest pattern for the strategy:
+//!
+//! ```ignore
+//! let bridge = Arc::new(MarketDataSpikeBridge::new(SpikeBridgeConfig::default()));
+//! let patterns = Arc::new(SpikePatterns::new(PopulationPatternClassifier::new(10_000)));
+//! patterns.clone().spawn(bridge.subscribe());
+//! bridge.spawn(feed.subscribe());
+//! let scanner = MarketScannerService::new(config).with_spike_patterns(patterns);
+//! ```
+
+use crate::exchanges::Symbol;
+use crate::market_data::{Spike, SpikeBatch};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// What a classifier found in a symbol's spikes
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpikePattern {
+    pub pattern_strength: f64, // 0..=1
+    pub direction: f64,        // Positive for up, negative for down
+    pub spike_count: usize,    // Spikes in the window it was found in
+    pub time_ms: u64,          // Of the newest of those spikes
+}
+
+/// Model inference over spike trains
+pub trait SpikePatternClassifier: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Pattern in `spikes`, oldest first, or None when there is none
+    fn classify(&self, symbol: &Symbol, spikes: &[Spike]) -> Option<SpikePattern>;
+}
+
+/// Decodes the return population (the first quarter of the neurons) of the
+/// `PopulationEncoder`: the strength-weighted mean return sets the direction,
+/// and the strength is that mean, up to one scale unit, times the share of
+/// spikes that agree with it
+pub struct PopulationPatternClassifier {
+    population_size: u32,
+}
+
+impl PopulationPatternClassifier {
+    /// For an encoder with `neuron_count` neurons
+    pub fn new(neuron_count: usize) -> Self {
+        Self { population_size: (neuron_count as u32 / 4).max(2) }
+    }
+}
+
+impl SpikePatternClassifier for PopulationPatternClassifier {
+    fn name(&self) -> &str {
+        "population"
+    }
+
+    fn classify(&self, _symbol: &Symbol, spikes: &[Spike]) -> Option<SpikePattern> {
+        let last = (self.population_size - 1) as f64;
+        let returns: Vec<(f64, f64)> = spikes
+            .iter()
+            .filter(|s| s.neuron_id < self.population_size)
+            .map(|s| (s.neuron_id as f64 / last * 6.0 - 3.0, s.strength as f64))
+            .collect();
+        let total: f64 = returns.iter().map(|(_, strength)| strength).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mean = returns.iter().map(|(value, strength)| value * strength).sum::<f64>() / total;
+        let agreeing: f64 = returns.iter().filter(|(value, _)| value * mean > 0.0).map(|(_, strength)| strength).sum();
+        Some(SpikePattern {
+            pattern_strength: mean.abs().min(1.0) * agreeing / total,
+            direction: mean,
+            spike_count: spikes.len(),
+            time_ms: spikes.last()?.timestamp_us / 1000,
+        })
+    }
+}
+
+/// Rolling spikes and the latest pattern per symbol
+pub struct SpikePatterns {
+    classifier: Box<dyn SpikePatternClassifier>,
+    window: Duration,
+    spikes: DashMap<Symbol, VecDeque<Spike>>,
+    latest: DashMap<Symbol, SpikePattern>,
+}
+
+impl SpikePatterns {
+    pub fn new(classifier: impl SpikePatternClassifier + 'static) -> Self {
+        Self {
+            classifier: Box::new(classifier),
+            window: Duration::from_secs(60),
+            spikes: DashMap::new(),
+            latest: DashMap::new(),
+        }
+    }
+
+    /// Spikes older than `window` before a symbol's newest one are dropped,
+    /// and a pattern is stale once it is `window` old
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn classifier_name(&self) -> &str {
+        self.classifier.name()
+    }
+
+    /// Add a batch and classify its symbol's window again
+    pub fn record(&self, batch: &SpikeBatch) {
+        let Some(newest) = batch.spikes.last() else { return };
+        let start_us = newest.timestamp_us.saturating_sub(self.window.as_micros() as u64);
+        let mut spikes = self.spikes.entry(batch.symbol.clone()).or_default();
+        spikes.extend(batch.spikes.iter().cloned());
+        while spikes.front().is_some_and(|s| s.timestamp_us < start_us) {
+            spikes.pop_front();
+        }
+        if let Some(pattern) = self.classifier.classify(&batch.symbol, spikes.make_contiguous()) {
+            self.latest.insert(batch.symbol.clone(), pattern);
+        } else {
+            self.latest.remove(&batch.symbol);
+        }
+    }
+
+    /// Latest pattern of `symbol`, unless it is older than the window at `now_ms`
+    pub fn pattern(&self, symbol: &Symbol, now_ms: u64) -> Option<SpikePattern> {
+        let pattern = self.latest.get(symbol)?.clone();
+        (now_ms.saturating_sub(pattern.time_ms) < self.window.as_millis() as u64).then_some(pattern)
+    }
+
+    /// Classify the batches of a spike bridge until it closes
+    pub fn spawn(self: Arc<Self>, mut batches: broadcast::Receiver<SpikeBatch>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match batches.recv().await {
+                    Ok(batch) => self.record(&batch),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Spike classifier fell behind the spike bridge");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side, UniversalMarketData, UniversalTrade};
+    use crate::market_data::{MarketDataSpikeBridge, SpikeBridgeConfig, UnifiedMarketEvent};
+    use crate::market_scanner::strategies::{NeuromorphicMomentumStrategy, TradingStrategy};
+    use crate::market_scanner::{MarketData, MarketRegime};
+    use chrono::DateTime;
+
+    fn trade(symbol: &Symbol, price: f64, time: u64) -> UnifiedMarketEvent {
+        let data = UniversalMarketData::Trade(UniversalTrade {
+            exchange: Exchange::Binance,
+            symbol: symbol.clone(),
+            price,
+            quantity: 1.0,
+            side: Side::Buy,
+            timestamp_exchange: time,
+            timestamp_local: time,
+            trade_id: time.to_string(),
+        });
+        UnifiedMarketEvent::new(Exchange::Binance, data, time)
+    }
+
+    #[tokio::test]
+    async fn test_strategy_trades_on_classified_spike_patterns() {
+        let bridge = MarketDataSpikeBridge::new(SpikeBridgeConfig {
+            neuron_count: 400,
+            enable_adaptive_encoding: false,
+            ..Default::default()
+        });
+        let mut batches = bridge.subscribe();
+        let patterns = Arc::new(SpikePatterns::new(PopulationPatternClassifier::new(400)));
+        let (rising, quiet) = (Symbol::new("SOL-USD"), Symbol::new("ADA-USD"));
+
+        // Steady 20bps upticks: two scale units up on every tick
+        let mut price = 100.0;
+        for n in 0..30u64 {
+            price *= 1.002;
+            bridge.process_event(&trade(&rising, price, 1_000_000 + n * 100));
+        }
+        bridge.flush_all();
+        while let Ok(batch) = batches.try_recv() {
+            patterns.record(&batch);
+        }
+        let pattern = patterns.pattern(&rising, 1_003_000).unwrap();
+        assert!(pattern.direction > 1.5 && pattern.pattern_strength > 0.99 && pattern.spike_count > 100);
+        assert!(patterns.pattern(&rising, 1_003_000 + 60_000).is_none());
+
+        let strategy = NeuromorphicMomentumStrategy::new().with_spike_patterns(patterns);
+        let mut data = MarketData::new(rising, price);
+        data.timestamp = DateTime::from_timestamp_millis(1_003_000).unwrap();
+        let opportunities = strategy.analyze(&data, &[], MarketRegime::Consolidation).await.unwrap();
+        assert_eq!(opportunities.len(), 1);
+        assert!(opportunities[0].reasoning.starts_with("Spike pattern strength 1.00"));
+        assert_eq!(opportunities[0].take_profit, Some(price * 1.12));
+
+        // No spikes, no signal: the price heuristics no longer stand in
+        let data = MarketData::new(quiet, 1.0);
+        assert!(strategy.analyze(&data, &[], MarketRegime::Consolidation).await.unwrap().is_empty());
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use super::{MarketData, TradingOpportunity};
+use std::sync::Arc;
+use super::{MarketData, MarketRegime, TradingOpportunity};
+use super::spike_patterns::SpikePatterns;
 use crate::exchanges::{Symbol, Side};
 use chrono::Utc;
 use async_trait::async_trait;
@@ -14,12 +16,22 @@ pub struct StrategyEngine {
 
 #[async_trait]
 pub trait TradingStrategy: Send + Sync {
-    async fn analyze(&self, data: &MarketData, history: &[MarketData]) -> Result<Vec<TradingOpportunity>>;
+    /// Opportunities in `data`; strategies scale their thresholds and
+    /// confidence to the current market regime
+    async fn analyze(&self, data: &MarketData, history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>>;
     fn get_name(&self) -> &str;
     fn get_description(&self) -> &str;
     fn get_risk_level(&self) -> RiskLevel;
 }
 
+/// Confidence of a trade given the regime: going against a strong trend
+/// costs 30%, against a mild one 15%
+pub(crate) fn regime_confidence(confidence: f64, side: Side, regime: MarketRegime) -> f64 {
+    let side_sign = if side == Side::Buy { 1.0 } else { -1.0 };
+    let against = (-regime.direction() * side_sign).max(0.0);
+    confidence * (1.0 - 0.3 * against)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RiskLevel {
     Conservative,
@@ -68,6 +80,8 @@ pub struct NeuromorphicMomentumStrategy {
     neural_confidence_threshold: f64,
     pattern_strength_threshold: f64,
     spike_density_threshold: f64,
+    full_density_spikes: usize, // Spikes in the window that count as full density
+    spike_patterns: Option<Arc<SpikePatterns>>, // Price heuristics stand in when unset
 }
 
 impl StrategyEngine {
@@ -88,7 +102,7 @@ impl StrategyEngine {
         }
     }
 
-    pub async fn analyze_opportunity(&self, data: &MarketData) -> Result<Vec<TradingOpportunity>> {
+    pub async fn analyze_opportunity(&self, data: &MarketData, regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         self.update_history(data).await;
         
         let history = self.market_history
@@ -99,7 +113,7 @@ impl StrategyEngine {
         let mut all_opportunities = Vec::new();
         
         for strategy in &self.strategies {
-            if let Ok(opportunities) = strategy.analyze(data, history).await {
+            if let Ok(opportunities) = strategy.analyze(data, history, regime).await {
                 all_opportunities.extend(opportunities);
             }
         }
@@ -109,6 +123,20 @@ impl StrategyEngine {
         Ok(all_opportunities)
     }
 
+    /// Run `strategy` along with the built-in ones
+    pub fn with_strategy(mut self, strategy: Box<dyn TradingStrategy>) -> Self {
+        self.strategies.push(strategy);
+        self
+    }
+
+    /// Drive the neuromorphic strategy from classified spike patterns
+    pub fn with_spike_patterns(mut self, patterns: Arc<SpikePatterns>) -> Self {
+        let strategy = NeuromorphicMomentumStrategy::new().with_spike_patterns(patterns);
+        self.strategies.retain(|s| s.get_name() != strategy.get_name());
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
     async fn update_history(&self, data: &MarketData) {
         // For now, just store the latest data point
         // In a full implementation, this would maintain proper history
@@ -127,7 +155,7 @@ impl MomentumBreakoutStrategy {
 
 #[async_trait]
 impl TradingStrategy for MomentumBreakoutStrategy {
-    async fn analyze(&self, data: &MarketData, history: &[MarketData]) -> Result<Vec<TradingOpportunity>> {
+    async fn analyze(&self, data: &MarketData, history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         let mut opportunities = Vec::new();
 
         if history.len() < self.consolidation_periods {
@@ -141,13 +169,17 @@ impl TradingStrategy for MomentumBreakoutStrategy {
         let volume_spike = data.volume / avg_volume;
         let price_change = data.change_24h.abs();
 
-        if volume_spike > self.min_volume_spike && price_change > self.min_price_change {
+        let min_volume_spike = self.min_volume_spike * regime.threshold_multiplier();
+        let min_price_change = self.min_price_change * regime.threshold_multiplier();
+
+        if volume_spike > min_volume_spike && price_change > min_price_change {
             let is_consolidating = self.is_consolidating(history);
             
             if is_consolidating {
                 let side = if data.change_24h > 0.0 { Side::Buy } else { Side::Sell };
-                let confidence = ((volume_spike - self.min_volume_spike) * 0.2 + 
-                                (price_change - self.min_price_change) * 0.1).min(0.95);
+                let confidence = ((volume_spike - min_volume_spike) * 0.2 + 
+                                (price_change - min_price_change) * 0.1).min(0.95);
+                let confidence = regime_confidence(confidence, side, regime);
 
                 opportunities.push(TradingOpportunity {
                     symbol: data.symbol.clone(),
@@ -165,6 +197,7 @@ impl TradingStrategy for MomentumBreakoutStrategy {
                     ),
                     risk_score: 1.0 - confidence,
                     timestamp: Utc::now(),
+                    exchange: data.exchange,
                 });
             }
         }
@@ -220,7 +253,7 @@ impl VolumeSpikeMomentumStrategy {
 
 #[async_trait]
 impl TradingStrategy for VolumeSpikeMomentumStrategy {
-    async fn analyze(&self, data: &MarketData, history: &[MarketData]) -> Result<Vec<TradingOpportunity>> {
+    async fn analyze(&self, data: &MarketData, history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         let mut opportunities = Vec::new();
 
         if history.is_empty() {
@@ -234,12 +267,13 @@ impl TradingStrategy for VolumeSpikeMomentumStrategy {
         let volume_ratio = data.volume / avg_volume;
         let price_change = data.change_24h.abs();
 
-        if volume_ratio > self.volume_threshold && price_change > self.price_threshold {
+        let multiplier = regime.threshold_multiplier();
+        if volume_ratio > self.volume_threshold * multiplier && price_change > self.price_threshold * multiplier {
             let momentum_confirmed = self.confirm_momentum(data, history);
             
             if momentum_confirmed {
                 let side = if data.change_24h > 0.0 { Side::Buy } else { Side::Sell };
-                let confidence = (volume_ratio * 0.15 + price_change * 0.1).min(0.9);
+                let confidence = regime_confidence((volume_ratio * 0.15 + price_change * 0.1).min(0.9), side, regime);
 
                 opportunities.push(TradingOpportunity {
                     symbol: data.symbol.clone(),
@@ -257,6 +291,7 @@ impl TradingStrategy for VolumeSpikeMomentumStrategy {
                     ),
                     risk_score: 1.0 - confidence + 0.1,
                     timestamp: Utc::now(),
+                    exchange: data.exchange,
                 });
             }
         }
@@ -300,20 +335,36 @@ impl NeuromorphicMomentumStrategy {
             neural_confidence_threshold: 0.75,
             pattern_strength_threshold: 0.8,
             spike_density_threshold: 0.7,
+            full_density_spikes: 100,
+            spike_patterns: None,
         }
     }
+
+    /// Take pattern strength, direction and spike count from a spike-pattern
+    /// classifier instead of price heuristics
+    pub fn with_spike_patterns(mut self, patterns: Arc<SpikePatterns>) -> Self {
+        self.spike_patterns = Some(patterns);
+        self
+    }
 }
 
 #[async_trait]
 impl TradingStrategy for NeuromorphicMomentumStrategy {
-    async fn analyze(&self, data: &MarketData, history: &[MarketData]) -> Result<Vec<TradingOpportunity>> {
+    async fn analyze(&self, data: &MarketData, history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         let mut opportunities = Vec::new();
 
-        let neural_signal = self.calculate_neuromorphic_signal(data, history).await?;
+        let neural_signal = match &self.spike_patterns {
+            Some(patterns) => self.spike_pattern_signal(data, patterns),
+            None => Some(self.calculate_neuromorphic_signal(data, history).await?),
+        };
+        let Some(mut neural_signal) = neural_signal else {
+            return Ok(opportunities);
+        };
+        let side = if neural_signal.direction > 0.0 { Side::Buy } else { Side::Sell };
+        neural_signal.confidence = regime_confidence(neural_signal.confidence, side, regime);
+        let threshold = (self.neural_confidence_threshold * regime.threshold_multiplier()).min(0.95);
         
-        if neural_signal.confidence > self.neural_confidence_threshold {
-            let side = if neural_signal.direction > 0.0 { Side::Buy } else { Side::Sell };
-            
+        if neural_signal.confidence > threshold {
             opportunities.push(TradingOpportunity {
                 symbol: data.symbol.clone(),
                 strategy: "Neuromorphic AI Momentum".to_string(),
@@ -324,12 +375,19 @@ impl TradingStrategy for NeuromorphicMomentumStrategy {
                 stop_loss: Some(data.price * if side == Side::Buy { 0.96 } else { 1.04 }),
                 take_profit: Some(data.price * if side == Side::Buy { 1.12 } else { 0.88 }),
                 position_size: (neural_signal.confidence * 0.12).min(0.06),
-                reasoning: format!(
-                    "Neural pattern recognition: {:.0}% confidence, {:.1}% expected move",
-                    neural_signal.confidence * 100.0, neural_signal.expected_move
-                ),
+                reasoning: match neural_signal.spike_count {
+                    Some(spikes) => format!(
+                        "Spike pattern strength {:.2} over {} spikes: {:.0}% confidence, {:.1}% expected move",
+                        neural_signal.pattern_strength, spikes, neural_signal.confidence * 100.0, neural_signal.expected_move
+                    ),
+                    None => format!(
+                        "Neural pattern recognition: {:.0}% confidence, {:.1}% expected move",
+                        neural_signal.confidence * 100.0, neural_signal.expected_move
+                    ),
+                },
                 risk_score: 1.0 - neural_signal.confidence,
                 timestamp: Utc::now(),
+                exchange: data.exchange,
             });
         }
 
@@ -355,9 +413,28 @@ struct NeuralSignal {
     direction: f64,
     expected_move: f64,
     pattern_strength: f64,
+    spike_count: Option<usize>, // Set when classified from spikes
 }
 
 impl NeuromorphicMomentumStrategy {
+    /// Signal from the symbol's latest spike pattern; None without a fresh
+    /// pattern that is strong and dense enough
+    fn spike_pattern_signal(&self, data: &MarketData, patterns: &SpikePatterns) -> Option<NeuralSignal> {
+        let pattern = patterns.pattern(&data.symbol, data.timestamp.timestamp_millis().max(0) as u64)?;
+        let spike_density = (pattern.spike_count as f64 / self.full_density_spikes as f64).min(1.0);
+        if pattern.pattern_strength < self.pattern_strength_threshold || spike_density < self.spike_density_threshold {
+            return None;
+        }
+        let confidence = (pattern.pattern_strength * 0.7 + spike_density * 0.3).min(0.95);
+        Some(NeuralSignal {
+            confidence,
+            direction: pattern.direction,
+            expected_move: data.change_24h.abs() * (1.0 + confidence),
+            pattern_strength: pattern.pattern_strength,
+            spike_count: Some(pattern.spike_count),
+        })
+    }
+
     async fn calculate_neuromorphic_signal(&self, data: &MarketData, history: &[MarketData]) -> Result<NeuralSignal> {
         let pattern_strength = self.analyze_price_patterns(data, history);
         let volume_pattern = self.analyze_volume_patterns(data, history);
@@ -380,6 +457,7 @@ impl NeuromorphicMomentumStrategy {
             direction,
             expected_move,
             pattern_strength,
+            spike_count: None,
         })
     }
 
@@ -472,14 +550,14 @@ impl GapAndGoStrategy {
 
 #[async_trait]
 impl TradingStrategy for GapAndGoStrategy {
-    async fn analyze(&self, data: &MarketData, _history: &[MarketData]) -> Result<Vec<TradingOpportunity>> {
+    async fn analyze(&self, data: &MarketData, _history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         let mut opportunities = Vec::new();
         
         let gap_percent = ((data.open - data.price) / data.price * 100.0).abs();
         
-        if gap_percent > self.min_gap_percent {
+        if gap_percent > self.min_gap_percent * regime.threshold_multiplier() {
             let side = if data.open > data.price { Side::Buy } else { Side::Sell };
-            let confidence = (gap_percent / 10.0).min(0.85);
+            let confidence = regime_confidence((gap_percent / 10.0).min(0.85), side, regime);
             
             opportunities.push(TradingOpportunity {
                 symbol: data.symbol.clone(),
@@ -494,6 +572,7 @@ impl TradingStrategy for GapAndGoStrategy {
                 reasoning: format!("Gap {:.1}% with continuation potential", gap_percent),
                 risk_score: 1.0 - confidence + 0.2,
                 timestamp: Utc::now(),
+                exchange: data.exchange,
             });
         }
         
@@ -525,7 +604,7 @@ impl RelativeStrengthStrategy {
 
 #[async_trait]
 impl TradingStrategy for RelativeStrengthStrategy {
-    async fn analyze(&self, data: &MarketData, history: &[MarketData]) -> Result<Vec<TradingOpportunity>> {
+    async fn analyze(&self, data: &MarketData, history: &[MarketData], _regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         let opportunities = Vec::new();
         
         Ok(opportunities)
@@ -556,7 +635,7 @@ impl VolatilityBreakoutStrategy {
 
 #[async_trait]
 impl TradingStrategy for VolatilityBreakoutStrategy {
-    async fn analyze(&self, data: &MarketData, history: &[MarketData]) -> Result<Vec<TradingOpportunity>> {
+    async fn analyze(&self, data: &MarketData, history: &[MarketData], _regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
         let opportunities = Vec::new();
         
         Ok(opportunities)
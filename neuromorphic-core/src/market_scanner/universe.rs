@@ -0,0 +1,354 @@
+//! Tradable symbol universe for the scanner
+//!
+//! The universe is the set of symbols the scanner analyses. It combines a
+//! watchlist, kept in memory or in a file with one symbol per line, with
+//! symbols discovered from exchange info. Discovered symbols must trade
+//! against one of the configured quote assets, sit inside the scanner's price
+//! thresholds and turn over at least `min_quote_volume` a day; the most liquid
+//! fill whatever room the watchlist leaves under `max_symbols`.
+//!
+//! Discovery reruns every `refresh_interval_secs`, re-reading the watchlist
+//! file so edits are picked up. An empty universe restricts nothing, so
+//! without discovery or a watchlist the scanner sees every symbol as before.
+
+use super::ScannerConfig;
+use crate::exchanges::{Exchange, ExchangeConnector, Symbol, SymbolStatus};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UniverseConfig {
+    /// Exchanges to discover symbols from; empty uses only the watchlist
+    pub exchanges: Vec<Exchange>,
+    /// Quote assets discovered symbols must trade against; empty allows any
+    pub quote_assets: Vec<String>,
+    pub min_quote_volume: f64, // 24h turnover in the quote asset
+    pub refresh_interval_secs: u64,
+    pub watchlist_file: Option<PathBuf>,
+}
+
+impl Default for UniverseConfig {
+    fn default() -> Self {
+        Self {
+            exchanges: Vec::new(),
+            quote_assets: vec!["USDT".to_string()],
+            min_quote_volume: 1_000_000.0,
+            refresh_interval_secs: 3600,
+            watchlist_file: None,
+        }
+    }
+}
+
+/// Tradable symbol offered by a source, with its last price and 24h turnover
+#[derive(Debug, Clone)]
+pub struct UniverseCandidate {
+    pub symbol: Symbol,
+    pub price: f64,
+    pub quote_volume: f64,
+}
+
+/// Where discovered symbols come from
+#[async_trait]
+pub trait UniverseSource: Send + Sync {
+    /// Symbols currently trading against one of `quote_assets` (any if empty)
+    async fn candidates(&self, quote_assets: &[String]) -> Result<Vec<UniverseCandidate>>;
+}
+
+#[async_trait]
+impl<C: ExchangeConnector> UniverseSource for C {
+    async fn candidates(&self, quote_assets: &[String]) -> Result<Vec<UniverseCandidate>> {
+        let info = self.get_exchange_info().await?;
+        let mut candidates = Vec::new();
+        for symbol in info.symbols {
+            let quoted = quote_assets.is_empty() || quote_assets.iter().any(|q| q.eq_ignore_ascii_case(&symbol.quote_asset));
+            if symbol.status != SymbolStatus::Trading || !symbol.is_spot_trading_allowed || !quoted {
+                continue;
+            }
+            match self.get_ticker(&symbol.symbol).await {
+                Ok(ticker) => candidates.push(UniverseCandidate {
+                    symbol: symbol.symbol,
+                    price: ticker.price,
+                    quote_volume: ticker.volume_quote_24h,
+                }),
+                Err(e) => warn!(symbol = %symbol.symbol, error = %e, "Skipping symbol without a ticker"),
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+/// Current universe, as served by the API
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UniverseStatus {
+    pub symbols: Vec<Symbol>,
+    pub watchlist: Vec<Symbol>,
+    pub discovered: Vec<Symbol>,
+    pub last_refresh: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Same symbol whatever the separator or case, e.g. "btc-usdt" and "BTCUSDT"
+pub(crate) fn universe_key(symbol: &Symbol) -> String {
+    symbol.as_str().chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_uppercase()).collect()
+}
+
+#[derive(Default)]
+struct UniverseState {
+    watchlist: BTreeMap<String, Symbol>, // Keyed by `universe_key`
+    discovered: Vec<Symbol>,             // Most liquid first
+    members: HashSet<String>,
+    last_refresh: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// Watchlist plus discovered symbols, refreshed periodically
+pub struct UniverseManager {
+    config: UniverseConfig,
+    min_price: f64,
+    max_price: f64,
+    max_symbols: usize,
+    sources: parking_lot::RwLock<Vec<Arc<dyn UniverseSource>>>,
+    state: parking_lot::RwLock<UniverseState>,
+}
+
+impl UniverseManager {
+    pub fn new(config: &ScannerConfig) -> Self {
+        Self {
+            config: config.universe.clone(),
+            min_price: config.min_price_threshold,
+            max_price: config.max_price_threshold,
+            max_symbols: config.max_symbols,
+            sources: parking_lot::RwLock::new(Vec::new()),
+            state: parking_lot::RwLock::new(UniverseState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &UniverseConfig {
+        &self.config
+    }
+
+    /// Discover symbols from `source` on every refresh
+    pub fn add_source(&self, source: Arc<dyn UniverseSource>) {
+        self.sources.write().push(source);
+    }
+
+    /// Re-read the watchlist file and rediscover symbols from every source.
+    /// If every source fails, the symbols discovered last time are kept.
+    pub async fn refresh(&self) -> Result<UniverseStatus> {
+        if let Some(path) = &self.config.watchlist_file {
+            let watchlist = load_watchlist(path)?;
+            self.state.write().watchlist = watchlist.into_iter().map(|s| (universe_key(&s), s)).collect();
+        }
+
+        let sources = self.sources.read().clone();
+        if !sources.is_empty() {
+            let mut candidates = Vec::new();
+            let mut errors = Vec::new();
+            for source in &sources {
+                match source.candidates(&self.config.quote_assets).await {
+                    Ok(found) => candidates.extend(found),
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+
+            let mut state = self.state.write();
+            if errors.len() < sources.len() {
+                state.discovered = self.select(candidates);
+                state.last_refresh = Some(Utc::now());
+            }
+            state.last_error = (!errors.is_empty()).then(|| errors.join("; "));
+        }
+
+        self.rebuild();
+        Ok(self.status())
+    }
+
+    /// Candidates inside the price and liquidity filters, most liquid first
+    fn select(&self, mut candidates: Vec<UniverseCandidate>) -> Vec<Symbol> {
+        candidates.retain(|c| {
+            c.price >= self.min_price && c.price <= self.max_price && c.quote_volume >= self.config.min_quote_volume
+        });
+        candidates.sort_by(|a, b| b.quote_volume.partial_cmp(&a.quote_volume).unwrap_or(std::cmp::Ordering::Equal));
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|c| seen.insert(universe_key(&c.symbol)))
+            .map(|c| c.symbol)
+            .collect()
+    }
+
+    /// Recompute the members: the watchlist, then discovered symbols up to `max_symbols`
+    fn rebuild(&self) {
+        let mut state = self.state.write();
+        let mut members: HashSet<String> = state.watchlist.keys().cloned().collect();
+        for symbol in &state.discovered {
+            if members.len() >= self.max_symbols {
+                break;
+            }
+            members.insert(universe_key(symbol));
+        }
+        state.members = members;
+    }
+
+    /// Whether the scanner should analyse `symbol`; anything goes while the universe is empty
+    pub fn contains(&self, symbol: &Symbol) -> bool {
+        let state = self.state.read();
+        state.members.is_empty() || state.members.contains(&universe_key(symbol))
+    }
+
+    /// Members of the universe, watchlist first
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let state = self.state.read();
+        let mut symbols: Vec<Symbol> = state.watchlist.values().cloned().collect();
+        let discovered = state.discovered.iter().filter(|s| !state.watchlist.contains_key(&universe_key(s)));
+        symbols.extend(discovered.take(self.max_symbols.saturating_sub(symbols.len())).cloned());
+        symbols
+    }
+
+    pub fn status(&self) -> UniverseStatus {
+        let symbols = self.symbols();
+        let state = self.state.read();
+        UniverseStatus {
+            symbols,
+            watchlist: state.watchlist.values().cloned().collect(),
+            discovered: state.discovered.clone(),
+            last_refresh: state.last_refresh,
+            last_error: state.last_error.clone(),
+        }
+    }
+
+    /// Add a symbol to the watchlist, saving the watchlist file if there is
+    /// one. Returns false if it was already listed.
+    pub fn add_to_watchlist(&self, symbol: Symbol) -> Result<bool> {
+        let added = match self.state.write().watchlist.entry(universe_key(&symbol)) {
+            Entry::Vacant(entry) => {
+                entry.insert(symbol);
+                true
+            }
+            Entry::Occupied(_) => false,
+        };
+        if added {
+            self.save_watchlist()?;
+            self.rebuild();
+        }
+        Ok(added)
+    }
+
+    /// Remove a symbol from the watchlist. Returns false if it wasn't listed.
+    pub fn remove_from_watchlist(&self, symbol: &Symbol) -> Result<bool> {
+        let removed = self.state.write().watchlist.remove(&universe_key(symbol)).is_some();
+        if removed {
+            self.save_watchlist()?;
+            self.rebuild();
+        }
+        Ok(removed)
+    }
+
+    fn save_watchlist(&self) -> Result<()> {
+        let Some(path) = &self.config.watchlist_file else {
+            return Ok(());
+        };
+        let contents: String = self.state.read().watchlist.values().map(|s| format!("{}\n", s)).collect();
+        std::fs::write(path, contents).with_context(|| format!("Failed to write watchlist {}", path.display()))
+    }
+
+    /// Refresh now and then every `refresh_interval_secs` until the returned task is aborted
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.refresh_interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                match self.refresh().await {
+                    Ok(status) => {
+                        if let Some(error) = &status.last_error {
+                            warn!(error = %error, "Symbol discovery failed");
+                        }
+                        info!(symbols = status.symbols.len(), watchlist = status.watchlist.len(), "Universe refreshed");
+                    }
+                    Err(e) => warn!(error = %e, "Universe refresh failed"),
+                }
+            }
+        })
+    }
+}
+
+/// Symbols of a watchlist file, one per line; blank lines and `#` comments
+/// are skipped. A missing file is an empty watchlist.
+fn load_watchlist(path: &Path) -> Result<Vec<Symbol>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read watchlist {}", path.display())),
+    };
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(Symbol::new)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedSource(Vec<(&'static str, f64, f64)>);
+
+    #[async_trait]
+    impl UniverseSource for FixedSource {
+        async fn candidates(&self, _quote_assets: &[String]) -> Result<Vec<UniverseCandidate>> {
+            Ok(self
+                .0
+                .iter()
+                .map(|&(symbol, price, quote_volume)| UniverseCandidate { symbol: Symbol::new(symbol), price, quote_volume })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discovery_filters_and_watchlist() {
+        let path = std::env::temp_dir().join(format!("universe-watchlist-{}.txt", std::process::id()));
+        std::fs::write(&path, "# Always scanned\nSOLUSDT\n\n").unwrap();
+        let config = ScannerConfig {
+            max_symbols: 3,
+            min_price_threshold: 1.0,
+            max_price_threshold: 100_000.0,
+            universe: UniverseConfig { watchlist_file: Some(path.clone()), ..UniverseConfig::default() },
+            ..ScannerConfig::default()
+        };
+        let universe = UniverseManager::new(&config);
+        assert!(universe.contains(&Symbol::new("ANYTHING"))); // Empty until the first refresh
+
+        universe.add_source(Arc::new(FixedSource(vec![
+            ("ETHUSDT", 3_000.0, 500_000_000.0),
+            ("BTCUSDT", 50_000.0, 900_000_000.0),
+            ("DOGEUSDT", 0.1, 300_000_000.0), // Under the minimum price
+            ("XYZUSDT", 5.0, 10_000.0),       // Illiquid
+            ("LINKUSDT", 15.0, 80_000_000.0), // No room left
+        ])));
+        let status = universe.refresh().await.unwrap();
+        let names = |symbols: &[Symbol]| symbols.iter().map(|s| s.0.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&status.symbols), ["SOLUSDT", "BTCUSDT", "ETHUSDT"]);
+        assert!(universe.contains(&Symbol::new("btc-usdt")));
+        assert!(!universe.contains(&Symbol::new("LINKUSDT")));
+
+        // Watchlist edits are saved and take the place of discovered symbols
+        assert!(universe.add_to_watchlist(Symbol::new("LINKUSDT")).unwrap());
+        assert!(!universe.add_to_watchlist(Symbol::new("LINK-USDT")).unwrap());
+        assert!(universe.remove_from_watchlist(&Symbol::new("SOLUSDT")).unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "LINKUSDT\n");
+        assert_eq!(names(&universe.refresh().await.unwrap().symbols), ["LINKUSDT", "BTCUSDT", "ETHUSDT"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
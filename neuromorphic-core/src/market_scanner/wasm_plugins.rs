@@ -0,0 +1,283 @@
+//! Strategies loaded at runtime as WASM plugins
+//!
+//! Each `[[scanner.plugins]]` entry is a WebAssembly module run as a
+//! strategy, so strategies can ship without recompiling the crate. Plugins
+//! get no imports: no files, network or clock, only the data they are given.
+//! Every call has a fuel budget and the module's memory is capped, so a
+//! runaway plugin fails its call instead of stalling the scanner.
+//!
+//! Host interface, version 1. The module exports
+//!
+//! - `memory`
+//! - `abi_version() -> i32`, returning 1
+//! - `alloc(len: i32) -> i32`, a buffer of `len` bytes for the input
+//! - `analyze(ptr: i32, len: i32) -> i64`, called with the input in that
+//!   buffer and returning where its output is, `ptr << 32 | len`
+//!
+//! The input is a `PluginInput` as JSON, the output a JSON array of
+//! `PluginOpportunity`. Plugin instances live as long as the scanner, so a
+//! plugin may keep state between calls. Running plugins needs the `wasm`
+//! feature; without it they are skipped with a warning.
+
+use super::{MarketData, MarketRegime, TradingOpportunity};
+use crate::exchanges::Side;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Version of the host interface plugins must implement
+pub const PLUGIN_ABI_VERSION: i32 = 1;
+
+/// One `[[scanner.plugins]]` entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WasmPluginConfig {
+    pub name: String, // Strategy name on its opportunities and in routes
+    pub path: PathBuf, // .wasm, or .wat text
+    #[serde(default = "default_fuel")]
+    pub fuel: u64, // Per call; roughly one unit per instruction
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: usize,
+}
+
+fn default_fuel() -> u64 {
+    10_000_000
+}
+
+fn default_max_memory_mb() -> usize {
+    64
+}
+
+impl WasmPluginConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("name must not be empty");
+        }
+        if self.fuel == 0 || self.max_memory_mb == 0 {
+            bail!("fuel and max_memory_mb must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+/// What a plugin is called with
+#[derive(Debug, Serialize)]
+pub struct PluginInput<'a> {
+    pub abi_version: i32,
+    pub data: &'a MarketData,
+    pub history: &'a [MarketData],
+    pub regime: MarketRegime,
+}
+
+/// An opportunity as a plugin reports it; the host prices its exits from the
+/// update's price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginOpportunity {
+    pub side: Side,
+    pub confidence: f64, // 0..=1
+    #[serde(default)]
+    pub expected_move: f64, // Percent
+    #[serde(default = "default_stop_loss_pct")]
+    pub stop_loss_pct: f64,
+    #[serde(default = "default_take_profit_pct")]
+    pub take_profit_pct: f64,
+    #[serde(default = "default_time_horizon")]
+    pub time_horizon: String,
+    #[serde(default)]
+    pub reasoning: String,
+}
+
+fn default_stop_loss_pct() -> f64 {
+    3.0
+}
+
+fn default_take_profit_pct() -> f64 {
+    6.0
+}
+
+fn default_time_horizon() -> String {
+    "1-4 hours".to_string()
+}
+
+/// Opportunities from a plugin's JSON output, for `data`. Entries with a
+/// confidence or exits out of range are dropped.
+pub fn parse_output(strategy: &str, data: &MarketData, output: &[u8]) -> Result<Vec<TradingOpportunity>> {
+    let reported: Vec<PluginOpportunity> = serde_json::from_slice(output)?;
+    Ok(reported
+        .into_iter()
+        .filter(|o| {
+            (0.0..=1.0).contains(&o.confidence)
+                && o.stop_loss_pct > 0.0
+                && o.stop_loss_pct < 100.0
+                && o.take_profit_pct > 0.0
+                && (o.side == Side::Buy || o.take_profit_pct < 100.0)
+        })
+        .map(|o| {
+            let sign = if o.side == Side::Buy { 1.0 } else { -1.0 };
+            TradingOpportunity {
+                symbol: data.symbol.clone(),
+                strategy: strategy.to_string(),
+                confidence: o.confidence,
+                expected_move: o.expected_move,
+                time_horizon: o.time_horizon,
+                entry_price: data.price,
+                stop_loss: Some(data.price * (1.0 - sign * o.stop_loss_pct / 100.0)),
+                take_profit: Some(data.price * (1.0 + sign * o.take_profit_pct / 100.0)),
+                position_size: (o.confidence * 0.1).min(0.05),
+                reasoning: o.reasoning,
+                risk_score: 1.0 - o.confidence,
+                timestamp: Utc::now(),
+                exchange: data.exchange,
+            }
+        })
+        .collect())
+}
+
+#[cfg(feature = "wasm")]
+pub use plugin::WasmStrategy;
+
+#[cfg(feature = "wasm")]
+mod plugin {
+    use super::*;
+    use crate::market_scanner::strategies::{RiskLevel, TradingStrategy};
+    use anyhow::Context;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use wasmtime::{Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+    /// Largest output a plugin may return
+    const MAX_OUTPUT_BYTES: usize = 1 << 20;
+
+    struct Loaded {
+        store: Store<StoreLimits>,
+        memory: Memory,
+        alloc: TypedFunc<i32, i32>,
+        analyze: TypedFunc<(i32, i32), i64>,
+    }
+
+    /// A strategy run by a sandboxed WASM module
+    pub struct WasmStrategy {
+        config: WasmPluginConfig,
+        loaded: Mutex<Loaded>,
+        description: String,
+    }
+
+    impl WasmStrategy {
+        /// Compile and instantiate the plugin, checking its interface version
+        pub fn load(config: WasmPluginConfig) -> Result<Self> {
+            config.validate()?;
+            let mut engine_config = Config::new();
+            engine_config.consume_fuel(true);
+            let engine = Engine::new(&engine_config)?;
+            let module = Module::from_file(&engine, &config.path)
+                .with_context(|| format!("Failed to load WASM plugin {}", config.path.display()))?;
+
+            let limits = StoreLimitsBuilder::new().memory_size(config.max_memory_mb << 20).build();
+            let mut store = Store::new(&engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(config.fuel)?;
+            let instance: Instance = Linker::new(&engine)
+                .instantiate(&mut store, &module)
+                .context("Plugins may not import anything")?;
+
+            let version = instance.get_typed_func::<(), i32>(&mut store, "abi_version")?.call(&mut store, ())?;
+            if version != PLUGIN_ABI_VERSION {
+                bail!("Plugin implements host interface {}, expected {}", version, PLUGIN_ABI_VERSION);
+            }
+            let memory = instance.get_memory(&mut store, "memory").context("Plugin exports no memory")?;
+            let alloc = instance.get_typed_func(&mut store, "alloc")?;
+            let analyze = instance.get_typed_func(&mut store, "analyze")?;
+            Ok(Self {
+                description: format!("WASM plugin {}", config.path.display()),
+                config,
+                loaded: Mutex::new(Loaded { store, memory, alloc, analyze }),
+            })
+        }
+
+        fn call(&self, input: &[u8]) -> Result<Vec<u8>> {
+            let mut loaded = self.loaded.lock();
+            let Loaded { store, memory, alloc, analyze } = &mut *loaded;
+            store.set_fuel(self.config.fuel)?;
+
+            let len = i32::try_from(input.len()).context("Input too large for the plugin")?;
+            let ptr = alloc.call(&mut *store, len)?;
+            memory.write(&mut *store, ptr as u32 as usize, input)?;
+            let packed = analyze.call(&mut *store, (ptr, len))?;
+
+            let (out_ptr, out_len) = ((packed >> 32) as u32 as usize, packed as u32 as usize);
+            if out_len > MAX_OUTPUT_BYTES {
+                bail!("Plugin returned {} bytes, more than {}", out_len, MAX_OUTPUT_BYTES);
+            }
+            let mut output = vec![0; out_len];
+            memory.read(&*store, out_ptr, &mut output)?;
+            Ok(output)
+        }
+    }
+
+    #[async_trait]
+    impl TradingStrategy for WasmStrategy {
+        async fn analyze(&self, data: &MarketData, history: &[MarketData], regime: MarketRegime) -> Result<Vec<TradingOpportunity>> {
+            let input = serde_json::to_vec(&PluginInput { abi_version: PLUGIN_ABI_VERSION, data, history, regime })?;
+            let output = self.call(&input).with_context(|| format!("Plugin '{}' failed", self.config.name))?;
+            parse_output(&self.config.name, data, &output)
+        }
+
+        fn get_name(&self) -> &str {
+            &self.config.name
+        }
+
+        fn get_description(&self) -> &str {
+            &self.description
+        }
+
+        fn get_risk_level(&self) -> RiskLevel {
+            RiskLevel::Moderate
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Symbol;
+    #[cfg(feature = "wasm")]
+    use crate::market_scanner::strategies::TradingStrategy;
+
+    #[tokio::test]
+    async fn test_plugin_output_becomes_opportunities() {
+        let data = MarketData::new(Symbol::new("ETHUSDT"), 2_000.0);
+        let output = br#"[
+            {"side": "Sell", "confidence": 0.8, "expected_move": 2.5, "reasoning": "fading the spike"},
+            {"side": "Buy", "confidence": 1.5}
+        ]"#;
+        let opportunities = parse_output("Fade", &data, output).unwrap();
+        assert_eq!(opportunities.len(), 1);
+        let fade = &opportunities[0];
+        assert_eq!((fade.strategy.as_str(), fade.side(), fade.stop_loss, fade.take_profit), ("Fade", Some(Side::Sell), Some(2_060.0), Some(1_880.0)));
+        assert!(parse_output("Fade", &data, b"not json").is_err());
+
+        // A plugin that reports one fixed opportunity, whatever it is given
+        #[cfg(feature = "wasm")]
+        {
+            let json = r#"[{"side":"Buy","confidence":0.9}]"#;
+            let wat = format!(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "{}")
+                    (func (export "abi_version") (result i32) i32.const 1)
+                    (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                    (func (export "analyze") (param i32 i32) (result i64) i64.const {}))"#,
+                json.replace('"', "\\\""),
+                json.len()
+            );
+            let path = std::env::temp_dir().join(format!("plugin-{}.wat", std::process::id()));
+            std::fs::write(&path, wat).unwrap();
+            let config = WasmPluginConfig { name: "Fixed".into(), path: path.clone(), fuel: 10_000, max_memory_mb: 1 };
+            let strategy = WasmStrategy::load(config).unwrap();
+            let opportunities = strategy.analyze(&data, &[], MarketRegime::Consolidation).await.unwrap();
+            assert_eq!((opportunities.len(), opportunities[0].confidence, opportunities[0].side()), (1, 0.9, Some(Side::Buy)));
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
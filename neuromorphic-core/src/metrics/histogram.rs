@@ -0,0 +1,271 @@
+//! Histograms of latencies and trade sizes
+//!
+//! Averages hide the slow fill and the one trade that lost a week's profit, so
+//! fill latency, signal-to-order latency, trade P&L and position duration are
+//! also counted into fixed buckets. The buckets are configurable under
+//! `[metrics.histograms]`; the JSON API reports them with estimated
+//! percentiles and the Prometheus endpoint as native histograms.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use utoipa::ToSchema;
+
+use crate::paper_trading::{Order, TradeOutcome};
+
+/// Upper bounds of the buckets of each histogram, in increasing order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistogramBuckets {
+    pub fill_latency_ms: Vec<f64>,
+    pub signal_to_order_ms: Vec<f64>,
+    pub trade_pnl: Vec<f64>, // In the quote currency, so losses get buckets too
+    pub position_duration_secs: Vec<f64>,
+}
+
+impl Default for HistogramBuckets {
+    fn default() -> Self {
+        Self {
+            fill_latency_ms: vec![10.0, 50.0, 100.0, 250.0, 500.0, 1_000.0, 2_500.0, 5_000.0, 10_000.0, 30_000.0, 60_000.0],
+            signal_to_order_ms: vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1_000.0],
+            trade_pnl: vec![-1_000.0, -500.0, -250.0, -100.0, -50.0, -10.0, 0.0, 10.0, 50.0, 100.0, 250.0, 500.0, 1_000.0],
+            position_duration_secs: vec![60.0, 300.0, 900.0, 1_800.0, 3_600.0, 14_400.0, 86_400.0, 259_200.0, 604_800.0],
+        }
+    }
+}
+
+impl HistogramBuckets {
+    /// Name and bounds of each histogram
+    pub fn all(&self) -> [(&'static str, &[f64]); 4] {
+        [
+            ("fill_latency_ms", &self.fill_latency_ms),
+            ("signal_to_order_ms", &self.signal_to_order_ms),
+            ("trade_pnl", &self.trade_pnl),
+            ("position_duration_secs", &self.position_duration_secs),
+        ]
+    }
+}
+
+/// Number of observations up to `le`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HistogramBucket {
+    pub le: f64,
+    pub count: u64, // Cumulative, as in Prometheus
+}
+
+/// Buckets, total and percentile estimates of one histogram
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HistogramSnapshot {
+    pub buckets: Vec<HistogramBucket>, // Observations above the last bound only count towards `count`
+    pub count: u64,
+    pub sum: f64,
+    pub p50: Option<f64>, // Interpolated within the bucket; None before any observation
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+impl HistogramSnapshot {
+    /// Estimate of the `q` quantile, interpolating linearly within its
+    /// bucket. Past the last bound the estimate is that bound.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = q.clamp(0.0, 1.0) * self.count as f64;
+        let mut lower: Option<(f64, u64)> = None;
+        for bucket in &self.buckets {
+            if bucket.count as f64 >= rank {
+                return Some(match lower {
+                    Some((le, count)) if bucket.count > count => {
+                        le + (bucket.le - le) * (rank - count as f64) / (bucket.count - count) as f64
+                    }
+                    // The first bucket starts at zero, unless its bound is not above it
+                    None if bucket.le > 0.0 => bucket.le * rank / bucket.count as f64,
+                    _ => bucket.le,
+                });
+            }
+            lower = Some((bucket.le, bucket.count));
+        }
+        self.buckets.last().map(|bucket| bucket.le)
+    }
+}
+
+struct HistogramState {
+    bounds: Vec<f64>,
+    counts: Vec<u64>, // Per bucket, plus one for everything above the last bound
+    sum: f64,
+}
+
+/// Observations counted into fixed buckets
+pub struct Histogram {
+    state: Mutex<HistogramState>,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        Self {
+            state: Mutex::new(HistogramState {
+                bounds: bounds.to_vec(),
+                counts: vec![0; bounds.len() + 1],
+                sum: 0.0,
+            }),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let mut state = self.state.lock();
+        let bucket = state.bounds.partition_point(|bound| *bound < value);
+        state.counts[bucket] += 1;
+        state.sum += value;
+    }
+
+    /// Start over with new bounds
+    pub fn reset(&self, bounds: &[f64]) {
+        *self.state.lock() = Histogram::new(bounds).state.into_inner();
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let state = self.state.lock();
+        let mut cumulative = 0;
+        let buckets = state
+            .bounds
+            .iter()
+            .zip(&state.counts)
+            .map(|(le, count)| {
+                cumulative += count;
+                HistogramBucket { le: *le, count: cumulative }
+            })
+            .collect();
+        let mut snapshot = HistogramSnapshot {
+            buckets,
+            count: state.counts.iter().sum(),
+            sum: state.sum,
+            ..HistogramSnapshot::default()
+        };
+        drop(state);
+        snapshot.p50 = snapshot.quantile(0.5);
+        snapshot.p90 = snapshot.quantile(0.9);
+        snapshot.p99 = snapshot.quantile(0.99);
+        snapshot
+    }
+}
+
+/// The histograms the engines and their outcomes feed, shared by all accounts
+pub struct TradingHistograms {
+    pub fill_latency_ms: Histogram,    // Order creation to fill, by the engine clock
+    pub signal_to_order_ms: Histogram, // Signal queued to order submitted
+    pub trade_pnl: Histogram,
+    pub position_duration_secs: Histogram,
+}
+
+impl Default for TradingHistograms {
+    fn default() -> Self {
+        Self::new(&HistogramBuckets::default())
+    }
+}
+
+impl TradingHistograms {
+    pub fn new(buckets: &HistogramBuckets) -> Self {
+        Self {
+            fill_latency_ms: Histogram::new(&buckets.fill_latency_ms),
+            signal_to_order_ms: Histogram::new(&buckets.signal_to_order_ms),
+            trade_pnl: Histogram::new(&buckets.trade_pnl),
+            position_duration_secs: Histogram::new(&buckets.position_duration_secs),
+        }
+    }
+
+    /// Use new bucket bounds, discarding what was counted so far
+    pub fn set_buckets(&self, buckets: &HistogramBuckets) {
+        self.fill_latency_ms.reset(&buckets.fill_latency_ms);
+        self.signal_to_order_ms.reset(&buckets.signal_to_order_ms);
+        self.trade_pnl.reset(&buckets.trade_pnl);
+        self.position_duration_secs.reset(&buckets.position_duration_secs);
+    }
+
+    pub fn record_fill(&self, order: &Order) {
+        if let Some(filled_time) = order.filled_time {
+            self.fill_latency_ms.observe(filled_time.saturating_sub(order.created_time) as f64);
+        }
+    }
+
+    pub fn record_outcome(&self, outcome: &TradeOutcome) {
+        self.trade_pnl.observe(outcome.pnl);
+        self.position_duration_secs.observe(outcome.holding_time.as_secs_f64());
+    }
+
+    pub fn snapshot(&self, timestamp: DateTime<Utc>) -> HistogramMetrics {
+        HistogramMetrics {
+            timestamp,
+            fill_latency_ms: self.fill_latency_ms.snapshot(),
+            signal_to_order_ms: self.signal_to_order_ms.snapshot(),
+            trade_pnl: self.trade_pnl.snapshot(),
+            position_duration_secs: self.position_duration_secs.snapshot(),
+        }
+    }
+}
+
+/// Latency, P&L and holding time distributions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistogramMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub fill_latency_ms: HistogramSnapshot,
+    pub signal_to_order_ms: HistogramSnapshot,
+    pub trade_pnl: HistogramSnapshot,
+    pub position_duration_secs: HistogramSnapshot,
+}
+
+impl HistogramMetrics {
+    /// Append every histogram in the Prometheus text format
+    pub fn write_prometheus(&self, out: &mut String) {
+        let histograms = [
+            ("neuromorphic_order_fill_latency_milliseconds", "Time from order creation to fill", &self.fill_latency_ms),
+            ("neuromorphic_signal_to_order_latency_milliseconds", "Time from a signal being queued to its order being submitted", &self.signal_to_order_ms),
+            ("neuromorphic_trade_pnl", "Realized P&L of closed trades in the quote currency", &self.trade_pnl),
+            ("neuromorphic_position_duration_seconds", "Holding time of closed positions", &self.position_duration_secs),
+        ];
+        for (name, help, histogram) in histograms {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} histogram", name);
+            for bucket in &histogram.buckets {
+                let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bucket.le, bucket.count);
+            }
+            let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count);
+            let _ = writeln!(out, "{}_sum {}", name, histogram.sum);
+            let _ = writeln!(out, "{}_count {}", name, histogram.count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_percentiles_and_exposition() {
+        let histogram = Histogram::new(&[10.0, 100.0, 1_000.0]);
+        for value in [5.0, 20.0, 40.0, 60.0, 80.0, 100.0, 150.0, 900.0, 5_000.0, f64::NAN] {
+            histogram.observe(value);
+        }
+        let snapshot = histogram.snapshot();
+        let counts: Vec<u64> = snapshot.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![1, 6, 8]);
+        assert_eq!((snapshot.count, snapshot.sum), (9, 6_355.0));
+        // 4.5th of 9 observations: 3.5 of the 5 between 10 and 100
+        assert!((snapshot.p50.unwrap() - 73.0).abs() < 1e-9);
+        assert_eq!(snapshot.p99, Some(1_000.0));
+
+        histogram.reset(&[1.0]);
+        assert_eq!((histogram.snapshot().count, histogram.snapshot().p50), (0, None));
+
+        let metrics = TradingHistograms::default().snapshot(Utc::now());
+        let mut text = String::new();
+        metrics.write_prometheus(&mut text);
+        assert!(text.contains("# TYPE neuromorphic_trade_pnl histogram"));
+        assert!(text.contains("neuromorphic_trade_pnl_bucket{le=\"-1000\"} 0"));
+        assert!(text.contains("neuromorphic_position_duration_seconds_count 0"));
+    }
+}
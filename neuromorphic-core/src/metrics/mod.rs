@@ -2,20 +2,45 @@
 //! 
 //! This module provides real-time metrics for the neuromorphic trading system
 //! that can be consumed by Grafana dashboards.
+//!
+//! Recording a signal is on the hot path, so it costs O(1): counters are
+//! atomic and the signal averages and counts are running totals over a ring
+//! buffer of recent signals, adjusted as signals enter and leave it rather
+//! than recomputed. Signal metrics are assembled when read, and so are the
+//! 24-hour volume, range, change and volatility of the market metrics, from
+//! one-minute bars of the prices and traded volume the engines are fed.
+//!
+//! The time series Grafana graphs are sampled as the portfolio metrics
+//! update; see `series` for keeping them across restarts.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use utoipa::ToSchema;
 
+pub mod histogram;
+pub mod series;
+
+pub use histogram::{Histogram, HistogramBucket, HistogramBuckets, HistogramMetrics, HistogramSnapshot, TradingHistograms};
+pub use series::{MetricSample, MetricSeries, SeriesConfig, SeriesFormat};
+
+use crate::control::{Decision, DecisionRecord};
 use crate::exchanges::Symbol;
-use crate::paper_trading::{PositionStatistics, TradingSignal};
+use crate::exchanges::Side;
+use crate::exchanges::{ConnectionStatus, Exchange, LatencyStatistics, StreamMetrics};
+use crate::market_data::CompositeQuote;
+use crate::market_scanner::MoversTracker;
+use crate::paper_trading::{system_clock, SharedClock, RingBuffer, Spill, AccountStatistics, CalibrationBucket, Competitor, ConfidenceCalibration, TradeOutcome, PnlAttribution, Position, PositionStatistics, QueueStatistics, ScenarioReport, TradingSignal, WindowStatistics};
 
 /// Real-time portfolio metrics for Grafana
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PortfolioMetrics {
     pub timestamp: DateTime<Utc>,
+    pub currency: String, // Base currency of the amounts
     pub total_capital: f64,
     pub available_capital: f64,
     pub total_pnl: f64,
@@ -35,10 +60,14 @@ pub struct PortfolioMetrics {
 }
 
 /// Neuromorphic signal metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SignalMetrics {
     pub timestamp: DateTime<Utc>,
     pub signals_processed: u64,
+    pub signals_throttled: u64, // Dropped by the per-symbol throttle rather than executed
+    pub opportunities_suppressed: u64, // Repeats of a recently traded scanner opportunity
+    #[serde(default)]
+    pub opportunities_skipped: HashMap<String, u64>, // Untraded scanner opportunities by `SkipReason`
     pub signals_per_minute: f64,
     pub avg_confidence: f64,
     pub avg_urgency: f64,
@@ -50,7 +79,7 @@ pub struct SignalMetrics {
 }
 
 /// Position-level metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PositionMetrics {
     pub timestamp: DateTime<Utc>,
     pub symbol: String,
@@ -62,23 +91,27 @@ pub struct PositionMetrics {
     pub unrealized_pnl_pct: f64,
     pub duration_minutes: i64,
     pub is_long: bool,
+    pub max_adverse_excursion: f64,
+    pub max_favorable_excursion: f64,
 }
 
 /// Market data metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MarketMetrics {
     pub timestamp: DateTime<Utc>,
     pub symbol: String,
     pub price: f64,
     pub volume_24h: f64,
+    pub high_24h: f64,
+    pub low_24h: f64,
     pub price_change_24h: f64,
     pub price_change_pct_24h: f64,
-    pub volatility: f64,
+    pub volatility: f64, // Daily, from one-minute returns, in percent
     pub last_update: DateTime<Utc>,
 }
 
 /// Risk metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RiskMetrics {
     pub timestamp: DateTime<Utc>,
     pub portfolio_var_95: f64,      // Value at Risk 95%
@@ -88,11 +121,80 @@ pub struct RiskMetrics {
     pub correlation_btc: f64,
     pub correlation_eth: f64,
     pub concentration_risk: f64,    // Largest position as % of portfolio
+    pub portfolio_heat: f64,        // Loss at the stops of open positions over equity
     pub daily_volatility: f64,
 }
 
+/// Per-account and consolidated account statistics
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub consolidated: AccountStatistics,
+    pub accounts: Vec<AccountStatistics>,
+}
+
+/// P&L attribution of one account
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountAttribution {
+    pub account_id: String,
+    pub attribution: PnlAttribution,
+}
+
+/// P&L by symbol, exchange, entry hour and weekday, per account and in total
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttributionMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub consolidated: PnlAttribution,
+    pub accounts: Vec<AccountAttribution>,
+}
+
+/// Trading statistics over trailing 1h / 24h / 7d windows
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RollingMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub windows: Vec<WindowStatistics>,
+}
+
+/// Depth and overflow counters of the engine's queues
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueueMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub signals: QueueStatistics,
+    pub order_events: QueueStatistics,
+}
+
+/// Latency and clock skew of the market data streams
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamLatencyMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub streams: HashMap<String, LatencyStatistics>, // Keyed by exchange
+}
+
+/// Best bid and offer of each instrument across venues, and what each venue
+/// contributes to it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuoteMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub symbols: Vec<CompositeQuote>,
+}
+
+/// Realized win rate by signal confidence decile
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CalibrationMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub buckets: Vec<CalibrationBucket>,
+}
+
+/// `[metrics]` settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub histograms: HistogramBuckets,
+    pub series: SeriesConfig,
+}
+
 /// Comprehensive metrics container
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TradingMetrics {
     pub portfolio: PortfolioMetrics,
     pub signals: SignalMetrics,
@@ -101,26 +203,127 @@ pub struct TradingMetrics {
     pub risk: RiskMetrics,
 }
 
+/// Signals the signal averages and counts are taken over, unless configured
+const SIGNAL_WINDOW: usize = 1000;
+
+/// Recent signals with running totals over them
+struct SignalWindow {
+    signals: RingBuffer<TradingSignal>, // Oldest first
+    confidence: f64,
+    urgency: f64,
+    pattern_strength: f64,
+    spike_count: f64,
+    volatility: f64,
+    actions: HashMap<String, u64>, // Buy, Sell, Hold, Close counts
+    regimes: HashMap<String, u64>,
+}
+
+impl SignalWindow {
+    fn new(capacity: usize, spill: Option<Spill>) -> Self {
+        Self {
+            signals: RingBuffer::new(capacity).with_spill(spill),
+            confidence: 0.0,
+            urgency: 0.0,
+            pattern_strength: 0.0,
+            spike_count: 0.0,
+            volatility: 0.0,
+            actions: HashMap::new(),
+            regimes: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, signal: TradingSignal) {
+        self.add(&signal, 1.0);
+        if let Some(oldest) = self.signals.push(signal) {
+            self.add(&oldest, -1.0);
+        }
+    }
+
+    fn signals_per_minute(&self) -> f64 {
+        // TODO: Add timestamp to TradingSignal and count the last 10 minutes only
+        self.signals.len() as f64 / 10.0
+    }
+
+    /// Add a signal to the totals with `sign` 1, or take it out with -1
+    fn add(&mut self, signal: &TradingSignal, sign: f64) {
+        self.confidence += sign * signal.confidence;
+        self.urgency += sign * signal.urgency;
+        self.pattern_strength += sign * signal.metadata.pattern_strength;
+        self.spike_count += sign * signal.metadata.spike_count as f64;
+        self.volatility += sign * signal.metadata.volatility;
+
+        let action = match &signal.action {
+            crate::paper_trading::SignalAction::Buy { .. } => "Buy",
+            crate::paper_trading::SignalAction::Sell { .. } => "Sell",
+            crate::paper_trading::SignalAction::Hold => "Hold",
+            crate::paper_trading::SignalAction::Close { .. } => "Close",
+            crate::paper_trading::SignalAction::ScaleIn { .. } => "ScaleIn",
+            crate::paper_trading::SignalAction::ScaleOut { .. } => "ScaleOut",
+        };
+        for (counts, key) in [(&mut self.actions, action), (&mut self.regimes, signal.metadata.market_regime.as_str())] {
+            if sign > 0.0 {
+                *counts.entry(key.to_string()).or_insert(0) += 1;
+            } else if let Some(count) = counts.get_mut(key) {
+                *count -= 1;
+                if *count == 0 {
+                    counts.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// Last reported state of an exchange's market data stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedStatus {
+    pub connection: ConnectionStatus,
+    pub last_data: Option<DateTime<Utc>>,
+}
+
 /// Metrics collector that aggregates data from the trading system
 pub struct MetricsCollector {
     portfolio_metrics: Arc<RwLock<PortfolioMetrics>>,
-    signal_metrics: Arc<RwLock<SignalMetrics>>,
     position_metrics: Arc<RwLock<Vec<PositionMetrics>>>,
     market_metrics: Arc<RwLock<HashMap<Symbol, MarketMetrics>>>,
+    market_stats: MoversTracker, // Rolling 24h bars behind the market metrics
     risk_metrics: Arc<RwLock<RiskMetrics>>,
+    account_metrics: Arc<RwLock<Vec<AccountStatistics>>>,
+    competitors: RwLock<Vec<Competitor>>, // Accounts as scored for the competition leaderboard
+    attribution: Arc<RwLock<Vec<AccountAttribution>>>,
+    rolling_metrics: Arc<RwLock<Vec<WindowStatistics>>>,
+    queue_metrics: Arc<RwLock<QueueMetrics>>,
+    stream_latency: Arc<RwLock<HashMap<String, LatencyStatistics>>>,
+    engines_running: RwLock<BTreeMap<String, bool>>, // By account
+    feed_status: RwLock<BTreeMap<String, FeedStatus>>, // By exchange
+    composite_quotes: RwLock<Vec<CompositeQuote>>,
+    calibration: Arc<ConfidenceCalibration>,
+    histograms: Arc<TradingHistograms>,
+    scenarios: Arc<RwLock<ScenarioReport>>,
     
     // Signal processing counters
-    signal_count: Arc<RwLock<u64>>,
-    signal_history: Arc<RwLock<Vec<TradingSignal>>>,
+    signals_processed: AtomicU64,
+    signals_throttled: AtomicU64,
+    opportunities_suppressed: AtomicU64,
+    opportunities_skipped: RwLock<HashMap<String, u64>>, // By `SkipReason`
+    signal_window: Mutex<SignalWindow>,
+    series: Mutex<MetricSeries>,
+    decision_history: Arc<RwLock<VecDeque<DecisionRecord>>>,
+    clock: SharedClock,
 }
 
 impl MetricsCollector {
     pub fn new() -> Self {
-        let now = Utc::now();
+        Self::with_clock(system_clock())
+    }
+
+    /// Create a collector that timestamps metrics from `clock`
+    pub fn with_clock(clock: SharedClock) -> Self {
+        let now = clock.now();
         
         Self {
             portfolio_metrics: Arc::new(RwLock::new(PortfolioMetrics {
                 timestamp: now,
+                currency: "USD".to_string(),
                 total_capital: 0.0,
                 available_capital: 0.0,
                 total_pnl: 0.0,
@@ -138,20 +341,10 @@ impl MetricsCollector {
                 max_drawdown: 0.0,
                 sharpe_ratio: 0.0,
             })),
-            signal_metrics: Arc::new(RwLock::new(SignalMetrics {
-                timestamp: now,
-                signals_processed: 0,
-                signals_per_minute: 0.0,
-                avg_confidence: 0.0,
-                avg_urgency: 0.0,
-                signal_distribution: HashMap::new(),
-                pattern_strength_avg: 0.0,
-                spike_count_avg: 0.0,
-                volatility_avg: 0.0,
-                market_regimes: HashMap::new(),
-            })),
             position_metrics: Arc::new(RwLock::new(Vec::new())),
             market_metrics: Arc::new(RwLock::new(HashMap::new())),
+            // Only its statistics are read, never its unusual volume list
+            market_stats: MoversTracker::new(f64::INFINITY),
             risk_metrics: Arc::new(RwLock::new(RiskMetrics {
                 timestamp: now,
                 portfolio_var_95: 0.0,
@@ -161,17 +354,88 @@ impl MetricsCollector {
                 correlation_btc: 0.0,
                 correlation_eth: 0.0,
                 concentration_risk: 0.0,
+                portfolio_heat: 0.0,
                 daily_volatility: 0.0,
             })),
-            signal_count: Arc::new(RwLock::new(0)),
-            signal_history: Arc::new(RwLock::new(Vec::new())),
+            account_metrics: Arc::new(RwLock::new(Vec::new())),
+            competitors: RwLock::new(Vec::new()),
+            attribution: Arc::new(RwLock::new(Vec::new())),
+            rolling_metrics: Arc::new(RwLock::new(Vec::new())),
+            queue_metrics: Arc::new(RwLock::new(QueueMetrics {
+                timestamp: now,
+                signals: QueueStatistics::default(),
+                order_events: QueueStatistics::default(),
+            })),
+            stream_latency: Arc::new(RwLock::new(HashMap::new())),
+            engines_running: RwLock::new(BTreeMap::new()),
+            feed_status: RwLock::new(BTreeMap::new()),
+            composite_quotes: RwLock::new(Vec::new()),
+            calibration: Arc::new(ConfidenceCalibration::default()),
+            histograms: Arc::new(TradingHistograms::default()),
+            scenarios: Arc::new(RwLock::new(ScenarioReport::default())),
+            signals_processed: AtomicU64::new(0),
+            signals_throttled: AtomicU64::new(0),
+            opportunities_suppressed: AtomicU64::new(0),
+            opportunities_skipped: RwLock::new(HashMap::new()),
+            signal_window: Mutex::new(SignalWindow::new(SIGNAL_WINDOW, None)),
+            series: Mutex::new(MetricSeries::new(&SeriesConfig::default())),
+            decision_history: Arc::new(RwLock::new(VecDeque::new())),
+            clock,
         }
     }
 
+    /// Report from a calibration kept elsewhere, e.g. the one the accounts size positions by
+    pub fn with_confidence_calibration(mut self, calibration: Arc<ConfidenceCalibration>) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Report histograms fed elsewhere, e.g. the ones the accounts record into
+    pub fn with_histograms(mut self, histograms: Arc<TradingHistograms>) -> Self {
+        self.histograms = histograms;
+        self
+    }
+
+    /// Take signal metrics over the latest `capacity` signals, appending older
+    /// ones to `spill`
+    pub fn with_signal_history(self, capacity: usize, spill: Option<Spill>) -> Self {
+        *self.signal_window.lock() = SignalWindow::new(capacity, spill);
+        self
+    }
+
+    /// Sample the time series per `config`, loading the samples persisted
+    /// within its retention; returns how many were loaded. When they can't
+    /// be loaded the series is kept in memory only.
+    pub fn open_series(&self, config: &SeriesConfig) -> anyhow::Result<usize> {
+        let opened = MetricSeries::open(config, self.clock.now());
+        let mut series = self.series.lock();
+        match opened {
+            Ok(opened) => {
+                *series = opened;
+                Ok(series.len())
+            }
+            Err(e) => {
+                *series = MetricSeries::new(config);
+                Err(e)
+            }
+        }
+    }
+
+    /// Samples of the time series in `[from, to]`, oldest first
+    pub fn get_series(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Vec<MetricSample> {
+        self.series.lock().range(from, to)
+    }
+
+    /// Write out samples the series still buffers, e.g. before exiting
+    pub fn flush_series(&self) {
+        self.series.lock().flush();
+    }
+
     /// Update portfolio metrics from trading statistics
     pub fn update_portfolio_metrics(&self, stats: &crate::paper_trading::TradingStatistics) {
         let mut metrics = self.portfolio_metrics.write();
-        metrics.timestamp = Utc::now();
+        metrics.timestamp = self.clock.now();
+        metrics.currency = stats.currency.clone();
         metrics.total_capital = stats.capital;
         metrics.total_pnl = stats.total_pnl;
         metrics.total_return_pct = stats.total_return_pct;
@@ -191,117 +455,178 @@ impl MetricsCollector {
         
         // Calculate Sharpe ratio if we have risk metrics
         metrics.sharpe_ratio = stats.risk_metrics.sharpe_ratio;
+        let sample = {
+            let window = self.signal_window.lock();
+            MetricSample {
+                timestamp: metrics.timestamp,
+                total_capital: metrics.total_capital,
+                total_pnl: metrics.total_pnl,
+                total_return_pct: metrics.total_return_pct,
+                signals_processed: self.signals_processed.load(Ordering::Relaxed),
+                signals_per_minute: window.signals_per_minute(),
+                avg_confidence: window.confidence / window.signals.len().max(1) as f64,
+            }
+        };
+        drop(metrics);
+        self.series.lock().record(sample);
+        
+        {
+            let mut risk = self.risk_metrics.write();
+            risk.timestamp = self.clock.now();
+            risk.current_leverage = stats.risk_metrics.leverage_ratio;
+            risk.portfolio_heat = stats.risk_metrics.portfolio_heat;
+            risk.portfolio_var_95 = stats.risk_metrics.var_95;
+            risk.portfolio_var_99 = stats.risk_metrics.var_99;
+        }
+        
+        *self.rolling_metrics.write() = stats.rolling.clone();
+        *self.queue_metrics.write() = QueueMetrics {
+            timestamp: self.clock.now(),
+            signals: stats.signal_queue.clone(),
+            order_events: stats.order_event_queue.clone(),
+        };
+    }
+
+    /// Record how many signals the engines have throttled so far
+    pub fn update_throttled_signals(&self, throttled: u64) {
+        self.signals_throttled.store(throttled, Ordering::Relaxed);
+    }
+
+    /// Record how many scanner opportunities were suppressed as repeats so far
+    pub fn update_suppressed_opportunities(&self, suppressed: u64) {
+        self.opportunities_suppressed.store(suppressed, Ordering::Relaxed);
+    }
+
+    /// Record what the autonomous system decided on an opportunity
+    pub fn record_decision(&self, record: DecisionRecord) {
+        if let Decision::Skipped { reason } = &record.decision {
+            *self.opportunities_skipped.write().entry(reason.to_string()).or_insert(0) += 1;
+        }
+
+        let mut history = self.decision_history.write();
+        history.push_back(record);
+        
+        // Keep only last 1000 decisions
+        if history.len() > 1000 {
+            history.pop_front();
+        }
+    }
+
+    /// Up to `limit` opportunity decisions, newest first
+    pub fn get_decision_history(&self, limit: usize) -> Vec<DecisionRecord> {
+        self.decision_history.read().iter().rev().take(limit).cloned().collect()
     }
 
     /// Record a new trading signal
     pub fn record_signal(&self, signal: &TradingSignal) {
-        {
-            let mut count = self.signal_count.write();
-            *count += 1;
-        }
+        self.signals_processed.fetch_add(1, Ordering::Relaxed);
+        self.signal_window.lock().push(signal.clone());
+    }
 
-        {
-            let mut history = self.signal_history.write();
-            history.push(signal.clone());
+    /// Update position-level metrics from the currently open positions
+    pub fn update_position_metrics(&self, positions: &[Position]) {
+        let now = self.clock.now();
+        let market_data = self.market_metrics.read();
+        
+        let metrics = positions.iter().map(|position| {
+            let current_price = market_data
+                .get(&position.symbol)
+                .map(|m| m.price)
+                .unwrap_or(position.entry_price);
+            let cost_basis = position.quantity * position.entry_price;
             
-            // Keep only last 1000 signals
-            if history.len() > 1000 {
-                history.remove(0);
+            PositionMetrics {
+                timestamp: now,
+                symbol: position.symbol.to_string(),
+                position_id: position.id.clone(),
+                size: position.quantity,
+                entry_price: position.entry_price,
+                current_price,
+                unrealized_pnl: position.unrealized_pnl,
+                unrealized_pnl_pct: if cost_basis > 0.0 {
+                    position.unrealized_pnl / cost_basis * 100.0
+                } else {
+                    0.0
+                },
+                duration_minutes: (now.timestamp_millis() - position.entry_time as i64) / 60_000,
+                is_long: position.side == Side::Buy,
+                max_adverse_excursion: position.max_adverse_excursion,
+                max_favorable_excursion: position.max_favorable_excursion,
             }
-        }
+        }).collect();
+        drop(market_data);
+        
+        *self.position_metrics.write() = metrics;
+    }
 
-        self.update_signal_metrics();
-    }
-
-    /// Update signal metrics based on recent signal history
-    fn update_signal_metrics(&self) {
-        let history = self.signal_history.read();
-        let count = self.signal_count.read();
-        let mut metrics = self.signal_metrics.write();
-
-        metrics.timestamp = Utc::now();
-        metrics.signals_processed = *count;
-
-        if !history.is_empty() {
-            // Calculate averages
-            let total_confidence: f64 = history.iter().map(|s| s.confidence).sum();
-            let total_urgency: f64 = history.iter().map(|s| s.urgency).sum();
-            let total_pattern_strength: f64 = history.iter().map(|s| s.metadata.pattern_strength).sum();
-            let total_spike_count: f64 = history.iter().map(|s| s.metadata.spike_count as f64).sum();
-            let total_volatility: f64 = history.iter().map(|s| s.metadata.volatility).sum();
-
-            let len = history.len() as f64;
-            metrics.avg_confidence = total_confidence / len;
-            metrics.avg_urgency = total_urgency / len;
-            metrics.pattern_strength_avg = total_pattern_strength / len;
-            metrics.spike_count_avg = total_spike_count / len;
-            metrics.volatility_avg = total_volatility / len;
-
-            // Count signal types
-            let mut distribution = HashMap::new();
-            let mut regimes = HashMap::new();
-
-            for signal in history.iter() {
-                let action_type = match &signal.action {
-                    crate::paper_trading::SignalAction::Buy { .. } => "Buy",
-                    crate::paper_trading::SignalAction::Sell { .. } => "Sell",
-                    crate::paper_trading::SignalAction::Hold => "Hold",
-                    crate::paper_trading::SignalAction::Close { .. } => "Close",
-                };
-                *distribution.entry(action_type.to_string()).or_insert(0) += 1;
-                *regimes.entry(signal.metadata.market_regime.clone()).or_insert(0) += 1;
-            }
+    /// Update per-account statistics
+    pub fn update_account_metrics(&self, accounts: Vec<AccountStatistics>) {
+        *self.account_metrics.write() = accounts;
+    }
 
-            metrics.signal_distribution = distribution;
-            metrics.market_regimes = regimes;
+    /// Update what the accounts are scored on for the competition leaderboard
+    pub fn update_competitors(&self, competitors: Vec<Competitor>) {
+        *self.competitors.write() = competitors;
+    }
 
-            // Calculate signals per minute (last 10 minutes)
-            let ten_minutes_ago = Utc::now() - chrono::Duration::minutes(10);
-            let recent_signals = history.iter()
-                .filter(|_| true) // TODO: Add timestamp to TradingSignal
-                .count();
-            metrics.signals_per_minute = recent_signals as f64 / 10.0;
-        }
+    pub fn get_competitors(&self) -> Vec<Competitor> {
+        self.competitors.read().clone()
     }
 
     /// Update market data metrics
     pub fn update_market_data(&self, symbol: Symbol, price: f64) {
-        let mut market_data = self.market_metrics.write();
-        
-        // Calculate price change if we have previous data
-        let (price_change_24h, price_change_pct_24h) = if let Some(existing) = market_data.get(&symbol) {
-            let change = price - existing.price;
-            let change_pct = if existing.price > 0.0 {
-                (change / existing.price) * 100.0
-            } else {
-                0.0
-            };
-            (change, change_pct)
-        } else {
-            (0.0, 0.0)
-        };
-        
+        let now = self.clock.now();
+        self.market_stats.update(&symbol, price, 0.0, now);
+
+        // The 24h statistics are filled in when read
         let metric = MarketMetrics {
-            timestamp: Utc::now(),
+            timestamp: now,
             symbol: symbol.to_string(),
             price,
-            volume_24h: 1_500_000.0, // Simulated volume for demo
-            price_change_24h,
-            price_change_pct_24h,
-            volatility: (price_change_pct_24h.abs() * 0.5).min(5.0), // Estimate volatility from price change
-            last_update: Utc::now(),
+            volume_24h: 0.0,
+            high_24h: price,
+            low_24h: price,
+            price_change_24h: 0.0,
+            price_change_pct_24h: 0.0,
+            volatility: 0.0,
+            last_update: now,
         };
-        
-        market_data.insert(symbol, metric);
+        self.market_metrics.write().insert(symbol, metric);
+    }
+
+    /// Record volume traded in a symbol at its latest price; ignored until
+    /// the symbol has a price
+    pub fn record_market_volume(&self, symbol: &Symbol, volume: f64) {
+        let Some(price) = self.market_metrics.read().get(symbol).map(|m| m.price) else { return };
+        self.market_stats.update(symbol, price, volume, self.clock.now());
+    }
+
+    /// Market metrics of every symbol with their rolling 24h statistics
+    pub fn get_market_metrics(&self) -> Vec<MarketMetrics> {
+        self.market_metrics
+            .read()
+            .iter()
+            .map(|(symbol, metric)| {
+                let mut metric = metric.clone();
+                if let Some(stats) = self.market_stats.stats(symbol) {
+                    metric.volume_24h = stats.volume_24h;
+                    (metric.high_24h, metric.low_24h) = (stats.high_24h, stats.low_24h);
+                    metric.price_change_24h = metric.price - stats.open_24h;
+                    metric.price_change_pct_24h = stats.change_pct_24h;
+                    metric.volatility = stats.volatility_pct;
+                }
+                metric
+            })
+            .collect()
     }
 
     /// Get all current metrics for Grafana
     pub fn get_all_metrics(&self) -> TradingMetrics {
         TradingMetrics {
             portfolio: self.portfolio_metrics.read().clone(),
-            signals: self.signal_metrics.read().clone(),
+            signals: self.get_signal_metrics(),
             positions: self.position_metrics.read().clone(),
-            market_data: self.market_metrics.read().values().cloned().collect(),
+            market_data: self.get_market_metrics(),
             risk: self.risk_metrics.read().clone(),
         }
     }
@@ -311,9 +636,199 @@ impl MetricsCollector {
         self.portfolio_metrics.read().clone()
     }
 
-    /// Get signal metrics only  
+    /// Get per-account statistics with their consolidated total
+    pub fn get_account_metrics(&self) -> AccountMetrics {
+        let accounts = self.account_metrics.read().clone();
+        AccountMetrics {
+            timestamp: self.clock.now(),
+            consolidated: AccountStatistics::consolidate(&accounts),
+            accounts,
+        }
+    }
+
+    /// Update the P&L attribution of every account
+    pub fn update_attribution_metrics(&self, accounts: Vec<AccountAttribution>) {
+        *self.attribution.write() = accounts;
+    }
+
+    /// Get the P&L attribution per account and consolidated
+    pub fn get_attribution_metrics(&self) -> AttributionMetrics {
+        let accounts = self.attribution.read().clone();
+        let attributions: Vec<PnlAttribution> = accounts.iter().map(|a| a.attribution.clone()).collect();
+        AttributionMetrics {
+            timestamp: self.clock.now(),
+            consolidated: PnlAttribution::merge(&attributions),
+            accounts,
+        }
+    }
+
+    /// Get rolling-window statistics
+    pub fn get_rolling_metrics(&self) -> RollingMetrics {
+        RollingMetrics {
+            timestamp: self.clock.now(),
+            windows: self.rolling_metrics.read().clone(),
+        }
+    }
+
+    /// Get signal and order event queue metrics
+    pub fn get_queue_metrics(&self) -> QueueMetrics {
+        self.queue_metrics.read().clone()
+    }
+
+    /// Record the latest metrics of an exchange's market data stream
+    pub fn update_stream_metrics(&self, exchange: Exchange, metrics: &StreamMetrics) {
+        self.stream_latency
+            .write()
+            .insert(format!("{:?}", exchange), metrics.latency.clone());
+    }
+
+    /// Record whether an account's engine is running
+    pub fn update_engine_running(&self, account: &str, running: bool) {
+        self.engines_running.write().insert(account.to_string(), running);
+    }
+
+    pub fn get_engines_running(&self) -> BTreeMap<String, bool> {
+        self.engines_running.read().clone()
+    }
+
+    /// Record an exchange stream's connection and how long ago it last delivered data
+    pub fn update_feed_status(&self, exchange: Exchange, connection: ConnectionStatus, last_data_age: Option<std::time::Duration>) {
+        let now = self.clock.now();
+        let last_data = last_data_age.and_then(|age| chrono::Duration::from_std(age).ok()).map(|age| now - age);
+        self.feed_status.write().insert(format!("{:?}", exchange), FeedStatus { connection, last_data });
+    }
+
+    pub fn get_feed_status(&self) -> BTreeMap<String, FeedStatus> {
+        self.feed_status.read().clone()
+    }
+
+    /// Record the latest cross-venue best bid and offer of every instrument
+    pub fn update_composite_quotes(&self, quotes: Vec<CompositeQuote>) {
+        *self.composite_quotes.write() = quotes;
+    }
+
+    /// Get the cross-venue best bid and offer and each venue's contribution
+    pub fn get_quote_metrics(&self) -> QuoteMetrics {
+        QuoteMetrics {
+            timestamp: self.clock.now(),
+            symbols: self.composite_quotes.read().clone(),
+        }
+    }
+
+    /// Symbols with a price, and when the newest price arrived
+    pub fn get_market_data_freshness(&self) -> (usize, Option<DateTime<Utc>>) {
+        let market = self.market_metrics.read();
+        (market.len(), market.values().map(|m| m.last_update).max())
+    }
+
+    /// Clock the metrics are timestamped from
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    /// Get market data stream latency and clock skew
+    pub fn get_stream_metrics(&self) -> StreamLatencyMetrics {
+        StreamLatencyMetrics {
+            timestamp: self.clock.now(),
+            streams: self.stream_latency.read().clone(),
+        }
+    }
+
+    /// Record a closed trade against its signal's confidence. Not needed when
+    /// the calibration is shared with `Accounts`, which records its own outcomes.
+    pub fn record_trade_outcome(&self, outcome: &TradeOutcome) {
+        self.calibration.record_outcome(outcome);
+    }
+
+    /// Get the realized win rate of each confidence decile
+    pub fn get_calibration_metrics(&self) -> CalibrationMetrics {
+        CalibrationMetrics {
+            timestamp: self.clock.now(),
+            buckets: self.calibration.table(),
+        }
+    }
+
+    /// Get the fill latency, signal-to-order latency, trade P&L and position
+    /// duration histograms
+    pub fn get_histogram_metrics(&self) -> HistogramMetrics {
+        self.histograms.snapshot(self.clock.now())
+    }
+
+    /// Portfolio and signal totals, venue contributions to the best bid and
+    /// offer, and the histograms in the Prometheus text format
+    pub fn prometheus_metrics(&self) -> String {
+        let portfolio = self.get_portfolio_metrics();
+        let signals = self.get_signal_metrics();
+        let currency = format!("{{currency=\"{}\"}}", portfolio.currency);
+        let gauges = [
+            ("neuromorphic_capital", "gauge", "Total capital", currency.as_str(), portfolio.total_capital),
+            ("neuromorphic_pnl", "gauge", "Total P&L", currency.as_str(), portfolio.total_pnl),
+            ("neuromorphic_open_positions", "gauge", "Open positions", "", portfolio.active_positions_count as f64),
+            ("neuromorphic_trades_total", "counter", "Closed trades", "", portfolio.total_trades as f64),
+            ("neuromorphic_signals_processed_total", "counter", "Signals processed", "", signals.signals_processed as f64),
+            ("neuromorphic_signals_throttled_total", "counter", "Signals dropped by the throttle", "", signals.signals_throttled as f64),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, labels, value) in gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+        {
+            let quotes = self.composite_quotes.read();
+            for (name, help, ask) in [
+                ("neuromorphic_venue_best_bid_total", "Updates after which the venue had the best bid", false),
+                ("neuromorphic_venue_best_ask_total", "Updates after which the venue had the best ask", true),
+            ] {
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+                let _ = writeln!(out, "# TYPE {} counter", name);
+                for quote in quotes.iter() {
+                    for venue in &quote.venues {
+                        let count = if ask { venue.at_best_ask } else { venue.at_best_bid };
+                        let _ = writeln!(out, "{}{{symbol=\"{}\",exchange=\"{:?}\"}} {}", name, quote.symbol, venue.exchange, count);
+                    }
+                }
+            }
+        }
+        self.get_histogram_metrics().write_prometheus(&mut out);
+        out
+    }
+
+    /// Update the what-if scenario results of the open portfolio
+    pub fn update_scenario_analysis(&self, report: ScenarioReport) {
+        *self.scenarios.write() = report;
+    }
+
+    /// Get the latest what-if scenario results
+    pub fn get_scenario_analysis(&self) -> ScenarioReport {
+        self.scenarios.read().clone()
+    }
+
+    /// Get signal metrics only; averages and counts cover the retained signals
     pub fn get_signal_metrics(&self) -> SignalMetrics {
-        self.signal_metrics.read().clone()
+        let window = self.signal_window.lock();
+        let len = window.signals.len().max(1) as f64;
+        SignalMetrics {
+            timestamp: self.clock.now(),
+            signals_processed: self.signals_processed.load(Ordering::Relaxed),
+            signals_throttled: self.signals_throttled.load(Ordering::Relaxed),
+            opportunities_suppressed: self.opportunities_suppressed.load(Ordering::Relaxed),
+            opportunities_skipped: self.opportunities_skipped.read().clone(),
+            signals_per_minute: window.signals_per_minute(),
+            avg_confidence: window.confidence / len,
+            avg_urgency: window.urgency / len,
+            signal_distribution: window.actions.clone(),
+            pattern_strength_avg: window.pattern_strength / len,
+            spike_count_avg: window.spike_count / len,
+            volatility_avg: window.volatility / len,
+            market_regimes: window.regimes.clone(),
+        }
+    }
+
+    /// Get the retained signal history, oldest first
+    pub fn get_signal_history(&self) -> Vec<TradingSignal> {
+        self.signal_window.lock().signals.iter().cloned().collect()
     }
 }
 
@@ -321,4 +836,63 @@ impl Default for MetricsCollector {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper_trading::{SignalAction, SignalMetadata};
+
+    fn signal(action: SignalAction, confidence: f64, regime: &str) -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new("BTCUSDT"),
+            exchange: Exchange::Binance,
+            action,
+            confidence,
+            urgency: 0.5,
+            metadata: SignalMetadata { market_regime: regime.to_string(), ..SignalMetadata::default() },
+        }
+    }
+
+    #[test]
+    fn test_signal_totals_follow_the_window() {
+        let collector = MetricsCollector::new();
+        collector.record_signal(&signal(SignalAction::Sell { size_hint: None }, 0.0, "downtrend"));
+        for _ in 0..SIGNAL_WINDOW {
+            collector.record_signal(&signal(SignalAction::Buy { size_hint: None }, 0.8, "uptrend"));
+        }
+
+        // The first signal has left the window and every total with it
+        let metrics = collector.get_signal_metrics();
+        assert_eq!(metrics.signals_processed, SIGNAL_WINDOW as u64 + 1);
+        assert!((metrics.avg_confidence - 0.8).abs() < 1e-9);
+        assert_eq!(metrics.signal_distribution, HashMap::from([("Buy".to_string(), SIGNAL_WINDOW as u64)]));
+        assert_eq!(metrics.market_regimes.get("downtrend"), None);
+        assert_eq!(collector.get_signal_history().len(), SIGNAL_WINDOW);
+    }
+
+    #[test]
+    fn test_market_metrics_cover_the_last_24_hours() {
+        let clock = Arc::new(crate::paper_trading::SimulatedClock::new(1_700_000_000_000));
+        let collector = MetricsCollector::with_clock(clock.clone());
+        let symbol = Symbol::new("BTCUSDT");
+        collector.record_market_volume(&symbol, 99.0); // No price yet
+
+        // A day and a half of minutes, each trading one unit
+        for minute in 0..2160 {
+            let price = if minute == 100 { 200.0 } else { 100.0 + (minute % 10) as f64 };
+            collector.update_market_data(symbol.clone(), price);
+            collector.record_market_volume(&symbol, 1.0);
+            clock.advance(std::time::Duration::from_secs(60));
+        }
+
+        let metrics = collector.get_market_metrics();
+        let btc = &metrics[0];
+        assert_eq!((btc.price, btc.volume_24h), (109.0, 1440.0));
+        // The spike left the window with the first half day
+        assert_eq!((btc.high_24h, btc.low_24h), (109.0, 100.0));
+        assert_eq!(btc.price_change_24h, 9.0);
+        assert!((btc.price_change_pct_24h - 9.0).abs() < 1e-9);
+        assert!(btc.volatility > 0.0);
+        assert_eq!(collector.get_all_metrics().market_data[0].volume_24h, 1440.0);
+    }
+}
@@ -0,0 +1,394 @@
+//! Metric time series that outlive the process
+//!
+//! Grafana draws `/api/v1/timeseries` from samples of the portfolio and
+//! signal metrics, taken as the portfolio metrics update, at most one per
+//! `interval_secs`, and kept for `retention_hours`. Held in memory only, the
+//! graphs would go blank on every restart, so with `dir` set the samples are
+//! also written there and the ones within the retention loaded back on start:
+//!
+//! - `format = "jsonl"` appends each sample to `metrics-<date>.jsonl`, one
+//!   file per UTC day
+//! - `format = "parquet"`, in builds with the `parquet` feature, writes every
+//!   `PARQUET_BATCH` samples as a part named after the times of its first and
+//!   last sample, like the kline cache, and the rest when the system stops
+//!
+//! Files past the retention are left for the operator to archive or delete.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Samples written to each Parquet part
+#[cfg(feature = "parquet")]
+const PARQUET_BATCH: usize = 360;
+
+/// Portfolio and signal metrics at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub timestamp: DateTime<Utc>,
+    pub total_capital: f64,
+    pub total_pnl: f64,
+    pub total_return_pct: f64,
+    pub signals_processed: u64,
+    pub signals_per_minute: f64,
+    pub avg_confidence: f64,
+}
+
+/// How samples are stored in `dir`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesFormat {
+    #[default]
+    Jsonl,
+    Parquet, // Needs the `parquet` feature
+}
+
+/// `[metrics.series]` settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeriesConfig {
+    pub interval_secs: u64, // At most one sample per interval
+    pub retention_hours: u64, // Kept in memory and loaded on start
+    pub dir: Option<PathBuf>, // Persist samples here
+    pub format: SeriesFormat,
+}
+
+impl Default for SeriesConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            retention_hours: 24,
+            dir: None,
+            format: SeriesFormat::Jsonl,
+        }
+    }
+}
+
+/// Where samples are persisted
+enum Store {
+    Jsonl { dir: PathBuf, file: Option<(NaiveDate, File)> },
+    #[cfg(feature = "parquet")]
+    Parquet { dir: PathBuf, pending: Vec<MetricSample> },
+}
+
+/// Samples within the retention, oldest first
+pub struct MetricSeries {
+    samples: VecDeque<MetricSample>,
+    interval: chrono::Duration,
+    retention: chrono::Duration,
+    store: Option<Store>,
+}
+
+impl MetricSeries {
+    /// Series held in memory only
+    pub fn new(config: &SeriesConfig) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            interval: chrono::Duration::seconds(config.interval_secs as i64),
+            retention: chrono::Duration::hours(config.retention_hours as i64),
+            store: None,
+        }
+    }
+
+    /// Series persisted in `config.dir`, if set, with the samples stored
+    /// there since `now` minus the retention loaded
+    pub fn open(config: &SeriesConfig, now: DateTime<Utc>) -> Result<Self> {
+        let mut series = Self::new(config);
+        let Some(dir) = &config.dir else { return Ok(series) };
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create metrics directory {}", dir.display()))?;
+        let since = now - series.retention;
+        let (mut samples, store) = match config.format {
+            SeriesFormat::Jsonl => (load_jsonl(dir, since, now)?, Store::Jsonl { dir: dir.clone(), file: None }),
+            #[cfg(feature = "parquet")]
+            SeriesFormat::Parquet => (parquet::load(dir, since)?, Store::Parquet { dir: dir.clone(), pending: Vec::new() }),
+            #[cfg(not(feature = "parquet"))]
+            SeriesFormat::Parquet => anyhow::bail!("Parquet metric series need a build with the parquet feature"),
+        };
+        samples.sort_by_key(|sample| sample.timestamp);
+        series.samples = samples.into();
+        series.store = Some(store);
+        Ok(series)
+    }
+
+    /// Add a sample unless the last one is less than an interval older;
+    /// returns whether it was added
+    pub fn record(&mut self, sample: MetricSample) -> bool {
+        if self.samples.back().is_some_and(|last| sample.timestamp - last.timestamp < self.interval) {
+            return false;
+        }
+        let cutoff = sample.timestamp - self.retention;
+        while self.samples.front().is_some_and(|oldest| oldest.timestamp < cutoff) {
+            self.samples.pop_front();
+        }
+        if let Some(store) = &mut self.store {
+            store.write(&sample);
+        }
+        self.samples.push_back(sample);
+        true
+    }
+
+    /// Write samples still buffered for the store
+    pub fn flush(&mut self) {
+        if let Some(store) = &mut self.store {
+            store.flush();
+        }
+    }
+
+    /// Samples with timestamps in `[from, to]`, oldest first
+    pub fn range(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Vec<MetricSample> {
+        self.samples
+            .iter()
+            .filter(|s| from.is_none_or(|from| s.timestamp >= from) && to.is_none_or(|to| s.timestamp <= to))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+impl Drop for MetricSeries {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl Store {
+    /// Persist a sample; failures are logged, the sample stays in memory
+    fn write(&mut self, sample: &MetricSample) {
+        let written = match self {
+            Store::Jsonl { dir, file } => append_jsonl(dir, file, sample),
+            #[cfg(feature = "parquet")]
+            Store::Parquet { dir, pending } => {
+                pending.push(sample.clone());
+                if pending.len() < PARQUET_BATCH {
+                    return;
+                }
+                parquet::write(dir, &std::mem::take(pending))
+            }
+        };
+        if let Err(e) = written {
+            warn!(error = %format!("{:#}", e), "Failed to persist metric sample");
+        }
+    }
+
+    fn flush(&mut self) {
+        #[cfg(feature = "parquet")]
+        if let Store::Parquet { dir, pending } = self {
+            if let Err(e) = parquet::write(dir, &std::mem::take(pending)) {
+                warn!(error = %format!("{:#}", e), "Failed to persist metric samples");
+            }
+        }
+    }
+}
+
+fn jsonl_path(dir: &Path, day: NaiveDate) -> PathBuf {
+    dir.join(format!("metrics-{}.jsonl", day))
+}
+
+fn append_jsonl(dir: &Path, file: &mut Option<(NaiveDate, File)>, sample: &MetricSample) -> Result<()> {
+    let day = sample.timestamp.date_naive();
+    if file.as_ref().is_none_or(|(open, _)| *open != day) {
+        let path = jsonl_path(dir, day);
+        let opened = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        *file = Some((day, opened));
+    }
+    let (_, file) = file.as_mut().expect("opened above");
+    file.write_all(format!("{}\n", serde_json::to_string(sample)?).as_bytes())?;
+    Ok(())
+}
+
+/// Samples from `since` to `now` from the daily files; a line cut short by
+/// a crash is skipped
+fn load_jsonl(dir: &Path, since: DateTime<Utc>, now: DateTime<Utc>) -> Result<Vec<MetricSample>> {
+    let mut samples = Vec::new();
+    let mut day = since.date_naive();
+    while day <= now.date_naive() {
+        let path = jsonl_path(dir, day);
+        day = day.succ_opt().context("Date out of range")?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            match serde_json::from_str::<MetricSample>(&line) {
+                Ok(sample) if sample.timestamp >= since => samples.push(sample),
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, path = %path.display(), "Skipping unreadable metric sample"),
+            }
+        }
+    }
+    Ok(samples)
+}
+
+#[cfg(feature = "parquet")]
+mod parquet {
+    use super::MetricSample;
+    use anyhow::{bail, Context, Result};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type, UInt64Type};
+    use arrow_array::{ArrayRef, ArrowPrimitiveType, Float64Array, Int64Array, PrimitiveArray, RecordBatch, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use chrono::{DateTime, Utc};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::arrow::ArrowWriter;
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    fn schema() -> Arc<Schema> {
+        let float = |name: &str| Field::new(name, DataType::Float64, false);
+        Arc::new(Schema::new(vec![
+            Field::new("timestamp", DataType::Int64, false), // Unix milliseconds
+            float("total_capital"),
+            float("total_pnl"),
+            float("total_return_pct"),
+            Field::new("signals_processed", DataType::UInt64, false),
+            float("signals_per_minute"),
+            float("avg_confidence"),
+        ]))
+    }
+
+    /// Write samples as one part, appearing in one rename
+    pub(super) fn write(dir: &Path, samples: &[MetricSample]) -> Result<()> {
+        let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+            return Ok(());
+        };
+        let name = format!("{}-{}.parquet", first.timestamp.timestamp_millis(), last.timestamp.timestamp_millis());
+        let (path, partial) = (dir.join(&name), dir.join(format!(".{}.partial", name)));
+
+        let float = |value: fn(&MetricSample) -> f64| Arc::new(Float64Array::from_iter_values(samples.iter().map(value))) as ArrayRef;
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from_iter_values(samples.iter().map(|s| s.timestamp.timestamp_millis()))),
+            float(|s| s.total_capital),
+            float(|s| s.total_pnl),
+            float(|s| s.total_return_pct),
+            Arc::new(UInt64Array::from_iter_values(samples.iter().map(|s| s.signals_processed))),
+            float(|s| s.signals_per_minute),
+            float(|s| s.avg_confidence),
+        ];
+        let batch = RecordBatch::try_new(schema(), columns)?;
+        let file = File::create(&partial).with_context(|| format!("Failed to create {}", partial.display()))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        std::fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Samples since `since` from the parts that reach it
+    pub(super) fn load(dir: &Path, since: DateTime<Utc>) -> Result<Vec<MetricSample>> {
+        let since_ms = since.timestamp_millis();
+        let mut samples = Vec::new();
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("parquet") {
+                continue;
+            }
+            let last_ms = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.split_once('-')).map(|(_, last)| last.parse::<i64>());
+            let Some(Ok(last_ms)) = last_ms else {
+                bail!("Unexpected file {} in the metrics directory", path.display());
+            };
+            if last_ms < since_ms {
+                continue;
+            }
+            let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+            for batch in ParquetRecordBatchReaderBuilder::try_new(file)?.build()? {
+                let read = from_batch(&batch?).with_context(|| format!("Invalid metrics file {}", path.display()))?;
+                samples.extend(read.into_iter().filter(|s| s.timestamp >= since));
+            }
+        }
+        Ok(samples)
+    }
+
+    fn column<'a, T: ArrowPrimitiveType>(batch: &'a RecordBatch, name: &str) -> Result<&'a PrimitiveArray<T>> {
+        batch.column_by_name(name).and_then(|c| c.as_primitive_opt::<T>()).with_context(|| format!("Missing column {}", name))
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<MetricSample>> {
+        let timestamp = column::<Int64Type>(batch, "timestamp")?;
+        let (capital, pnl, return_pct) = (
+            column::<Float64Type>(batch, "total_capital")?,
+            column::<Float64Type>(batch, "total_pnl")?,
+            column::<Float64Type>(batch, "total_return_pct")?,
+        );
+        let signals = column::<UInt64Type>(batch, "signals_processed")?;
+        let (per_minute, confidence) = (column::<Float64Type>(batch, "signals_per_minute")?, column::<Float64Type>(batch, "avg_confidence")?);
+        (0..batch.num_rows())
+            .map(|i| {
+                Ok(MetricSample {
+                    timestamp: DateTime::from_timestamp_millis(timestamp.value(i)).context("Timestamp out of range")?,
+                    total_capital: capital.value(i),
+                    total_pnl: pnl.value(i),
+                    total_return_pct: return_pct.value(i),
+                    signals_processed: signals.value(i),
+                    signals_per_minute: per_minute.value(i),
+                    avg_confidence: confidence.value(i),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(secs: i64, pnl: f64) -> MetricSample {
+        MetricSample {
+            timestamp: DateTime::from_timestamp(1_735_689_600 + secs, 0).unwrap(),
+            total_capital: 10_000.0 + pnl,
+            total_pnl: pnl,
+            total_return_pct: pnl / 100.0,
+            signals_processed: secs as u64,
+            signals_per_minute: 1.5,
+            avg_confidence: 0.7,
+        }
+    }
+
+    #[test]
+    fn test_series_survives_a_restart() {
+        let mut formats = vec![SeriesFormat::Jsonl];
+        if cfg!(feature = "parquet") {
+            formats.push(SeriesFormat::Parquet);
+        }
+        for format in formats {
+            let dir = std::env::temp_dir().join(format!("metric-series-{:?}-{}", format, std::process::id()));
+            let config = SeriesConfig { interval_secs: 10, retention_hours: 1, dir: Some(dir.clone()), format };
+            let now = sample(0, 0.0).timestamp;
+            let mut series = MetricSeries::open(&config, now).unwrap();
+            // Across midnight, with one sample inside the interval of the last
+            for (secs, pnl) in [(-30, 1.0), (-25, 9.0), (-15, 2.0), (5, 3.0)] {
+                series.record(sample(secs, pnl));
+            }
+            assert_eq!(series.len(), 3);
+            drop(series);
+
+            let restarted = MetricSeries::open(&config, now + chrono::Duration::minutes(5)).unwrap();
+            let pnl: Vec<f64> = restarted.range(None, None).iter().map(|s| s.total_pnl).collect();
+            assert_eq!(pnl, [1.0, 2.0, 3.0], "{:?}", format);
+            assert_eq!(restarted.range(Some(sample(-15, 0.0).timestamp), None).len(), 2);
+
+            // Past the retention nothing is loaded
+            let later = MetricSeries::open(&config, now + chrono::Duration::hours(2)).unwrap();
+            assert!(later.is_empty());
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
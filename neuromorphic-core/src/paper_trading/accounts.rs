@@ -0,0 +1,408 @@
+//! Isolated paper trading accounts sharing one process
+//!
+//! Each account runs its own engine with its own capital, risk limits and
+//! positions. Signals pick their account through `SignalMetadata::account_id`;
+//! untagged signals go to the default account.
+
+use super::clock::{self, SharedClock};
+use super::calibration::ConfidenceCalibration;
+use super::outcomes::{OutcomePublisher, SignalResult, TradeOutcome};
+use super::snapshot::EngineSnapshot;
+use super::scoring::Competitor;
+use super::{ExecutionMode, ExecutionVenue, PaperTradingConfig, PaperTradingEngine, TradingSignal, TradingStatistics};
+use crate::exchanges::Symbol;
+use crate::market_scanner::PriceHistory;
+use crate::metrics::TradingHistograms;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Account that receives signals without an account id
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// Account id used for the consolidated totals
+pub const CONSOLIDATED_ACCOUNT: &str = "consolidated";
+
+/// Summary statistics for one account, or all accounts combined
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct AccountStatistics {
+    pub account_id: String,
+    #[serde(default)]
+    pub currency: String, // Of every amount below
+    pub initial_capital: f64,
+    pub capital: f64,
+    pub total_pnl: f64,
+    pub total_return_pct: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub commission: f64,
+    pub open_positions: u64,
+    pub winning_trades: u64,
+    pub losing_trades: u64,
+    pub win_rate: f64, // Percent of closed trades
+    pub max_drawdown: f64,
+    #[serde(default)]
+    pub sharpe_ratio: f64, // Of the account alone; zero in the consolidated total
+    pub signals_processed: u64,
+    pub signals_executed: u64,
+}
+
+impl AccountStatistics {
+    pub fn from_statistics(account_id: &str, initial_capital: f64, stats: &TradingStatistics) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            currency: stats.currency.clone(),
+            initial_capital,
+            capital: stats.capital,
+            total_pnl: stats.total_pnl,
+            total_return_pct: stats.total_return_pct,
+            realized_pnl: stats.position_stats.total_realized_pnl,
+            unrealized_pnl: stats.position_stats.total_unrealized_pnl,
+            commission: stats.position_stats.total_commission,
+            open_positions: stats.position_stats.open_positions,
+            winning_trades: stats.position_stats.winning_positions,
+            losing_trades: stats.position_stats.losing_positions,
+            win_rate: stats.position_stats.win_rate,
+            max_drawdown: stats.risk_metrics.max_drawdown,
+            sharpe_ratio: stats.risk_metrics.sharpe_ratio,
+            signals_processed: stats.signals_processed,
+            signals_executed: stats.signals_executed,
+        }
+    }
+
+    /// Combine accounts into one total. Amounts are summed as-is, so accounts
+    /// should share a reporting currency; drawdown is the worst of any account.
+    pub fn consolidate(accounts: &[AccountStatistics]) -> Self {
+        let mut total = Self {
+            account_id: CONSOLIDATED_ACCOUNT.to_string(),
+            currency: accounts.first().map(|a| a.currency.clone()).unwrap_or_default(),
+            ..Default::default()
+        };
+
+        for account in accounts {
+            total.initial_capital += account.initial_capital;
+            total.capital += account.capital;
+            total.total_pnl += account.total_pnl;
+            total.realized_pnl += account.realized_pnl;
+            total.unrealized_pnl += account.unrealized_pnl;
+            total.commission += account.commission;
+            total.open_positions += account.open_positions;
+            total.winning_trades += account.winning_trades;
+            total.losing_trades += account.losing_trades;
+            total.max_drawdown = total.max_drawdown.max(account.max_drawdown);
+            total.signals_processed += account.signals_processed;
+            total.signals_executed += account.signals_executed;
+        }
+
+        if total.initial_capital > 0.0 {
+            total.total_return_pct = total.total_pnl / total.initial_capital * 100.0;
+        }
+        let closed = total.winning_trades + total.losing_trades;
+        if closed > 0 {
+            total.win_rate = total.winning_trades as f64 / closed as f64 * 100.0;
+        }
+
+        total
+    }
+}
+
+/// Trading engines keyed by account id, the default account first
+pub struct Accounts {
+    accounts: Vec<(String, PaperTradingEngine)>,
+    clock: SharedClock,
+    outcomes: OutcomePublisher, // Shared by every account
+    calibration: Arc<ConfidenceCalibration>, // Fed by the outcomes of every account
+    histograms: Arc<TradingHistograms>, // Fed by every engine and the outcomes of every account
+    price_history: Option<Arc<PriceHistory>>, // For the ATR stops of every account
+}
+
+impl Accounts {
+    /// Create with only the default account
+    pub fn new(config: PaperTradingConfig) -> Self {
+        Self::with_clock(config, clock::system_clock())
+    }
+
+    /// Create with only the default account; every account runs off `clock`
+    pub fn with_clock(config: PaperTradingConfig, clock: SharedClock) -> Self {
+        let outcomes = OutcomePublisher::default();
+        let calibration = Arc::new(ConfidenceCalibration::default());
+        let histograms = Arc::new(TradingHistograms::default());
+        let mut engine = PaperTradingEngine::with_outcome_publisher(config, clock.clone(), outcomes.clone());
+        engine.risk_manager().set_confidence_calibration(calibration.clone());
+        engine.set_histograms(histograms.clone());
+        Self {
+            accounts: vec![(DEFAULT_ACCOUNT.to_string(), engine)],
+            clock,
+            outcomes,
+            calibration,
+            histograms,
+            price_history: None,
+        }
+    }
+
+    /// Add an account; it starts with the others, so add it before `start_all`
+    pub fn add(&mut self, id: impl Into<String>, config: PaperTradingConfig) -> Result<()> {
+        let id = id.into();
+        if id.trim().is_empty() || id == CONSOLIDATED_ACCOUNT {
+            bail!("Invalid account id '{}'", id);
+        }
+        if self.get(&id).is_some() {
+            bail!("Account '{}' already exists", id);
+        }
+        let mut engine = PaperTradingEngine::with_outcome_publisher(config, self.clock.clone(), self.outcomes.clone());
+        engine.risk_manager().set_confidence_calibration(self.calibration.clone());
+        engine.set_histograms(self.histograms.clone());
+        if let Some(history) = &self.price_history {
+            engine.set_price_history(history.clone());
+        }
+        self.accounts.push((id, engine));
+        Ok(())
+    }
+
+    /// Outcomes of positions closed in any account
+    pub fn subscribe_outcomes(&self) -> tokio::sync::broadcast::Receiver<TradeOutcome> {
+        self.outcomes.subscribe()
+    }
+
+    /// What became of each signal processed by any account
+    pub fn subscribe_signal_results(&self) -> tokio::sync::broadcast::Receiver<SignalResult> {
+        self.outcomes.subscribe_signals()
+    }
+
+    pub fn outcome_publisher(&self) -> &OutcomePublisher {
+        &self.outcomes
+    }
+
+    /// Win rate by signal confidence across all accounts, updated as positions
+    /// close once the accounts are started
+    pub fn confidence_calibration(&self) -> &Arc<ConfidenceCalibration> {
+        &self.calibration
+    }
+
+    /// Fill and signal-to-order latencies of every engine, and the P&L and
+    /// duration of positions closed in any account once the accounts are started
+    pub fn histograms(&self) -> &Arc<TradingHistograms> {
+        &self.histograms
+    }
+
+    pub fn default_engine(&self) -> &PaperTradingEngine {
+        &self.accounts[0].1
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PaperTradingEngine> {
+        self.accounts.iter().find(|(account, _)| account == id).map(|(_, engine)| engine)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|(id, _)| id.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PaperTradingEngine)> {
+        self.accounts.iter().map(|(id, engine)| (id.as_str(), engine))
+    }
+
+    /// The account a signal is tagged for, or the default account if untagged
+    pub fn route(&self, signal: &TradingSignal) -> Result<(&str, &PaperTradingEngine)> {
+        let id = signal.metadata.account_id.as_deref().unwrap_or(DEFAULT_ACCOUNT);
+        match self.iter().find(|(account, _)| *account == id) {
+            Some(account) => Ok(account),
+            None => bail!("Unknown account '{}' for {} signal", id, signal.symbol),
+        }
+    }
+
+    /// Attach a venue to every account using `mode`; returns how many use it
+    pub fn set_execution_venue(&mut self, mode: ExecutionMode, venue: Arc<dyn ExecutionVenue>) -> usize {
+        let mut attached = 0;
+        for (_, engine) in &mut self.accounts {
+            if engine.config().execution == mode {
+                engine.set_execution_venue(venue.clone());
+                attached += 1;
+            }
+        }
+        attached
+    }
+
+    /// Bars the ATR stops of every account, and of accounts added later, are
+    /// taken from; call before `start_all`
+    pub fn set_price_history(&mut self, history: Arc<PriceHistory>) {
+        for (_, engine) in &mut self.accounts {
+            engine.set_price_history(history.clone());
+        }
+        self.price_history = Some(history);
+    }
+
+    /// Execution modes in use, without duplicates
+    pub fn execution_modes(&self) -> Vec<ExecutionMode> {
+        let mut modes: Vec<ExecutionMode> = Vec::new();
+        for (_, engine) in &self.accounts {
+            if !modes.contains(&engine.config().execution) {
+                modes.push(engine.config().execution);
+            }
+        }
+        modes
+    }
+
+    pub async fn start_all(&mut self) -> Result<()> {
+        for (_, engine) in &mut self.accounts {
+            engine.start().await?;
+        }
+
+        // Runs until every engine and this set of accounts are dropped
+        let mut outcomes = self.outcomes.subscribe();
+        let calibration = self.calibration.clone();
+        let histograms = self.histograms.clone();
+        tokio::spawn(async move {
+            loop {
+                match outcomes.recv().await {
+                    Ok(outcome) => {
+                        calibration.record_outcome(&outcome);
+                        histograms.record_outcome(&outcome);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub async fn stop_all(&self) -> Result<()> {
+        for (_, engine) in &self.accounts {
+            engine.stop().await?;
+        }
+        Ok(())
+    }
+
+    /// Snapshot every account to `<dir>/<account>.json`. Each file is
+    /// replaced in one rename, so a crash mid-write leaves the previous one.
+    pub fn save_snapshots(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        for (id, engine) in &self.accounts {
+            let (path, partial) = (Self::snapshot_path(dir, id), dir.join(format!(".{}.json.partial", id)));
+            engine.snapshot().save(&partial)?;
+            std::fs::rename(&partial, &path).with_context(|| format!("Failed to replace snapshot {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Restore each account that has a snapshot in `dir`, before `start_all`;
+    /// returns the restored accounts. Accounts without one start afresh.
+    pub fn restore_snapshots(&self, dir: &Path) -> Result<Vec<String>> {
+        let mut restored = Vec::new();
+        for (id, engine) in &self.accounts {
+            let path = Self::snapshot_path(dir, id);
+            if path.exists() {
+                engine.restore(&EngineSnapshot::load(&path)?).with_context(|| format!("Failed to restore account '{}'", id))?;
+                restored.push(id.clone());
+            }
+        }
+        Ok(restored)
+    }
+
+    fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+        dir.join(format!("{}.json", id))
+    }
+
+    /// Prices are shared: every account sees every update
+    pub fn update_price(&self, symbol: &Symbol, price: f64) {
+        for (_, engine) in &self.accounts {
+            engine.update_price(symbol.clone(), price);
+        }
+    }
+
+    /// Like prices, traded volume is shared by every account
+    pub fn update_volume(&self, symbol: &Symbol, volume: f64) {
+        for (_, engine) in &self.accounts {
+            engine.update_volume(symbol, volume);
+        }
+    }
+
+    /// And so is the displayed book
+    pub fn update_book(&self, symbol: &Symbol, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        for (_, engine) in &self.accounts {
+            engine.update_book(symbol, bids, asks);
+        }
+    }
+
+    /// And the composite mark across venues
+    pub fn update_composite_price(&self, symbol: &Symbol, price: f64) {
+        for (_, engine) in &self.accounts {
+            engine.update_composite_price(symbol, price);
+        }
+    }
+
+    pub fn statistics(&self, id: &str) -> Option<AccountStatistics> {
+        self.get(id).map(|engine| Self::account_statistics(id, engine))
+    }
+
+    /// Statistics of every account, in the order they were added
+    pub fn all_statistics(&self) -> Vec<AccountStatistics> {
+        self.iter().map(|(id, engine)| Self::account_statistics(id, engine)).collect()
+    }
+
+    pub fn consolidated_statistics(&self) -> AccountStatistics {
+        AccountStatistics::consolidate(&self.all_statistics())
+    }
+
+    /// Every account as a competitor, to score them against each other
+    pub fn competitors(&self) -> Vec<Competitor> {
+        self.iter()
+            .map(|(id, engine)| Competitor::from_account(&Self::account_statistics(id, engine), &engine.returns_history()))
+            .collect()
+    }
+
+    fn account_statistics(id: &str, engine: &PaperTradingEngine) -> AccountStatistics {
+        AccountStatistics::from_statistics(id, engine.config().initial_capital, &engine.get_statistics())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::{SignalAction, SignalMetadata};
+
+    fn signal(account_id: Option<&str>) -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new("BTC-USD"),
+            exchange: Exchange::Binance,
+            action: SignalAction::Buy { size_hint: None },
+            confidence: 0.9,
+            urgency: 0.5,
+            metadata: SignalMetadata {
+                account_id: account_id.map(str::to_string),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_account_routing_and_consolidation() {
+        let mut accounts = Accounts::new(PaperTradingConfig::default());
+        let small = PaperTradingConfig {
+            initial_capital: 25_000.0,
+            ..Default::default()
+        };
+        accounts.add("momentum", small.clone()).unwrap();
+        assert!(accounts.add("momentum", small).is_err());
+
+        assert_eq!(accounts.route(&signal(None)).unwrap().0, DEFAULT_ACCOUNT);
+        assert_eq!(accounts.route(&signal(Some("momentum"))).unwrap().0, "momentum");
+        assert!(accounts.route(&signal(Some("missing"))).is_err());
+
+        let momentum = accounts.statistics("momentum").unwrap();
+        assert_eq!(momentum.capital, 25_000.0);
+
+        let total = accounts.consolidated_statistics();
+        assert_eq!(total.account_id, CONSOLIDATED_ACCOUNT);
+        assert_eq!(total.initial_capital, 125_000.0);
+        assert_eq!(total.capital, 125_000.0);
+        assert_eq!(total.currency, "USD");
+
+        // Win rates are percentages, like those of each account
+        let row = |winning_trades, losing_trades| AccountStatistics { winning_trades, losing_trades, ..Default::default() };
+        assert_eq!(AccountStatistics::consolidate(&[row(3, 1), row(1, 3)]).win_rate, 50.0);
+    }
+}
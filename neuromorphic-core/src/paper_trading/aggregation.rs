@@ -0,0 +1,456 @@
+//! Ensemble aggregation of signals from several prediction models
+//!
+//! Signals carrying `SignalMetadata::source_id` are held per symbol for a
+//! short window. When every expected source has voted, or the window runs
+//! out, the votes are combined under the configured policy into at most one
+//! signal for the engine. Each source's latest signal in a window is its
+//! vote; Hold votes count toward the quorum but for no direction.
+//!
+//! Close and scale signals, and signals without a source id, are not votes
+//! and pass straight through.
+
+use super::engine::{SignalAction, TradingSignal};
+use super::outcomes::TradeOutcome;
+use crate::exchanges::Symbol;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tracing::debug;
+
+/// How votes are combined
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationPolicy {
+    /// The direction with the most (weighted) votes, if it beats every other
+    /// direction including Hold
+    MajorityVote,
+    /// Sum of direction times confidence, weighted; acts when the magnitude
+    /// reaches `min_confidence`
+    ConfidenceWeighted,
+    /// Acts only if no source votes against the direction of the others
+    Veto,
+}
+
+/// Aggregation settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AggregatorConfig {
+    pub policy: AggregationPolicy,
+    /// How long votes for a symbol are collected after the first one
+    pub window_ms: u64,
+    /// Sources whose votes complete a window early; empty waits for the window
+    pub expected_sources: Vec<String>,
+    /// Fewest distinct sources a window needs to produce a signal
+    pub min_sources: usize,
+    /// Consolidated confidence below this is not forwarded
+    pub min_confidence: f64,
+    /// Vote weights by source; unlisted sources weigh 1
+    pub source_weights: HashMap<String, f64>,
+}
+
+impl Default for AggregatorConfig {
+    fn default() -> Self {
+        Self {
+            policy: AggregationPolicy::MajorityVote,
+            window_ms: 250,
+            expected_sources: Vec::new(),
+            min_sources: 1,
+            min_confidence: 0.0,
+            source_weights: HashMap::new(),
+        }
+    }
+}
+
+/// How one source's votes fared
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SourceStatistics {
+    pub source_id: String,
+    pub signals: u64,
+    pub agreed: u64,    // Votes in the direction that was forwarded
+    pub dissented: u64, // Votes against the forwarded or the winning direction
+    pub vetoes: u64,    // Windows this source blocked under the veto policy
+    /// Closed positions opened by consolidated signals this source agreed with
+    pub attributed_trades: u64,
+    pub attributed_pnl: f64, // Their full P&L, not a share
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AggregatorStatistics {
+    pub windows: u64,
+    pub forwarded: u64,
+    pub suppressed: u64, // No consensus, vetoed, too few sources or too little confidence
+    pub passed_through: u64,
+    pub sources: Vec<SourceStatistics>,
+}
+
+struct Window {
+    opened_ms: u64,
+    votes: BTreeMap<String, TradingSignal>, // Latest signal by source
+}
+
+#[derive(Default)]
+struct State {
+    windows: HashMap<Symbol, Window>,
+    sources: BTreeMap<String, SourceStatistics>,
+    contributors: HashMap<String, Vec<String>>, // Consolidated signal id -> agreeing sources
+    contributor_order: VecDeque<String>,
+    next_id: u64,
+    windows_closed: u64,
+    forwarded: u64,
+    suppressed: u64,
+    passed_through: u64,
+}
+
+/// Combines simultaneous signals for a symbol from several sources
+pub struct SignalAggregator {
+    config: AggregatorConfig,
+    state: Mutex<State>,
+}
+
+impl SignalAggregator {
+    /// Consolidated signals remembered for outcome attribution
+    const MAX_TRACKED_SIGNALS: usize = 10_000;
+
+    pub fn new(config: AggregatorConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn config(&self) -> &AggregatorConfig {
+        &self.config
+    }
+
+    /// Add a signal; returns the signals now ready for the engine, including
+    /// those of other symbols whose window has run out
+    pub fn submit(&self, signal: TradingSignal, now_ms: u64) -> Vec<TradingSignal> {
+        let mut ready = self.flush_expired(now_ms);
+        let mut state = self.state.lock();
+
+        let source = match (&signal.action, &signal.metadata.source_id) {
+            (SignalAction::Buy { .. } | SignalAction::Sell { .. } | SignalAction::Hold, Some(source)) => source.clone(),
+            _ => {
+                state.passed_through += 1;
+                ready.push(signal);
+                return ready;
+            }
+        };
+        state.sources.entry(source.clone()).or_insert_with(|| SourceStatistics {
+            source_id: source.clone(),
+            ..Default::default()
+        }).signals += 1;
+
+        let symbol = signal.symbol.clone();
+        let window = state.windows.entry(symbol.clone()).or_insert_with(|| Window {
+            opened_ms: now_ms,
+            votes: BTreeMap::new(),
+        });
+        window.votes.insert(source, signal);
+
+        let expected = &self.config.expected_sources;
+        let complete = !expected.is_empty() && expected.iter().all(|s| window.votes.contains_key(s));
+        if complete {
+            let window = state.windows.remove(&symbol).expect("window just updated");
+            ready.extend(self.close_window(&mut state, window));
+        }
+        ready
+    }
+
+    /// Close windows older than `window_ms`. Call periodically, since a window
+    /// whose sources never all vote only closes here or on a later `submit`.
+    pub fn flush_expired(&self, now_ms: u64) -> Vec<TradingSignal> {
+        let mut state = self.state.lock();
+        let expired: Vec<Symbol> = state
+            .windows
+            .iter()
+            .filter(|(_, w)| now_ms.saturating_sub(w.opened_ms) >= self.config.window_ms)
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        let mut ready = Vec::new();
+        for symbol in expired {
+            if let Some(window) = state.windows.remove(&symbol) {
+                ready.extend(self.close_window(&mut state, window));
+            }
+        }
+        ready
+    }
+
+    /// Credit a closed trade to the sources behind the signal that opened it
+    pub fn record_outcome(&self, outcome: &TradeOutcome) {
+        let Some(signal_id) = &outcome.signal_id else {
+            return;
+        };
+        let mut state = self.state.lock();
+        let Some(sources) = state.contributors.get(signal_id).cloned() else {
+            return;
+        };
+        for source in sources {
+            if let Some(stats) = state.sources.get_mut(&source) {
+                stats.attributed_trades += 1;
+                stats.attributed_pnl += outcome.pnl;
+            }
+        }
+    }
+
+    pub fn statistics(&self) -> AggregatorStatistics {
+        let state = self.state.lock();
+        AggregatorStatistics {
+            windows: state.windows_closed,
+            forwarded: state.forwarded,
+            suppressed: state.suppressed,
+            passed_through: state.passed_through,
+            sources: state.sources.values().cloned().collect(),
+        }
+    }
+
+    fn weight(&self, source: &str) -> f64 {
+        self.config.source_weights.get(source).copied().unwrap_or(1.0).max(0.0)
+    }
+
+    fn direction(signal: &TradingSignal) -> i8 {
+        match signal.action {
+            SignalAction::Buy { .. } => 1,
+            SignalAction::Sell { .. } => -1,
+            _ => 0,
+        }
+    }
+
+    /// Combine a window's votes into a direction and confidence, or `None` to suppress
+    fn decide(&self, votes: &BTreeMap<String, TradingSignal>) -> (Option<(i8, f64)>, Vec<String>) {
+        let weighted = |direction: i8| -> (f64, f64) {
+            votes
+                .iter()
+                .filter(|(_, s)| Self::direction(s) == direction)
+                .fold((0.0, 0.0), |(w, c), (source, s)| {
+                    let weight = self.weight(source);
+                    (w + weight, c + weight * s.confidence)
+                })
+        };
+        let total_weight: f64 = votes.keys().map(|s| self.weight(s)).sum();
+        if total_weight <= 0.0 {
+            return (None, Vec::new());
+        }
+
+        match self.config.policy {
+            AggregationPolicy::MajorityVote => {
+                let tallies = [1, -1, 0].map(|d| (d, weighted(d)));
+                let (direction, (weight, confidence_sum)) = tallies
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+                    .expect("three tallies");
+                let clear = tallies.iter().filter(|(_, (w, _))| *w >= weight).count() == 1;
+                if !clear || direction == 0 {
+                    return (None, Vec::new());
+                }
+                (Some((direction, confidence_sum / weight * weight / total_weight)), Vec::new())
+            }
+            AggregationPolicy::ConfidenceWeighted => {
+                let (up, down) = (weighted(1).1, weighted(-1).1);
+                let score = (up - down) / total_weight;
+                if score == 0.0 {
+                    return (None, Vec::new());
+                }
+                (Some((score.signum() as i8, score.abs())), Vec::new())
+            }
+            AggregationPolicy::Veto => {
+                let (up, down) = (weighted(1), weighted(-1));
+                let direction = match (up.0 > 0.0, down.0 > 0.0) {
+                    (true, false) => 1,
+                    (false, true) => -1,
+                    (false, false) => return (None, Vec::new()),
+                    (true, true) => {
+                        // Whoever is outnumbered blocks the rest
+                        let minority = if up.0 < down.0 { 1 } else { -1 };
+                        let vetoers = votes
+                            .iter()
+                            .filter(|(_, s)| Self::direction(s) == minority)
+                            .map(|(source, _)| source.clone())
+                            .collect();
+                        return (None, vetoers);
+                    }
+                };
+                let (weight, confidence_sum) = if direction == 1 { up } else { down };
+                (Some((direction, confidence_sum / weight)), Vec::new())
+            }
+        }
+    }
+
+    fn close_window(&self, state: &mut State, window: Window) -> Option<TradingSignal> {
+        state.windows_closed += 1;
+        let votes = window.votes;
+        let (decision, vetoers) = self.decide(&votes);
+        for source in &vetoers {
+            if let Some(stats) = state.sources.get_mut(source) {
+                stats.vetoes += 1;
+            }
+        }
+
+        let decision = decision.filter(|&(_, confidence)| {
+            votes.len() >= self.config.min_sources && confidence >= self.config.min_confidence
+        });
+        let Some((direction, confidence)) = decision else {
+            state.suppressed += 1;
+            let symbol = votes.values().next().map(|s| s.symbol.to_string()).unwrap_or_default();
+            debug!(symbol = %symbol, sources = votes.len(), "Ensemble signal suppressed");
+            return None;
+        };
+
+        let agreeing: Vec<(&String, &TradingSignal)> = votes.iter().filter(|(_, s)| Self::direction(s) == direction).collect();
+        for (source, signal) in &votes {
+            let vote = Self::direction(signal);
+            if let Some(stats) = state.sources.get_mut(source) {
+                if vote == direction {
+                    stats.agreed += 1;
+                } else if vote == -direction {
+                    stats.dissented += 1;
+                }
+            }
+        }
+
+        // The most confident agreeing signal supplies exchange and metadata
+        let (_, lead) = agreeing
+            .iter()
+            .max_by(|a, b| a.1.confidence.total_cmp(&b.1.confidence))
+            .expect("a direction was chosen, so someone voted for it");
+        let hints: Vec<f64> = agreeing
+            .iter()
+            .filter_map(|(_, s)| match s.action {
+                SignalAction::Buy { size_hint } | SignalAction::Sell { size_hint } => size_hint,
+                _ => None,
+            })
+            .collect();
+        let size_hint = (!hints.is_empty()).then(|| hints.iter().sum::<f64>() / hints.len() as f64);
+
+        state.next_id += 1;
+        let signal_id = format!("ensemble-{}", state.next_id);
+        let mut metadata = lead.metadata.clone();
+        metadata.source_id = None;
+        metadata.signal_id = Some(signal_id.clone());
+
+        state.contributors.insert(signal_id.clone(), agreeing.iter().map(|(source, _)| (*source).clone()).collect());
+        state.contributor_order.push_back(signal_id);
+        if state.contributor_order.len() > Self::MAX_TRACKED_SIGNALS {
+            if let Some(oldest) = state.contributor_order.pop_front() {
+                state.contributors.remove(&oldest);
+            }
+        }
+        state.forwarded += 1;
+
+        Some(TradingSignal {
+            symbol: lead.symbol.clone(),
+            exchange: lead.exchange,
+            action: if direction > 0 { SignalAction::Buy { size_hint } } else { SignalAction::Sell { size_hint } },
+            confidence: confidence.clamp(0.0, 1.0),
+            urgency: agreeing.iter().map(|(_, s)| s.urgency).fold(0.0, f64::max),
+            metadata,
+        })
+    }
+}
+
+impl Default for SignalAggregator {
+    fn default() -> Self {
+        Self::new(AggregatorConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side};
+    use crate::paper_trading::{ExitReason, SignalMetadata};
+    use std::time::Duration;
+
+    fn vote(source: &str, action: SignalAction, confidence: f64) -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new("BTC-USD"),
+            exchange: Exchange::Binance,
+            action,
+            confidence,
+            urgency: 0.5,
+            metadata: SignalMetadata {
+                source_id: Some(source.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn buy(source: &str, confidence: f64) -> TradingSignal {
+        vote(source, SignalAction::Buy { size_hint: Some(1000.0) }, confidence)
+    }
+
+    fn sell(source: &str, confidence: f64) -> TradingSignal {
+        vote(source, SignalAction::Sell { size_hint: None }, confidence)
+    }
+
+    fn run(config: AggregatorConfig, votes: Vec<TradingSignal>) -> (Option<TradingSignal>, SignalAggregator) {
+        let aggregator = SignalAggregator::new(config);
+        let mut ready = Vec::new();
+        for v in votes {
+            ready.extend(aggregator.submit(v, 0));
+        }
+        ready.extend(aggregator.flush_expired(1_000));
+        assert!(ready.len() <= 1);
+        (ready.pop(), aggregator)
+    }
+
+    #[test]
+    fn test_aggregation_policies_and_attribution() {
+        let votes = || vec![buy("lstm", 0.9), buy("snn", 0.6), sell("gbm", 0.8)];
+
+        // Majority: two buys beat one sell
+        let (signal, aggregator) = run(AggregatorConfig::default(), votes());
+        let signal = signal.unwrap();
+        assert!(matches!(signal.action, SignalAction::Buy { size_hint: Some(h) } if h == 1000.0));
+        assert!((signal.confidence - 0.5).abs() < 1e-9); // Mean 0.75 of the winners, 2 of 3 votes
+        assert_eq!(signal.metadata.source_id, None);
+
+        // Outcomes are credited to the agreeing sources only
+        aggregator.record_outcome(&TradeOutcome {
+            signal_id: signal.metadata.signal_id.clone(),
+            position_id: "POS_1".to_string(),
+            symbol: Symbol::new("BTC-USD"),
+            exchange: Exchange::Binance,
+            side: Side::Buy,
+            strategy: None,
+            confidence: Some(signal.confidence),
+            pnl: 25.0,
+            return_pct: 2.5,
+            holding_time: Duration::from_secs(60),
+            exit_reason: ExitReason::TakeProfit,
+            entry_price: 100.0,
+            exit_price: 102.5,
+            closed_at: 60_000,
+        });
+        let stats = aggregator.statistics();
+        let source = |id: &str| stats.sources.iter().find(|s| s.source_id == id).unwrap().clone();
+        assert_eq!((source("lstm").agreed, source("lstm").attributed_pnl), (1, 25.0));
+        assert_eq!((source("gbm").dissented, source("gbm").attributed_trades), (1, 0));
+
+        // Confidence-weighted: (0.9 + 0.6 - 0.8) / 3, under a 0.3 floor
+        let weighted = AggregatorConfig { policy: AggregationPolicy::ConfidenceWeighted, ..Default::default() };
+        let (signal, _) = run(weighted.clone(), votes());
+        assert!((signal.unwrap().confidence - 0.7 / 3.0).abs() < 1e-9);
+        let (signal, aggregator) = run(AggregatorConfig { min_confidence: 0.3, ..weighted }, votes());
+        assert!(signal.is_none());
+        assert_eq!(aggregator.statistics().suppressed, 1);
+
+        // Veto: the lone seller blocks the buyers; unanimous votes go through
+        let veto = AggregatorConfig { policy: AggregationPolicy::Veto, ..Default::default() };
+        let (signal, aggregator) = run(veto.clone(), votes());
+        assert!(signal.is_none());
+        assert_eq!(aggregator.statistics().sources.iter().find(|s| s.source_id == "gbm").unwrap().vetoes, 1);
+        let (signal, _) = run(veto, vec![buy("lstm", 0.9), buy("snn", 0.7), vote("gbm", SignalAction::Hold, 0.5)]);
+        assert!((signal.unwrap().confidence - 0.8).abs() < 1e-9);
+
+        // A window closes as soon as every expected source has voted; closes pass through
+        let aggregator = SignalAggregator::new(AggregatorConfig {
+            expected_sources: vec!["lstm".to_string(), "snn".to_string()],
+            ..Default::default()
+        });
+        assert!(aggregator.submit(buy("lstm", 0.9), 0).is_empty());
+        assert_eq!(aggregator.submit(buy("snn", 0.8), 10).len(), 1);
+        assert_eq!(aggregator.submit(vote("lstm", SignalAction::Close { position_id: None }, 0.9), 20).len(), 1);
+    }
+}
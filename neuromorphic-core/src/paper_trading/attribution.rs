@@ -0,0 +1,201 @@
+//! P&L attribution
+//!
+//! Splits P&L by symbol, by exchange and by the UTC hour of day and day of
+//! week positions were opened in, to see where and when a strategy makes or
+//! loses money. Realized P&L is added up as positions close, so the cost does
+//! not grow with the number of closed positions; unrealized P&L comes from
+//! the open positions when the attribution is read.
+
+use super::position_manager::{Position, PositionStatus};
+use crate::exchanges::Symbol;
+use chrono::{DateTime, Datelike, Timelike};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// P&L of one symbol, exchange or time bucket, in the reporting currency
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PnlBucket {
+    pub key: String, // Symbol, exchange, hour ("00" to "23") or weekday ("Mon" to "Sun")
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub closed_trades: u64,
+    pub winning_trades: u64,
+}
+
+/// P&L broken down four ways; each breakdown adds up to the same totals
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PnlAttribution {
+    pub by_symbol: Vec<PnlBucket>,   // Sorted by key
+    pub by_exchange: Vec<PnlBucket>, // Sorted by key
+    pub by_hour: Vec<PnlBucket>,     // All 24 hours, by entry time in UTC
+    pub by_weekday: Vec<PnlBucket>,  // Monday first, by entry time in UTC
+}
+
+impl PnlAttribution {
+    /// Sum of several attributions, e.g. of all accounts
+    pub fn merge(attributions: &[PnlAttribution]) -> PnlAttribution {
+        let merge = |rows: &dyn Fn(&PnlAttribution) -> &Vec<PnlBucket>| {
+            let mut merged: Vec<PnlBucket> = Vec::new();
+            for row in attributions.iter().flat_map(|a| rows(a).iter()) {
+                match merged.iter_mut().find(|m| m.key == row.key) {
+                    Some(m) => {
+                        m.realized_pnl += row.realized_pnl;
+                        m.unrealized_pnl += row.unrealized_pnl;
+                        m.closed_trades += row.closed_trades;
+                        m.winning_trades += row.winning_trades;
+                    }
+                    None => merged.push(row.clone()),
+                }
+            }
+            merged
+        };
+        let mut by_symbol = merge(&|a| &a.by_symbol);
+        by_symbol.sort_by(|a, b| a.key.cmp(&b.key));
+        let mut by_exchange = merge(&|a| &a.by_exchange);
+        by_exchange.sort_by(|a, b| a.key.cmp(&b.key));
+        PnlAttribution {
+            by_symbol,
+            by_exchange,
+            by_hour: merge(&|a| &a.by_hour),
+            by_weekday: merge(&|a| &a.by_weekday),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Totals {
+    realized_cents: i64,
+    closed: u64,
+    winning: u64,
+}
+
+#[derive(Default)]
+struct Breakdown {
+    by_symbol: BTreeMap<String, Totals>,
+    by_exchange: BTreeMap<String, Totals>,
+    by_hour: [Totals; 24],
+    by_weekday: [Totals; 7],
+}
+
+/// Running realized P&L per symbol, exchange, hour and weekday
+#[derive(Default)]
+pub(crate) struct PnlAttributor {
+    breakdown: Mutex<Breakdown>,
+}
+
+impl PnlAttributor {
+    /// Add realized P&L of `position`, counting a trade if it is now closed
+    pub fn record(&self, position: &Position, realized_cents: i64) {
+        let closed = position.status == PositionStatus::Closed;
+        let winning = closed && position.realized_pnl > 0.0;
+        let (hour, weekday) = entry_buckets(position);
+        let mut breakdown = self.breakdown.lock();
+        let Breakdown { by_symbol, by_exchange, by_hour, by_weekday } = &mut *breakdown;
+        for totals in [
+            by_symbol.entry(position.symbol.0.clone()).or_default(),
+            by_exchange.entry(position.exchange.to_string()).or_default(),
+            &mut by_hour[hour],
+            &mut by_weekday[weekday],
+        ] {
+            totals.realized_cents += realized_cents;
+            totals.closed += closed as u64;
+            totals.winning += winning as u64;
+        }
+    }
+
+    /// Start over from the realized P&L of `positions`
+    pub fn rebuild(&self, positions: &[Position], to_cents: impl Fn(&Symbol, f64) -> i64) {
+        self.reset();
+        for position in positions.iter().filter(|p| p.realized_pnl != 0.0 || p.status == PositionStatus::Closed) {
+            self.record(position, to_cents(&position.symbol, position.realized_pnl));
+        }
+    }
+
+    pub fn reset(&self) {
+        *self.breakdown.lock() = Breakdown::default();
+    }
+
+    /// The attribution, with the unrealized P&L of `open` converted by `to_reporting`
+    pub fn attribution(&self, open: &[Position], to_reporting: impl Fn(&Symbol, f64) -> f64) -> PnlAttribution {
+        let breakdown = self.breakdown.lock();
+        let row = |key: String, totals: &Totals| PnlBucket {
+            key,
+            realized_pnl: totals.realized_cents as f64 / 100.0,
+            unrealized_pnl: 0.0,
+            closed_trades: totals.closed,
+            winning_trades: totals.winning,
+        };
+        let mut attribution = PnlAttribution {
+            by_symbol: breakdown.by_symbol.iter().map(|(k, t)| row(k.clone(), t)).collect(),
+            by_exchange: breakdown.by_exchange.iter().map(|(k, t)| row(k.clone(), t)).collect(),
+            by_hour: breakdown.by_hour.iter().enumerate().map(|(h, t)| row(format!("{:02}", h), t)).collect(),
+            by_weekday: breakdown.by_weekday.iter().zip(WEEKDAYS).map(|(t, day)| row(day.to_string(), t)).collect(),
+        };
+        drop(breakdown);
+
+        for position in open {
+            let unrealized = to_reporting(&position.symbol, position.unrealized_pnl);
+            let (hour, weekday) = entry_buckets(position);
+            let keyed = |rows: &mut Vec<PnlBucket>, key: String| match rows.binary_search_by(|r| r.key.cmp(&key)) {
+                Ok(i) => rows[i].unrealized_pnl += unrealized,
+                Err(i) => rows.insert(i, PnlBucket { key, unrealized_pnl: unrealized, ..PnlBucket::default() }),
+            };
+            keyed(&mut attribution.by_symbol, position.symbol.0.clone());
+            keyed(&mut attribution.by_exchange, position.exchange.to_string());
+            attribution.by_hour[hour].unrealized_pnl += unrealized;
+            attribution.by_weekday[weekday].unrealized_pnl += unrealized;
+        }
+        attribution
+    }
+}
+
+/// Hour of day and weekday (Monday = 0) of a position's entry, in UTC
+fn entry_buckets(position: &Position) -> (usize, usize) {
+    let entry = DateTime::from_timestamp_millis(position.entry_time as i64).unwrap_or_default();
+    (entry.hour() as usize, entry.weekday().num_days_from_monday() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchanges::{Exchange, Side, Symbol};
+    use crate::paper_trading::{clock, ExitReason, PositionManager, SimulatedClock};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_attribution_by_symbol_exchange_and_entry_time() {
+        // Thursday 2024-01-04 14:30 UTC
+        let clock = Arc::new(SimulatedClock::new(1_704_378_600_000));
+        let manager = PositionManager::new().with_clock(clock.clone() as clock::SharedClock);
+        let btc = Symbol::new("BTCUSDT");
+        let eth = Symbol::new("ETHUSDT");
+
+        let won = manager.open_position(btc.clone(), Exchange::Binance, Side::Buy, 1.0, 100.0, 0.0, 0.0).unwrap();
+        let lost = manager.open_position(eth.clone(), Exchange::Coinbase, Side::Buy, 1.0, 100.0, 0.0, 0.0).unwrap();
+        clock.advance(std::time::Duration::from_secs(86_400)); // Closed the next day; entry time counts
+        manager.partial_close_position(&won, 0.5, 120.0, 0.0, 0.0, ExitReason::Signal).unwrap();
+        manager.close_position(&won, 110.0, 0.0, 0.0, ExitReason::Signal).unwrap();
+        manager.close_position(&lost, 90.0, 0.0, 0.0, ExitReason::StopLoss).unwrap();
+        manager.open_position(btc.clone(), Exchange::Binance, Side::Buy, 2.0, 100.0, 0.0, 0.0).unwrap();
+        let prices = dashmap::DashMap::from_iter([(btc.clone(), 105.0)]);
+        manager.update_prices(&prices);
+
+        let attribution = manager.pnl_attribution();
+        let btc_row = &attribution.by_symbol[0];
+        assert_eq!((btc_row.key.as_str(), btc_row.realized_pnl, btc_row.unrealized_pnl), ("BTCUSDT", 15.0, 10.0));
+        assert_eq!((btc_row.closed_trades, btc_row.winning_trades), (1, 1));
+        assert_eq!((attribution.by_symbol[1].realized_pnl, attribution.by_symbol[1].winning_trades), (-10.0, 0));
+        assert_eq!(attribution.by_exchange.len(), 2);
+        assert_eq!((attribution.by_hour[14].realized_pnl, attribution.by_hour[14].closed_trades), (5.0, 2));
+        assert_eq!(attribution.by_weekday[3].key, "Thu");
+        assert_eq!((attribution.by_weekday[3].realized_pnl, attribution.by_weekday[4].unrealized_pnl), (5.0, 10.0));
+
+        // Restoring rebuilds the same breakdown
+        let restored = PositionManager::new();
+        restored.restore(&manager.snapshot());
+        assert_eq!(restored.pnl_attribution(), attribution);
+    }
+}
@@ -0,0 +1,158 @@
+//! Confidence calibration
+//!
+//! Closed trades are bucketed by the confidence of the signal that opened
+//! them, so the realized win rate per decile shows whether a signal source's
+//! confidence means what it says. With `RiskLimits::calibrated_sizing` the
+//! risk manager sizes positions by a bucket's realized win rate instead of
+//! the raw confidence, once the bucket has seen enough trades.
+
+use super::outcomes::TradeOutcome;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Number of confidence buckets, each a tenth wide
+pub const CALIBRATION_BUCKETS: usize = 10;
+
+/// Realized results of the trades in one confidence bucket
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct CalibrationBucket {
+    pub min_confidence: f64,
+    pub max_confidence: f64,
+    pub trades: u64,
+    pub wins: u64,
+    pub win_rate: f64,
+    pub avg_confidence: f64, // Mean stated confidence, to compare with win_rate
+    pub total_pnl: f64,
+}
+
+#[derive(Clone, Copy, Default)]
+struct BucketState {
+    trades: u64,
+    wins: u64,
+    confidence_sum: f64,
+    pnl: f64,
+}
+
+/// Win rate by signal confidence decile
+pub struct ConfidenceCalibration {
+    buckets: RwLock<[BucketState; CALIBRATION_BUCKETS]>,
+    min_trades: u64,
+}
+
+impl ConfidenceCalibration {
+    /// `min_trades` is how many trades a bucket needs before its win rate replaces raw confidence
+    pub fn new(min_trades: u64) -> Self {
+        Self {
+            buckets: RwLock::new([BucketState::default(); CALIBRATION_BUCKETS]),
+            min_trades,
+        }
+    }
+
+    fn bucket(confidence: f64) -> usize {
+        ((confidence.clamp(0.0, 1.0) * CALIBRATION_BUCKETS as f64) as usize).min(CALIBRATION_BUCKETS - 1)
+    }
+
+    pub fn record(&self, confidence: f64, pnl: f64) {
+        if !confidence.is_finite() {
+            return;
+        }
+        let mut buckets = self.buckets.write();
+        let bucket = &mut buckets[Self::bucket(confidence)];
+        bucket.trades += 1;
+        bucket.wins += (pnl > 0.0) as u64;
+        bucket.confidence_sum += confidence;
+        bucket.pnl += pnl;
+    }
+
+    /// Record a closed trade; outcomes without a signal confidence are skipped
+    pub fn record_outcome(&self, outcome: &TradeOutcome) {
+        if let Some(confidence) = outcome.confidence {
+            self.record(confidence, outcome.pnl);
+        }
+    }
+
+    /// Realized win rate of the confidence's bucket, or the confidence itself
+    /// while the bucket has too few trades
+    pub fn calibrate(&self, confidence: f64) -> f64 {
+        let bucket = self.buckets.read()[Self::bucket(confidence)];
+        if bucket.trades >= self.min_trades.max(1) {
+            bucket.wins as f64 / bucket.trades as f64
+        } else {
+            confidence
+        }
+    }
+
+    pub fn table(&self) -> Vec<CalibrationBucket> {
+        let width = 1.0 / CALIBRATION_BUCKETS as f64;
+        self.buckets
+            .read()
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                let trades = bucket.trades.max(1) as f64;
+                CalibrationBucket {
+                    min_confidence: i as f64 * width,
+                    max_confidence: (i + 1) as f64 * width,
+                    trades: bucket.trades,
+                    wins: bucket.wins,
+                    win_rate: bucket.wins as f64 / trades,
+                    avg_confidence: bucket.confidence_sum / trades,
+                    total_pnl: bucket.pnl,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for ConfidenceCalibration {
+    fn default() -> Self {
+        Self::new(20)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper_trading::{RiskLimits, RiskManager};
+    use crate::exchanges::Symbol;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_win_rate_by_confidence_decile() {
+        let calibration = ConfidenceCalibration::new(10);
+
+        // Signals at 0.8-0.9 win 11 of 20; one at 0.95 loses
+        for i in 0..20 {
+            calibration.record(0.85, if i < 11 { 10.0 } else { -10.0 });
+        }
+        calibration.record(0.95, -5.0);
+        calibration.record(1.0, 5.0); // Full confidence lands in the top bucket
+
+        let table = calibration.table();
+        assert_eq!(table.len(), CALIBRATION_BUCKETS);
+        let bucket = &table[8];
+        assert_eq!((bucket.trades, bucket.wins), (20, 11));
+        assert!((bucket.win_rate - 0.55).abs() < 1e-9);
+        assert!((bucket.min_confidence - 0.8).abs() < 1e-9 && (bucket.max_confidence - 0.9).abs() < 1e-9);
+        assert_eq!(table[9].trades, 2);
+
+        // Sizing uses the realized rate once a bucket has enough trades
+        assert!((calibration.calibrate(0.82) - 0.55).abs() < 1e-9);
+        assert_eq!(calibration.calibrate(0.97), 0.97);
+
+        // The risk manager sizes an 0.85 signal like a 0.55 one, only when asked to
+        let calibration = Arc::new(calibration);
+        let limits = RiskLimits { position_size_pct: 10.0, ..Default::default() };
+        let symbol = Symbol::new("BTC-USD");
+        let raw = RiskManager::new(limits.clone(), 100_000.0);
+        raw.set_confidence_calibration(calibration.clone());
+        let calibrated = RiskManager::new(RiskLimits { calibrated_sizing: true, ..limits }, 100_000.0);
+        calibrated.set_confidence_calibration(calibration);
+        let sizes = |risk: &RiskManager| (risk.calculate_position_size(&symbol, 100_000.0, 0.85), risk.calculate_position_size(&symbol, 100_000.0, 0.55));
+        let (raw_85, raw_55) = sizes(&raw);
+        let (calibrated_85, _) = sizes(&calibrated);
+        assert!(raw_85 > raw_55);
+        assert!((calibrated_85 - raw_55).abs() < 1e-9);
+    }
+}
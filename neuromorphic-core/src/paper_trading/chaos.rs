@@ -0,0 +1,263 @@
+//! Injected faults for testing degraded conditions
+//!
+//! Strategies and risk limits are tuned on clean data, but live feeds go
+//! quiet, lag and repeat themselves, and venues refuse orders. A fault
+//! injector sits between the price feed and an engine and between the engine
+//! and its order book, and breaks them on purpose:
+//!
+//! - `outage`: a symbol's ticks and volume are dropped
+//! - `delay`: ticks arrive late, after ticks that came later, once the next
+//!   tick for any symbol reaches the engine
+//! - `duplicate`: every tick arrives twice
+//! - `reject_orders`: new orders are refused at submission, like a venue
+//!   rejecting them; exits are retried on a later tick
+//!
+//! Faults run on a schedule, as windows of engine time after the engine is
+//! created, or at random at configured rates. Random faults come from a
+//! seeded sequence so a degraded run can be reproduced.
+
+use super::rng::SeededRng;
+use crate::exchanges::Symbol;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultKind {
+    Outage,
+    Delay,
+    Duplicate,
+    RejectOrders,
+}
+
+/// A fault over a window of engine time
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledFault {
+    pub kind: FaultKind,
+    pub after_secs: u64, // From when the engine was created
+    pub duration_secs: u64,
+    #[serde(default)]
+    pub delay_ms: u64, // How late ticks arrive, for `delay`
+    #[serde(default)]
+    pub symbols: Vec<String>, // Every symbol when empty
+}
+
+/// Chances of faults at random; zero disables each
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RandomFaults {
+    pub outage_rate: f64, // Per tick, that the symbol's feed goes down
+    pub outage_secs: u64,
+    pub delay_rate: f64, // Per tick
+    pub max_delay_ms: u64, // Delays are uniform up to this
+    pub duplicate_rate: f64, // Per tick
+    pub reject_rate: f64, // Per order
+}
+
+impl Default for RandomFaults {
+    fn default() -> Self {
+        Self {
+            outage_rate: 0.0,
+            outage_secs: 30,
+            delay_rate: 0.0,
+            max_delay_ms: 1000,
+            duplicate_rate: 0.0,
+            reject_rate: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChaosConfig {
+    pub seed: Option<u64>, // Of the random faults; a stream of the engine's `seed`, or different every run, when unset
+    pub faults: Vec<ScheduledFault>,
+    pub random: RandomFaults,
+}
+
+/// Faults injected so far
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct FaultStatistics {
+    pub ticks_dropped: u64,
+    pub ticks_delayed: u64,
+    pub ticks_duplicated: u64,
+    pub orders_rejected: u64,
+}
+
+/// Applies a `ChaosConfig` to ticks and orders
+pub struct FaultInjector {
+    config: ChaosConfig,
+    started_ms: u64,
+    rng: SeededRng,
+    outages: DashMap<Symbol, u64>, // End of the random outage of each symbol
+    delayed: Mutex<Vec<(u64, Symbol, f64)>>, // Due time, symbol and price
+    ticks_dropped: AtomicU64,
+    ticks_delayed: AtomicU64,
+    ticks_duplicated: AtomicU64,
+    orders_rejected: AtomicU64,
+}
+
+impl FaultInjector {
+    /// Injector whose schedule starts at `now_ms`
+    pub fn new(config: ChaosConfig, now_ms: u64) -> Self {
+        let rng = SeededRng::seeded_or_random(config.seed);
+        Self {
+            config,
+            started_ms: now_ms,
+            rng,
+            outages: DashMap::new(),
+            delayed: Mutex::new(Vec::new()),
+            ticks_dropped: AtomicU64::new(0),
+            ticks_delayed: AtomicU64::new(0),
+            ticks_duplicated: AtomicU64::new(0),
+            orders_rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Ticks to deliver now for a tick of `symbol` at `price`: delayed ticks
+    /// that are due, oldest first, then this one as often as it arrives
+    pub fn on_tick(&self, symbol: &Symbol, price: f64, now_ms: u64) -> Vec<(Symbol, f64)> {
+        let mut ticks = self.release_due(now_ms);
+
+        let random = self.config.random;
+        if !self.feed_down(symbol, now_ms) && self.chance(random.outage_rate) {
+            self.outages.insert(symbol.clone(), now_ms + random.outage_secs * 1000);
+        }
+        if self.feed_down(symbol, now_ms) {
+            self.ticks_dropped.fetch_add(1, Ordering::Relaxed);
+            return ticks;
+        }
+
+        let delay_ms = match self.scheduled(FaultKind::Delay, symbol, now_ms) {
+            Some(fault) => fault.delay_ms,
+            None if self.chance(random.delay_rate) => (self.draw() * random.max_delay_ms as f64) as u64,
+            None => 0,
+        };
+        if delay_ms > 0 {
+            self.ticks_delayed.fetch_add(1, Ordering::Relaxed);
+            self.delayed.lock().push((now_ms + delay_ms, symbol.clone(), price));
+            return ticks;
+        }
+
+        ticks.push((symbol.clone(), price));
+        if self.scheduled(FaultKind::Duplicate, symbol, now_ms).is_some() || self.chance(random.duplicate_rate) {
+            self.ticks_duplicated.fetch_add(1, Ordering::Relaxed);
+            ticks.push((symbol.clone(), price));
+        }
+        ticks
+    }
+
+    /// Whether the feed of `symbol` is out
+    pub fn feed_down(&self, symbol: &Symbol, now_ms: u64) -> bool {
+        self.scheduled(FaultKind::Outage, symbol, now_ms).is_some()
+            || self.outages.get(symbol).is_some_and(|until| now_ms < *until)
+    }
+
+    /// Whether a new order in `symbol` is rejected
+    pub fn reject_order(&self, symbol: &Symbol, now_ms: u64) -> bool {
+        let rejected = self.scheduled(FaultKind::RejectOrders, symbol, now_ms).is_some() || self.chance(self.config.random.reject_rate);
+        if rejected {
+            self.orders_rejected.fetch_add(1, Ordering::Relaxed);
+        }
+        rejected
+    }
+
+    pub fn statistics(&self) -> FaultStatistics {
+        FaultStatistics {
+            ticks_dropped: self.ticks_dropped.load(Ordering::Relaxed),
+            ticks_delayed: self.ticks_delayed.load(Ordering::Relaxed),
+            ticks_duplicated: self.ticks_duplicated.load(Ordering::Relaxed),
+            orders_rejected: self.orders_rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    fn release_due(&self, now_ms: u64) -> Vec<(Symbol, f64)> {
+        let mut delayed = self.delayed.lock();
+        let (mut due, waiting): (Vec<_>, Vec<_>) = delayed.drain(..).partition(|(at, _, _)| *at <= now_ms);
+        *delayed = waiting;
+        due.sort_by_key(|(at, _, _)| *at);
+        due.into_iter().map(|(_, symbol, price)| (symbol, price)).collect()
+    }
+
+    /// The scheduled fault of `kind` covering `symbol` now, if any
+    fn scheduled(&self, kind: FaultKind, symbol: &Symbol, now_ms: u64) -> Option<&ScheduledFault> {
+        let elapsed_ms = now_ms.saturating_sub(self.started_ms);
+        self.config.faults.iter().find(|fault| {
+            fault.kind == kind
+                && (fault.after_secs * 1000..(fault.after_secs + fault.duration_secs) * 1000).contains(&elapsed_ms)
+                && (fault.symbols.is_empty() || fault.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol.as_str())))
+        })
+    }
+
+    /// True with probability `rate`; draws nothing when the rate is zero so
+    /// the sequence of other faults stays the same
+    fn chance(&self, rate: f64) -> bool {
+        rate > 0.0 && self.draw() < rate
+    }
+
+    /// Next number of the seeded sequence, in [0, 1)
+    fn draw(&self) -> f64 {
+        self.rng.next_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheduled_faults() {
+        let fault = |kind, after_secs, delay_ms, symbols: &[&str]| ScheduledFault {
+            kind,
+            after_secs,
+            duration_secs: 10,
+            delay_ms,
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+        };
+        let config = ChaosConfig {
+            faults: vec![
+                fault(FaultKind::Outage, 10, 0, &["BTCUSDT"]),
+                fault(FaultKind::Delay, 20, 5_000, &[]),
+                fault(FaultKind::Duplicate, 30, 0, &[]),
+                fault(FaultKind::RejectOrders, 30, 0, &["ethusdt"]),
+            ],
+            ..Default::default()
+        };
+        let chaos = FaultInjector::new(config, 1_000_000);
+        let (btc, eth) = (Symbol::new("BTCUSDT"), Symbol::new("ETHUSDT"));
+        let at = |secs: u64| 1_000_000 + secs * 1000;
+
+        assert_eq!(chaos.on_tick(&btc, 1.0, at(0)), [(btc.clone(), 1.0)]);
+        assert!(chaos.on_tick(&btc, 2.0, at(10)).is_empty());
+        assert!(chaos.feed_down(&btc, at(19)) && !chaos.feed_down(&eth, at(19)));
+        // Late ticks come out behind the ticks that overtook them
+        assert!(chaos.on_tick(&btc, 3.0, at(20)).is_empty());
+        assert!(chaos.on_tick(&eth, 4.0, at(21)).is_empty());
+        assert_eq!(chaos.on_tick(&btc, 5.0, at(30)), [(btc.clone(), 3.0), (eth.clone(), 4.0), (btc.clone(), 5.0), (btc.clone(), 5.0)]);
+        assert!(chaos.reject_order(&eth, at(30)) && !chaos.reject_order(&btc, at(30)) && !chaos.reject_order(&eth, at(40)));
+        assert_eq!(chaos.statistics(), FaultStatistics { ticks_dropped: 1, ticks_delayed: 2, ticks_duplicated: 1, orders_rejected: 1 });
+    }
+
+    #[test]
+    fn test_random_faults_follow_the_seed() {
+        let config = ChaosConfig {
+            seed: Some(7),
+            random: RandomFaults { outage_rate: 0.01, outage_secs: 1, delay_rate: 0.1, duplicate_rate: 0.1, reject_rate: 0.2, ..Default::default() },
+            ..Default::default()
+        };
+        let symbol = Symbol::new("BTCUSDT");
+        let run = || {
+            let chaos = FaultInjector::new(config.clone(), 0);
+            let ticks: Vec<usize> = (0..1000).map(|i| chaos.on_tick(&symbol, 1.0, i * 100).len()).collect();
+            let rejected = (0..100).filter(|i| chaos.reject_order(&symbol, 100_000 + i)).count();
+            (ticks, rejected, chaos.statistics())
+        };
+        let (ticks, rejected, statistics) = run();
+        assert_eq!(run(), (ticks, rejected, statistics));
+        assert!((10..40).contains(&rejected));
+        assert!(statistics.ticks_dropped > 0 && statistics.ticks_delayed > 50 && statistics.ticks_duplicated > 50);
+    }
+}
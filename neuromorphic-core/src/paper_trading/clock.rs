@@ -0,0 +1,79 @@
+//! Time source for the trading components
+//!
+//! Order expiry, time stops, fill latency and session statistics all read
+//! the time through a `Clock`, so tests and backtests can run on simulated
+//! time instead of the wall clock.
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch
+    fn now_ms(&self) -> u64;
+
+    fn now(&self) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.now_ms() as i64)
+            .single()
+            .unwrap_or_default()
+    }
+}
+
+/// Clock shared between the engine, its managers and the metrics collector
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall-clock time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+}
+
+/// The wall clock, shared
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Time that only moves when told to
+#[derive(Debug, Default)]
+pub struct SimulatedClock {
+    now_ms: AtomicU64,
+}
+
+impl SimulatedClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self {
+            now_ms: AtomicU64::new(start_ms),
+        }
+    }
+
+    pub fn starting_at(start: DateTime<Utc>) -> Self {
+        Self::new(start.timestamp_millis().max(0) as u64)
+    }
+
+    /// Jump to a point in time. Going backwards is allowed, e.g. to replay a session.
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.set_ms(time.timestamp_millis().max(0) as u64);
+    }
+
+    pub fn set_ms(&self, now_ms: u64) {
+        self.now_ms.store(now_ms, Ordering::Release);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now_ms.fetch_add(by.as_millis() as u64, Ordering::AcqRel);
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now_ms(&self) -> u64 {
+        self.now_ms.load(Ordering::Acquire)
+    }
+}
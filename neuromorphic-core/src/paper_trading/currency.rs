@@ -0,0 +1,182 @@
+//! Currency conversion for reporting P&L across quote currencies
+//!
+//! Rates come from the price stream: every price of a pair like "EURUSDT" is
+//! a rate between its base and quote. Fixed FX rates can be given for
+//! currencies the stream doesn't cover, or covers only later; a streamed rate
+//! for the same pair replaces them.
+
+use crate::exchanges::Symbol;
+use dashmap::DashMap;
+use std::collections::HashMap;
+
+/// Quote currencies recognised at the end of concatenated symbols like "ETHBTC",
+/// longest first so "USDT" wins over "USD"
+const KNOWN_QUOTES: &[&str] = &["USDT", "USDC", "BUSD", "TUSD", "FDUSD", "USD", "EUR", "GBP", "BTC", "ETH", "BNB"];
+
+/// Tracks conversion rates from the price stream and converts amounts
+/// into a single reporting currency
+pub struct CurrencyConverter {
+    reporting_currency: String,
+    aliases: HashMap<String, String>, // Pegged currencies treated as equal, e.g. USDT -> USD
+    rates: DashMap<(String, String), f64>, // (base, quote) -> quote units per base unit
+}
+
+impl CurrencyConverter {
+    pub fn new(reporting_currency: impl Into<String>) -> Self {
+        let aliases = ["USDT", "USDC", "BUSD", "TUSD", "FDUSD"]
+            .iter()
+            .map(|c| (c.to_string(), "USD".to_string()))
+            .collect();
+
+        Self {
+            reporting_currency: reporting_currency.into().to_uppercase(),
+            aliases,
+            rates: DashMap::new(),
+        }
+    }
+
+    /// Fall back to these values in USD of one unit of each currency, e.g.
+    /// `("EUR", 1.08)`, until prices give a rate for the pair
+    pub fn with_fx_rates<'a>(self, usd_per_unit: impl IntoIterator<Item = (&'a String, &'a f64)>) -> Self {
+        for (currency, rate) in usd_per_unit {
+            if rate.is_finite() && *rate > 0.0 {
+                self.rates.insert((self.normalize(currency), "USD".to_string()), *rate);
+            }
+        }
+        self
+    }
+
+    /// Treat `currency` as equal in value to `pegged_to`
+    pub fn with_alias(mut self, currency: &str, pegged_to: &str) -> Self {
+        self.aliases.insert(currency.to_uppercase(), pegged_to.to_uppercase());
+        self
+    }
+
+    pub fn reporting_currency(&self) -> &str {
+        &self.reporting_currency
+    }
+
+    /// Split a symbol into (base, quote), e.g. "BTC-USD" or "ETHBTC"
+    pub fn split_symbol(symbol: &Symbol) -> Option<(String, String)> {
+        let s = symbol.as_str().to_uppercase();
+
+        if let Some((base, quote)) = s.split_once(['-', '/', '_']) {
+            if !base.is_empty() && !quote.is_empty() {
+                return Some((base.to_string(), quote.to_string()));
+            }
+        }
+
+        KNOWN_QUOTES
+            .iter()
+            .find(|q| s.len() > q.len() && s.ends_with(*q))
+            .map(|q| (s[..s.len() - q.len()].to_string(), q.to_string()))
+    }
+
+    /// Currency the symbol's prices and P&L are denominated in.
+    /// Symbols without a recognisable quote (e.g. equities) use the reporting currency.
+    pub fn quote_currency(&self, symbol: &Symbol) -> String {
+        Self::split_symbol(symbol)
+            .map(|(_, quote)| quote)
+            .unwrap_or_else(|| self.reporting_currency.clone())
+    }
+
+    /// Record a price as a conversion rate between the symbol's base and quote
+    pub fn update_price(&self, symbol: &Symbol, price: f64) {
+        if !price.is_finite() || price <= 0.0 {
+            return;
+        }
+        if let Some((base, quote)) = Self::split_symbol(symbol) {
+            self.rates.insert((self.normalize(&base), self.normalize(&quote)), price);
+        }
+    }
+
+    /// Rate to convert one unit of `from` into `to`, direct, inverse or through one intermediate
+    pub fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        let from = self.normalize(from);
+        let to = self.normalize(to);
+        if from == to {
+            return Some(1.0);
+        }
+        if let Some(rate) = self.direct_rate(&from, &to) {
+            return Some(rate);
+        }
+
+        // Collect pairs first so no map guard is held while looking up rates
+        let pairs: Vec<(String, String)> = self.rates.iter().map(|entry| entry.key().clone()).collect();
+        pairs
+            .iter()
+            .filter_map(|(base, quote)| {
+                let via = if *base == from {
+                    quote
+                } else if *quote == from {
+                    base
+                } else {
+                    return None;
+                };
+                Some(self.direct_rate(&from, via)? * self.direct_rate(via, &to)?)
+            })
+            .next()
+    }
+
+    /// Convert an amount between currencies
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        self.rate(from, to).map(|rate| amount * rate)
+    }
+
+    /// Convert an amount denominated in the symbol's quote currency into the reporting currency.
+    /// Falls back to the unconverted amount when no rate is known yet.
+    pub fn to_reporting(&self, symbol: &Symbol, amount: f64) -> f64 {
+        let quote = self.quote_currency(symbol);
+        self.convert(amount, &quote, &self.reporting_currency).unwrap_or(amount)
+    }
+
+    fn direct_rate(&self, from: &str, to: &str) -> Option<f64> {
+        if let Some(rate) = self.rates.get(&(from.to_string(), to.to_string())) {
+            return Some(*rate);
+        }
+        self.rates
+            .get(&(to.to_string(), from.to_string()))
+            .map(|rate| 1.0 / *rate)
+    }
+
+    fn normalize(&self, currency: &str) -> String {
+        let currency = currency.to_uppercase();
+        self.aliases.get(&currency).cloned().unwrap_or(currency)
+    }
+}
+
+impl Default for CurrencyConverter {
+    fn default() -> Self {
+        Self::new("USD")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cross_quote_conversion() {
+        let converter = CurrencyConverter::new("USD");
+        converter.update_price(&Symbol::new("BTCUSDT"), 50_000.0);
+        converter.update_price(&Symbol::new("ETHBTC"), 0.05);
+
+        assert_eq!(CurrencyConverter::split_symbol(&Symbol::new("ETH-BTC")), Some(("ETH".into(), "BTC".into())));
+        assert_eq!(converter.quote_currency(&Symbol::new("AAPL")), "USD");
+
+        // 0.01 BTC of P&L on ETHBTC is $500; USDT is pegged to the reporting currency
+        assert_eq!(converter.to_reporting(&Symbol::new("ETHBTC"), 0.01), 500.0);
+        assert_eq!(converter.to_reporting(&Symbol::new("BTCUSDT"), 100.0), 100.0);
+
+        // ETH -> USD goes through BTC
+        assert!((converter.rate("ETH", "USD").unwrap() - 2_500.0).abs() < 1e-9);
+        assert!((converter.rate("USD", "BTC").unwrap() - 1.0 / 50_000.0).abs() < 1e-12);
+
+        // A EUR base takes the fixed rate until EURUSDT trades
+        let fx_rates = std::collections::BTreeMap::from([("EUR".to_string(), 1.25)]);
+        let converter = CurrencyConverter::new("EUR").with_fx_rates(&fx_rates);
+        assert_eq!(converter.to_reporting(&Symbol::new("BTCUSDT"), 100.0), 80.0);
+        converter.update_price(&Symbol::new("EURUSDT"), 1.0);
+        assert_eq!(converter.to_reporting(&Symbol::new("BTCUSDT"), 100.0), 100.0);
+    }
+}
@@ -1,44 +1,88 @@
 //! Paper trading engine
 
 use super::{
-    position_manager::{PositionManager, Position, PositionStatistics},
-    order_manager::{OrderManager, Order, OrderEvent, OrderType, SlippageModel},
+    position_manager::{PositionManager, Position, PositionStatus, PositionStatistics, ExitReason, TriggeredExit},
+    order_manager::{OrderManager, Order, BasketOrder, Fill, OrderEvent, OrderStatus, OrderType, SlippageModel},
     risk_manager::{RiskManager, RiskLimits, RiskCheckResult, RiskMetrics},
+    fees::FeeSchedule,
+    currency::CurrencyConverter,
+    execution::{self, ExecutionMode, ExecutionVenue},
+    rolling::{RollingSample, RollingStatistics, WindowStatistics},
+    returns::ReturnStatistics,
+    queue::{self, QueueConfig, QueueError, QueueReceiver, QueueSender, QueueStatistics},
+    clock::{self, SharedClock},
+    events::{EngineEvent, EventLog},
+    outcomes::{OutcomePublisher, SignalOutcome, SignalResult},
+    throttle::{SignalThrottle, ThrottleConfig, ThrottleStatistics},
+    snapshot::{EngineSnapshot, SNAPSHOT_VERSION},
+    attribution::PnlAttribution,
+    scenarios::{Scenario, ScenarioPosition, ScenarioReport},
+    import::{ImportedPosition, PortfolioImport},
+    groups,
+    execution_algos::ExecutionAlgo,
+    participation::ParticipationConfig,
+    instruments::{InstrumentConfig, InstrumentRegistry},
+    stops::StopPlacement,
+    trade_management::{TradeManager, TradeRule},
+    rebalancing::{self, RebalanceConfig, RebalancePlan},
+    chaos::{ChaosConfig, FaultInjector, FaultStatistics},
+    history::HistoryConfig,
+    marks::{MarkPrice, MarkPrices},
+    rng::{self, SeededRng},
 };
 use crate::exchanges::{Symbol, Exchange, Side};
-use anyhow::Result;
+use crate::market_scanner::PriceHistory;
+use crate::metrics::TradingHistograms;
+use anyhow::{Context, Result};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::mpsc;
 use std::time::{Duration, Instant};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 
 /// Trading signal from neuromorphic system
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TradingSignal {
     pub symbol: Symbol,
     pub exchange: Exchange,
     pub action: SignalAction,
     pub confidence: f64,
     pub urgency: f64,
+    #[serde(default)]
     pub metadata: SignalMetadata,
 }
 
 /// Signal action
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SignalAction {
     Buy { size_hint: Option<f64> },
     Sell { size_hint: Option<f64> },
     Close { position_id: Option<String> },
+    ScaleIn { fraction: f64 },  // Add this fraction of the current position
+    ScaleOut { fraction: f64 }, // Reduce by this fraction of the current position
     Hold,
 }
 
 /// Signal metadata
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SignalMetadata {
     pub spike_count: u64,
     pub pattern_strength: f64,
     pub market_regime: String,
     pub volatility: f64,
+    pub max_hold: Option<Duration>, // Close the resulting position after this long
+    pub account_id: Option<String>, // Target account; the default account when unset
+    pub strategy: Option<String>, // Name of the strategy that produced the signal
+    pub signal_id: Option<String>, // Echoed in the outcome of the position the signal opens
+    pub source_id: Option<String>, // Model that produced the signal, for ensemble aggregation
+    pub size_multiplier: Option<f64>, // Scales buy/sell sizes, e.g. from a routing rule
+    pub stop_loss: Option<f64>, // Stop price for the position it opens, instead of risk_limits.stop_loss_pct
+    pub take_profit: Option<f64>, // Target price for the position it opens, instead of risk_limits.take_profit_pct
+    pub group_id: Option<String>, // Position group of the position it opens, e.g. a strategy book, see `groups`
+    pub decision_price: Option<f64>, // Price the strategy decided at; shortfall is measured from the price on arrival when unset
 }
 
 /// Paper trading configuration
@@ -46,10 +90,36 @@ pub struct SignalMetadata {
 pub struct PaperTradingConfig {
     pub initial_capital: f64,
     pub commission_rate: f64,
+    pub fee_schedule: Option<FeeSchedule>, // Overrides commission_rate when set
     pub slippage_model: SlippageModel,
+    pub participation: ParticipationConfig, // Caps simulated fills at a share of traded volume
+    pub queue_position: bool, // Resting limit orders wait for the queue ahead of them, see `queue_position`
     pub risk_limits: RiskLimits,
     pub enable_stop_loss: bool,
     pub enable_take_profit: bool,
+    pub max_holding_period: Option<Duration>, // Default time stop when a signal sets none
+    pub hedge_mode: bool, // Keep long and short positions per symbol side by side
+    pub reporting_currency: String, // Base currency of capital and statistics; P&L in other quote currencies is converted into this
+    pub fx_rates: BTreeMap<String, f64>, // USD per unit of a currency, until prices give a rate
+    pub execution: ExecutionMode, // Non-simulated modes need a venue attached before start
+    pub signal_queue: QueueConfig,
+    pub order_event_queue: QueueConfig,
+    pub signal_throttle: ThrottleConfig, // Per-symbol cooldown and rate limit on new exposure
+    pub seed: Option<u64>, // Of everything random in the simulation, see `rng`; different every run when unset
+    pub id_seed: Option<u64>, // Reproducible position and order IDs, instead of a stream of `seed`
+    pub event_log: Option<PathBuf>, // Append engine events here from `start`, see `events`
+    pub portfolio_file: Option<PathBuf>, // Positions `start` opens in a flat engine, see `import`
+    pub exit_rules: Vec<PathBuf>, // Rhai exit rules `start` loads, see `scripting`
+    pub instruments: BTreeMap<String, InstrumentConfig>, // By symbol, over the defaults of its asset class
+    pub stops: StopPlacement, // How exit levels of new positions are placed, see `stops`
+    pub strategy_stops: BTreeMap<String, StopPlacement>, // By strategy name, instead of `stops`
+    pub trade_management: Vec<TradeRule>, // Break-even, partial profit and trailing rules, in order; none when empty
+    pub rebalance: Option<RebalanceConfig>, // Target weights `start` rebalances to every `interval_secs`, see `rebalancing`
+    pub chaos: Option<ChaosConfig>, // Feed and order faults injected on purpose, see `chaos`
+    pub history: HistoryConfig, // Signals, returns and finished orders kept in memory, see `history`
+    pub marks: MarkPrice, // What open positions are valued at, see `marks`
+    pub symbol_marks: BTreeMap<String, MarkPrice>, // By symbol, instead of `marks`
+    pub stepped: bool, // `start` runs no engine tasks; the caller drives `process_signal_now` and `process_orders_once`
     pub update_interval: Duration,
 }
 
@@ -58,18 +128,62 @@ impl Default for PaperTradingConfig {
         Self {
             initial_capital: 100000.0,
             commission_rate: 0.1, // 0.1%
+            fee_schedule: None,
             slippage_model: SlippageModel::Percentage(0.01), // 0.01%
+            participation: ParticipationConfig::default(),
+            queue_position: false,
             risk_limits: RiskLimits::default(),
             enable_stop_loss: true,
             enable_take_profit: true,
+            max_holding_period: None,
+            hedge_mode: false,
+            reporting_currency: "USD".to_string(),
+            fx_rates: BTreeMap::new(),
+            execution: ExecutionMode::Simulated,
+            signal_queue: QueueConfig::default(),
+            order_event_queue: QueueConfig::default(),
+            signal_throttle: ThrottleConfig::default(),
+            seed: None,
+            id_seed: None,
+            event_log: None,
+            portfolio_file: None,
+            exit_rules: Vec::new(),
+            instruments: BTreeMap::new(),
+            stops: StopPlacement::default(),
+            strategy_stops: BTreeMap::new(),
+            trade_management: Vec::new(),
+            rebalance: None,
+            chaos: None,
+            history: HistoryConfig::default(),
+            marks: MarkPrice::default(),
+            symbol_marks: BTreeMap::new(),
+            stepped: false,
             update_interval: Duration::from_millis(100),
         }
     }
 }
 
+impl PaperTradingConfig {
+    /// Seed of position and order IDs: `id_seed`, else a stream of `seed`
+    pub fn ids_seed(&self) -> Option<u64> {
+        self.id_seed.or(self.seed.map(|seed| rng::stream_seed(seed, "ids")))
+    }
+
+    /// Stop placement for positions opened by signals of `strategy`
+    pub fn stops_for(&self, strategy: Option<&str>) -> &StopPlacement {
+        strategy.and_then(|name| self.strategy_stops.get(name)).unwrap_or(&self.stops)
+    }
+
+    /// Hours of price history the ATR stops of any strategy need
+    pub fn stop_history_hours(&self) -> u64 {
+        std::iter::once(&self.stops).chain(self.strategy_stops.values()).map(StopPlacement::history_hours).max().unwrap_or(1)
+    }
+}
+
 /// Paper trading statistics
 #[derive(Default, Clone, Debug)]
 pub struct TradingStatistics {
+    pub currency: String, // Of capital and P&L
     pub capital: f64,
     pub total_pnl: f64,
     pub total_return_pct: f64,
@@ -77,6 +191,31 @@ pub struct TradingStatistics {
     pub risk_metrics: RiskMetrics,
     pub signals_processed: u64,
     pub signals_executed: u64,
+    pub signals_throttled: ThrottleStatistics, // Dropped by the per-symbol throttle, not executed
+    pub rolling: Vec<WindowStatistics>, // 1h / 24h / 7d windows
+    pub signal_queue: QueueStatistics,
+    pub order_event_queue: QueueStatistics,
+}
+
+/// Trading statistics with their P&L attribution
+#[derive(Clone, Debug)]
+pub struct DetailedStatistics {
+    pub statistics: TradingStatistics,
+    pub attribution: PnlAttribution,
+}
+
+/// Queues signals on an engine from elsewhere, e.g. an API route, without
+/// holding the engine
+#[derive(Clone)]
+pub struct SignalSubmitter {
+    sender: QueueSender<(TradingSignal, Instant)>,
+}
+
+impl SignalSubmitter {
+    /// Queue a signal without waiting; fails while the signal queue is full
+    pub fn try_submit(&self, signal: TradingSignal) -> Result<(), QueueError> {
+        self.sender.try_send((signal, Instant::now()))
+    }
 }
 
 /// Paper trading engine
@@ -86,47 +225,346 @@ pub struct PaperTradingEngine {
     risk_manager: Arc<RiskManager>,
     config: PaperTradingConfig,
     current_capital: Arc<parking_lot::RwLock<f64>>,
-    current_prices: Arc<DashMap<Symbol, f64>>,
-    signal_sender: mpsc::UnboundedSender<TradingSignal>,
-    signal_receiver: Option<mpsc::UnboundedReceiver<TradingSignal>>,
+    current_prices: Arc<DashMap<Symbol, f64>>, // Last trades, which orders fill against
+    marks: Arc<MarkPrices>, // What positions are valued at
+    signal_sender: QueueSender<(TradingSignal, Instant)>, // With the time it was queued
+    signal_receiver: Option<QueueReceiver<(TradingSignal, Instant)>>,
     statistics: Arc<parking_lot::RwLock<TradingStatistics>>,
     running: Arc<tokio::sync::RwLock<bool>>,
-    returns_history: Arc<parking_lot::RwLock<Vec<f64>>>,
+    returns_history: Arc<parking_lot::RwLock<ReturnStatistics>>,
+    entry_plans: Arc<DashMap<String, EntryPlan>>, // Keyed by entry order ID
+    order_spans: Arc<DashMap<String, Span>>, // Signal span each order was submitted under, until it fills
+    venue: Option<Arc<dyn ExecutionVenue>>, // Fills come from here instead of the simulator when set
+    throttle: Arc<SignalThrottle>,
+    histograms: Arc<TradingHistograms>,
+    events: EventLog,
+    clock: SharedClock,
+    price_history: Option<Arc<PriceHistory>>, // Bars for ATR stops
+    trade_manager: TradeManager,
+    faults: Option<Arc<FaultInjector>>, // Between the feed and the engine, and the engine and its orders
+    rng: Arc<SeededRng>,
+}
+
+/// Position settings carried from a signal to the position its order opens
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct EntryPlan {
+    max_hold: Option<Duration>,
+    signal_id: Option<String>,
+    strategy: Option<String>,
+    confidence: Option<f64>,
+    #[serde(default)]
+    stop_loss: Option<f64>,
+    #[serde(default)]
+    take_profit: Option<f64>,
+    #[serde(default)]
+    group_id: Option<String>,
+    #[serde(default)]
+    atr: Option<f64>, // When the signal arrived, for ATR stops
+}
+
+impl EntryPlan {
+    fn from_signal(signal: &TradingSignal) -> Self {
+        Self {
+            max_hold: signal.metadata.max_hold,
+            signal_id: signal.metadata.signal_id.clone(),
+            strategy: signal.metadata.strategy.clone(),
+            confidence: Some(signal.confidence),
+            stop_loss: signal.metadata.stop_loss,
+            take_profit: signal.metadata.take_profit,
+            group_id: signal.metadata.group_id.clone(),
+            atr: None,
+        }
+    }
+}
+
+/// What the signal processor works with, so a signal can also be handled
+/// outside it by `process_signal_now`
+struct SignalHandler {
+    position_manager: Arc<PositionManager>,
+    order_manager: Arc<OrderManager>,
+    risk_manager: Arc<RiskManager>,
+    current_capital: Arc<parking_lot::RwLock<f64>>,
+    current_prices: Arc<DashMap<Symbol, f64>>,
+    statistics: Arc<parking_lot::RwLock<TradingStatistics>>,
+    config: PaperTradingConfig,
+    entry_plans: Arc<DashMap<String, EntryPlan>>,
+    order_spans: Arc<DashMap<String, Span>>,
+    throttle: Arc<SignalThrottle>,
+    histograms: Arc<TradingHistograms>,
+    events: EventLog,
+    clock: SharedClock,
+    outcomes: OutcomePublisher,
+    price_history: Option<Arc<PriceHistory>>,
+}
+
+impl SignalHandler {
+    /// Throttle, risk-check and execute one signal queued at `queued`, and
+    /// publish what became of it
+    async fn handle(&self, signal: TradingSignal, queued: Instant) -> SignalOutcome {
+        // Update statistics
+        self.statistics.write().signals_processed += 1;
+        self.events.record(|| EngineEvent::SignalReceived { signal: signal.clone() });
+        
+        if let Err(reason) = self.throttle.check(&signal, self.clock.now_ms()) {
+            debug!(symbol = %signal.symbol, action = ?signal.action, ?reason, "Signal throttled");
+            let outcome = SignalOutcome::Throttled { reason };
+            self.outcomes.publish_signal(SignalResult::new(&signal, outcome.clone(), self.clock.now_ms()));
+            return outcome;
+        }
+        
+        // One span per signal, carried through risk check, order and fill
+        let span = info_span!(
+            "signal",
+            symbol = %signal.symbol,
+            action = ?signal.action,
+            confidence = signal.confidence,
+        );
+        
+        // Every handler counts the signal as executed once it submits an order
+        let executed = self.statistics.read().signals_executed;
+        let atr = self.price_history
+            .as_deref()
+            .filter(|_| matches!(signal.action, SignalAction::Buy { .. } | SignalAction::Sell { .. }))
+            .and_then(|history| self.config.stops_for(signal.metadata.strategy.as_deref()).atr(history, &signal.symbol));
+        
+        // Process signal based on action
+        let outcome = async {
+            debug!("Signal received");
+            let (handled, action) = match signal.action {
+                SignalAction::Buy { size_hint } => (
+                    PaperTradingEngine::handle_buy_signal(
+                        &signal,
+                        size_hint,
+                        atr,
+                        &self.position_manager,
+                        &self.order_manager,
+                        &self.risk_manager,
+                        &self.current_capital,
+                        &self.current_prices,
+                        &self.statistics,
+                        &self.entry_plans,
+                        &self.order_spans,
+                    ).await,
+                    "buy",
+                ),
+                SignalAction::Sell { size_hint } => (
+                    PaperTradingEngine::handle_sell_signal(
+                        &signal,
+                        size_hint,
+                        atr,
+                        &self.position_manager,
+                        &self.order_manager,
+                        &self.risk_manager,
+                        &self.current_capital,
+                        &self.current_prices,
+                        &self.statistics,
+                        &self.entry_plans,
+                        &self.order_spans,
+                        &self.config,
+                    ).await,
+                    "sell",
+                ),
+                SignalAction::Close { ref position_id } => (
+                    PaperTradingEngine::handle_close_signal(
+                        &signal,
+                        position_id.clone(),
+                        &self.position_manager,
+                        &self.order_manager,
+                        &self.current_prices,
+                        &self.statistics,
+                        &self.order_spans,
+                    ).await,
+                    "close",
+                ),
+                SignalAction::ScaleIn { fraction } | SignalAction::ScaleOut { fraction } => (
+                    PaperTradingEngine::handle_scale_signal(
+                        &signal,
+                        fraction,
+                        matches!(signal.action, SignalAction::ScaleIn { .. }),
+                        &self.position_manager,
+                        &self.order_manager,
+                        &self.risk_manager,
+                        &self.current_capital,
+                        &self.current_prices,
+                        &self.statistics,
+                        &self.order_spans,
+                    ).await,
+                    "scale",
+                ),
+                SignalAction::Hold => (Ok(SignalOutcome::Ignored { reason: "hold".to_string() }), "hold"),
+            };
+            handled.unwrap_or_else(|e| {
+                error!(error = %e, "Failed to handle {} signal", action);
+                SignalOutcome::Rejected { reason: format!("{:#}", e) }
+            })
+        }
+        .instrument(span)
+        .await;
+        self.outcomes.publish_signal(SignalResult::new(&signal, outcome.clone(), self.clock.now_ms()));
+        
+        if self.statistics.read().signals_executed > executed {
+            self.histograms.signal_to_order_ms.observe(queued.elapsed().as_secs_f64() * 1000.0);
+        }
+        outcome
+    }
 }
 
 impl PaperTradingEngine {
     pub fn new(config: PaperTradingConfig) -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
+        Self::with_clock(config, clock::system_clock())
+    }
+    
+    /// Create an engine whose time-dependent logic runs off `clock`, e.g. a
+    /// `SimulatedClock` driven by a backtest
+    pub fn with_clock(config: PaperTradingConfig, clock: SharedClock) -> Self {
+        Self::with_outcome_publisher(config, clock, OutcomePublisher::default())
+    }
+    
+    /// Create an engine publishing the outcomes of its closed positions to
+    /// `outcomes`, which other engines may share
+    pub fn with_outcome_publisher(config: PaperTradingConfig, clock: SharedClock, outcomes: OutcomePublisher) -> Self {
+        let (tx, rx) = queue::bounded(config.signal_queue);
         
         let initial_capital = config.initial_capital;
-        let commission_rate = config.commission_rate;
+        let fee_schedule = config.fee_schedule
+            .clone()
+            .unwrap_or_else(|| FeeSchedule::flat(config.commission_rate));
         let slippage_model = config.slippage_model.clone();
         let risk_limits = config.risk_limits.clone();
+        let converter = Arc::new(CurrencyConverter::new(config.reporting_currency.clone()).with_fx_rates(&config.fx_rates));
         
         let mut stats = TradingStatistics::default();
+        stats.currency = converter.reporting_currency().to_string();
         stats.capital = initial_capital;
         
+        let throttle = Arc::new(SignalThrottle::new(config.signal_throttle));
+        let events = EventLog::new(clock.clone());
+        let instruments = Arc::new(InstrumentRegistry::new(&config.instruments));
+        let trade_manager = TradeManager::new(config.trade_management.clone());
+        
+        let mut position_manager = PositionManager::with_currency_converter(converter)
+            .with_instruments(instruments.clone())
+            .with_clock(clock.clone())
+            .with_outcome_publisher(outcomes)
+            .with_event_log(events.clone());
+        let mut order_manager = OrderManager::with_fee_schedule(fee_schedule, slippage_model)
+            .with_event_queue(config.order_event_queue)
+            .with_participation(config.participation)
+            .with_instruments(instruments)
+            .with_clock(clock.clone())
+            .with_event_log(events.clone())
+            .with_history(config.history.orders, config.history.spill("orders"));
+        if config.queue_position {
+            order_manager = order_manager.with_queue_model();
+        }
+        let faults = config.chaos.clone().map(|mut chaos| {
+            chaos.seed = chaos.seed.or(config.seed.map(|seed| rng::stream_seed(seed, "chaos")));
+            Arc::new(FaultInjector::new(chaos, clock.now_ms()))
+        });
+        if let Some(faults) = &faults {
+            order_manager = order_manager.with_fault_injector(faults.clone());
+        }
+        if let Some(seed) = config.ids_seed() {
+            position_manager = position_manager.with_id_seed(seed);
+            order_manager = order_manager.with_id_seed(seed);
+        }
+        let returns_history = ReturnStatistics::new(config.history.returns).with_spill(config.history.spill("returns"));
+        let marks = Arc::new(MarkPrices::new(config.marks, config.symbol_marks.clone()));
+        let rng = Arc::new(SeededRng::seeded_or_random(config.seed.map(|seed| rng::stream_seed(seed, "simulation"))));
+        
         Self {
-            position_manager: Arc::new(PositionManager::new()),
-            order_manager: Arc::new(OrderManager::new(commission_rate, slippage_model)),
-            risk_manager: Arc::new(RiskManager::new(risk_limits, initial_capital)),
+            position_manager: Arc::new(position_manager),
+            order_manager: Arc::new(order_manager),
+            risk_manager: Arc::new(
+                RiskManager::new(risk_limits, initial_capital)
+                    .with_clock(clock.clone())
+                    .with_event_log(events.clone()),
+            ),
             config,
             current_capital: Arc::new(parking_lot::RwLock::new(initial_capital)),
             current_prices: Arc::new(DashMap::new()),
+            marks,
             signal_sender: tx,
             signal_receiver: Some(rx),
             statistics: Arc::new(parking_lot::RwLock::new(stats)),
             running: Arc::new(tokio::sync::RwLock::new(false)),
-            returns_history: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            returns_history: Arc::new(parking_lot::RwLock::new(returns_history)),
+            entry_plans: Arc::new(DashMap::new()),
+            order_spans: Arc::new(DashMap::new()),
+            venue: None,
+            throttle,
+            histograms: Arc::new(TradingHistograms::default()),
+            events,
+            clock,
+            price_history: None,
+            trade_manager,
+            faults,
+            rng,
         }
     }
     
+    /// Time source shared by this engine's managers
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+    
+    /// Random numbers for the rest of the simulation, e.g. slippage or latency
+    /// models and signal IDs; a stream of `seed` when it is set
+    pub fn rng(&self) -> &Arc<SeededRng> {
+        &self.rng
+    }
+    
+    /// Route orders to an exchange instead of simulating fills; call before `start`
+    pub fn set_execution_venue(&mut self, venue: Arc<dyn ExecutionVenue>) {
+        self.venue = Some(venue);
+    }
+    
+    /// Record fill and signal-to-order latencies into these histograms, e.g.
+    /// ones shared by several accounts; call before `start`
+    pub fn set_histograms(&mut self, histograms: Arc<TradingHistograms>) {
+        self.histograms = histograms;
+    }
+    
+    /// Take the ATR of stops in ATR mode from these bars, e.g. the scanner's;
+    /// without them every position gets percentage stops. Call before `start`.
+    pub fn set_price_history(&mut self, history: Arc<PriceHistory>) {
+        self.price_history = Some(history);
+    }
+    
+    /// Log of this engine's events; open from `start` when `event_log` is set
+    pub fn event_log(&self) -> &EventLog {
+        &self.events
+    }
+    
     /// Start the trading engine
     pub async fn start(&mut self) -> Result<()> {
+        if self.config.execution != ExecutionMode::Simulated && self.venue.is_none() {
+            anyhow::bail!("{:?} execution needs a venue attached before start", self.config.execution);
+        }
+        if let Some(path) = &self.config.event_log {
+            self.events.open(path)?;
+        }
+        // Not again on top of a restored snapshot or replayed event log
+        if let Some(path) = &self.config.portfolio_file {
+            if self.position_manager.get_all_positions().is_empty() {
+                self.import_positions(&PortfolioImport::load(path)?.positions)?;
+            }
+        }
+        for path in &self.config.exit_rules {
+            #[cfg(feature = "scripting")]
+            self.position_manager.add_exit_rule(Arc::new(crate::scripting::ScriptExitRule::load(path)?));
+            #[cfg(not(feature = "scripting"))]
+            warn!(script = %path.display(), "Built without the scripting feature; skipping exit rule");
+        }
+        
         let mut running = self.running.write().await;
         *running = true;
         drop(running);
         
+        if self.config.stepped {
+            return Ok(());
+        }
+        
         // Start signal processing
         self.spawn_signal_processor().await?;
         
@@ -136,9 +574,19 @@ impl PaperTradingEngine {
         // Start statistics updater
         self.spawn_statistics_updater().await?;
         
+        // Start scheduled rebalancing
+        if let Some(rebalance) = self.config.rebalance.clone().filter(|r| r.interval_secs.is_some()) {
+            self.spawn_rebalancer(rebalance).await?;
+        }
+        
         Ok(())
     }
     
+    /// Whether the engine was started and not stopped since
+    pub async fn is_running(&self) -> bool {
+        *self.running.read().await
+    }
+    
     /// Stop the trading engine
     pub async fn stop(&self) -> Result<()> {
         let mut running = self.running.write().await;
@@ -146,88 +594,268 @@ impl PaperTradingEngine {
         Ok(())
     }
     
-    /// Process trading signal
+    /// Queue a trading signal. Fails if the signal queue is full and its
+    /// policy is `RejectNew`; waits for room under `Block`. Once processed,
+    /// its `SignalResult` is published to the outcome publisher's signal
+    /// channel.
     pub async fn process_signal(&self, signal: TradingSignal) -> Result<()> {
-        self.signal_sender.send(signal)?;
+        self.signal_sender.send((signal, Instant::now())).await?;
         Ok(())
     }
     
-    /// Update market price
+    /// Submitter queueing signals on this engine, subject to the same queue
+    /// policy, throttle and risk limits as `process_signal`
+    pub fn signal_submitter(&self) -> SignalSubmitter {
+        SignalSubmitter { sender: self.signal_sender.clone() }
+    }
+    
+    /// Open the positions of an existing portfolio; none are opened if any
+    /// is invalid. Symbols without a price yet are marked at the entry price.
+    pub fn import_positions(&self, positions: &[ImportedPosition]) -> Result<Vec<String>> {
+        for (i, position) in positions.iter().enumerate() {
+            position.validate().with_context(|| format!("position {}", i + 1))?;
+        }
+        let mut ids = Vec::with_capacity(positions.len());
+        for position in positions {
+            if !self.current_prices.contains_key(&position.symbol) {
+                self.position_manager.currency_converter().update_price(&position.symbol, position.entry_price);
+                self.current_prices.insert(position.symbol.clone(), position.entry_price);
+                self.marks.on_trade(&position.symbol, position.entry_price, self.clock.now_ms());
+            }
+            ids.push(self.position_manager.import_position(position)?);
+        }
+        *self.current_capital.write() = self.config.initial_capital + self.position_manager.total_pnl();
+        info!(positions = ids.len(), "Imported portfolio positions");
+        Ok(ids)
+    }
+    
+    /// Submit the legs of a basket together after checking them as one
+    /// order; the positions they open share the basket's group id. Legs
+    /// without a limit price are checked at the current price.
+    pub fn submit_basket(&self, basket: BasketOrder) -> Result<Vec<String>> {
+        basket.validate()?;
+        let legs = basket.legs
+            .iter()
+            .map(|leg| {
+                let price = leg.price
+                    .or_else(|| self.current_prices.get(&leg.symbol).map(|p| *p))
+                    .ok_or_else(|| anyhow::anyhow!("No price for {}", leg.symbol))?;
+                Ok((leg.symbol.clone(), leg.side, leg.quantity, price))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        match self.risk_manager.check_basket(&legs, *self.current_capital.read()) {
+            RiskCheckResult::Rejected { reason } => anyhow::bail!("Basket rejected by risk check: {}", reason),
+            RiskCheckResult::Warning { message } => warn!(group_id = %basket.group_id, message = %message, "Risk warning"),
+            RiskCheckResult::Approved => {}
+        }
+        
+        let group_id = basket.group_id.clone();
+        let order_ids = self.order_manager.submit_basket(basket)?;
+        for (order_id, (symbol, side, quantity, _)) in order_ids.iter().zip(&legs) {
+            self.risk_manager.record_order(symbol);
+            Self::track_order(&self.order_spans, order_id, *side, *quantity);
+            let plan = EntryPlan { group_id: Some(group_id.clone()), ..Default::default() };
+            self.entry_plans.insert(order_id.clone(), plan);
+        }
+        info!(group_id = %group_id, legs = order_ids.len(), "Basket submitted");
+        Ok(order_ids)
+    }
+    
+    /// Work a large order through child orders over time, see
+    /// `execution_algos`. The whole quantity is risk checked up front.
+    pub fn submit_algo_order(&self, order: Order, algo: ExecutionAlgo) -> Result<String> {
+        let price = order.price
+            .or_else(|| self.current_prices.get(&order.symbol).map(|p| *p))
+            .ok_or_else(|| anyhow::anyhow!("No price for {}", order.symbol))?;
+        let capital = *self.current_capital.read();
+        match self.risk_manager.check_order(&order.symbol, order.side, order.quantity, price, capital) {
+            RiskCheckResult::Rejected { reason } => anyhow::bail!("Order rejected by risk check: {}", reason),
+            RiskCheckResult::Warning { message } => warn!(symbol = %order.symbol, message = %message, "Risk warning"),
+            RiskCheckResult::Approved => {}
+        }
+        
+        let (symbol, side, quantity, algo_name) = (order.symbol.clone(), order.side, order.quantity, algo.name());
+        let order_id = self.order_manager.submit_algo_order(order, algo)?;
+        self.risk_manager.record_order(&symbol);
+        self.entry_plans.insert(order_id.clone(), EntryPlan::default());
+        info!(order_id = %order_id, symbol = %symbol, side = ?side, quantity, algo = algo_name, "Algo order submitted");
+        Ok(order_id)
+    }
+    
+    /// Record traded volume, which VWAP orders are sliced by; it trades at
+    /// the current price for the queue ahead of resting limit orders
+    pub fn update_volume(&self, symbol: &Symbol, volume: f64) {
+        if self.faults.as_ref().is_some_and(|faults| faults.feed_down(symbol, self.clock.now_ms())) {
+            return;
+        }
+        self.order_manager.record_volume(symbol, volume);
+        if let Some(price) = self.current_prices.get(symbol).map(|p| *p) {
+            self.order_manager.record_trade(symbol, price, volume);
+        }
+    }
+    
+    /// Record the displayed book of a symbol, levels as (price, size), which
+    /// resting limit orders queue behind with `queue_position` set, and
+    /// positions marked from the mid are valued at
+    pub fn update_book(&self, symbol: &Symbol, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        self.order_manager.record_book(symbol, bids, asks);
+        if let (Some((bid, _)), Some((ask, _))) = (bids.first(), asks.first()) {
+            if let Some(mark) = self.marks.on_book(symbol, *bid, *ask, self.clock.now_ms()) {
+                self.apply_mark(symbol, mark);
+            }
+        }
+    }
+    
+    /// Record the mark of a symbol's best bid and offer across venues, which
+    /// positions marked from the composite are valued at
+    pub fn update_composite_price(&self, symbol: &Symbol, price: f64) {
+        let now_ms = self.clock.now_ms();
+        if self.faults.as_ref().is_some_and(|faults| faults.feed_down(symbol, now_ms)) {
+            return;
+        }
+        if let Some(mark) = self.marks.on_composite(symbol, price, now_ms) {
+            self.apply_mark(symbol, mark);
+        }
+    }
+    
+    /// Close every open position of a group and cancel its unfilled basket
+    /// legs, returning the closing order IDs
+    pub fn close_group(&self, group_id: &str) -> Result<Vec<String>> {
+        let order_ids = groups::close_group(&self.position_manager, &self.order_manager, group_id)?;
+        info!(group_id, orders = order_ids.len(), "Closing position group");
+        Ok(order_ids)
+    }
+    
+    /// Trade towards target weights of equity, see `rebalancing`; returns
+    /// the orders submitted and the symbols left as they are
+    pub fn rebalance(&self, config: &RebalanceConfig) -> Result<RebalancePlan> {
+        Self::submit_rebalance(
+            &self.position_manager,
+            &self.order_manager,
+            &self.risk_manager,
+            &self.current_prices,
+            &self.entry_plans,
+            self.config.initial_capital,
+            config,
+        )
+    }
+    
+    fn submit_rebalance(
+        position_manager: &PositionManager,
+        order_manager: &OrderManager,
+        risk_manager: &RiskManager,
+        current_prices: &DashMap<Symbol, f64>,
+        entry_plans: &DashMap<String, EntryPlan>,
+        initial_capital: f64,
+        config: &RebalanceConfig,
+    ) -> Result<RebalancePlan> {
+        // Weights are of the equity at the latest prices, not at the last fill
+        let equity = initial_capital + position_manager.total_pnl();
+        let plan = rebalancing::rebalance(position_manager, order_manager, risk_manager, current_prices, equity, config)?;
+        for order in &plan.orders {
+            if let Some(order_id) = &order.order_id {
+                info!(order_id = %order_id, symbol = %order.symbol, side = ?order.side, quantity = order.quantity, "Rebalance order submitted");
+                entry_plans.insert(order_id.clone(), EntryPlan::default());
+            }
+        }
+        Ok(plan)
+    }
+    
+    /// Update market price, through the fault injector if any
     pub fn update_price(&self, symbol: Symbol, price: f64) {
-        self.current_prices.insert(symbol, price);
-        self.position_manager.update_prices(&self.current_prices);
+        match &self.faults {
+            Some(faults) => {
+                for (symbol, price) in faults.on_tick(&symbol, price, self.clock.now_ms()) {
+                    self.apply_price(symbol, price);
+                }
+            }
+            None => self.apply_price(symbol, price),
+        }
     }
     
+    /// Faults injected so far, when `chaos` is configured
+    pub fn fault_statistics(&self) -> Option<FaultStatistics> {
+        self.faults.as_ref().map(|faults| faults.statistics())
+    }
+    
+    fn apply_price(&self, symbol: Symbol, price: f64) {
+        self.position_manager.currency_converter().update_price(&symbol, price);
+        self.current_prices.insert(symbol.clone(), price);
+        if let Some(mark) = self.marks.on_trade(&symbol, price, self.clock.now_ms()) {
+            self.apply_mark(&symbol, mark);
+        }
+    }
+    
+    /// Value positions in a symbol at its new mark, and manage and check their exits
+    fn apply_mark(&self, symbol: &Symbol, mark: f64) {
+        self.risk_manager.record_price(symbol, mark, self.clock.now_ms());
+        
+        // Only positions in the marked symbol are marked, managed and checked;
+        // a partial profit is dropped when its position exits whole
+        self.position_manager.update_symbol_price(symbol, mark);
+        let partials = self.trade_manager.on_price(&self.position_manager, symbol, mark);
+        let mut exits = self.position_manager.check_exits_for_symbol(symbol, mark);
+        let partials: Vec<TriggeredExit> = partials
+            .into_iter()
+            .filter(|partial| !exits.iter().any(|exit| exit.position_id == partial.position_id))
+            .collect();
+        exits.extend(partials);
+        Self::submit_exits(&self.position_manager, &self.order_manager, exits);
+    }
+    
+    /// Submit closing orders for positions whose stop-loss, take-profit or time stop has triggered
+    fn enforce_exits(
+        position_manager: &PositionManager,
+        order_manager: &OrderManager,
+        marks: &DashMap<Symbol, f64>,
+    ) {
+        Self::submit_exits(position_manager, order_manager, position_manager.check_exits(marks));
+    }
+    
+    fn submit_exits(position_manager: &PositionManager, order_manager: &OrderManager, exits: Vec<TriggeredExit>) {
+        for exit in exits {
+            let side = match exit.side {
+                Side::Buy => Side::Sell,
+                Side::Sell => Side::Buy,
+            };
+            
+            let strategy = position_manager.get_position(&exit.position_id).and_then(|p| p.strategy);
+            let mut order = Order::market(exit.symbol, exit.exchange, side, exit.quantity)
+                .with_benchmarks(strategy, exit.price, exit.price);
+            order.position_id = Some(exit.position_id.clone());
+            
+            match order_manager.submit_order(order) {
+                Ok(order_id) => info!(
+                    position_id = %exit.position_id,
+                    order_id = %order_id,
+                    reason = %exit.reason,
+                    price = exit.price,
+                    "Exit triggered"
+                ),
+                Err(e) => {
+                    error!(position_id = %exit.position_id, reason = %exit.reason, error = %e, "Failed to submit exit");
+                    position_manager.clear_pending_exit(&exit.position_id);
+                }
+            }
+        }
+    }
+    
+    
     /// Spawn signal processor task
     async fn spawn_signal_processor(&mut self) -> Result<()> {
         let mut receiver = self.signal_receiver
             .take()
             .ok_or_else(|| anyhow::anyhow!("Signal receiver already taken"))?;
         
-        let position_manager = self.position_manager.clone();
-        let order_manager = self.order_manager.clone();
-        let risk_manager = self.risk_manager.clone();
-        let current_capital = self.current_capital.clone();
-        let current_prices = self.current_prices.clone();
-        let statistics = self.statistics.clone();
+        let handler = self.signal_handler();
         let running = self.running.clone();
-        let config = self.config.clone();
         
         tokio::spawn(async move {
             while *running.read().await {
                 tokio::select! {
-                    Some(signal) = receiver.recv() => {
-                        // Update statistics
-                        statistics.write().signals_processed += 1;
-                        
-                        // Process signal based on action
-                        match signal.action {
-                            SignalAction::Buy { size_hint } => {
-                                if let Err(e) = Self::handle_buy_signal(
-                                    &signal,
-                                    size_hint,
-                                    &position_manager,
-                                    &order_manager,
-                                    &risk_manager,
-                                    &current_capital,
-                                    &current_prices,
-                                    &statistics,
-                                    &config,
-                                ).await {
-                                    eprintln!("Error handling buy signal: {}", e);
-                                }
-                            }
-                            SignalAction::Sell { size_hint } => {
-                                if let Err(e) = Self::handle_sell_signal(
-                                    &signal,
-                                    size_hint,
-                                    &position_manager,
-                                    &order_manager,
-                                    &risk_manager,
-                                    &current_capital,
-                                    &current_prices,
-                                    &statistics,
-                                    &config,
-                                ).await {
-                                    eprintln!("Error handling sell signal: {}", e);
-                                }
-                            }
-                            SignalAction::Close { ref position_id } => {
-                                if let Err(e) = Self::handle_close_signal(
-                                    &signal,
-                                    position_id.clone(),
-                                    &position_manager,
-                                    &order_manager,
-                                    &current_prices,
-                                    &statistics,
-                                ).await {
-                                    eprintln!("Error handling close signal: {}", e);
-                                }
-                            }
-                            SignalAction::Hold => {
-                                // No action needed
-                            }
-                        }
+                    Some((signal, queued)) = receiver.recv() => {
+                        handler.handle(signal, queued).await;
                     }
                     _ = tokio::time::sleep(Duration::from_millis(10)) => {
                         // Continue loop
@@ -239,18 +867,47 @@ impl PaperTradingEngine {
         Ok(())
     }
     
+    /// Handle a signal right away instead of queueing it, returning what
+    /// became of it. With `process_orders_once` this drives a `stepped`
+    /// engine, e.g. in backtests.
+    pub async fn process_signal_now(&self, signal: TradingSignal) -> SignalOutcome {
+        self.signal_handler().handle(signal, Instant::now()).await
+    }
+    
+    fn signal_handler(&self) -> SignalHandler {
+        SignalHandler {
+            position_manager: self.position_manager.clone(),
+            order_manager: self.order_manager.clone(),
+            risk_manager: self.risk_manager.clone(),
+            current_capital: self.current_capital.clone(),
+            current_prices: self.current_prices.clone(),
+            statistics: self.statistics.clone(),
+            config: self.config.clone(),
+            entry_plans: self.entry_plans.clone(),
+            order_spans: self.order_spans.clone(),
+            throttle: self.throttle.clone(),
+            histograms: self.histograms.clone(),
+            events: self.events.clone(),
+            clock: self.clock.clone(),
+            outcomes: self.position_manager.outcome_publisher().clone(),
+            price_history: self.price_history.clone(),
+        }
+    }
+    
     /// Handle buy signal
     async fn handle_buy_signal(
         signal: &TradingSignal,
         size_hint: Option<f64>,
+        atr: Option<f64>,
         position_manager: &Arc<PositionManager>,
         order_manager: &Arc<OrderManager>,
         risk_manager: &Arc<RiskManager>,
         current_capital: &Arc<parking_lot::RwLock<f64>>,
         current_prices: &Arc<DashMap<Symbol, f64>>,
         statistics: &Arc<parking_lot::RwLock<TradingStatistics>>,
-        config: &PaperTradingConfig,
-    ) -> Result<()> {
+        entry_plans: &DashMap<String, EntryPlan>,
+        order_spans: &DashMap<String, Span>,
+    ) -> Result<SignalOutcome> {
         let capital = *current_capital.read();
         let price = current_prices
             .get(&signal.symbol)
@@ -264,17 +921,19 @@ impl PaperTradingEngine {
             risk_manager.calculate_position_size(&signal.symbol, capital, signal.confidence)
         };
         
-        let quantity = position_size / price;
+        // Contracts of the instrument, each worth price times its multiplier
+        let multiplier = order_manager.instruments().multiplier(&signal.symbol);
+        let quantity = position_size * signal.metadata.size_multiplier.unwrap_or(1.0) / (price * multiplier);
         
         // Risk check
-        match risk_manager.check_order(&signal.symbol, Side::Buy, quantity, price, capital) {
-            RiskCheckResult::Approved => {},
+        match risk_manager.check_order(&signal.symbol, Side::Buy, quantity * multiplier, price, capital) {
+            RiskCheckResult::Approved => debug!(quantity, price, "Risk check approved"),
             RiskCheckResult::Rejected { reason } => {
-                println!("Order rejected: {}", reason);
-                return Ok(());
+                warn!(quantity, price, reason = %reason, "Order rejected by risk check");
+                return Ok(SignalOutcome::Rejected { reason });
             }
             RiskCheckResult::Warning { message } => {
-                println!("Risk warning: {}", message);
+                warn!(quantity, price, message = %message, "Risk warning");
             }
         }
         
@@ -285,55 +944,45 @@ impl PaperTradingEngine {
             Order::limit(signal.symbol.clone(), signal.exchange, Side::Buy, quantity, price * 0.999)
         };
         
-        // Submit order
-        let order_id = order_manager.submit_order(order)?;
-        risk_manager.record_order();
-        
-        // Create stop loss and take profit if enabled
-        if config.enable_stop_loss || config.enable_take_profit {
-            let stop_price = price * (1.0 - config.risk_limits.stop_loss_pct / 100.0);
-            let tp_price = price * (1.0 + config.risk_limits.take_profit_pct / 100.0);
-            
-            if config.enable_stop_loss && config.enable_take_profit {
-                order_manager.create_bracket_order(
-                    signal.symbol.clone(),
-                    signal.exchange,
-                    Side::Buy,
-                    quantity,
-                    None,
-                    stop_price,
-                    tp_price,
-                )?;
-            }
-        }
+        // Submit order; exit levels are attached to the position once it fills
+        let order_id = order_manager.submit_order(Self::benchmarked(order, signal, price))?;
+        risk_manager.record_order(&signal.symbol);
+        Self::track_order(order_spans, &order_id, Side::Buy, quantity);
+        entry_plans.insert(order_id.clone(), EntryPlan { atr, ..EntryPlan::from_signal(signal) });
         
         statistics.write().signals_executed += 1;
         
-        Ok(())
+        Ok(SignalOutcome::Executed { order_ids: vec![order_id] })
     }
     
     /// Handle sell signal
     async fn handle_sell_signal(
         signal: &TradingSignal,
         size_hint: Option<f64>,
+        atr: Option<f64>,
         position_manager: &Arc<PositionManager>,
         order_manager: &Arc<OrderManager>,
         risk_manager: &Arc<RiskManager>,
         current_capital: &Arc<parking_lot::RwLock<f64>>,
         current_prices: &Arc<DashMap<Symbol, f64>>,
         statistics: &Arc<parking_lot::RwLock<TradingStatistics>>,
+        entry_plans: &DashMap<String, EntryPlan>,
+        order_spans: &DashMap<String, Span>,
         config: &PaperTradingConfig,
-    ) -> Result<()> {
+    ) -> Result<SignalOutcome> {
         let capital = *current_capital.read();
         let price = current_prices
             .get(&signal.symbol)
             .map(|p| *p)
             .ok_or_else(|| anyhow::anyhow!("No price for {}", signal.symbol))?;
         
-        // Check if we have a position to sell
+        let multiplier = order_manager.instruments().multiplier(&signal.symbol);
+        
+        // Check if we have a long to sell; in hedge mode sells always open or add to the short
         let net_position = position_manager.get_net_position(&signal.symbol);
+        let closes_long = !config.hedge_mode && net_position > 0.0;
         
-        let quantity = if net_position > 0.0 {
+        let quantity = if closes_long {
             // Close long position
             net_position.min(size_hint.unwrap_or(net_position))
         } else {
@@ -343,18 +992,18 @@ impl PaperTradingEngine {
             } else {
                 risk_manager.calculate_position_size(&signal.symbol, capital, signal.confidence)
             };
-            position_size / price
+            position_size * signal.metadata.size_multiplier.unwrap_or(1.0) / (price * multiplier)
         };
         
         // Risk check
-        match risk_manager.check_order(&signal.symbol, Side::Sell, quantity, price, capital) {
-            RiskCheckResult::Approved => {},
+        match risk_manager.check_order(&signal.symbol, Side::Sell, quantity * multiplier, price, capital) {
+            RiskCheckResult::Approved => debug!(quantity, price, "Risk check approved"),
             RiskCheckResult::Rejected { reason } => {
-                println!("Order rejected: {}", reason);
-                return Ok(());
+                warn!(quantity, price, reason = %reason, "Order rejected by risk check");
+                return Ok(SignalOutcome::Rejected { reason });
             }
             RiskCheckResult::Warning { message } => {
-                println!("Risk warning: {}", message);
+                warn!(quantity, price, message = %message, "Risk warning");
             }
         }
         
@@ -366,12 +1015,92 @@ impl PaperTradingEngine {
         };
         
         // Submit order
-        order_manager.submit_order(order)?;
-        risk_manager.record_order();
+        let order_id = order_manager.submit_order(Self::benchmarked(order, signal, price))?;
+        risk_manager.record_order(&signal.symbol);
+        Self::track_order(order_spans, &order_id, Side::Sell, quantity);
+        if !closes_long {
+            entry_plans.insert(order_id.clone(), EntryPlan { atr, ..EntryPlan::from_signal(signal) });
+        }
         
         statistics.write().signals_executed += 1;
         
-        Ok(())
+        Ok(SignalOutcome::Executed { order_ids: vec![order_id] })
+    }
+    
+    /// Handle scale-in / scale-out signal against the largest open position in the symbol
+    async fn handle_scale_signal(
+        signal: &TradingSignal,
+        fraction: f64,
+        scale_in: bool,
+        position_manager: &Arc<PositionManager>,
+        order_manager: &Arc<OrderManager>,
+        risk_manager: &Arc<RiskManager>,
+        current_capital: &Arc<parking_lot::RwLock<f64>>,
+        current_prices: &Arc<DashMap<Symbol, f64>>,
+        statistics: &Arc<parking_lot::RwLock<TradingStatistics>>,
+        order_spans: &DashMap<String, Span>,
+    ) -> Result<SignalOutcome> {
+        if !fraction.is_finite() || fraction <= 0.0 {
+            anyhow::bail!("Invalid scale fraction {}", fraction);
+        }
+        
+        let price = current_prices
+            .get(&signal.symbol)
+            .map(|p| *p)
+            .ok_or_else(|| anyhow::anyhow!("No price for {}", signal.symbol))?;
+        
+        let position = position_manager
+            .get_open_positions_by_symbol(&signal.symbol)
+            .into_iter()
+            .max_by(|a, b| a.quantity.partial_cmp(&b.quantity).unwrap_or(std::cmp::Ordering::Equal))
+            .ok_or_else(|| anyhow::anyhow!("No open position in {} to scale", signal.symbol))?;
+        
+        let (side, quantity) = if scale_in {
+            let quantity = position.quantity * fraction;
+            
+            // Only the added exposure needs to pass risk checks
+            let capital = *current_capital.read();
+            match risk_manager.check_order(&signal.symbol, position.side, quantity * position.multiplier, price, capital) {
+                RiskCheckResult::Approved => debug!(quantity, price, "Risk check approved"),
+                RiskCheckResult::Rejected { reason } => {
+                    warn!(position_id = %position.id, quantity, price, reason = %reason, "Scale-in rejected by risk check");
+                    return Ok(SignalOutcome::Rejected { reason });
+                }
+                RiskCheckResult::Warning { message } => {
+                    warn!(quantity, price, message = %message, "Risk warning");
+                }
+            }
+            
+            (position.side, quantity)
+        } else {
+            let side = match position.side {
+                Side::Buy => Side::Sell,
+                Side::Sell => Side::Buy,
+            };
+            (side, position.quantity * fraction.min(1.0))
+        };
+        
+        let mut order = if signal.urgency > 0.8 {
+            Order::market(signal.symbol.clone(), signal.exchange, side, quantity)
+        } else {
+            let limit_price = match side {
+                Side::Buy => price * 0.999,
+                Side::Sell => price * 1.001,
+            };
+            Order::limit(signal.symbol.clone(), signal.exchange, side, quantity, limit_price)
+        };
+        order.position_id = Some(position.id.clone());
+        
+        let order_id = order_manager.submit_order(Self::benchmarked(order, signal, price))?;
+        risk_manager.record_order(&signal.symbol);
+        Self::track_order(order_spans, &order_id, side, quantity);
+        if !scale_in && fraction >= 1.0 {
+            position_manager.mark_pending_exit(&position.id, ExitReason::Signal);
+        }
+        
+        statistics.write().signals_executed += 1;
+        
+        Ok(SignalOutcome::Executed { order_ids: vec![order_id] })
     }
     
     /// Handle close signal
@@ -382,121 +1111,469 @@ impl PaperTradingEngine {
         order_manager: &Arc<OrderManager>,
         current_prices: &Arc<DashMap<Symbol, f64>>,
         statistics: &Arc<parking_lot::RwLock<TradingStatistics>>,
-    ) -> Result<()> {
+        order_spans: &DashMap<String, Span>,
+    ) -> Result<SignalOutcome> {
         let price = current_prices
             .get(&signal.symbol)
             .map(|p| *p)
             .ok_or_else(|| anyhow::anyhow!("No price for {}", signal.symbol))?;
         
+        // Positions already closed or with an exit in flight are left alone,
+        // otherwise a second closing order would trade against nothing
+        let closable = |p: &Position| p.status != PositionStatus::Closed && position_manager.pending_exit_reason(&p.id).is_none();
+        let mut order_ids = Vec::new();
+        
         if let Some(id) = position_id {
             // Close specific position
-            if let Some(position) = position_manager.get_position(&id) {
+            if let Some(position) = position_manager.get_position(&id).filter(closable) {
                 let side = match position.side {
                     Side::Buy => Side::Sell,
                     Side::Sell => Side::Buy,
                 };
                 
-                let order = Order::market(
+                let mut order = Order::market(
                     position.symbol,
                     position.exchange,
                     side,
                     position.quantity
                 );
+                order.position_id = Some(position.id.clone());
                 
-                order_manager.submit_order(order)?;
+                let order_id = order_manager.submit_order(Self::benchmarked(order, signal, price))?;
+                Self::track_order(order_spans, &order_id, side, position.quantity);
+                position_manager.mark_pending_exit(&position.id, ExitReason::Signal);
+                order_ids.push(order_id);
             }
         } else {
             // Close all positions for symbol
             let positions = position_manager.get_open_positions_by_symbol(&signal.symbol);
             
-            for position in positions {
+            for position in positions.into_iter().filter(closable) {
                 let side = match position.side {
                     Side::Buy => Side::Sell,
                     Side::Sell => Side::Buy,
                 };
                 
-                let order = Order::market(
+                let mut order = Order::market(
                     position.symbol,
                     position.exchange,
                     side,
                     position.quantity
                 );
+                order.position_id = Some(position.id.clone());
                 
-                order_manager.submit_order(order)?;
+                let order_id = order_manager.submit_order(Self::benchmarked(order, signal, price))?;
+                Self::track_order(order_spans, &order_id, side, position.quantity);
+                position_manager.mark_pending_exit(&position.id, ExitReason::Signal);
+                order_ids.push(order_id);
             }
         }
         
         statistics.write().signals_executed += 1;
         
-        Ok(())
+        if order_ids.is_empty() {
+            return Ok(SignalOutcome::Ignored { reason: "no open position to close".to_string() });
+        }
+        Ok(SignalOutcome::Executed { order_ids })
+    }
+    
+    /// `order` with the signal's strategy and decision price, arriving at `price`
+    fn benchmarked(order: Order, signal: &TradingSignal, price: f64) -> Order {
+        order.with_benchmarks(signal.metadata.strategy.clone(), signal.metadata.decision_price.unwrap_or(price), price)
+    }
+    
+    /// Remember the current signal span for an order so its fill is logged under it
+    fn track_order(order_spans: &DashMap<String, Span>, order_id: &str, side: Side, quantity: f64) {
+        info!(order_id, side = ?side, quantity, "Order submitted");
+        order_spans.insert(order_id.to_string(), Span::current());
     }
     
     /// Spawn order processor task
     async fn spawn_order_processor(&self) -> Result<()> {
         let order_manager = self.order_manager.clone();
         let position_manager = self.position_manager.clone();
+        let risk_manager = self.risk_manager.clone();
         let current_prices = self.current_prices.clone();
+        let marks = self.marks.clone();
         let current_capital = self.current_capital.clone();
         let running = self.running.clone();
+        let config = self.config.clone();
+        let entry_plans = self.entry_plans.clone();
+        let order_spans = self.order_spans.clone();
+        let histograms = self.histograms.clone();
+        let venue = self.venue.clone();
+        let venue_orders = DashMap::new(); // Local order ID -> venue order ID
         let update_interval = self.config.update_interval;
         
         tokio::spawn(async move {
             while *running.read().await {
-                // Process pending orders
-                if let Ok(filled_orders) = order_manager.process_orders(&current_prices) {
+                // Fill pending orders, at the venue or in simulation
+                let filled_orders = match &venue {
+                    Some(venue) => Ok(execution::sync_venue_orders(
+                        venue.as_ref(),
+                        &order_manager,
+                        &venue_orders,
+                        &current_prices,
+                    ).await),
+                    None => order_manager.process_orders(&current_prices),
+                };
+                if let Ok(filled_orders) = filled_orders {
+                    let any_filled = !filled_orders.is_empty();
                     for order_id in filled_orders {
                         if let Some(order) = order_manager.get_order(&order_id) {
-                            // Update positions
-                            match order.side {
-                                Side::Buy => {
-                                    position_manager.open_position(
-                                        order.symbol,
-                                        order.exchange,
-                                        order.side,
-                                        order.filled_quantity,
-                                        order.avg_fill_price,
-                                        order.commission,
-                                        order.slippage,
-                                    ).ok();
-                                }
-                                Side::Sell => {
-                                    // Check if closing existing position
-                                    let positions = position_manager.get_open_positions_by_symbol(&order.symbol);
-                                    if !positions.is_empty() {
-                                        // Close position
-                                        for pos in positions {
-                                            if pos.side == Side::Buy {
-                                                position_manager.close_position(
-                                                    &pos.id,
-                                                    order.avg_fill_price,
-                                                    order.commission,
-                                                    order.slippage,
-                                                ).ok();
-                                                break;
-                                            }
-                                        }
-                                    } else {
-                                        // Open short position
-                                        position_manager.open_position(
-                                            order.symbol,
-                                            order.exchange,
-                                            order.side,
-                                            order.filled_quantity,
-                                            order.avg_fill_price,
-                                            order.commission,
-                                            order.slippage,
-                                        ).ok();
-                                    }
-                                }
-                            }
+                            let span = order_spans
+                                .remove(&order_id)
+                                .map(|(_, span)| span)
+                                .unwrap_or_else(Span::none);
+                            let _entered = span.enter();
+                            info!(
+                                order_id = %order_id,
+                                symbol = %order.symbol,
+                                side = ?order.side,
+                                quantity = order.filled_quantity,
+                                price = order.avg_fill_price,
+                                commission = order.commission,
+                                position_id = order.position_id.as_deref().unwrap_or(""),
+                                "Order filled"
+                            );
+                            histograms.record_fill(&order);
                             
-                            // Update capital
-                            let mut capital = current_capital.write();
-                            *capital -= order.commission + order.slippage;
+                            Self::settle_fill(&position_manager, &order_manager, &current_capital, &config, &risk_manager.get_limits(), &entry_plans, &order);
                         }
                     }
+                    if any_filled {
+                        Self::recheck_risk(&position_manager, &order_manager, &risk_manager, marks.prices(), &current_capital);
+                    }
                 }
                 
+                // Drop plans and spans for orders that will never fill
+                let is_active = |order_id: &String| {
+                    order_manager.get_order(order_id).is_some_and(|o| matches!(
+                        o.status,
+                        OrderStatus::Pending | OrderStatus::Submitted | OrderStatus::PartiallyFilled
+                    ))
+                };
+                entry_plans.retain(|order_id, _| is_active(order_id));
+                order_spans.retain(|order_id, _| is_active(order_id));
+                
+                // Time stops fire even when no new prices arrive
+                Self::enforce_exits(&position_manager, &order_manager, marks.prices());
+                
+                tokio::time::sleep(update_interval).await;
+            }
+        });
+        
+        Ok(())
+    }
+    
+    /// Book a fill with the plan its order was submitted with. Children of an
+    /// algo order share their parent's plan, and once a fill opens a position
+    /// the later fills of the order, or of its siblings, add to it.
+    fn settle_fill(
+        position_manager: &PositionManager,
+        order_manager: &OrderManager,
+        current_capital: &parking_lot::RwLock<f64>,
+        config: &PaperTradingConfig,
+        limits: &RiskLimits,
+        entry_plans: &DashMap<String, EntryPlan>,
+        order: &Order,
+    ) {
+        let partial = order.status == OrderStatus::PartiallyFilled;
+        let own_plan = if partial {
+            entry_plans.get(&order.id).map(|p| p.clone())
+        } else {
+            entry_plans.remove(&order.id).map(|(_, plan)| plan)
+        };
+        let plan = own_plan
+            .or_else(|| order.parent_order_id.as_ref().and_then(|parent| entry_plans.get(parent).map(|p| p.clone())))
+            .unwrap_or_default();
+        let Some(position_id) = Self::book_fill(position_manager, current_capital, config, limits, &plan, order) else {
+            return;
+        };
+        if partial {
+            order_manager.set_order_position(&order.id, &position_id);
+        } else {
+            order_manager.set_filled_position(&order.id, &position_id);
+        }
+        if let Some(parent_id) = &order.parent_order_id {
+            order_manager.set_order_position(parent_id, &position_id);
+        }
+    }
+    
+    /// Apply an order's latest fill to positions and capital, returning the
+    /// position it opened, if any.
+    /// An order tied to a position scales it in, reduces it or closes it; any
+    /// quantity left over, e.g. because the position was already closed by a
+    /// stop, is booked like an untied fill. In one-way mode untied fills net
+    /// against opposite positions first, oldest first, and the rest opens a
+    /// new position (or adds to the existing one in hedge mode).
+    fn book_fill(
+        position_manager: &PositionManager,
+        current_capital: &parking_lot::RwLock<f64>,
+        config: &PaperTradingConfig,
+        limits: &RiskLimits,
+        plan: &EntryPlan,
+        order: &Order,
+    ) -> Option<String> {
+        // Orders filled outside `Order::fill` are booked whole
+        let fill = order.last_fill.unwrap_or(Fill {
+            quantity: order.filled_quantity,
+            price: order.avg_fill_price,
+            commission: order.commission,
+            slippage: order.slippage,
+            time: order.updated_time,
+        });
+        let filled = fill.quantity;
+        if filled <= 0.0 {
+            return None;
+        }
+        // Costs are shared out in proportion to the quantity each step books
+        let costs = |quantity: f64| (fill.commission * quantity / filled, fill.slippage * quantity / filled);
+        let mut remaining = filled;
+        let mut opened = None;
+        
+        let tied = order.position_id
+            .as_ref()
+            .and_then(|id| position_manager.get_position(id))
+            .filter(|pos| pos.status != PositionStatus::Closed);
+        if let Some(pos) = tied {
+            if pos.side == order.side {
+                position_manager.add_to_position(&pos.id, remaining, fill.price, fill.commission, fill.slippage).ok();
+                remaining = 0.0;
+            } else {
+                let reason = position_manager.pending_exit_reason(&pos.id).unwrap_or(ExitReason::Signal);
+                remaining -= Self::reduce_position(position_manager, &pos, remaining, fill.price, costs, reason);
+            }
+        }
+        
+        if remaining > filled * 1e-9 && !config.hedge_mode {
+            let opposite: Vec<Position> = position_manager
+                .get_open_positions_by_symbol(&order.symbol)
+                .into_iter()
+                .filter(|pos| pos.side != order.side)
+                .collect();
+            for pos in opposite {
+                if remaining <= filled * 1e-9 {
+                    break;
+                }
+                remaining -= Self::reduce_position(position_manager, &pos, remaining, fill.price, costs, ExitReason::Signal);
+            }
+        }
+        
+        if remaining > filled * 1e-9 {
+            let (commission, slippage) = costs(remaining);
+            let hedge_position = if config.hedge_mode {
+                position_manager
+                    .get_open_positions_by_direction(&order.symbol, order.side)
+                    .into_iter()
+                    .next()
+            } else {
+                None
+            };
+            
+            if let Some(pos) = hedge_position {
+                position_manager.add_to_position(&pos.id, remaining, fill.price, commission, slippage).ok();
+            } else if let Ok(id) = position_manager.open_position(
+                order.symbol.clone(),
+                order.exchange,
+                order.side,
+                remaining,
+                fill.price,
+                commission,
+                slippage,
+            ) {
+                Self::attach_exit_levels(position_manager, config, limits, plan, &id, order.side, fill.price);
+                opened = Some(id);
+            }
+        }
+        
+        // Capital follows the books, so fill costs are never counted twice
+        *current_capital.write() = config.initial_capital + position_manager.total_pnl();
+        opened
+    }
+    
+    /// Close up to `quantity` of a position, returning the quantity closed
+    fn reduce_position(
+        position_manager: &PositionManager,
+        pos: &Position,
+        quantity: f64,
+        price: f64,
+        costs: impl Fn(f64) -> (f64, f64),
+        reason: ExitReason,
+    ) -> f64 {
+        let closed = quantity.min(pos.quantity);
+        let (commission, slippage) = costs(closed);
+        let result = if closed < pos.quantity * (1.0 - 1e-9) {
+            position_manager.partial_close_position(&pos.id, closed, price, commission, slippage, reason)
+        } else {
+            position_manager.close_position(&pos.id, price, commission, slippage, reason)
+        };
+        if result.is_ok() { closed } else { 0.0 }
+    }
+    
+    /// Fill triggered orders at the current prices and book them into
+    /// positions, once. The order processor task does this every update
+    /// interval; tests and step-wise simulations can call it directly.
+    pub fn process_orders_once(&self) -> Result<Vec<String>> {
+        let filled = self.order_manager.process_orders(&self.current_prices)?;
+        for order_id in &filled {
+            if let Some(order) = self.order_manager.get_order(order_id) {
+                self.histograms.record_fill(&order);
+                Self::settle_fill(&self.position_manager, &self.order_manager, &self.current_capital, &self.config, &self.risk_manager.get_limits(), &self.entry_plans, &order);
+            }
+        }
+        if !filled.is_empty() {
+            Self::recheck_risk(&self.position_manager, &self.order_manager, &self.risk_manager, self.marks.prices(), &self.current_capital);
+        }
+        Ok(filled)
+    }
+    
+    /// Gross notional of the open positions at their marks, in the reporting currency
+    fn total_exposure(position_manager: &PositionManager, marks: &DashMap<Symbol, f64>) -> f64 {
+        position_manager
+            .get_open_positions()
+            .iter()
+            .map(|p| {
+                let notional = p.quantity * p.multiplier * marks.get(&p.symbol).map(|pr| *pr).unwrap_or(0.0);
+                position_manager.currency_converter().to_reporting(&p.symbol, notional)
+            })
+            .sum()
+    }
+    
+    /// After fills, bring exposure and leverage up to date and cancel resting
+    /// orders that would now take the account over its limits. Orders that
+    /// close or reduce a position are left alone.
+    fn recheck_risk(
+        position_manager: &PositionManager,
+        order_manager: &OrderManager,
+        risk_manager: &RiskManager,
+        marks: &DashMap<Symbol, f64>,
+        current_capital: &parking_lot::RwLock<f64>,
+    ) {
+        let equity = *current_capital.read();
+        risk_manager.update_exposure(Self::total_exposure(position_manager, marks), equity);
+        risk_manager.update_portfolio_heat(position_manager.capital_at_risk(marks), equity);
+        
+        let mut resting: Vec<Order> = order_manager
+            .get_active_orders()
+            .into_iter()
+            .filter(|o| matches!(o.order_type, OrderType::Market | OrderType::Limit | OrderType::StopLimit))
+            .filter(|o| o.parent_order_id.is_none())
+            .filter(|o| match &o.position_id {
+                Some(id) => position_manager.get_position(id).is_some_and(|p| p.status != PositionStatus::Closed && p.side == o.side),
+                None => true,
+            })
+            .collect();
+        resting.sort_by(|a, b| a.created_time.cmp(&b.created_time).then_with(|| a.id.cmp(&b.id)));
+        let notionals: Vec<(String, f64)> = resting
+            .iter()
+            .filter_map(|o| {
+                let price = o.price.or_else(|| marks.get(&o.symbol).map(|p| *p))?;
+                let notional = (o.quantity - o.filled_quantity) * price;
+                Some((o.id.clone(), position_manager.currency_converter().to_reporting(&o.symbol, notional)))
+            })
+            .collect();
+        
+        for (order_id, reason) in risk_manager.check_resting_orders(&notionals, equity) {
+            match order_manager.cancel_order(&order_id) {
+                Ok(()) => warn!(order_id = %order_id, reason = %reason, "Resting order cancelled by risk check"),
+                Err(e) => error!(order_id = %order_id, error = %e, "Failed to cancel resting order"),
+            }
+        }
+    }
+    
+    /// Attach the stop-loss / take-profit levels and time stop to a newly
+    /// opened position, and tag it with its signal. Levels set by the signal
+    /// win over the ATR multiples or percentages in the current `limits`
+    /// unless the fill is already past them.
+    fn attach_exit_levels(
+        position_manager: &PositionManager,
+        config: &PaperTradingConfig,
+        limits: &RiskLimits,
+        plan: &EntryPlan,
+        position_id: &str,
+        side: Side,
+        entry_price: f64,
+    ) {
+        let stop_pct = limits.stop_loss_pct / 100.0;
+        let tp_pct = limits.take_profit_pct / 100.0;
+        
+        let stops = config.stops_for(plan.strategy.as_deref());
+        let (stop_loss, take_profit) = match plan.atr.and_then(|atr| stops.levels(side, entry_price, atr)) {
+            Some(levels) => levels,
+            None => match side {
+                Side::Buy => (entry_price * (1.0 - stop_pct), entry_price * (1.0 + tp_pct)),
+                Side::Sell => (entry_price * (1.0 + stop_pct), entry_price * (1.0 - tp_pct)),
+            },
+        };
+        let sign = if side == Side::Buy { 1.0 } else { -1.0 };
+        let stop_loss = plan.stop_loss.filter(|stop| (entry_price - stop) * sign > 0.0).unwrap_or(stop_loss);
+        let take_profit = plan.take_profit.filter(|target| (target - entry_price) * sign > 0.0).unwrap_or(take_profit);
+        
+        let stop_loss = config.enable_stop_loss.then_some(stop_loss);
+        let take_profit = config.enable_take_profit.then_some(take_profit);
+        
+        if stop_loss.is_some() || take_profit.is_some() {
+            if let Err(e) = position_manager.modify_position_exits(position_id, stop_loss, take_profit) {
+                error!(position_id, error = %e, "Failed to attach exit levels");
+            }
+        }
+        
+        if let Some(signal_id) = &plan.signal_id {
+            position_manager.set_position_signal(position_id, signal_id.as_str()).ok();
+        }
+        if let Some(strategy) = &plan.strategy {
+            position_manager.set_position_strategy(position_id, strategy.as_str()).ok();
+        }
+        if let Some(confidence) = plan.confidence {
+            position_manager.set_position_confidence(position_id, confidence).ok();
+        }
+        if let Some(group_id) = &plan.group_id {
+            position_manager.set_position_group(position_id, group_id.as_str()).ok();
+        }
+        
+        let max_hold = plan.max_hold.or(config.max_holding_period);
+        if max_hold.is_some() {
+            if let Err(e) = position_manager.set_max_hold(position_id, max_hold) {
+                error!(position_id, error = %e, "Failed to set time stop");
+            }
+        }
+    }
+    
+    /// Spawn the task rebalancing every `interval_secs` of engine time, the
+    /// first time once every target has a price
+    async fn spawn_rebalancer(&self, rebalance: RebalanceConfig) -> Result<()> {
+        let position_manager = self.position_manager.clone();
+        let order_manager = self.order_manager.clone();
+        let risk_manager = self.risk_manager.clone();
+        let current_prices = self.current_prices.clone();
+        let entry_plans = self.entry_plans.clone();
+        let running = self.running.clone();
+        let clock = self.clock.clone();
+        let initial_capital = self.config.initial_capital;
+        let update_interval = self.config.update_interval;
+        let interval_ms = rebalance.interval_secs.unwrap_or_default() * 1000;
+        let symbols: Vec<Symbol> = rebalance.targets.keys().map(Symbol::new).collect();
+        
+        tokio::spawn(async move {
+            let mut last_ms: Option<u64> = None;
+            while *running.read().await {
+                let now_ms = clock.now_ms();
+                let due = last_ms.is_none_or(|last| now_ms.saturating_sub(last) >= interval_ms);
+                if due && symbols.iter().all(|symbol| current_prices.contains_key(symbol)) {
+                    if let Err(e) = Self::submit_rebalance(
+                        &position_manager,
+                        &order_manager,
+                        &risk_manager,
+                        &current_prices,
+                        &entry_plans,
+                        initial_capital,
+                        &rebalance,
+                    ) {
+                        error!(error = %e, "Scheduled rebalance failed");
+                    }
+                    last_ms = Some(now_ms);
+                }
                 tokio::time::sleep(update_interval).await;
             }
         });
@@ -507,20 +1584,23 @@ impl PaperTradingEngine {
     /// Spawn statistics updater task
     async fn spawn_statistics_updater(&self) -> Result<()> {
         let position_manager = self.position_manager.clone();
+        let order_manager = self.order_manager.clone();
         let risk_manager = self.risk_manager.clone();
         let current_capital = self.current_capital.clone();
-        let current_prices = self.current_prices.clone();
+        let marks = self.marks.clone();
         let statistics = self.statistics.clone();
         let returns_history = self.returns_history.clone();
         let running = self.running.clone();
         let initial_capital = self.config.initial_capital;
+        let clock = self.clock.clone();
         
         tokio::spawn(async move {
-            let mut last_capital = initial_capital;
+            let mut last_capital = *current_capital.read(); // Not the initial capital after a restore
+            let mut rolling = RollingStatistics::new();
             
             while *running.read().await {
-                // Update position prices
-                position_manager.update_prices(&current_prices);
+                // Mark positions
+                position_manager.update_prices(marks.prices());
                 
                 // Get position statistics
                 let pos_stats = position_manager.get_statistics();
@@ -531,6 +1611,36 @@ impl PaperTradingEngine {
                 let total_pnl = realized_pnl + unrealized_pnl;
                 let current_cap = initial_capital + total_pnl;
                 
+                // Equity stop-out: force-close the worst loser not already on its way out
+                let candidates: Vec<Position> = position_manager
+                    .get_open_positions()
+                    .into_iter()
+                    .filter(|p| position_manager.pending_exit_reason(&p.id).is_none())
+                    .collect();
+                if let Some(position) = risk_manager.check_equity_stop_out(current_cap, &candidates) {
+                    let side = match position.side {
+                        Side::Buy => Side::Sell,
+                        Side::Sell => Side::Buy,
+                    };
+                    let mut order = Order::market(position.symbol.clone(), position.exchange, side, position.quantity);
+                    order.position_id = Some(position.id.clone());
+                    
+                    match order_manager.submit_order(order) {
+                        Ok(order_id) => {
+                            warn!(
+                                position_id = %position.id,
+                                order_id = %order_id,
+                                symbol = %position.symbol,
+                                unrealized_pnl = position.unrealized_pnl,
+                                equity = current_cap,
+                                "Equity stop-out"
+                            );
+                            position_manager.mark_pending_exit(&position.id, ExitReason::StopOut);
+                        }
+                        Err(e) => error!(position_id = %position.id, error = %e, "Failed to submit stop-out"),
+                    }
+                }
+                
                 // Calculate return
                 let return_pct = if last_capital > 0.0 {
                     (current_cap - last_capital) / last_capital
@@ -538,29 +1648,18 @@ impl PaperTradingEngine {
                     0.0
                 };
                 
-                // Update returns history
-                {
-                    let mut returns = returns_history.write();
-                    returns.push(return_pct);
-                    if returns.len() > 1000 {
-                        returns.remove(0);
-                    }
-                }
+                returns_history.write().push(return_pct);
                 
-                // Calculate total exposure
-                let positions = position_manager.get_open_positions();
-                let total_exposure: f64 = positions.iter()
-                    .map(|p| p.quantity * current_prices.get(&p.symbol).map(|pr| *pr).unwrap_or(0.0))
-                    .sum();
+                let total_exposure = Self::total_exposure(&position_manager, marks.prices());
                 
                 // Update risk metrics
-                let returns_copy = returns_history.read().clone();
                 risk_manager.update_metrics(
                     current_cap,
                     total_exposure,
                     realized_pnl,
-                    &returns_copy
+                    &returns_history.read()
                 );
+                risk_manager.update_portfolio_heat(position_manager.capital_at_risk(marks.prices()), current_cap);
                 
                 // Update Kelly parameters if we have enough data
                 if pos_stats.winning_positions + pos_stats.losing_positions > 20 {
@@ -571,6 +1670,16 @@ impl PaperTradingEngine {
                     );
                 }
                 
+                rolling.record(
+                    clock.now_ms(),
+                    RollingSample {
+                        equity: current_cap,
+                        winning_trades: pos_stats.winning_positions,
+                        losing_trades: pos_stats.losing_positions,
+                        traded_volume: order_manager.traded_volume(),
+                    },
+                );
+                
                 // Update statistics
                 {
                     let mut stats = statistics.write();
@@ -579,6 +1688,7 @@ impl PaperTradingEngine {
                     stats.total_return_pct = ((current_cap - initial_capital) / initial_capital) * 100.0;
                     stats.position_stats = pos_stats;
                     stats.risk_metrics = risk_manager.get_metrics();
+                    stats.rolling = rolling.snapshot();
                 }
                 
                 // Update current capital
@@ -594,9 +1704,163 @@ impl PaperTradingEngine {
         Ok(())
     }
     
+    /// Initial capital plus realized and unrealized P&L, as of the last fill or statistics update
+    pub fn capital(&self) -> f64 {
+        *self.current_capital.read()
+    }
+    
+    /// Equity returns of the latest statistics updates, as fractions, oldest first
+    pub fn returns_history(&self) -> Vec<f64> {
+        self.returns_history.read().returns()
+    }
+    
     /// Get current statistics
     pub fn get_statistics(&self) -> TradingStatistics {
-        self.statistics.read().clone()
+        let mut stats = self.statistics.read().clone();
+        stats.signal_queue = self.signal_sender.statistics();
+        stats.order_event_queue = self.order_manager.event_queue_statistics();
+        stats.signals_throttled = self.throttle.statistics();
+        stats
+    }
+    
+    /// Statistics with the P&L broken down by symbol, exchange and entry time
+    pub fn get_statistics_detailed(&self) -> DetailedStatistics {
+        DetailedStatistics {
+            statistics: self.get_statistics(),
+            attribution: self.position_manager.pnl_attribution(),
+        }
+    }
+    
+    /// Latest price of a symbol
+    pub fn market_price(&self, symbol: &Symbol) -> Option<f64> {
+        self.current_prices.get(symbol).map(|price| *price)
+    }
+    
+    /// Price positions in a symbol are valued at, per its `marks` policy
+    pub fn mark_price(&self, symbol: &Symbol) -> Option<f64> {
+        self.marks.get(symbol)
+    }
+    
+    /// Loss if every open position hit its stop, as a fraction of equity
+    pub fn portfolio_heat(&self) -> f64 {
+        let equity = *self.current_capital.read();
+        if equity <= 0.0 {
+            return 0.0;
+        }
+        self.position_manager.capital_at_risk(self.marks.prices()) / equity
+    }
+    
+    /// What the open positions would gain or lose under each scenario, at
+    /// current prices and equity
+    pub fn run_scenarios(&self, scenarios: &[Scenario]) -> ScenarioReport {
+        let converter = self.position_manager.currency_converter();
+        let positions: Vec<ScenarioPosition> = self.position_manager
+            .get_open_positions()
+            .into_iter()
+            .map(|p| ScenarioPosition {
+                price: self.marks.get(&p.symbol).unwrap_or(p.entry_price),
+                daily_volatility: self.risk_manager.daily_volatility(&p.symbol),
+                fx_rate: converter.to_reporting(&p.symbol, 1.0),
+                position_id: p.id,
+                symbol: p.symbol,
+                side: p.side,
+                quantity: p.quantity * p.multiplier, // In units of the price
+            })
+            .collect();
+        self.risk_manager.run_scenarios(scenarios, &positions, *self.current_capital.read(), self.clock.now_ms())
+    }
+    
+    /// Full state of the engine, to checkpoint a run or fork it. Take it
+    /// between signals: signals still queued are not included.
+    pub fn snapshot(&self) -> EngineSnapshot {
+        let mut prices: Vec<(Symbol, f64)> = self.current_prices.iter().map(|e| (e.key().clone(), *e.value())).collect();
+        prices.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        let mut entry_plans: Vec<(String, EntryPlan)> = self.entry_plans.iter().map(|e| (e.key().clone(), e.value().clone())).collect();
+        entry_plans.sort_by(|a, b| a.0.cmp(&b.0));
+        let (signals_processed, signals_executed) = {
+            let stats = self.statistics.read();
+            (stats.signals_processed, stats.signals_executed)
+        };
+        
+        EngineSnapshot {
+            version: SNAPSHOT_VERSION,
+            taken_at: self.clock.now_ms(),
+            initial_capital: self.config.initial_capital,
+            capital: *self.current_capital.read(),
+            id_seed: self.config.ids_seed(),
+            rng_draws: self.rng.draws(),
+            prices,
+            positions: self.position_manager.snapshot(),
+            orders: self.order_manager.snapshot(),
+            risk: self.risk_manager.snapshot(),
+            throttle: self.throttle.snapshot(),
+            returns_history: self.returns_history.read().returns(),
+            signals_processed,
+            signals_executed,
+            entry_plans,
+        }
+    }
+    
+    /// Replace this engine's state with a snapshot, before `start`. The engine
+    /// must have the initial capital and ID seed the snapshot was taken with.
+    /// The clock is left alone: move a simulated clock to `taken_at` to resume
+    /// at the same time. Rolling-window statistics start afresh.
+    pub fn restore(&self, snapshot: &EngineSnapshot) -> Result<()> {
+        if self.running.try_read().map_or(true, |running| *running) {
+            anyhow::bail!("Stop the engine before restoring a snapshot");
+        }
+        if snapshot.version != SNAPSHOT_VERSION {
+            anyhow::bail!("Snapshot version {} is not supported, expected {}", snapshot.version, SNAPSHOT_VERSION);
+        }
+        if snapshot.initial_capital != self.config.initial_capital {
+            anyhow::bail!(
+                "Snapshot was taken with initial capital {}, engine has {}",
+                snapshot.initial_capital, self.config.initial_capital
+            );
+        }
+        if snapshot.id_seed != self.config.ids_seed() {
+            anyhow::bail!("Snapshot was taken with ID seed {:?}, engine has {:?}", snapshot.id_seed, self.config.ids_seed());
+        }
+        self.rng.set_draws(snapshot.rng_draws);
+        
+        self.current_prices.clear();
+        self.marks.clear();
+        for (symbol, price) in &snapshot.prices {
+            self.position_manager.currency_converter().update_price(symbol, *price);
+            self.current_prices.insert(symbol.clone(), *price);
+            self.marks.on_trade(symbol, *price, snapshot.taken_at);
+        }
+        self.position_manager.restore(&snapshot.positions);
+        self.order_manager.restore(&snapshot.orders);
+        self.risk_manager.restore(&snapshot.risk);
+        self.throttle.restore(&snapshot.throttle);
+        
+        self.entry_plans.clear();
+        for (order_id, plan) in &snapshot.entry_plans {
+            self.entry_plans.insert(order_id.clone(), plan.clone());
+        }
+        self.order_spans.clear();
+        let mut returns_history = self.returns_history.write();
+        returns_history.clear();
+        returns_history.extend(snapshot.returns_history.iter().copied());
+        drop(returns_history);
+        *self.current_capital.write() = snapshot.capital;
+        
+        let mut stats = self.statistics.write();
+        stats.capital = snapshot.capital;
+        stats.total_pnl = snapshot.capital - snapshot.initial_capital;
+        stats.total_return_pct = stats.total_pnl / snapshot.initial_capital * 100.0;
+        stats.position_stats = self.position_manager.get_statistics();
+        stats.risk_metrics = snapshot.risk.metrics.clone();
+        stats.signals_processed = snapshot.signals_processed;
+        stats.signals_executed = snapshot.signals_executed;
+        stats.rolling = Vec::new();
+        Ok(())
+    }
+    
+    /// Get engine configuration
+    pub fn config(&self) -> &PaperTradingConfig {
+        &self.config
     }
     
     /// Get position manager
@@ -619,11 +1883,14 @@ impl PaperTradingEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::paper_trading::{FaultKind, MarkSource, RandomFaults, RiskEvent, ScheduledFault};
     
     #[tokio::test]
     async fn test_paper_trading_engine() {
         let config = PaperTradingConfig::default();
         let mut engine = PaperTradingEngine::new(config);
+        let histograms = Arc::new(TradingHistograms::default());
+        engine.set_histograms(histograms.clone());
         
         engine.start().await.unwrap();
         
@@ -648,7 +1915,280 @@ mod tests {
         // Check statistics
         let stats = engine.get_statistics();
         assert_eq!(stats.signals_processed, 1);
+        assert_eq!(histograms.signal_to_order_ms.snapshot().count, 1);
         
         engine.stop().await.unwrap();
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_closed_position_reports_its_signal() {
+        let mut engine = PaperTradingEngine::new(PaperTradingConfig::default());
+        let mut outcomes = engine.position_manager().outcome_publisher().subscribe();
+        engine.start().await.unwrap();
+        engine.update_price(Symbol::new("ETH-USD"), 2000.0);
+
+        let signal = |action, signal_id: &str| TradingSignal {
+            symbol: Symbol::new("ETH-USD"),
+            exchange: Exchange::Binance,
+            action,
+            confidence: 0.7,
+            urgency: 0.9,
+            metadata: SignalMetadata {
+                signal_id: Some(signal_id.to_string()),
+                strategy: Some("momentum".to_string()),
+                ..Default::default()
+            },
+        };
+        engine.process_signal(signal(SignalAction::Buy { size_hint: Some(2000.0) }, "entry-1")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        engine.update_price(Symbol::new("ETH-USD"), 2020.0);
+        engine.process_signal(signal(SignalAction::Close { position_id: None }, "exit-1")).await.unwrap();
+
+        let outcome = tokio::time::timeout(Duration::from_secs(2), outcomes.recv()).await.unwrap().unwrap();
+        assert_eq!(outcome.signal_id.as_deref(), Some("entry-1"));
+        assert_eq!(outcome.strategy.as_deref(), Some("momentum"));
+        assert_eq!(outcome.confidence, Some(0.7));
+        assert_eq!(outcome.exit_reason, ExitReason::Signal);
+        assert!(outcome.pnl > 0.0);
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signal_results_say_what_became_of_each_signal() {
+        let mut engine = PaperTradingEngine::new(PaperTradingConfig::default());
+        let mut results = engine.position_manager().outcome_publisher().subscribe_signals();
+        engine.start().await.unwrap();
+
+        let signal = |action, signal_id: &str| TradingSignal {
+            symbol: Symbol::new("BTC-USD"),
+            exchange: Exchange::Binance,
+            action,
+            confidence: 0.8,
+            urgency: 0.9,
+            metadata: SignalMetadata { signal_id: Some(signal_id.to_string()), ..Default::default() },
+        };
+        async fn next(results: &mut tokio::sync::broadcast::Receiver<SignalResult>) -> SignalResult {
+            tokio::time::timeout(Duration::from_secs(2), results.recv()).await.unwrap().unwrap()
+        }
+
+        engine.process_signal(signal(SignalAction::Buy { size_hint: Some(1_000.0) }, "unpriced")).await.unwrap();
+        let result = next(&mut results).await;
+        assert_eq!(result.signal_id.as_deref(), Some("unpriced"));
+        assert!(matches!(result.outcome, SignalOutcome::Rejected { ref reason } if reason.contains("No price")));
+
+        engine.update_price(Symbol::new("BTC-USD"), 50_000.0);
+        engine.process_signal(signal(SignalAction::Buy { size_hint: Some(10_000_000.0) }, "oversized")).await.unwrap();
+        assert!(matches!(next(&mut results).await.outcome, SignalOutcome::Rejected { .. }));
+        engine.process_signal(signal(SignalAction::Hold, "hold")).await.unwrap();
+        assert!(matches!(next(&mut results).await.outcome, SignalOutcome::Ignored { .. }));
+        engine.process_signal(signal(SignalAction::Buy { size_hint: Some(1_000.0) }, "entry")).await.unwrap();
+        let SignalOutcome::Executed { order_ids } = next(&mut results).await.outcome else { panic!("entry not executed") };
+        assert!(engine.order_manager().get_order(&order_ids[0]).is_some());
+        engine.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signal_exit_levels_replace_configured_ones() {
+        let mut engine = PaperTradingEngine::new(PaperTradingConfig::default());
+        engine.start().await.unwrap();
+        engine.update_price(Symbol::new("SOL-USD"), 100.0);
+
+        // The target is on the wrong side of a long, so the configured one is used
+        engine.process_signal(TradingSignal {
+            symbol: Symbol::new("SOL-USD"),
+            exchange: Exchange::Binance,
+            action: SignalAction::Buy { size_hint: Some(1000.0) },
+            confidence: 0.8,
+            urgency: 0.9,
+            metadata: SignalMetadata {
+                stop_loss: Some(97.0),
+                take_profit: Some(90.0),
+                ..Default::default()
+            },
+        }).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let position = &engine.position_manager().get_open_positions()[0];
+        let config = engine.config();
+        assert_eq!(position.stop_loss, Some(97.0));
+        let target = position.entry_price * (1.0 + config.risk_limits.take_profit_pct / 100.0);
+        assert!((position.take_profit.unwrap() - target).abs() < 1e-9);
+        engine.stop().await.unwrap();
+    }
+    
+    #[test]
+    fn test_fill_cancels_resting_orders_over_leverage() {
+        let config = PaperTradingConfig {
+            risk_limits: RiskLimits { max_leverage: 1.0, ..Default::default() },
+            ..Default::default()
+        };
+        let engine = PaperTradingEngine::new(config);
+        let mut events = engine.risk_manager().subscribe();
+        let btc = Symbol::new("BTC-USD");
+        engine.update_price(btc.clone(), 50000.0);
+        
+        // Two resting bids below the market, then a market buy that fills
+        let orders = engine.order_manager();
+        let kept = orders.submit_order(Order::limit(btc.clone(), Exchange::Binance, Side::Buy, 0.4, 45000.0)).unwrap();
+        let cancelled = orders.submit_order(Order::limit(btc.clone(), Exchange::Binance, Side::Buy, 1.0, 40000.0)).unwrap();
+        orders.submit_order(Order::market(btc.clone(), Exchange::Binance, Side::Buy, 1.5)).unwrap();
+        engine.process_orders_once().unwrap();
+        
+        // 75k filled: the 18k bid still fits under 1x leverage, the 40k one no longer does
+        let leverage = engine.risk_manager().get_metrics().leverage_ratio;
+        assert!(leverage > 0.74 && leverage < 0.76);
+        assert_eq!(orders.get_order(&kept).unwrap().status, OrderStatus::Submitted);
+        assert_eq!(orders.get_order(&cancelled).unwrap().status, OrderStatus::Cancelled);
+        assert!(matches!(events.try_recv(), Ok(RiskEvent::RestingOrderRejected { order_id, .. }) if order_id == cancelled));
+    }
+    
+    #[test]
+    fn test_trade_management_takes_partial_profit_and_trails() {
+        let config = PaperTradingConfig { trade_management: TradeRule::standard(), ..Default::default() };
+        let engine = PaperTradingEngine::new(config);
+        let eth = Symbol::new("ETH-USD");
+        engine.update_price(eth.clone(), 100.0);
+        engine.order_manager().submit_order(Order::market(eth.clone(), Exchange::Binance, Side::Buy, 2.0)).unwrap();
+        engine.process_orders_once().unwrap();
+        let id = engine.position_manager().get_open_positions()[0].id.clone();
+        
+        // Past +2R of the 2% stop: half is sold, the rest trails without a target
+        engine.update_price(eth.clone(), 104.5);
+        engine.process_orders_once().unwrap();
+        let position = engine.position_manager().get_position(&id).unwrap();
+        assert!((position.quantity - 1.0).abs() < 1e-9);
+        assert!(position.trailing_stop && position.take_profit.is_none());
+        assert!(position.stop_loss.unwrap() > position.entry_price);
+        
+        engine.update_price(eth.clone(), 102.0);
+        engine.process_orders_once().unwrap();
+        let position = engine.position_manager().get_position(&id).unwrap();
+        assert_eq!((position.status, position.exit_reason), (PositionStatus::Closed, Some(ExitReason::TrailingStop)));
+    }
+    
+    #[test]
+    fn test_positions_marked_from_the_mid_ignore_stray_prints() {
+        let config = PaperTradingConfig {
+            symbol_marks: BTreeMap::from([("ETH-USD".to_string(), MarkPrice { source: MarkSource::Mid, ..Default::default() })]),
+            ..Default::default()
+        };
+        let engine = PaperTradingEngine::new(config);
+        let eth = Symbol::new("ETH-USD");
+        engine.update_price(eth.clone(), 100.0);
+        engine.update_book(&eth, &[(99.9, 5.0)], &[(100.1, 5.0)]);
+        engine.order_manager().submit_order(Order::market(eth.clone(), Exchange::Binance, Side::Buy, 1.0)).unwrap();
+        engine.process_orders_once().unwrap();
+        let id = engine.position_manager().get_open_positions()[0].id.clone();
+        
+        // A print through the 2% stop moves neither the mark nor the position
+        engine.update_price(eth.clone(), 90.0);
+        assert_eq!((engine.market_price(&eth), engine.mark_price(&eth)), (Some(90.0), Some(100.0)));
+        let position = engine.position_manager().get_position(&id).unwrap();
+        assert!(position.status == PositionStatus::Open && position.unrealized_pnl > -0.5); // Costs only
+        
+        // The book moving through it does
+        engine.update_book(&eth, &[(96.9, 5.0)], &[(97.1, 5.0)]);
+        engine.process_orders_once().unwrap();
+        let position = engine.position_manager().get_position(&id).unwrap();
+        assert_eq!((position.status, position.exit_reason), (PositionStatus::Closed, Some(ExitReason::StopLoss)));
+    }
+    
+    #[test]
+    fn test_rebalance_trades_back_to_target_weights() {
+        // A buy-and-hold book: no exit levels
+        let config = PaperTradingConfig { enable_stop_loss: false, enable_take_profit: false, ..Default::default() };
+        let engine = PaperTradingEngine::new(config);
+        let (btc, eth) = (Symbol::new("BTC-USD"), Symbol::new("ETH-USD"));
+        engine.update_price(btc.clone(), 50000.0);
+        engine.update_price(eth.clone(), 2000.0);
+        let rebalance = RebalanceConfig {
+            targets: BTreeMap::from([("BTC-USD".to_string(), 0.5), ("ETH-USD".to_string(), 0.3)]),
+            exchange: Exchange::Coinbase,
+            ..Default::default()
+        };
+        
+        let plan = engine.rebalance(&rebalance).unwrap();
+        assert_eq!(plan.orders.len(), 2);
+        engine.process_orders_once().unwrap();
+        assert!((engine.position_manager().get_net_position(&btc) - 1.0).abs() < 1e-9);
+        assert!((engine.position_manager().get_net_position(&eth) - 15.0).abs() < 1e-9);
+        assert!(engine.rebalance(&rebalance).unwrap().orders.is_empty());
+        
+        // BTC rallies past the band: some is sold, first, and ETH topped up in place
+        engine.update_price(btc.clone(), 60000.0);
+        let plan = engine.rebalance(&rebalance).unwrap();
+        let sides: Vec<(&str, Side)> = plan.orders.iter().map(|o| (o.symbol.as_str(), o.side)).collect();
+        assert_eq!(sides, [("BTC-USD", Side::Sell), ("ETH-USD", Side::Buy)]);
+        engine.process_orders_once().unwrap();
+        assert_eq!(engine.position_manager().get_open_positions().len(), 2);
+        engine.update_price(btc.clone(), 60000.0);
+        let equity = 100000.0 + engine.position_manager().total_pnl();
+        let btc_weight = engine.position_manager().get_net_position(&btc) * 60000.0 / equity;
+        assert!((btc_weight - 0.5).abs() < 0.001);
+        
+        let limited = PaperTradingEngine::new(PaperTradingConfig {
+            risk_limits: RiskLimits { max_position_size: 40000.0, ..Default::default() },
+            ..Default::default()
+        });
+        limited.update_price(btc.clone(), 50000.0);
+        limited.update_price(eth.clone(), 2000.0);
+        let plan = limited.rebalance(&rebalance).unwrap();
+        assert_eq!(plan.orders[0].symbol, "ETH-USD");
+        assert!(plan.skipped[0].1.starts_with("Position size"));
+    }
+    
+    #[test]
+    fn test_stops_ride_out_an_outage_and_retry_rejected_exits() {
+        let fault = |kind, after_secs| ScheduledFault { kind, after_secs, duration_secs: 10, delay_ms: 0, symbols: Vec::new() };
+        let chaos = ChaosConfig { faults: vec![fault(FaultKind::Outage, 10), fault(FaultKind::RejectOrders, 30)], ..Default::default() };
+        let clock = Arc::new(clock::SimulatedClock::new(0));
+        let engine = PaperTradingEngine::with_clock(PaperTradingConfig { chaos: Some(chaos), ..Default::default() }, clock.clone());
+        let eth = Symbol::new("ETH-USD");
+        engine.update_price(eth.clone(), 100.0);
+        engine.order_manager().submit_order(Order::market(eth.clone(), Exchange::Binance, Side::Buy, 1.0)).unwrap();
+        engine.process_orders_once().unwrap();
+        
+        // The crash happens while the feed is out: the stop can't see it
+        clock.advance(Duration::from_secs(15));
+        engine.update_price(eth.clone(), 90.0);
+        assert_eq!(engine.current_prices.get(&eth).map(|p| *p), Some(100.0));
+        
+        // Then the venue refuses the exit, which is tried again on the next tick
+        clock.advance(Duration::from_secs(15));
+        engine.update_price(eth.clone(), 90.0);
+        assert!(engine.order_manager().get_active_orders().is_empty());
+        clock.advance(Duration::from_secs(10));
+        engine.update_price(eth.clone(), 90.0);
+        engine.process_orders_once().unwrap();
+        assert!(engine.position_manager().get_open_positions().is_empty());
+        let faults = engine.fault_statistics().unwrap();
+        assert_eq!((faults.ticks_dropped, faults.orders_rejected), (1, 1));
+    }
+    
+    #[test]
+    fn test_runs_with_the_same_seed_are_identical() {
+        let run = |seed| {
+            let random = RandomFaults { delay_rate: 0.2, max_delay_ms: 3000, reject_rate: 0.3, ..Default::default() };
+            let config = PaperTradingConfig { seed: Some(seed), chaos: Some(ChaosConfig { random, ..Default::default() }), ..Default::default() };
+            let clock = Arc::new(clock::SimulatedClock::new(0));
+            let engine = PaperTradingEngine::with_clock(config, clock.clone());
+            let eth = Symbol::new("ETH-USD");
+            for i in 0..50u64 {
+                clock.advance(Duration::from_secs(1));
+                engine.update_price(eth.clone(), 100.0 + (i * 37 % 11) as f64);
+                let side = if i % 3 == 2 { Side::Sell } else { Side::Buy };
+                let _ = engine.order_manager().submit_order(Order::market(eth.clone(), Exchange::Binance, side, 1.0));
+                engine.process_orders_once().unwrap();
+            }
+            let mut orders: Vec<(String, OrderStatus)> = engine.order_manager().get_all_orders().into_iter().map(|o| (o.id, o.status)).collect();
+            let mut positions: Vec<(String, f64)> = engine.position_manager().get_all_positions().into_iter().map(|p| (p.id, p.realized_pnl)).collect();
+            orders.sort_by(|a, b| a.0.cmp(&b.0));
+            positions.sort_by(|a, b| a.0.cmp(&b.0));
+            (orders, positions, engine.capital(), engine.fault_statistics().unwrap(), engine.rng().next_u64())
+        };
+        let first = run(42);
+        assert!(first.3.orders_rejected > 0 && first.3.ticks_delayed > 0);
+        assert_eq!(run(42), first);
+        assert_ne!(run(43).0, first.0);
+    }
+}
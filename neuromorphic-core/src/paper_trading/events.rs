@@ -0,0 +1,345 @@
+//! Append-only log of engine events
+//!
+//! With `PaperTradingConfig::event_log` set, every domain event of an engine
+//! (signals received, risk rejections, order and position changes) is
+//! appended to a JSON lines file, one record per line with a sequence number
+//! that only ever increases, also across restarts that reopen the file.
+//! Order and position events carry the full state after the change, so the
+//! log can be read on its own, and `EventReplayer` can rebuild an engine's
+//! positions and orders from it to debug or audit a run.
+
+use super::clock::{self, SharedClock};
+use super::order_manager::{OrderBook, OrderStatus};
+use super::position_manager::PositionBook;
+use super::{Order, PaperTradingEngine, Position, TradingSignal};
+use crate::exchanges::{Side, Symbol};
+use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Something that happened in an engine
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EngineEvent {
+    SignalReceived { signal: TradingSignal },
+    RiskRejected { symbol: Symbol, side: Side, quantity: f64, price: f64, reason: String },
+    OrderSubmitted { order: Order },
+    OrderFilled { order: Order },
+    OrderAmended { order: Order },
+    OrderCancelled { order: Order },
+    OrderExpired { order: Order },
+    OrderRejected { order: Order, reason: String },
+    PositionOpened { position: Position },
+    PositionUpdated { position: Position }, // Scaled in or partly closed
+    PositionClosed { position: Position },
+}
+
+/// One line of the log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub sequence: u64, // From 1, without gaps
+    pub timestamp: u64, // Engine clock, unix millis
+    pub event: EngineEvent,
+}
+
+struct Writer {
+    file: File,
+    sequence: u64, // Of the last record written
+}
+
+/// Handle to an engine's event log, shared by its managers. Events are
+/// dropped until a file is opened.
+#[derive(Clone)]
+pub struct EventLog {
+    enabled: Arc<AtomicBool>,
+    writer: Arc<Mutex<Option<Writer>>>,
+    clock: SharedClock,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(clock::system_clock())
+    }
+}
+
+impl EventLog {
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(false)),
+            writer: Arc::new(Mutex::new(None)),
+            clock,
+        }
+    }
+
+    /// Append to the log at `path`, carrying on from its last sequence number
+    pub fn open(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let sequence = match File::open(path) {
+            Ok(file) => match BufReader::new(file).lines().map_while(|line| line.ok()).filter(|l| !l.trim().is_empty()).last() {
+                Some(line) => serde_json::from_str::<EventRecord>(&line)
+                    .with_context(|| format!("Invalid last record in event log {}", path.display()))?
+                    .sequence,
+                None => 0,
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read event log {}", path.display())),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open event log {}", path.display()))?;
+
+        *self.writer.lock() = Some(Writer { file, sequence });
+        self.enabled.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Append an event; `event` is only built while a log is open. A failed
+    /// write is logged rather than failing the operation that raised it.
+    pub fn record(&self, event: impl FnOnce() -> EngineEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+        let event = event();
+        let timestamp = self.clock.now_ms();
+        let mut writer = self.writer.lock();
+        let Some(writer) = writer.as_mut() else {
+            return;
+        };
+        let record = EventRecord {
+            sequence: writer.sequence + 1,
+            timestamp,
+            event,
+        };
+        let written = serde_json::to_string(&record)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| writer.file.write_all(format!("{}\n", line).as_bytes()).context("Failed to append to the event log"));
+        match written {
+            Ok(()) => writer.sequence = record.sequence,
+            Err(e) => warn!(error = %e, sequence = record.sequence, "Failed to write engine event"),
+        }
+    }
+
+    /// Every record of the log at `path`, in order
+    pub fn read(path: impl AsRef<Path>) -> Result<Vec<EventRecord>> {
+        let path = path.as_ref();
+        let file = File::open(path).with_context(|| format!("Failed to read event log {}", path.display()))?;
+        BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+            .map(|(i, line)| {
+                let line = line.with_context(|| format!("Failed to read event log {}", path.display()))?;
+                serde_json::from_str(&line).with_context(|| format!("Invalid record on line {} of {}", i + 1, path.display()))
+            })
+            .collect()
+    }
+}
+
+/// Positions and orders rebuilt from an event log
+#[derive(Default)]
+pub struct EventReplayer {
+    last_sequence: u64,
+    signals: u64,
+    risk_rejections: u64,
+    orders: Vec<Order>, // In submission order
+    order_index: HashMap<String, usize>,
+    positions: Vec<Position>, // In opening order
+    position_index: HashMap<String, usize>,
+}
+
+impl EventReplayer {
+    /// Replay every record of the log at `path`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let mut replayer = Self::default();
+        for record in EventLog::read(path)? {
+            replayer.apply(&record)?;
+        }
+        Ok(replayer)
+    }
+
+    /// Apply the next record; fails on a sequence number out of order
+    pub fn apply(&mut self, record: &EventRecord) -> Result<()> {
+        if record.sequence <= self.last_sequence {
+            bail!("Event {} follows event {}; the log is out of order", record.sequence, self.last_sequence);
+        }
+        self.last_sequence = record.sequence;
+
+        match &record.event {
+            EngineEvent::SignalReceived { .. } => self.signals += 1,
+            EngineEvent::RiskRejected { .. } => self.risk_rejections += 1,
+            EngineEvent::OrderSubmitted { order }
+            | EngineEvent::OrderFilled { order }
+            | EngineEvent::OrderAmended { order }
+            | EngineEvent::OrderCancelled { order }
+            | EngineEvent::OrderExpired { order }
+            | EngineEvent::OrderRejected { order, .. } => {
+                upsert(&mut self.orders, &mut self.order_index, order.id.clone(), order.clone())
+            }
+            EngineEvent::PositionOpened { position }
+            | EngineEvent::PositionUpdated { position }
+            | EngineEvent::PositionClosed { position } => {
+                upsert(&mut self.positions, &mut self.position_index, position.id.clone(), position.clone())
+            }
+        }
+        Ok(())
+    }
+
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    pub fn signals_received(&self) -> u64 {
+        self.signals
+    }
+
+    pub fn risk_rejections(&self) -> u64 {
+        self.risk_rejections
+    }
+
+    /// Latest state of every order, in submission order
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    /// Latest state of every position, in opening order
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    /// Replace a stopped engine's positions, orders and capital with the
+    /// replayed ones. Prices, risk state and throttles are left as they are;
+    /// open P&L catches up with the next price update.
+    pub fn restore_into(&self, engine: &PaperTradingEngine) -> Result<()> {
+        let mut snapshot = engine.snapshot();
+        let converter = engine.position_manager().currency_converter();
+        let cents = |symbol: &Symbol, amount: f64| (converter.to_reporting(symbol, amount) * 100.0).round() as i64;
+
+        let mut positions = self.positions.clone();
+        positions.sort_by(|a, b| a.symbol.0.cmp(&b.symbol.0)); // Stable: keeps each symbol's opening order
+        let realized_cents: i64 = positions.iter().map(|p| cents(&p.symbol, p.realized_pnl)).sum();
+        snapshot.positions = PositionBook {
+            opened: positions.len() as u64,
+            realized_cents,
+            unrealized_cents: Vec::new(),
+            commission_cents: positions.iter().map(|p| cents(&p.symbol, p.commission)).sum(),
+            slippage_cents: positions.iter().map(|p| cents(&p.symbol, p.slippage)).sum(),
+            pending_exits: Vec::new(),
+            ids_issued: snapshot.positions.ids_issued,
+            positions,
+        };
+
+        let with_status = |statuses: &[OrderStatus]| -> Vec<Order> {
+            self.orders.iter().filter(|o| statuses.contains(&o.status)).cloned().collect()
+        };
+        let mut traded_volume: Vec<(_, f64)> = Vec::new();
+        // An algo parent's fills are its children's
+        for order in self.orders.iter().filter(|o| o.filled_quantity > 0.0 && o.algo.is_none()) {
+            match traded_volume.iter_mut().find(|(exchange, _)| *exchange == order.exchange) {
+                Some((_, volume)) => *volume += order.filled_quantity * order.avg_fill_price,
+                None => traded_volume.push((order.exchange, order.filled_quantity * order.avg_fill_price)),
+            }
+        }
+        let open = [OrderStatus::Pending, OrderStatus::Submitted, OrderStatus::PartiallyFilled];
+        let (pending, active) = with_status(&open).into_iter().partition(|o| o.algo.is_some());
+        snapshot.orders = OrderBook {
+            orders: self.orders.clone(),
+            active,
+            pending,
+            filled: with_status(&[OrderStatus::Filled]),
+            submitted: self.orders.len() as u64,
+            traded_volume,
+            ids_issued: snapshot.orders.ids_issued,
+            retired: Default::default(),
+        };
+
+        snapshot.capital = snapshot.initial_capital + realized_cents as f64 / 100.0;
+        snapshot.signals_processed = self.signals;
+        engine.restore(&snapshot)
+    }
+}
+
+fn upsert<T>(items: &mut Vec<T>, index: &mut HashMap<String, usize>, id: String, item: T) {
+    match index.get(&id) {
+        Some(&i) => items[i] = item,
+        None => {
+            index.insert(id, items.len());
+            items.push(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::{PaperTradingConfig, SignalAction, SignalMetadata};
+
+    #[tokio::test]
+    async fn test_log_and_replay_rebuild_positions() {
+        let path = std::env::temp_dir().join(format!("engine-events-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = PaperTradingConfig { event_log: Some(path.clone()), ..PaperTradingConfig::default() };
+        let mut engine = PaperTradingEngine::new(config.clone());
+        engine.start().await.unwrap();
+
+        let btc = Symbol::new("BTCUSDT");
+        engine.update_price(btc.clone(), 50_000.0);
+        let buy = TradingSignal {
+            symbol: btc.clone(),
+            exchange: Exchange::Binance,
+            action: SignalAction::Buy { size_hint: Some(5_000.0) },
+            confidence: 0.8,
+            urgency: 0.9,
+            metadata: SignalMetadata::default(),
+        };
+        engine.process_signal(buy).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        engine.process_orders_once().unwrap();
+        engine.stop().await.unwrap();
+
+        // Reopening carries on the sequence
+        let log = EventLog::default();
+        log.open(&path).unwrap();
+        log.record(|| EngineEvent::RiskRejected {
+            symbol: btc.clone(),
+            side: Side::Buy,
+            quantity: 1.0,
+            price: 50_000.0,
+            reason: "test".to_string(),
+        });
+        let records = EventLog::read(&path).unwrap();
+        assert!(records.windows(2).all(|w| w[1].sequence == w[0].sequence + 1));
+        assert!(matches!(records[0].event, EngineEvent::SignalReceived { .. }));
+        for kind in ["order_submitted", "order_filled", "position_opened", "risk_rejected"] {
+            assert!(records.iter().any(|r| serde_json::to_value(&r.event).unwrap()["type"] == kind), "no {kind} event");
+        }
+
+        let replayer = EventReplayer::from_file(&path).unwrap();
+        assert_eq!((replayer.signals_received(), replayer.risk_rejections()), (1, 1));
+        let fresh = PaperTradingEngine::new(PaperTradingConfig::default());
+        replayer.restore_into(&fresh).unwrap();
+        let open = fresh.position_manager().get_open_positions();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id, engine.position_manager().get_open_positions()[0].id);
+        assert_eq!(fresh.order_manager().get_all_orders().len(), 1);
+
+        // A record out of order is refused
+        let mut replayer = EventReplayer::default();
+        replayer.apply(&records[1]).unwrap();
+        assert!(replayer.apply(&records[0]).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,283 @@
+//! Order execution venues
+//!
+//! By default the engine simulates fills against the prices it is fed. With
+//! a venue attached, orders are placed on an exchange (e.g. the Binance Spot
+//! Testnet) and positions are built from the fills the exchange reports.
+
+use super::order_manager::{Order, OrderManager, OrderStatus as LocalStatus, OrderType};
+use crate::exchanges::{BinanceUserDataStream, ExchangeConnector, OrderRequest, OrderStatus, Side, Symbol, UniversalOrder};
+use anyhow::Result;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Where the engine's orders are filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// Fill locally from market prices with the configured slippage and fees
+    #[default]
+    Simulated,
+    /// Place real orders on the Binance Spot Testnet
+    BinanceTestnet,
+}
+
+/// Exchange that fills orders on the engine's behalf
+#[async_trait]
+pub trait ExecutionVenue: Send + Sync {
+    /// Place the order, returning the venue's view of it
+    async fn place(&self, order: &Order) -> Result<UniversalOrder>;
+
+    async fn status(&self, order: &Order, venue_order_id: &str) -> Result<UniversalOrder>;
+
+    async fn cancel(&self, order: &Order, venue_order_id: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl<C: ExchangeConnector> ExecutionVenue for C {
+    async fn place(&self, order: &Order) -> Result<UniversalOrder> {
+        Ok(self.place_order(to_request(order)).await?)
+    }
+
+    async fn status(&self, _order: &Order, venue_order_id: &str) -> Result<UniversalOrder> {
+        Ok(self.get_order(venue_order_id).await?)
+    }
+
+    async fn cancel(&self, _order: &Order, venue_order_id: &str) -> Result<()> {
+        Ok(self.cancel_order(venue_order_id).await?)
+    }
+}
+
+/// Venue that learns about fills from the user data stream instead of
+/// polling each order. Orders are only polled while the stream is down or
+/// before it has reported on them.
+pub struct StreamingVenue<C> {
+    connector: Arc<C>,
+    stream: Arc<BinanceUserDataStream>,
+}
+
+impl<C: ExchangeConnector> StreamingVenue<C> {
+    pub fn new(connector: Arc<C>, stream: Arc<BinanceUserDataStream>) -> Self {
+        Self { connector, stream }
+    }
+}
+
+#[async_trait]
+impl<C: ExchangeConnector> ExecutionVenue for StreamingVenue<C> {
+    async fn place(&self, order: &Order) -> Result<UniversalOrder> {
+        let remote = self.connector.place_order(to_request(order)).await?;
+        self.stream.track(&remote);
+        Ok(remote)
+    }
+
+    async fn status(&self, _order: &Order, venue_order_id: &str) -> Result<UniversalOrder> {
+        let pushed = self.stream.order(venue_order_id).filter(|_| self.stream.is_connected());
+        let remote = match pushed {
+            Some(remote) => remote,
+            None => {
+                let remote = self.connector.get_order(venue_order_id).await?;
+                self.stream.track(&remote);
+                remote
+            }
+        };
+        if !remote.is_active() {
+            self.stream.forget(venue_order_id);
+        }
+        Ok(remote)
+    }
+
+    async fn cancel(&self, _order: &Order, venue_order_id: &str) -> Result<()> {
+        self.connector.cancel_order(venue_order_id).await?;
+        self.stream.forget(venue_order_id);
+        Ok(())
+    }
+}
+
+/// Venue request for a paper order. Stop and take-profit orders are only
+/// placed once triggered, so they go out as market orders.
+pub fn to_request(order: &Order) -> OrderRequest {
+    let symbol = order.symbol.clone();
+    let mut request = match (&order.order_type, order.price) {
+        (OrderType::Limit | OrderType::StopLimit, Some(price)) => match order.side {
+            Side::Buy => OrderRequest::limit_buy(symbol, order.quantity, price),
+            Side::Sell => OrderRequest::limit_sell(symbol, order.quantity, price),
+        },
+        _ => match order.side {
+            Side::Buy => OrderRequest::market_buy(symbol, order.quantity),
+            Side::Sell => OrderRequest::market_sell(symbol, order.quantity),
+        },
+    };
+    request.client_order_id = Some(order.id.clone());
+    request
+}
+
+/// Place new orders at the venue and pull fills for the ones already placed.
+/// `venue_orders` maps local order IDs to venue order IDs. Returns the IDs of
+/// orders filled since the last call.
+pub async fn sync_venue_orders(
+    venue: &dyn ExecutionVenue,
+    order_manager: &OrderManager,
+    venue_orders: &DashMap<String, String>,
+    prices: &DashMap<Symbol, f64>,
+) -> Vec<String> {
+    let mut filled = Vec::new();
+
+    // Orders cancelled locally are cancelled at the venue too
+    let tracked: Vec<(String, String)> = venue_orders
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect();
+    for (order_id, venue_id) in tracked {
+        let Some(order) = order_manager.get_order(&order_id) else {
+            venue_orders.remove(&order_id);
+            continue;
+        };
+
+        if matches!(order.status, LocalStatus::Cancelled | LocalStatus::Expired) {
+            if let Err(e) = venue.cancel(&order, &venue_id).await {
+                warn!(order_id = %order_id, venue_order_id = %venue_id, error = %e, "Venue cancel failed");
+            }
+            venue_orders.remove(&order_id);
+            continue;
+        }
+
+        let remote = match venue.status(&order, &venue_id).await {
+            Ok(remote) => remote,
+            Err(e) => {
+                warn!(order_id = %order_id, venue_order_id = %venue_id, error = %e, "Venue status poll failed");
+                continue;
+            }
+        };
+
+        if remote.is_active() {
+            continue;
+        }
+        venue_orders.remove(&order_id);
+
+        if remote.filled_quantity > 0.0 {
+            let price = remote.average_price().or(remote.price).unwrap_or(order.price.unwrap_or_default());
+            let commission = remote.fees.as_ref().map(|fee| fee.amount);
+            match order_manager.apply_external_fill(&order_id, remote.filled_quantity, price, commission) {
+                Ok(()) => filled.push(order_id),
+                Err(e) => warn!(order_id = %order_id, error = %e, "Failed to apply venue fill"),
+            }
+        } else {
+            let reason = format!("Venue order {} ended {:?} without fills", venue_id, remote.status);
+            order_manager.reject_order(&order_id, &reason).ok();
+        }
+    }
+
+    // Place orders the venue hasn't seen yet
+    for order in order_manager.get_active_orders() {
+        if venue_orders.contains_key(&order.id) {
+            continue;
+        }
+
+        // Stops stay local until their trigger price is reached
+        if matches!(order.order_type, OrderType::StopLoss | OrderType::TakeProfit | OrderType::StopLimit) {
+            match prices.get(&order.symbol) {
+                Some(price) if order.should_trigger(*price) => {}
+                _ => continue,
+            }
+        }
+
+        match venue.place(&order).await {
+            Ok(remote) => {
+                info!(order_id = %order.id, venue_order_id = %remote.id, status = ?remote.status, "Order placed at venue");
+                venue_orders.insert(order.id.clone(), remote.id.clone());
+
+                // Market orders usually come back already filled
+                if matches!(remote.status, OrderStatus::Filled) {
+                    venue_orders.remove(&order.id);
+                    let price = remote.average_price().or(remote.price).unwrap_or_default();
+                    let commission = remote.fees.as_ref().map(|fee| fee.amount);
+                    match order_manager.apply_external_fill(&order.id, remote.filled_quantity, price, commission) {
+                        Ok(()) => filled.push(order.id.clone()),
+                        Err(e) => warn!(order_id = %order.id, error = %e, "Failed to apply venue fill"),
+                    }
+                }
+            }
+            Err(e) => {
+                warn!(order_id = %order.id, error = %e, "Venue rejected order");
+                order_manager.reject_order(&order.id, &e.to_string()).ok();
+            }
+        }
+    }
+
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, TimeInForce};
+    use crate::paper_trading::order_manager::SlippageModel;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    /// Fills every order immediately at a fixed price
+    struct FixedPriceVenue(f64);
+
+    #[async_trait]
+    impl ExecutionVenue for FixedPriceVenue {
+        async fn place(&self, order: &Order) -> Result<UniversalOrder> {
+            let request = to_request(order);
+            let mut metadata = HashMap::new();
+            metadata.insert(UniversalOrder::AVG_PRICE_KEY.to_string(), self.0.to_string());
+            Ok(UniversalOrder {
+                id: format!("venue-{}", order.id),
+                client_order_id: request.client_order_id,
+                symbol: request.symbol,
+                side: request.side,
+                order_type: request.order_type,
+                quantity: request.quantity,
+                filled_quantity: request.quantity,
+                remaining_quantity: 0.0,
+                price: request.price,
+                stop_price: None,
+                status: OrderStatus::Filled,
+                time_in_force: TimeInForce::IOC,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                exchange: Exchange::Binance,
+                fees: None,
+                metadata,
+            })
+        }
+
+        async fn status(&self, _order: &Order, _venue_order_id: &str) -> Result<UniversalOrder> {
+            anyhow::bail!("unexpected poll")
+        }
+
+        async fn cancel(&self, _order: &Order, _venue_order_id: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_venue_fills_replace_simulation() {
+        let order_manager = OrderManager::new(0.1, SlippageModel::Percentage(0.0));
+        let venue_orders = DashMap::new();
+        let prices = DashMap::new();
+        prices.insert(Symbol::new("BTC-USD"), 50_000.0);
+
+        let order = Order::market(Symbol::new("BTC-USD"), Exchange::Binance, Side::Buy, 0.1);
+        let order_id = order_manager.submit_order(order).unwrap();
+        let stop = Order::stop_loss(Symbol::new("BTC-USD"), Exchange::Binance, Side::Sell, 0.1, 45_000.0);
+        let stop_id = order_manager.submit_order(stop).unwrap();
+
+        let filled = sync_venue_orders(&FixedPriceVenue(50_100.0), &order_manager, &venue_orders, &prices).await;
+        assert_eq!(filled, vec![order_id.clone()]);
+
+        let order = order_manager.get_order(&order_id).unwrap();
+        assert_eq!(order.status, LocalStatus::Filled);
+        assert_eq!(order.avg_fill_price, 50_100.0);
+        assert!((order.commission - 5.01).abs() < 1e-9); // Taker fee from the schedule
+
+        // The untriggered stop is still only local
+        assert_eq!(order_manager.get_order(&stop_id).unwrap().status, LocalStatus::Submitted);
+        assert!(venue_orders.is_empty());
+    }
+}
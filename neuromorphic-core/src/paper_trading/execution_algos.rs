@@ -0,0 +1,233 @@
+//! Execution algorithms for large orders
+//!
+//! A parent order submitted with an `ExecutionAlgo` is never matched itself.
+//! It waits among the pending orders while the order manager releases child
+//! orders for it, linked through `parent_order_id` and `child_order_ids`:
+//!
+//! - TWAP splits the quantity into equal slices, one per interval.
+//! - VWAP sizes the slices by the volume the `VolumeCurve` recorded at their
+//!   time of day, so more is traded when the market trades more. Until volume
+//!   is recorded the slices are equal, like TWAP.
+//! - Iceberg shows `visible_quantity` at a time and replenishes it once filled.
+//!
+//! Children copy the parent's limit price, or are market orders when it has
+//! none. The parent's fills are the sum of its children's: it is filled once
+//! they add up to its quantity, and cancelled with what they filled if a child
+//! ends unfilled. Cancelling the parent cancels its active children.
+
+use super::order_manager::{Order, OrderStatus};
+use crate::exchanges::Symbol;
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Width of a volume curve bucket
+const BUCKET_MS: u64 = 5 * 60_000;
+const BUCKETS_PER_DAY: usize = (86_400_000 / BUCKET_MS) as usize;
+
+/// How a parent order is worked
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionAlgo {
+    Twap { slices: u32, interval_ms: u64 },
+    Vwap {
+        slices: u32,
+        interval_ms: u64,
+        #[serde(default)]
+        weights: Vec<f64>, // Share of each slice, set from the volume curve on submission
+    },
+    Iceberg { visible_quantity: f64 },
+}
+
+impl ExecutionAlgo {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutionAlgo::Twap { .. } => "twap",
+            ExecutionAlgo::Vwap { .. } => "vwap",
+            ExecutionAlgo::Iceberg { .. } => "iceberg",
+        }
+    }
+
+    pub fn validate(&self, quantity: f64) -> Result<()> {
+        if !(quantity.is_finite() && quantity > 0.0) {
+            bail!("{} order quantity must be positive", self.name());
+        }
+        match self {
+            ExecutionAlgo::Twap { slices, .. } | ExecutionAlgo::Vwap { slices, .. } if *slices == 0 => {
+                bail!("{} order needs at least one slice", self.name())
+            }
+            ExecutionAlgo::Iceberg { visible_quantity } if !(visible_quantity.is_finite() && *visible_quantity > 0.0) => {
+                bail!("iceberg visible_quantity must be positive")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Child orders it plans, or None when it replenishes as it fills
+    pub fn slices(&self) -> Option<u32> {
+        match self {
+            ExecutionAlgo::Twap { slices, .. } | ExecutionAlgo::Vwap { slices, .. } => Some(*slices),
+            ExecutionAlgo::Iceberg { .. } => None,
+        }
+    }
+
+    /// Quantity of the child to release for `parent` at `now_ms`, if one is due
+    pub(crate) fn next_slice(&self, parent: &Order, children: &[Order], now_ms: u64) -> Option<f64> {
+        let unreleased = parent.quantity - children.iter().map(|c| c.quantity).sum::<f64>();
+        if unreleased <= parent.quantity * 1e-9 {
+            return None;
+        }
+        match self {
+            ExecutionAlgo::Iceberg { visible_quantity } => children
+                .iter()
+                .all(|c| c.status == OrderStatus::Filled)
+                .then(|| visible_quantity.min(unreleased)),
+            ExecutionAlgo::Twap { slices, interval_ms } | ExecutionAlgo::Vwap { slices, interval_ms, .. } => {
+                let released = children.len();
+                if now_ms < parent.created_time + released as u64 * interval_ms {
+                    return None;
+                }
+                if released + 1 >= *slices as usize {
+                    return Some(unreleased);
+                }
+                let weight = match self {
+                    ExecutionAlgo::Vwap { weights, .. } if weights.len() == *slices as usize => weights[released],
+                    _ => 1.0 / *slices as f64,
+                };
+                Some((parent.quantity * weight).min(unreleased))
+            }
+        }
+    }
+}
+
+/// Progress of a parent order still being worked
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AlgoProgress {
+    pub order_id: String,
+    pub symbol: Symbol,
+    pub algo: &'static str,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    pub children: usize, // Released so far
+    pub slices: Option<u32>,
+}
+
+impl AlgoProgress {
+    pub(crate) fn of(parent: &Order) -> Option<Self> {
+        let algo = parent.algo.as_ref()?;
+        Some(Self {
+            order_id: parent.id.clone(),
+            symbol: parent.symbol.clone(),
+            algo: algo.name(),
+            quantity: parent.quantity,
+            filled_quantity: parent.filled_quantity,
+            children: parent.child_order_ids.len(),
+            slices: algo.slices(),
+        })
+    }
+}
+
+/// Traded volume by symbol and time of day, in five minute buckets
+#[derive(Default)]
+pub struct VolumeCurve {
+    buckets: DashMap<Symbol, Vec<f64>>,
+}
+
+impl VolumeCurve {
+    pub fn record(&self, symbol: &Symbol, volume: f64, now_ms: u64) {
+        if !(volume.is_finite() && volume > 0.0) {
+            return;
+        }
+        let mut buckets = self.buckets.entry(symbol.clone()).or_insert_with(|| vec![0.0; BUCKETS_PER_DAY]);
+        buckets[Self::bucket(now_ms)] += volume;
+    }
+
+    /// Share of the volume traded in each of `slices` intervals from `start_ms`;
+    /// equal shares when none was recorded for those times
+    pub fn weights(&self, symbol: &Symbol, start_ms: u64, slices: u32, interval_ms: u64) -> Vec<f64> {
+        let volumes: Vec<f64> = (0..slices as u64)
+            .map(|i| {
+                self.buckets
+                    .get(symbol)
+                    .map_or(0.0, |buckets| buckets[Self::bucket(start_ms + i * interval_ms)])
+            })
+            .collect();
+        let total: f64 = volumes.iter().sum();
+        if total <= 0.0 {
+            return vec![1.0 / slices as f64; slices as usize];
+        }
+        volumes.into_iter().map(|v| v / total).collect()
+    }
+
+    fn bucket(ms: u64) -> usize {
+        ((ms % 86_400_000) / BUCKET_MS) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side};
+    use crate::paper_trading::clock::{Clock, SimulatedClock};
+    use crate::paper_trading::{OrderManager, SlippageModel};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_twap_vwap_and_iceberg_children() {
+        let clock = Arc::new(SimulatedClock::new(0));
+        let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0)).with_clock(clock.clone());
+        let symbol = Symbol::new("BTC-USD");
+        let prices = DashMap::new();
+        prices.insert(symbol.clone(), 50_000.0);
+        let order = |quantity| Order::market(symbol.clone(), Exchange::Binance, Side::Buy, quantity);
+
+        // TWAP: one slice now and one a minute later
+        let twap = manager.submit_algo_order(order(2.0), ExecutionAlgo::Twap { slices: 2, interval_ms: 60_000 }).unwrap();
+        assert_eq!(manager.process_orders(&prices).unwrap().len(), 1);
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+        let progress = manager.get_statistics().algo_orders;
+        assert_eq!((progress.len(), progress[0].filled_quantity, progress[0].children), (1, 1.0, 1));
+        clock.advance(Duration::from_secs(60));
+        manager.process_orders(&prices).unwrap();
+        let parent = manager.get_order(&twap).unwrap();
+        assert_eq!((parent.status, parent.filled_quantity, parent.child_order_ids.len()), (OrderStatus::Filled, 2.0, 2));
+
+        // VWAP: three times the volume in the second bucket gives it three quarters
+        manager.volume_curve().record(&symbol, 100.0, clock.now_ms());
+        manager.volume_curve().record(&symbol, 300.0, clock.now_ms() + BUCKET_MS);
+        let vwap = manager.submit_algo_order(order(4.0), ExecutionAlgo::Vwap { slices: 2, interval_ms: BUCKET_MS, weights: vec![] }).unwrap();
+        manager.process_orders(&prices).unwrap();
+        assert_eq!(manager.get_order(&vwap).unwrap().filled_quantity, 1.0);
+
+        // Iceberg: a new clip each pass once the last one filled; cancelling stops it
+        let iceberg = manager.submit_algo_order(order(1.0), ExecutionAlgo::Iceberg { visible_quantity: 0.4 }).unwrap();
+        manager.process_orders(&prices).unwrap();
+        manager.process_orders(&prices).unwrap();
+        manager.cancel_order(&iceberg).unwrap();
+        let parent = manager.get_order(&iceberg).unwrap();
+        assert_eq!((parent.status, parent.filled_quantity), (OrderStatus::Cancelled, 0.8));
+        assert!(manager.submit_algo_order(order(1.0), ExecutionAlgo::Twap { slices: 0, interval_ms: 1 }).is_err());
+    }
+
+    #[test]
+    fn test_children_build_one_position() {
+        use crate::paper_trading::{PaperTradingConfig, PaperTradingEngine};
+
+        let clock = Arc::new(SimulatedClock::new(0));
+        let engine = PaperTradingEngine::with_clock(PaperTradingConfig::default(), clock.clone());
+        let symbol = Symbol::new("ETH-USD");
+        engine.update_price(symbol.clone(), 2_000.0);
+        let order = Order::market(symbol.clone(), Exchange::Binance, Side::Buy, 3.0);
+        let parent = engine.submit_algo_order(order, ExecutionAlgo::Twap { slices: 3, interval_ms: 1_000 }).unwrap();
+        for _ in 0..3 {
+            engine.process_orders_once().unwrap();
+            clock.advance(Duration::from_secs(1));
+        }
+
+        let positions = engine.position_manager().get_open_positions();
+        assert_eq!((positions.len(), positions[0].quantity), (1, 3.0));
+        assert_eq!(engine.order_manager().get_order(&parent).unwrap().status, OrderStatus::Filled);
+    }
+}
@@ -0,0 +1,129 @@
+//! Commission schedules for paper trading
+
+use crate::exchanges::Exchange;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Whether a fill added liquidity to the book or took it
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum LiquidityRole {
+    Maker,
+    Taker,
+}
+
+/// Discounted rates that apply once traded volume reaches `min_volume`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub min_volume: f64,
+    pub maker_pct: f64,
+    pub taker_pct: f64,
+}
+
+/// Maker/taker rates (in %) with a minimum fee per fill
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeRates {
+    pub maker_pct: f64,
+    pub taker_pct: f64,
+    pub min_fee: f64,
+    pub tiers: Vec<FeeTier>,
+}
+
+impl FeeRates {
+    /// Same rate for makers and takers, no minimum and no tiers
+    pub fn flat(rate_pct: f64) -> Self {
+        Self {
+            maker_pct: rate_pct,
+            taker_pct: rate_pct,
+            min_fee: 0.0,
+            tiers: Vec::new(),
+        }
+    }
+
+    /// Rate in % for a role at the given traded volume
+    pub fn rate(&self, role: LiquidityRole, traded_volume: f64) -> f64 {
+        let tier = self.tiers
+            .iter()
+            .filter(|t| traded_volume >= t.min_volume)
+            .max_by(|a, b| a.min_volume.partial_cmp(&b.min_volume).unwrap_or(std::cmp::Ordering::Equal));
+
+        match (role, tier) {
+            (LiquidityRole::Maker, Some(t)) => t.maker_pct,
+            (LiquidityRole::Taker, Some(t)) => t.taker_pct,
+            (LiquidityRole::Maker, None) => self.maker_pct,
+            (LiquidityRole::Taker, None) => self.taker_pct,
+        }
+    }
+}
+
+/// Commission schedule with per-exchange overrides
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub default_rates: FeeRates,
+    pub exchange_overrides: HashMap<Exchange, FeeRates>,
+}
+
+impl FeeSchedule {
+    pub fn new(default_rates: FeeRates) -> Self {
+        Self {
+            default_rates,
+            exchange_overrides: HashMap::new(),
+        }
+    }
+
+    /// Single flat rate everywhere, matching the legacy `commission_rate`
+    pub fn flat(rate_pct: f64) -> Self {
+        Self::new(FeeRates::flat(rate_pct))
+    }
+
+    /// Use different rates on one exchange
+    pub fn with_exchange(mut self, exchange: Exchange, rates: FeeRates) -> Self {
+        self.exchange_overrides.insert(exchange, rates);
+        self
+    }
+
+    /// Rates that apply on an exchange
+    pub fn rates_for(&self, exchange: Exchange) -> &FeeRates {
+        self.exchange_overrides.get(&exchange).unwrap_or(&self.default_rates)
+    }
+
+    /// Commission for a fill of `notional` value, given volume already traded on the exchange
+    pub fn commission(&self, exchange: Exchange, role: LiquidityRole, notional: f64, traded_volume: f64) -> f64 {
+        let rates = self.rates_for(exchange);
+        let fee = notional.abs() * rates.rate(role, traded_volume) / 100.0;
+        fee.max(rates.min_fee)
+    }
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self::flat(0.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_schedule() {
+        let schedule = FeeSchedule::new(FeeRates {
+            maker_pct: 0.02,
+            taker_pct: 0.05,
+            min_fee: 0.5,
+            tiers: vec![FeeTier { min_volume: 1_000_000.0, maker_pct: 0.0, taker_pct: 0.03 }],
+        })
+        .with_exchange(Exchange::Coinbase, FeeRates::flat(0.4));
+
+        // Maker vs taker on the base tier
+        assert!((schedule.commission(Exchange::Binance, LiquidityRole::Maker, 10_000.0, 0.0) - 2.0).abs() < 1e-9);
+        assert!((schedule.commission(Exchange::Binance, LiquidityRole::Taker, 10_000.0, 0.0) - 5.0).abs() < 1e-9);
+
+        // Volume tier discount, with the minimum fee as a floor
+        assert!((schedule.commission(Exchange::Binance, LiquidityRole::Taker, 10_000.0, 2_000_000.0) - 3.0).abs() < 1e-9);
+        assert_eq!(schedule.commission(Exchange::Binance, LiquidityRole::Maker, 10_000.0, 2_000_000.0), 0.5);
+
+        // Exchange override
+        assert!((schedule.commission(Exchange::Coinbase, LiquidityRole::Maker, 10_000.0, 0.0) - 40.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,164 @@
+//! Position groups and strategy books
+//!
+//! Positions that belong together carry the same `group_id`: the legs of a
+//! basket, entries a strategy pyramids into one idea, or a strategy's own
+//! book when its signals set `SignalMetadata::group_id`. A group is summed up
+//! like one position, with its P&L and exposure, and can be closed in one
+//! call. Positions are also summed up by strategy, whether grouped or not.
+//!
+//! Amounts are in the reporting currency; exposure is marked at the price
+//! each open position was last updated with.
+
+use super::{ExitReason, OrderManager, Order, Position, PositionManager, PositionStatus};
+use crate::exchanges::Side;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// P&L and exposure of the positions sharing a group id or strategy
+#[derive(Clone, Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct PositionGroup {
+    pub id: String,
+    pub open_positions: usize,
+    pub closed_positions: usize,
+    pub symbols: Vec<String>, // Of the open positions, sorted
+    pub realized_pnl: f64, // Partial closes of open positions included
+    pub unrealized_pnl: f64,
+    pub gross_exposure: f64, // Marked notional of the open positions
+    pub net_exposure: f64, // Long less short
+}
+
+impl PositionGroup {
+    pub fn total_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl
+    }
+
+    fn add(&mut self, position: &Position, to_reporting: impl Fn(f64) -> f64) {
+        self.realized_pnl += to_reporting(position.realized_pnl);
+        if position.status == PositionStatus::Closed {
+            self.closed_positions += 1;
+            return;
+        }
+        self.open_positions += 1;
+        self.unrealized_pnl += to_reporting(position.unrealized_pnl);
+        let notional = to_reporting(position.quantity * position.multiplier * position.mark_price());
+        self.gross_exposure += notional;
+        self.net_exposure += match position.side {
+            Side::Buy => notional,
+            Side::Sell => -notional,
+        };
+        if !self.symbols.contains(&position.symbol.0) {
+            self.symbols.push(position.symbol.0.clone());
+            self.symbols.sort();
+        }
+    }
+}
+
+impl PositionManager {
+    /// Every position group, by group id
+    pub fn position_groups(&self) -> Vec<PositionGroup> {
+        self.summarize(|p| p.group_id.as_deref())
+    }
+
+    pub fn position_group(&self, group_id: &str) -> Option<PositionGroup> {
+        self.position_groups().into_iter().find(|g| g.id == group_id)
+    }
+
+    /// Positions summed up by the strategy that opened them; positions
+    /// without a strategy are left out
+    pub fn strategy_books(&self) -> Vec<PositionGroup> {
+        self.summarize(|p| p.strategy.as_deref())
+    }
+
+    pub fn group_positions(&self, group_id: &str) -> Vec<Position> {
+        self.get_all_positions()
+            .into_iter()
+            .filter(|p| p.group_id.as_deref() == Some(group_id))
+            .collect()
+    }
+
+    fn summarize(&self, key: impl Fn(&Position) -> Option<&str>) -> Vec<PositionGroup> {
+        let mut groups: BTreeMap<String, PositionGroup> = BTreeMap::new();
+        for position in self.get_all_positions() {
+            if let Some(id) = key(&position) {
+                groups
+                    .entry(id.to_string())
+                    .or_insert_with(|| PositionGroup { id: id.to_string(), ..Default::default() })
+                    .add(&position, |amount| self.currency_converter().to_reporting(&position.symbol, amount));
+            }
+        }
+        groups.into_values().collect()
+    }
+}
+
+/// Cancel the group's unfilled basket legs and submit market orders closing
+/// its open positions, returning their IDs. Positions with an exit already in
+/// flight are left to it.
+pub fn close_group(positions: &PositionManager, orders: &OrderManager, group_id: &str) -> Result<Vec<String>> {
+    if !group_exists(positions, orders, group_id) {
+        bail!("Unknown position group '{}'", group_id);
+    }
+    let members = positions.group_positions(group_id);
+    orders.cancel_basket(group_id)?;
+
+    let mut order_ids = Vec::new();
+    for position in members {
+        if position.status == PositionStatus::Closed || positions.pending_exit_reason(&position.id).is_some() {
+            continue;
+        }
+        let side = match position.side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let mut order = Order::market(position.symbol, position.exchange, side, position.quantity);
+        order.position_id = Some(position.id.clone());
+        order_ids.push(orders.submit_order(order)?);
+        positions.mark_pending_exit(&position.id, ExitReason::Manual);
+    }
+    Ok(order_ids)
+}
+
+/// Whether a position or an active order carries `group_id`
+pub fn group_exists(positions: &PositionManager, orders: &OrderManager, group_id: &str) -> bool {
+    !positions.group_positions(group_id).is_empty()
+        || orders.get_active_orders().iter().any(|o| o.group_id.as_deref() == Some(group_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Symbol};
+    use crate::paper_trading::{BasketOrder, PaperTradingConfig, PaperTradingEngine};
+
+    #[test]
+    fn test_basket_opens_one_group_and_closes_in_one_call() {
+        let engine = PaperTradingEngine::new(PaperTradingConfig { enable_stop_loss: false, enable_take_profit: false, ..Default::default() });
+        let (btc, eth) = (Symbol::new("BTCUSDT"), Symbol::new("ETHUSDT"));
+        engine.update_price(btc.clone(), 50_000.0);
+        engine.update_price(eth.clone(), 2_500.0);
+        let basket = BasketOrder::new(vec![
+            Order::market(btc.clone(), Exchange::Binance, Side::Buy, 0.1),
+            Order::market(eth.clone(), Exchange::Binance, Side::Sell, 2.0),
+        ])
+        .with_group_id("pair-1");
+        engine.submit_basket(basket).unwrap();
+        engine.process_orders_once().unwrap();
+
+        engine.update_price(btc.clone(), 51_000.0);
+        engine.update_price(eth.clone(), 2_400.0);
+        let group = engine.position_manager().position_group("pair-1").unwrap();
+        assert_eq!((group.open_positions, group.symbols.clone()), (2, vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()]));
+        assert_eq!((group.gross_exposure, group.net_exposure), (5_100.0 + 4_800.0, 5_100.0 - 4_800.0));
+        // 100 on the long and 200 on the short, less entry costs
+        assert!(group.unrealized_pnl > 250.0 && group.unrealized_pnl < 300.0);
+
+        let order_ids = engine.close_group("pair-1").unwrap();
+        assert_eq!(order_ids.len(), 2);
+        assert!(engine.close_group("pair-1").unwrap().is_empty());
+        engine.process_orders_once().unwrap();
+        let group = engine.position_manager().position_group("pair-1").unwrap();
+        assert_eq!((group.open_positions, group.closed_positions, group.gross_exposure), (0, 2, 0.0));
+        assert!(engine.close_group("no-such-group").is_err());
+    }
+}
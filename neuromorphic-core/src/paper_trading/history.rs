@@ -0,0 +1,173 @@
+//! Bounded history of signals, returns and orders
+//!
+//! An engine left running for weeks must not grow with every signal it saw
+//! and every order it filled. The signal metrics, the risk metrics' returns
+//! and the order manager's finished orders are each kept to a configured
+//! number of the latest entries; older ones leave memory oldest first. Totals
+//! over everything, like order counts and implementation shortfall, are
+//! carried over as entries leave, so statistics don't change when they do.
+//!
+//! With `spill_dir` set, whatever leaves memory is appended to a JSON lines
+//! file there instead of being lost: `signals.jsonl`, `returns.jsonl` and
+//! `orders.jsonl`. Give each account its own directory.
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// How much history is kept in memory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub signals: usize, // Latest signals the signal metrics are taken over
+    pub returns: usize, // Latest equity returns the risk metrics are computed over
+    pub orders: usize, // Finished orders kept for lookups; live orders are always kept
+    pub spill_dir: Option<PathBuf>, // Append what leaves memory here
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            signals: 1000,
+            returns: 1000,
+            orders: 10_000,
+            spill_dir: None,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Spill file `name` in `spill_dir`, if set; kept in memory only, with a
+    /// warning, when it can't be opened
+    pub fn spill(&self, name: &str) -> Option<Spill> {
+        let dir = self.spill_dir.as_ref()?;
+        Spill::open(dir, name).map_err(|e| warn!(error = %format!("{:#}", e), "Dropping history instead of spilling it")).ok()
+    }
+}
+
+/// JSON lines file that records leaving memory are appended to
+#[derive(Clone)]
+pub struct Spill {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl Spill {
+    /// Append to `<dir>/<name>.jsonl`, creating the directory if needed
+    pub fn open(dir: impl AsRef<Path>, name: &str) -> Result<Self> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create history directory {}", dir.display()))?;
+        let path = dir.join(format!("{}.jsonl", name));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open history file {}", path.display()))?;
+        Ok(Self { path, file: Arc::new(Mutex::new(file)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record; a failed write is logged rather than failing the caller
+    pub fn write(&self, record: &impl Serialize) {
+        let written = serde_json::to_string(record)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| self.file.lock().write_all(format!("{}\n", line).as_bytes()).map_err(Into::into));
+        if let Err(e) = written {
+            warn!(error = %e, path = %self.path.display(), "Failed to spill history");
+        }
+    }
+}
+
+/// The latest `capacity` items, oldest first
+#[derive(Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    spill: Option<Spill>,
+}
+
+impl<T> std::fmt::Debug for RingBuffer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RingBuffer").field("len", &self.items.len()).field("capacity", &self.capacity).finish()
+    }
+}
+
+impl<T: Serialize> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { items: VecDeque::with_capacity(capacity.min(1024)), capacity, spill: None }
+    }
+
+    /// Append items dropped for being the oldest to `spill`
+    pub fn with_spill(mut self, spill: Option<Spill>) -> Self {
+        self.spill = spill;
+        self
+    }
+
+    /// Add an item, returning the oldest once full; it is spilled first
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let evicted = (self.items.len() == self.capacity).then(|| self.items.pop_front()).flatten();
+        if let (Some(evicted), Some(spill)) = (&evicted, &self.spill) {
+            spill.write(evicted);
+        }
+        self.items.push_back(item);
+        evicted
+    }
+
+    /// Drop everything held, without spilling it
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.items.iter()
+    }
+}
+
+impl<T: Serialize> std::ops::Index<usize> for RingBuffer<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_spills_what_it_drops() {
+        let dir = std::env::temp_dir().join(format!("history-{}", std::process::id()));
+        let spill = Spill::open(&dir, "returns").unwrap();
+        let mut buffer = RingBuffer::new(3).with_spill(Some(spill.clone()));
+
+        let evicted: Vec<Option<f64>> = [1.0, 2.0, 3.0, 4.0, 5.0].into_iter().map(|r| buffer.push(r)).collect();
+        assert_eq!(evicted, [None, None, None, Some(1.0), Some(2.0)]);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [3.0, 4.0, 5.0]);
+        assert_eq!(std::fs::read_to_string(spill.path()).unwrap(), "1.0\n2.0\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+//! Starting from an existing portfolio
+//!
+//! A paper session normally starts flat. To model the risk of a real
+//! portfolio instead, its positions can be imported at startup from a JSON or
+//! CSV file (`PaperTradingConfig::portfolio_file`), or into a running account
+//! through the API. Imported positions are opened at their own entry price and
+//! time without fees, so the session's P&L is what they make or lose from
+//! then on. A JSON file may also set the starting capital, which replaces
+//! `initial_capital` when the config is loaded.
+//!
+//! CSV columns: symbol,side,quantity,entry_price, then optionally
+//! exchange,entry_time,stop_loss,take_profit,strategy. Empty optional fields
+//! are left unset and a header row is optional. Sides are buy/long or
+//! sell/short; entry times are unix seconds, unix milliseconds or RFC 3339.
+
+use crate::exchanges::{Exchange, Side, Symbol};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use utoipa::ToSchema;
+
+/// One position of an existing portfolio
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ImportedPosition {
+    pub symbol: Symbol,
+    pub side: Side,
+    pub quantity: f64,
+    pub entry_price: f64,
+    #[serde(default = "default_exchange")]
+    pub exchange: Exchange,
+    #[serde(default)]
+    pub entry_time: Option<DateTime<Utc>>, // Import time when unset
+    #[serde(default)]
+    pub stop_loss: Option<f64>, // No stop unless set; risk_limits.stop_loss_pct is not applied
+    #[serde(default)]
+    pub take_profit: Option<f64>,
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+fn default_exchange() -> Exchange {
+    Exchange::Binance
+}
+
+impl ImportedPosition {
+    /// Positive size and price, and exit levels on the right side of the entry
+    pub fn validate(&self) -> Result<()> {
+        if !self.symbol.validate() {
+            bail!("Invalid symbol '{}'", self.symbol);
+        }
+        if !(self.quantity.is_finite() && self.quantity > 0.0) {
+            bail!("{}: quantity must be positive", self.symbol);
+        }
+        if !(self.entry_price.is_finite() && self.entry_price > 0.0) {
+            bail!("{}: entry_price must be positive", self.symbol);
+        }
+        let sign = self.side.multiplier();
+        if self.stop_loss.is_some_and(|stop| (self.entry_price - stop) * sign <= 0.0) {
+            bail!("{}: stop_loss must be on the losing side of entry_price", self.symbol);
+        }
+        if self.take_profit.is_some_and(|target| (target - self.entry_price) * sign <= 0.0) {
+            bail!("{}: take_profit must be on the winning side of entry_price", self.symbol);
+        }
+        Ok(())
+    }
+}
+
+/// Capital and positions to start a session from
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortfolioImport {
+    #[serde(default)]
+    pub capital: Option<f64>, // Equity of the portfolio, positions included
+    #[serde(default)]
+    pub positions: Vec<ImportedPosition>,
+}
+
+impl PortfolioImport {
+    /// Read a `.csv` file of positions, or JSON otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read portfolio {}", path.display()))?;
+        let portfolio = if path.extension().and_then(|e| e.to_str()) == Some("csv") {
+            Self { capital: None, positions: parse_csv(&text)? }
+        } else {
+            serde_json::from_str(&text).with_context(|| format!("Invalid portfolio {}", path.display()))?
+        };
+        portfolio.validate().with_context(|| format!("Invalid portfolio {}", path.display()))?;
+        Ok(portfolio)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(capital) = self.capital {
+            if !(capital.is_finite() && capital > 0.0) {
+                bail!("capital must be positive");
+            }
+        }
+        for (i, position) in self.positions.iter().enumerate() {
+            position.validate().with_context(|| format!("position {}", i + 1))?;
+        }
+        Ok(())
+    }
+}
+
+/// Positions from CSV rows, see the module docs for the columns
+pub fn parse_csv(text: &str) -> Result<Vec<ImportedPosition>> {
+    let mut positions = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if line.trim().is_empty() || (i == 0 && fields[0].eq_ignore_ascii_case("symbol")) {
+            continue;
+        }
+        if fields.len() < 4 {
+            bail!("line {}: expected at least 4 columns, found {}", i + 1, fields.len());
+        }
+        let field = |idx: usize| fields.get(idx).copied().filter(|f| !f.is_empty());
+        let number = |idx: usize| -> Result<Option<f64>> {
+            field(idx)
+                .map(|f| f.parse().with_context(|| format!("line {}: invalid number '{}'", i + 1, f)))
+                .transpose()
+        };
+
+        let side = match fields[1].to_ascii_lowercase().as_str() {
+            "buy" | "long" => Side::Buy,
+            "sell" | "short" => Side::Sell,
+            other => bail!("line {}: invalid side '{}'", i + 1, other),
+        };
+        let exchange = match field(4) {
+            Some(name) => *Exchange::ALL
+                .iter()
+                .find(|e| e.to_string().eq_ignore_ascii_case(name))
+                .with_context(|| format!("line {}: unknown exchange '{}'", i + 1, name))?,
+            None => default_exchange(),
+        };
+        let entry_time = field(5)
+            .map(|t| parse_time(t).with_context(|| format!("line {}: invalid entry time '{}'", i + 1, t)))
+            .transpose()?;
+
+        positions.push(ImportedPosition {
+            symbol: Symbol::new(fields[0]),
+            side,
+            quantity: number(2)?.unwrap_or(0.0),
+            entry_price: number(3)?.unwrap_or(0.0),
+            exchange,
+            entry_time,
+            stop_loss: number(6)?,
+            take_profit: number(7)?,
+            strategy: field(8).map(str::to_string),
+        });
+    }
+
+    Ok(positions)
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(n) = value.parse::<i64>() {
+        // Anything past year ~5000 in seconds is taken as milliseconds
+        return if n > 100_000_000_000 {
+            Utc.timestamp_millis_opt(n).single()
+        } else {
+            Utc.timestamp_opt(n, 0).single()
+        };
+    }
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_rows_with_optional_columns() {
+        let csv = "symbol,side,quantity,entry_price,exchange,entry_time,stop_loss,take_profit,strategy\n\
+                   BTCUSDT,long,0.5,60000\n\
+                   AAPL,buy,10,180.5,nasdaq,1704067200,170,,core\n\
+                   ETHUSDT,short,2,3000,,2024-01-01T00:00:00Z,3200,2500\n";
+        let positions = parse_csv(csv).unwrap();
+        assert_eq!(positions.len(), 3);
+        assert_eq!((positions[0].exchange, positions[0].entry_time), (Exchange::Binance, None));
+        assert_eq!((positions[1].exchange, positions[1].stop_loss, positions[1].take_profit), (Exchange::NASDAQ, Some(170.0), None));
+        assert_eq!(positions[1].strategy.as_deref(), Some("core"));
+        assert_eq!(positions[1].entry_time, positions[2].entry_time);
+        assert_eq!(positions[2].side, Side::Sell);
+        let portfolio = PortfolioImport { capital: Some(250_000.0), positions };
+        assert!(portfolio.validate().is_ok());
+
+        // A short's stop is above its entry
+        let wrong_stop = "ETHUSDT,sell,2,3000,,,2800\n";
+        let err = PortfolioImport { capital: None, positions: parse_csv(wrong_stop).unwrap() }.validate().unwrap_err();
+        assert!(format!("{:#}", err).contains("stop_loss"));
+        assert!(parse_csv("BTCUSDT,hold,1,100\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_engine_starts_from_portfolio_file() {
+        use crate::paper_trading::PaperTradingEngine;
+        use crate::RunConfig;
+
+        let path = std::env::temp_dir().join(format!("portfolio-{}.json", std::process::id()));
+        let json = r#"{"capital": 250000.0, "positions": [
+            {"symbol": "BTCUSDT", "side": "Buy", "quantity": 2.0, "entry_price": 50000.0, "stop_loss": 45000.0},
+            {"symbol": "ETHUSDT", "side": "Sell", "quantity": 10.0, "entry_price": 3000.0, "entry_time": "2024-01-01T00:00:00Z"}
+        ]}"#;
+        std::fs::write(&path, json).unwrap();
+        let toml = format!("[trading]\nportfolio_file = {:?}\n", path.display().to_string());
+        let config = RunConfig::from_sources(vec![(path.with_extension("toml"), toml)], vec![]).unwrap();
+        assert_eq!(config.trading.initial_capital, 250_000.0);
+
+        let mut engine = PaperTradingEngine::new(config.trading.clone());
+        engine.start().await.unwrap();
+        engine.stop().await.unwrap();
+        let mut open = engine.position_manager().get_open_positions();
+        open.sort_by(|a, b| a.symbol.0.cmp(&b.symbol.0));
+        assert_eq!(open.len(), 2);
+        assert_eq!((open[0].stop_loss, open[0].commission), (Some(45_000.0), 0.0));
+        assert_eq!(open[1].entry_time, 1_704_067_200_000);
+
+        // Marked at entry until prices arrive, then the P&L is the session's
+        engine.update_price(Symbol::new("BTCUSDT"), 51_000.0);
+        assert_eq!(engine.position_manager().total_pnl(), 2_000.0);
+
+        // A restored engine does not import on top of its positions
+        let mut resumed = PaperTradingEngine::new(config.trading.clone());
+        resumed.restore(&engine.snapshot()).unwrap();
+        resumed.start().await.unwrap();
+        resumed.stop().await.unwrap();
+        assert_eq!(resumed.position_manager().get_open_positions().len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}
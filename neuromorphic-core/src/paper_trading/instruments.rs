@@ -0,0 +1,248 @@
+//! Per-symbol instrument settings
+//!
+//! Crypto pairs and equities trade differently: a Binance pair trades around
+//! the clock in fractions of a coin, a US stock in whole shares at cents
+//! during the New York session. An `InstrumentConfig` says, per symbol,
+//!
+//! - the contract multiplier, quote currency per unit of quantity and price,
+//!   which P&L, exposure and position sizing scale by
+//! - the quote precision limit and stop prices are rounded to, and the step
+//!   order quantities are rounded down to
+//! - the trading hours, outside which orders are rejected
+//!
+//! Symbols without an entry get the defaults of their asset class: crypto for
+//! pairs with a recognisable quote currency, like "BTCUSDT" or "ETH-USD", US
+//! equities for anything else, like "AAPL". Positions keep the multiplier
+//! they were opened with.
+
+use super::currency::CurrencyConverter;
+use super::order_manager::Order;
+use crate::exchanges::Symbol;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Crypto,
+    Equity,
+}
+
+/// Time zone of a trading session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketTimezone {
+    Utc,
+    NewYork, // With US daylight saving time
+}
+
+impl MarketTimezone {
+    fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Self::Utc => at.naive_utc(),
+            Self::NewYork => at.naive_utc() - chrono::Duration::hours(if us_daylight_saving(at) { 4 } else { 5 }),
+        }
+    }
+}
+
+/// From 2am on the second Sunday of March to 2am on the first Sunday of November
+fn us_daylight_saving(at: DateTime<Utc>) -> bool {
+    let sunday = |month, n| NaiveDate::from_weekday_of_month_opt(at.year(), month, Weekday::Sun, n).expect("months have two Sundays");
+    let starts = sunday(3, 2).and_hms_opt(7, 0, 0).expect("valid time").and_utc();
+    let ends = sunday(11, 1).and_hms_opt(6, 0, 0).expect("valid time").and_utc();
+    at >= starts && at < ends
+}
+
+/// When an instrument trades
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingHours {
+    Always,
+    /// Daily from `open` to `close` local time; a session past midnight has `close` before `open`
+    Session {
+        open: NaiveTime,
+        close: NaiveTime,
+        timezone: MarketTimezone,
+        #[serde(default)]
+        weekdays_only: bool,
+        #[serde(default)]
+        holidays: Vec<NaiveDate>, // Local dates the market stays closed
+    },
+}
+
+impl TradingHours {
+    /// NYSE and Nasdaq regular hours, 9:30 to 16:00 New York time on weekdays
+    pub fn us_equities() -> Self {
+        Self::Session {
+            open: NaiveTime::from_hms_opt(9, 30, 0).expect("valid time"),
+            close: NaiveTime::from_hms_opt(16, 0, 0).expect("valid time"),
+            timezone: MarketTimezone::NewYork,
+            weekdays_only: true,
+            holidays: Vec::new(),
+        }
+    }
+
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        let Self::Session { open, close, timezone, weekdays_only, holidays } = self else {
+            return true;
+        };
+        let local = timezone.local(at);
+        if (*weekdays_only && local.weekday().number_from_monday() > 5) || holidays.contains(&local.date()) {
+            return false;
+        }
+        let time = local.time();
+        if open <= close {
+            time >= *open && time < *close
+        } else {
+            time >= *open || time < *close
+        }
+    }
+}
+
+/// How a symbol trades, `[trading.instruments.<symbol>]`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentConfig {
+    pub asset_class: AssetClass,
+    pub multiplier: f64,     // Quote currency per unit of quantity and price
+    pub price_decimals: u32, // Limit and stop prices are rounded to these
+    pub quantity_step: f64,  // Quantities are rounded down to a multiple; 0 allows any
+    pub hours: TradingHours,
+}
+
+impl InstrumentConfig {
+    /// Binance spot pairs: around the clock, to eight decimals, any quantity
+    pub fn crypto() -> Self {
+        Self {
+            asset_class: AssetClass::Crypto,
+            multiplier: 1.0,
+            price_decimals: 8,
+            quantity_step: 0.0,
+            hours: TradingHours::Always,
+        }
+    }
+
+    /// US stocks: whole shares at cents in regular hours
+    pub fn us_equity() -> Self {
+        Self {
+            asset_class: AssetClass::Equity,
+            multiplier: 1.0,
+            price_decimals: 2,
+            quantity_step: 1.0,
+            hours: TradingHours::us_equities(),
+        }
+    }
+
+    pub fn for_class(asset_class: AssetClass) -> Self {
+        match asset_class {
+            AssetClass::Crypto => Self::crypto(),
+            AssetClass::Equity => Self::us_equity(),
+        }
+    }
+
+    /// Defaults for the symbol's asset class, guessed from its name
+    pub fn for_symbol(symbol: &Symbol) -> Self {
+        if CurrencyConverter::split_symbol(symbol).is_some() { Self::crypto() } else { Self::us_equity() }
+    }
+
+    pub fn round_price(&self, price: f64) -> f64 {
+        let scale = 10_f64.powi(self.price_decimals as i32);
+        (price * scale).round() / scale
+    }
+
+    /// Down to the quantity step, allowing for float error just below one
+    pub fn round_quantity(&self, quantity: f64) -> f64 {
+        if self.quantity_step <= 0.0 {
+            return quantity;
+        }
+        (quantity / self.quantity_step + 1e-9).floor() * self.quantity_step
+    }
+
+    /// Round an order's prices and quantity to what the instrument allows;
+    /// fails when its market is closed or no quantity is left
+    pub fn normalize(&self, order: &mut Order, now: DateTime<Utc>) -> Result<()> {
+        if !self.hours.is_open(now) {
+            bail!("Market for {} is closed", order.symbol);
+        }
+        let quantity = self.round_quantity(order.quantity);
+        if quantity <= 0.0 {
+            bail!("Quantity {} of {} is below its step of {}", order.quantity, order.symbol, self.quantity_step);
+        }
+        order.quantity = quantity;
+        order.price = order.price.map(|price| self.round_price(price));
+        order.stop_price = order.stop_price.map(|price| self.round_price(price));
+        Ok(())
+    }
+}
+
+/// Instrument settings of every symbol: configured ones, else the defaults
+/// of its asset class
+#[derive(Default)]
+pub struct InstrumentRegistry {
+    instruments: DashMap<Symbol, InstrumentConfig>,
+}
+
+impl InstrumentRegistry {
+    pub fn new(configured: &BTreeMap<String, InstrumentConfig>) -> Self {
+        let registry = Self::default();
+        for (symbol, config) in configured {
+            registry.set(Symbol::new(symbol), config.clone());
+        }
+        registry
+    }
+
+    pub fn get(&self, symbol: &Symbol) -> InstrumentConfig {
+        self.instruments.get(symbol).map_or_else(|| InstrumentConfig::for_symbol(symbol), |config| config.clone())
+    }
+
+    /// Configure a symbol; positions already open keep their multiplier
+    pub fn set(&self, symbol: Symbol, config: InstrumentConfig) {
+        self.instruments.insert(symbol, config);
+    }
+
+    pub fn multiplier(&self, symbol: &Symbol) -> f64 {
+        self.instruments.get(symbol).map_or(1.0, |config| config.multiplier)
+    }
+
+    /// Whether the symbol's market is open at `at`
+    pub fn is_open(&self, symbol: &Symbol, at: DateTime<Utc>) -> bool {
+        self.get(symbol).hours.is_open(at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side};
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_equities_trade_in_whole_shares_during_new_york_hours() {
+        let registry = InstrumentRegistry::default();
+        let (aapl, btc) = (Symbol::new("AAPL"), Symbol::new("BTCUSDT"));
+        assert_eq!(registry.get(&aapl).asset_class, AssetClass::Equity);
+        assert_eq!(registry.get(&btc).asset_class, AssetClass::Crypto);
+
+        // 9:30 New York is 13:30 UTC in summer and 14:30 in winter
+        assert!(registry.is_open(&aapl, utc("2026-07-15T13:30:00Z")));
+        assert!(!registry.is_open(&aapl, utc("2026-01-15T13:30:00Z")));
+        assert!(registry.is_open(&aapl, utc("2026-01-15T14:30:00Z")));
+        assert!(!registry.is_open(&aapl, utc("2026-07-18T15:00:00Z"))); // Saturday
+        assert!(registry.is_open(&btc, utc("2026-07-18T15:00:00Z")));
+
+        let mut order = Order::limit(aapl.clone(), Exchange::Binance, Side::Buy, 10.7, 187.23456);
+        registry.get(&aapl).normalize(&mut order, utc("2026-07-15T15:00:00Z")).unwrap();
+        assert_eq!((order.quantity, order.price), (10.0, Some(187.23)));
+        let mut order = Order::market(aapl.clone(), Exchange::Binance, Side::Buy, 0.4);
+        assert!(registry.get(&aapl).normalize(&mut order, utc("2026-07-15T15:00:00Z")).is_err());
+
+        let future = InstrumentConfig { multiplier: 50.0, hours: TradingHours::Always, ..InstrumentConfig::us_equity() };
+        registry.set(Symbol::new("ES"), future);
+        assert_eq!((registry.multiplier(&Symbol::new("ES")), registry.multiplier(&aapl)), (50.0, 1.0));
+    }
+}
@@ -0,0 +1,226 @@
+//! Property tests for order matching and fill booking
+//!
+//! Random sequences of orders, cancels, amendments, position closes and price
+//! moves are run through an engine one step at a time. After every step the
+//! order book and the position book must agree.
+
+use super::{Order, OrderStatus, PaperTradingConfig, PaperTradingEngine};
+use crate::exchanges::{Exchange, Side, Symbol};
+use dashmap::DashMap;
+use proptest::prelude::*;
+
+const SYMBOLS: [&str; 2] = ["BTC-USD", "ETH-USD"];
+const START_PRICE: f64 = 100.0;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Price { symbol: usize, price: f64 },
+    Market { symbol: usize, side: Side, quantity: f64 },
+    Limit { symbol: usize, side: Side, quantity: f64, offset_pct: f64 },
+    Stop { symbol: usize, side: Side, quantity: f64, offset_pct: f64 },
+    /// Closing order tied to an open position, submitted even if one is already in flight
+    Close { position: usize },
+    Cancel { order: usize },
+    Amend { order: usize, quantity: Option<f64>, offset_pct: Option<f64> },
+}
+
+fn side() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Buy), Just(Side::Sell)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let symbol = 0..SYMBOLS.len();
+    let quantity = 0.01..5.0f64;
+    let offset = -5.0..5.0f64;
+    prop_oneof![
+        3 => (symbol.clone(), 50.0..150.0f64).prop_map(|(symbol, price)| Op::Price { symbol, price }),
+        2 => (symbol.clone(), side(), quantity.clone())
+            .prop_map(|(symbol, side, quantity)| Op::Market { symbol, side, quantity }),
+        2 => (symbol.clone(), side(), quantity.clone(), offset.clone())
+            .prop_map(|(symbol, side, quantity, offset_pct)| Op::Limit { symbol, side, quantity, offset_pct }),
+        1 => (symbol, side(), quantity.clone(), offset.clone())
+            .prop_map(|(symbol, side, quantity, offset_pct)| Op::Stop { symbol, side, quantity, offset_pct }),
+        1 => any::<usize>().prop_map(|position| Op::Close { position }),
+        1 => any::<usize>().prop_map(|order| Op::Cancel { order }),
+        1 => (any::<usize>(), proptest::option::of(quantity), proptest::option::of(offset))
+            .prop_map(|(order, quantity, offset_pct)| Op::Amend { order, quantity, offset_pct }),
+    ]
+}
+
+struct Harness {
+    engine: PaperTradingEngine,
+    marks: DashMap<Symbol, f64>,
+    steps: usize,
+}
+
+impl Harness {
+    fn new(hedge_mode: bool) -> Self {
+        let engine = PaperTradingEngine::new(PaperTradingConfig {
+            hedge_mode,
+            ..Default::default()
+        });
+        let marks = DashMap::new();
+        for symbol in SYMBOLS {
+            engine.update_price(Symbol::new(symbol), START_PRICE);
+            marks.insert(Symbol::new(symbol), START_PRICE);
+        }
+        Self { engine, marks, steps: 0 }
+    }
+
+    fn mark(&self, symbol: usize) -> f64 {
+        *self.marks.get(&Symbol::new(SYMBOLS[symbol])).unwrap()
+    }
+
+    fn apply(&mut self, op: Op) {
+        let orders = self.engine.order_manager();
+        let exchange = Exchange::Binance;
+        match op {
+            Op::Price { symbol, price } => {
+                self.marks.insert(Symbol::new(SYMBOLS[symbol]), price);
+                self.engine.update_price(Symbol::new(SYMBOLS[symbol]), price);
+            }
+            Op::Market { symbol, side, quantity } => {
+                orders.submit_order(Order::market(Symbol::new(SYMBOLS[symbol]), exchange, side, quantity)).unwrap();
+            }
+            Op::Limit { symbol, side, quantity, offset_pct } => {
+                let price = self.mark(symbol) * (1.0 + offset_pct / 100.0);
+                orders.submit_order(Order::limit(Symbol::new(SYMBOLS[symbol]), exchange, side, quantity, price)).unwrap();
+            }
+            Op::Stop { symbol, side, quantity, offset_pct } => {
+                let stop = self.mark(symbol) * (1.0 + offset_pct / 100.0);
+                orders.submit_order(Order::stop_loss(Symbol::new(SYMBOLS[symbol]), exchange, side, quantity, stop)).unwrap();
+            }
+            Op::Close { position } => {
+                let open = self.engine.position_manager().get_open_positions();
+                if let Some(pos) = open.get(position % open.len().max(1)) {
+                    let side = match pos.side {
+                        Side::Buy => Side::Sell,
+                        Side::Sell => Side::Buy,
+                    };
+                    let mut order = Order::market(pos.symbol.clone(), pos.exchange, side, pos.quantity);
+                    order.position_id = Some(pos.id.clone());
+                    orders.submit_order(order).unwrap();
+                }
+            }
+            Op::Cancel { order } => {
+                let active = orders.get_active_orders();
+                if let Some(o) = active.get(order % active.len().max(1)) {
+                    orders.cancel_order(&o.id).unwrap();
+                }
+            }
+            Op::Amend { order, quantity, offset_pct } => {
+                let active = orders.get_active_orders();
+                if let Some(o) = active.get(order % active.len().max(1)) {
+                    let price = offset_pct.map(|pct| *self.marks.get(&o.symbol).unwrap() * (1.0 + pct / 100.0));
+                    // Market orders have no price to amend; that refusal is expected
+                    let _ = orders.amend_order(&o.id, quantity, price);
+                }
+            }
+        }
+
+        let filled = self.engine.process_orders_once().unwrap();
+        self.steps += 1;
+
+        // Capital tracks the books as of the fill, with costs counted once
+        if !filled.is_empty() {
+            let expected = self.engine.config().initial_capital + self.engine.position_manager().total_pnl();
+            assert!(
+                (self.engine.capital() - expected).abs() < 1e-6,
+                "capital {} drifted from initial capital plus P&L {}",
+                self.engine.capital(),
+                expected
+            );
+        }
+    }
+
+    fn check_books(&self) {
+        let positions = self.engine.position_manager();
+        let hedge_mode = self.engine.config().hedge_mode;
+        positions.update_prices(&self.marks);
+
+        let mut expected_pnl = 0.0;
+        let mut order_commission = 0.0;
+        let mut notional = 0.0;
+
+        for name in SYMBOLS {
+            let symbol = Symbol::new(name);
+            let mut filled_net = 0.0;
+
+            for order in self.engine.order_manager().get_orders_by_symbol(&symbol) {
+                assert!(
+                    order.filled_quantity <= order.quantity + 1e-9,
+                    "order {} filled {} of {}",
+                    order.id,
+                    order.filled_quantity,
+                    order.quantity
+                );
+                if order.status != OrderStatus::Filled {
+                    continue;
+                }
+                let signed = match order.side {
+                    Side::Buy => order.filled_quantity,
+                    Side::Sell => -order.filled_quantity,
+                };
+                filled_net += signed;
+                expected_pnl -= signed * order.avg_fill_price + order.commission + order.slippage;
+                order_commission += order.commission;
+                notional += order.filled_quantity * order.avg_fill_price;
+            }
+
+            let open = positions.get_open_positions_by_symbol(&symbol);
+            assert!(open.iter().all(|p| p.quantity > 0.0), "non-positive open position in {}", name);
+            if !hedge_mode {
+                let sides: Vec<Side> = open.iter().map(|p| p.side).collect();
+                assert!(
+                    !(sides.contains(&Side::Buy) && sides.contains(&Side::Sell)),
+                    "one-way mode holds both a long and a short in {}",
+                    name
+                );
+            }
+
+            // Every filled quantity is reflected in the positions, nothing lost or doubled
+            let position_net = positions.get_net_position(&symbol);
+            assert!(
+                (position_net - filled_net).abs() < 1e-6,
+                "{}: positions net {} but fills net {}",
+                name,
+                position_net,
+                filled_net
+            );
+            expected_pnl += filled_net * *self.marks.get(&symbol).unwrap();
+        }
+
+        // Cents rounding on the position totals allows a little slack per step
+        let tolerance = 0.01 * (self.steps + 2) as f64 + notional * 1e-9;
+        let actual_pnl = positions.total_pnl();
+        assert!(
+            (actual_pnl - expected_pnl).abs() < tolerance,
+            "position P&L {} but fills imply {}",
+            actual_pnl,
+            expected_pnl
+        );
+        let stats = positions.get_statistics();
+        assert!(
+            (stats.total_commission - order_commission).abs() < tolerance,
+            "positions charged {} commission but orders paid {}",
+            stats.total_commission,
+            order_commission
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn prop_order_and_position_books_agree(
+        hedge_mode in any::<bool>(),
+        ops in proptest::collection::vec(op(), 1..60),
+    ) {
+        let mut harness = Harness::new(hedge_mode);
+        for op in ops {
+            harness.apply(op);
+            harness.check_books();
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Ranking of accounts, e.g. the tenants of a hosted competition
+//!
+//! Each account is ranked on its own statistics by return, Sharpe ratio or
+//! maximum drawdown. Ties keep the accounts' order, so a ranking only changes
+//! when the numbers do.
+
+use super::accounts::AccountStatistics;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What accounts are ranked by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    #[default]
+    Return, // Highest first
+    Sharpe, // Highest first
+    Drawdown, // Smallest first
+}
+
+/// One account's place on the leaderboard
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LeaderboardEntry {
+    pub rank: usize, // From 1
+    pub account_id: String,
+    pub total_return_pct: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub total_pnl: f64,
+    pub capital: f64,
+    pub trades: u64, // Closed
+    pub win_rate: f64,
+}
+
+/// Accounts ranked by one metric
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct Leaderboard {
+    pub by: LeaderboardMetric,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn rank(accounts: &[AccountStatistics], by: LeaderboardMetric) -> Self {
+        let mut ranked: Vec<&AccountStatistics> = accounts.iter().collect();
+        match by {
+            LeaderboardMetric::Return => ranked.sort_by(|a, b| b.total_return_pct.total_cmp(&a.total_return_pct)),
+            LeaderboardMetric::Sharpe => ranked.sort_by(|a, b| b.sharpe_ratio.total_cmp(&a.sharpe_ratio)),
+            LeaderboardMetric::Drawdown => ranked.sort_by(|a, b| a.max_drawdown.total_cmp(&b.max_drawdown)),
+        }
+        let entries = ranked
+            .into_iter()
+            .enumerate()
+            .map(|(i, account)| LeaderboardEntry {
+                rank: i + 1,
+                account_id: account.account_id.clone(),
+                total_return_pct: account.total_return_pct,
+                sharpe_ratio: account.sharpe_ratio,
+                max_drawdown: account.max_drawdown,
+                total_pnl: account.total_pnl,
+                capital: account.capital,
+                trades: account.winning_trades + account.losing_trades,
+                win_rate: account.win_rate,
+            })
+            .collect();
+        Self { by, entries }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accounts_rank_by_each_metric() {
+        let account = |id: &str, total_return_pct, sharpe_ratio, max_drawdown| AccountStatistics {
+            account_id: id.to_string(),
+            total_return_pct,
+            sharpe_ratio,
+            max_drawdown,
+            ..Default::default()
+        };
+        let accounts = [account("alice", 12.0, 0.8, 0.20), account("bob", 4.0, 1.9, 0.03), account("carol", 4.0, 1.1, 0.08)];
+        let order = |by| Leaderboard::rank(&accounts, by).entries.into_iter().map(|e| e.account_id).collect::<Vec<_>>();
+
+        assert_eq!(order(LeaderboardMetric::Return), ["alice", "bob", "carol"]);
+        assert_eq!(order(LeaderboardMetric::Sharpe), ["bob", "carol", "alice"]);
+        assert_eq!(order(LeaderboardMetric::Drawdown), ["bob", "carol", "alice"]);
+        assert_eq!(Leaderboard::rank(&accounts, LeaderboardMetric::Sharpe).entries[2].rank, 3);
+    }
+}
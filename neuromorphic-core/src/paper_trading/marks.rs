@@ -0,0 +1,212 @@
+//! Mark prices of open positions
+//!
+//! Marking positions to the last trade lets one odd print in a thin market
+//! move unrealized P&L, trip a stop or breach a risk limit. The mark price a
+//! symbol's positions are valued at is chosen per symbol instead: the last
+//! trade, the mid of the displayed book, the composite best bid and offer
+//! across venues, or an exponentially weighted average of the mid. Positions,
+//! their exits and the risk manager's exposure and volatility all read the
+//! mark; orders still fill against the last trade.
+//!
+//! Every source falls back to the last trade until it has a value of its
+//! own, so an engine fed trades only marks to them whatever the policy.
+
+use crate::exchanges::Symbol;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkSource {
+    Last, // Last trade
+    Mid, // Mid of the best bid and ask of the displayed book
+    #[default]
+    Composite, // Mark of the best bid and offer across venues, when the feed consolidates them
+    EwmaMid, // Exponentially weighted average of the mid, with a half-life of `half_life_secs`
+}
+
+/// How a symbol's positions are marked
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkPrice {
+    pub source: MarkSource,
+    pub half_life_secs: f64, // Of the EWMA of the mid
+}
+
+impl Default for MarkPrice {
+    fn default() -> Self {
+        Self {
+            source: MarkSource::Composite,
+            half_life_secs: 30.0,
+        }
+    }
+}
+
+enum Input {
+    Trade(f64),
+    Mid(f64),
+    Composite(f64),
+}
+
+#[derive(Default)]
+struct Inputs {
+    last: Option<f64>,
+    mid: Option<f64>,
+    composite: Option<f64>,
+    ewma: Option<(f64, u64)>, // Value and when it was last updated
+}
+
+/// Latest mark of every symbol, from its prices under its policy
+pub struct MarkPrices {
+    default: MarkPrice,
+    symbols: BTreeMap<String, MarkPrice>,
+    inputs: DashMap<Symbol, Inputs>,
+    marks: DashMap<Symbol, f64>,
+}
+
+impl MarkPrices {
+    /// Mark by `default`, or by `symbols` for the symbols it names
+    pub fn new(default: MarkPrice, symbols: BTreeMap<String, MarkPrice>) -> Self {
+        Self {
+            default,
+            symbols,
+            inputs: DashMap::new(),
+            marks: DashMap::new(),
+        }
+    }
+
+    pub fn policy(&self, symbol: &Symbol) -> &MarkPrice {
+        self.symbols.get(symbol.as_str()).unwrap_or(&self.default)
+    }
+
+    /// Record a trade; returns the symbol's mark, which trades always feed
+    pub fn on_trade(&self, symbol: &Symbol, price: f64, now_ms: u64) -> Option<f64> {
+        self.update(symbol, Input::Trade(price), now_ms)
+    }
+
+    /// Record the top of the book; returns the new mark if the symbol is
+    /// marked from the mid
+    pub fn on_book(&self, symbol: &Symbol, bid: f64, ask: f64, now_ms: u64) -> Option<f64> {
+        if !(bid > 0.0 && ask >= bid) {
+            return None; // One-sided or crossed
+        }
+        self.update(symbol, Input::Mid((bid + ask) / 2.0), now_ms)
+    }
+
+    /// Record the composite mark across venues; returns the new mark if the
+    /// symbol is marked from it
+    pub fn on_composite(&self, symbol: &Symbol, price: f64, now_ms: u64) -> Option<f64> {
+        self.update(symbol, Input::Composite(price), now_ms)
+    }
+
+    pub fn get(&self, symbol: &Symbol) -> Option<f64> {
+        self.marks.get(symbol).map(|mark| *mark)
+    }
+
+    /// Marks by symbol
+    pub fn prices(&self) -> &DashMap<Symbol, f64> {
+        &self.marks
+    }
+
+    /// Forget every price and mark
+    pub fn clear(&self) {
+        self.inputs.clear();
+        self.marks.clear();
+    }
+
+    fn update(&self, symbol: &Symbol, input: Input, now_ms: u64) -> Option<f64> {
+        let (Input::Trade(price) | Input::Mid(price) | Input::Composite(price)) = input;
+        if !price.is_finite() || price <= 0.0 {
+            return None;
+        }
+        let policy = *self.policy(symbol);
+        let mut inputs = match self.inputs.get_mut(symbol) {
+            Some(inputs) => inputs,
+            None => self.inputs.entry(symbol.clone()).or_default(),
+        };
+        let first_mid = matches!(input, Input::Mid(_)) && inputs.mid.is_none();
+        let used = match input {
+            Input::Trade(_) => {
+                inputs.last = Some(price);
+                true
+            }
+            Input::Mid(_) => {
+                inputs.mid = Some(price);
+                matches!(policy.source, MarkSource::Mid | MarkSource::EwmaMid)
+            }
+            Input::Composite(_) => {
+                inputs.composite = Some(price);
+                policy.source == MarkSource::Composite
+            }
+        };
+        // The average follows trades until there is a mid, then starts over from it
+        if policy.source == MarkSource::EwmaMid && (matches!(input, Input::Mid(_)) || inputs.mid.is_none()) {
+            inputs.ewma = Some(match inputs.ewma.filter(|_| !first_mid) {
+                Some((ewma, at)) => {
+                    let elapsed_secs = now_ms.saturating_sub(at) as f64 / 1000.0;
+                    let alpha = 1.0 - 0.5f64.powf(elapsed_secs / policy.half_life_secs);
+                    (ewma + alpha * (price - ewma), now_ms)
+                }
+                None => (price, now_ms),
+            });
+        }
+        if !used {
+            return None;
+        }
+        let mark = match policy.source {
+            MarkSource::Last => inputs.last,
+            MarkSource::Mid => inputs.mid.or(inputs.last),
+            MarkSource::Composite => inputs.composite.or(inputs.last),
+            MarkSource::EwmaMid => inputs.ewma.map(|(ewma, _)| ewma).or(inputs.last),
+        }?;
+        drop(inputs);
+        match self.marks.get_mut(symbol) {
+            Some(mut current) => *current = mark,
+            None => {
+                self.marks.insert(symbol.clone(), mark);
+            }
+        }
+        Some(mark)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks_follow_the_symbol_policy() {
+        let policy = |source| MarkPrice { source, half_life_secs: 10.0 };
+        let symbols = BTreeMap::from([
+            ("LAST".to_string(), policy(MarkSource::Last)),
+            ("MID".to_string(), policy(MarkSource::Mid)),
+            ("EWMA".to_string(), policy(MarkSource::EwmaMid)),
+        ]);
+        let marks = MarkPrices::new(MarkPrice::default(), symbols);
+        let (last, mid, ewma, composite) = (Symbol::new("LAST"), Symbol::new("MID"), Symbol::new("EWMA"), Symbol::new("BTCUSDT"));
+
+        // Trades mark every symbol until its own source has a price
+        for symbol in [&last, &mid, &ewma, &composite] {
+            assert_eq!(marks.on_trade(symbol, 100.0, 0), Some(100.0));
+        }
+        assert_eq!(marks.on_book(&last, 99.0, 101.0, 0), None);
+        assert_eq!(marks.on_book(&mid, 101.0, 103.0, 0), Some(102.0));
+        assert_eq!(marks.on_composite(&composite, 100.5, 0), Some(100.5));
+
+        // A print away from the book moves the last trade mark only
+        for symbol in [&last, &mid, &composite] {
+            marks.on_trade(symbol, 90.0, 1_000);
+        }
+        assert_eq!(marks.get(&last), Some(90.0));
+        assert_eq!(marks.get(&mid), Some(102.0));
+        assert_eq!(marks.get(&composite), Some(100.5));
+
+        // The average moves half way to the mid each half-life
+        assert_eq!(marks.on_book(&ewma, 109.0, 111.0, 0), Some(110.0));
+        let moved = marks.on_book(&ewma, 119.0, 121.0, 10_000).unwrap();
+        assert!((moved - 115.0).abs() < 1e-9, "{}", moved);
+        assert_eq!(marks.on_trade(&ewma, 50.0, 10_000), Some(moved));
+        assert_eq!(marks.prices().len(), 4);
+    }
+}
@@ -4,17 +4,97 @@ pub mod position_manager;
 pub mod order_manager;
 pub mod risk_manager;
 pub mod engine;
+pub mod fees;
+pub mod currency;
+pub mod accounts;
+pub mod routing;
+pub mod execution;
+pub mod reconciliation;
+pub mod rolling;
+pub mod returns;
+pub mod history;
+pub mod queue;
+pub mod clock;
+pub mod outcomes;
+pub mod calibration;
+pub mod throttle;
+pub mod aggregation;
+pub mod snapshot;
+pub mod events;
+pub mod attribution;
+pub mod scenarios;
+pub mod import;
+pub mod groups;
+pub mod execution_algos;
+pub mod participation;
+pub mod queue_position;
+pub mod chaos;
+pub mod shortfall;
+pub mod leaderboard;
+pub mod scoring;
+pub mod instruments;
+pub mod stops;
+pub mod trade_management;
+pub mod rebalancing;
+pub mod marks;
+pub mod rng;
 
-pub use position_manager::{PositionManager, Position, PositionStatus, PositionStatistics};
+#[cfg(test)]
+mod invariants;
+
+pub use position_manager::{
+    PositionManager, Position, PositionBook, PositionStatus, PositionStatistics,
+    ExitReason, ExitReasonStats, ExitRule, TriggeredExit
+};
 pub use order_manager::{
-    OrderManager, Order, OrderType, OrderStatus, OrderEvent, 
-    TimeInForce, SlippageModel
+    OrderManager, Order, OrderBook, OrderType, OrderStatus, OrderEvent, 
+    TimeInForce, SlippageModel, BasketOrder, Fill
 };
 pub use risk_manager::{
-    RiskManager, RiskLimits, RiskMetrics, RiskCheckResult,
-    KellyCriterion, PortfolioHeatMap
+    RiskManager, RiskLimits, RiskMetrics, RiskCheckResult, RiskEvent,
+    KellyCriterion, PortfolioHeatMap, RiskState
+};
+pub use fees::{FeeSchedule, FeeRates, FeeTier, LiquidityRole};
+pub use currency::CurrencyConverter;
+pub use accounts::{Accounts, AccountStatistics, DEFAULT_ACCOUNT, CONSOLIDATED_ACCOUNT};
+pub use routing::{RouteRule, SignalRouter};
+pub use execution::{ExecutionMode, ExecutionVenue, StreamingVenue};
+pub use reconciliation::{
+    Reconciler, ReconciliationConfig, ReconciliationEvent, Discrepancy, VenueState
+};
+pub use queue::{OverflowPolicy, QueueConfig, QueueStatistics, QueueError};
+pub use aggregation::{
+    AggregationPolicy, AggregatorConfig, AggregatorStatistics, SignalAggregator, SourceStatistics
+};
+pub use scenarios::{
+    PositionShock, Scenario, ScenarioPosition, ScenarioReport, ScenarioResult, Shock, DEFAULT_DAILY_VOLATILITY
 };
+pub use snapshot::{EngineSnapshot, SNAPSHOT_VERSION};
+pub use events::{EngineEvent, EventLog, EventRecord, EventReplayer};
+pub use attribution::{PnlAttribution, PnlBucket};
+pub use import::{ImportedPosition, PortfolioImport};
+pub use groups::PositionGroup;
+pub use execution_algos::{AlgoProgress, ExecutionAlgo, VolumeCurve};
+pub use participation::{ParticipationConfig, ParticipationTracker};
+pub use queue_position::QueueModel;
+pub use chaos::{ChaosConfig, FaultInjector, FaultKind, FaultStatistics, RandomFaults, ScheduledFault};
+pub use shortfall::{OrderShortfall, ShortfallReport, ShortfallStats};
+pub use leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardMetric};
+pub use instruments::{AssetClass, InstrumentConfig, InstrumentRegistry, MarketTimezone, TradingHours};
+pub use stops::{StopMode, StopPlacement};
+pub use trade_management::{TradeManager, TradeRule};
+pub use rebalancing::{RebalanceConfig, RebalanceOrder, RebalancePlan};
+pub use marks::{MarkPrice, MarkPrices, MarkSource};
+pub use rng::{SeededRng, stream_seed};
+pub use scoring::{CompetitionScore, Competitor, Scoreboard, ScoringConfig};
+pub use throttle::{SignalThrottle, ThrottleConfig, ThrottleReason, ThrottleState, ThrottleStatistics};
+pub use calibration::{CalibrationBucket, ConfidenceCalibration, CALIBRATION_BUCKETS};
+pub use outcomes::{ExecutionError, OutcomePublisher, OutcomeWebhookConfig, SignalExecution, SignalOutcome, SignalResult, TradeOutcome};
+pub use clock::{Clock, SharedClock, SimulatedClock, SystemClock, system_clock};
+pub use rolling::{RollingStatistics, RollingSample, WindowStatistics, ROLLING_WINDOWS};
+pub use returns::{QuantileSketch, ReturnStatistics, RETURNS_WINDOW};
+pub use history::{HistoryConfig, RingBuffer, Spill};
 pub use engine::{
     PaperTradingEngine, PaperTradingConfig, TradingSignal, 
-    SignalAction, SignalMetadata, TradingStatistics
+    SignalAction, SignalMetadata, SignalSubmitter, TradingStatistics, DetailedStatistics
 };
\ No newline at end of file
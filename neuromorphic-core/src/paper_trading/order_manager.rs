@@ -1,15 +1,31 @@
 //! Order management for paper trading
 
+use super::clock::{self, SharedClock};
+use super::events::{EngineEvent, EventLog};
+use super::execution_algos::{AlgoProgress, ExecutionAlgo, VolumeCurve};
+use super::participation::{ParticipationConfig, ParticipationTracker};
+use super::queue_position::QueueModel;
+use super::chaos::FaultInjector;
+use super::history::{HistoryConfig, Spill};
+use super::shortfall::ShortfallReport;
+use super::fees::{FeeSchedule, LiquidityRole};
+use super::instruments::InstrumentRegistry;
+use super::queue::{self, QueueConfig, QueueError, QueueReceiver, QueueSender, QueueStatistics};
+use super::snapshot::IdSequence;
 use crate::exchanges::{Symbol, Exchange, Side};
 use anyhow::Result;
 use dashmap::DashMap;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH, Duration};
-use tokio::sync::mpsc;
+use tracing::debug;
+use utoipa::ToSchema;
 
 /// Order type
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum OrderType {
     Market,
     Limit,
@@ -19,7 +35,7 @@ pub enum OrderType {
 }
 
 /// Order status
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum OrderStatus {
     Pending,
     Submitted,
@@ -31,7 +47,7 @@ pub enum OrderStatus {
 }
 
 /// Time in force
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum TimeInForce {
     GTC,  // Good Till Cancelled
     IOC,  // Immediate or Cancel
@@ -39,8 +55,18 @@ pub enum TimeInForce {
     GTD(u64),  // Good Till Date (timestamp)
 }
 
+/// One fill of an order
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Fill {
+    pub quantity: f64,
+    pub price: f64,
+    pub commission: f64,
+    pub slippage: f64,
+    pub time: u64,
+}
+
 /// Order structure
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct Order {
     pub id: String,
     pub symbol: Symbol,
@@ -62,6 +88,20 @@ pub struct Order {
     pub position_id: Option<String>,
     pub parent_order_id: Option<String>,
     pub child_order_ids: Vec<String>,
+    #[serde(default)]
+    pub liquidity: Option<LiquidityRole>, // Maker once resting on the book, taker if it fills on arrival
+    #[serde(default)]
+    pub group_id: Option<String>, // Basket the order is a leg of, carried to the position it opens
+    #[serde(default)]
+    pub algo: Option<ExecutionAlgo>, // Set on parent orders worked through child orders
+    #[serde(default)]
+    pub last_fill: Option<Fill>, // What the latest fill added, for booking partial fills
+    #[serde(default)]
+    pub strategy: Option<String>, // Strategy of the signal it was submitted for
+    #[serde(default)]
+    pub decision_price: Option<f64>, // Price the signal was decided at, see `shortfall`
+    #[serde(default)]
+    pub arrival_price: Option<f64>, // Market price when it was submitted
 }
 
 impl Order {
@@ -124,9 +164,24 @@ impl Order {
             position_id: None,
             parent_order_id: None,
             child_order_ids: Vec::new(),
+            liquidity: None,
+            group_id: None,
+            algo: None,
+            last_fill: None,
+            strategy: None,
+            decision_price: None,
+            arrival_price: None,
         }
     }
     
+    /// Prices its implementation shortfall is measured from, see `shortfall`
+    pub fn with_benchmarks(mut self, strategy: Option<String>, decision_price: f64, arrival_price: f64) -> Self {
+        self.strategy = strategy;
+        self.decision_price = Some(decision_price);
+        self.arrival_price = Some(arrival_price);
+        self
+    }
+    
     /// Check if order should trigger based on current price
     pub fn should_trigger(&self, current_price: f64) -> bool {
         match self.order_type {
@@ -165,21 +220,15 @@ impl Order {
     }
     
     /// Check if order has expired
-    pub fn is_expired(&self) -> bool {
+    pub fn is_expired(&self, now_ms: u64) -> bool {
         match self.time_in_force {
-            TimeInForce::GTD(expiry) => {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64;
-                now > expiry
-            }
+            TimeInForce::GTD(expiry) => now_ms > expiry,
             _ => false,
         }
     }
     
     /// Fill order partially or completely
-    pub fn fill(&mut self, fill_quantity: f64, fill_price: f64, commission: f64, slippage: f64) {
+    pub fn fill(&mut self, fill_quantity: f64, fill_price: f64, commission: f64, slippage: f64, now_ms: u64) {
         let prev_filled = self.filled_quantity;
         self.filled_quantity = (self.filled_quantity + fill_quantity).min(self.quantity);
         let actual_fill = self.filled_quantity - prev_filled;
@@ -194,10 +243,8 @@ impl Order {
         
         self.commission += commission;
         self.slippage += slippage;
-        self.updated_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        self.updated_time = now_ms;
+        self.last_fill = Some(Fill { quantity: actual_fill, price: fill_price, commission, slippage, time: now_ms });
         
         if self.filled_quantity >= self.quantity {
             self.status = OrderStatus::Filled;
@@ -208,21 +255,60 @@ impl Order {
     }
     
     /// Cancel order
-    pub fn cancel(&mut self) {
+    pub fn cancel(&mut self, now_ms: u64) {
         self.status = OrderStatus::Cancelled;
-        self.updated_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        self.updated_time = now_ms;
     }
     
     /// Reject order
-    pub fn reject(&mut self, _reason: &str) {
+    pub fn reject(&mut self, _reason: &str, now_ms: u64) {
         self.status = OrderStatus::Rejected;
-        self.updated_time = SystemTime::now()
+        self.updated_time = now_ms;
+    }
+}
+
+/// Orders on several symbols that form one logical order, e.g. long A /
+/// short B. Every leg carries the basket's group id. In simulation the legs
+/// fill in the same pass or not at all, and once one leg is cancelled,
+/// rejected or expires the others are cancelled too.
+#[derive(Clone, Debug)]
+pub struct BasketOrder {
+    pub group_id: String,
+    pub legs: Vec<Order>,
+}
+
+impl BasketOrder {
+    pub fn new(legs: Vec<Order>) -> Self {
+        let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
-            .as_millis() as u64;
+            .as_millis();
+        Self {
+            group_id: format!("GRP_{}_{}", now, nanoid::nanoid!(8)),
+            legs,
+        }
+    }
+    
+    /// Use `group_id`, e.g. to add legs to an existing position group
+    pub fn with_group_id(mut self, group_id: impl Into<String>) -> Self {
+        self.group_id = group_id.into();
+        self
+    }
+    
+    /// Legs with a positive quantity, at most one per symbol
+    pub fn validate(&self) -> Result<()> {
+        if self.legs.is_empty() {
+            anyhow::bail!("Basket {} has no legs", self.group_id);
+        }
+        for (i, leg) in self.legs.iter().enumerate() {
+            if !(leg.quantity.is_finite() && leg.quantity > 0.0) {
+                anyhow::bail!("Basket {} leg {}: quantity must be positive", self.group_id, leg.symbol);
+            }
+            if self.legs[..i].iter().any(|other| other.symbol == leg.symbol) {
+                anyhow::bail!("Basket {} has more than one {} leg", self.group_id, leg.symbol);
+            }
+        }
+        Ok(())
     }
 }
 
@@ -233,26 +319,86 @@ pub enum OrderEvent {
     Filled { order_id: String, fill_price: f64, fill_quantity: f64 },
     PartiallyFilled { order_id: String, fill_price: f64, fill_quantity: f64 },
     Cancelled(String),
+    Amended(Order),
     Rejected { order_id: String, reason: String },
     Expired(String),
 }
 
+/// Serializable state of an order manager, see `OrderManager::snapshot`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub orders: Vec<Order>, // Every order, oldest first
+    pub active: Vec<Order>, // Live copies, which carry resting-liquidity flags
+    pub pending: Vec<Order>,
+    pub filled: Vec<Order>,
+    pub submitted: u64,
+    pub traded_volume: Vec<(Exchange, f64)>,
+    pub ids_issued: u64, // From the seeded ID sequence, if any
+    #[serde(default)]
+    pub retired: RetiredOrders,
+}
+
+/// Totals of finished orders no longer held in memory, see `history`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RetiredOrders {
+    pub orders: u64,
+    pub filled: u64,
+    pub fill_time_ms: u64, // Summed over the filled orders with a fill time
+    pub timed_fills: u64,
+    pub maker_fills: u64,
+    pub taker_fills: u64,
+    pub shortfall: ShortfallReport,
+}
+
+impl RetiredOrders {
+    fn add(&mut self, order: &Order, filled: bool) {
+        self.orders += 1;
+        if filled {
+            self.filled += 1;
+            if let Some(filled_time) = order.filled_time {
+                self.fill_time_ms += filled_time.saturating_sub(order.created_time);
+                self.timed_fills += 1;
+            }
+            match order.liquidity {
+                Some(LiquidityRole::Maker) => self.maker_fills += 1,
+                Some(LiquidityRole::Taker) => self.taker_fills += 1,
+                None => {}
+            }
+        }
+        self.shortfall.add(order);
+    }
+}
+
 /// Order manager
 pub struct OrderManager {
     orders: DashMap<String, Order>,
-    pending_orders: DashMap<String, Order>,
+    pending_orders: DashMap<String, Order>, // Parent orders of execution algos being worked
     active_orders: DashMap<String, Order>,
     filled_orders: DashMap<String, Order>,
     orders_by_symbol: DashMap<Symbol, Vec<String>>,
+    baskets: DashMap<String, Vec<String>>, // Leg order IDs of baskets with a leg still active
     order_counter: AtomicU64,
-    event_sender: mpsc::UnboundedSender<OrderEvent>,
-    event_receiver: Option<mpsc::UnboundedReceiver<OrderEvent>>,
-    commission_rate: f64,
+    event_sender: QueueSender<OrderEvent>,
+    event_receiver: Option<QueueReceiver<OrderEvent>>,
+    fee_schedule: FeeSchedule,
+    traded_volume: DashMap<Exchange, f64>, // Cumulative filled notional, for fee tiers
+    volume_curve: VolumeCurve,
+    participation: ParticipationTracker, // Uncapped unless configured
+    queue_model: Option<QueueModel>, // Resting limit orders fill on touch when unset
+    faults: Option<Arc<FaultInjector>>, // Rejects orders on purpose, see `chaos`
     slippage_model: SlippageModel,
+    clock: SharedClock,
+    ids: Option<IdSequence>, // Random IDs when unset
+    events: EventLog,
+    instruments: Arc<InstrumentRegistry>, // Orders are rounded to these and rejected when the market is closed
+    retention: usize, // Finished orders held, see `history`
+    held: Mutex<VecDeque<String>>, // IDs of the orders held, oldest first
+    retired: Mutex<RetiredOrders>,
+    spill: Option<Spill>, // Where retired orders are appended
 }
 
 /// Slippage model for realistic execution
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SlippageModel {
     Fixed(f64),
     Percentage(f64),
@@ -261,7 +407,12 @@ pub enum SlippageModel {
 
 impl OrderManager {
     pub fn new(commission_rate: f64, slippage_model: SlippageModel) -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
+        Self::with_fee_schedule(FeeSchedule::flat(commission_rate), slippage_model)
+    }
+    
+    /// Create an order manager with maker/taker, per-exchange and tiered fees
+    pub fn with_fee_schedule(fee_schedule: FeeSchedule, slippage_model: SlippageModel) -> Self {
+        let (tx, rx) = queue::bounded(QueueConfig::default());
         
         Self {
             orders: DashMap::new(),
@@ -269,18 +420,41 @@ impl OrderManager {
             active_orders: DashMap::new(),
             filled_orders: DashMap::new(),
             orders_by_symbol: DashMap::new(),
+            baskets: DashMap::new(),
             order_counter: AtomicU64::new(0),
             event_sender: tx,
             event_receiver: Some(rx),
-            commission_rate,
+            fee_schedule,
+            traded_volume: DashMap::new(),
+            volume_curve: VolumeCurve::default(),
+            participation: ParticipationTracker::default(),
+            queue_model: None,
+            faults: None,
             slippage_model,
+            clock: clock::system_clock(),
+            ids: None,
+            events: EventLog::default(),
+            instruments: Arc::new(InstrumentRegistry::default()),
+            retention: HistoryConfig::default().orders,
+            held: Mutex::new(VecDeque::new()),
+            retired: Mutex::new(RetiredOrders::default()),
+            spill: None,
         }
     }
     
     /// Submit a new order
     pub fn submit_order(&self, mut order: Order) -> Result<String> {
-        let order_id = order.id.clone();
+        self.instruments.get(&order.symbol).normalize(&mut order, self.clock.now())?;
+        if self.faults.as_ref().is_some_and(|faults| faults.reject_order(&order.symbol, self.clock.now_ms())) {
+            anyhow::bail!("Order for {} rejected by an injected fault", order.symbol);
+        }
         order.status = OrderStatus::Submitted;
+        order.created_time = self.clock.now_ms();
+        if let Some(ids) = &self.ids {
+            order.id = ids.next("ORD", order.created_time);
+        }
+        let order_id = order.id.clone();
+        order.updated_time = order.created_time;
         
         // Store order
         self.orders.insert(order_id.clone(), order.clone());
@@ -293,17 +467,141 @@ impl OrderManager {
             .push(order_id.clone());
         
         // Send event
-        self.event_sender.send(OrderEvent::Submitted(order))?;
+        self.emit(OrderEvent::Submitted(order));
+        
+        self.order_counter.fetch_add(1, Ordering::Relaxed);
+        self.hold(&order_id);
         
+        Ok(order_id)
+    }
+    
+    /// Submit a parent order to be worked by `algo`, see `execution_algos`.
+    /// Its first child is released right away.
+    pub fn submit_algo_order(&self, mut order: Order, mut algo: ExecutionAlgo) -> Result<String> {
+        self.instruments.get(&order.symbol).normalize(&mut order, self.clock.now())?;
+        algo.validate(order.quantity)?;
+        let now = self.clock.now_ms();
+        if let ExecutionAlgo::Vwap { slices, interval_ms, weights } = &mut algo {
+            *weights = self.volume_curve.weights(&order.symbol, now, *slices, *interval_ms);
+        }
+        order.algo = Some(algo);
+        order.status = OrderStatus::Pending;
+        order.created_time = now;
+        order.updated_time = now;
+        if let Some(ids) = &self.ids {
+            order.id = ids.next("ORD", now);
+        }
+        let order_id = order.id.clone();
+        
+        self.orders.insert(order_id.clone(), order.clone());
+        self.pending_orders.insert(order_id.clone(), order.clone());
+        self.orders_by_symbol.entry(order.symbol.clone()).or_default().push(order_id.clone());
+        self.emit(OrderEvent::Submitted(order));
         self.order_counter.fetch_add(1, Ordering::Relaxed);
+        self.hold(&order_id);
         
+        self.release_algo_slices(now)?;
         Ok(order_id)
     }
     
-    /// Cancel an order
+    /// Record traded volume, for VWAP slicing and the participation cap
+    pub fn record_volume(&self, symbol: &Symbol, volume: f64) {
+        let now = self.clock.now_ms();
+        self.volume_curve.record(symbol, volume, now);
+        self.participation.record_volume(symbol, volume, now);
+    }
+    
+    /// Record the displayed book of a symbol, for the queue ahead of resting
+    /// limit orders; levels as (price, size)
+    pub fn record_book(&self, symbol: &Symbol, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        if let Some(queue_model) = &self.queue_model {
+            queue_model.record_book(symbol, bids, asks);
+        }
+    }
+    
+    /// Record volume traded at a price, which works through the queue ahead
+    /// of limit orders resting there
+    pub fn record_trade(&self, symbol: &Symbol, price: f64, quantity: f64) {
+        if let Some(queue_model) = &self.queue_model {
+            queue_model.record_trade(symbol, price, quantity);
+        }
+    }
+    
+    pub fn volume_curve(&self) -> &VolumeCurve {
+        &self.volume_curve
+    }
+    
+    /// Tie an order with fills still to come to the position its first fill
+    /// opened, so later fills add to it. For an algo order that covers its
+    /// children, current and future.
+    pub fn set_order_position(&self, order_id: &str, position_id: &str) {
+        if let Some(mut order) = self.active_orders.get_mut(order_id) {
+            order.position_id.get_or_insert_with(|| position_id.to_string());
+            return;
+        }
+        let parent_id = order_id;
+        let children = match self.pending_orders.get_mut(parent_id) {
+            Some(mut parent) => {
+                parent.position_id = Some(position_id.to_string());
+                parent.child_order_ids.clone()
+            }
+            None => return,
+        };
+        if let Some(mut parent) = self.orders.get_mut(parent_id) {
+            parent.position_id = Some(position_id.to_string());
+        }
+        for child_id in children {
+            if let Some(mut child) = self.active_orders.get_mut(&child_id) {
+                child.position_id.get_or_insert_with(|| position_id.to_string());
+            }
+        }
+    }
+    
+    /// Record the position a filled order opened or changed
+    pub fn set_filled_position(&self, order_id: &str, position_id: &str) {
+        for orders in [&self.orders, &self.filled_orders] {
+            if let Some(mut order) = orders.get_mut(order_id) {
+                order.position_id.get_or_insert_with(|| position_id.to_string());
+            }
+        }
+    }
+    
+    /// Submit every leg of a basket, or none if it is invalid
+    pub fn submit_basket(&self, basket: BasketOrder) -> Result<Vec<String>> {
+        basket.validate()?;
+        let mut leg_ids = Vec::with_capacity(basket.legs.len());
+        for mut leg in basket.legs {
+            leg.group_id = Some(basket.group_id.clone());
+            leg_ids.push(self.submit_order(leg)?);
+        }
+        self.baskets.insert(basket.group_id, leg_ids.clone());
+        Ok(leg_ids)
+    }
+    
+    /// Cancel the legs of a basket still active
+    pub fn cancel_basket(&self, group_id: &str) -> Result<()> {
+        if let Some((_, leg_ids)) = self.baskets.remove(group_id) {
+            for leg_id in leg_ids {
+                self.cancel_order(&leg_id)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Cancel an order; cancelling an algo order cancels its active children
     pub fn cancel_order(&self, order_id: &str) -> Result<()> {
+        if let Some((_, mut parent)) = self.pending_orders.remove(order_id) {
+            for child_id in &parent.child_order_ids {
+                self.cancel_order(child_id)?;
+            }
+            self.sync_parent(&mut parent);
+            parent.cancel(self.clock.now_ms());
+            self.orders.insert(order_id.to_string(), parent);
+            self.emit(OrderEvent::Cancelled(order_id.to_string()));
+            return Ok(());
+        }
         if let Some(mut order) = self.active_orders.get_mut(order_id) {
-            order.cancel();
+            order.cancel(self.clock.now_ms());
             
             // Move to appropriate collection
             let cancelled_order = order.clone();
@@ -313,74 +611,309 @@ impl OrderManager {
             self.orders.insert(order_id.to_string(), cancelled_order);
             
             // Send event
-            self.event_sender.send(OrderEvent::Cancelled(order_id.to_string()))?;
+            self.emit(OrderEvent::Cancelled(order_id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Change the quantity and/or price of an active order; for stop-loss
+    /// orders the price is the trigger. The amended order loses its place on
+    /// the book: it is matched again as if newly submitted.
+    pub fn amend_order(&self, order_id: &str, quantity: Option<f64>, price: Option<f64>) -> Result<()> {
+        let mut order = self.active_orders
+            .get_mut(order_id)
+            .ok_or_else(|| anyhow::anyhow!("Order {} is not active", order_id))?;
+        
+        if let Some(quantity) = quantity {
+            if !quantity.is_finite() || quantity <= order.filled_quantity {
+                anyhow::bail!("Invalid quantity {} for order {} with {} filled", quantity, order_id, order.filled_quantity);
+            }
+        }
+        if let Some(price) = price {
+            if !price.is_finite() || price <= 0.0 {
+                anyhow::bail!("Invalid price {} for order {}", price, order_id);
+            }
+            let price = self.instruments.get(&order.symbol).round_price(price);
+            let level = match order.order_type {
+                OrderType::StopLoss => &mut order.stop_price,
+                _ => &mut order.price,
+            };
+            if level.is_none() {
+                anyhow::bail!("Order {} has no price to amend", order_id);
+            }
+            *level = Some(price);
         }
+        order.quantity = quantity.unwrap_or(order.quantity);
+        order.liquidity = None;
+        order.updated_time = self.clock.now_ms();
+        let amended = order.clone();
+        drop(order);
         
+        self.orders.insert(order_id.to_string(), amended.clone());
+        self.emit(OrderEvent::Amended(amended));
         Ok(())
     }
     
     /// Process orders based on current market prices
     pub fn process_orders(&self, prices: &DashMap<Symbol, f64>) -> Result<Vec<String>> {
         let mut filled_orders = Vec::new();
+        let now = self.clock.now_ms();
+        self.release_algo_slices(now)?;
+        let held = self.coordinate_baskets(prices)?;
         
-        for entry in self.active_orders.iter() {
-            let mut order = entry.value().clone();
+        // Work on a snapshot so filled orders can be removed from the active set,
+        // oldest first so fills come out in the same order every run
+        let mut active: Vec<Order> = self.active_orders
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        active.sort_by(|a, b| a.created_time.cmp(&b.created_time).then_with(|| a.id.cmp(&b.id)));
+        
+        for mut order in active {
+            let price = match prices.get(&order.symbol) {
+                Some(price) => *price,
+                None => continue,
+            };
+            
+            // Limit orders that rested on the book fill at their price as makers;
+            // everything else crosses the spread and pays slippage
+            let role = match (&order.order_type, order.liquidity, order.price) {
+                (OrderType::Limit, Some(LiquidityRole::Maker), Some(_)) => LiquidityRole::Maker,
+                _ => LiquidityRole::Taker,
+            };
             
-            if let Some(price) = prices.get(&order.symbol) {
-                // Check if order should trigger
-                if order.should_trigger(*price) {
-                    // Calculate execution details
-                    let (exec_price, slippage) = self.calculate_execution_price(
-                        *price,
-                        &order.side,
-                        order.quantity
-                    );
-                    
-                    let commission = self.calculate_commission(order.quantity, exec_price);
-                    
-                    // Fill the order
-                    order.fill(order.quantity, exec_price, commission, slippage);
-                    
-                    // Update collections
+            // Fills are held to the symbol's participation cap, if any, and
+            // makers to the volume traded past the queue ahead of them
+            let mut fill_quantity = match self.participation.available(&order.symbol, now) {
+                Some(available) => available.min(order.quantity - order.filled_quantity),
+                None => order.quantity - order.filled_quantity,
+            };
+            let queued = self.queue_model.as_ref().filter(|_| role == LiquidityRole::Maker);
+            if let Some(fillable) = queued.and_then(|queue_model| queue_model.fillable(&order, price)) {
+                fill_quantity = fill_quantity.min(fillable);
+            }
+            
+            // Check if order should trigger
+            if order.should_trigger(price) && !held.contains(&order.id) && fill_quantity > 0.0 {
+                let (exec_price, slippage) = match (role, order.price) {
+                    (LiquidityRole::Maker, Some(limit)) => (limit, 0.0),
+                    _ => self.calculate_execution_price(price, &order.side, fill_quantity),
+                };
+                
+                let commission = self.calculate_commission(order.exchange, role, fill_quantity, exec_price);
+                
+                // Fill the order
+                order.fill(fill_quantity, exec_price, commission, slippage, now);
+                order.liquidity = Some(role);
+                *self.traded_volume.entry(order.exchange).or_insert(0.0) += fill_quantity * exec_price;
+                self.participation.record_fill(&order.symbol, fill_quantity, now);
+                if let Some(queue_model) = queued {
+                    queue_model.record_fill(&order, fill_quantity);
+                }
+                
+                // Update collections; a partly filled order stays on the book
+                if order.status == OrderStatus::Filled {
                     self.active_orders.remove(&order.id);
                     self.filled_orders.insert(order.id.clone(), order.clone());
-                    self.orders.insert(order.id.clone(), order.clone());
-                    
-                    // Send event
-                    let event = if order.status == OrderStatus::Filled {
-                        OrderEvent::Filled {
-                            order_id: order.id.clone(),
-                            fill_price: exec_price,
-                            fill_quantity: order.quantity,
-                        }
-                    } else {
-                        OrderEvent::PartiallyFilled {
-                            order_id: order.id.clone(),
-                            fill_price: exec_price,
-                            fill_quantity: order.filled_quantity,
-                        }
-                    };
-                    
-                    self.event_sender.send(event)?;
-                    filled_orders.push(order.id.clone());
+                } else {
+                    self.active_orders.insert(order.id.clone(), order.clone());
                 }
+                self.orders.insert(order.id.clone(), order.clone());
                 
-                // Check expiration
-                if order.is_expired() {
-                    order.status = OrderStatus::Expired;
-                    
-                    let order_id = order.id.clone();
-                    self.active_orders.remove(&order.id);
-                    self.orders.insert(order.id.clone(), order);
-                    
-                    self.event_sender.send(OrderEvent::Expired(order_id))?;
+                // Send event
+                let event = if order.status == OrderStatus::Filled {
+                    OrderEvent::Filled {
+                        order_id: order.id.clone(),
+                        fill_price: exec_price,
+                        fill_quantity: order.quantity,
+                    }
+                } else {
+                    OrderEvent::PartiallyFilled {
+                        order_id: order.id.clone(),
+                        fill_price: exec_price,
+                        fill_quantity: order.filled_quantity,
+                    }
+                };
+                
+                self.emit(event);
+                filled_orders.push(order.id.clone());
+            } else if order.is_expired(now) {
+                order.status = OrderStatus::Expired;
+                
+                let order_id = order.id.clone();
+                self.active_orders.remove(&order.id);
+                self.orders.insert(order.id.clone(), order);
+                
+                self.emit(OrderEvent::Expired(order_id));
+            } else if order.order_type == OrderType::Limit && order.liquidity.is_none() {
+                // Not marketable on arrival: the order now rests on the book,
+                // at the back of the queue at its price
+                if let Some(mut resting) = self.active_orders.get_mut(&order.id) {
+                    resting.liquidity = Some(LiquidityRole::Maker);
+                }
+                if let Some(queue_model) = &self.queue_model {
+                    queue_model.join(&order);
                 }
             }
         }
+        if let Some(queue_model) = &self.queue_model {
+            queue_model.retain(|order_id| self.active_orders.get(order_id).is_some_and(|order| order.liquidity.is_some()));
+        }
         
+        self.settle_algo_parents()?;
         Ok(filled_orders)
     }
     
+    /// Submit the child orders of algo parents that are due
+    fn release_algo_slices(&self, now: u64) -> Result<()> {
+        let mut parents: Vec<Order> = self.pending_orders.iter().map(|e| e.value().clone()).collect();
+        parents.sort_by(|a, b| a.created_time.cmp(&b.created_time).then_with(|| a.id.cmp(&b.id)));
+        
+        for parent in parents {
+            let Some(algo) = &parent.algo else { continue };
+            let children: Vec<Order> = parent.child_order_ids.iter().filter_map(|id| self.get_order(id)).collect();
+            let Some(quantity) = algo.next_slice(&parent, &children, now) else { continue };
+            
+            let mut child = match parent.price {
+                Some(price) => Order::limit(parent.symbol.clone(), parent.exchange, parent.side, quantity, price),
+                None => Order::market(parent.symbol.clone(), parent.exchange, parent.side, quantity),
+            };
+            child.parent_order_id = Some(parent.id.clone());
+            child.position_id = parent.position_id.clone();
+            child.group_id = parent.group_id.clone();
+            child.time_in_force = parent.time_in_force.clone();
+            let child_id = self.submit_order(child)?;
+            
+            for map in [&self.pending_orders, &self.orders] {
+                if let Some(mut parent) = map.get_mut(&parent.id) {
+                    parent.child_order_ids.push(child_id.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Bring algo parents up to date with their children's fills, and finish
+    /// those that are filled or have a child that ended unfilled
+    fn settle_algo_parents(&self) -> Result<()> {
+        let parents: Vec<Order> = self.pending_orders.iter().map(|e| e.value().clone()).collect();
+        for mut parent in parents {
+            self.sync_parent(&mut parent);
+            let children: Vec<Order> = parent.child_order_ids.iter().filter_map(|id| self.get_order(id)).collect();
+            
+            if parent.filled_quantity >= parent.quantity * (1.0 - 1e-9) {
+                parent.status = OrderStatus::Filled;
+                parent.filled_time = Some(parent.updated_time);
+                self.pending_orders.remove(&parent.id);
+                self.filled_orders.insert(parent.id.clone(), parent.clone());
+                self.orders.insert(parent.id.clone(), parent.clone());
+                self.emit(OrderEvent::Filled {
+                    order_id: parent.id.clone(),
+                    fill_price: parent.avg_fill_price,
+                    fill_quantity: parent.filled_quantity,
+                });
+            } else if children.iter().any(|c| matches!(c.status, OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired)) {
+                self.cancel_order(&parent.id)?;
+            } else {
+                self.pending_orders.insert(parent.id.clone(), parent.clone());
+                self.orders.insert(parent.id.clone(), parent);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Fills, costs and status of a parent from its children
+    fn sync_parent(&self, parent: &mut Order) {
+        let children: Vec<Order> = parent.child_order_ids.iter().filter_map(|id| self.get_order(id)).collect();
+        let filled: f64 = children.iter().map(|c| c.filled_quantity).sum();
+        if filled > parent.filled_quantity {
+            parent.avg_fill_price = children.iter().map(|c| c.avg_fill_price * c.filled_quantity).sum::<f64>() / filled;
+            parent.filled_quantity = filled;
+            parent.commission = children.iter().map(|c| c.commission).sum();
+            parent.slippage = children.iter().map(|c| c.slippage).sum();
+            parent.updated_time = children.iter().map(|c| c.updated_time).max().unwrap_or(parent.updated_time);
+            parent.status = OrderStatus::PartiallyFilled;
+        }
+    }
+    
+    /// Cancel what is left of baskets with a leg that won't fill, and return
+    /// the legs of baskets that can't all fill at `prices`, to be held back
+    fn coordinate_baskets(&self, prices: &DashMap<Symbol, f64>) -> Result<HashSet<String>> {
+        let baskets: Vec<(String, Vec<Order>)> = self.baskets
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().iter().filter_map(|id| self.get_order(id)).collect()))
+            .collect();
+        
+        let mut held = HashSet::new();
+        for (group_id, legs) in baskets {
+            let is_active = |leg: &Order| self.active_orders.contains_key(&leg.id);
+            let dead = legs.iter().any(|leg| matches!(leg.status, OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired));
+            if dead || !legs.iter().any(is_active) {
+                self.cancel_basket(&group_id)?;
+                continue;
+            }
+            let ready = legs.iter().filter(|leg| is_active(leg)).all(|leg| {
+                prices.get(&leg.symbol).is_some_and(|price| leg.should_trigger(*price))
+            });
+            if !ready {
+                held.extend(legs.iter().filter(|leg| is_active(leg)).map(|leg| leg.id.clone()));
+            }
+        }
+        Ok(held)
+    }
+    
+    /// Record a fill reported by an external venue. Without a venue commission
+    /// the fee schedule is applied: taker for market orders, maker for limits.
+    pub fn apply_external_fill(&self, order_id: &str, quantity: f64, price: f64, commission: Option<f64>) -> Result<()> {
+        let (_, mut order) = self.active_orders
+            .remove(order_id)
+            .ok_or_else(|| anyhow::anyhow!("Order {} is not active", order_id))?;
+        
+        let role = match order.order_type {
+            OrderType::Limit | OrderType::StopLimit => LiquidityRole::Maker,
+            _ => LiquidityRole::Taker,
+        };
+        let commission = commission.unwrap_or_else(|| self.calculate_commission(order.exchange, role, quantity, price));
+        
+        // The venue reports the whole fill; whatever it didn't fill won't fill later
+        order.fill(quantity, price, commission, 0.0, self.clock.now_ms());
+        order.status = OrderStatus::Filled;
+        order.filled_time.get_or_insert(order.updated_time);
+        order.liquidity = Some(role);
+        *self.traded_volume.entry(order.exchange).or_insert(0.0) += quantity * price;
+        
+        self.filled_orders.insert(order.id.clone(), order.clone());
+        self.orders.insert(order.id.clone(), order);
+        
+        self.emit(OrderEvent::Filled {
+            order_id: order_id.to_string(),
+            fill_price: price,
+            fill_quantity: quantity,
+        });
+        Ok(())
+    }
+    
+    /// Reject an active order, e.g. when the venue refuses it
+    pub fn reject_order(&self, order_id: &str, reason: &str) -> Result<()> {
+        if let Some((_, mut order)) = self.active_orders.remove(order_id) {
+            order.reject(reason, self.clock.now_ms());
+            self.orders.insert(order_id.to_string(), order);
+            
+            self.emit(OrderEvent::Rejected {
+                order_id: order_id.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+        
+        Ok(())
+    }
+    
+    /// Price a market order of `quantity` would fill at, before fees
+    pub fn estimate_fill_price(&self, market_price: f64, side: &Side, quantity: f64) -> f64 {
+        self.calculate_execution_price(market_price, side, quantity).0
+    }
+    
     /// Calculate execution price with slippage
     fn calculate_execution_price(&self, market_price: f64, side: &Side, quantity: f64) -> (f64, f64) {
         let slippage = match &self.slippage_model {
@@ -399,9 +932,20 @@ impl OrderManager {
         (exec_price, slippage)
     }
     
-    /// Calculate commission
-    fn calculate_commission(&self, quantity: f64, price: f64) -> f64 {
-        quantity * price * self.commission_rate / 100.0
+    /// Calculate commission from the fee schedule and volume traded so far
+    fn calculate_commission(&self, exchange: Exchange, role: LiquidityRole, quantity: f64, price: f64) -> f64 {
+        let traded_volume = self.traded_volume.get(&exchange).map(|v| *v).unwrap_or(0.0);
+        self.fee_schedule.commission(exchange, role, quantity * price, traded_volume)
+    }
+    
+    /// Cumulative filled notional across exchanges
+    pub fn traded_volume(&self) -> f64 {
+        self.traded_volume.iter().map(|v| *v.value()).sum()
+    }
+    
+    /// Get the fee schedule
+    pub fn fee_schedule(&self) -> &FeeSchedule {
+        &self.fee_schedule
     }
     
     /// Create bracket order (entry + stop loss + take profit)
@@ -467,6 +1011,14 @@ impl OrderManager {
         self.orders.get(order_id).map(|o| o.clone())
     }
     
+    /// Get every order in its latest state
+    pub fn get_all_orders(&self) -> Vec<Order> {
+        self.orders
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+    
     /// Get active orders
     pub fn get_active_orders(&self) -> Vec<Order> {
         self.active_orders
@@ -487,19 +1039,216 @@ impl OrderManager {
             .unwrap_or_default()
     }
     
+    /// Replace the order event queue, e.g. with a different capacity or policy
+    pub fn with_event_queue(mut self, config: QueueConfig) -> Self {
+        let (tx, rx) = queue::bounded(config);
+        self.event_sender = tx;
+        self.event_receiver = Some(rx);
+        self
+    }
+    
+    /// Hold simulated fills to a share of the traded volume, see `participation`
+    pub fn with_participation(mut self, config: ParticipationConfig) -> Self {
+        self.participation = ParticipationTracker::new(config);
+        self
+    }
+    
+    /// Fill resting limit orders only once the queue ahead of them has
+    /// traded, see `queue_position`
+    pub fn with_queue_model(mut self) -> Self {
+        self.queue_model = Some(QueueModel::new());
+        self
+    }
+    
+    pub fn queue_model(&self) -> Option<&QueueModel> {
+        self.queue_model.as_ref()
+    }
+    
+    /// Reject orders when `faults` says so, see `chaos`
+    pub fn with_fault_injector(mut self, faults: Arc<FaultInjector>) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+    
+    /// Hold the latest `retention` finished orders, appending older ones to
+    /// `spill`, see `history`
+    pub fn with_history(mut self, retention: usize, spill: Option<Spill>) -> Self {
+        self.retention = retention;
+        self.spill = spill;
+        self
+    }
+    
+    /// Hold a new order, retiring the oldest finished orders beyond the retention
+    fn hold(&self, order_id: &str) {
+        let mut held = self.held.lock();
+        held.push_back(order_id.to_string());
+        
+        // Live orders don't count towards the retention and are never retired
+        let live = self.active_orders.len() + self.pending_orders.len();
+        if held.len() <= self.retention + live {
+            return;
+        }
+        let children: HashSet<String> = self.pending_orders.iter().flat_map(|e| e.value().child_order_ids.clone()).collect();
+        let mut i = 0;
+        while held.len() > self.retention + live && i < held.len() {
+            if self.is_live(&held[i]) || children.contains(&held[i]) {
+                i += 1;
+            } else if let Some(order_id) = held.remove(i) {
+                self.retire(&order_id);
+            }
+        }
+    }
+    
+    /// Whether an order is still being worked, or is a leg of a basket that is
+    fn is_live(&self, order_id: &str) -> bool {
+        self.active_orders.contains_key(order_id)
+            || self.pending_orders.contains_key(order_id)
+            || self.orders.get(order_id).and_then(|order| order.group_id.clone()).is_some_and(|group_id| self.baskets.contains_key(&group_id))
+    }
+    
+    /// Forget a finished order, keeping it in the totals
+    fn retire(&self, order_id: &str) {
+        let Some((_, order)) = self.orders.remove(order_id) else { return };
+        let filled = self.filled_orders.remove(order_id).is_some();
+        self.orders_by_symbol.remove_if_mut(&order.symbol, |_, ids| {
+            ids.retain(|id| id != order_id);
+            ids.is_empty()
+        });
+        self.retired.lock().add(&order, filled);
+        if let Some(spill) = &self.spill {
+            spill.write(&order);
+        }
+    }
+    
+    /// Take order, fill and expiry times from `clock` instead of the wall clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Generate order IDs from `seed` so runs can be reproduced
+    pub fn with_id_seed(mut self, seed: u64) -> Self {
+        self.ids = Some(IdSequence::new(seed));
+        self
+    }
+    
+    /// Append every order event, with the order as it stands after it, to `events`
+    pub fn with_event_log(mut self, events: EventLog) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Round orders to these instruments and keep to their trading hours
+    pub fn with_instruments(mut self, instruments: Arc<InstrumentRegistry>) -> Self {
+        self.instruments = instruments;
+        self
+    }
+    
+    pub fn instruments(&self) -> &Arc<InstrumentRegistry> {
+        &self.instruments
+    }
+    
+    /// Copy of every order and running total
+    pub fn snapshot(&self) -> OrderBook {
+        let sorted = |orders: &DashMap<String, Order>| {
+            let mut orders: Vec<Order> = orders.iter().map(|e| e.value().clone()).collect();
+            orders.sort_by(|a, b| a.created_time.cmp(&b.created_time).then_with(|| a.id.cmp(&b.id)));
+            orders
+        };
+        let mut traded_volume: Vec<(Exchange, f64)> = self.traded_volume.iter().map(|e| (*e.key(), *e.value())).collect();
+        traded_volume.sort_by_key(|(exchange, _)| format!("{:?}", exchange));
+        
+        OrderBook {
+            orders: sorted(&self.orders),
+            active: sorted(&self.active_orders),
+            pending: sorted(&self.pending_orders),
+            filled: sorted(&self.filled_orders),
+            submitted: self.order_counter.load(Ordering::Relaxed),
+            traded_volume,
+            ids_issued: self.ids.as_ref().map_or(0, |ids| ids.issued()),
+            retired: self.retired.lock().clone(),
+        }
+    }
+    
+    /// Replace every order and total with those of a snapshot. No events are
+    /// emitted for the restored orders.
+    pub fn restore(&self, book: &OrderBook) {
+        for map in [&self.orders, &self.active_orders, &self.pending_orders, &self.filled_orders] {
+            map.clear();
+        }
+        self.orders_by_symbol.clear();
+        self.baskets.clear();
+        self.traded_volume.clear();
+        *self.held.lock() = book.orders.iter().map(|order| order.id.clone()).collect();
+        *self.retired.lock() = book.retired.clone();
+        
+        for order in &book.orders {
+            self.orders_by_symbol.entry(order.symbol.clone()).or_default().push(order.id.clone());
+            if let Some(group_id) = &order.group_id {
+                self.baskets.entry(group_id.clone()).or_default().push(order.id.clone());
+            }
+            self.orders.insert(order.id.clone(), order.clone());
+        }
+        for (map, orders) in [(&self.active_orders, &book.active), (&self.pending_orders, &book.pending), (&self.filled_orders, &book.filled)] {
+            for order in orders {
+                map.insert(order.id.clone(), order.clone());
+            }
+        }
+        for (exchange, volume) in &book.traded_volume {
+            self.traded_volume.insert(*exchange, *volume);
+        }
+        self.order_counter.store(book.submitted, Ordering::Relaxed);
+        if let Some(ids) = &self.ids {
+            ids.set_issued(book.ids_issued);
+        }
+    }
+    
     /// Subscribe to order events
-    pub fn subscribe(&mut self) -> Option<mpsc::UnboundedReceiver<OrderEvent>> {
+    pub fn subscribe(&mut self) -> Option<QueueReceiver<OrderEvent>> {
         self.event_receiver.take()
     }
     
+    /// Depth and overflow counters of the order event queue
+    pub fn event_queue_statistics(&self) -> QueueStatistics {
+        self.event_sender.statistics()
+    }
+    
+    /// Queue an order event. Events are notifications, so a full queue never
+    /// fails the order operation that raised them.
+    fn emit(&self, event: OrderEvent) {
+        if self.events.is_enabled() {
+            let order = |id: &str| self.orders.get(id).map(|o| o.clone());
+            let logged = match &event {
+                OrderEvent::Submitted(order) => Some(EngineEvent::OrderSubmitted { order: order.clone() }),
+                OrderEvent::Amended(order) => Some(EngineEvent::OrderAmended { order: order.clone() }),
+                OrderEvent::Filled { order_id, .. } | OrderEvent::PartiallyFilled { order_id, .. } => {
+                    order(order_id).map(|order| EngineEvent::OrderFilled { order })
+                }
+                OrderEvent::Cancelled(order_id) => order(order_id).map(|order| EngineEvent::OrderCancelled { order }),
+                OrderEvent::Expired(order_id) => order(order_id).map(|order| EngineEvent::OrderExpired { order }),
+                OrderEvent::Rejected { order_id, reason } => {
+                    order(order_id).map(|order| EngineEvent::OrderRejected { order, reason: reason.clone() })
+                }
+            };
+            if let Some(logged) = logged {
+                self.events.record(|| logged);
+            }
+        }
+        match self.event_sender.try_send(event) {
+            Ok(()) | Err(QueueError::Closed) => {}
+            Err(e) => debug!(error = %e, "Order event dropped"),
+        }
+    }
+    
     /// Get order statistics
     pub fn get_statistics(&self) -> OrderStatistics {
+        let retired = self.retired.lock().clone();
         let mut stats = OrderStatistics::default();
         
         stats.total_orders = self.order_counter.load(Ordering::Relaxed);
         stats.pending_orders = self.pending_orders.len() as u64;
         stats.active_orders = self.active_orders.len() as u64;
-        stats.filled_orders = self.filled_orders.len() as u64;
+        stats.filled_orders = self.filled_orders.len() as u64 + retired.filled;
         
         // Calculate fill rate
         if stats.total_orders > 0 {
@@ -511,12 +1260,29 @@ impl OrderManager {
             .iter()
             .filter_map(|e| {
                 let order = e.value();
-                order.filled_time.map(|ft| ft - order.created_time)
+                order.filled_time.map(|ft| ft.saturating_sub(order.created_time))
             })
             .collect();
         
-        if !fill_times.is_empty() {
-            stats.avg_fill_time_ms = fill_times.iter().sum::<u64>() as f64 / fill_times.len() as f64;
+        let timed_fills = fill_times.len() as u64 + retired.timed_fills;
+        if timed_fills > 0 {
+            stats.avg_fill_time_ms = (fill_times.iter().sum::<u64>() + retired.fill_time_ms) as f64 / timed_fills as f64;
+        }
+        
+        stats.shortfall = retired.shortfall;
+        for entry in self.orders.iter() {
+            stats.shortfall.add(entry.value());
+        }
+        stats.algo_orders = self.pending_orders.iter().filter_map(|e| AlgoProgress::of(e.value())).collect();
+        stats.algo_orders.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+        
+        (stats.maker_fills, stats.taker_fills) = (retired.maker_fills, retired.taker_fills);
+        for entry in self.filled_orders.iter() {
+            match entry.value().liquidity {
+                Some(LiquidityRole::Maker) => stats.maker_fills += 1,
+                Some(LiquidityRole::Taker) => stats.taker_fills += 1,
+                None => {}
+            }
         }
         
         stats
@@ -534,11 +1300,18 @@ pub struct OrderStatistics {
     pub rejected_orders: u64,
     pub fill_rate: f64,
     pub avg_fill_time_ms: f64,
+    pub maker_fills: u64,
+    pub taker_fills: u64,
+    pub algo_orders: Vec<AlgoProgress>, // Parent orders still being worked
+    pub shortfall: ShortfallReport, // Implementation shortfall per strategy and symbol
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::paper_trading::clock::SimulatedClock;
+    use crate::paper_trading::fees::FeeRates;
+    use std::sync::Arc;
     
     #[test]
     fn test_order_lifecycle() {
@@ -565,6 +1338,40 @@ mod tests {
         // Check order is filled
         let order = manager.get_order(&order_id).unwrap();
         assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.liquidity, Some(LiquidityRole::Taker));
+    }
+    
+    #[test]
+    fn test_resting_limit_fills_as_maker() {
+        let manager = OrderManager::with_fee_schedule(
+            FeeSchedule::new(FeeRates {
+                maker_pct: 0.0,
+                taker_pct: 0.1,
+                min_fee: 0.0,
+                tiers: Vec::new(),
+            }),
+            SlippageModel::Fixed(1.0),
+        );
+        let symbol = Symbol::new("BTC-USD");
+        
+        let order_id = manager.submit_order(
+            Order::limit(symbol.clone(), Exchange::Binance, Side::Buy, 1.0, 49000.0)
+        ).unwrap();
+        
+        // Above the limit the order rests on the book
+        let prices = DashMap::new();
+        prices.insert(symbol.clone(), 50000.0);
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+        
+        // Price trades down through the limit: maker fill at the limit price, no fee
+        prices.insert(symbol, 48900.0);
+        assert_eq!(manager.process_orders(&prices).unwrap(), vec![order_id.clone()]);
+        
+        let order = manager.get_order(&order_id).unwrap();
+        assert_eq!(order.liquidity, Some(LiquidityRole::Maker));
+        assert_eq!(order.avg_fill_price, 49000.0);
+        assert_eq!(order.commission, 0.0);
+        assert_eq!(manager.get_statistics().maker_fills, 1);
     }
     
     #[test]
@@ -585,4 +1392,92 @@ mod tests {
         assert!(manager.get_order(&stop_id).is_some());
         assert!(manager.get_order(&tp_id).is_some());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_expiry_follows_clock() {
+        let clock = Arc::new(SimulatedClock::new(1_000_000));
+        let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0)).with_clock(clock.clone());
+        let symbol = Symbol::new("BTC-USD");
+        
+        let mut order = Order::limit(symbol.clone(), Exchange::Binance, Side::Buy, 1.0, 49000.0);
+        order.time_in_force = TimeInForce::GTD(1_060_000);
+        let order_id = manager.submit_order(order).unwrap();
+        assert_eq!(manager.get_order(&order_id).unwrap().created_time, 1_000_000);
+        
+        let prices = DashMap::new();
+        prices.insert(symbol, 50000.0);
+        manager.process_orders(&prices).unwrap();
+        assert_eq!(manager.get_order(&order_id).unwrap().status, OrderStatus::Submitted);
+        
+        clock.advance(Duration::from_secs(61));
+        manager.process_orders(&prices).unwrap();
+        assert_eq!(manager.get_order(&order_id).unwrap().status, OrderStatus::Expired);
+    }
+    
+    #[test]
+    fn test_basket_legs_fill_together() {
+        let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0));
+        let (btc, eth) = (Symbol::new("BTC-USD"), Symbol::new("ETH-USD"));
+        let basket = BasketOrder::new(vec![
+            Order::limit(btc.clone(), Exchange::Binance, Side::Buy, 1.0, 49000.0),
+            Order::market(eth.clone(), Exchange::Binance, Side::Sell, 10.0),
+        ]);
+        let group_id = basket.group_id.clone();
+        let ids = manager.submit_basket(basket).unwrap();
+        assert_eq!(manager.get_order(&ids[1]).unwrap().group_id.as_deref(), Some(group_id.as_str()));
+        
+        // The market leg waits for the limit leg
+        let prices = DashMap::new();
+        prices.insert(btc.clone(), 50000.0);
+        prices.insert(eth.clone(), 3000.0);
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+        prices.insert(btc.clone(), 48900.0);
+        assert_eq!(manager.process_orders(&prices).unwrap().len(), 2);
+        
+        // Cancelling one leg cancels the rest
+        let ids = manager.submit_basket(BasketOrder::new(vec![
+            Order::limit(btc.clone(), Exchange::Binance, Side::Buy, 1.0, 40000.0),
+            Order::limit(eth, Exchange::Binance, Side::Buy, 1.0, 2000.0),
+        ])).unwrap();
+        manager.cancel_order(&ids[0]).unwrap();
+        manager.process_orders(&prices).unwrap();
+        assert_eq!(manager.get_order(&ids[1]).unwrap().status, OrderStatus::Cancelled);
+        
+        let duplicate = BasketOrder::new(vec![Order::market(btc.clone(), Exchange::Binance, Side::Buy, 1.0); 2]);
+        assert!(manager.submit_basket(duplicate).is_err());
+    }
+    
+    #[test]
+    fn test_finished_orders_beyond_the_retention_are_retired() {
+        let dir = std::env::temp_dir().join(format!("order-history-{}", std::process::id()));
+        let spill = Spill::open(&dir, "orders").unwrap();
+        let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0)).with_history(3, Some(spill.clone()));
+        let btc = Symbol::new("BTC-USD");
+        let prices = DashMap::new();
+        prices.insert(btc.clone(), 50000.0);
+        
+        // A resting order stays however old it gets
+        let resting = manager.submit_order(Order::limit(btc.clone(), Exchange::Binance, Side::Buy, 1.0, 40000.0)).unwrap();
+        let filled: Vec<String> = (0..6)
+            .map(|_| {
+                let order_id = manager.submit_order(Order::market(btc.clone(), Exchange::Binance, Side::Buy, 1.0)).unwrap();
+                manager.process_orders(&prices).unwrap();
+                order_id
+            })
+            .collect();
+        
+        // Held: the resting order, the three latest fills and the one submitted last
+        assert!(manager.get_order(&resting).is_some());
+        assert!(filled[..2].iter().all(|id| manager.get_order(id).is_none()));
+        assert!(filled[2..].iter().all(|id| manager.get_order(id).is_some()));
+        assert_eq!(manager.get_orders_by_symbol(&btc).len(), 5);
+        let spilled: Vec<Order> = std::fs::read_to_string(spill.path()).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(spilled.iter().map(|o| o.id.clone()).collect::<Vec<_>>(), filled[..2]);
+        
+        // Statistics still count every order
+        let stats = manager.get_statistics();
+        assert_eq!((stats.total_orders, stats.filled_orders, stats.taker_fills), (7, 6, 6));
+        assert_eq!(manager.snapshot().retired.orders, 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
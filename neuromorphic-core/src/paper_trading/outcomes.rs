@@ -0,0 +1,340 @@
+//! Trade outcomes fed back to the signal source
+//!
+//! Every position that closes, however it closes, produces one
+//! [`TradeOutcome`], tagged with the `signal_id` of the signal that opened it.
+//! Prediction engines learn from these online: subscribe to the broadcast
+//! channel in-process, or have them POSTed as JSON to a callback URL.
+//!
+//! Signals are queued, so submitting one only says it was accepted. Once the
+//! engine has processed it, a [`SignalResult`] on a second channel says what
+//! became of it: orders submitted, rejected by a risk check or for lack of a
+//! price, throttled, or nothing to do.
+//!
+//! Scripts that would rather wait for the fill get a [`SignalExecution`], or
+//! an [`ExecutionError`] saying why there was none, from
+//! `NeuromorphicPaperTrader::execute_signal_sync`.
+
+use super::engine::TradingSignal;
+use super::order_manager::{Order, OrderStatus};
+use super::position_manager::{ExitReason, Position};
+use super::throttle::ThrottleReason;
+use crate::exchanges::{Exchange, Side, Symbol};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Result of a closed position
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradeOutcome {
+    /// ID the signal source gave the opening signal, if any
+    pub signal_id: Option<String>,
+    pub position_id: String,
+    pub symbol: Symbol,
+    pub exchange: Exchange,
+    pub side: Side,
+    pub strategy: Option<String>,
+    pub confidence: Option<f64>, // Of the opening signal
+    /// Realized P&L in the symbol's quote currency, net of costs
+    pub pnl: f64,
+    pub return_pct: f64, // On the entry notional
+    pub holding_time: Duration,
+    pub exit_reason: ExitReason,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub closed_at: u64, // Unix millis
+}
+
+impl TradeOutcome {
+    /// Outcome of a closed position; `None` while it is still open
+    pub fn from_position(position: &Position) -> Option<Self> {
+        let (exit_price, closed_at) = (position.exit_price?, position.exit_time?);
+        let notional = position.entry_price * position.quantity;
+        Some(Self {
+            signal_id: position.signal_id.clone(),
+            position_id: position.id.clone(),
+            symbol: position.symbol.clone(),
+            exchange: position.exchange,
+            side: position.side,
+            strategy: position.strategy.clone(),
+            confidence: position.signal_confidence,
+            pnl: position.realized_pnl,
+            return_pct: if notional > 0.0 { position.realized_pnl / notional * 100.0 } else { 0.0 },
+            holding_time: Duration::from_millis(closed_at.saturating_sub(position.entry_time)),
+            exit_reason: position.exit_reason.unwrap_or(ExitReason::Manual),
+            entry_price: position.entry_price,
+            exit_price,
+            closed_at,
+        })
+    }
+}
+
+/// What became of a processed signal
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum SignalOutcome {
+    Executed { order_ids: Vec<String> }, // Several for a close of every position in the symbol
+    Rejected { reason: String },         // By a risk check, or it couldn't be executed, e.g. no price yet
+    Throttled { reason: ThrottleReason },
+    Ignored { reason: String },          // Nothing to do, e.g. a hold or a close without a position
+}
+
+/// Outcome of one signal, tagged with the `signal_id` the source gave it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignalResult {
+    pub signal_id: Option<String>,
+    pub account_id: Option<String>,
+    pub symbol: Symbol,
+    pub strategy: Option<String>,
+    #[serde(flatten)]
+    pub outcome: SignalOutcome,
+    pub processed_at: u64, // Unix millis
+}
+
+impl SignalResult {
+    pub fn new(signal: &TradingSignal, outcome: SignalOutcome, processed_at: u64) -> Self {
+        Self {
+            signal_id: signal.metadata.signal_id.clone(),
+            account_id: signal.metadata.account_id.clone(),
+            symbol: signal.symbol.clone(),
+            strategy: signal.metadata.strategy.clone(),
+            outcome,
+            processed_at,
+        }
+    }
+}
+
+/// Fills of a signal's orders once they are done
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignalExecution {
+    pub signal_id: String,
+    pub order_ids: Vec<String>,
+    pub fill_price: f64, // Average over the orders, by quantity
+    pub quantity: f64,
+    pub position_id: Option<String>, // Opened or changed; the first order's when a close covered several
+}
+
+impl SignalExecution {
+    /// None while any order may still fill, or its fill isn't booked to a
+    /// position yet. An error when they are all done without a fill.
+    pub fn from_orders(signal_id: &str, orders: &[Order]) -> Result<Option<Self>, ExecutionError> {
+        let done = |order: &Order| match order.status {
+            OrderStatus::Filled => order.position_id.is_some(),
+            OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired => true,
+            OrderStatus::Pending | OrderStatus::Submitted | OrderStatus::PartiallyFilled => false,
+        };
+        if orders.is_empty() || !orders.iter().all(done) {
+            return Ok(None);
+        }
+        let quantity: f64 = orders.iter().map(|o| o.filled_quantity).sum();
+        if quantity <= 0.0 {
+            return Err(ExecutionError::Unfilled { order_id: orders[0].id.clone(), status: orders[0].status.clone() });
+        }
+        Ok(Some(Self {
+            signal_id: signal_id.to_string(),
+            order_ids: orders.iter().map(|o| o.id.clone()).collect(),
+            fill_price: orders.iter().map(|o| o.avg_fill_price * o.filled_quantity).sum::<f64>() / quantity,
+            quantity,
+            position_id: orders.iter().find_map(|o| o.position_id.clone()),
+        }))
+    }
+}
+
+/// Why a signal executed synchronously didn't fill
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum ExecutionError {
+    #[error("signal rejected: {0}")]
+    Rejected(String),
+    #[error("signal throttled: {0:?}")]
+    Throttled(ThrottleReason),
+    #[error("signal ignored: {0}")]
+    Ignored(String),
+    #[error("order {order_id} ended {status:?} without a fill")]
+    Unfilled { order_id: String, status: OrderStatus },
+    #[error("no fill within {0:?}")]
+    Timeout(Duration),
+}
+
+/// HTTP callback settings
+#[derive(Debug, Clone)]
+pub struct OutcomeWebhookConfig {
+    pub url: String,
+    pub timeout: Duration,
+    /// Retries of a failed POST, with doubling pauses from `retry_backoff`
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+}
+
+impl OutcomeWebhookConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: Duration::from_secs(5),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Publishes trade outcomes and signal results. Clones share the channels,
+/// so accounts can publish into one stream.
+#[derive(Clone)]
+pub struct OutcomePublisher {
+    sender: broadcast::Sender<TradeOutcome>,
+    signals: broadcast::Sender<SignalResult>,
+    published: Arc<AtomicU64>,
+}
+
+impl OutcomePublisher {
+    /// `capacity` is how many outcomes a subscriber may fall behind before losing the oldest
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        let (signals, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            signals,
+            published: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn publish(&self, outcome: TradeOutcome) {
+        self.published.fetch_add(1, Ordering::Relaxed);
+        let _ = self.sender.send(outcome); // No subscribers is fine
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TradeOutcome> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish_signal(&self, result: SignalResult) {
+        let _ = self.signals.send(result); // No subscribers is fine
+    }
+
+    /// What became of each signal processed from now on
+    pub fn subscribe_signals(&self) -> broadcast::Receiver<SignalResult> {
+        self.signals.subscribe()
+    }
+
+    /// Outcomes published so far
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    /// POST every outcome from now on to a callback URL as JSON. Outcomes that
+    /// still fail after the retries are logged and dropped.
+    pub fn spawn_webhook(&self, config: OutcomeWebhookConfig) -> JoinHandle<()> {
+        let mut outcomes = self.subscribe();
+        tokio::spawn(async move {
+            let client = reqwest::Client::builder()
+                .timeout(config.timeout)
+                .build()
+                .unwrap_or_default();
+            loop {
+                let outcome = match outcomes.recv().await {
+                    Ok(outcome) => outcome,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, url = %config.url, "Outcome webhook fell behind, outcomes dropped");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                let mut attempt = 0;
+                loop {
+                    let result = client.post(&config.url).json(&outcome).send().await.and_then(|r| r.error_for_status());
+                    match result {
+                        Ok(_) => break,
+                        Err(e) if attempt < config.max_retries => {
+                            tokio::time::sleep(config.retry_backoff.saturating_mul(1 << attempt.min(16))).await;
+                            attempt += 1;
+                            warn!(url = %config.url, attempt, error = %e, "Outcome webhook failed, retrying");
+                        }
+                        Err(e) => {
+                            warn!(url = %config.url, position_id = %outcome.position_id, error = %e, "Outcome webhook failed, outcome dropped");
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Default for OutcomePublisher {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper_trading::PositionManager;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_outcome_published_and_posted_to_webhook() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/outcomes", listener.local_addr().unwrap());
+
+        // The first POST is refused, the retry is accepted
+        let server = tokio::spawn(async move {
+            let mut bodies = Vec::new();
+            for status in ["500 Internal Server Error", "200 OK"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length: usize = head
+                            .lines()
+                            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break body.to_string();
+                        }
+                    }
+                };
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+                bodies.push(body);
+            }
+            bodies
+        });
+
+        let publisher = OutcomePublisher::default();
+        let mut outcomes = publisher.subscribe();
+        publisher.spawn_webhook(OutcomeWebhookConfig {
+            retry_backoff: Duration::from_millis(10),
+            ..OutcomeWebhookConfig::new(url)
+        });
+
+        let manager = PositionManager::new().with_outcome_publisher(publisher.clone());
+        let id = manager
+            .open_position(Symbol::new("BTC-USD"), Exchange::Binance, Side::Buy, 0.1, 50000.0, 5.0, 0.0)
+            .unwrap();
+        manager.set_position_signal(&id, "sig-42").unwrap();
+        manager.partial_close_position(&id, 0.05, 51000.0, 2.5, 0.0, ExitReason::Signal).unwrap();
+        assert!(outcomes.try_recv().is_err()); // Nothing until the position is closed
+        manager.close_position(&id, 52000.0, 2.5, 0.0, ExitReason::TakeProfit).unwrap();
+
+        let outcome = outcomes.recv().await.unwrap();
+        assert_eq!(outcome.signal_id.as_deref(), Some("sig-42"));
+        assert_eq!(outcome.exit_reason, ExitReason::TakeProfit);
+        assert!((outcome.pnl - (50.0 + 100.0 - 10.0)).abs() < 1e-9);
+        assert_eq!(publisher.published(), 1);
+
+        let bodies = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        let posted: TradeOutcome = serde_json::from_str(&bodies[1]).unwrap();
+        assert_eq!(posted.position_id, id);
+        assert_eq!(posted.signal_id.as_deref(), Some("sig-42"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Volume participation cap on simulated fills
+//!
+//! Without a cap a simulated order fills whole the moment it triggers, however
+//! little the market traded. With `max_rate` set, an order's fills in a symbol
+//! are held to that share of the volume traded over the last `window_ms`, less
+//! what the account already filled in the window, so large orders in thin
+//! symbols fill over several passes as partial fills. Symbols no volume was
+//! recorded for fill as before.
+
+use crate::exchanges::Symbol;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Share of rolling traded volume simulated fills may take; zero disables it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParticipationConfig {
+    pub max_rate: f64, // e.g. 0.1 for 10% of the volume
+    pub window_ms: u64,
+}
+
+impl Default for ParticipationConfig {
+    fn default() -> Self {
+        Self {
+            max_rate: 0.0,
+            window_ms: 60_000,
+        }
+    }
+}
+
+/// Rolling market volume and own fills per symbol
+#[derive(Default)]
+pub struct ParticipationTracker {
+    config: ParticipationConfig,
+    market: DashMap<Symbol, VecDeque<(u64, f64)>>,
+    filled: DashMap<Symbol, VecDeque<(u64, f64)>>,
+}
+
+impl ParticipationTracker {
+    pub fn new(config: ParticipationConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    pub fn config(&self) -> ParticipationConfig {
+        self.config
+    }
+
+    pub fn record_volume(&self, symbol: &Symbol, volume: f64, now_ms: u64) {
+        if self.config.max_rate > 0.0 && volume.is_finite() && volume > 0.0 {
+            self.market.entry(symbol.clone()).or_default().push_back((now_ms, volume));
+        }
+    }
+
+    pub fn record_fill(&self, symbol: &Symbol, quantity: f64, now_ms: u64) {
+        if self.market.contains_key(symbol) {
+            self.filled.entry(symbol.clone()).or_default().push_back((now_ms, quantity));
+        }
+    }
+
+    /// Quantity that may still fill in `symbol` now, or None when uncapped
+    pub fn available(&self, symbol: &Symbol, now_ms: u64) -> Option<f64> {
+        if self.config.max_rate <= 0.0 || !self.market.contains_key(symbol) {
+            return None;
+        }
+        let market = self.window_total(&self.market, symbol, now_ms);
+        let filled = self.window_total(&self.filled, symbol, now_ms);
+        Some((market * self.config.max_rate - filled).max(0.0))
+    }
+
+    /// Sum over the window, dropping older entries
+    fn window_total(&self, map: &DashMap<Symbol, VecDeque<(u64, f64)>>, symbol: &Symbol, now_ms: u64) -> f64 {
+        let Some(mut entries) = map.get_mut(symbol) else { return 0.0 };
+        let start = now_ms.saturating_sub(self.config.window_ms);
+        while entries.front().is_some_and(|(at, _)| *at <= start) {
+            entries.pop_front();
+        }
+        entries.iter().map(|(_, amount)| amount).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side};
+    use crate::paper_trading::clock::{Clock, SimulatedClock};
+    use crate::paper_trading::{Order, OrderManager, OrderStatus, SlippageModel};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_large_order_fills_with_the_volume() {
+        let clock = Arc::new(SimulatedClock::new(1_000_000));
+        let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0))
+            .with_clock(clock.clone())
+            .with_participation(ParticipationConfig { max_rate: 0.1, window_ms: 60_000 });
+        let (thin, unknown) = (Symbol::new("THIN-USD"), Symbol::new("BTC-USD"));
+        let prices = DashMap::new();
+        prices.insert(thin.clone(), 10.0);
+        prices.insert(unknown.clone(), 50_000.0);
+
+        manager.record_volume(&thin, 100.0);
+        let order_id = manager.submit_order(Order::market(thin.clone(), Exchange::Binance, Side::Buy, 25.0)).unwrap();
+        let untracked = manager.submit_order(Order::market(unknown, Exchange::Binance, Side::Buy, 5.0)).unwrap();
+        let mut filled = manager.process_orders(&prices).unwrap();
+        filled.sort();
+        let mut expected = vec![order_id.clone(), untracked];
+        expected.sort();
+        assert_eq!(filled, expected);
+        let order = manager.get_order(&order_id).unwrap();
+        assert_eq!((order.status, order.filled_quantity, order.last_fill.unwrap().quantity), (OrderStatus::PartiallyFilled, 10.0, 10.0));
+
+        // Nothing left of the window's share until more trades
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+        clock.advance(Duration::from_secs(61));
+        manager.record_volume(&thin, 200.0);
+        manager.process_orders(&prices).unwrap();
+        let order = manager.get_order(&order_id).unwrap();
+        assert_eq!((order.status, order.filled_quantity, order.last_fill.unwrap().quantity), (OrderStatus::Filled, 25.0, 15.0));
+        assert_eq!(order.last_fill.unwrap().time, clock.now_ms());
+    }
+}
@@ -1,14 +1,25 @@
 //! Position management for paper trading
 
+use super::attribution::{PnlAttribution, PnlAttributor};
+use super::clock::{self, SharedClock};
+use super::currency::CurrencyConverter;
+use super::events::{EngineEvent, EventLog};
+use super::import::ImportedPosition;
+use super::instruments::InstrumentRegistry;
+use super::outcomes::{OutcomePublisher, TradeOutcome};
+use super::snapshot::IdSequence;
 use crate::exchanges::{Symbol, Exchange, Side};
 use anyhow::Result;
 use dashmap::DashMap;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
 
 /// Position status
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum PositionStatus {
     Open,
     Closed,
@@ -16,7 +27,7 @@ pub enum PositionStatus {
 }
 
 /// Individual position
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct Position {
     pub id: String,
     pub symbol: Symbol,
@@ -32,6 +43,93 @@ pub struct Position {
     pub status: PositionStatus,
     pub commission: f64,
     pub slippage: f64,
+    #[serde(default)]
+    pub strategy: Option<String>,
+    #[serde(default)]
+    pub signal_id: Option<String>, // Signal that opened the position, echoed in its trade outcome
+    #[serde(default)]
+    pub signal_confidence: Option<f64>,
+    #[serde(default)]
+    pub max_adverse_excursion: f64,   // Worst open P&L seen (<= 0)
+    #[serde(default)]
+    pub max_favorable_excursion: f64, // Best open P&L seen (>= 0)
+    #[serde(default)]
+    pub stop_loss: Option<f64>,
+    #[serde(default)]
+    pub take_profit: Option<f64>,
+    #[serde(default)]
+    pub max_hold_ms: Option<u64>,
+    #[serde(default)]
+    pub exit_reason: Option<ExitReason>,
+    #[serde(default)]
+    pub exit_costs: f64, // Costs of partial closes already charged to realized P&L
+    #[serde(default)]
+    pub closed_quantity: f64, // Quantity closed so far, by partial and final closes
+    #[serde(default)]
+    pub group_id: Option<String>, // Position group, e.g. the basket that opened it
+    #[serde(default = "unit_multiplier")]
+    pub multiplier: f64, // Contract multiplier of the symbol when the position opened, see `instruments`
+    #[serde(default)]
+    pub trailing_stop: bool, // The stop follows the price, see `trade_management`
+}
+
+fn unit_multiplier() -> f64 {
+    1.0
+}
+
+/// Why a position was closed
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum ExitReason {
+    StopLoss,
+    TakeProfit,
+    TrailingStop,
+    Signal,
+    TimeStop,
+    KillSwitch,
+    StopOut,
+    Manual,
+    Rule, // A custom exit rule, e.g. a script
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ExitReason::StopLoss => "stop loss",
+            ExitReason::TakeProfit => "take profit",
+            ExitReason::TrailingStop => "trailing stop",
+            ExitReason::Signal => "signal",
+            ExitReason::TimeStop => "time stop",
+            ExitReason::KillSwitch => "kill switch",
+            ExitReason::StopOut => "stop out",
+            ExitReason::Manual => "manual",
+            ExitReason::Rule => "exit rule",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Custom exit condition checked for every open position on each tick, after
+/// the stop, target and time stop
+pub trait ExitRule: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Every price update, whether or not a position is open in the symbol
+    fn record_price(&self, _symbol: &Symbol, _price: f64, _now_ms: u64) {}
+
+    /// Whether `position`, marked at `price`, should be closed
+    fn should_exit(&self, position: &Position, price: f64, now_ms: u64) -> bool;
+}
+
+/// Position whose exit level was crossed and needs closing
+#[derive(Clone, Debug)]
+pub struct TriggeredExit {
+    pub position_id: String,
+    pub symbol: Symbol,
+    pub exchange: Exchange,
+    pub side: Side,
+    pub quantity: f64,
+    pub reason: ExitReason,
+    pub price: f64,
 }
 
 impl Position {
@@ -68,12 +166,26 @@ impl Position {
             status: PositionStatus::Open,
             commission: 0.0,
             slippage: 0.0,
+            strategy: None,
+            signal_id: None,
+            signal_confidence: None,
+            max_adverse_excursion: 0.0,
+            max_favorable_excursion: 0.0,
+            stop_loss: None,
+            take_profit: None,
+            max_hold_ms: None,
+            exit_reason: None,
+            exit_costs: 0.0,
+            closed_quantity: 0.0,
+            group_id: None,
+            multiplier: 1.0,
+            trailing_stop: false,
         }
     }
     
     /// Update unrealized P&L based on current price
     pub fn update_unrealized_pnl(&mut self, current_price: f64) {
-        if self.status != PositionStatus::Open {
+        if self.status == PositionStatus::Closed {
             return;
         }
         
@@ -82,25 +194,84 @@ impl Position {
             Side::Sell => self.entry_price - current_price,
         };
         
-        self.unrealized_pnl = price_diff * self.quantity - self.commission - self.slippage;
+        self.unrealized_pnl = price_diff * self.quantity * self.multiplier - self.open_costs();
+        self.track_excursion(price_diff);
+    }
+    
+    /// Price the position was last marked at, from its unrealized P&L
+    pub fn mark_price(&self) -> f64 {
+        if self.quantity <= 0.0 {
+            return self.entry_price;
+        }
+        let price_diff = (self.unrealized_pnl + self.open_costs()) / (self.quantity * self.multiplier);
+        match self.side {
+            Side::Buy => self.entry_price + price_diff,
+            Side::Sell => self.entry_price - price_diff,
+        }
+    }
+    
+    /// Entry costs not yet charged to realized P&L
+    fn open_costs(&self) -> f64 {
+        self.commission + self.slippage - self.exit_costs
+    }
+    
+    /// Record excursion from entry, measured on price movement before costs
+    fn track_excursion(&mut self, price_diff: f64) {
+        let excursion = price_diff * self.quantity * self.multiplier;
+        self.max_adverse_excursion = self.max_adverse_excursion.min(excursion);
+        self.max_favorable_excursion = self.max_favorable_excursion.max(excursion);
+    }
+    
+    /// Check whether the price crosses this position's stop-loss or take-profit
+    pub fn exit_trigger(&self, current_price: f64) -> Option<ExitReason> {
+        if self.status == PositionStatus::Closed {
+            return None;
+        }
+        
+        let (stop_hit, target_hit) = match self.side {
+            Side::Buy => (
+                self.stop_loss.is_some_and(|sl| current_price <= sl),
+                self.take_profit.is_some_and(|tp| current_price >= tp),
+            ),
+            Side::Sell => (
+                self.stop_loss.is_some_and(|sl| current_price >= sl),
+                self.take_profit.is_some_and(|tp| current_price <= tp),
+            ),
+        };
+        
+        // Stop takes precedence if a gap crosses both levels
+        if stop_hit && self.trailing_stop {
+            Some(ExitReason::TrailingStop)
+        } else if stop_hit {
+            Some(ExitReason::StopLoss)
+        } else if target_hit {
+            Some(ExitReason::TakeProfit)
+        } else {
+            None
+        }
+    }
+    
+    /// Check whether the position has been held past its maximum duration
+    pub fn held_too_long(&self, now_ms: u64) -> bool {
+        self.status != PositionStatus::Closed
+            && self.max_hold_ms
+                .is_some_and(|max| now_ms.saturating_sub(self.entry_time) >= max)
     }
     
     /// Close position at given price
-    pub fn close(&mut self, exit_price: f64, commission: f64, slippage: f64) {
+    pub fn close(&mut self, exit_price: f64, commission: f64, slippage: f64, now_ms: u64) {
         self.exit_price = Some(exit_price);
-        self.exit_time = Some(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64
-        );
+        self.exit_time = Some(now_ms);
         
         let price_diff = match self.side {
             Side::Buy => exit_price - self.entry_price,
             Side::Sell => self.entry_price - exit_price,
         };
+        self.track_excursion(price_diff);
         
-        self.realized_pnl = price_diff * self.quantity - self.commission - commission - self.slippage - slippage;
+        // Partial closes have already added their share to realized_pnl
+        self.realized_pnl += price_diff * self.quantity * self.multiplier - self.open_costs() - commission - slippage;
+        self.closed_quantity += self.quantity;
         self.unrealized_pnl = 0.0;
         self.status = PositionStatus::Closed;
         self.commission += commission;
@@ -108,29 +279,41 @@ impl Position {
     }
     
     /// Partially close position
-    pub fn partial_close(&mut self, quantity: f64, exit_price: f64, commission: f64, slippage: f64) -> f64 {
+    pub fn partial_close(&mut self, quantity: f64, exit_price: f64, commission: f64, slippage: f64, now_ms: u64) -> f64 {
         if quantity >= self.quantity {
-            self.close(exit_price, commission, slippage);
-            return self.realized_pnl;
+            let realized_before = self.realized_pnl;
+            self.close(exit_price, commission, slippage, now_ms);
+            return self.realized_pnl - realized_before;
         }
         
-        let _close_ratio = quantity / self.quantity;
-        
         let price_diff = match self.side {
             Side::Buy => exit_price - self.entry_price,
             Side::Sell => self.entry_price - exit_price,
         };
         
-        let partial_pnl = price_diff * quantity - commission - slippage;
+        let partial_pnl = price_diff * quantity * self.multiplier - commission - slippage;
         self.realized_pnl += partial_pnl;
         self.quantity -= quantity;
+        self.closed_quantity += quantity;
         self.commission += commission;
         self.slippage += slippage;
+        self.exit_costs += commission + slippage;
         self.status = PositionStatus::PartiallyClosed;
         
         partial_pnl
     }
     
+    /// Add to the position, moving the entry price to the quantity-weighted average
+    pub fn add(&mut self, quantity: f64, price: f64, commission: f64, slippage: f64) {
+        let total_quantity = self.quantity + quantity;
+        if total_quantity > 0.0 {
+            self.entry_price = (self.entry_price * self.quantity + price * quantity) / total_quantity;
+        }
+        self.quantity = total_quantity;
+        self.commission += commission;
+        self.slippage += slippage;
+    }
+    
     /// Get total P&L (realized + unrealized)
     pub fn total_pnl(&self) -> f64 {
         self.realized_pnl + self.unrealized_pnl
@@ -138,12 +321,12 @@ impl Position {
     
     /// Get position value at current price
     pub fn current_value(&self, current_price: f64) -> f64 {
-        self.quantity * current_price
+        self.quantity * current_price * self.multiplier
     }
     
     /// Calculate return on investment
     pub fn roi(&self) -> f64 {
-        let initial_value = self.quantity * self.entry_price;
+        let initial_value = self.quantity * self.entry_price * self.multiplier;
         if initial_value == 0.0 {
             return 0.0;
         }
@@ -167,36 +350,132 @@ pub struct PositionStatistics {
     pub avg_loss: f64,
     pub profit_factor: f64,
     pub sharpe_ratio: f64,
+    pub exits_by_reason: HashMap<ExitReason, ExitReasonStats>,
+}
+
+/// Closed-position totals for one exit reason
+#[derive(Default, Clone, Debug)]
+pub struct ExitReasonStats {
+    pub count: u64,
+    pub winning: u64,
+    pub total_pnl: f64,
+}
+
+/// Serializable state of a position manager, see `PositionManager::snapshot`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PositionBook {
+    pub positions: Vec<Position>, // By symbol, each symbol's in opening order
+    pub pending_exits: Vec<(String, ExitReason)>,
+    pub opened: u64,
+    pub realized_cents: i64,
+    pub unrealized_cents: Vec<(Symbol, i64)>,
+    pub commission_cents: i64,
+    pub slippage_cents: i64,
+    pub ids_issued: u64, // From the seeded ID sequence, if any
 }
 
 /// Position manager for paper trading
 pub struct PositionManager {
     positions: DashMap<String, Position>,
     positions_by_symbol: DashMap<Symbol, Vec<String>>,
+    positions_by_direction: DashMap<(Symbol, Side), Vec<String>>,
     open_positions: DashMap<String, Position>,
+    open_by_symbol: DashMap<Symbol, Vec<String>>, // Open IDs only, so a tick touches just its own symbol
+    unrealized_by_symbol: DashMap<Symbol, i64>, // Each symbol's share of total_unrealized_pnl, in cents
     closed_positions: DashMap<String, Position>,
+    pending_exits: DashMap<String, ExitReason>, // Positions with an exit order in flight
     position_counter: AtomicU64,
-    total_realized_pnl: AtomicI64, // Store as cents to avoid float atomics
+    total_realized_pnl: AtomicI64, // Store as cents of the reporting currency to avoid float atomics
     total_unrealized_pnl: AtomicI64,
     total_commission: AtomicI64,
     total_slippage: AtomicI64,
+    attribution: PnlAttributor, // Realized P&L by symbol, exchange and entry time
+    converter: Arc<CurrencyConverter>,
+    instruments: Arc<InstrumentRegistry>, // Multipliers of the positions it opens
+    clock: SharedClock,
+    outcomes: OutcomePublisher,
+    ids: Option<IdSequence>, // Random IDs when unset
+    events: EventLog,
+    exit_rules: parking_lot::RwLock<Vec<Arc<dyn ExitRule>>>,
 }
 
 impl PositionManager {
     pub fn new() -> Self {
+        Self::with_currency_converter(Arc::new(CurrencyConverter::default()))
+    }
+    
+    /// Create a position manager that reports totals through the given converter
+    pub fn with_currency_converter(converter: Arc<CurrencyConverter>) -> Self {
         Self {
             positions: DashMap::new(),
             positions_by_symbol: DashMap::new(),
+            positions_by_direction: DashMap::new(),
             open_positions: DashMap::new(),
+            open_by_symbol: DashMap::new(),
+            unrealized_by_symbol: DashMap::new(),
             closed_positions: DashMap::new(),
+            pending_exits: DashMap::new(),
             position_counter: AtomicU64::new(0),
             total_realized_pnl: AtomicI64::new(0),
             total_unrealized_pnl: AtomicI64::new(0),
             total_commission: AtomicI64::new(0),
             total_slippage: AtomicI64::new(0),
+            attribution: PnlAttributor::default(),
+            converter,
+            instruments: Arc::new(InstrumentRegistry::default()),
+            clock: clock::system_clock(),
+            outcomes: OutcomePublisher::default(),
+            ids: None,
+            events: EventLog::default(),
+            exit_rules: parking_lot::RwLock::new(Vec::new()),
         }
     }
     
+    /// Take entry, exit and time-stop times from `clock` instead of the wall clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Open positions with the multipliers of these instruments
+    pub fn with_instruments(mut self, instruments: Arc<InstrumentRegistry>) -> Self {
+        self.instruments = instruments;
+        self
+    }
+    
+    /// Publish the outcomes of closed positions here, e.g. a publisher shared by several accounts
+    pub fn with_outcome_publisher(mut self, outcomes: OutcomePublisher) -> Self {
+        self.outcomes = outcomes;
+        self
+    }
+    
+    /// Generate position IDs from `seed` so runs can be reproduced
+    pub fn with_id_seed(mut self, seed: u64) -> Self {
+        self.ids = Some(IdSequence::new(seed));
+        self
+    }
+    
+    /// Append positions opened, changed and closed to `events`
+    pub fn with_event_log(mut self, events: EventLog) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Where the outcomes of closed positions are published
+    pub fn outcome_publisher(&self) -> &OutcomePublisher {
+        &self.outcomes
+    }
+    
+    /// Get the currency converter used for reporting totals
+    pub fn currency_converter(&self) -> &Arc<CurrencyConverter> {
+        &self.converter
+    }
+    
+    /// Convert an amount in the symbol's quote currency to reporting-currency cents
+    fn to_cents(&self, symbol: &Symbol, amount: f64) -> i64 {
+        (self.converter.to_reporting(symbol, amount) * 100.0).round() as i64
+    }
+    
     /// Open a new position
     pub fn open_position(
         &self,
@@ -208,28 +487,69 @@ impl PositionManager {
         commission: f64,
         slippage: f64,
     ) -> Result<String> {
-        let mut position = Position::new(symbol.clone(), exchange, side, quantity, entry_price);
+        let mut position = Position::new(symbol, exchange, side, quantity, entry_price);
+        position.entry_time = self.clock.now_ms();
         position.commission = commission;
         position.slippage = slippage;
-        
+        Ok(self.track_opened(position))
+    }
+    
+    /// Open a position carried over from an existing portfolio, with its own
+    /// entry time and exit levels and no fees
+    pub fn import_position(&self, imported: &ImportedPosition) -> Result<String> {
+        imported.validate()?;
+        let mut position = Position::new(
+            imported.symbol.clone(),
+            imported.exchange,
+            imported.side,
+            imported.quantity,
+            imported.entry_price,
+        );
+        position.entry_time = match imported.entry_time {
+            Some(time) => time.timestamp_millis().max(0) as u64,
+            None => self.clock.now_ms(),
+        };
+        position.stop_loss = imported.stop_loss;
+        position.take_profit = imported.take_profit;
+        position.strategy = imported.strategy.clone();
+        Ok(self.track_opened(position))
+    }
+    
+    /// Index a newly opened position and count its costs
+    fn track_opened(&self, mut position: Position) -> String {
+        if let Some(ids) = &self.ids {
+            position.id = ids.next("POS", position.entry_time);
+        }
+        position.multiplier = self.instruments.multiplier(&position.symbol);
+        let symbol = position.symbol.clone();
+        let side = position.side;
         let position_id = position.id.clone();
         
         // Update tracking
+        self.events.record(|| EngineEvent::PositionOpened { position: position.clone() });
         self.positions.insert(position_id.clone(), position.clone());
         self.open_positions.insert(position_id.clone(), position.clone());
         
-        // Track by symbol
+        // Track by symbol and by (symbol, direction)
+        self.positions_by_direction
+            .entry((symbol.clone(), side))
+            .or_insert_with(Vec::new)
+            .push(position_id.clone());
         self.positions_by_symbol
-            .entry(symbol)
+            .entry(symbol.clone())
+            .or_insert_with(Vec::new)
+            .push(position_id.clone());
+        self.open_by_symbol
+            .entry(symbol.clone())
             .or_insert_with(Vec::new)
             .push(position_id.clone());
         
         // Update counters
         self.position_counter.fetch_add(1, Ordering::Relaxed);
-        self.total_commission.fetch_add((commission * 100.0) as i64, Ordering::Relaxed);
-        self.total_slippage.fetch_add((slippage * 100.0) as i64, Ordering::Relaxed);
+        self.total_commission.fetch_add(self.to_cents(&symbol, position.commission), Ordering::Relaxed);
+        self.total_slippage.fetch_add(self.to_cents(&symbol, position.slippage), Ordering::Relaxed);
         
-        Ok(position_id)
+        position_id
     }
     
     /// Close a position
@@ -239,23 +559,33 @@ impl PositionManager {
         exit_price: f64,
         commission: f64,
         slippage: f64,
+        reason: ExitReason,
     ) -> Result<f64> {
         let mut position = self.open_positions
             .remove(position_id)
             .ok_or_else(|| anyhow::anyhow!("Position {} not found or already closed", position_id))?
             .1;
         
-        position.close(exit_price, commission, slippage);
-        let pnl = position.realized_pnl;
+        let realized_before = position.realized_pnl;
+        position.close(exit_price, commission, slippage, self.clock.now_ms());
+        position.exit_reason = Some(reason);
+        let pnl = position.realized_pnl - realized_before;
+        self.pending_exits.remove(position_id);
+        let symbol = position.symbol.clone();
+        self.attribution.record(&position, self.to_cents(&symbol, pnl));
         
         // Move to closed positions
+        self.events.record(|| EngineEvent::PositionClosed { position: position.clone() });
+        self.publish_outcome(&position);
         self.closed_positions.insert(position_id.to_string(), position.clone());
         self.positions.insert(position_id.to_string(), position);
         
+        self.remove_open_index(&symbol, position_id);
+        
         // Update totals
-        self.total_realized_pnl.fetch_add((pnl * 100.0) as i64, Ordering::Relaxed);
-        self.total_commission.fetch_add((commission * 100.0) as i64, Ordering::Relaxed);
-        self.total_slippage.fetch_add((slippage * 100.0) as i64, Ordering::Relaxed);
+        self.total_realized_pnl.fetch_add(self.to_cents(&symbol, pnl), Ordering::Relaxed);
+        self.total_commission.fetch_add(self.to_cents(&symbol, commission), Ordering::Relaxed);
+        self.total_slippage.fetch_add(self.to_cents(&symbol, slippage), Ordering::Relaxed);
         
         Ok(pnl)
     }
@@ -268,44 +598,426 @@ impl PositionManager {
         exit_price: f64,
         commission: f64,
         slippage: f64,
+        reason: ExitReason,
     ) -> Result<f64> {
         let mut position = self.open_positions
             .get_mut(position_id)
             .ok_or_else(|| anyhow::anyhow!("Position {} not found", position_id))?;
         
-        let pnl = position.partial_close(quantity, exit_price, commission, slippage);
+        let pnl = position.partial_close(quantity, exit_price, commission, slippage, self.clock.now_ms());
+        if position.status == PositionStatus::Closed {
+            position.exit_reason = Some(reason);
+        }
+        let updated = position.clone();
+        drop(position); // Release the lock
         
         // If fully closed, move to closed positions
-        if position.status == PositionStatus::Closed {
-            let closed_position = position.clone();
-            drop(position); // Release the lock
-            
+        if updated.status == PositionStatus::Closed {
             self.open_positions.remove(position_id);
-            self.closed_positions.insert(position_id.to_string(), closed_position);
+            self.pending_exits.remove(position_id);
+            self.publish_outcome(&updated);
+            self.closed_positions.insert(position_id.to_string(), updated.clone());
+        }
+        let symbol = updated.symbol.clone();
+        let closed = updated.status == PositionStatus::Closed;
+        self.attribution.record(&updated, self.to_cents(&symbol, pnl));
+        self.events.record(|| match closed {
+            true => EngineEvent::PositionClosed { position: updated.clone() },
+            false => EngineEvent::PositionUpdated { position: updated.clone() },
+        });
+        self.positions.insert(position_id.to_string(), updated);
+        if closed {
+            self.remove_open_index(&symbol, position_id);
+        } else {
+            self.refresh_unrealized(&symbol);
         }
         
         // Update totals
-        self.total_realized_pnl.fetch_add((pnl * 100.0) as i64, Ordering::Relaxed);
-        self.total_commission.fetch_add((commission * 100.0) as i64, Ordering::Relaxed);
-        self.total_slippage.fetch_add((slippage * 100.0) as i64, Ordering::Relaxed);
+        self.total_realized_pnl.fetch_add(self.to_cents(&symbol, pnl), Ordering::Relaxed);
+        self.total_commission.fetch_add(self.to_cents(&symbol, commission), Ordering::Relaxed);
+        self.total_slippage.fetch_add(self.to_cents(&symbol, slippage), Ordering::Relaxed);
         
         Ok(pnl)
     }
     
+    /// Add quantity to an open position at the given fill price
+    pub fn add_to_position(
+        &self,
+        position_id: &str,
+        quantity: f64,
+        price: f64,
+        commission: f64,
+        slippage: f64,
+    ) -> Result<()> {
+        let mut position = self.open_positions
+            .get_mut(position_id)
+            .ok_or_else(|| anyhow::anyhow!("Position {} not found or already closed", position_id))?;
+        
+        position.add(quantity, price, commission, slippage);
+        let updated = position.clone();
+        drop(position);
+        let symbol = updated.symbol.clone();
+        self.events.record(|| EngineEvent::PositionUpdated { position: updated.clone() });
+        self.positions.insert(position_id.to_string(), updated);
+        
+        self.total_commission.fetch_add(self.to_cents(&symbol, commission), Ordering::Relaxed);
+        self.total_slippage.fetch_add(self.to_cents(&symbol, slippage), Ordering::Relaxed);
+        
+        Ok(())
+    }
+    
+    /// Adjust the open positions of `symbol` for a split of `ratio` new shares
+    /// per old one: the quantity is multiplied, the entry price and exit levels
+    /// divided, so P&L is unchanged. Returns the number of positions adjusted.
+    pub fn apply_split(&self, symbol: &Symbol, ratio: f64) -> Result<usize> {
+        if !ratio.is_finite() || ratio <= 0.0 {
+            anyhow::bail!("Invalid split ratio {} for {}", ratio, symbol);
+        }
+        let ids = self.open_by_symbol.get(symbol).map(|ids| ids.clone()).unwrap_or_default();
+        for id in &ids {
+            self.modify_position(id, |p| {
+                p.quantity *= ratio;
+                p.closed_quantity *= ratio;
+                p.entry_price /= ratio;
+                p.stop_loss = p.stop_loss.map(|level| level / ratio);
+                p.take_profit = p.take_profit.map(|level| level / ratio);
+            })?;
+            if let Some(position) = self.get_position(id) {
+                self.events.record(|| EngineEvent::PositionUpdated { position });
+            }
+        }
+        Ok(ids.len())
+    }
+    
+    /// Pay a cash dividend of `amount` per share on the open positions of
+    /// `symbol`, as realized P&L: longs receive it, shorts owe it. Returns the
+    /// net amount in the symbol's quote currency.
+    pub fn apply_dividend(&self, symbol: &Symbol, amount: f64) -> Result<f64> {
+        if !amount.is_finite() {
+            anyhow::bail!("Invalid dividend {} for {}", amount, symbol);
+        }
+        let ids = self.open_by_symbol.get(symbol).map(|ids| ids.clone()).unwrap_or_default();
+        let mut total = 0.0;
+        for id in &ids {
+            let Some(quantity) = self.open_positions.get(id).map(|p| match p.side {
+                Side::Buy => p.quantity * p.multiplier,
+                Side::Sell => -p.quantity * p.multiplier,
+            }) else {
+                continue;
+            };
+            let paid = amount * quantity;
+            self.modify_position(id, |p| p.realized_pnl += paid)?;
+            let cents = self.to_cents(symbol, paid);
+            self.total_realized_pnl.fetch_add(cents, Ordering::Relaxed);
+            if let Some(position) = self.get_position(id) {
+                self.attribution.record(&position, cents);
+                self.events.record(|| EngineEvent::PositionUpdated { position });
+            }
+            total += paid;
+        }
+        Ok(total)
+    }
+    
+    /// Apply a change to every stored copy of a position
+    fn modify_position<F>(&self, position_id: &str, f: F) -> Result<()>
+    where
+        F: Fn(&mut Position),
+    {
+        let mut position = self.positions
+            .get_mut(position_id)
+            .ok_or_else(|| anyhow::anyhow!("Position {} not found", position_id))?;
+        f(&mut position);
+        drop(position);
+        
+        if let Some(mut open) = self.open_positions.get_mut(position_id) {
+            f(&mut open);
+        }
+        if let Some(mut closed) = self.closed_positions.get_mut(position_id) {
+            f(&mut closed);
+        }
+        
+        Ok(())
+    }
+    
+    /// Tag a position with the ID of the signal that opened it
+    pub fn set_position_signal(&self, position_id: &str, signal_id: impl Into<String>) -> Result<()> {
+        let signal_id = signal_id.into();
+        self.modify_position(position_id, |p| p.signal_id = Some(signal_id.clone()))
+    }
+    
+    /// Record the confidence of the signal that opened a position
+    pub fn set_position_confidence(&self, position_id: &str, confidence: f64) -> Result<()> {
+        self.modify_position(position_id, |p| p.signal_confidence = Some(confidence))
+    }
+    
+    /// Tag a position with the strategy that opened it
+    pub fn set_position_strategy(&self, position_id: &str, strategy: impl Into<String>) -> Result<()> {
+        let strategy = strategy.into();
+        self.modify_position(position_id, |p| p.strategy = Some(strategy.clone()))
+    }
+    
+    /// Put an open position in a position group
+    pub fn set_position_group(&self, position_id: &str, group_id: impl Into<String>) -> Result<()> {
+        let group_id = group_id.into();
+        self.modify_position(position_id, |p| p.group_id = Some(group_id.clone()))
+    }
+    
+    /// Set or clear the stop-loss and take-profit levels of an open position
+    pub fn modify_position_exits(
+        &self,
+        position_id: &str,
+        stop_loss: Option<f64>,
+        take_profit: Option<f64>,
+    ) -> Result<()> {
+        let position = self.open_positions
+            .get(position_id)
+            .map(|p| p.clone())
+            .ok_or_else(|| anyhow::anyhow!("Position {} not found or already closed", position_id))?;
+        
+        for level in stop_loss.iter().chain(take_profit.iter()) {
+            if !level.is_finite() || *level <= 0.0 {
+                anyhow::bail!("Invalid exit level {} for position {}", level, position_id);
+            }
+        }
+        
+        if let (Some(sl), Some(tp)) = (stop_loss, take_profit) {
+            let ordered = match position.side {
+                Side::Buy => sl < tp,
+                Side::Sell => sl > tp,
+            };
+            if !ordered {
+                anyhow::bail!(
+                    "Stop-loss {} and take-profit {} are on the wrong sides for a {:?} position",
+                    sl, tp, position.side
+                );
+            }
+        }
+        
+        self.modify_position(position_id, |p| {
+            p.stop_loss = stop_loss;
+            p.take_profit = take_profit;
+        })
+    }
+    
+    /// Set or clear the maximum holding duration of an open position
+    pub fn set_max_hold(&self, position_id: &str, max_hold: Option<Duration>) -> Result<()> {
+        if !self.open_positions.contains_key(position_id) {
+            anyhow::bail!("Position {} not found or already closed", position_id);
+        }
+        
+        let max_hold_ms = max_hold.map(|d| d.as_millis() as u64);
+        self.modify_position(position_id, |p| p.max_hold_ms = max_hold_ms)
+    }
+    
+    /// Mark the stop of an open position as trailing the price, so it closes
+    /// with `TrailingStop` rather than `StopLoss`
+    pub fn set_trailing_stop(&self, position_id: &str, trailing: bool) -> Result<()> {
+        if !self.open_positions.contains_key(position_id) {
+            anyhow::bail!("Position {} not found or already closed", position_id);
+        }
+        self.modify_position(position_id, |p| p.trailing_stop = trailing)
+    }
+    
+    /// Find open positions whose exit levels are crossed by current prices
+    /// or that have exceeded their maximum holding time.
+    /// Each position is reported once until it is closed.
+    pub fn check_exits(&self, prices: &DashMap<Symbol, f64>) -> Vec<TriggeredExit> {
+        let now = self.clock.now_ms();
+        
+        // Oldest first, so exit orders are submitted in the same order every run
+        let mut open: Vec<Position> = self.open_positions.iter().map(|entry| entry.value().clone()).collect();
+        open.sort_by(|a, b| a.entry_time.cmp(&b.entry_time).then_with(|| a.id.cmp(&b.id)));
+        
+        open.iter()
+            .filter_map(|position| {
+                let price = *prices.get(&position.symbol)?;
+                self.exit_for(position, price, now)
+            })
+            .collect()
+    }
+    
+    /// Like `check_exits`, but only for the open positions in one symbol
+    pub fn check_exits_for_symbol(&self, symbol: &Symbol, price: f64) -> Vec<TriggeredExit> {
+        let now = self.clock.now_ms();
+        let ids = match self.open_by_symbol.get(symbol) {
+            Some(ids) => ids,
+            None => return Vec::new(),
+        };
+        
+        ids.iter()
+            .filter_map(|id| {
+                let position = self.open_positions.get(id)?;
+                self.exit_for(&position, price, now)
+            })
+            .collect()
+    }
+    
+    /// Exit for a position at `price`, unless none triggers or one is already pending
+    fn exit_for(&self, position: &Position, price: f64, now: u64) -> Option<TriggeredExit> {
+        let reason = position.exit_trigger(price)
+            .or_else(|| position.held_too_long(now).then_some(ExitReason::TimeStop))
+            .or_else(|| self.rule_exit(position, price, now))?;
+        
+        match self.pending_exits.entry(position.id.clone()) {
+            dashmap::mapref::entry::Entry::Vacant(pending) => {
+                pending.insert(reason);
+                Some(TriggeredExit {
+                    position_id: position.id.clone(),
+                    symbol: position.symbol.clone(),
+                    exchange: position.exchange,
+                    side: position.side,
+                    quantity: position.quantity,
+                    reason,
+                    price,
+                })
+            }
+            dashmap::mapref::entry::Entry::Occupied(_) => None,
+        }
+    }
+    
+    /// `Rule` when a custom exit rule closes the position
+    fn rule_exit(&self, position: &Position, price: f64, now: u64) -> Option<ExitReason> {
+        if self.pending_exits.contains_key(&position.id) {
+            return None;
+        }
+        self.exit_rules
+            .read()
+            .iter()
+            .any(|rule| rule.should_exit(position, price, now))
+            .then_some(ExitReason::Rule)
+    }
+    
+    /// Close positions when `rule` says so, see `ExitRule`
+    pub fn add_exit_rule(&self, rule: Arc<dyn ExitRule>) {
+        self.exit_rules.write().push(rule);
+    }
+    
+    /// Record that an exit order is in flight for a position, so price
+    /// triggers don't submit a second one and the fill keeps its reason
+    pub fn mark_pending_exit(&self, position_id: &str, reason: ExitReason) {
+        self.pending_exits.insert(position_id.to_string(), reason);
+    }
+    
+    /// Reason recorded for a position's in-flight exit order
+    pub fn pending_exit_reason(&self, position_id: &str) -> Option<ExitReason> {
+        self.pending_exits.get(position_id).map(|r| *r)
+    }
+    
+    /// Allow a position to trigger again, e.g. after its exit order failed
+    pub fn clear_pending_exit(&self, position_id: &str) {
+        self.pending_exits.remove(position_id);
+    }
+    
+    /// Get all closed positions
+    pub fn get_closed_positions(&self) -> Vec<Position> {
+        self.closed_positions
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+    
     /// Update all open positions with current prices
     pub fn update_prices(&self, prices: &DashMap<Symbol, f64>) {
-        let mut total_unrealized = 0i64;
+        let symbols: Vec<Symbol> = self.open_by_symbol.iter().map(|entry| entry.key().clone()).collect();
+        
+        for symbol in symbols {
+            if let Some(price) = prices.get(&symbol).map(|p| *p) {
+                self.update_symbol_price(&symbol, price);
+            }
+        }
+    }
+    
+    /// Mark the open positions in one symbol to `price`. Other symbols are
+    /// untouched and the unrealized total is adjusted by this symbol's change.
+    pub fn update_symbol_price(&self, symbol: &Symbol, price: f64) {
+        let rules = self.exit_rules.read();
+        if !rules.is_empty() {
+            let now = self.clock.now_ms();
+            rules.iter().for_each(|rule| rule.record_price(symbol, price, now));
+        }
+        drop(rules);
         
-        for mut entry in self.open_positions.iter_mut() {
-            let position = entry.value_mut();
+        let ids = match self.open_by_symbol.get(symbol) {
+            Some(ids) => ids,
+            None => return,
+        };
+        
+        let mut unrealized = 0.0;
+        for id in ids.iter() {
+            let marked = match self.open_positions.get_mut(id) {
+                Some(mut position) => {
+                    position.update_unrealized_pnl(price);
+                    (position.unrealized_pnl, position.max_adverse_excursion, position.max_favorable_excursion)
+                }
+                None => continue,
+            };
+            unrealized += marked.0;
             
-            if let Some(price) = prices.get(&position.symbol) {
-                position.update_unrealized_pnl(*price);
-                total_unrealized += (position.unrealized_pnl * 100.0) as i64;
+            // Keep the master record in sync so lookups by ID see live P&L and excursions
+            if let Some(mut record) = self.positions.get_mut(id) {
+                (record.unrealized_pnl, record.max_adverse_excursion, record.max_favorable_excursion) = marked;
             }
         }
+        drop(ids);
+        
+        self.set_symbol_unrealized(symbol, self.to_cents(symbol, unrealized));
+    }
+    
+    /// Recompute a symbol's unrealized share from the last marked P&L of its open positions
+    fn refresh_unrealized(&self, symbol: &Symbol) {
+        let unrealized: f64 = self.open_by_symbol
+            .get(symbol)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| self.open_positions.get(id).map(|p| p.unrealized_pnl))
+                    .sum()
+            })
+            .unwrap_or_default();
         
-        self.total_unrealized_pnl.store(total_unrealized, Ordering::Relaxed);
+        self.set_symbol_unrealized(symbol, self.to_cents(symbol, unrealized));
+    }
+    
+    fn set_symbol_unrealized(&self, symbol: &Symbol, cents: i64) {
+        let previous = match self.unrealized_by_symbol.get_mut(symbol) {
+            Some(mut share) => std::mem::replace(&mut *share, cents),
+            None => {
+                self.unrealized_by_symbol.insert(symbol.clone(), cents);
+                0
+            }
+        };
+        self.total_unrealized_pnl.fetch_add(cents - previous, Ordering::Relaxed);
+    }
+    
+    fn publish_outcome(&self, position: &Position) {
+        if let Some(outcome) = TradeOutcome::from_position(position) {
+            self.outcomes.publish(outcome);
+        }
+    }
+
+    /// Drop a closed position from the open index and its symbol's unrealized share
+    fn remove_open_index(&self, symbol: &Symbol, position_id: &str) {
+        let now_empty = match self.open_by_symbol.get_mut(symbol) {
+            Some(mut ids) => {
+                ids.retain(|id| id != position_id);
+                ids.is_empty()
+            }
+            None => false,
+        };
+        if now_empty {
+            self.open_by_symbol.remove_if(symbol, |_, ids| ids.is_empty());
+        }
+        self.refresh_unrealized(symbol);
+    }
+    
+    /// Realized plus unrealized P&L in the reporting currency
+    pub fn total_pnl(&self) -> f64 {
+        let cents = self.total_realized_pnl.load(Ordering::Relaxed) + self.total_unrealized_pnl.load(Ordering::Relaxed);
+        cents as f64 / 100.0
+    }
+    
+    /// Realized and unrealized P&L by symbol, exchange, entry hour and weekday
+    pub fn pnl_attribution(&self) -> PnlAttribution {
+        self.attribution.attribution(&self.get_open_positions(), |symbol, amount| self.converter.to_reporting(symbol, amount))
     }
     
     /// Get position by ID
@@ -313,6 +1025,14 @@ impl PositionManager {
         self.positions.get(position_id).map(|p| p.clone())
     }
     
+    /// Get every position, open or closed, in its latest state
+    pub fn get_all_positions(&self) -> Vec<Position> {
+        self.positions
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+    
     /// Get all open positions
     pub fn get_open_positions(&self) -> Vec<Position> {
         self.open_positions
@@ -321,6 +1041,26 @@ impl PositionManager {
             .collect()
     }
     
+    /// What the open positions would lose from `prices` if every one hit its
+    /// stop, in the reporting currency. A position without a stop puts its
+    /// whole notional at risk. Positions without a price are taken at their
+    /// entry price.
+    pub fn capital_at_risk(&self, prices: &DashMap<Symbol, f64>) -> f64 {
+        self.open_positions
+            .iter()
+            .map(|entry| {
+                let p = entry.value();
+                let price = prices.get(&p.symbol).map_or(p.entry_price, |price| *price);
+                let loss_per_unit = match (p.stop_loss, p.side) {
+                    (Some(stop), Side::Buy) => (price - stop).max(0.0),
+                    (Some(stop), Side::Sell) => (stop - price).max(0.0),
+                    (None, _) => price,
+                };
+                self.converter.to_reporting(&p.symbol, loss_per_unit * p.quantity * p.multiplier)
+            })
+            .sum()
+    }
+    
     /// Get open positions for a symbol
     pub fn get_open_positions_by_symbol(&self, symbol: &Symbol) -> Vec<Position> {
         self.positions_by_symbol
@@ -335,6 +1075,20 @@ impl PositionManager {
             .unwrap_or_default()
     }
     
+    /// Get open positions for a symbol on one side (long or short)
+    pub fn get_open_positions_by_direction(&self, symbol: &Symbol, side: Side) -> Vec<Position> {
+        self.positions_by_direction
+            .get(&(symbol.clone(), side))
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| {
+                        self.open_positions.get(id).map(|p| p.clone())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
     /// Get net position for a symbol
     pub fn get_net_position(&self, symbol: &Symbol) -> f64 {
         self.get_open_positions_by_symbol(symbol)
@@ -358,12 +1112,22 @@ impl PositionManager {
         
         for entry in self.closed_positions.iter() {
             let position = entry.value();
-            if position.realized_pnl > 0.0 {
+            let pnl = self.converter.to_reporting(&position.symbol, position.realized_pnl);
+            if pnl > 0.0 {
                 stats.winning_positions += 1;
-                wins.push(position.realized_pnl);
-            } else if position.realized_pnl < 0.0 {
+                wins.push(pnl);
+            } else if pnl < 0.0 {
                 stats.losing_positions += 1;
-                losses.push(position.realized_pnl.abs());
+                losses.push(pnl.abs());
+            }
+            
+            if let Some(reason) = position.exit_reason {
+                let by_reason = stats.exits_by_reason.entry(reason).or_default();
+                by_reason.count += 1;
+                by_reason.total_pnl += pnl;
+                if pnl > 0.0 {
+                    by_reason.winning += 1;
+                }
             }
         }
         
@@ -418,23 +1182,91 @@ impl PositionManager {
         stats
     }
     
+    /// Copy of every position and running total
+    pub fn snapshot(&self) -> PositionBook {
+        let mut symbols: Vec<Symbol> = self.positions_by_symbol.iter().map(|e| e.key().clone()).collect();
+        symbols.sort_by(|a, b| a.0.cmp(&b.0));
+        let positions = symbols
+            .iter()
+            .filter_map(|symbol| self.positions_by_symbol.get(symbol).map(|ids| ids.clone()))
+            .flatten()
+            .filter_map(|id| self.positions.get(&id).map(|p| p.clone()))
+            .collect();
+        
+        let mut pending_exits: Vec<(String, ExitReason)> = self.pending_exits.iter().map(|e| (e.key().clone(), *e.value())).collect();
+        pending_exits.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut unrealized_cents: Vec<(Symbol, i64)> = self.unrealized_by_symbol.iter().map(|e| (e.key().clone(), *e.value())).collect();
+        unrealized_cents.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        
+        PositionBook {
+            positions,
+            pending_exits,
+            opened: self.position_counter.load(Ordering::Relaxed),
+            realized_cents: self.total_realized_pnl.load(Ordering::Relaxed),
+            unrealized_cents,
+            commission_cents: self.total_commission.load(Ordering::Relaxed),
+            slippage_cents: self.total_slippage.load(Ordering::Relaxed),
+            ids_issued: self.ids.as_ref().map_or(0, |ids| ids.issued()),
+        }
+    }
+    
+    /// Replace every position and total with those of a snapshot. Nothing is
+    /// published for positions that were already closed when it was taken.
+    pub fn restore(&self, book: &PositionBook) {
+        self.reset();
+        for position in &book.positions {
+            let id = position.id.clone();
+            self.positions.insert(id.clone(), position.clone());
+            self.positions_by_symbol.entry(position.symbol.clone()).or_default().push(id.clone());
+            self.positions_by_direction.entry((position.symbol.clone(), position.side)).or_default().push(id.clone());
+            if position.status == PositionStatus::Closed {
+                self.closed_positions.insert(id, position.clone());
+            } else {
+                self.open_by_symbol.entry(position.symbol.clone()).or_default().push(id.clone());
+                self.open_positions.insert(id, position.clone());
+            }
+        }
+        for (id, reason) in &book.pending_exits {
+            self.pending_exits.insert(id.clone(), *reason);
+        }
+        for (symbol, cents) in &book.unrealized_cents {
+            self.unrealized_by_symbol.insert(symbol.clone(), *cents);
+        }
+        
+        self.attribution.rebuild(&book.positions, |symbol, amount| self.to_cents(symbol, amount));
+        self.position_counter.store(book.opened, Ordering::Relaxed);
+        self.total_realized_pnl.store(book.realized_cents, Ordering::Relaxed);
+        self.total_unrealized_pnl.store(book.unrealized_cents.iter().map(|(_, c)| c).sum(), Ordering::Relaxed);
+        self.total_commission.store(book.commission_cents, Ordering::Relaxed);
+        self.total_slippage.store(book.slippage_cents, Ordering::Relaxed);
+        if let Some(ids) = &self.ids {
+            ids.set_issued(book.ids_issued);
+        }
+    }
+    
     /// Reset all positions (for testing/reset)
     pub fn reset(&self) {
         self.positions.clear();
         self.positions_by_symbol.clear();
+        self.positions_by_direction.clear();
         self.open_positions.clear();
+        self.open_by_symbol.clear();
+        self.unrealized_by_symbol.clear();
         self.closed_positions.clear();
+        self.pending_exits.clear();
         self.position_counter.store(0, Ordering::Relaxed);
         self.total_realized_pnl.store(0, Ordering::Relaxed);
         self.total_unrealized_pnl.store(0, Ordering::Relaxed);
         self.total_commission.store(0, Ordering::Relaxed);
         self.total_slippage.store(0, Ordering::Relaxed);
+        self.attribution.reset();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::paper_trading::clock::SimulatedClock;
     
     #[test]
     fn test_position_lifecycle() {
@@ -459,14 +1291,208 @@ mod tests {
         // Check unrealized P&L
         let position = manager.get_position(&id).unwrap();
         assert!(position.unrealized_pnl > 0.0);
+        assert_eq!(position.max_favorable_excursion, 1000.0);
+        
+        // Adverse move is tracked without losing the favorable peak
+        prices.insert(Symbol::new("BTC-USD"), 49500.0);
+        manager.update_prices(&prices);
+        let position = manager.get_position(&id).unwrap();
+        assert_eq!(position.max_adverse_excursion, -500.0);
+        assert_eq!(position.max_favorable_excursion, 1000.0);
         
         // Close position
-        let pnl = manager.close_position(&id, 51000.0, 10.0, 5.0).unwrap();
+        let pnl = manager.close_position(&id, 51000.0, 10.0, 5.0, ExitReason::Signal).unwrap();
         assert!(pnl > 0.0);
         
         // Check statistics
         let stats = manager.get_statistics();
         assert_eq!(stats.winning_positions, 1);
         assert_eq!(stats.win_rate, 100.0);
+        assert_eq!(stats.exits_by_reason[&ExitReason::Signal].count, 1);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_position_exit_levels() {
+        let manager = PositionManager::new();
+        let symbol = Symbol::new("BTC-USD");
+        
+        let id = manager.open_position(
+            symbol.clone(),
+            Exchange::Binance,
+            Side::Buy,
+            1.0,
+            50000.0,
+            0.0,
+            0.0,
+        ).unwrap();
+        
+        // Levels on the wrong side of a long are rejected
+        assert!(manager.modify_position_exits(&id, Some(52000.0), Some(48000.0)).is_err());
+        manager.modify_position_exits(&id, Some(49000.0), Some(52000.0)).unwrap();
+        assert_eq!(manager.get_position(&id).unwrap().stop_loss, Some(49000.0));
+        
+        let prices = DashMap::new();
+        prices.insert(symbol.clone(), 50500.0);
+        assert!(manager.check_exits(&prices).is_empty());
+        
+        // Crossing the stop triggers exactly once
+        prices.insert(symbol.clone(), 48900.0);
+        let exits = manager.check_exits(&prices);
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].reason, ExitReason::StopLoss);
+        assert!(manager.check_exits(&prices).is_empty());
+        
+        assert_eq!(manager.pending_exit_reason(&id), Some(ExitReason::StopLoss));
+        manager.close_position(&id, 48900.0, 0.0, 0.0, ExitReason::StopLoss).unwrap();
+        assert_eq!(manager.get_position(&id).unwrap().exit_reason, Some(ExitReason::StopLoss));
+        assert!(manager.modify_position_exits(&id, None, None).is_err());
+    }
+    
+    #[test]
+    fn test_capital_at_risk() {
+        let manager = PositionManager::new();
+        let btc = manager.open_position(Symbol::new("BTC-USD"), Exchange::Binance, Side::Buy, 0.5, 50000.0, 0.0, 0.0).unwrap();
+        let eth = manager.open_position(Symbol::new("ETH-USD"), Exchange::Binance, Side::Sell, 2.0, 3000.0, 0.0, 0.0).unwrap();
+        manager.modify_position_exits(&btc, Some(48000.0), None).unwrap();
+        
+        let prices = DashMap::new();
+        prices.insert(Symbol::new("BTC-USD"), 51000.0);
+        prices.insert(Symbol::new("ETH-USD"), 2900.0);
+        // 0.5 * 3000 to the BTC stop, and all of the stopless ETH short
+        assert!((manager.capital_at_risk(&prices) - (1500.0 + 5800.0)).abs() < 1e-9);
+        
+        // Risk runs from the current price, even when the stop is past entry
+        manager.modify_position_exits(&eth, Some(2950.0), None).unwrap();
+        assert!((manager.capital_at_risk(&prices) - (1500.0 + 100.0)).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_time_stop() {
+        let manager = PositionManager::new();
+        let symbol = Symbol::new("ETH-USD");
+        
+        let id = manager.open_position(
+            symbol.clone(),
+            Exchange::Binance,
+            Side::Sell,
+            2.0,
+            3000.0,
+            0.0,
+            0.0,
+        ).unwrap();
+        
+        let prices = DashMap::new();
+        prices.insert(symbol, 3000.0);
+        
+        manager.set_max_hold(&id, Some(Duration::from_secs(3600))).unwrap();
+        assert!(manager.check_exits(&prices).is_empty());
+        
+        manager.set_max_hold(&id, Some(Duration::ZERO)).unwrap();
+        let exits = manager.check_exits(&prices);
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].reason, ExitReason::TimeStop);
+        
+        // On a simulated clock the stop fires only once time is advanced past it
+        let clock = Arc::new(SimulatedClock::new(0));
+        let manager = PositionManager::new().with_clock(clock.clone());
+        let id = manager.open_position(Symbol::new("ETH-USD"), Exchange::Binance, Side::Buy, 1.0, 3000.0, 0.0, 0.0).unwrap();
+        manager.set_max_hold(&id, Some(Duration::from_secs(3600))).unwrap();
+        clock.advance(Duration::from_secs(3599));
+        assert!(manager.check_exits(&prices).is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(manager.check_exits(&prices)[0].reason, ExitReason::TimeStop);
+    }
+    
+    #[test]
+    fn test_scale_in_and_out() {
+        let manager = PositionManager::new();
+        
+        let id = manager.open_position(
+            Symbol::new("BTC-USD"),
+            Exchange::Binance,
+            Side::Buy,
+            1.0,
+            100.0,
+            1.0,
+            0.0,
+        ).unwrap();
+        
+        // Scale in at a higher price moves the average entry
+        manager.add_to_position(&id, 1.0, 110.0, 1.0, 0.0).unwrap();
+        let position = manager.get_position(&id).unwrap();
+        assert_eq!(position.quantity, 2.0);
+        assert_eq!(position.entry_price, 105.0);
+        
+        // Scale out half: (120 - 105) * 1 - 1 exit commission
+        let pnl = manager.partial_close_position(&id, 1.0, 120.0, 1.0, 0.0, ExitReason::Signal).unwrap();
+        assert_eq!(pnl, 14.0);
+        assert_eq!(manager.get_position(&id).unwrap().quantity, 1.0);
+        
+        // Close the rest: (120 - 105) * 1 - 2 entry commissions - 1 exit commission
+        let pnl = manager.close_position(&id, 120.0, 1.0, 0.0, ExitReason::Signal).unwrap();
+        assert_eq!(pnl, 12.0);
+        assert_eq!(manager.get_position(&id).unwrap().realized_pnl, 26.0);
+        assert_eq!(manager.get_statistics().total_realized_pnl, 26.0);
+    }
+    
+    #[test]
+    fn test_positions_by_direction() {
+        let manager = PositionManager::new();
+        let symbol = Symbol::new("BTC-USD");
+        
+        manager.open_position(symbol.clone(), Exchange::Binance, Side::Buy, 2.0, 100.0, 0.0, 0.0).unwrap();
+        let short = manager.open_position(symbol.clone(), Exchange::Binance, Side::Sell, 0.5, 100.0, 0.0, 0.0).unwrap();
+        
+        // Long and short coexist and are looked up independently
+        assert_eq!(manager.get_open_positions_by_direction(&symbol, Side::Buy).len(), 1);
+        let shorts = manager.get_open_positions_by_direction(&symbol, Side::Sell);
+        assert_eq!(shorts.len(), 1);
+        assert_eq!(shorts[0].id, short);
+        assert_eq!(manager.get_net_position(&symbol), 1.5);
+        
+        manager.close_position(&short, 95.0, 0.0, 0.0, ExitReason::Manual).unwrap();
+        assert!(manager.get_open_positions_by_direction(&symbol, Side::Sell).is_empty());
+    }
+    
+    #[test]
+    fn test_cross_quote_pnl() {
+        let converter = Arc::new(CurrencyConverter::new("USD"));
+        converter.update_price(&Symbol::new("BTCUSDT"), 50_000.0);
+        let manager = PositionManager::with_currency_converter(converter);
+        
+        // 10 ETH bought at 0.05 BTC and sold at 0.06 BTC makes 0.1 BTC
+        let id = manager.open_position(Symbol::new("ETHBTC"), Exchange::Binance, Side::Buy, 10.0, 0.05, 0.0, 0.0).unwrap();
+        let pnl = manager.close_position(&id, 0.06, 0.0, 0.0, ExitReason::Signal).unwrap();
+        assert!((pnl - 0.1).abs() < 1e-9);
+        
+        // Totals and averages are reported in USD
+        let stats = manager.get_statistics();
+        assert!((stats.total_realized_pnl - 5_000.0).abs() < 0.01);
+        assert!((stats.avg_win - 5_000.0).abs() < 0.01);
+    }
+    
+    #[test]
+    fn test_symbol_tick_updates_only_its_positions() {
+        let manager = PositionManager::new();
+        let (btc, eth) = (Symbol::new("BTC-USD"), Symbol::new("ETH-USD"));
+        let btc_id = manager.open_position(btc.clone(), Exchange::Binance, Side::Buy, 1.0, 100.0, 0.0, 0.0).unwrap();
+        let eth_id = manager.open_position(eth.clone(), Exchange::Binance, Side::Sell, 2.0, 50.0, 0.0, 0.0).unwrap();
+        
+        manager.update_symbol_price(&btc, 110.0);
+        manager.update_symbol_price(&eth, 45.0);
+        manager.update_symbol_price(&btc, 105.0);
+        assert_eq!(manager.get_position(&btc_id).unwrap().unrealized_pnl, 5.0);
+        assert_eq!(manager.get_position(&eth_id).unwrap().unrealized_pnl, 10.0);
+        assert_eq!(manager.get_statistics().total_unrealized_pnl, 15.0);
+        
+        // Closing drops the position's share of the total
+        manager.close_position(&eth_id, 45.0, 0.0, 0.0, ExitReason::Signal).unwrap();
+        assert_eq!(manager.get_statistics().total_unrealized_pnl, 5.0);
+        
+        manager.modify_position_exits(&btc_id, Some(95.0), None).unwrap();
+        assert!(manager.check_exits_for_symbol(&eth, 1.0).is_empty());
+        let exits = manager.check_exits_for_symbol(&btc, 94.0);
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].reason, ExitReason::StopLoss);
+    }
+}
@@ -0,0 +1,273 @@
+//! Bounded queues for the signal and order event pipelines
+//!
+//! A burst of signals must not grow memory without limit or leave the engine
+//! working through minutes-old signals, so queues have a fixed capacity and a
+//! policy for what happens when they are full.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+/// What a full queue does with a new item
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued item to make room
+    #[default]
+    DropOldest,
+    /// Refuse the new item
+    RejectNew,
+    /// Wait for room. Senders that can't wait, like order events raised
+    /// from synchronous code, refuse the new item instead.
+    Block,
+}
+
+/// Queue size and overflow behaviour
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            policy: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+/// Depth and overflow counters
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct QueueStatistics {
+    pub depth: usize,
+    pub capacity: usize,
+    pub enqueued: u64,
+    pub dropped: u64, // Evicted by DropOldest
+    pub rejected: u64, // Refused when full
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueueError {
+    #[error("queue full ({0} items)")]
+    Full(usize),
+    #[error("queue closed")]
+    Closed,
+}
+
+struct Shared<T> {
+    items: Mutex<VecDeque<T>>,
+    config: QueueConfig,
+    item_ready: Notify,
+    space_ready: Notify,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+    enqueued: AtomicU64,
+    dropped: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl<T> Shared<T> {
+    fn statistics(&self) -> QueueStatistics {
+        QueueStatistics {
+            depth: self.items.lock().len(),
+            capacity: self.config.capacity,
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Push unless full; with `evict` the oldest item makes room
+    fn push(&self, item: T, evict: bool) -> Result<(), T> {
+        if !self.receiver_alive.load(Ordering::Acquire) {
+            return Err(item);
+        }
+        {
+            let mut items = self.items.lock();
+            if items.len() >= self.config.capacity {
+                if !evict {
+                    return Err(item);
+                }
+                items.pop_front();
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            items.push_back(item);
+        }
+        self.enqueued.fetch_add(1, Ordering::Relaxed);
+        self.item_ready.notify_one();
+        Ok(())
+    }
+
+    fn pop(&self) -> Option<T> {
+        let item = self.items.lock().pop_front();
+        if item.is_some() {
+            self.space_ready.notify_one();
+        }
+        item
+    }
+}
+
+/// Create a bounded queue
+pub fn bounded<T>(config: QueueConfig) -> (QueueSender<T>, QueueReceiver<T>) {
+    let shared = Arc::new(Shared {
+        items: Mutex::new(VecDeque::with_capacity(config.capacity.min(1024))),
+        config: QueueConfig {
+            capacity: config.capacity.max(1),
+            ..config
+        },
+        item_ready: Notify::new(),
+        space_ready: Notify::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+        enqueued: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+        rejected: AtomicU64::new(0),
+    });
+    (
+        QueueSender { shared: shared.clone() },
+        QueueReceiver { shared },
+    )
+}
+
+pub struct QueueSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueSender<T> {
+    /// Enqueue without waiting. Under `Block` a full queue refuses the item.
+    pub fn try_send(&self, item: T) -> Result<(), QueueError> {
+        let evict = self.shared.config.policy == OverflowPolicy::DropOldest;
+        self.shared.push(item, evict).map_err(|_| self.refused())
+    }
+
+    /// Enqueue, waiting for room under `Block`
+    pub async fn send(&self, item: T) -> Result<(), QueueError> {
+        if self.shared.config.policy != OverflowPolicy::Block {
+            return self.try_send(item);
+        }
+
+        let mut item = item;
+        loop {
+            let space = self.shared.space_ready.notified();
+            match self.shared.push(item, false) {
+                Ok(()) => return Ok(()),
+                Err(_) if !self.shared.receiver_alive.load(Ordering::Acquire) => return Err(QueueError::Closed),
+                Err(returned) => item = returned,
+            }
+            space.await;
+        }
+    }
+
+    pub fn statistics(&self) -> QueueStatistics {
+        self.shared.statistics()
+    }
+
+    fn refused(&self) -> QueueError {
+        if !self.shared.receiver_alive.load(Ordering::Acquire) {
+            return QueueError::Closed;
+        }
+        self.shared.rejected.fetch_add(1, Ordering::Relaxed);
+        QueueError::Full(self.shared.config.capacity)
+    }
+}
+
+impl<T> Clone for QueueSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for QueueSender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.item_ready.notify_one();
+        }
+    }
+}
+
+pub struct QueueReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> QueueReceiver<T> {
+    /// Next item, or `None` once every sender is gone and the queue is empty.
+    /// Cancel safe, so it can be used in `tokio::select!`.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            let ready = self.shared.item_ready.notified();
+            if let Some(item) = self.shared.pop() {
+                return Some(item);
+            }
+            if self.shared.senders.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            ready.await;
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.shared.pop()
+    }
+
+    pub fn statistics(&self) -> QueueStatistics {
+        self.shared.statistics()
+    }
+}
+
+impl<T> Drop for QueueReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+        self.shared.items.lock().clear();
+        self.shared.space_ready.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_overflow_policies() {
+        let config = |policy| QueueConfig { capacity: 2, policy };
+
+        let (tx, mut rx) = bounded(config(OverflowPolicy::DropOldest));
+        for i in 0..3 {
+            tx.send(i).await.unwrap();
+        }
+        assert_eq!((rx.recv().await, rx.recv().await), (Some(1), Some(2)));
+        assert_eq!(tx.statistics().dropped, 1);
+
+        let (tx, mut rx) = bounded(config(OverflowPolicy::RejectNew));
+        tx.send(0).await.unwrap();
+        tx.send(1).await.unwrap();
+        assert_eq!(tx.send(2).await, Err(QueueError::Full(2)));
+        assert_eq!(rx.recv().await, Some(0));
+        assert_eq!(tx.statistics().rejected, 1);
+
+        // A blocked sender resumes once the receiver makes room
+        let (tx, mut rx) = bounded(config(OverflowPolicy::Block));
+        tx.send(0).await.unwrap();
+        tx.send(1).await.unwrap();
+        assert!(tx.try_send(2).is_err());
+        let sender = tokio::spawn(async move {
+            tx.send(3).await.unwrap();
+            tx.statistics()
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(rx.recv().await, Some(0));
+        let stats = sender.await.unwrap();
+        assert_eq!((stats.depth, stats.enqueued), (2, 3));
+        assert_eq!((rx.recv().await, rx.recv().await), (Some(1), Some(3)));
+        assert_eq!(rx.recv().await, None); // Sender dropped
+    }
+}
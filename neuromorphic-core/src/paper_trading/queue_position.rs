@@ -0,0 +1,171 @@
+//! Queue position of resting limit orders
+//!
+//! A real limit order joins the back of the queue at its price, behind the
+//! size already displayed there, and is only filled once the trades at that
+//! price have worked through the queue ahead of it. Without this model a
+//! resting order fills as soon as the price touches its level, which flatters
+//! passive strategies: they get every fill at the best price and none of the
+//! adverse selection.
+//!
+//! With it, an order that starts resting is placed behind the size displayed
+//! at its level in the latest book, and volume traded at its level first
+//! consumes the queue ahead, then fills the order. Displayed size shrinking
+//! below the queue ahead is taken as cancellations ahead of the order. A price
+//! trading through the level fills the order whole, as before. Orders placed
+//! while no book is known, or restored from a snapshot, have no queue ahead.
+
+use super::order_manager::Order;
+use crate::exchanges::{Side, Symbol};
+use dashmap::DashMap;
+use std::collections::HashMap;
+
+/// Where a resting order stands at its level
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct QueueSpot {
+    side: Side,
+    price: f64,
+    ahead: f64, // Displayed size still in front of the order
+    traded_past: f64, // Volume traded at the level behind the queue ahead, not yet filled
+}
+
+/// Displayed size by price level
+#[derive(Clone, Debug, Default)]
+struct DisplayedBook {
+    bids: Vec<(f64, f64)>,
+    asks: Vec<(f64, f64)>,
+}
+
+/// Estimated queue ahead of each resting limit order
+#[derive(Default)]
+pub struct QueueModel {
+    books: DashMap<Symbol, DisplayedBook>, // Latest displayed size per symbol
+    spots: DashMap<Symbol, HashMap<String, QueueSpot>>, // By symbol, then order ID
+}
+
+impl QueueModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the displayed book of `symbol`, levels as (price, size)
+    pub fn record_book(&self, symbol: &Symbol, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        let book = DisplayedBook { bids: bids.to_vec(), asks: asks.to_vec() };
+        if let Some(mut spots) = self.spots.get_mut(symbol) {
+            for spot in spots.values_mut() {
+                spot.ahead = spot.ahead.min(displayed(&book, spot.side, spot.price));
+            }
+        }
+        self.books.insert(symbol.clone(), book);
+    }
+
+    /// Record `quantity` traded at `price`; it works through the queue ahead
+    /// of the orders resting at that price
+    pub fn record_trade(&self, symbol: &Symbol, price: f64, quantity: f64) {
+        if !quantity.is_finite() || quantity <= 0.0 {
+            return;
+        }
+        let Some(mut spots) = self.spots.get_mut(symbol) else { return };
+        for spot in spots.values_mut() {
+            if through(spot, price) {
+                spot.ahead = 0.0;
+                spot.traded_past += quantity;
+            } else if at_level(spot.price, price) {
+                let consumed = quantity.min(spot.ahead);
+                spot.ahead -= consumed;
+                spot.traded_past += quantity - consumed;
+            }
+        }
+    }
+
+    /// Place an order that starts resting behind the size displayed at its price
+    pub fn join(&self, order: &Order) {
+        let Some(price) = order.price else { return };
+        let ahead = self.books.get(&order.symbol).map_or(0.0, |book| displayed(&book, order.side, price));
+        let spot = QueueSpot { side: order.side, price, ahead, traded_past: 0.0 };
+        self.spots.entry(order.symbol.clone()).or_default().insert(order.id.clone(), spot);
+    }
+
+    /// Quantity of a resting order that may fill at `market_price`; None when
+    /// it is not queued or the price traded through its level
+    pub fn fillable(&self, order: &Order, market_price: f64) -> Option<f64> {
+        let spots = self.spots.get(&order.symbol)?;
+        let spot = spots.get(&order.id)?;
+        (!through(spot, market_price)).then_some(spot.traded_past)
+    }
+
+    /// Take a fill off the volume traded past the order
+    pub fn record_fill(&self, order: &Order, quantity: f64) {
+        if let Some(spot) = self.spots.get_mut(&order.symbol).as_mut().and_then(|spots| spots.get_mut(&order.id)) {
+            spot.traded_past = (spot.traded_past - quantity).max(0.0);
+        }
+    }
+
+    /// Forget orders no longer resting
+    pub fn retain(&self, mut resting: impl FnMut(&str) -> bool) {
+        self.spots.retain(|_, spots| {
+            spots.retain(|id, _| resting(id));
+            !spots.is_empty()
+        });
+    }
+
+    /// Queue still ahead of an order, if it is queued
+    pub fn ahead(&self, order: &Order) -> Option<f64> {
+        self.spots.get(&order.symbol)?.get(&order.id).map(|spot| spot.ahead)
+    }
+}
+
+/// Size displayed at `price` on the side a `side` order rests on
+fn displayed(book: &DisplayedBook, side: Side, price: f64) -> f64 {
+    let levels = if side == Side::Buy { &book.bids } else { &book.asks };
+    levels.iter().find(|(level, _)| at_level(*level, price)).map_or(0.0, |(_, size)| *size)
+}
+
+/// Whether `price` is better than the spot's level for the other side
+fn through(spot: &QueueSpot, price: f64) -> bool {
+    !at_level(spot.price, price) && if spot.side == Side::Buy { price < spot.price } else { price > spot.price }
+}
+
+fn at_level(level: f64, price: f64) -> bool {
+    (level - price).abs() <= level.abs() * 1e-9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::{OrderManager, OrderStatus, SlippageModel};
+
+    #[test]
+    fn test_resting_order_waits_for_the_queue_ahead() {
+        let manager = OrderManager::new(0.1, SlippageModel::Fixed(0.0)).with_queue_model();
+        let symbol = Symbol::new("BTC-USD");
+        let prices = DashMap::new();
+        prices.insert(symbol.clone(), 100.5);
+        manager.record_book(&symbol, &[(100.0, 5.0), (99.0, 8.0)], &[(101.0, 3.0)]);
+
+        let order_id = manager.submit_order(Order::limit(symbol.clone(), Exchange::Binance, Side::Buy, 2.0, 100.0)).unwrap();
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+        let order = manager.get_order(&order_id).unwrap();
+        assert_eq!(manager.queue_model().unwrap().ahead(&order), Some(5.0));
+
+        // Touching the level fills nothing until the queue ahead has traded
+        prices.insert(symbol.clone(), 100.0);
+        manager.record_trade(&symbol, 100.0, 3.0);
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+        // One of the two left ahead is cancelled, then 1.5 trade: 0.5 past the order
+        manager.record_book(&symbol, &[(100.0, 1.0)], &[(101.0, 3.0)]);
+        assert_eq!(manager.queue_model().unwrap().ahead(&order), Some(1.0));
+        manager.record_trade(&symbol, 100.0, 1.5);
+        assert_eq!(manager.process_orders(&prices).unwrap(), vec![order_id.clone()]);
+        let order = manager.get_order(&order_id).unwrap();
+        assert_eq!((order.status, order.filled_quantity), (OrderStatus::PartiallyFilled, 0.5));
+        assert!(manager.process_orders(&prices).unwrap().is_empty());
+
+        // Trading through the level fills the rest
+        prices.insert(symbol.clone(), 99.5);
+        manager.process_orders(&prices).unwrap();
+        let order = manager.get_order(&order_id).unwrap();
+        assert_eq!(manager.queue_model().unwrap().ahead(&order), None);
+        assert_eq!((order.status, order.filled_quantity, order.avg_fill_price), (OrderStatus::Filled, 2.0, 100.0));
+    }
+}
@@ -0,0 +1,219 @@
+//! Target-weight rebalancing
+//!
+//! A passive book, say 60% SPY and 40% TLT, is what an active strategy has
+//! to beat. The rebalancer holds an account to such an allocation: each
+//! symbol's target is a share of equity, the rest is cash. A symbol is only
+//! traded once its weight drifts more than `tolerance` from the target, and
+//! then straight back to it, so a rebalance sends as few orders as it can.
+//! Orders worth less than `min_notional` are skipped, and orders adding
+//! exposure must pass the risk checks like any other. Sells go first, so the
+//! buys can use the cash they free.
+//!
+//! Weights are of the net position: shorts count negative. Positions in
+//! symbols without a target are left alone. A rebalance runs on demand, or
+//! every `interval_secs` of engine time once all targets have a price.
+//! Positions it opens get exit levels like any other, so a buy-and-hold
+//! benchmark account runs with stop-loss and take-profit disabled.
+
+use super::{Order, OrderManager, PositionManager, RiskCheckResult, RiskManager};
+use crate::exchanges::{Exchange, Side, Symbol};
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, warn};
+
+/// Target allocation and when to trade towards it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RebalanceConfig {
+    pub targets: BTreeMap<String, f64>, // Share of equity by symbol; the rest stays in cash
+    pub tolerance: f64, // Drift in weight tolerated before a symbol is traded
+    pub min_notional: f64, // Smallest order, in the reporting currency
+    pub interval_secs: Option<u64>, // Rebalance on this schedule too, not only on demand
+    pub exchange: Exchange, // For symbols not held yet
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            targets: BTreeMap::new(),
+            tolerance: 0.02,
+            min_notional: 10.0,
+            interval_secs: None,
+            exchange: Exchange::NYSE,
+        }
+    }
+}
+
+/// A symbol's net holding, valued in the reporting currency
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Holding {
+    pub quantity: f64, // Negative when net short
+    pub unit_value: Option<f64>, // Of one contract at the current price; None without a price
+}
+
+/// One order of a rebalance
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RebalanceOrder {
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub notional: f64,
+    pub current_weight: f64,
+    pub target_weight: f64,
+    pub order_id: Option<String>, // Once submitted
+}
+
+/// What a rebalance did, or would do before it is submitted
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RebalancePlan {
+    pub equity: f64,
+    pub orders: Vec<RebalanceOrder>, // Sells first
+    pub skipped: Vec<(String, String)>, // Symbol and why it was not traded
+}
+
+impl RebalancePlan {
+    /// Orders that bring the holdings back to the targets, for symbols outside
+    /// the tolerance band
+    pub fn new(config: &RebalanceConfig, holdings: &HashMap<Symbol, Holding>, equity: f64) -> Self {
+        let mut plan = Self { equity, ..Default::default() };
+        if equity <= 0.0 {
+            plan.skipped = config.targets.keys().map(|symbol| (symbol.clone(), "no equity".to_string())).collect();
+            return plan;
+        }
+        for (symbol, &target_weight) in &config.targets {
+            let holding = holdings.get(&Symbol::new(symbol)).copied().unwrap_or_default();
+            let Some(unit_value) = holding.unit_value.filter(|value| *value > 0.0) else {
+                plan.skipped.push((symbol.clone(), "no price".to_string()));
+                continue;
+            };
+            let current_weight = holding.quantity * unit_value / equity;
+            if (current_weight - target_weight).abs() <= config.tolerance {
+                continue;
+            }
+            let delta = target_weight * equity / unit_value - holding.quantity;
+            let notional = delta.abs() * unit_value;
+            if notional < config.min_notional {
+                plan.skipped.push((symbol.clone(), format!("{:.2} is under the minimum notional", notional)));
+                continue;
+            }
+            plan.orders.push(RebalanceOrder {
+                symbol: symbol.clone(),
+                side: if delta > 0.0 { Side::Buy } else { Side::Sell },
+                quantity: delta.abs(),
+                notional,
+                current_weight,
+                target_weight,
+                order_id: None,
+            });
+        }
+        plan.orders.sort_by_key(|order| order.side == Side::Buy);
+        plan
+    }
+}
+
+/// Plan a rebalance of the account's positions and submit its orders as
+/// market orders; orders rejected by the risk checks or the order manager are
+/// moved to `skipped`
+pub fn rebalance(
+    position_manager: &PositionManager,
+    order_manager: &OrderManager,
+    risk_manager: &RiskManager,
+    current_prices: &DashMap<Symbol, f64>,
+    equity: f64,
+    config: &RebalanceConfig,
+) -> Result<RebalancePlan> {
+    if config.targets.is_empty() {
+        bail!("No rebalance targets");
+    }
+    let converter = position_manager.currency_converter();
+    let holdings: HashMap<Symbol, Holding> = config
+        .targets
+        .keys()
+        .map(Symbol::new)
+        .map(|symbol| {
+            let quantity = position_manager.get_net_position(&symbol);
+            let multiplier = order_manager.instruments().multiplier(&symbol);
+            let unit_value = current_prices.get(&symbol).map(|price| converter.to_reporting(&symbol, *price * multiplier));
+            (symbol, Holding { quantity, unit_value })
+        })
+        .collect();
+
+    let mut plan = RebalancePlan::new(config, &holdings, equity);
+    let mut submitted = Vec::new();
+    for mut planned in std::mem::take(&mut plan.orders) {
+        let symbol = Symbol::new(&planned.symbol);
+        let Some(price) = current_prices.get(&symbol).map(|price| *price) else { continue };
+        let open = position_manager.get_open_positions_by_symbol(&symbol);
+        let exchange = open.first().map_or(config.exchange, |position| position.exchange);
+
+        // Only orders adding exposure are risk checked
+        if planned.target_weight.abs() > planned.current_weight.abs() {
+            let multiplier = order_manager.instruments().multiplier(&symbol);
+            if let RiskCheckResult::Rejected { reason } = risk_manager.check_order(&symbol, planned.side, planned.quantity * multiplier, price, equity) {
+                warn!(symbol = %symbol, reason = %reason, "Rebalance order rejected by risk check");
+                plan.skipped.push((planned.symbol, reason));
+                continue;
+            }
+        }
+
+        // Adding to a holding tops up its largest position rather than opening another
+        let mut order = Order::market(symbol.clone(), exchange, planned.side, planned.quantity);
+        order.position_id = open
+            .iter()
+            .filter(|position| position.side == planned.side)
+            .max_by(|a, b| a.quantity.total_cmp(&b.quantity))
+            .map(|position| position.id.clone());
+        match order_manager.submit_order(order) {
+            Ok(order_id) => {
+                risk_manager.record_order(&symbol);
+                planned.order_id = Some(order_id);
+                submitted.push(planned);
+            }
+            Err(e) => plan.skipped.push((planned.symbol, format!("{:#}", e))),
+        }
+    }
+    plan.orders = submitted;
+    info!(orders = plan.orders.len(), skipped = plan.skipped.len(), equity, "Rebalanced");
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_symbols_outside_the_band_are_traded() {
+        let config = RebalanceConfig {
+            targets: BTreeMap::from([
+                ("SPY".to_string(), 0.6),
+                ("TLT".to_string(), 0.3),
+                ("GLD".to_string(), 0.1),
+                ("IWM".to_string(), 0.0),
+                ("QQQ".to_string(), 0.05),
+            ]),
+            tolerance: 0.02,
+            min_notional: 100.0,
+            ..Default::default()
+        };
+        let holding = |quantity, value| Holding { quantity, unit_value: Some(value) };
+        let holdings = HashMap::from([
+            (Symbol::new("SPY"), holding(130.0, 500.0)), // 65%: sell 10
+            (Symbol::new("TLT"), holding(310.0, 100.0)), // 31%: within the band
+            (Symbol::new("IWM"), holding(-20.0, 200.0)), // -4% short: buy it back
+            (Symbol::new("QQQ"), Holding { quantity: 0.0, unit_value: None }),
+        ]);
+
+        let plan = RebalancePlan::new(&config, &holdings, 100_000.0);
+        let orders: Vec<(&str, Side, f64)> = plan.orders.iter().map(|o| (o.symbol.as_str(), o.side, o.quantity)).collect();
+        // GLD isn't held but has no price either; sells come first
+        assert_eq!(orders, [("SPY", Side::Sell, 10.0), ("IWM", Side::Buy, 20.0)]);
+        assert_eq!(plan.skipped.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>(), ["GLD", "QQQ"]);
+
+        let holdings = HashMap::from([(Symbol::new("SPY"), holding(119.9, 500.0))]);
+        let spy = RebalanceConfig { targets: BTreeMap::from([("SPY".to_string(), 0.6)]), tolerance: 0.0, ..config };
+        let plan = RebalancePlan::new(&spy, &holdings, 100_000.0);
+        assert!(plan.orders.is_empty() && plan.skipped[0].1.contains("minimum notional"));
+    }
+}
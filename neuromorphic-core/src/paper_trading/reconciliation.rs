@@ -0,0 +1,449 @@
+//! Reconciliation of local order and position state against an execution venue
+//!
+//! With external execution the exchange is the source of truth. The reconciler
+//! periodically pulls open orders and balances from the venue, reports where
+//! the local OrderManager / PositionManager disagree, and can correct them.
+//!
+//! Spot balances include whatever the account held before trading, so the
+//! first pass records a baseline and later passes compare changes since then.
+
+use super::order_manager::{OrderManager, OrderType};
+use super::position_manager::{ExitReason, PositionManager};
+use super::PaperTradingEngine;
+use crate::exchanges::{Balance, ExchangeConnector, Side, Symbol, UniversalOrder};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Quote assets recognised at the end of symbols without a separator, e.g. "BTCUSDT"
+const QUOTE_ASSETS: &[&str] = &["USDT", "USDC", "BUSD", "FDUSD", "TUSD", "USD", "EUR", "BTC", "ETH", "BNB"];
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationConfig {
+    pub enabled: bool,
+    pub interval: Duration,
+    pub auto_correct: bool, // Fix discrepancies instead of only reporting them
+    pub grace_period: Duration, // Orders younger than this may not have reached the venue yet
+    pub tolerance_pct: f64, // Quantity differences within this % are ignored (fees paid in the base asset)
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(30),
+            auto_correct: false,
+            grace_period: Duration::from_secs(10),
+            tolerance_pct: 0.5,
+        }
+    }
+}
+
+/// Difference between local and venue state
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Discrepancy {
+    /// Open at the venue but not placed by any tracked account
+    UnknownVenueOrder { venue_order_id: String, symbol: Symbol },
+    /// Active locally but never seen at the venue
+    MissingVenueOrder { account: String, order_id: String, symbol: Symbol },
+    /// Net holdings changed differently at the venue than locally
+    PositionMismatch { asset: String, local_quantity: f64, venue_quantity: f64 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReconciliationEvent {
+    pub timestamp: DateTime<Utc>,
+    pub discrepancy: Discrepancy,
+    pub corrected: bool,
+}
+
+/// Venue state the reconciler compares against
+#[async_trait]
+pub trait VenueState: Send + Sync {
+    async fn open_orders(&self) -> Result<Vec<UniversalOrder>>;
+
+    /// Recent orders for a symbol, including filled and cancelled ones
+    async fn order_history(&self, symbol: &Symbol) -> Result<Vec<UniversalOrder>>;
+
+    async fn balances(&self) -> Result<Vec<Balance>>;
+
+    async fn cancel_venue_order(&self, venue_order_id: &str) -> Result<()>;
+
+    async fn last_price(&self, symbol: &Symbol) -> Result<f64>;
+}
+
+#[async_trait]
+impl<C: ExchangeConnector> VenueState for C {
+    async fn open_orders(&self) -> Result<Vec<UniversalOrder>> {
+        Ok(self.get_open_orders(None).await?)
+    }
+
+    async fn order_history(&self, symbol: &Symbol) -> Result<Vec<UniversalOrder>> {
+        Ok(self.get_order_history(Some(symbol), Some(100)).await?)
+    }
+
+    async fn balances(&self) -> Result<Vec<Balance>> {
+        Ok(self.get_balances().await?)
+    }
+
+    async fn cancel_venue_order(&self, venue_order_id: &str) -> Result<()> {
+        Ok(self.cancel_order(venue_order_id).await?)
+    }
+
+    async fn last_price(&self, symbol: &Symbol) -> Result<f64> {
+        Ok(self.get_ticker(symbol).await?.price)
+    }
+}
+
+/// Base asset of a spot symbol, e.g. "BTC-USDT" or "BTCUSDT" -> "BTC"
+pub fn base_asset(symbol: &Symbol) -> String {
+    let symbol = symbol.as_str().to_uppercase();
+    if let Some((base, _)) = symbol.split_once(['-', '/', '_']) {
+        return base.to_string();
+    }
+    QUOTE_ASSETS
+        .iter()
+        .find_map(|quote| symbol.strip_suffix(quote).filter(|base| !base.is_empty()))
+        .unwrap_or(&symbol)
+        .to_string()
+}
+
+struct TrackedAccount {
+    id: String,
+    order_manager: Arc<OrderManager>,
+    position_manager: Arc<PositionManager>,
+}
+
+/// Periodic comparison of tracked accounts against one venue account
+pub struct Reconciler {
+    venue: Arc<dyn VenueState>,
+    config: ReconciliationConfig,
+    accounts: Vec<TrackedAccount>,
+    baseline: parking_lot::Mutex<Option<HashMap<String, (f64, f64)>>>, // Asset -> (venue, local) at the first pass
+    event_sender: broadcast::Sender<ReconciliationEvent>,
+}
+
+impl Reconciler {
+    pub fn new(venue: Arc<dyn VenueState>, config: ReconciliationConfig) -> Self {
+        let (event_sender, _) = broadcast::channel(1000);
+
+        Self {
+            venue,
+            config,
+            accounts: Vec::new(),
+            baseline: parking_lot::Mutex::new(None),
+            event_sender,
+        }
+    }
+
+    /// Include an account whose orders go to this venue
+    pub fn track(&mut self, account_id: impl Into<String>, engine: &PaperTradingEngine) {
+        self.accounts.push(TrackedAccount {
+            id: account_id.into(),
+            order_manager: engine.order_manager().clone(),
+            position_manager: engine.position_manager().clone(),
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ReconciliationEvent> {
+        self.event_sender.subscribe()
+    }
+
+    /// Reconcile every `interval` until the returned task is aborted
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.reconcile().await {
+                    warn!(error = %e, "Reconciliation failed");
+                }
+            }
+        })
+    }
+
+    /// Run one pass, returning the discrepancies found
+    pub async fn reconcile(&self) -> Result<Vec<ReconciliationEvent>> {
+        let mut events = Vec::new();
+        let venue_open = self.venue.open_orders().await?;
+
+        // Venue orders carry the local order ID as their client order ID
+        for order in &venue_open {
+            let known = order.client_order_id.as_ref().is_some_and(|id| {
+                self.accounts.iter().any(|account| account.order_manager.get_order(id).is_some())
+            });
+            if known {
+                continue;
+            }
+            let corrected = self.config.auto_correct && self.venue.cancel_venue_order(&order.id).await.is_ok();
+            events.push(self.event(Discrepancy::UnknownVenueOrder {
+                venue_order_id: order.id.clone(),
+                symbol: order.symbol.clone(),
+            }, corrected));
+        }
+
+        let venue_client_ids: HashSet<&str> = venue_open.iter().filter_map(|o| o.client_order_id.as_deref()).collect();
+        let mut history: HashMap<Symbol, HashSet<String>> = HashMap::new();
+        for account in &self.accounts {
+            for order in account.order_manager.get_active_orders() {
+                // Stops are held locally until triggered
+                let held_locally = matches!(order.order_type, OrderType::StopLoss | OrderType::TakeProfit | OrderType::StopLimit);
+                if held_locally || venue_client_ids.contains(order.id.as_str()) || !self.past_grace_period(order.created_time) {
+                    continue;
+                }
+
+                // Filled or cancelled at the venue since the last sync: not missing
+                if !history.contains_key(&order.symbol) {
+                    let ids = self.venue
+                        .order_history(&order.symbol)
+                        .await?
+                        .into_iter()
+                        .filter_map(|o| o.client_order_id)
+                        .collect();
+                    history.insert(order.symbol.clone(), ids);
+                }
+                if history[&order.symbol].contains(&order.id) {
+                    continue;
+                }
+
+                let corrected = self.config.auto_correct
+                    && account.order_manager.reject_order(&order.id, "Not found at venue").is_ok();
+                events.push(self.event(Discrepancy::MissingVenueOrder {
+                    account: account.id.clone(),
+                    order_id: order.id,
+                    symbol: order.symbol,
+                }, corrected));
+            }
+        }
+
+        events.extend(self.reconcile_positions().await?);
+        Ok(events)
+    }
+
+    /// Compare the change in venue balances with the change in local net positions
+    async fn reconcile_positions(&self) -> Result<Vec<ReconciliationEvent>> {
+        let venue: HashMap<String, f64> = self.venue
+            .balances()
+            .await?
+            .into_iter()
+            .map(|b| (b.asset.to_uppercase(), b.total))
+            .collect();
+        let local = self.local_holdings();
+
+        let baseline = {
+            let mut baseline = self.baseline.lock();
+            match baseline.as_ref() {
+                Some(baseline) => baseline.clone(),
+                None => {
+                    let assets = venue.keys().chain(local.keys()).collect::<HashSet<_>>();
+                    *baseline = Some(
+                        assets
+                            .into_iter()
+                            .map(|asset| {
+                                let quantities = (venue.get(asset).copied().unwrap_or(0.0), local.get(asset).copied().unwrap_or(0.0));
+                                (asset.clone(), quantities)
+                            })
+                            .collect(),
+                    );
+                    info!(assets = venue.len(), "Reconciliation baseline recorded");
+                    return Ok(Vec::new());
+                }
+            }
+        };
+
+        let mut mismatches = Vec::new();
+        for asset in baseline.keys().chain(local.keys()).collect::<HashSet<_>>() {
+            let (venue_start, local_start) = baseline.get(asset).copied().unwrap_or((0.0, 0.0));
+            let venue_quantity = venue.get(asset).copied().unwrap_or(0.0) - venue_start;
+            let local_quantity = local.get(asset).copied().unwrap_or(0.0) - local_start;
+
+            let tolerance = venue_quantity.abs().max(local_quantity.abs()) * self.config.tolerance_pct / 100.0 + 1e-9;
+            if (venue_quantity - local_quantity).abs() > tolerance {
+                mismatches.push((asset.clone(), local_quantity, venue_quantity));
+            }
+        }
+
+        let mut events = Vec::new();
+        for (asset, local_quantity, venue_quantity) in mismatches {
+            let corrected = self.config.auto_correct
+                && local_quantity > venue_quantity
+                && self.reduce_longs(&asset, local_quantity - venue_quantity).await;
+            events.push(self.event(Discrepancy::PositionMismatch { asset, local_quantity, venue_quantity }, corrected));
+        }
+        Ok(events)
+    }
+
+    /// Net quantity per base asset across tracked accounts, shorts negative
+    fn local_holdings(&self) -> HashMap<String, f64> {
+        let mut holdings = HashMap::new();
+        for account in &self.accounts {
+            for position in account.position_manager.get_open_positions() {
+                let signed = match position.side {
+                    Side::Buy => position.quantity,
+                    Side::Sell => -position.quantity,
+                };
+                *holdings.entry(base_asset(&position.symbol)).or_insert(0.0) += signed;
+            }
+        }
+        holdings
+    }
+
+    /// Close `excess` of local longs in `asset` the venue no longer holds, at the venue price.
+    /// A venue holding more than we do is only reported: there is no local entry to book it against.
+    async fn reduce_longs(&self, asset: &str, mut excess: f64) -> bool {
+        for account in &self.accounts {
+            for position in account.position_manager.get_open_positions() {
+                if excess <= 1e-12 || position.side != Side::Buy || base_asset(&position.symbol) != asset {
+                    continue;
+                }
+                let price = match self.venue.last_price(&position.symbol).await {
+                    Ok(price) => price,
+                    Err(e) => {
+                        warn!(symbol = %position.symbol, error = %e, "No venue price to correct position");
+                        return false;
+                    }
+                };
+
+                let closed = if excess >= position.quantity {
+                    account.position_manager.close_position(&position.id, price, 0.0, 0.0, ExitReason::Manual)
+                } else {
+                    account.position_manager.partial_close_position(&position.id, excess, price, 0.0, 0.0, ExitReason::Manual)
+                };
+                if closed.is_err() {
+                    return false;
+                }
+                excess -= excess.min(position.quantity);
+            }
+        }
+        excess <= 1e-12
+    }
+
+    fn past_grace_period(&self, created_ms: u64) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        now.saturating_sub(created_ms) >= self.config.grace_period.as_millis() as u64
+    }
+
+    fn event(&self, discrepancy: Discrepancy, corrected: bool) -> ReconciliationEvent {
+        warn!(?discrepancy, corrected, "Reconciliation discrepancy");
+        let event = ReconciliationEvent {
+            timestamp: Utc::now(),
+            discrepancy,
+            corrected,
+        };
+        let _ = self.event_sender.send(event.clone());
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, OrderStatus, TimeInForce};
+    use crate::paper_trading::{Order, PaperTradingConfig};
+
+    /// Venue with fixed open orders and a BTC balance that can be changed
+    struct StaticVenue {
+        open: Vec<UniversalOrder>,
+        btc: parking_lot::Mutex<f64>,
+    }
+
+    #[async_trait]
+    impl VenueState for StaticVenue {
+        async fn open_orders(&self) -> Result<Vec<UniversalOrder>> {
+            Ok(self.open.clone())
+        }
+
+        async fn order_history(&self, _symbol: &Symbol) -> Result<Vec<UniversalOrder>> {
+            Ok(Vec::new())
+        }
+
+        async fn balances(&self) -> Result<Vec<Balance>> {
+            Ok(vec![Balance::new("BTC".to_string(), *self.btc.lock(), 0.0)])
+        }
+
+        async fn cancel_venue_order(&self, _venue_order_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn last_price(&self, _symbol: &Symbol) -> Result<f64> {
+            Ok(50_000.0)
+        }
+    }
+
+    fn venue_order(id: &str, client_order_id: &str) -> UniversalOrder {
+        UniversalOrder {
+            id: id.to_string(),
+            client_order_id: Some(client_order_id.to_string()),
+            symbol: Symbol::new("BTCUSDT"),
+            side: Side::Buy,
+            order_type: crate::exchanges::OrderType::Limit { price: 40_000.0 },
+            quantity: 0.1,
+            filled_quantity: 0.0,
+            remaining_quantity: 0.1,
+            price: Some(40_000.0),
+            stop_price: None,
+            status: OrderStatus::New,
+            time_in_force: TimeInForce::GTC,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            exchange: Exchange::Binance,
+            fees: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_orders_and_positions() {
+        let engine = PaperTradingEngine::new(PaperTradingConfig::default());
+        let symbol = Symbol::new("BTC-USDT");
+        let resting = Order::limit(symbol.clone(), Exchange::Binance, Side::Buy, 0.1, 40_000.0);
+        let resting_id = engine.order_manager().submit_order(resting).unwrap();
+        let lost = Order::limit(symbol.clone(), Exchange::Binance, Side::Buy, 0.2, 39_000.0);
+        let lost_id = engine.order_manager().submit_order(lost).unwrap();
+        engine.position_manager()
+            .open_position(symbol.clone(), Exchange::Binance, Side::Buy, 0.5, 50_000.0, 0.0, 0.0)
+            .unwrap();
+
+        let venue = Arc::new(StaticVenue {
+            open: vec![venue_order("1", &resting_id), venue_order("2", "manual-order")],
+            btc: parking_lot::Mutex::new(1.0), // Held before trading began
+        });
+        let mut reconciler = Reconciler::new(venue.clone(), ReconciliationConfig {
+            auto_correct: true,
+            grace_period: Duration::ZERO,
+            ..Default::default()
+        });
+        reconciler.track("default", &engine);
+
+        let events = reconciler.reconcile().await.unwrap();
+        let discrepancies: Vec<_> = events.iter().map(|e| e.discrepancy.clone()).collect();
+        assert_eq!(discrepancies, vec![
+            Discrepancy::UnknownVenueOrder { venue_order_id: "2".to_string(), symbol: Symbol::new("BTCUSDT") },
+            Discrepancy::MissingVenueOrder { account: "default".to_string(), order_id: lost_id.clone(), symbol: symbol.clone() },
+        ]);
+        assert!(events.iter().all(|e| e.corrected));
+        assert!(engine.order_manager().get_active_orders().iter().all(|o| o.id != lost_id));
+
+        // The venue sold 0.25 BTC the local books know nothing about
+        *venue.btc.lock() = 0.75;
+        let events = reconciler.reconcile().await.unwrap();
+        let mismatch = events.iter().find(|e| matches!(e.discrepancy, Discrepancy::PositionMismatch { .. })).unwrap();
+        assert_eq!(mismatch.discrepancy, Discrepancy::PositionMismatch {
+            asset: "BTC".to_string(),
+            local_quantity: 0.0,
+            venue_quantity: -0.25,
+        });
+        assert!(mismatch.corrected);
+        let remaining = engine.position_manager().get_open_positions();
+        assert!((remaining[0].quantity - 0.25).abs() < 1e-9);
+
+        assert_eq!(base_asset(&Symbol::new("ETHUSDT")), "ETH");
+    }
+}
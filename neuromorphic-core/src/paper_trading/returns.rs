@@ -0,0 +1,272 @@
+//! Incremental statistics over the latest equity returns
+//!
+//! The statistics updater adds one return per tick and the risk metrics are
+//! refreshed from the latest `RETURNS_WINDOW` of them. Copying and sorting
+//! the window every tick costs more the longer it gets, so the mean,
+//! deviations and sum are kept as running sums instead, and the quantiles
+//! VaR is read from come from a sketch: returns are counted in buckets of
+//! logarithmically spaced magnitude, each within 1% of the returns in it.
+//! Returns leaving the window are taken back out of both, so each tick costs
+//! the same however many returns the window holds.
+
+use super::history::{RingBuffer, Spill};
+use std::collections::BTreeMap;
+
+/// Returns the risk metrics are computed over
+pub const RETURNS_WINDOW: usize = 1000;
+
+/// Relative error of the quantiles
+const RELATIVE_ACCURACY: f64 = 0.01;
+
+/// Returns closer to zero than this are counted as zero
+const MIN_MAGNITUDE: f64 = 1e-12;
+
+/// Quantiles of a multiset of numbers that values can be added to and removed from
+#[derive(Clone, Debug)]
+pub struct QuantileSketch {
+    gamma: f64,
+    ln_gamma: f64,
+    negative: BTreeMap<i32, u64>, // Count by bucket of the magnitude
+    positive: BTreeMap<i32, u64>,
+    zeros: u64,
+    count: u64,
+}
+
+impl Default for QuantileSketch {
+    fn default() -> Self {
+        Self::new(RELATIVE_ACCURACY)
+    }
+}
+
+impl QuantileSketch {
+    /// Sketch whose quantiles are within `relative_accuracy` of the true ones
+    pub fn new(relative_accuracy: f64) -> Self {
+        let gamma = (1.0 + relative_accuracy) / (1.0 - relative_accuracy);
+        Self {
+            gamma,
+            ln_gamma: gamma.ln(),
+            negative: BTreeMap::new(),
+            positive: BTreeMap::new(),
+            zeros: 0,
+            count: 0,
+        }
+    }
+
+    pub fn insert(&mut self, value: f64) {
+        match self.bucket(value) {
+            Some(index) => *self.buckets(value).entry(index).or_default() += 1,
+            None => self.zeros += 1,
+        }
+        self.count += 1;
+    }
+
+    /// Remove one `value` added before
+    pub fn remove(&mut self, value: f64) {
+        match self.bucket(value) {
+            Some(index) => {
+                let buckets = self.buckets(value);
+                let Some(n) = buckets.get_mut(&index) else { return };
+                *n -= 1;
+                if *n == 0 {
+                    buckets.remove(&index);
+                }
+            }
+            None if self.zeros > 0 => self.zeros -= 1,
+            None => return,
+        }
+        self.count -= 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Value of rank `floor(q * count)` in ascending order, as when indexing
+    /// the sorted values
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64) as u64).min(self.count - 1);
+        let mut seen = 0;
+        for (&index, &n) in self.negative.iter().rev() {
+            seen += n;
+            if seen > rank {
+                return Some(-self.value(index));
+            }
+        }
+        seen += self.zeros;
+        if seen > rank {
+            return Some(0.0);
+        }
+        for (&index, &n) in &self.positive {
+            seen += n;
+            if seen > rank {
+                return Some(self.value(index));
+            }
+        }
+        None
+    }
+
+    /// Bucket of the magnitude of `value`; None for zero
+    fn bucket(&self, value: f64) -> Option<i32> {
+        (value.abs() >= MIN_MAGNITUDE).then(|| (value.abs().ln() / self.ln_gamma).ceil() as i32)
+    }
+
+    /// Buckets of the sign of `value`
+    fn buckets(&mut self, value: f64) -> &mut BTreeMap<i32, u64> {
+        if value < 0.0 { &mut self.negative } else { &mut self.positive }
+    }
+
+    /// Magnitude a bucket stands for, within the accuracy of everything in it
+    fn value(&self, index: i32) -> f64 {
+        2.0 * self.gamma.powi(index) / (self.gamma + 1.0)
+    }
+}
+
+/// Running moments and quantiles of the latest returns
+#[derive(Clone, Debug)]
+pub struct ReturnStatistics {
+    returns: RingBuffer<f64>,
+    sum: f64,
+    sq_sum: f64,
+    downside_sq_sum: f64,
+    downside_count: usize,
+    sketch: QuantileSketch,
+}
+
+impl Default for ReturnStatistics {
+    fn default() -> Self {
+        Self::new(RETURNS_WINDOW)
+    }
+}
+
+impl ReturnStatistics {
+    /// Statistics over the latest `capacity` returns
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            returns: RingBuffer::new(capacity),
+            sum: 0.0,
+            sq_sum: 0.0,
+            downside_sq_sum: 0.0,
+            downside_count: 0,
+            sketch: QuantileSketch::default(),
+        }
+    }
+
+    /// Append returns leaving the window to `spill`
+    pub fn with_spill(mut self, spill: Option<Spill>) -> Self {
+        self.returns = self.returns.with_spill(spill);
+        self
+    }
+
+    /// Add a return, dropping the oldest once the window is full
+    pub fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if let Some(oldest) = self.returns.push(value) {
+            self.sum -= oldest;
+            self.sq_sum -= oldest * oldest;
+            if oldest < 0.0 {
+                self.downside_sq_sum -= oldest * oldest;
+                self.downside_count -= 1;
+            }
+            self.sketch.remove(oldest);
+        }
+        self.sum += value;
+        self.sq_sum += value * value;
+        if value < 0.0 {
+            self.downside_sq_sum += value * value;
+            self.downside_count += 1;
+        }
+        self.sketch.insert(value);
+    }
+
+    /// Forget every return, keeping the window length and spill file
+    pub fn clear(&mut self) {
+        self.returns.clear();
+        (self.sum, self.sq_sum, self.downside_sq_sum, self.downside_count) = (0.0, 0.0, 0.0, 0);
+        self.sketch = QuantileSketch::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.returns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.returns.is_empty()
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.returns.is_empty() { 0.0 } else { self.sum / self.returns.len() as f64 }
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.returns.is_empty() {
+            return 0.0;
+        }
+        let mean = self.mean();
+        (self.sq_sum / self.returns.len() as f64 - mean * mean).max(0.0).sqrt()
+    }
+
+    /// Root mean square of the negative returns; None without any
+    pub fn downside_deviation(&self) -> Option<f64> {
+        (self.downside_count > 0).then(|| (self.downside_sq_sum / self.downside_count as f64).max(0.0).sqrt())
+    }
+
+    /// Approximate return of rank `floor(q * len)` in ascending order
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.sketch.quantile(q)
+    }
+
+    /// The returns in the window, oldest first
+    pub fn returns(&self) -> Vec<f64> {
+        self.returns.iter().copied().collect()
+    }
+}
+
+impl Extend<f64> for ReturnStatistics {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, returns: I) {
+        for value in returns {
+            self.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_statistics_match_the_window() {
+        let mut stats = ReturnStatistics::new(500);
+        // Deterministic returns between -2% and 2%, some exactly zero
+        let returns: Vec<f64> = (0..2000u64).map(|i| if i % 7 == 0 { 0.0 } else { ((i * 7919) % 401) as f64 / 10_000.0 - 0.02 }).collect();
+        stats.extend(returns.iter().copied());
+
+        let window = &returns[1500..];
+        assert_eq!(stats.returns(), window);
+        let n = window.len() as f64;
+        let mean = window.iter().sum::<f64>() / n;
+        let std_dev = (window.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        let downside: Vec<f64> = window.iter().copied().filter(|r| *r < 0.0).collect();
+        let downside_dev = (downside.iter().map(|r| r * r).sum::<f64>() / downside.len() as f64).sqrt();
+        assert!((stats.mean() - mean).abs() < 1e-12);
+        assert!((stats.std_dev() - std_dev).abs() < 1e-12);
+        assert!((stats.downside_deviation().unwrap() - downside_dev).abs() < 1e-12);
+
+        let mut sorted = window.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        for q in [0.01, 0.05, 0.5, 0.95] {
+            let exact = sorted[(n * q) as usize];
+            let estimate = stats.quantile(q).unwrap();
+            assert!((estimate - exact).abs() <= exact.abs() * RELATIVE_ACCURACY + 1e-15, "q {}: {} vs {}", q, estimate, exact);
+        }
+    }
+}
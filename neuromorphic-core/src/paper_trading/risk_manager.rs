@@ -1,14 +1,29 @@
 //! Risk management for paper trading
+//!
+//! Order rates are limited over a sliding minute, both across all symbols and
+//! per symbol, so one symbol in a tight loop is stopped before it uses up the
+//! allowance of the others. The window follows the engine's clock, so
+//! simulated runs are limited in simulated time.
 
+use super::calibration::ConfidenceCalibration;
+use super::clock::{self, SharedClock};
+use super::events::{EngineEvent, EventLog};
+use super::history::RingBuffer;
+use super::position_manager::Position;
+use super::returns::ReturnStatistics;
+use super::scenarios::{self, MarginLimits, Scenario, ScenarioPosition, ScenarioReport, DEFAULT_DAILY_VOLATILITY};
 use crate::exchanges::{Symbol, Side};
 use anyhow::Result;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
 /// Risk limits configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RiskLimits {
     pub max_position_size: f64,
     pub max_daily_loss: f64,
@@ -16,10 +31,13 @@ pub struct RiskLimits {
     pub max_leverage: f64,
     pub max_positions: usize,
     pub max_orders_per_minute: u64,
+    pub max_orders_per_minute_per_symbol: u64,
     pub max_correlation: f64,
     pub position_size_pct: f64,  // % of capital per position
     pub stop_loss_pct: f64,      // Default stop loss %
     pub take_profit_pct: f64,    // Default take profit %
+    pub equity_stop_out_pct: f64, // Liquidate losers when equity < this % of initial capital (0 disables)
+    pub calibrated_sizing: bool, // Size by the realized win rate of the signal's confidence bucket
 }
 
 impl Default for RiskLimits {
@@ -31,22 +49,27 @@ impl Default for RiskLimits {
             max_leverage: 3.0,
             max_positions: 10,
             max_orders_per_minute: 100,
+            max_orders_per_minute_per_symbol: 20,
             max_correlation: 0.7,
             position_size_pct: 2.0,  // 2% per position
             stop_loss_pct: 2.0,      // 2% stop loss
             take_profit_pct: 4.0,    // 4% take profit
+            equity_stop_out_pct: 50.0, // Stop out at half the starting capital
+            calibrated_sizing: false,
         }
     }
 }
 
 /// Risk metrics
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct RiskMetrics {
     pub current_drawdown: f64,
     pub max_drawdown: f64,
     pub daily_pnl: f64,
     pub total_exposure: f64,
     pub leverage_ratio: f64,
+    #[serde(default)]
+    pub portfolio_heat: f64, // Loss if every open position hit its stop, over equity
     pub var_95: f64,  // Value at Risk 95%
     pub var_99: f64,  // Value at Risk 99%
     pub sharpe_ratio: f64,
@@ -62,7 +85,17 @@ pub enum RiskCheckResult {
     Warning { message: String },
 }
 
+/// Risk event emitted by the risk manager
+#[derive(Clone, Debug)]
+pub enum RiskEvent {
+    EquityStopOut { equity: f64, threshold: f64 },
+    ForcedClose { position_id: String, symbol: Symbol, unrealized_pnl: f64, equity: f64 },
+    /// A resting order would breach a limit if it filled; the engine cancels it
+    RestingOrderRejected { order_id: String, reason: String },
+}
+
 /// Kelly Criterion calculator
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KellyCriterion {
     win_rate: f64,
     avg_win: f64,
@@ -101,7 +134,7 @@ impl KellyCriterion {
 /// Portfolio heat map for correlation tracking
 pub struct PortfolioHeatMap {
     correlations: DashMap<(Symbol, Symbol), f64>,
-    returns_history: DashMap<Symbol, Vec<f64>>,
+    returns_history: DashMap<Symbol, RingBuffer<f64>>,
     window_size: usize,
 }
 
@@ -116,14 +149,10 @@ impl PortfolioHeatMap {
     
     /// Update returns for a symbol
     pub fn update_returns(&self, symbol: Symbol, return_pct: f64) {
-        let mut history = self.returns_history
+        self.returns_history
             .entry(symbol)
-            .or_insert_with(Vec::new);
-        
-        history.push(return_pct);
-        if history.len() > self.window_size {
-            history.remove(0);
-        }
+            .or_insert_with(|| RingBuffer::new(self.window_size))
+            .push(return_pct);
     }
     
     /// Calculate correlation between two symbols
@@ -179,101 +208,305 @@ impl PortfolioHeatMap {
     }
 }
 
+/// Serializable state of a risk manager, see `RiskManager::snapshot`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RiskState {
+    pub metrics: RiskMetrics,
+    pub kelly: KellyCriterion,
+    pub daily_loss: f64,
+    pub peak_capital: f64,
+    pub recent_orders: Vec<(Symbol, Vec<u64>)>, // Order times within the rate window, per symbol
+}
+
+/// Order times within the last `ORDER_WINDOW_MS`, oldest first
+#[derive(Default)]
+struct OrderWindow {
+    all: VecDeque<u64>,
+    by_symbol: HashMap<Symbol, VecDeque<u64>>,
+}
+
+impl OrderWindow {
+    const ORDER_WINDOW_MS: u64 = 60_000;
+
+    fn expire(&mut self, now_ms: u64) {
+        let expired = |times: &mut VecDeque<u64>| {
+            while times.front().is_some_and(|&t| now_ms.saturating_sub(t) >= Self::ORDER_WINDOW_MS) {
+                times.pop_front();
+            }
+        };
+        expired(&mut self.all);
+        self.by_symbol.retain(|_, times| {
+            expired(times);
+            !times.is_empty()
+        });
+    }
+
+    fn count(&self, symbol: &Symbol) -> (u64, u64) {
+        let for_symbol = self.by_symbol.get(symbol).map_or(0, |times| times.len());
+        (self.all.len() as u64, for_symbol as u64)
+    }
+}
+
+/// Realized volatility of one symbol: an exponentially weighted average of
+/// squared log returns per millisecond, so irregular ticks weigh by their gap
+#[derive(Clone, Copy, Debug)]
+struct VolatilityEstimate {
+    last_price: f64,
+    last_ms: u64,
+    variance_per_ms: f64,
+    samples: u64,
+}
+
+impl VolatilityEstimate {
+    const DECAY: f64 = 0.94;
+    const MIN_SAMPLES: u64 = 20;
+    const DAY_MS: f64 = 86_400_000.0;
+}
+
 /// Risk manager
 pub struct RiskManager {
-    limits: RiskLimits,
+    limits: parking_lot::RwLock<RiskLimits>, // Replaced whole on reload
     metrics: Arc<parking_lot::RwLock<RiskMetrics>>,
     portfolio_heat_map: Arc<PortfolioHeatMap>,
     kelly_criterion: Arc<parking_lot::RwLock<KellyCriterion>>,
     daily_loss: Arc<parking_lot::RwLock<f64>>,
     peak_capital: Arc<parking_lot::RwLock<f64>>,
-    orders_per_minute: Arc<AtomicU64>,
+    recent_orders: parking_lot::Mutex<OrderWindow>,
     position_count: Arc<AtomicU64>,
+    initial_capital: f64,
+    event_sender: broadcast::Sender<RiskEvent>,
+    calibration: parking_lot::RwLock<Option<Arc<ConfidenceCalibration>>>,
+    volatility: DashMap<Symbol, VolatilityEstimate>,
+    clock: SharedClock,
+    events: EventLog,
 }
 
 impl RiskManager {
     pub fn new(limits: RiskLimits, initial_capital: f64) -> Self {
+        let (event_sender, _) = broadcast::channel(1000);
+        
         Self {
-            limits,
+            limits: parking_lot::RwLock::new(limits),
             metrics: Arc::new(parking_lot::RwLock::new(RiskMetrics::default())),
             portfolio_heat_map: Arc::new(PortfolioHeatMap::new(100)),
             kelly_criterion: Arc::new(parking_lot::RwLock::new(KellyCriterion::new(0.5, 2.0, 1.0))),
             daily_loss: Arc::new(parking_lot::RwLock::new(0.0)),
             peak_capital: Arc::new(parking_lot::RwLock::new(initial_capital)),
-            orders_per_minute: Arc::new(AtomicU64::new(0)),
+            recent_orders: parking_lot::Mutex::new(OrderWindow::default()),
             position_count: Arc::new(AtomicU64::new(0)),
+            initial_capital,
+            event_sender,
+            calibration: parking_lot::RwLock::new(None),
+            volatility: DashMap::new(),
+            clock: clock::system_clock(),
+            events: EventLog::default(),
+        }
+    }
+    
+    /// Time the order rate window by `clock` instead of the wall clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Append rejected orders to `events`
+    pub fn with_event_log(mut self, events: EventLog) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Realized win rates to size by when `calibrated_sizing` is on
+    pub fn set_confidence_calibration(&self, calibration: Arc<ConfidenceCalibration>) {
+        *self.calibration.write() = Some(calibration);
+    }
+    
+    /// Update the symbol's volatility estimate with a new price
+    pub fn record_price(&self, symbol: &Symbol, price: f64, now_ms: u64) {
+        if !price.is_finite() || price <= 0.0 {
+            return;
+        }
+        let mut estimate = self.volatility.entry(symbol.clone()).or_insert(VolatilityEstimate {
+            last_price: price,
+            last_ms: now_ms,
+            variance_per_ms: 0.0,
+            samples: 0,
+        });
+        let elapsed = now_ms.saturating_sub(estimate.last_ms);
+        if elapsed == 0 {
+            return; // Measured from the first price of the millisecond
         }
+        let r = (price / estimate.last_price).ln();
+        let sample = r * r / elapsed as f64;
+        estimate.variance_per_ms = if estimate.samples == 0 {
+            sample
+        } else {
+            VolatilityEstimate::DECAY * estimate.variance_per_ms + (1.0 - VolatilityEstimate::DECAY) * sample
+        };
+        estimate.samples += 1;
+        estimate.last_price = price;
+        estimate.last_ms = now_ms;
+    }
+    
+    /// Estimated daily volatility as a fraction of price, or
+    /// `DEFAULT_DAILY_VOLATILITY` until enough prices have been seen
+    pub fn daily_volatility(&self, symbol: &Symbol) -> f64 {
+        self.volatility
+            .get(symbol)
+            .filter(|e| e.samples >= VolatilityEstimate::MIN_SAMPLES)
+            .map(|e| (e.variance_per_ms * VolatilityEstimate::DAY_MS).sqrt())
+            .unwrap_or(DEFAULT_DAILY_VOLATILITY)
+    }
+    
+    /// Hypothetical P&L and margin of positions under each scenario, checked
+    /// against the leverage limit and equity stop-out
+    pub fn run_scenarios(&self, scenarios: &[Scenario], positions: &[ScenarioPosition], equity: f64, now_ms: u64) -> ScenarioReport {
+        let limits = self.limits.read();
+        let margin = MarginLimits {
+            max_leverage: limits.max_leverage,
+            stop_out_equity: self.initial_capital * limits.equity_stop_out_pct / 100.0,
+        };
+        scenarios::run(scenarios, positions, equity, margin, now_ms)
     }
     
     /// Check if order should be allowed
     pub fn check_order(
         &self,
-        _symbol: &Symbol,
-        _side: Side,
+        symbol: &Symbol,
+        side: Side,
+        quantity: f64,
+        price: f64,
+        current_capital: f64,
+    ) -> RiskCheckResult {
+        let result = self.evaluate_order(symbol, quantity, price, current_capital, 0, 0.0);
+        if let RiskCheckResult::Rejected { reason } = &result {
+            self.events.record(|| EngineEvent::RiskRejected {
+                symbol: symbol.clone(),
+                side,
+                quantity,
+                price,
+                reason: reason.clone(),
+            });
+        }
+        result
+    }
+    
+    /// Check the legs of a basket, given as (symbol, side, quantity, price),
+    /// as one order: each leg counts the orders, positions and exposure of
+    /// the legs before it, and the basket is rejected if any leg is
+    pub fn check_basket(&self, legs: &[(Symbol, Side, f64, f64)], current_capital: f64) -> RiskCheckResult {
+        let mut pending_exposure = 0.0;
+        let mut warning = None;
+        for (i, (symbol, side, quantity, price)) in legs.iter().enumerate() {
+            match self.evaluate_order(symbol, *quantity, *price, current_capital, i, pending_exposure) {
+                RiskCheckResult::Rejected { reason } => {
+                    let reason = format!("Basket leg {}: {}", symbol, reason);
+                    self.events.record(|| EngineEvent::RiskRejected {
+                        symbol: symbol.clone(),
+                        side: *side,
+                        quantity: *quantity,
+                        price: *price,
+                        reason: reason.clone(),
+                    });
+                    return RiskCheckResult::Rejected { reason };
+                }
+                RiskCheckResult::Warning { message } => {
+                    warning.get_or_insert(message);
+                }
+                RiskCheckResult::Approved => {}
+            }
+            pending_exposure += quantity * price;
+        }
+        match warning {
+            Some(message) => RiskCheckResult::Warning { message },
+            None => RiskCheckResult::Approved,
+        }
+    }
+    
+    /// `pending_orders` and `pending_exposure` are of orders checked along
+    /// with this one but not recorded yet, like earlier legs of a basket
+    fn evaluate_order(
+        &self,
+        symbol: &Symbol,
         quantity: f64,
         price: f64,
         current_capital: f64,
+        pending_orders: usize,
+        pending_exposure: f64,
     ) -> RiskCheckResult {
-        // Check order rate limit
-        let orders_count = self.orders_per_minute.load(Ordering::Relaxed);
-        if orders_count >= self.limits.max_orders_per_minute {
+        let limits = self.limits.read();
+        // Check order rate limits over the last minute
+        let (all_orders, symbol_orders) = {
+            let mut recent = self.recent_orders.lock();
+            recent.expire(self.clock.now_ms());
+            recent.count(symbol)
+        };
+        let all_orders = all_orders + pending_orders as u64;
+        if all_orders >= limits.max_orders_per_minute {
+            return RiskCheckResult::Rejected {
+                reason: format!(
+                    "Order rate limit exceeded: {}/{} orders/min",
+                    all_orders, limits.max_orders_per_minute
+                )
+            };
+        }
+        if symbol_orders >= limits.max_orders_per_minute_per_symbol {
             return RiskCheckResult::Rejected {
                 reason: format!(
-                    "Order rate limit exceeded: {} orders/min",
-                    orders_count
+                    "Order rate limit exceeded for {}: {}/{} orders/min",
+                    symbol, symbol_orders, limits.max_orders_per_minute_per_symbol
                 )
             };
         }
         
         // Check position count
-        let pos_count = self.position_count.load(Ordering::Relaxed) as usize;
-        if pos_count >= self.limits.max_positions {
+        let pos_count = self.position_count.load(Ordering::Relaxed) as usize + pending_orders;
+        if pos_count >= limits.max_positions {
             return RiskCheckResult::Rejected {
                 reason: format!(
                     "Max positions limit reached: {}/{}",
-                    pos_count, self.limits.max_positions
+                    pos_count, limits.max_positions
                 )
             };
         }
         
         // Check position size
         let position_value = quantity * price;
-        if position_value > self.limits.max_position_size {
+        if position_value > limits.max_position_size {
             return RiskCheckResult::Rejected {
                 reason: format!(
-                    "Position size ${:.2} exceeds limit ${:.2}",
-                    position_value, self.limits.max_position_size
+                    "Position size {:.2} exceeds limit {:.2}",
+                    position_value, limits.max_position_size
                 )
             };
         }
         
         // Check daily loss limit
         let daily_loss = *self.daily_loss.read();
-        if daily_loss.abs() > self.limits.max_daily_loss {
+        if daily_loss.abs() > limits.max_daily_loss {
             return RiskCheckResult::Rejected {
                 reason: format!(
-                    "Daily loss limit exceeded: ${:.2}/${:.2}",
-                    daily_loss.abs(), self.limits.max_daily_loss
+                    "Daily loss limit exceeded: {:.2}/{:.2}",
+                    daily_loss.abs(), limits.max_daily_loss
                 )
             };
         }
         
         // Check leverage
         let metrics = self.metrics.read();
-        let new_exposure = metrics.total_exposure + position_value;
+        let new_exposure = metrics.total_exposure + pending_exposure + position_value;
         let leverage = new_exposure / current_capital;
         
-        if leverage > self.limits.max_leverage {
+        if leverage > limits.max_leverage {
             return RiskCheckResult::Rejected {
                 reason: format!(
                     "Leverage limit exceeded: {:.2}x/{:.2}x",
-                    leverage, self.limits.max_leverage
+                    leverage, limits.max_leverage
                 )
             };
         }
         
         // Check drawdown
-        if metrics.current_drawdown > self.limits.max_drawdown {
+        if metrics.current_drawdown > limits.max_drawdown {
             return RiskCheckResult::Warning {
                 message: format!(
                     "High drawdown: {:.1}%",
@@ -292,18 +525,23 @@ impl RiskManager {
         current_capital: f64,
         confidence: f64,
     ) -> f64 {
+        let limits = self.limits.read();
         // Use Kelly Criterion for sizing
         let kelly = self.kelly_criterion.read();
         let kelly_size = kelly.calculate_position_size(current_capital, 0.25); // 25% of full Kelly
         
         // Apply position size percentage limit
-        let pct_size = current_capital * (self.limits.position_size_pct / 100.0);
+        let pct_size = current_capital * (limits.position_size_pct / 100.0);
         
         // Apply confidence adjustment
+        let confidence = match &*self.calibration.read() {
+            Some(calibration) if limits.calibrated_sizing => calibration.calibrate(confidence),
+            _ => confidence,
+        };
         let confidence_adjusted = pct_size * confidence.min(1.0).max(0.1);
         
         // Return minimum of all constraints
-        kelly_size.min(pct_size).min(confidence_adjusted).min(self.limits.max_position_size)
+        kelly_size.min(pct_size).min(confidence_adjusted).min(limits.max_position_size)
     }
     
     /// Update risk metrics
@@ -312,7 +550,7 @@ impl RiskManager {
         current_capital: f64,
         total_exposure: f64,
         daily_pnl: f64,
-        returns: &[f64],
+        returns: &ReturnStatistics,
     ) {
         let mut metrics = self.metrics.write();
         let mut peak = self.peak_capital.write();
@@ -340,54 +578,115 @@ impl RiskManager {
         
         // Calculate VaR if we have enough data
         if returns.len() > 20 {
-            let mut sorted_returns = returns.to_vec();
-            sorted_returns.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            
-            let var_95_idx = (returns.len() as f64 * 0.05) as usize;
-            let var_99_idx = (returns.len() as f64 * 0.01) as usize;
-            
-            metrics.var_95 = sorted_returns[var_95_idx].abs() * current_capital;
-            metrics.var_99 = sorted_returns[var_99_idx].abs() * current_capital;
+            metrics.var_95 = returns.quantile(0.05).unwrap_or_default().abs() * current_capital;
+            metrics.var_99 = returns.quantile(0.01).unwrap_or_default().abs() * current_capital;
         }
         
         // Calculate Sharpe ratio
         if returns.len() > 1 {
-            let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
-            let variance = returns.iter()
-                .map(|r| (r - mean_return).powi(2))
-                .sum::<f64>() / returns.len() as f64;
-            let std_dev = variance.sqrt();
-            
-            if std_dev > 0.0 {
-                metrics.sharpe_ratio = (mean_return * 252.0_f64.sqrt()) / std_dev; // Annualized
+            let std_dev = returns.std_dev();
+            if std_dev > 1e-12 {
+                metrics.sharpe_ratio = (returns.mean() * 252.0_f64.sqrt()) / std_dev; // Annualized
             }
         }
         
         // Calculate Sortino ratio (downside deviation)
         if returns.len() > 1 {
-            let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
-            let downside_returns: Vec<f64> = returns.iter()
-                .filter(|&&r| r < 0.0)
-                .copied()
-                .collect();
-            
-            if !downside_returns.is_empty() {
-                let downside_variance = downside_returns.iter()
-                    .map(|r| r.powi(2))
-                    .sum::<f64>() / downside_returns.len() as f64;
-                let downside_dev = downside_variance.sqrt();
-                
-                if downside_dev > 0.0 {
-                    metrics.sortino_ratio = (mean_return * 252.0_f64.sqrt()) / downside_dev;
-                }
+            if let Some(downside_dev) = returns.downside_deviation().filter(|dev| *dev > 1e-12) {
+                metrics.sortino_ratio = (returns.mean() * 252.0_f64.sqrt()) / downside_dev;
             }
         }
         
         // Calculate Calmar ratio
         if metrics.max_drawdown > 0.0 && returns.len() > 252 {
-            let annual_return = returns.iter().sum::<f64>();
-            metrics.calmar_ratio = annual_return / metrics.max_drawdown;
+            metrics.calmar_ratio = returns.sum() / metrics.max_drawdown;
+        }
+    }
+    
+    /// Refresh exposure and leverage straight away, e.g. after a fill, rather
+    /// than on the next statistics update
+    pub fn update_exposure(&self, total_exposure: f64, equity: f64) {
+        let mut metrics = self.metrics.write();
+        metrics.total_exposure = total_exposure;
+        metrics.leverage_ratio = if equity > 0.0 { total_exposure / equity } else { 0.0 };
+    }
+    
+    /// Update the portfolio heat, see `PositionManager::capital_at_risk`
+    pub fn update_portfolio_heat(&self, capital_at_risk: f64, equity: f64) {
+        self.metrics.write().portfolio_heat = if equity > 0.0 { capital_at_risk / equity } else { 0.0 };
+    }
+    
+    /// Resting orders that would breach the size, daily loss or leverage limit
+    /// if they filled now, with the reason. `orders` are (order ID, notional)
+    /// of orders adding exposure, oldest first; each order kept counts towards
+    /// the exposure the next one is checked against.
+    pub fn check_resting_orders(&self, orders: &[(String, f64)], equity: f64) -> Vec<(String, String)> {
+        let limits = self.limits.read();
+        let daily_loss = self.daily_loss.read().abs();
+        let mut exposure = self.metrics.read().total_exposure;
+        let mut rejected = Vec::new();
+        
+        for (order_id, notional) in orders {
+            let leverage = if equity > 0.0 { (exposure + notional) / equity } else { f64::INFINITY };
+            let reason = if *notional > limits.max_position_size {
+                Some(format!("Position size {:.2} exceeds limit {:.2}", notional, limits.max_position_size))
+            } else if daily_loss > limits.max_daily_loss {
+                Some(format!("Daily loss limit exceeded: {:.2}/{:.2}", daily_loss, limits.max_daily_loss))
+            } else if leverage > limits.max_leverage {
+                Some(format!("Leverage limit exceeded: {:.2}x/{:.2}x", leverage, limits.max_leverage))
+            } else {
+                None
+            };
+            
+            match reason {
+                Some(reason) => {
+                    let _ = self.event_sender.send(RiskEvent::RestingOrderRejected {
+                        order_id: order_id.clone(),
+                        reason: reason.clone(),
+                    });
+                    rejected.push((order_id.clone(), reason));
+                }
+                None => exposure += notional,
+            }
+        }
+        rejected
+    }
+    
+    /// Check equity (capital plus unrealized P&L) against the stop-out level.
+    /// While below it, returns the candidate with the largest loss to force-close;
+    /// callers re-check after each close until equity is back above the threshold.
+    pub fn check_equity_stop_out(&self, equity: f64, candidates: &[Position]) -> Option<Position> {
+        let limits = self.limits.read();
+        if limits.equity_stop_out_pct <= 0.0 {
+            return None;
+        }
+        
+        let threshold = self.initial_capital * limits.equity_stop_out_pct / 100.0;
+        if equity >= threshold {
+            return None;
         }
+        
+        let _ = self.event_sender.send(RiskEvent::EquityStopOut { equity, threshold });
+        
+        let worst = candidates
+            .iter()
+            .filter(|p| p.unrealized_pnl < 0.0)
+            .min_by(|a, b| a.unrealized_pnl.partial_cmp(&b.unrealized_pnl).unwrap_or(std::cmp::Ordering::Equal))?
+            .clone();
+        
+        let _ = self.event_sender.send(RiskEvent::ForcedClose {
+            position_id: worst.id.clone(),
+            symbol: worst.symbol.clone(),
+            unrealized_pnl: worst.unrealized_pnl,
+            equity,
+        });
+        
+        Some(worst)
+    }
+    
+    /// Subscribe to risk events
+    pub fn subscribe(&self) -> broadcast::Receiver<RiskEvent> {
+        self.event_sender.subscribe()
     }
     
     /// Update Kelly Criterion parameters
@@ -395,32 +694,81 @@ impl RiskManager {
         *self.kelly_criterion.write() = KellyCriterion::new(win_rate, avg_win, avg_loss);
     }
     
-    /// Record order for rate limiting
-    pub fn record_order(&self) {
-        self.orders_per_minute.fetch_add(1, Ordering::Relaxed);
+    /// Record a submitted order against the rate limits
+    pub fn record_order(&self, symbol: &Symbol) {
+        let now_ms = self.clock.now_ms();
+        let mut recent = self.recent_orders.lock();
+        recent.expire(now_ms);
+        recent.all.push_back(now_ms);
+        recent.by_symbol.entry(symbol.clone()).or_default().push_back(now_ms);
     }
     
     /// Reset daily metrics
     pub fn reset_daily_metrics(&self) {
         *self.daily_loss.write() = 0.0;
-        self.orders_per_minute.store(0, Ordering::Relaxed);
         
         let mut metrics = self.metrics.write();
         metrics.daily_pnl = 0.0;
     }
     
+    /// Copy of the drawdown, daily loss and sizing state
+    pub fn snapshot(&self) -> RiskState {
+        RiskState {
+            metrics: self.metrics.read().clone(),
+            kelly: self.kelly_criterion.read().clone(),
+            daily_loss: *self.daily_loss.read(),
+            peak_capital: *self.peak_capital.read(),
+            recent_orders: self.recent_orders_by_symbol(),
+        }
+    }
+    
+    pub fn restore(&self, state: &RiskState) {
+        *self.metrics.write() = state.metrics.clone();
+        *self.kelly_criterion.write() = state.kelly.clone();
+        *self.daily_loss.write() = state.daily_loss;
+        *self.peak_capital.write() = state.peak_capital;
+        
+        let mut recent = self.recent_orders.lock();
+        recent.by_symbol = state
+            .recent_orders
+            .iter()
+            .map(|(symbol, times)| (symbol.clone(), times.iter().copied().collect()))
+            .collect();
+        let mut all: Vec<u64> = recent.by_symbol.values().flatten().copied().collect();
+        all.sort_unstable();
+        recent.all = all.into();
+    }
+    
+    fn recent_orders_by_symbol(&self) -> Vec<(Symbol, Vec<u64>)> {
+        let mut recent = self.recent_orders.lock();
+        recent.expire(self.clock.now_ms());
+        let mut by_symbol: Vec<(Symbol, Vec<u64>)> = recent
+            .by_symbol
+            .iter()
+            .map(|(symbol, times)| (symbol.clone(), times.iter().copied().collect()))
+            .collect();
+        by_symbol.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        by_symbol
+    }
+    
     /// Get current risk metrics
     pub fn get_metrics(&self) -> RiskMetrics {
         self.metrics.read().clone()
     }
     
     /// Get risk limits
-    pub fn get_limits(&self) -> &RiskLimits {
-        &self.limits
+    pub fn get_limits(&self) -> RiskLimits {
+        self.limits.read().clone()
+    }
+    
+    /// Replace the risk limits; checks from now on use the new ones
+    pub fn set_limits(&self, limits: RiskLimits) {
+        *self.limits.write() = limits;
     }
     
     /// Check portfolio correlation risk
     pub fn check_correlation_risk(&self, positions: &[(Symbol, f64)]) -> RiskCheckResult {
+        let limits = self.limits.read();
         let concentration = self.portfolio_heat_map.get_concentration_risk(positions);
         
         if concentration > 0.3 {
@@ -436,7 +784,7 @@ impl RiskManager {
                     &positions[i].0,
                     &positions[j].0
                 ) {
-                    if corr.abs() > self.limits.max_correlation {
+                    if corr.abs() > limits.max_correlation {
                         return RiskCheckResult::Warning {
                             message: format!(
                                 "High correlation between {} and {}: {:.2}",
@@ -499,5 +847,98 @@ mod tests {
             RiskCheckResult::Rejected { .. } => {},
             _ => panic!("Expected rejection"),
         }
+        
+        // Legs that pass alone are rejected together once their exposure adds up
+        let leg = |symbol: &str| (Symbol::new(symbol), Side::Buy, 1.0, 90000.0);
+        let basket = vec![leg("BTC-USD"), leg("ETH-USD"), leg("SOL-USD")];
+        assert!(matches!(manager.check_basket(&basket, 100000.0), RiskCheckResult::Approved));
+        let basket = vec![leg("BTC-USD"), leg("ETH-USD"), leg("SOL-USD"), leg("ADA-USD")];
+        match manager.check_basket(&basket, 100000.0) {
+            RiskCheckResult::Rejected { reason } => assert!(reason.starts_with("Basket leg ADA-USD: Leverage")),
+            _ => panic!("Expected rejection"),
+        }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_equity_stop_out() {
+        use crate::exchanges::Exchange;
+        
+        let manager = RiskManager::new(RiskLimits::default(), 10000.0);
+        let mut events = manager.subscribe();
+        
+        let mut small_loss = Position::new(Symbol::new("ETH-USD"), Exchange::Binance, Side::Buy, 1.0, 3000.0);
+        small_loss.unrealized_pnl = -500.0;
+        let mut large_loss = Position::new(Symbol::new("BTC-USD"), Exchange::Binance, Side::Buy, 1.0, 50000.0);
+        large_loss.unrealized_pnl = -2000.0;
+        let positions = vec![small_loss, large_loss.clone()];
+        
+        // Equity 7500 is above the 50% stop-out level
+        assert!(manager.check_equity_stop_out(7500.0, &positions).is_none());
+        
+        // Equity 4500 is below it: the largest loser goes first
+        let forced = manager.check_equity_stop_out(4500.0, &positions).unwrap();
+        assert_eq!(forced.id, large_loss.id);
+        
+        assert!(matches!(events.try_recv(), Ok(RiskEvent::EquityStopOut { .. })));
+        assert!(matches!(events.try_recv(), Ok(RiskEvent::ForcedClose { .. })));
+    }
+    
+    #[test]
+    fn test_daily_volatility_from_prices() {
+        let manager = RiskManager::new(RiskLimits::default(), 10000.0);
+        let symbol = Symbol::new("BTC-USD");
+        
+        // A 1% swing every minute, twenty minutes long
+        for i in 0..=20u64 {
+            let price = if i % 2 == 0 { 100.0 } else { 101.0 };
+            manager.record_price(&symbol, price, i * 60_000);
+            manager.record_price(&symbol, price * 2.0, i * 60_000); // Same millisecond, ignored
+            if i < 20 {
+                assert_eq!(manager.daily_volatility(&symbol), DEFAULT_DAILY_VOLATILITY);
+            }
+        }
+        let expected = 1.01_f64.ln() * 1440.0_f64.sqrt();
+        assert!((manager.daily_volatility(&symbol) - expected).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_order_rate_limits_slide() {
+        use crate::paper_trading::SimulatedClock;
+        use std::time::Duration;
+        
+        let limits = RiskLimits {
+            max_orders_per_minute: 5,
+            max_orders_per_minute_per_symbol: 3,
+            ..RiskLimits::default()
+        };
+        let clock = Arc::new(SimulatedClock::new(0));
+        let manager = RiskManager::new(limits, 100000.0).with_clock(clock.clone());
+        let (btc, eth, sol) = (Symbol::new("BTC-USD"), Symbol::new("ETH-USD"), Symbol::new("SOL-USD"));
+        let approved = |symbol: &Symbol| matches!(manager.check_order(symbol, Side::Buy, 0.01, 100.0, 100000.0), RiskCheckResult::Approved);
+        
+        // Three BTC orders use up BTC's allowance but not ETH's
+        for _ in 0..3 {
+            assert!(approved(&btc));
+            manager.record_order(&btc);
+        }
+        assert!(!approved(&btc));
+        assert!(approved(&eth));
+        
+        // Thirty seconds later two ETH orders use up the global allowance
+        clock.advance(Duration::from_secs(30));
+        manager.record_order(&eth);
+        manager.record_order(&eth);
+        assert!(!approved(&sol));
+        
+        // The BTC orders leave the window a minute after they were sent; the
+        // count carries over into a restored manager
+        clock.advance(Duration::from_secs(30));
+        let restored = RiskManager::new(manager.get_limits(), 100000.0).with_clock(clock.clone());
+        restored.restore(&manager.snapshot());
+        for manager in [&manager, &restored] {
+            assert!(matches!(manager.check_order(&btc, Side::Buy, 0.01, 100.0, 100000.0), RiskCheckResult::Approved));
+            manager.record_order(&eth);
+            assert!(matches!(manager.check_order(&eth, Side::Buy, 0.01, 100.0, 100000.0), RiskCheckResult::Rejected { .. }));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Rolling-window trading statistics
+//!
+//! The statistics updater feeds cumulative totals once per tick. They are
+//! bucketed by minute and each window keeps running sums over its buckets,
+//! so a snapshot costs the same however long the engine has been running.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Bucket width; Sharpe ratios are computed from per-bucket returns
+const BUCKET_MS: u64 = 60_000;
+
+/// Buckets per year, to annualise the Sharpe ratio
+const BUCKETS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0;
+
+/// Window lengths reported
+pub const ROLLING_WINDOWS: &[(&str, Duration)] = &[
+    ("1h", Duration::from_secs(3_600)),
+    ("24h", Duration::from_secs(86_400)),
+    ("7d", Duration::from_secs(604_800)),
+];
+
+/// Statistics over one trailing window
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct WindowStatistics {
+    pub window: String,
+    pub pnl: f64,
+    pub winning_trades: u64,
+    pub losing_trades: u64,
+    pub win_rate: f64, // Percent of closed trades
+    pub sharpe_ratio: f64, // Annualised from per-minute returns
+    pub turnover: f64, // Traded notional
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    start_ms: u64,
+    open_equity: f64,
+    pnl: f64,
+    wins: u64,
+    losses: u64,
+    notional: f64,
+}
+
+impl Bucket {
+    fn return_pct(&self) -> f64 {
+        if self.open_equity > 0.0 { self.pnl / self.open_equity } else { 0.0 }
+    }
+}
+
+/// Running sums over the buckets inside one window
+#[derive(Debug)]
+struct Window {
+    label: &'static str,
+    length_ms: u64,
+    buckets: VecDeque<Bucket>,
+    pnl: f64,
+    wins: u64,
+    losses: u64,
+    notional: f64,
+    return_sum: f64,
+    return_sq_sum: f64,
+}
+
+impl Window {
+    fn push(&mut self, bucket: Bucket) {
+        let r = bucket.return_pct();
+        self.pnl += bucket.pnl;
+        self.wins += bucket.wins;
+        self.losses += bucket.losses;
+        self.notional += bucket.notional;
+        self.return_sum += r;
+        self.return_sq_sum += r * r;
+        self.buckets.push_back(bucket);
+    }
+
+    /// Drop buckets that ended before the window start
+    fn evict(&mut self, now_ms: u64) {
+        while let Some(oldest) = self.buckets.front() {
+            if oldest.start_ms + BUCKET_MS + self.length_ms > now_ms {
+                break;
+            }
+            let r = oldest.return_pct();
+            self.pnl -= oldest.pnl;
+            self.wins -= oldest.wins;
+            self.losses -= oldest.losses;
+            self.notional -= oldest.notional;
+            self.return_sum -= r;
+            self.return_sq_sum -= r * r;
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Statistics including the bucket still filling up
+    fn snapshot(&self, current: Option<&Bucket>) -> WindowStatistics {
+        let (pnl, wins, losses, notional) = match current {
+            Some(c) => (self.pnl + c.pnl, self.wins + c.wins, self.losses + c.losses, self.notional + c.notional),
+            None => (self.pnl, self.wins, self.losses, self.notional),
+        };
+        let closed = wins + losses;
+
+        // Only complete buckets count towards the Sharpe ratio
+        let n = self.buckets.len() as f64;
+        let sharpe_ratio = if n >= 2.0 {
+            let mean = self.return_sum / n;
+            let std = (self.return_sq_sum / n - mean * mean).max(0.0).sqrt();
+            if std > 1e-12 { mean / std * BUCKETS_PER_YEAR.sqrt() } else { 0.0 }
+        } else {
+            0.0
+        };
+
+        WindowStatistics {
+            window: self.label.to_string(),
+            pnl,
+            winning_trades: wins,
+            losing_trades: losses,
+            win_rate: if closed > 0 { wins as f64 / closed as f64 * 100.0 } else { 0.0 },
+            sharpe_ratio,
+            turnover: notional,
+        }
+    }
+}
+
+/// Cumulative totals at one point in time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RollingSample {
+    pub equity: f64,
+    pub winning_trades: u64,
+    pub losing_trades: u64,
+    pub traded_volume: f64,
+}
+
+/// Incrementally maintained 1h / 24h / 7d statistics
+#[derive(Debug)]
+pub struct RollingStatistics {
+    windows: Vec<Window>,
+    current: Option<Bucket>,
+    last: Option<RollingSample>,
+}
+
+impl Default for RollingStatistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RollingStatistics {
+    pub fn new() -> Self {
+        Self {
+            windows: ROLLING_WINDOWS
+                .iter()
+                .map(|(label, length)| Window {
+                    label,
+                    length_ms: length.as_millis() as u64,
+                    buckets: VecDeque::new(),
+                    pnl: 0.0,
+                    wins: 0,
+                    losses: 0,
+                    notional: 0.0,
+                    return_sum: 0.0,
+                    return_sq_sum: 0.0,
+                })
+                .collect(),
+            current: None,
+            last: None,
+        }
+    }
+
+    /// Add the change since the previous sample
+    pub fn record(&mut self, now_ms: u64, sample: RollingSample) {
+        let previous = self.last.replace(sample).unwrap_or(sample);
+        let bucket_start = now_ms - now_ms % BUCKET_MS;
+
+        if let Some(current) = self.current {
+            if current.start_ms != bucket_start {
+                for window in &mut self.windows {
+                    window.push(current);
+                }
+                self.current = None;
+            }
+        }
+        for window in &mut self.windows {
+            window.evict(now_ms);
+        }
+
+        let current = self.current.get_or_insert(Bucket {
+            start_ms: bucket_start,
+            open_equity: previous.equity,
+            pnl: 0.0,
+            wins: 0,
+            losses: 0,
+            notional: 0.0,
+        });
+        current.pnl += sample.equity - previous.equity;
+        current.wins += sample.winning_trades.saturating_sub(previous.winning_trades);
+        current.losses += sample.losing_trades.saturating_sub(previous.losing_trades);
+        current.notional += (sample.traded_volume - previous.traded_volume).max(0.0);
+    }
+
+    pub fn snapshot(&self) -> Vec<WindowStatistics> {
+        self.windows.iter().map(|w| w.snapshot(self.current.as_ref())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_roll_off() {
+        let mut rolling = RollingStatistics::new();
+        let hour = 3_600_000;
+        let sample = |equity, wins, losses, volume| RollingSample {
+            equity,
+            winning_trades: wins,
+            losing_trades: losses,
+            traded_volume: volume,
+        };
+
+        rolling.record(0, sample(100_000.0, 0, 0, 0.0));
+        rolling.record(30_000, sample(100_500.0, 1, 0, 10_000.0));
+        rolling.record(2 * hour, sample(100_200.0, 1, 1, 15_000.0));
+
+        let stats = rolling.snapshot();
+        let (one_hour, one_day) = (&stats[0], &stats[1]);
+        assert_eq!(one_hour.window, "1h");
+
+        // The first minute has left the 1h window but not the 24h one
+        assert_eq!(one_hour.pnl, -300.0);
+        assert_eq!((one_hour.winning_trades, one_hour.losing_trades), (0, 1));
+        assert_eq!(one_hour.turnover, 5_000.0);
+        assert_eq!(one_day.pnl, 200.0);
+        assert_eq!(one_day.win_rate, 50.0);
+        assert_eq!(one_day.turnover, 15_000.0);
+    }
+}
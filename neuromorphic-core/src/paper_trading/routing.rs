@@ -0,0 +1,198 @@
+//! Signal routing rules that pick an account and sizing for each signal
+//!
+//! Rules are checked in order and the first enabled rule that matches wins.
+//! A signal already tagged with an account id skips routing.
+
+use super::TradingSignal;
+use crate::exchanges::{Exchange, Symbol};
+use anyhow::{bail, Result};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Send matching signals to an account, scaling their size.
+/// Empty match lists match anything; the confidence band is inclusive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteRule {
+    pub name: String,
+    pub account: String,
+    #[serde(default)]
+    pub symbols: Vec<Symbol>,
+    #[serde(default)]
+    pub exchanges: Vec<Exchange>,
+    #[serde(default)]
+    pub strategies: Vec<String>,
+    #[serde(default)]
+    pub min_confidence: Option<f64>,
+    #[serde(default)]
+    pub max_confidence: Option<f64>,
+    #[serde(default = "default_size_multiplier")]
+    pub size_multiplier: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_size_multiplier() -> f64 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RouteRule {
+    /// Rule matching every signal
+    pub fn new(name: impl Into<String>, account: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            account: account.into(),
+            symbols: Vec::new(),
+            exchanges: Vec::new(),
+            strategies: Vec::new(),
+            min_confidence: None,
+            max_confidence: None,
+            size_multiplier: default_size_multiplier(),
+            enabled: default_enabled(),
+        }
+    }
+
+    pub fn matches(&self, signal: &TradingSignal) -> bool {
+        let strategy = signal.metadata.strategy.as_deref();
+        (self.symbols.is_empty() || self.symbols.contains(&signal.symbol))
+            && (self.exchanges.is_empty() || self.exchanges.contains(&signal.exchange))
+            && (self.strategies.is_empty() || self.strategies.iter().any(|s| Some(s.as_str()) == strategy))
+            && self.min_confidence.is_none_or(|min| signal.confidence >= min)
+            && self.max_confidence.is_none_or(|max| signal.confidence <= max)
+    }
+}
+
+/// Ordered routing rules, editable while trading
+#[derive(Default)]
+pub struct SignalRouter {
+    rules: RwLock<Vec<RouteRule>>,
+}
+
+impl SignalRouter {
+    pub fn new(rules: Vec<RouteRule>) -> Self {
+        Self {
+            rules: RwLock::new(rules),
+        }
+    }
+
+    pub fn rules(&self) -> Vec<RouteRule> {
+        self.rules.read().clone()
+    }
+
+    /// Append a rule; it is checked after the existing ones
+    pub fn add_rule(&self, rule: RouteRule) -> Result<()> {
+        let mut rules = self.rules.write();
+        if rules.iter().any(|r| r.name == rule.name) {
+            bail!("Route '{}' already exists", rule.name);
+        }
+        rules.push(rule);
+        Ok(())
+    }
+
+    pub fn remove_rule(&self, name: &str) -> Option<RouteRule> {
+        let mut rules = self.rules.write();
+        let index = rules.iter().position(|r| r.name == name)?;
+        Some(rules.remove(index))
+    }
+
+    /// Turn a rule on or off; disabled rules are skipped
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        self.modify_rule(name, |rule| rule.enabled = enabled)
+    }
+
+    pub fn set_size_multiplier(&self, name: &str, size_multiplier: f64) -> Result<()> {
+        if size_multiplier <= 0.0 {
+            bail!("Size multiplier must be positive, got {}", size_multiplier);
+        }
+        self.modify_rule(name, |rule| rule.size_multiplier = size_multiplier)
+    }
+
+    /// First enabled rule matching the signal
+    pub fn matching_rule(&self, signal: &TradingSignal) -> Option<RouteRule> {
+        self.rules.read().iter().find(|r| r.enabled && r.matches(signal)).cloned()
+    }
+
+    /// Tag the signal with the account and size multiplier of its matching rule.
+    /// Signals that already name an account or match no rule pass through unchanged.
+    pub fn route(&self, mut signal: TradingSignal) -> TradingSignal {
+        if signal.metadata.account_id.is_some() {
+            return signal;
+        }
+
+        if let Some(rule) = self.matching_rule(&signal) {
+            debug!(route = %rule.name, account = %rule.account, symbol = %signal.symbol, "Signal routed");
+            signal.metadata.account_id = Some(rule.account);
+            signal.metadata.size_multiplier =
+                Some(signal.metadata.size_multiplier.unwrap_or(1.0) * rule.size_multiplier);
+        }
+        signal
+    }
+
+    fn modify_rule(&self, name: &str, f: impl FnOnce(&mut RouteRule)) -> Result<()> {
+        let mut rules = self.rules.write();
+        match rules.iter_mut().find(|r| r.name == name) {
+            Some(rule) => {
+                f(rule);
+                Ok(())
+            }
+            None => bail!("Route '{}' not found", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paper_trading::{SignalAction, SignalMetadata};
+
+    fn signal(symbol: &str, strategy: &str, confidence: f64) -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new(symbol),
+            exchange: Exchange::Binance,
+            action: SignalAction::Buy { size_hint: None },
+            confidence,
+            urgency: 0.5,
+            metadata: SignalMetadata {
+                strategy: Some(strategy.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_route_rules() {
+        let router = SignalRouter::new(vec![
+            RouteRule {
+                strategies: vec!["Momentum Breakout".to_string()],
+                min_confidence: Some(0.8),
+                size_multiplier: 0.5,
+                ..RouteRule::new("momentum", "aggressive")
+            },
+            RouteRule {
+                symbols: vec![Symbol::new("ETH-USD")],
+                ..RouteRule::new("eth", "crypto")
+            },
+        ]);
+
+        let routed = router.route(signal("BTC-USD", "Momentum Breakout", 0.9));
+        assert_eq!(routed.metadata.account_id.as_deref(), Some("aggressive"));
+        assert_eq!(routed.metadata.size_multiplier, Some(0.5));
+
+        // Below the confidence band, and no other rule matches
+        let routed = router.route(signal("BTC-USD", "Momentum Breakout", 0.7));
+        assert_eq!(routed.metadata.account_id, None);
+
+        let routed = router.route(signal("ETH-USD", "Gap Trading", 0.5));
+        assert_eq!(routed.metadata.account_id.as_deref(), Some("crypto"));
+
+        // Disabled at runtime
+        router.set_enabled("eth", false).unwrap();
+        assert_eq!(router.route(signal("ETH-USD", "Gap Trading", 0.5)).metadata.account_id, None);
+        assert!(router.set_enabled("missing", true).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! What-if scenarios on the open portfolio
+//!
+//! Each scenario moves the current prices by a hypothetical shock and reports
+//! what the open positions would gain or lose, and whether the account would
+//! then be short of margin or below its equity stop-out. Nothing is traded;
+//! the positions and prices are copies.
+//!
+//! Volatility-based shocks use each symbol's daily volatility as estimated by
+//! the risk manager from the prices it has seen, or
+//! [`DEFAULT_DAILY_VOLATILITY`] until enough prices have arrived.
+
+use super::currency::CurrencyConverter;
+use crate::exchanges::{Side, Symbol};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Daily volatility assumed for symbols without an estimate yet
+pub const DEFAULT_DAILY_VOLATILITY: f64 = 0.04;
+
+/// Hypothetical price move
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Shock {
+    /// Move every symbol with this base asset (e.g. "BTC") by a percentage
+    Asset { asset: String, move_pct: f64 },
+    /// Move every symbol by this many daily volatilities; negative is down
+    Sigma { sigmas: f64 },
+    /// Correlations go to one: every symbol moves this many daily
+    /// volatilities in the same direction, whichever hurts the book more
+    CorrelationStress { sigmas: f64 },
+}
+
+/// Named shock
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub shock: Shock,
+}
+
+impl Scenario {
+    pub fn new(name: impl Into<String>, shock: Shock) -> Self {
+        Self {
+            name: name.into(),
+            shock,
+        }
+    }
+
+    /// BTC -20%, every symbol up and down 2σ, and a 2σ move at correlation one
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("BTC -20%", Shock::Asset { asset: "BTC".to_string(), move_pct: -20.0 }),
+            Self::new("All +2σ", Shock::Sigma { sigmas: 2.0 }),
+            Self::new("All -2σ", Shock::Sigma { sigmas: -2.0 }),
+            Self::new("Correlation 1, 2σ", Shock::CorrelationStress { sigmas: 2.0 }),
+        ]
+    }
+}
+
+/// Open position as seen by the scenarios
+#[derive(Clone, Debug)]
+pub struct ScenarioPosition {
+    pub position_id: String,
+    pub symbol: Symbol,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+    pub daily_volatility: f64,
+    pub fx_rate: f64, // Reporting currency per unit of the symbol's quote currency
+}
+
+/// Effect of a scenario on one position
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PositionShock {
+    pub position_id: String,
+    pub symbol: Symbol,
+    pub move_pct: f64,
+    pub pnl: f64,
+}
+
+/// Effect of a scenario on the account, in the reporting currency
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub shock: Shock,
+    pub pnl: f64,
+    pub pnl_pct: f64, // Of current equity
+    pub equity: f64,  // After the shock
+    pub margin_required: f64, // Gross exposure after the shock over the leverage limit
+    pub margin_change: f64,
+    pub margin_call: bool, // Equity would not cover the margin required
+    pub stop_out: bool,    // Equity would fall below the equity stop-out level
+    pub positions: Vec<PositionShock>,
+}
+
+/// Scenario results for the current portfolio
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ScenarioReport {
+    pub timestamp: u64, // Unix millis
+    pub equity: f64,
+    pub margin_required: f64,
+    pub results: Vec<ScenarioResult>,
+}
+
+/// Account limits the scenarios are checked against
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MarginLimits {
+    pub max_leverage: f64,
+    pub stop_out_equity: f64, // Zero when the stop-out is disabled
+}
+
+fn margin(positions: &[ScenarioPosition], moves: &[f64], limits: MarginLimits) -> f64 {
+    let gross: f64 = positions
+        .iter()
+        .zip(moves)
+        .map(|(p, m)| p.quantity * p.price * (1.0 + m) * p.fx_rate)
+        .sum();
+    if limits.max_leverage > 0.0 { gross / limits.max_leverage } else { gross }
+}
+
+/// Fractional price move of each position under a shock
+fn moves(shock: &Shock, positions: &[ScenarioPosition]) -> Vec<f64> {
+    match shock {
+        Shock::Asset { asset, move_pct } => positions
+            .iter()
+            .map(|p| {
+                let base = CurrencyConverter::split_symbol(&p.symbol).map_or_else(|| p.symbol.0.clone(), |(base, _)| base);
+                if base.eq_ignore_ascii_case(asset) { move_pct / 100.0 } else { 0.0 }
+            })
+            .collect(),
+        Shock::Sigma { sigmas } => positions.iter().map(|p| sigmas * p.daily_volatility).collect(),
+        Shock::CorrelationStress { sigmas } => {
+            let up = moves(&Shock::Sigma { sigmas: sigmas.abs() }, positions);
+            let down = moves(&Shock::Sigma { sigmas: -sigmas.abs() }, positions);
+            let pnl = |moves: &[f64]| positions.iter().zip(moves).map(|(p, m)| position_pnl(p, *m)).sum::<f64>();
+            if pnl(&up) < pnl(&down) { up } else { down }
+        }
+    }
+}
+
+fn position_pnl(position: &ScenarioPosition, price_move: f64) -> f64 {
+    // Prices cannot go below zero
+    let price_move = price_move.max(-1.0);
+    position.side.multiplier() * position.quantity * position.price * price_move * position.fx_rate
+}
+
+pub(crate) fn run(scenarios: &[Scenario], positions: &[ScenarioPosition], equity: f64, limits: MarginLimits, timestamp: u64) -> ScenarioReport {
+    let margin_required = margin(positions, &vec![0.0; positions.len()], limits);
+    let results = scenarios
+        .iter()
+        .map(|scenario| {
+            let moves: Vec<f64> = moves(&scenario.shock, positions).into_iter().map(|m| m.max(-1.0)).collect();
+            let shocks: Vec<PositionShock> = positions
+                .iter()
+                .zip(&moves)
+                .map(|(p, m)| PositionShock {
+                    position_id: p.position_id.clone(),
+                    symbol: p.symbol.clone(),
+                    move_pct: m * 100.0,
+                    pnl: position_pnl(p, *m),
+                })
+                .collect();
+            let pnl: f64 = shocks.iter().map(|s| s.pnl).sum();
+            let shocked_equity = equity + pnl;
+            let shocked_margin = margin(positions, &moves, limits);
+            ScenarioResult {
+                name: scenario.name.clone(),
+                shock: scenario.shock.clone(),
+                pnl,
+                pnl_pct: if equity > 0.0 { pnl / equity * 100.0 } else { 0.0 },
+                equity: shocked_equity,
+                margin_required: shocked_margin,
+                margin_change: shocked_margin - margin_required,
+                margin_call: shocked_equity < shocked_margin,
+                stop_out: limits.stop_out_equity > 0.0 && shocked_equity < limits.stop_out_equity,
+                positions: shocks,
+            }
+        })
+        .collect();
+
+    ScenarioReport {
+        timestamp,
+        equity,
+        margin_required,
+        results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, symbol: &str, side: Side, quantity: f64, price: f64, daily_volatility: f64) -> ScenarioPosition {
+        ScenarioPosition {
+            position_id: id.to_string(),
+            symbol: Symbol::new(symbol),
+            side,
+            quantity,
+            price,
+            daily_volatility,
+            fx_rate: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_default_scenarios() {
+        // Long 1 BTC at 50k (3% vol), short 5 ETH at 3k (5% vol)
+        let positions = vec![
+            position("btc", "BTC-USD", Side::Buy, 1.0, 50_000.0, 0.03),
+            position("eth", "ETH-USDT", Side::Sell, 5.0, 3_000.0, 0.05),
+        ];
+        let limits = MarginLimits { max_leverage: 2.0, stop_out_equity: 32_000.0 };
+        let report = run(&Scenario::defaults(), &positions, 40_000.0, limits, 0);
+        assert!((report.margin_required - 32_500.0).abs() < 1e-9);
+        let result = |name: &str| report.results.iter().find(|r| r.name == name).unwrap();
+
+        // Only the BTC position moves: equity 30k against 27.5k margin
+        let btc = result("BTC -20%");
+        assert!((btc.pnl + 10_000.0).abs() < 1e-9);
+        assert_eq!(btc.positions[1].pnl, 0.0);
+        assert!((btc.margin_change + 5_000.0).abs() < 1e-9);
+        assert!(btc.stop_out && !btc.margin_call);
+
+        // Up 2σ: BTC +6% earns 3000, the ETH short loses 1500
+        assert!((result("All +2σ").pnl - 1_500.0).abs() < 1e-9);
+        assert!((result("All -2σ").pnl + 1_500.0).abs() < 1e-9);
+
+        // At correlation one the worse direction is chosen
+        assert_eq!(result("Correlation 1, 2σ").pnl, result("All -2σ").pnl);
+    }
+}
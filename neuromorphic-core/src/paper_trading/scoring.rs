@@ -0,0 +1,208 @@
+//! Competition scores of strategies run side by side
+//!
+//! A tournament's competitors are accounts running concurrently, or saved
+//! sessions. Raw return favours whoever took the most risk, so each is scored
+//! on three components, standardized over the field as z-scores:
+//!
+//! - risk-adjusted return: total return over the volatility of the run, the
+//!   standard deviation of its period returns scaled to the run's length
+//! - consistency: the share of periods that gained, of those that moved
+//! - maximum drawdown, which counts against the score
+//!
+//! The score is their weighted sum, so it ranks a competitor against the
+//! others rather than on an absolute scale. Periods are the engine's
+//! statistics updates for an account and the trades for a session, so a
+//! tournament should compare competitors of one kind. Competitors with fewer
+//! trades than the minimum are listed but not ranked.
+
+use super::accounts::AccountStatistics;
+use crate::reports::SessionReport;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use utoipa::ToSchema;
+
+/// Weights of the score components, `[api.scoring]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringConfig {
+    pub return_weight: f64,
+    pub consistency_weight: f64,
+    pub drawdown_weight: f64,
+    pub min_trades: u64, // Closed trades a competitor needs to be ranked
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            return_weight: 1.0,
+            consistency_weight: 0.5,
+            drawdown_weight: 1.0,
+            min_trades: 1,
+        }
+    }
+}
+
+/// What a competitor is scored on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Competitor {
+    pub id: String,
+    pub total_return_pct: f64,
+    pub volatility_pct: f64, // Of the whole run
+    pub gaining_periods: u64,
+    pub losing_periods: u64,
+    pub max_drawdown_pct: f64,
+    pub trades: u64,
+}
+
+impl Competitor {
+    /// From period returns, as fractions
+    pub fn from_returns(id: impl Into<String>, returns: &[f64], total_return_pct: f64, max_drawdown_pct: f64, trades: u64) -> Self {
+        let n = returns.len() as f64;
+        let volatility_pct = if returns.len() > 1 {
+            let mean = returns.iter().sum::<f64>() / n;
+            let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            variance.sqrt() * n.sqrt() * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            id: id.into(),
+            total_return_pct,
+            volatility_pct,
+            gaining_periods: returns.iter().filter(|r| **r > 0.0).count() as u64,
+            losing_periods: returns.iter().filter(|r| **r < 0.0).count() as u64,
+            max_drawdown_pct,
+            trades,
+        }
+    }
+
+    /// A running account, from its statistics and the engine's returns
+    pub fn from_account(stats: &AccountStatistics, returns: &[f64]) -> Self {
+        let trades = stats.winning_trades + stats.losing_trades;
+        Self::from_returns(stats.account_id.clone(), returns, stats.total_return_pct, stats.max_drawdown * 100.0, trades)
+    }
+
+    /// A saved session, one period per trade of its equity curve
+    pub fn from_session(id: impl Into<String>, report: &SessionReport) -> Self {
+        let mut previous = report.initial_capital;
+        let returns: Vec<f64> = report
+            .equity_curve
+            .iter()
+            .map(|point| {
+                let period = if previous > 0.0 { point.equity / previous - 1.0 } else { 0.0 };
+                previous = point.equity;
+                period
+            })
+            .collect();
+        Self::from_returns(id, &returns, report.total_return_pct, report.max_drawdown_pct, report.trades.len() as u64)
+    }
+
+    pub fn risk_adjusted_return(&self) -> f64 {
+        if self.volatility_pct > 0.0 { self.total_return_pct / self.volatility_pct } else { 0.0 }
+    }
+
+    /// Share of the periods that moved which gained; 0.5 when none did
+    pub fn consistency(&self) -> f64 {
+        let moved = self.gaining_periods + self.losing_periods;
+        if moved > 0 { self.gaining_periods as f64 / moved as f64 } else { 0.5 }
+    }
+}
+
+/// One competitor's standing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CompetitionScore {
+    pub rank: Option<usize>, // From 1; none below the minimum trades
+    pub id: String,
+    pub score: Option<f64>,
+    pub total_return_pct: f64,
+    pub risk_adjusted_return: f64,
+    pub consistency: f64,
+    pub max_drawdown_pct: f64,
+    pub trades: u64,
+}
+
+/// Competitors ranked by score, then those too new to rank
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Scoreboard {
+    pub entries: Vec<CompetitionScore>,
+}
+
+impl Scoreboard {
+    pub fn score(competitors: &[Competitor], config: &ScoringConfig) -> Self {
+        let (ranked, unranked): (Vec<&Competitor>, Vec<&Competitor>) = competitors.iter().partition(|c| c.trades >= config.min_trades);
+        let risk_adjusted = standardize(ranked.iter().map(|c| c.risk_adjusted_return()));
+        let consistency = standardize(ranked.iter().map(|c| c.consistency()));
+        let drawdown = standardize(ranked.iter().map(|c| c.max_drawdown_pct));
+
+        let mut scored: Vec<(f64, &Competitor)> = ranked
+            .iter()
+            .enumerate()
+            .map(|(i, competitor)| {
+                let score = config.return_weight * risk_adjusted[i] + config.consistency_weight * consistency[i] - config.drawdown_weight * drawdown[i];
+                (score, *competitor)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let entry = |rank: Option<usize>, score: Option<f64>, c: &Competitor| CompetitionScore {
+            rank,
+            id: c.id.clone(),
+            score,
+            total_return_pct: c.total_return_pct,
+            risk_adjusted_return: c.risk_adjusted_return(),
+            consistency: c.consistency(),
+            max_drawdown_pct: c.max_drawdown_pct,
+            trades: c.trades,
+        };
+        let mut entries: Vec<CompetitionScore> = scored.into_iter().enumerate().map(|(i, (score, c))| entry(Some(i + 1), Some(score), c)).collect();
+        entries.extend(unranked.into_iter().map(|c| entry(None, None, c)));
+        Self { entries }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("rank,id,score,total_return_pct,risk_adjusted_return,consistency,max_drawdown_pct,trades\n");
+        for e in &self.entries {
+            let rank = e.rank.map(|rank| rank.to_string()).unwrap_or_default();
+            let score = e.score.map(|score| format!("{:.4}", score)).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{},{},{},{:.4},{:.4},{:.4},{:.4},{}",
+                rank, e.id, score, e.total_return_pct, e.risk_adjusted_return, e.consistency, e.max_drawdown_pct, e.trades
+            );
+        }
+        out
+    }
+}
+
+/// Z-scores of `values`; all zero when they don't vary
+fn standardize(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let values: Vec<f64> = values.collect();
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    values.iter().map(|v| if std_dev > 0.0 { (v - mean) / std_dev } else { 0.0 }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steady_returns_outscore_a_lucky_swing() {
+        let steady = Competitor::from_returns("steady", &[0.01, 0.01, 0.012, -0.002, 0.01, 0.01], 5.0, 0.2, 6);
+        let swing = Competitor::from_returns("swing", &[0.3, -0.2, 0.25, -0.15, 0.1, -0.05], 12.0, 20.0, 6);
+        let flat = Competitor::from_returns("flat", &[0.0, 0.001, -0.001, 0.0, 0.0, 0.0], 0.0, 0.1, 6);
+        let new = Competitor::from_returns("new", &[], 0.0, 0.0, 0);
+        let board = Scoreboard::score(&[swing, flat, new, steady], &ScoringConfig::default());
+
+        let order: Vec<_> = board.entries.iter().map(|e| (e.rank, e.id.as_str())).collect();
+        assert_eq!(order, [(Some(1), "steady"), (Some(2), "flat"), (Some(3), "swing"), (None, "new")]);
+        assert!((board.entries[0].consistency - 5.0 / 6.0).abs() < 1e-9);
+        assert!(board.entries[0].risk_adjusted_return > board.entries[2].risk_adjusted_return);
+        assert!(board.to_csv().lines().last().unwrap().starts_with(",new,,"));
+
+        // Alone, a competitor is as good as the field
+        let board = Scoreboard::score(&[Competitor::from_returns("solo", &[0.01, -0.01], 0.0, 1.0, 2)], &ScoringConfig::default());
+        assert_eq!(board.entries[0].score, Some(0.0));
+    }
+}
@@ -0,0 +1,129 @@
+//! Implementation shortfall of filled orders
+//!
+//! Orders submitted for signals and exits carry two benchmark prices: the
+//! decision price the strategy acted on and the arrival price, the market
+//! price when the order was submitted. Against the average fill price they
+//! split the cost of trading into
+//!
+//! - delay, decision to arrival: the market moving while the signal was in flight
+//! - execution, arrival to fill: slippage, negative when the order filled
+//!   better than it arrived (price improvement)
+//!
+//! which add up to the shortfall, decision to fill. Costs are in the quote
+//! currency and positive when they hurt; the basis points are of the decision
+//! notional, so they are notional weighted when aggregated.
+
+use super::order_manager::Order;
+use crate::exchanges::Side;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Costs of one order's fills
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrderShortfall {
+    pub notional: f64, // Filled quantity at the decision price
+    pub delay_cost: f64,
+    pub execution_cost: f64,
+}
+
+impl OrderShortfall {
+    /// None until the order has filled, or when it carries no benchmarks
+    pub fn of(order: &Order) -> Option<Self> {
+        let (decision, arrival) = (order.decision_price?, order.arrival_price?);
+        if order.filled_quantity <= 0.0 || decision <= 0.0 {
+            return None;
+        }
+        let sign = if order.side == Side::Buy { 1.0 } else { -1.0 };
+        Some(Self {
+            notional: decision * order.filled_quantity,
+            delay_cost: sign * (arrival - decision) * order.filled_quantity,
+            execution_cost: sign * (order.avg_fill_price - arrival) * order.filled_quantity,
+        })
+    }
+}
+
+/// Shortfall totals of a set of orders
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShortfallStats {
+    pub orders: u64,
+    pub improved_orders: u64, // Filled better than their arrival price
+    pub notional: f64,
+    pub delay_cost: f64,
+    pub execution_cost: f64,
+    pub shortfall_cost: f64,
+    pub delay_bps: f64,
+    pub execution_bps: f64,
+    pub shortfall_bps: f64,
+}
+
+impl ShortfallStats {
+    pub fn add(&mut self, order: OrderShortfall) {
+        self.orders += 1;
+        if order.execution_cost < 0.0 {
+            self.improved_orders += 1;
+        }
+        self.notional += order.notional;
+        self.delay_cost += order.delay_cost;
+        self.execution_cost += order.execution_cost;
+        self.shortfall_cost = self.delay_cost + self.execution_cost;
+
+        let bps = |cost: f64| if self.notional > 0.0 { cost / self.notional * 10_000.0 } else { 0.0 };
+        (self.delay_bps, self.execution_bps, self.shortfall_bps) = (bps(self.delay_cost), bps(self.execution_cost), bps(self.shortfall_cost));
+    }
+}
+
+/// Shortfall in total, per strategy and per symbol
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShortfallReport {
+    pub total: ShortfallStats,
+    pub by_strategy: BTreeMap<String, ShortfallStats>, // "unattributed" for orders of no strategy
+    pub by_symbol: BTreeMap<String, ShortfallStats>,
+}
+
+impl ShortfallReport {
+    pub fn from_orders<'a>(orders: impl IntoIterator<Item = &'a Order>) -> Self {
+        let mut report = Self::default();
+        orders.into_iter().for_each(|order| report.add(order));
+        report
+    }
+
+    /// Count `order` in, if it has filled and carries its benchmarks
+    pub fn add(&mut self, order: &Order) {
+        let Some(shortfall) = OrderShortfall::of(order) else { return };
+        let strategy = order.strategy.clone().unwrap_or_else(|| "unattributed".to_string());
+        self.total.add(shortfall);
+        self.by_strategy.entry(strategy).or_default().add(shortfall);
+        self.by_symbol.entry(order.symbol.to_string()).or_default().add(shortfall);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Symbol};
+
+    fn filled(side: Side, decision: f64, arrival: f64, fill: f64, strategy: &str) -> Order {
+        let mut order = Order::market(Symbol::new("BTC-USD"), Exchange::Binance, side, 2.0);
+        (order.decision_price, order.arrival_price, order.strategy) = (Some(decision), Some(arrival), Some(strategy.to_string()));
+        (order.filled_quantity, order.avg_fill_price) = (2.0, fill);
+        order
+    }
+
+    #[test]
+    fn test_shortfall_splits_into_delay_and_execution() {
+        let orders = [
+            filled(Side::Buy, 100.0, 101.0, 101.5, "momentum"), // Chased the move
+            filled(Side::Sell, 100.0, 100.0, 100.2, "reversion"), // Limit filled above arrival
+            Order::market(Symbol::new("ETH-USD"), Exchange::Binance, Side::Buy, 1.0), // Unfilled
+        ];
+        let report = ShortfallReport::from_orders(&orders);
+
+        let momentum = &report.by_strategy["momentum"];
+        assert_eq!((momentum.delay_cost, momentum.execution_cost, momentum.shortfall_bps), (2.0, 1.0, 150.0));
+        let reversion = &report.by_strategy["reversion"];
+        assert_eq!(reversion.improved_orders, 1);
+        assert!((reversion.execution_bps + 20.0).abs() < 1e-9);
+        assert_eq!((report.total.orders, report.by_symbol["BTC-USD"].notional), (2, 400.0));
+        assert!((report.total.shortfall_bps - 65.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,160 @@
+//! Engine snapshots
+//!
+//! A snapshot holds everything a stopped engine needs to carry on exactly
+//! where another left off: positions, open orders, capital, risk state,
+//! throttle windows and the position of the ID sequences. Restoring one into
+//! a fresh engine resumes a checkpointed experiment; restoring the same one
+//! into two engines forks it into what-if branches.
+//!
+//! With `PaperTradingConfig::seed` or `id_seed` set, position and order IDs
+//! come from a seeded sequence, see `rng`, whose position is part of the
+//! snapshot along with that of the engine's other random numbers, so a
+//! restored engine driven by the same clock and inputs produces the same
+//! IDs, fills and P&L as the original.
+
+use super::engine::EntryPlan;
+use super::order_manager::OrderBook;
+use super::position_manager::PositionBook;
+use super::risk_manager::RiskState;
+use super::throttle::ThrottleState;
+use crate::exchanges::Symbol;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use super::rng::SeededRng;
+
+/// Format version written into snapshots; others are refused on restore
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Full state of a paper trading engine, see `PaperTradingEngine::snapshot`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub version: u32,
+    pub taken_at: u64, // Engine clock, unix millis
+    pub initial_capital: f64,
+    pub capital: f64,
+    pub id_seed: Option<u64>,
+    #[serde(default)]
+    pub rng_draws: u64, // Taken from the engine's `rng`
+    pub prices: Vec<(Symbol, f64)>,
+    pub positions: PositionBook,
+    pub orders: OrderBook,
+    pub risk: RiskState,
+    pub throttle: ThrottleState,
+    pub returns_history: Vec<f64>,
+    pub signals_processed: u64,
+    pub signals_executed: u64,
+    pub(crate) entry_plans: Vec<(String, EntryPlan)>, // Position settings of entry orders still open
+}
+
+impl EngineSnapshot {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Invalid engine snapshot")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json()?).with_context(|| format!("Failed to write snapshot {}", path.display()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+        Self::from_json(&json)
+    }
+}
+
+/// Reproducible IDs: a seed and the number of IDs issued from it
+pub(crate) struct IdSequence {
+    rng: SeededRng,
+}
+
+impl IdSequence {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { rng: SeededRng::new(seed) }
+    }
+
+    /// Next ID, in the same `PREFIX_<millis>_<suffix>` shape as random IDs
+    pub(crate) fn next(&self, prefix: &str, now_ms: u64) -> String {
+        format!("{}_{}_{:016x}", prefix, now_ms, self.rng.next_u64())
+    }
+
+    pub(crate) fn issued(&self) -> u64 {
+        self.rng.draws()
+    }
+
+    pub(crate) fn set_issued(&self, issued: u64) {
+        self.rng.set_draws(issued);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::{PaperTradingConfig, PaperTradingEngine, SignalAction, SignalMetadata, SimulatedClock, TradingSignal};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn buy() -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new("ETH-USD"),
+            exchange: Exchange::Binance,
+            action: SignalAction::Buy { size_hint: Some(2000.0) },
+            confidence: 0.8,
+            urgency: 0.9,
+            metadata: SignalMetadata::default(),
+        }
+    }
+
+    fn open_ids(engine: &PaperTradingEngine) -> Vec<String> {
+        let mut ids: Vec<String> = engine.position_manager().get_open_positions().into_iter().map(|p| p.id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_forks_resume_identically() {
+        let config = PaperTradingConfig { id_seed: Some(7), ..Default::default() };
+        let start_ms = 1_700_000_000_000;
+        let mut original = PaperTradingEngine::with_clock(config.clone(), Arc::new(SimulatedClock::new(start_ms)));
+        original.start().await.unwrap();
+        original.update_price(Symbol::new("ETH-USD"), 2000.0);
+        original.process_signal(buy()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(open_ids(&original).len(), 1);
+
+        let snapshot = EngineSnapshot::from_json(&original.snapshot().to_json().unwrap()).unwrap();
+        assert!(original.restore(&snapshot).is_err()); // Still running
+        original.stop().await.unwrap();
+        let unseeded = PaperTradingEngine::new(PaperTradingConfig::default());
+        assert!(unseeded.restore(&snapshot).is_err());
+
+        // Two branches from the same checkpoint take the same next trade
+        let mut branches = Vec::new();
+        for _ in 0..2 {
+            let mut branch = PaperTradingEngine::with_clock(config.clone(), Arc::new(SimulatedClock::new(snapshot.taken_at)));
+            branch.restore(&snapshot).unwrap();
+            assert_eq!(open_ids(&branch), open_ids(&original));
+            branch.start().await.unwrap();
+            branch.update_price(Symbol::new("ETH-USD"), 2010.0);
+            branch.process_signal(buy()).await.unwrap();
+            branches.push(branch);
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let (a, b) = (&branches[0], &branches[1]);
+        assert_eq!(open_ids(a).len(), 2);
+        assert_eq!(open_ids(a), open_ids(b));
+        let pnl = |engine: &PaperTradingEngine| engine.position_manager().total_pnl();
+        assert_eq!(pnl(a), pnl(b));
+        assert_eq!(a.snapshot().to_json().unwrap(), b.snapshot().to_json().unwrap());
+        for branch in &branches {
+            branch.stop().await.unwrap();
+        }
+    }
+}
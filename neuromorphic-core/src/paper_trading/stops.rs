@@ -0,0 +1,117 @@
+//! Volatility-scaled stop placement
+//!
+//! A 2% stop is inside the noise of a small cap and far outside that of a
+//! treasury ETF, so fixed percentages from `RiskLimits` stop out one kind of
+//! asset on every wiggle and let the other run far past its usual range. In
+//! ATR mode the stop-loss and take-profit sit a multiple of the average true
+//! range away from the fill instead, taken over the bars the scanner's
+//! `PriceHistory` aggregates. The ATR is measured when the signal arrives;
+//! symbols without enough history yet fall back to the percentages.
+
+use crate::exchanges::{Side, Symbol};
+use crate::market_scanner::{Granularity, PriceBar, PriceHistory};
+use serde::{Deserialize, Serialize};
+
+const HOUR_MS: u64 = 3_600_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopMode {
+    #[default]
+    Percent, // risk_limits.stop_loss_pct and take_profit_pct of the entry price
+    Atr, // Multiples of the average true range
+}
+
+/// How the exit levels of new positions are placed
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StopPlacement {
+    pub mode: StopMode,
+    pub granularity: Granularity, // Of the bars the ATR is taken over
+    pub atr_period: usize,
+    pub stop_multiple: f64,
+    pub take_profit_multiple: f64,
+}
+
+impl Default for StopPlacement {
+    fn default() -> Self {
+        Self {
+            mode: StopMode::Percent,
+            granularity: Granularity::FiveMinutes,
+            atr_period: 14,
+            stop_multiple: 2.0,
+            take_profit_multiple: 3.0,
+        }
+    }
+}
+
+impl StopPlacement {
+    /// ATR of the symbol's recorded bars; None in percent mode or while
+    /// fewer than `atr_period + 1` bars are recorded
+    pub fn atr(&self, history: &PriceHistory, symbol: &Symbol) -> Option<f64> {
+        if self.mode != StopMode::Atr {
+            return None;
+        }
+        let bars = history.bars(symbol, self.granularity, self.history_hours())?;
+        average_true_range(&bars, self.atr_period)
+    }
+
+    /// Stop-loss and take-profit for an entry at `price`; None when either
+    /// would not be a positive price
+    pub fn levels(&self, side: Side, price: f64, atr: f64) -> Option<(f64, f64)> {
+        let sign = if side == Side::Buy { 1.0 } else { -1.0 };
+        let stop_loss = price - sign * self.stop_multiple * atr;
+        let take_profit = price + sign * self.take_profit_multiple * atr;
+        (atr > 0.0 && stop_loss > 0.0 && take_profit > 0.0).then_some((stop_loss, take_profit))
+    }
+
+    /// Hours of history the ATR needs
+    pub fn history_hours(&self) -> u64 {
+        (self.granularity.millis() * (self.atr_period as u64 + 2)).div_ceil(HOUR_MS)
+    }
+}
+
+/// Wilder's average true range over `period` bars, oldest first; needs one
+/// bar more for the first true range
+pub fn average_true_range(bars: &[PriceBar], period: usize) -> Option<f64> {
+    if period == 0 || bars.len() <= period {
+        return None;
+    }
+    let ranges: Vec<f64> = bars
+        .windows(2)
+        .map(|pair| {
+            let (previous, bar) = (&pair[0], &pair[1]);
+            (bar.high - bar.low).max((bar.high - previous.close).abs()).max((bar.low - previous.close).abs())
+        })
+        .collect();
+    let first = ranges[..period].iter().sum::<f64>() / period as f64;
+    Some(ranges[period..].iter().fold(first, |atr, range| (atr * (period - 1) as f64 + range) / period as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_scanner::MarketData;
+    use chrono::DateTime;
+
+    #[test]
+    fn test_atr_levels_follow_volatility() {
+        let history = PriceHistory::new(24);
+        let atr = StopPlacement { mode: StopMode::Atr, granularity: Granularity::OneMinute, atr_period: 3, ..Default::default() };
+        let symbol = Symbol::new("BTCUSDT");
+        // Closes alternating 100 and 102: every true range is 2
+        for minute in 0..4 {
+            let mut data = MarketData::new(symbol.clone(), if minute % 2 == 0 { 100.0 } else { 102.0 });
+            data.timestamp = DateTime::from_timestamp_millis(minute * 60_000).unwrap();
+            assert_eq!(atr.atr(&history, &symbol), None);
+            history.record(&data);
+        }
+        assert_eq!(atr.atr(&history, &symbol), Some(2.0));
+        assert_eq!(StopPlacement::default().atr(&history, &symbol), None);
+
+        assert_eq!(atr.levels(Side::Buy, 100.0, 2.0), Some((96.0, 106.0)));
+        assert_eq!(atr.levels(Side::Sell, 100.0, 2.0), Some((104.0, 94.0)));
+        assert_eq!(atr.levels(Side::Sell, 10.0, 4.0), None);
+        assert_eq!(StopPlacement::default().history_hours(), 2);
+    }
+}
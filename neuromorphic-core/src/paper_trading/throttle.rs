@@ -0,0 +1,198 @@
+//! Per-symbol signal throttling
+//!
+//! A misbehaving model can send dozens of signals for one symbol in a second.
+//! Each symbol gets a cooldown after an accepted signal and a cap on signals
+//! per interval; signals over either limit are counted and dropped instead of
+//! executed. Close and Hold signals are never throttled, since neither adds
+//! exposure.
+
+use super::engine::{SignalAction, TradingSignal};
+use crate::exchanges::Symbol;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Throttling limits; zero disables a limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThrottleConfig {
+    /// Minimum time between accepted signals for a symbol
+    pub cooldown_ms: u64,
+    /// Accepted signals allowed per symbol within `interval_ms`
+    pub max_signals: u32,
+    pub interval_ms: u64,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            cooldown_ms: 0,
+            max_signals: 0,
+            interval_ms: 1000,
+        }
+    }
+}
+
+/// Why a signal was throttled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleReason {
+    Cooldown,
+    RateLimit,
+}
+
+/// Throttled signal counters
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThrottleStatistics {
+    pub cooldown: u64,
+    pub rate_limited: u64,
+}
+
+impl ThrottleStatistics {
+    pub fn total(&self) -> u64 {
+        self.cooldown + self.rate_limited
+    }
+}
+
+/// Serializable state of a throttle, see `SignalThrottle::snapshot`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThrottleState {
+    pub accepted: Vec<(Symbol, Vec<u64>)>,
+    pub statistics: ThrottleStatistics,
+}
+
+/// Signal throttle keyed by symbol
+pub struct SignalThrottle {
+    config: ThrottleConfig,
+    accepted: DashMap<Symbol, VecDeque<u64>>, // Accepted signal times within the interval, oldest first
+    cooldown: AtomicU64,
+    rate_limited: AtomicU64,
+}
+
+impl SignalThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            accepted: DashMap::new(),
+            cooldown: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+        }
+    }
+
+    /// Accept the signal, recording it against its symbol, or say why not
+    pub fn check(&self, signal: &TradingSignal, now_ms: u64) -> Result<(), ThrottleReason> {
+        if matches!(signal.action, SignalAction::Hold | SignalAction::Close { .. }) {
+            return Ok(());
+        }
+        let ThrottleConfig { cooldown_ms, max_signals, interval_ms } = self.config;
+        if cooldown_ms == 0 && max_signals == 0 {
+            return Ok(());
+        }
+
+        let mut accepted = self.accepted.entry(signal.symbol.clone()).or_default();
+        let window = interval_ms.max(cooldown_ms);
+        while accepted.front().is_some_and(|&t| now_ms.saturating_sub(t) >= window) {
+            accepted.pop_front();
+        }
+
+        let reason = if accepted.back().is_some_and(|&last| now_ms.saturating_sub(last) < cooldown_ms) {
+            Some(ThrottleReason::Cooldown)
+        } else if max_signals > 0
+            && accepted.iter().filter(|&&t| now_ms.saturating_sub(t) < interval_ms).count() >= max_signals as usize
+        {
+            Some(ThrottleReason::RateLimit)
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => {
+                let counter = match reason {
+                    ThrottleReason::Cooldown => &self.cooldown,
+                    ThrottleReason::RateLimit => &self.rate_limited,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+                Err(reason)
+            }
+            None => {
+                accepted.push_back(now_ms);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn statistics(&self) -> ThrottleStatistics {
+        ThrottleStatistics {
+            cooldown: self.cooldown.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Accepted signal times still inside a window, and the counters
+    pub fn snapshot(&self) -> ThrottleState {
+        let mut accepted: Vec<(Symbol, Vec<u64>)> = self
+            .accepted
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().iter().copied().collect()))
+            .collect();
+        accepted.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+        ThrottleState {
+            accepted,
+            statistics: self.statistics(),
+        }
+    }
+
+    pub fn restore(&self, state: &ThrottleState) {
+        self.accepted.clear();
+        for (symbol, times) in &state.accepted {
+            self.accepted.insert(symbol.clone(), times.iter().copied().collect());
+        }
+        self.cooldown.store(state.statistics.cooldown, Ordering::Relaxed);
+        self.rate_limited.store(state.statistics.rate_limited, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::SignalMetadata;
+
+    fn signal(symbol: &str, action: SignalAction) -> TradingSignal {
+        TradingSignal {
+            symbol: Symbol::new(symbol),
+            exchange: Exchange::Binance,
+            action,
+            confidence: 0.9,
+            urgency: 0.9,
+            metadata: SignalMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_cooldown_and_rate_limit_per_symbol() {
+        let throttle = SignalThrottle::new(ThrottleConfig {
+            cooldown_ms: 100,
+            max_signals: 3,
+            interval_ms: 1000,
+        });
+        let buy = |symbol| signal(symbol, SignalAction::Buy { size_hint: None });
+
+        // A burst of 50 in the same millisecond: one gets through
+        let accepted = (0..50).filter(|_| throttle.check(&buy("BTC-USD"), 0).is_ok()).count();
+        assert_eq!(accepted, 1);
+        assert_eq!(throttle.statistics().cooldown, 49);
+
+        // Other symbols and closes are unaffected
+        assert!(throttle.check(&buy("ETH-USD"), 0).is_ok());
+        assert!(throttle.check(&signal("BTC-USD", SignalAction::Close { position_id: None }), 0).is_ok());
+
+        // Past the cooldown, the cap of 3 per second applies
+        assert!(throttle.check(&buy("BTC-USD"), 150).is_ok());
+        assert!(throttle.check(&buy("BTC-USD"), 300).is_ok());
+        assert_eq!(throttle.check(&buy("BTC-USD"), 450), Err(ThrottleReason::RateLimit));
+        assert!(throttle.check(&buy("BTC-USD"), 1000).is_ok()); // The first has left the window
+        assert_eq!(throttle.statistics().total(), 50);
+    }
+}
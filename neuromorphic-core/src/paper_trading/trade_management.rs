@@ -0,0 +1,238 @@
+//! Managing open trades by their initial risk
+//!
+//! A position's initial risk, R, is the distance from its entry to the stop
+//! it opened with. Trade management rules act on how many R the price has
+//! moved in the position's favour, on every price update:
+//!
+//! - `break_even` moves the stop to the entry (plus `offset_r`) at `at_r`
+//! - `partial_profit` closes `fraction` of what is open at `at_r`
+//! - `trail` keeps the stop `distance_r` behind the best price from `at_r`
+//!   on, dropping the take-profit so the rest can run
+//!
+//! Rules are evaluated in the order they are configured, each break-even and
+//! partial profit once per position; partial profits hit by the same update
+//! each take their fraction of what the ones before left open. Stops only
+//! ever move in the position's favour. Positions without a stop have no R and
+//! are left alone, as are positions with an exit order in flight.
+
+use super::position_manager::{ExitReason, Position, PositionManager, TriggeredExit};
+use crate::exchanges::{Side, Symbol};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+/// One trade management rule; distances are multiples of the initial risk
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TradeRule {
+    BreakEven {
+        at_r: f64,
+        #[serde(default)]
+        offset_r: f64, // Beyond the entry, e.g. to cover fees
+    },
+    PartialProfit { at_r: f64, fraction: f64 },
+    Trail { at_r: f64, distance_r: f64 },
+}
+
+impl TradeRule {
+    /// The rules of a classic plan: break-even at +1R, half off at +2R, trail
+    /// the rest one R behind from there
+    pub fn standard() -> Vec<TradeRule> {
+        vec![
+            TradeRule::BreakEven { at_r: 1.0, offset_r: 0.0 },
+            TradeRule::PartialProfit { at_r: 2.0, fraction: 0.5 },
+            TradeRule::Trail { at_r: 2.0, distance_r: 1.0 },
+        ]
+    }
+}
+
+/// What the rules know of a position
+#[derive(Clone, Debug)]
+struct Managed {
+    risk: f64, // Price distance of one R
+    best: f64, // Most favourable price seen
+    fired: Vec<bool>, // By rule index
+}
+
+/// Applies trade rules to the open positions
+pub struct TradeManager {
+    rules: Vec<TradeRule>,
+    managed: DashMap<Symbol, HashMap<String, Managed>>, // Open positions by symbol, then ID
+}
+
+impl TradeManager {
+    pub fn new(rules: Vec<TradeRule>) -> Self {
+        Self { rules, managed: DashMap::new() }
+    }
+
+    pub fn rules(&self) -> &[TradeRule] {
+        &self.rules
+    }
+
+    /// Evaluate the rules for the open positions in `symbol` at `price`.
+    /// Stops are moved right away; the partial closes are returned for the
+    /// caller to submit.
+    pub fn on_price(&self, positions: &PositionManager, symbol: &Symbol, price: f64) -> Vec<TriggeredExit> {
+        if self.rules.is_empty() || !price.is_finite() || price <= 0.0 {
+            return Vec::new();
+        }
+        let open = positions.get_open_positions_by_symbol(symbol);
+        let mut managed = self.managed.entry(symbol.clone()).or_default();
+        // Forget closed positions
+        managed.retain(|id, _| open.iter().any(|position| &position.id == id));
+
+        let mut exits = Vec::new();
+        for position in open {
+            if positions.pending_exit_reason(&position.id).is_some() {
+                continue;
+            }
+            let state = match managed.get_mut(&position.id) {
+                Some(state) => state,
+                None => {
+                    let Some(stop) = position.stop_loss.filter(|stop| (position.entry_price - stop).abs() > 0.0) else {
+                        continue;
+                    };
+                    let state = Managed { risk: (position.entry_price - stop).abs(), best: position.entry_price, fired: vec![false; self.rules.len()] };
+                    managed.entry(position.id.clone()).or_insert(state)
+                }
+            };
+            exits.extend(self.apply_rules(positions, &position, state, price));
+        }
+        exits
+    }
+
+    fn apply_rules(&self, positions: &PositionManager, position: &Position, state: &mut Managed, price: f64) -> Option<TriggeredExit> {
+        let sign = if position.side == Side::Buy { 1.0 } else { -1.0 };
+        if (price - state.best) * sign > 0.0 {
+            state.best = price;
+        }
+        let gained_r = (price - position.entry_price) * sign / state.risk;
+        let best_r = (state.best - position.entry_price) * sign / state.risk;
+
+        let (mut stop, mut take_profit, mut trailing) = (position.stop_loss, position.take_profit, position.trailing_stop);
+        let mut open = position.quantity;
+        for (i, rule) in self.rules.iter().enumerate() {
+            match *rule {
+                TradeRule::BreakEven { at_r, offset_r } if !state.fired[i] && gained_r >= at_r => {
+                    state.fired[i] = true;
+                    stop = tighter(stop, position.entry_price + sign * offset_r * state.risk, sign);
+                }
+                TradeRule::PartialProfit { at_r, fraction } if !state.fired[i] && gained_r >= at_r => {
+                    state.fired[i] = true;
+                    open -= open * fraction.clamp(0.0, 1.0);
+                }
+                TradeRule::Trail { at_r, distance_r } if best_r >= at_r => {
+                    stop = tighter(stop, state.best - sign * distance_r * state.risk, sign);
+                    (take_profit, trailing) = (None, true);
+                }
+                _ => {}
+            }
+        }
+
+        if (stop, take_profit) != (position.stop_loss, position.take_profit) {
+            debug!(position_id = %position.id, stop_loss = ?stop, take_profit = ?take_profit, gained_r, "Trade management moved exits");
+            if let Err(e) = positions.modify_position_exits(&position.id, stop, take_profit) {
+                warn!(position_id = %position.id, error = %e, "Failed to move exits");
+            }
+        }
+        if trailing != position.trailing_stop {
+            positions.set_trailing_stop(&position.id, trailing).ok();
+        }
+
+        let quantity = position.quantity - open;
+        (quantity > 0.0).then(|| TriggeredExit {
+            position_id: position.id.clone(),
+            symbol: position.symbol.clone(),
+            exchange: position.exchange,
+            side: position.side,
+            quantity,
+            reason: ExitReason::TakeProfit,
+            price,
+        })
+    }
+}
+
+/// The stop closer to the price of the two, for a position of `sign`
+fn tighter(current: Option<f64>, candidate: f64, sign: f64) -> Option<f64> {
+    if candidate <= 0.0 {
+        return current;
+    }
+    Some(current.map_or(candidate, |stop| if (candidate - stop) * sign > 0.0 { candidate } else { stop }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+
+    fn open(positions: &PositionManager, side: Side, stop: f64, take_profit: f64) -> String {
+        let id = positions.open_position(Symbol::new("BTCUSDT"), Exchange::Binance, side, 2.0, 100.0, 0.0, 0.0).unwrap();
+        positions.modify_position_exits(&id, Some(stop), Some(take_profit)).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_break_even_then_partial_then_trail() {
+        let positions = PositionManager::new();
+        let manager = TradeManager::new(TradeRule::standard());
+        let symbol = Symbol::new("BTCUSDT");
+        let id = open(&positions, Side::Buy, 95.0, 150.0); // R = 5
+        let stop = |positions: &PositionManager| positions.get_position(&id).unwrap().stop_loss;
+
+        assert!(manager.on_price(&positions, &symbol, 103.0).is_empty());
+        assert_eq!(stop(&positions), Some(95.0));
+        assert!(manager.on_price(&positions, &symbol, 105.0).is_empty());
+        assert_eq!(stop(&positions), Some(100.0));
+        // Back down: the stop stays at break-even
+        manager.on_price(&positions, &symbol, 101.0);
+        assert_eq!(stop(&positions), Some(100.0));
+
+        // +2R: half off, and the rest trails one R behind without a target
+        let exits = manager.on_price(&positions, &symbol, 110.0);
+        assert_eq!(exits.len(), 1);
+        assert_eq!((exits[0].quantity, exits[0].side, exits[0].reason), (1.0, Side::Buy, ExitReason::TakeProfit));
+        let position = positions.get_position(&id).unwrap();
+        assert_eq!((position.stop_loss, position.take_profit, position.trailing_stop), (Some(105.0), None, true));
+
+        // Partial profit fires once; the trail ratchets up and never back
+        assert!(manager.on_price(&positions, &symbol, 120.0).is_empty());
+        assert_eq!(stop(&positions), Some(115.0));
+        manager.on_price(&positions, &symbol, 116.0);
+        assert_eq!(stop(&positions), Some(115.0));
+        assert_eq!(positions.check_exits_for_symbol(&symbol, 114.0)[0].reason, ExitReason::TrailingStop);
+
+        // With an exit in flight and once closed, the position is left alone
+        assert!(manager.on_price(&positions, &symbol, 130.0).is_empty());
+        assert_eq!(stop(&positions), Some(115.0));
+        positions.close_position(&id, 114.0, 0.0, 0.0, ExitReason::TrailingStop).unwrap();
+        manager.on_price(&positions, &symbol, 114.0);
+        assert!(manager.managed.get(&symbol).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gaps_through_several_rules_for_shorts() {
+        let positions = PositionManager::new();
+        let symbol = Symbol::new("BTCUSDT");
+        let manager = TradeManager::new(vec![
+            TradeRule::PartialProfit { at_r: 1.0, fraction: 0.5 },
+            TradeRule::PartialProfit { at_r: 2.0, fraction: 0.5 },
+            TradeRule::BreakEven { at_r: 1.0, offset_r: 0.2 },
+            TradeRule::Trail { at_r: 3.0, distance_r: 1.0 },
+        ]);
+        let short = open(&positions, Side::Sell, 110.0, 50.0); // R = 10
+        let unstopped = positions.open_position(symbol.clone(), Exchange::Binance, Side::Buy, 1.0, 100.0, 0.0, 0.0).unwrap();
+
+        // +2.5R at once: half, then half of the rest, and the stop 0.2R past the entry
+        let exits = manager.on_price(&positions, &symbol, 75.0);
+        assert_eq!(exits.len(), 1);
+        assert_eq!((exits[0].position_id.as_str(), exits[0].quantity), (short.as_str(), 1.5));
+        let position = positions.get_position(&short).unwrap();
+        assert_eq!((position.stop_loss, position.take_profit, position.trailing_stop), (Some(98.0), Some(50.0), false));
+        assert_eq!(positions.get_position(&unstopped).unwrap().stop_loss, None);
+
+        manager.on_price(&positions, &symbol, 60.0);
+        assert_eq!(positions.get_position(&short).unwrap().stop_loss, Some(70.0));
+        assert!(serde_json::from_str::<TradeRule>(r#"{"rule":"trail","at_r":1.0}"#).is_err());
+    }
+}
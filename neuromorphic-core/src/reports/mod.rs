@@ -0,0 +1,663 @@
+//! End-of-session trade analytics reports
+//!
+//! Builds a `SessionReport` from closed positions and the signal history and
+//! renders it as Markdown or HTML, with amounts in the session's base
+//! currency formatted for a `Locale`. `tax` summarizes its closed lots by year.
+
+pub mod money;
+pub mod tax;
+
+pub use money::Locale;
+pub use tax::{HoldingPeriod, SymbolTaxSummary, TaxLot, TaxSummary};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::exchanges::Side;
+use crate::paper_trading::{system_clock, ExitReason, Position, PositionManager, SharedClock, ShortfallReport, ShortfallStats, SignalAction, TradingSignal};
+
+/// Output format for rendered reports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/// Point on the realized equity curve
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EquityPoint {
+    pub timestamp: DateTime<Utc>,
+    pub equity: f64,
+    pub drawdown_pct: f64,
+}
+
+/// Single closed trade
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub position_id: String,
+    pub symbol: String,
+    pub strategy: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub entry_time: DateTime<Utc>,
+    pub exit_time: DateTime<Utc>,
+    pub duration_secs: u64,
+    pub pnl: f64,
+    pub return_pct: f64,
+    pub mae: f64,
+    pub mfe: f64,
+    pub exit_reason: Option<ExitReason>,
+    #[serde(default)]
+    pub fees: f64, // Commission and slippage of entry and exit, already in `pnl`
+    #[serde(default)]
+    pub closed_quantity: f64, // Including partial closes; 0 in sessions saved before it was recorded
+}
+
+impl TradeRecord {
+    /// Build a trade record from a closed position
+    pub fn from_position(position: &Position) -> Option<Self> {
+        let exit_price = position.exit_price?;
+        let exit_time = position.exit_time?;
+
+        Some(Self {
+            position_id: position.id.clone(),
+            symbol: position.symbol.to_string(),
+            strategy: position.strategy.clone().unwrap_or_else(|| "unattributed".to_string()),
+            side: position.side,
+            quantity: position.quantity,
+            entry_price: position.entry_price,
+            exit_price,
+            entry_time: millis_to_datetime(position.entry_time),
+            exit_time: millis_to_datetime(exit_time),
+            duration_secs: exit_time.saturating_sub(position.entry_time) / 1000,
+            pnl: position.realized_pnl,
+            return_pct: position.roi(),
+            mae: position.max_adverse_excursion,
+            mfe: position.max_favorable_excursion,
+            exit_reason: position.exit_reason,
+            fees: position.commission + position.slippage,
+            closed_quantity: position.closed_quantity,
+        })
+    }
+
+    /// Exit reason as shown in exports
+    pub fn exit_reason_label(&self) -> String {
+        self.exit_reason
+            .map(|r| r.to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// Aggregated statistics for a single strategy
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StrategyStats {
+    pub strategy: String,
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub win_rate: f64,
+    pub total_pnl: f64,
+    pub avg_pnl: f64,
+    pub avg_duration_secs: f64,
+    pub profit_factor: f64,
+}
+
+/// Histogram bucket
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DistributionBucket {
+    pub label: String,
+    pub count: usize,
+}
+
+/// Summary of the signals seen during the session
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SignalSummary {
+    pub total_signals: usize,
+    pub by_action: HashMap<String, usize>,
+    pub avg_confidence: f64,
+    pub avg_urgency: f64,
+}
+
+/// End-of-session report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionReport {
+    pub generated_at: DateTime<Utc>,
+    #[serde(default = "default_currency")]
+    pub currency: String, // Base currency of capital and equity
+    pub initial_capital: f64,
+    pub final_equity: f64,
+    pub total_pnl: f64,
+    pub total_return_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub equity_curve: Vec<EquityPoint>,
+    pub strategy_stats: Vec<StrategyStats>,
+    pub duration_distribution: Vec<DistributionBucket>,
+    pub return_distribution: Vec<DistributionBucket>,
+    pub trades: Vec<TradeRecord>,
+    pub largest_winners: Vec<TradeRecord>,
+    pub largest_losers: Vec<TradeRecord>,
+    pub signal_summary: SignalSummary,
+    #[serde(default)]
+    pub shortfall: ShortfallReport, // Execution costs of the orders, see `with_shortfall`
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// Report generator
+pub struct ReportGenerator {
+    initial_capital: f64,
+    currency: String,
+    top_n: usize,
+    clock: SharedClock,
+}
+
+const DURATION_BUCKETS: &[(u64, &str)] = &[
+    (60, "< 1m"),
+    (300, "1m - 5m"),
+    (900, "5m - 15m"),
+    (3_600, "15m - 1h"),
+    (14_400, "1h - 4h"),
+    (86_400, "4h - 1d"),
+    (u64::MAX, "> 1d"),
+];
+
+const RETURN_BUCKETS: &[(f64, &str)] = &[
+    (-5.0, "< -5%"),
+    (-2.0, "-5% to -2%"),
+    (0.0, "-2% to 0%"),
+    (2.0, "0% to 2%"),
+    (5.0, "2% to 5%"),
+    (f64::INFINITY, "> 5%"),
+];
+
+impl ReportGenerator {
+    pub fn new(initial_capital: f64) -> Self {
+        Self {
+            initial_capital,
+            currency: default_currency(),
+            top_n: 5,
+            clock: system_clock(),
+        }
+    }
+
+    /// Stamp reports with the time from `clock`, e.g. the end of a backtest
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set the base currency the initial capital is in
+    pub fn with_currency(mut self, currency: &str) -> Self {
+        self.currency = currency.to_uppercase();
+        self
+    }
+
+    /// Set how many trades to list as largest winners/losers
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = top_n;
+        self
+    }
+
+    /// Generate a report from the position manager and signal history
+    pub fn generate(&self, positions: &PositionManager, signals: &[TradingSignal]) -> SessionReport {
+        self.generate_from_positions(&positions.get_closed_positions(), signals)
+    }
+
+    /// Generate a report from an explicit set of closed positions
+    pub fn generate_from_positions(&self, closed: &[Position], signals: &[TradingSignal]) -> SessionReport {
+        let mut trades: Vec<TradeRecord> = closed
+            .iter()
+            .filter_map(TradeRecord::from_position)
+            .collect();
+        trades.sort_by_key(|t| t.exit_time);
+
+        let equity_curve = self.build_equity_curve(&trades);
+        let max_drawdown_pct = equity_curve
+            .iter()
+            .map(|p| p.drawdown_pct)
+            .fold(0.0, f64::max);
+        let total_pnl: f64 = trades.iter().map(|t| t.pnl).sum();
+        let final_equity = self.initial_capital + total_pnl;
+
+        let mut by_pnl = trades.clone();
+        by_pnl.sort_by(|a, b| b.pnl.partial_cmp(&a.pnl).unwrap_or(std::cmp::Ordering::Equal));
+        let largest_winners: Vec<TradeRecord> = by_pnl
+            .iter()
+            .filter(|t| t.pnl > 0.0)
+            .take(self.top_n)
+            .cloned()
+            .collect();
+        let largest_losers: Vec<TradeRecord> = by_pnl
+            .iter()
+            .rev()
+            .filter(|t| t.pnl < 0.0)
+            .take(self.top_n)
+            .cloned()
+            .collect();
+
+        SessionReport {
+            generated_at: self.clock.now(),
+            currency: self.currency.clone(),
+            initial_capital: self.initial_capital,
+            final_equity,
+            total_pnl,
+            total_return_pct: if self.initial_capital > 0.0 {
+                total_pnl / self.initial_capital * 100.0
+            } else {
+                0.0
+            },
+            max_drawdown_pct,
+            equity_curve,
+            strategy_stats: Self::strategy_stats(&trades),
+            duration_distribution: Self::duration_distribution(&trades),
+            return_distribution: Self::return_distribution(&trades),
+            trades,
+            largest_winners,
+            largest_losers,
+            signal_summary: Self::signal_summary(signals),
+            shortfall: ShortfallReport::default(),
+        }
+    }
+
+    fn build_equity_curve(&self, trades: &[TradeRecord]) -> Vec<EquityPoint> {
+        let mut equity = self.initial_capital;
+        let mut peak = self.initial_capital;
+
+        trades
+            .iter()
+            .map(|trade| {
+                equity += trade.pnl;
+                peak = peak.max(equity);
+                let drawdown_pct = if peak > 0.0 {
+                    (peak - equity) / peak * 100.0
+                } else {
+                    0.0
+                };
+
+                EquityPoint {
+                    timestamp: trade.exit_time,
+                    equity,
+                    drawdown_pct,
+                }
+            })
+            .collect()
+    }
+
+    fn strategy_stats(trades: &[TradeRecord]) -> Vec<StrategyStats> {
+        let mut grouped: HashMap<&str, Vec<&TradeRecord>> = HashMap::new();
+        for trade in trades {
+            grouped.entry(trade.strategy.as_str()).or_default().push(trade);
+        }
+
+        let mut stats: Vec<StrategyStats> = grouped
+            .into_iter()
+            .map(|(strategy, trades)| {
+                let wins = trades.iter().filter(|t| t.pnl > 0.0).count();
+                let losses = trades.iter().filter(|t| t.pnl < 0.0).count();
+                let gross_profit: f64 = trades.iter().filter(|t| t.pnl > 0.0).map(|t| t.pnl).sum();
+                let gross_loss: f64 = trades.iter().filter(|t| t.pnl < 0.0).map(|t| t.pnl.abs()).sum();
+                let total_pnl: f64 = trades.iter().map(|t| t.pnl).sum();
+                let n = trades.len() as f64;
+
+                StrategyStats {
+                    strategy: strategy.to_string(),
+                    trades: trades.len(),
+                    wins,
+                    losses,
+                    win_rate: wins as f64 / n * 100.0,
+                    total_pnl,
+                    avg_pnl: total_pnl / n,
+                    avg_duration_secs: trades.iter().map(|t| t.duration_secs as f64).sum::<f64>() / n,
+                    profit_factor: if gross_loss > 0.0 { gross_profit / gross_loss } else { 0.0 },
+                }
+            })
+            .collect();
+
+        stats.sort_by(|a, b| b.total_pnl.partial_cmp(&a.total_pnl).unwrap_or(std::cmp::Ordering::Equal));
+        stats
+    }
+
+    fn duration_distribution(trades: &[TradeRecord]) -> Vec<DistributionBucket> {
+        let mut counts = vec![0usize; DURATION_BUCKETS.len()];
+        for trade in trades {
+            let idx = DURATION_BUCKETS
+                .iter()
+                .position(|(upper, _)| trade.duration_secs < *upper)
+                .unwrap_or(DURATION_BUCKETS.len() - 1);
+            counts[idx] += 1;
+        }
+
+        DURATION_BUCKETS
+            .iter()
+            .zip(counts)
+            .map(|((_, label), count)| DistributionBucket { label: label.to_string(), count })
+            .collect()
+    }
+
+    fn return_distribution(trades: &[TradeRecord]) -> Vec<DistributionBucket> {
+        let mut counts = vec![0usize; RETURN_BUCKETS.len()];
+        for trade in trades {
+            let idx = RETURN_BUCKETS
+                .iter()
+                .position(|(upper, _)| trade.return_pct < *upper)
+                .unwrap_or(RETURN_BUCKETS.len() - 1);
+            counts[idx] += 1;
+        }
+
+        RETURN_BUCKETS
+            .iter()
+            .zip(counts)
+            .map(|((_, label), count)| DistributionBucket { label: label.to_string(), count })
+            .collect()
+    }
+
+    fn signal_summary(signals: &[TradingSignal]) -> SignalSummary {
+        let mut summary = SignalSummary {
+            total_signals: signals.len(),
+            ..Default::default()
+        };
+
+        if signals.is_empty() {
+            return summary;
+        }
+
+        for signal in signals {
+            let action = match signal.action {
+                SignalAction::Buy { .. } => "Buy",
+                SignalAction::Sell { .. } => "Sell",
+                SignalAction::Close { .. } => "Close",
+                SignalAction::ScaleIn { .. } => "ScaleIn",
+                SignalAction::ScaleOut { .. } => "ScaleOut",
+                SignalAction::Hold => "Hold",
+            };
+            *summary.by_action.entry(action.to_string()).or_insert(0) += 1;
+        }
+
+        let n = signals.len() as f64;
+        summary.avg_confidence = signals.iter().map(|s| s.confidence).sum::<f64>() / n;
+        summary.avg_urgency = signals.iter().map(|s| s.urgency).sum::<f64>() / n;
+
+        summary
+    }
+}
+
+impl SessionReport {
+    /// Render the report in the requested format
+    pub fn render(&self, format: ReportFormat) -> String {
+        self.render_localized(format, Locale::default())
+    }
+
+    /// Render the report with amounts and percentages formatted for `locale`
+    pub fn render_localized(&self, format: ReportFormat, locale: Locale) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_markdown(locale),
+            ReportFormat::Html => self.to_html(locale),
+        }
+    }
+
+    /// Include the implementation shortfall of the session's orders
+    pub fn with_shortfall(mut self, shortfall: ShortfallReport) -> Self {
+        self.shortfall = shortfall;
+        self
+    }
+
+    /// Export closed trades as CSV
+    pub fn trades_csv(&self) -> String {
+        let mut out = String::from(
+            "position_id,symbol,strategy,side,quantity,entry_price,exit_price,entry_time,exit_time,duration_secs,pnl,return_pct,mae,mfe,exit_reason\n"
+        );
+        for t in &self.trades {
+            let _ = writeln!(
+                out,
+                "{},{},{},{:?},{},{},{},{},{},{},{:.2},{:.4},{:.2},{:.2},{}",
+                t.position_id, t.symbol, t.strategy, t.side, t.quantity, t.entry_price, t.exit_price,
+                t.entry_time.to_rfc3339(), t.exit_time.to_rfc3339(), t.duration_secs,
+                t.pnl, t.return_pct, t.mae, t.mfe, t.exit_reason_label()
+            );
+        }
+        out
+    }
+
+    fn to_markdown(&self, locale: Locale) -> String {
+        let mut out = String::new();
+        let money = |amount: f64| locale.money(amount, &self.currency);
+        let pct = |value: f64| locale.number(value, 2);
+
+        let _ = writeln!(out, "# Session Report\n");
+        let _ = writeln!(out, "Generated: {}\n", self.generated_at.to_rfc3339());
+        let _ = writeln!(out, "| Metric | Value |\n|---|---|");
+        let _ = writeln!(out, "| Initial capital | {} |", money(self.initial_capital));
+        let _ = writeln!(out, "| Final equity | {} |", money(self.final_equity));
+        let _ = writeln!(out, "| Total P&L | {} ({}%) |", money(self.total_pnl), pct(self.total_return_pct));
+        let _ = writeln!(out, "| Max drawdown | {}% |", pct(self.max_drawdown_pct));
+        let _ = writeln!(out, "| Trades | {} |", self.trades.len());
+        let _ = writeln!(out, "| Signals | {} |\n", self.signal_summary.total_signals);
+
+        let _ = writeln!(out, "## Equity Curve\n");
+        let _ = writeln!(out, "| Time | Equity | Drawdown |\n|---|---|---|");
+        for p in &self.equity_curve {
+            let _ = writeln!(out, "| {} | {} | {}% |", p.timestamp.to_rfc3339(), money(p.equity), pct(p.drawdown_pct));
+        }
+
+        let _ = writeln!(out, "\n## Strategies\n");
+        let _ = writeln!(out, "| Strategy | Trades | Win rate | Total P&L | Avg P&L | Profit factor |\n|---|---|---|---|---|---|");
+        for s in &self.strategy_stats {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {}% | {} | {} | {} |",
+                s.strategy, s.trades, locale.number(s.win_rate, 1), money(s.total_pnl), money(s.avg_pnl), locale.number(s.profit_factor, 2)
+            );
+        }
+
+        if self.shortfall.total.orders > 0 {
+            let _ = writeln!(out, "\n## Execution\n");
+            let _ = writeln!(out, "Implementation shortfall in bps of the decision price; negative execution is price improvement.\n");
+            let _ = writeln!(out, "| Strategy / symbol | Orders | Improved | Delay | Execution | Shortfall | Cost |\n|---|---|---|---|---|---|---|");
+            for (name, s) in self.shortfall_rows() {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} | {} | {} |",
+                    name, s.orders, s.improved_orders, locale.number(s.delay_bps, 1), locale.number(s.execution_bps, 1),
+                    locale.number(s.shortfall_bps, 1), money(s.shortfall_cost)
+                );
+            }
+        }
+
+        let _ = writeln!(out, "\n## Trade Durations\n");
+        Self::markdown_distribution(&mut out, &self.duration_distribution);
+        let _ = writeln!(out, "\n## Trade Returns\n");
+        Self::markdown_distribution(&mut out, &self.return_distribution);
+
+        let _ = writeln!(out, "\n## Largest Winners\n");
+        self.markdown_trades(&mut out, &self.largest_winners, locale);
+        let _ = writeln!(out, "\n## Largest Losers\n");
+        self.markdown_trades(&mut out, &self.largest_losers, locale);
+        let _ = writeln!(out, "\n## All Trades\n");
+        self.markdown_trades(&mut out, &self.trades, locale);
+
+        out
+    }
+
+    /// Shortfall rows: strategies, symbols, then the total
+    fn shortfall_rows(&self) -> Vec<(String, &ShortfallStats)> {
+        let shortfall = &self.shortfall;
+        shortfall.by_strategy.iter().map(|(name, s)| (name.clone(), s))
+            .chain(shortfall.by_symbol.iter().map(|(symbol, s)| (symbol.clone(), s)))
+            .chain(std::iter::once(("Total".to_string(), &shortfall.total)))
+            .collect()
+    }
+
+    fn markdown_distribution(out: &mut String, buckets: &[DistributionBucket]) {
+        let _ = writeln!(out, "| Bucket | Trades |\n|---|---|");
+        for b in buckets {
+            let _ = writeln!(out, "| {} | {} |", b.label, b.count);
+        }
+    }
+
+    fn markdown_trades(&self, out: &mut String, trades: &[TradeRecord], locale: Locale) {
+        let money = |amount: f64| locale.money(amount, &self.currency);
+        let _ = writeln!(out, "| Symbol | Strategy | Side | Entry | Exit | Duration (s) | P&L | Return | MAE | MFE | Exit |\n|---|---|---|---|---|---|---|---|---|---|---|");
+        for t in trades {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {:?} | {} | {} | {} | {} | {}% | {} | {} | {} |",
+                t.symbol, t.strategy, t.side, locale.number(t.entry_price, 4), locale.number(t.exit_price, 4),
+                t.duration_secs, money(t.pnl), locale.number(t.return_pct, 2), money(t.mae), money(t.mfe), t.exit_reason_label()
+            );
+        }
+    }
+
+    fn to_html(&self, locale: Locale) -> String {
+        let mut out = String::new();
+        let money = |amount: f64| locale.money(amount, &self.currency);
+        let pct = |value: f64| locale.number(value, 2);
+
+        let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Session Report</title></head>\n<body>");
+        let _ = writeln!(out, "<h1>Session Report</h1>\n<p>Generated: {}</p>", self.generated_at.to_rfc3339());
+        let _ = writeln!(out, "<table>");
+        let _ = writeln!(out, "<tr><td>Initial capital</td><td>{}</td></tr>", money(self.initial_capital));
+        let _ = writeln!(out, "<tr><td>Final equity</td><td>{}</td></tr>", money(self.final_equity));
+        let _ = writeln!(out, "<tr><td>Total P&amp;L</td><td>{} ({}%)</td></tr>", money(self.total_pnl), pct(self.total_return_pct));
+        let _ = writeln!(out, "<tr><td>Max drawdown</td><td>{}%</td></tr>", pct(self.max_drawdown_pct));
+        let _ = writeln!(out, "<tr><td>Trades</td><td>{}</td></tr>", self.trades.len());
+        let _ = writeln!(out, "<tr><td>Signals</td><td>{}</td></tr>", self.signal_summary.total_signals);
+        let _ = writeln!(out, "</table>");
+
+        // Chart data is embedded as JSON so a dashboard can plot it directly
+        let curve = serde_json::to_string(&self.equity_curve).unwrap_or_else(|_| "[]".to_string());
+        let _ = writeln!(out, "<h2>Equity Curve</h2>\n<script type=\"application/json\" id=\"equity-curve\">{}</script>", curve);
+
+        let _ = writeln!(out, "<h2>Strategies</h2>\n<table>\n<tr><th>Strategy</th><th>Trades</th><th>Win rate</th><th>Total P&amp;L</th><th>Avg P&amp;L</th><th>Profit factor</th></tr>");
+        for s in &self.strategy_stats {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}%</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html_escape(&s.strategy), s.trades, locale.number(s.win_rate, 1), money(s.total_pnl), money(s.avg_pnl), locale.number(s.profit_factor, 2)
+            );
+        }
+        let _ = writeln!(out, "</table>");
+
+        if self.shortfall.total.orders > 0 {
+            let _ = writeln!(out, "<h2>Execution</h2>\n<table>\n<tr><th>Strategy / symbol</th><th>Orders</th><th>Improved</th><th>Delay (bps)</th><th>Execution (bps)</th><th>Shortfall (bps)</th><th>Cost</th></tr>");
+            for (name, s) in self.shortfall_rows() {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    html_escape(&name), s.orders, s.improved_orders, locale.number(s.delay_bps, 1), locale.number(s.execution_bps, 1),
+                    locale.number(s.shortfall_bps, 1), money(s.shortfall_cost)
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+
+        let _ = writeln!(out, "<h2>Trade Durations</h2>");
+        Self::html_distribution(&mut out, &self.duration_distribution);
+        let _ = writeln!(out, "<h2>Trade Returns</h2>");
+        Self::html_distribution(&mut out, &self.return_distribution);
+
+        let _ = writeln!(out, "<h2>Largest Winners</h2>");
+        self.html_trades(&mut out, &self.largest_winners, locale);
+        let _ = writeln!(out, "<h2>Largest Losers</h2>");
+        self.html_trades(&mut out, &self.largest_losers, locale);
+        let _ = writeln!(out, "<h2>All Trades</h2>");
+        self.html_trades(&mut out, &self.trades, locale);
+
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+
+    fn html_distribution(out: &mut String, buckets: &[DistributionBucket]) {
+        let _ = writeln!(out, "<table>\n<tr><th>Bucket</th><th>Trades</th></tr>");
+        for b in buckets {
+            let _ = writeln!(out, "<tr><td>{}</td><td>{}</td></tr>", html_escape(&b.label), b.count);
+        }
+        let _ = writeln!(out, "</table>");
+    }
+
+    fn html_trades(&self, out: &mut String, trades: &[TradeRecord], locale: Locale) {
+        let money = |amount: f64| locale.money(amount, &self.currency);
+        let _ = writeln!(out, "<table>\n<tr><th>Symbol</th><th>Strategy</th><th>Side</th><th>Entry</th><th>Exit</th><th>Duration (s)</th><th>P&amp;L</th><th>Return</th><th>MAE</th><th>MFE</th><th>Exit</th></tr>");
+        for t in trades {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}%</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html_escape(&t.symbol), html_escape(&t.strategy), t.side, locale.number(t.entry_price, 4), locale.number(t.exit_price, 4),
+                t.duration_secs, money(t.pnl), locale.number(t.return_pct, 2), money(t.mae), money(t.mfe), t.exit_reason_label()
+            );
+        }
+        let _ = writeln!(out, "</table>");
+    }
+}
+
+fn millis_to_datetime(millis: u64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis as i64)
+        .single()
+        .unwrap_or_else(Utc::now)
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Symbol};
+
+    fn closed_position(strategy: &str, entry: f64, exit: f64, held_ms: u64) -> Position {
+        let mut position = Position::new(Symbol::new("BTC-USD"), Exchange::Binance, Side::Buy, 1.0, entry);
+        position.strategy = Some(strategy.to_string());
+        position.close(exit, 0.0, 0.0, 0);
+        position.entry_time = 1_700_000_000_000;
+        position.exit_time = Some(position.entry_time + held_ms);
+        position
+    }
+
+    #[test]
+    fn test_report_aggregates_trades() {
+        let positions = vec![
+            closed_position("momentum", 100.0, 110.0, 30_000),
+            closed_position("momentum", 100.0, 95.0, 600_000),
+            closed_position("gap", 100.0, 120.0, 7_200_000),
+        ];
+
+        let report = ReportGenerator::new(10_000.0).generate_from_positions(&positions, &[]);
+
+        assert_eq!(report.trades.len(), 3);
+        assert_eq!(report.total_pnl, 25.0);
+        assert_eq!(report.largest_winners[0].strategy, "gap");
+        assert_eq!(report.largest_losers.len(), 1);
+        assert_eq!(report.largest_losers[0].mae, -5.0);
+        assert_eq!(report.strategy_stats.len(), 2);
+        assert!(report.max_drawdown_pct > 0.0);
+
+        let duration_total: usize = report.duration_distribution.iter().map(|b| b.count).sum();
+        assert_eq!(duration_total, 3);
+    }
+
+    #[test]
+    fn test_report_rendering() {
+        let mut position = closed_position("momentum", 100.0, 110.0, 30_000);
+        position.exit_reason = Some(ExitReason::TakeProfit);
+        let report = ReportGenerator::new(10_000.0).generate_from_positions(&[position.clone()], &[]);
+
+        assert!(report.render(ReportFormat::Markdown).contains("# Session Report"));
+        assert!(report.render(ReportFormat::Html).contains("<h1>Session Report</h1>"));
+        assert!(report.render(ReportFormat::Markdown).contains("| Initial capital | $10,000.00 |"));
+        let report = ReportGenerator::new(10_000.0).with_currency("eur").generate_from_positions(&[position], &[]);
+        assert_eq!(report.currency, "EUR");
+        assert!(report.render_localized(ReportFormat::Html, Locale::De).contains("<td>10.010,00\u{a0}€</td>"));
+        let csv = report.trades_csv();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",take profit"));
+    }
+}
@@ -0,0 +1,87 @@
+//! Locale-aware formatting of amounts in rendered reports
+//!
+//! Reports render amounts in the session's base currency with the symbol
+//! and separators of the reader's locale, e.g. "$1,234.56" or "1.234,56 €".
+//! Currencies without a symbol, like USDT, show their code after the amount.
+//! CSV and JSON exports stay plain numbers.
+
+use serde::{Deserialize, Serialize};
+
+/// Number conventions of a report's reader
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    #[default]
+    En, // 1,234.56, symbol first
+    De, // 1.234,56, symbol last
+    Fr, // 1 234,56, symbol last
+}
+
+impl Locale {
+    fn separators(self) -> (char, char) {
+        match self {
+            Locale::En => (',', '.'),
+            Locale::De => ('.', ','),
+            Locale::Fr => ('\u{202f}', ','), // Narrow no-break space
+        }
+    }
+
+    /// `value` with `decimals` digits and the locale's separators
+    pub fn number(self, value: f64, decimals: usize) -> String {
+        let (group, decimal) = self.separators();
+        let plain = format!("{:.*}", decimals, value.abs());
+        let (int, frac) = plain.split_once('.').unwrap_or((&plain, ""));
+
+        let mut out = String::new();
+        if value < 0.0 && plain.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+            out.push('-');
+        }
+        for (i, digit) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                out.push(group);
+            }
+            out.push(digit);
+        }
+        if !frac.is_empty() {
+            out.push(decimal);
+            out.push_str(frac);
+        }
+        out
+    }
+
+    /// `amount` in `currency` to two decimals, e.g. "-$12.50" or "12,50 €"
+    pub fn money(self, amount: f64, currency: &str) -> String {
+        let number = self.number(amount, 2);
+        let symbol = match currency.to_uppercase().as_str() {
+            "USD" => Some("$"),
+            "EUR" => Some("€"),
+            "GBP" => Some("£"),
+            "JPY" => Some("¥"),
+            _ => None,
+        };
+        match (symbol, self) {
+            (Some(symbol), Locale::En) => match number.strip_prefix('-') {
+                Some(abs) => format!("-{}{}", symbol, abs),
+                None => format!("{}{}", symbol, number),
+            },
+            (Some(symbol), _) => format!("{}\u{a0}{}", number, symbol),
+            (None, _) => format!("{}\u{a0}{}", number, currency),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money_in_each_locale() {
+        assert_eq!(Locale::En.money(1_234_567.891, "USD"), "$1,234,567.89");
+        assert_eq!(Locale::En.money(-12.5, "usd"), "-$12.50");
+        assert_eq!(Locale::En.money(-0.001, "USD"), "$0.00");
+        assert_eq!(Locale::De.money(1_234.5, "EUR"), "1.234,50\u{a0}€");
+        assert_eq!(Locale::Fr.money(-999.0, "EUR"), "-999,00\u{a0}€");
+        assert_eq!(Locale::Fr.number(1_000.0, 1), "1\u{202f}000,0");
+        assert_eq!(Locale::En.money(100.0, "USDT"), "100.00\u{a0}USDT");
+    }
+}
@@ -0,0 +1,232 @@
+//! Tax-lot and fee summary
+//!
+//! Each closed position is one lot. A long lot's cost basis is its entry
+//! notional and its proceeds the exit notional; a short lot is sold first, so
+//! the entry notional is the proceeds and the exit notional the cost basis.
+//! Fees (commission and slippage) are listed on their own, so proceeds less
+//! cost basis less fees is the realized P&L of the trade and the totals can
+//! be checked against other tools. Lots held over a year are long-term.
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{SessionReport, TradeRecord};
+use crate::exchanges::Side;
+
+/// Holding time above which a lot is long-term
+const LONG_TERM_SECS: u64 = 365 * 86_400;
+
+/// Short-term or long-term
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldingPeriod {
+    ShortTerm,
+    LongTerm,
+}
+
+impl std::fmt::Display for HoldingPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HoldingPeriod::ShortTerm => "short",
+            HoldingPeriod::LongTerm => "long",
+        })
+    }
+}
+
+/// One closed position
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaxLot {
+    pub position_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub acquired: DateTime<Utc>, // Entry, also for shorts
+    pub disposed: DateTime<Utc>,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub fees: f64,
+    pub gain: f64, // Proceeds less cost basis and fees
+    pub holding_period: HoldingPeriod,
+}
+
+impl TaxLot {
+    pub fn from_trade(trade: &TradeRecord) -> Self {
+        let quantity = if trade.closed_quantity > 0.0 { trade.closed_quantity } else { trade.quantity };
+        let entry_notional = trade.entry_price * quantity;
+        let gross = trade.pnl + trade.fees; // Partial closes at other prices are in here too
+        let (proceeds, cost_basis) = match trade.side {
+            Side::Buy => (entry_notional + gross, entry_notional),
+            Side::Sell => (entry_notional, entry_notional - gross),
+        };
+
+        Self {
+            position_id: trade.position_id.clone(),
+            symbol: trade.symbol.clone(),
+            side: trade.side,
+            quantity,
+            acquired: trade.entry_time,
+            disposed: trade.exit_time,
+            proceeds,
+            cost_basis,
+            fees: trade.fees,
+            gain: trade.pnl,
+            holding_period: if trade.duration_secs > LONG_TERM_SECS {
+                HoldingPeriod::LongTerm
+            } else {
+                HoldingPeriod::ShortTerm
+            },
+        }
+    }
+}
+
+/// Lots of one symbol added up
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SymbolTaxSummary {
+    pub symbol: String,
+    pub lots: usize,
+    pub quantity: f64,
+    pub proceeds: f64,
+    pub cost_basis: f64,
+    pub fees: f64,
+    pub short_term_gain: f64,
+    pub long_term_gain: f64,
+}
+
+impl SymbolTaxSummary {
+    fn add(&mut self, lot: &TaxLot) {
+        self.lots += 1;
+        self.quantity += lot.quantity;
+        self.proceeds += lot.proceeds;
+        self.cost_basis += lot.cost_basis;
+        self.fees += lot.fees;
+        match lot.holding_period {
+            HoldingPeriod::ShortTerm => self.short_term_gain += lot.gain,
+            HoldingPeriod::LongTerm => self.long_term_gain += lot.gain,
+        }
+    }
+}
+
+/// Lots disposed of in one calendar year (UTC), by symbol
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaxSummary {
+    pub year: i32,
+    pub generated_at: DateTime<Utc>, // Of the session; lots after it are not in yet
+    pub currency: String, // Of the session's capital; lot amounts are in each symbol's quote currency
+    pub by_symbol: Vec<SymbolTaxSummary>, // Sorted by symbol
+    pub total: SymbolTaxSummary, // Symbol "total"
+    pub lots: Vec<TaxLot>, // In disposal order
+}
+
+impl TaxSummary {
+    /// Symbol totals as CSV, ending with the overall total
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("symbol,lots,quantity,proceeds,cost_basis,fees,short_term_gain,long_term_gain\n");
+        for s in self.by_symbol.iter().chain(std::iter::once(&self.total)) {
+            let _ = writeln!(
+                out,
+                "{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2}",
+                s.symbol, s.lots, s.quantity, s.proceeds, s.cost_basis, s.fees, s.short_term_gain, s.long_term_gain
+            );
+        }
+        out
+    }
+
+    /// One CSV row per lot
+    pub fn lots_csv(&self) -> String {
+        let mut out = String::from("position_id,symbol,side,quantity,acquired,disposed,proceeds,cost_basis,fees,gain,holding_period\n");
+        for lot in &self.lots {
+            let _ = writeln!(
+                out,
+                "{},{},{:?},{},{},{},{:.2},{:.2},{:.2},{:.2},{}",
+                lot.position_id, lot.symbol, lot.side, lot.quantity, lot.acquired.to_rfc3339(), lot.disposed.to_rfc3339(),
+                lot.proceeds, lot.cost_basis, lot.fees, lot.gain, lot.holding_period
+            );
+        }
+        out
+    }
+}
+
+impl SessionReport {
+    /// Lots closed in `year`; for the year the session ends in, that is year to date
+    pub fn tax_summary(&self, year: i32) -> TaxSummary {
+        let lots: Vec<TaxLot> = self.trades
+            .iter()
+            .filter(|t| t.exit_time.year() == year)
+            .map(TaxLot::from_trade)
+            .collect();
+
+        let mut by_symbol: BTreeMap<&str, SymbolTaxSummary> = BTreeMap::new();
+        let mut total = SymbolTaxSummary { symbol: "total".to_string(), ..Default::default() };
+        for lot in &lots {
+            by_symbol
+                .entry(&lot.symbol)
+                .or_insert_with(|| SymbolTaxSummary { symbol: lot.symbol.clone(), ..Default::default() })
+                .add(lot);
+            total.add(lot);
+        }
+
+        TaxSummary {
+            year,
+            generated_at: self.generated_at,
+            currency: self.currency.clone(),
+            by_symbol: by_symbol.into_values().collect(),
+            total,
+            lots,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Symbol};
+    use crate::paper_trading::Position;
+    use crate::reports::ReportGenerator;
+
+    #[test]
+    fn test_lots_add_up_to_realized_pnl() {
+        let day_ms = 86_400_000;
+        let start = 1_704_067_200_000; // 2024-01-01
+        let position = |symbol: &str, side: Side, entry: f64, exits: &[(f64, f64)], held_days: u64| {
+            let mut p = Position::new(Symbol::new(symbol), Exchange::Binance, side, 2.0, entry);
+            p.entry_time = start;
+            p.commission = 1.0;
+            let (last, partials) = exits.split_last().unwrap();
+            for (quantity, price) in partials {
+                p.partial_close(*quantity, *price, 0.5, 0.0, start);
+            }
+            p.close(last.1, 1.0, 0.5, start + held_days * day_ms);
+            p
+        };
+        let positions = vec![
+            position("BTCUSDT", Side::Buy, 100.0, &[(1.0, 120.0), (1.0, 110.0)], 10),
+            position("BTCUSDT", Side::Sell, 100.0, &[(2.0, 90.0)], 400),
+            position("ETHUSDT", Side::Buy, 50.0, &[(2.0, 40.0)], 30),
+            position("ETHUSDT", Side::Buy, 50.0, &[(2.0, 60.0)], 800), // Closed in 2026
+        ];
+        let report = ReportGenerator::new(10_000.0).generate_from_positions(&positions, &[]);
+
+        let summary = report.tax_summary(2025);
+        assert_eq!(summary.lots.len(), 1);
+        assert_eq!(summary.lots[0].holding_period, HoldingPeriod::LongTerm);
+        let short = &summary.lots[0];
+        // Short 2 @ 100, covered @ 90: sold for 200, bought back for 180, 2.5 in fees
+        assert_eq!((short.proceeds, short.cost_basis, short.fees, short.gain), (200.0, 180.0, 2.5, 17.5));
+
+        let summary = report.tax_summary(2024);
+        assert_eq!(summary.lots.len(), 2);
+        let btc = &summary.by_symbol[0];
+        // 1 @ 120 and 1 @ 110 after buying 2 @ 100
+        assert_eq!((btc.quantity, btc.proceeds, btc.cost_basis, btc.fees), (2.0, 230.0, 200.0, 3.0));
+        assert_eq!((btc.short_term_gain, btc.long_term_gain), (27.0, 0.0));
+        let total = &summary.total;
+        assert!((total.proceeds - total.cost_basis - total.fees - (27.0 - 22.5)).abs() < 1e-9);
+
+        let csv = summary.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().last().unwrap().starts_with("total,2,4,"));
+        assert!(summary.lots_csv().lines().nth(1).unwrap().ends_with(",short"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Rhai scripts for custom screening and exits
+//!
+//! Screening predicates and exit rules can be written as short Rhai scripts
+//! instead of Rust, e.g. an exit rule closing a stretched winner:
+//!
+//! ```text
+//! rsi > 75 && pnl_pct > 2
+//! ```
+//!
+//! A script is an expression, or statements ending in one, that evaluates to
+//! a bool. It sees only the variables listed on `ScriptExitRule` and
+//! `ScriptScreen`: there is no file, network or process access, and each run
+//! is capped in operations, call depth and sizes, so a runaway script fails
+//! its run instead of stalling the tick. A failing run counts as false and is
+//! logged once. Edits to a script file take effect within a second; a version
+//! that doesn't compile is logged and the last good one keeps running.
+
+use crate::exchanges::{Side, Symbol};
+use crate::market_scanner::{MarketData, ScreeningPredicate};
+use crate::paper_trading::{ExitRule, Position};
+use anyhow::{anyhow, Context, Result};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rhai::{Engine, Scope, AST};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+/// How often a script file is checked for changes
+const RELOAD_CHECK: Duration = Duration::from_secs(1);
+
+/// Period of the `rsi` variable, in one-minute closes
+const RSI_PERIOD: usize = 14;
+
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(100_000);
+    engine.set_max_call_levels(16);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(4_096);
+    engine.set_max_array_size(1_024);
+    engine.set_max_map_size(256);
+    engine.disable_symbol("eval");
+    engine.on_print(|text| info!(target: "script", "{}", text));
+    engine.on_debug(|text, _, position| debug!(target: "script", "{} ({})", text, position));
+    engine
+}
+
+struct Compiled {
+    ast: Arc<AST>,
+    modified: Option<(SystemTime, u64)>, // Time and length of the compiled version
+    checked: Instant,
+    error: Option<String>, // Last run's error, so it is logged once
+}
+
+/// A script file, recompiled when it changes
+struct ScriptFile {
+    path: PathBuf,
+    engine: Engine,
+    compiled: Mutex<Compiled>,
+}
+
+impl ScriptFile {
+    fn load(path: &Path) -> Result<Self> {
+        let engine = sandboxed_engine();
+        let modified = Self::modified(path);
+        let ast = Self::compile(&engine, path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            compiled: Mutex::new(Compiled { ast: Arc::new(ast), modified, checked: Instant::now(), error: None }),
+        })
+    }
+
+    fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    fn compile(engine: &Engine, path: &Path) -> Result<AST> {
+        let source = std::fs::read_to_string(path).with_context(|| format!("Failed to read script {}", path.display()))?;
+        engine.compile(source).map_err(|e| anyhow!("Failed to compile script {}: {}", path.display(), e))
+    }
+
+    /// Recompile if the file changed since the last check; true when it did
+    fn reload(&self) -> Result<bool> {
+        let mut compiled = self.compiled.lock();
+        compiled.checked = Instant::now();
+        let modified = Self::modified(&self.path);
+        if modified == compiled.modified {
+            return Ok(false);
+        }
+        compiled.modified = modified; // A broken version isn't retried until it changes again
+        compiled.ast = Arc::new(Self::compile(&self.engine, &self.path)?);
+        Ok(true)
+    }
+
+    /// Run with `scope`; errors count as false
+    fn eval(&self, mut scope: Scope) -> bool {
+        if self.compiled.lock().checked.elapsed() >= RELOAD_CHECK {
+            if let Err(e) = self.reload() {
+                warn!(error = %format!("{:#}", e), "Keeping the last good version of the script");
+            }
+        }
+        let ast = self.compiled.lock().ast.clone();
+        let result = self.engine.eval_ast_with_scope::<bool>(&mut scope, &ast);
+
+        let mut compiled = self.compiled.lock();
+        match result {
+            Ok(passes) => {
+                compiled.error = None;
+                passes
+            }
+            Err(e) => {
+                let message = e.to_string();
+                if compiled.error.as_ref() != Some(&message) {
+                    warn!(script = %self.path.display(), error = %message, "Script failed");
+                    compiled.error = Some(message);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// One-minute closes per symbol, for the `rsi` variable
+#[derive(Default)]
+struct MinuteCloses {
+    closes: DashMap<Symbol, (u64, VecDeque<f64>)>, // Minute of the newest close, and the closes
+}
+
+impl MinuteCloses {
+    fn record(&self, symbol: &Symbol, price: f64, now_ms: u64) {
+        let minute = now_ms / 60_000;
+        let mut entry = self.closes.entry(symbol.clone()).or_default();
+        let (last_minute, closes) = &mut *entry;
+        match closes.back_mut() {
+            Some(close) if *last_minute == minute => *close = price,
+            _ => {
+                closes.push_back(price);
+                if closes.len() > RSI_PERIOD + 1 {
+                    closes.pop_front();
+                }
+                *last_minute = minute;
+            }
+        }
+    }
+
+    /// RSI of the last `RSI_PERIOD` closes; NaN until there are enough, so
+    /// comparisons with it are false
+    fn rsi(&self, symbol: &Symbol) -> f64 {
+        let Some(entry) = self.closes.get(symbol) else { return f64::NAN };
+        let closes = &entry.1;
+        if closes.len() <= RSI_PERIOD {
+            return f64::NAN;
+        }
+        let (gains, losses) = closes.iter().zip(closes.iter().skip(1)).fold((0.0, 0.0), |(gains, losses), (a, b)| {
+            if b > a { (gains + b - a, losses) } else { (gains, losses + a - b) }
+        });
+        if losses == 0.0 {
+            return 100.0;
+        }
+        100.0 - 100.0 / (1.0 + gains / losses)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned())
+}
+
+/// Exit rule from a script. Its variables are `symbol`, `side` ("buy" or
+/// "sell"), `price`, `entry_price`, `quantity`, `pnl`, `pnl_pct`, `held_secs`,
+/// `strategy` ("" when none) and `rsi`.
+pub struct ScriptExitRule {
+    name: String,
+    script: ScriptFile,
+    closes: MinuteCloses,
+}
+
+impl ScriptExitRule {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Ok(Self { name: file_name(path), script: ScriptFile::load(path)?, closes: MinuteCloses::default() })
+    }
+
+    /// Recompile now if the file changed, instead of at the next check
+    pub fn reload(&self) -> Result<bool> {
+        self.script.reload()
+    }
+}
+
+impl ExitRule for ScriptExitRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn record_price(&self, symbol: &Symbol, price: f64, now_ms: u64) {
+        self.closes.record(symbol, price, now_ms);
+    }
+
+    fn should_exit(&self, position: &Position, price: f64, now_ms: u64) -> bool {
+        let sign = if position.side == Side::Buy { 1.0 } else { -1.0 };
+        let mut scope = Scope::new();
+        scope.push_constant("symbol", position.symbol.0.clone());
+        scope.push_constant("side", if position.side == Side::Buy { "buy" } else { "sell" }.to_string());
+        scope.push_constant("price", price);
+        scope.push_constant("entry_price", position.entry_price);
+        scope.push_constant("quantity", position.quantity);
+        scope.push_constant("pnl", sign * (price - position.entry_price) * position.quantity);
+        scope.push_constant("pnl_pct", sign * (price / position.entry_price - 1.0) * 100.0);
+        scope.push_constant("held_secs", (now_ms.saturating_sub(position.entry_time) / 1000) as i64);
+        scope.push_constant("strategy", position.strategy.clone().unwrap_or_default());
+        scope.push_constant("rsi", self.closes.rsi(&position.symbol));
+        self.script.eval(scope)
+    }
+}
+
+/// Screening predicate from a script. Its variables are `symbol`, `price`,
+/// `open`, `high`, `low`, `volume`, `volume_24h`, `change_24h` (percent) and
+/// `rsi`.
+pub struct ScriptScreen {
+    script: ScriptFile,
+    closes: MinuteCloses,
+}
+
+impl ScriptScreen {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self { script: ScriptFile::load(path.as_ref())?, closes: MinuteCloses::default() })
+    }
+
+    /// Recompile now if the file changed, instead of at the next check
+    pub fn reload(&self) -> Result<bool> {
+        self.script.reload()
+    }
+}
+
+impl ScreeningPredicate for ScriptScreen {
+    fn passes(&self, data: &MarketData) -> bool {
+        self.closes.record(&data.symbol, data.price, data.timestamp.timestamp_millis().max(0) as u64);
+        let mut scope = Scope::new();
+        scope.push_constant("symbol", data.symbol.0.clone());
+        scope.push_constant("price", data.price);
+        scope.push_constant("open", data.open);
+        scope.push_constant("high", data.high);
+        scope.push_constant("low", data.low);
+        scope.push_constant("volume", data.volume);
+        scope.push_constant("volume_24h", data.volume_24h);
+        scope.push_constant("change_24h", data.change_24h);
+        scope.push_constant("rsi", self.closes.rsi(&data.symbol));
+        self.script.eval(scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::Exchange;
+    use crate::paper_trading::clock::SimulatedClock;
+    use crate::paper_trading::{ExitReason, PositionManager};
+
+    #[test]
+    fn test_scripts_close_positions_and_screen_symbols() {
+        let dir = std::env::temp_dir().join(format!("scripts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exit_path = dir.join("stretched.rhai");
+        std::fs::write(&exit_path, "rsi > 75 && pnl_pct > 2").unwrap();
+
+        let clock = Arc::new(SimulatedClock::new(0));
+        let manager = PositionManager::new().with_clock(clock.clone());
+        let rule = Arc::new(ScriptExitRule::load(&exit_path).unwrap());
+        manager.add_exit_rule(rule.clone());
+        let symbol = Symbol::new("BTC-USD");
+        manager.open_position(symbol.clone(), Exchange::Binance, Side::Buy, 1.0, 100.0, 0.0, 0.0).unwrap();
+
+        // Up a little every minute; no RSI until the fifteenth close
+        for minute in 0..14 {
+            clock.set_ms(minute * 60_000);
+            let price = 100.0 + minute as f64 * 0.25;
+            manager.update_symbol_price(&symbol, price);
+            assert!(manager.check_exits_for_symbol(&symbol, price).is_empty());
+        }
+        clock.set_ms(14 * 60_000);
+        manager.update_symbol_price(&symbol, 103.0);
+        let exits = manager.check_exits_for_symbol(&symbol, 103.0);
+        assert_eq!((exits.len(), exits[0].reason), (1, ExitReason::Rule));
+
+        // A broken edit keeps the old version; a fixed one takes over
+        std::fs::write(&exit_path, "pnl_pct >").unwrap();
+        assert!(rule.reload().is_err());
+        std::fs::write(&exit_path, "held_secs >= 3600 || side == \"sell\"").unwrap();
+        assert!(rule.reload().unwrap());
+        let position = manager.get_open_positions().remove(0);
+        assert!(!rule.should_exit(&position, 103.0, 3_599_000) && rule.should_exit(&position, 103.0, 3_600_000));
+
+        let screen_path = dir.join("screen.rhai");
+        std::fs::write(&screen_path, "let liquid = volume_24h > 1e6;\nliquid && change_24h.abs() > 5").unwrap();
+        let screen = ScriptScreen::load(&screen_path).unwrap();
+        let mut data = MarketData::new(symbol, 100.0);
+        data.volume_24h = 2e6;
+        data.change_24h = -6.0;
+        assert!(screen.passes(&data));
+        data.change_24h = 1.0;
+        assert!(!screen.passes(&data));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,282 @@
+//! Long-running service deployment
+//!
+//! `Service` runs the autonomous system the way a container does: until
+//! SIGTERM or Ctrl+C, with its state on a persistent volume. With a state
+//! directory it
+//!
+//! - restores every account from `<state_dir>/snapshots` on startup, so a
+//!   restarted container carries on with its positions, orders and capital
+//! - snapshots the accounts there every `snapshot_interval` and once more on
+//!   shutdown, each file replaced in one rename
+//! - keeps the daily counters in `<state_dir>/daily-counters.json` unless
+//!   `autonomous.daily_state_path` says otherwise, and the metric time
+//!   series in `<state_dir>/metrics` unless `metrics.series.dir` does
+//! - has the readiness probe check that the directory stays writable
+//!
+//! With an alert webhook it POSTs a JSON alert when it starts, when the
+//! readiness probe turns unready or ready again, and when it shuts down.
+//! SIGHUP reloads the config files.
+
+use crate::api::{HealthConfig, HealthReport};
+use crate::config::RunConfig;
+use crate::{AutonomousTradingSystem, SessionReport};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::signal;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// How often readiness is checked for alerts
+const READINESS_CHECK: Duration = Duration::from_secs(15);
+
+/// Settings of the service deployment, `[service]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceConfig {
+    pub state_dir: Option<PathBuf>, // Snapshots and daily counters; mount a volume here
+    pub snapshot_interval: Duration,
+    pub alert_webhook: Option<String>, // URL alerts are POSTed to
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            state_dir: None,
+            snapshot_interval: Duration::from_secs(60),
+            alert_webhook: None,
+        }
+    }
+}
+
+/// What an alert is about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ServiceEvent {
+    Started { recovered_accounts: Vec<String> },
+    NotReady { problems: Vec<String> },
+    Ready,
+    Stopping { reason: String },
+    Stopped { total_pnl: f64, return_pct: f64 },
+}
+
+/// Body of an alert POST
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub service: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: ServiceEvent,
+}
+
+/// Posts alerts to a webhook; failures are logged, never retried
+#[derive(Clone)]
+struct AlertWebhook {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl AlertWebhook {
+    async fn send(&self, event: ServiceEvent) {
+        let alert = Alert { service: "neuromorphic-paper-trader".to_string(), timestamp: Utc::now(), event };
+        let sent = self.client.post(&self.url).timeout(Duration::from_secs(5)).json(&alert).send().await;
+        if let Err(e) = sent.and_then(|response| response.error_for_status()) {
+            warn!(error = %e, event = ?alert.event, "Failed to send alert");
+        }
+    }
+}
+
+/// The autonomous system with persistent state, alerting and signal handling
+pub struct Service {
+    system: AutonomousTradingSystem,
+    health: HealthConfig,
+    alerts: Option<AlertWebhook>,
+    recovered: Vec<String>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Service {
+    /// Build the system from `config` and restore its accounts from the state
+    /// directory. A snapshot that can't be restored is an error rather than a
+    /// fresh start, so a bad volume doesn't silently reset the accounts.
+    pub fn new(config: &RunConfig, config_files: Vec<PathBuf>) -> Result<Self> {
+        let service = &config.service;
+        let mut autonomous = config.autonomous.clone();
+        if let Some(dir) = &service.state_dir {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create state directory {}", dir.display()))?;
+            autonomous.daily_state_path.get_or_insert_with(|| dir.join("daily-counters.json"));
+            autonomous.metrics.series.dir.get_or_insert_with(|| dir.join("metrics"));
+            if !autonomous.api.health.storage.contains(dir) {
+                autonomous.api.health.storage.push(dir.clone());
+            }
+        }
+        let health = autonomous.api.health.clone();
+        let mut system = AutonomousTradingSystem::new(autonomous).with_config_files(config_files);
+
+        let mut recovered = Vec::new();
+        if let Some(dir) = &service.state_dir {
+            let snapshots = dir.join("snapshots");
+            recovered = system.paper_trader().accounts().restore_snapshots(&snapshots)?;
+            if !recovered.is_empty() {
+                info!(accounts = ?recovered, dir = %snapshots.display(), "Recovered accounts from snapshots");
+            }
+            system = system.with_snapshots(snapshots, service.snapshot_interval);
+        }
+
+        Ok(Self {
+            system,
+            health,
+            alerts: service.alert_webhook.clone().map(|url| AlertWebhook { client: reqwest::Client::new(), url }),
+            recovered,
+            tasks: Vec::new(),
+        })
+    }
+
+    pub fn system(&self) -> &AutonomousTradingSystem {
+        &self.system
+    }
+
+    /// The system, e.g. to attach execution venues before `run_forever`
+    pub fn system_mut(&mut self) -> &mut AutonomousTradingSystem {
+        &mut self.system
+    }
+
+    /// Accounts restored from snapshots on startup
+    pub fn recovered_accounts(&self) -> &[String] {
+        &self.recovered
+    }
+
+    /// A background task to abort on shutdown, e.g. a reconciler
+    pub fn add_task(&mut self, task: JoinHandle<()>) {
+        self.tasks.push(task);
+    }
+
+    /// Trade until SIGTERM or Ctrl+C, then stop everything, snapshot the
+    /// accounts and return the session. Fails, after the same clean stop, if
+    /// the system does.
+    pub async fn run_forever(mut self) -> Result<SessionReport> {
+        self.tasks.extend(spawn_reload_on_hangup(&self.system));
+        if let Some(alerts) = self.alerts.clone() {
+            self.tasks.push(self.spawn_readiness_alerts(alerts.clone()));
+            alerts.send(ServiceEvent::Started { recovered_accounts: self.recovered.clone() }).await;
+        }
+
+        let ended = tokio::select! {
+            result = self.system.start() => Err(result.err().unwrap_or_else(|| anyhow!("Trading loop ended"))),
+            signal = shutdown_signal() => Ok(signal),
+        };
+        let reason = match &ended {
+            Ok(signal) => format!("{} received", signal),
+            Err(e) => format!("{:#}", e),
+        };
+        info!(%reason, "Shutting down");
+        if let Some(alerts) = &self.alerts {
+            alerts.send(ServiceEvent::Stopping { reason }).await;
+        }
+
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.system.stop().await?;
+        let report = self.system.paper_trader().session_report();
+        if let Some(alerts) = &self.alerts {
+            alerts.send(ServiceEvent::Stopped { total_pnl: report.total_pnl, return_pct: report.total_return_pct }).await;
+        }
+        ended?;
+        Ok(report)
+    }
+
+    /// Alert when readiness changes
+    fn spawn_readiness_alerts(&self, alerts: AlertWebhook) -> JoinHandle<()> {
+        let metrics = self.system.paper_trader().metrics_collector().clone();
+        let health = self.health.clone();
+        tokio::spawn(async move {
+            let mut ready = true;
+            let mut check = tokio::time::interval(READINESS_CHECK);
+            loop {
+                check.tick().await;
+                let report = HealthReport::check(&metrics, &health);
+                if report.ready != ready {
+                    ready = report.ready;
+                    let event = if ready { ServiceEvent::Ready } else { ServiceEvent::NotReady { problems: report.problems() } };
+                    alerts.send(event).await;
+                }
+            }
+        })
+    }
+}
+
+/// Name of the first of SIGTERM and Ctrl+C to arrive
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            return tokio::select! {
+                _ = terminate.recv() => "SIGTERM",
+                _ = signal::ctrl_c() => "SIGINT",
+            };
+        }
+        Err(e) => warn!(error = %e, "Cannot listen for SIGTERM, only Ctrl+C stops the service"),
+    }
+    let _ = signal::ctrl_c().await;
+    "SIGINT"
+}
+
+/// Request a config reload on every SIGHUP
+#[cfg(unix)]
+fn spawn_reload_on_hangup(system: &AutonomousTradingSystem) -> Option<JoinHandle<()>> {
+    let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            warn!(error = %e, "Cannot listen for SIGHUP, reload through the API instead");
+            return None;
+        }
+    };
+    let control = system.control().clone();
+    Some(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading config");
+            control.request_reload();
+        }
+    }))
+}
+
+#[cfg(not(unix))]
+fn spawn_reload_on_hangup(_system: &AutonomousTradingSystem) -> Option<JoinHandle<()>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::{Exchange, Side, Symbol};
+
+    #[tokio::test]
+    async fn test_service_recovers_accounts_from_its_state_directory() {
+        let dir = std::env::temp_dir().join(format!("service-state-{}", std::process::id()));
+        let config = RunConfig {
+            service: ServiceConfig { state_dir: Some(dir.clone()), ..Default::default() },
+            ..Default::default()
+        };
+
+        let first = Service::new(&config, Vec::new()).unwrap();
+        assert!(first.recovered_accounts().is_empty());
+        let positions = first.system().paper_trader().positions();
+        positions.open_position(Symbol::new("BTC-USD"), Exchange::Binance, Side::Buy, 0.5, 50_000.0, 0.0, 0.0).unwrap();
+        first.system().paper_trader().accounts().save_snapshots(&dir.join("snapshots")).unwrap();
+        drop(first);
+
+        let second = Service::new(&config, Vec::new()).unwrap();
+        assert_eq!(second.recovered_accounts(), ["default"]);
+        let restored = second.system().paper_trader().positions().get_open_positions();
+        assert_eq!((restored.len(), restored[0].quantity), (1, 0.5));
+        assert!(second.health.storage.contains(&dir));
+        assert!(dir.join("snapshots/default.json").exists());
+
+        let alert = Alert { service: "paper".into(), timestamp: Utc::now(), event: ServiceEvent::NotReady { problems: vec!["engine default".into()] } };
+        let json = serde_json::to_value(&alert).unwrap();
+        assert_eq!((json["event"].as_str(), json["problems"][0].as_str()), (Some("not_ready"), Some("engine default")));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
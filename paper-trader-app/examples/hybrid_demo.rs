@@ -9,7 +9,7 @@ use tracing::{info, error};
 
 use neuromorphic_core::exchanges::{Symbol, Exchange};
 use neuromorphic_core::paper_trading::{TradingSignal, SignalAction, SignalMetadata};
-use neuromorphic_barter_bridge::{NeuromorphicBarterBridge, BridgeResult};
+use neuromorphic_barter_bridge::{BridgeConfig, NeuromorphicBarterBridge};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -22,7 +22,12 @@ async fn main() -> Result<()> {
     info!("🔗 Neuromorphic Signals ↔️ Barter-rs Framework");
 
     // Create and start the bridge
-    let mut bridge = NeuromorphicBarterBridge::new().await?;
+    let config = BridgeConfig {
+        symbols: ["BTC-USD", "ETH-USD", "ADA-USD", "SOL-USD"].into_iter().map(Symbol::new).collect(),
+        cash_asset: "USD".to_string(),
+        ..BridgeConfig::default()
+    };
+    let mut bridge = NeuromorphicBarterBridge::with_config(config).await?;
     bridge.start().await?;
     info!("✅ Neuromorphic-Barter bridge initialized");
 
@@ -88,6 +93,7 @@ fn generate_demo_signals() -> Vec<TradingSignal> {
                 pattern_strength: 0.9,
                 market_regime: "strong_uptrend".to_string(),
                 volatility: 0.025,
+                ..Default::default()
             },
         },
         
@@ -103,6 +109,7 @@ fn generate_demo_signals() -> Vec<TradingSignal> {
                 pattern_strength: 0.7,
                 market_regime: "consolidation".to_string(),
                 volatility: 0.018,
+                ..Default::default()
             },
         },
         
@@ -118,6 +125,7 @@ fn generate_demo_signals() -> Vec<TradingSignal> {
                 pattern_strength: 0.5,
                 market_regime: "sideways".to_string(),
                 volatility: 0.015,
+                ..Default::default()
             },
         },
         
@@ -133,6 +141,7 @@ fn generate_demo_signals() -> Vec<TradingSignal> {
                 pattern_strength: 0.8,
                 market_regime: "bearish_reversal".to_string(),
                 volatility: 0.035,
+                ..Default::default()
             },
         },
         
@@ -148,6 +157,7 @@ fn generate_demo_signals() -> Vec<TradingSignal> {
                 pattern_strength: 0.95,
                 market_regime: "risk_off".to_string(),
                 volatility: 0.045,
+                ..Default::default()
             },
         },
     ]
@@ -164,6 +174,8 @@ impl SignalActionExt for SignalAction {
             SignalAction::Buy { .. } => "BUY",
             SignalAction::Sell { .. } => "SELL", 
             SignalAction::Close { .. } => "CLOSE",
+            SignalAction::ScaleIn { .. } => "SCALE_IN",
+            SignalAction::ScaleOut { .. } => "SCALE_OUT",
             SignalAction::Hold => "HOLD",
         }
     }
@@ -4,26 +4,36 @@
 //! the Barter-rs trading framework for production-grade execution.
 
 use anyhow::Result;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
 use tracing::{info, warn, error};
 
 use neuromorphic_core::exchanges::{Symbol, Exchange, BinanceWebSocketManager, StreamManager, StreamSubscription};
-use neuromorphic_core::paper_trading::{TradingSignal, SignalAction, SignalMetadata};
-use neuromorphic_barter_bridge::NeuromorphicBarterBridge;
+use neuromorphic_core::paper_trading::{TradingSignal, SignalAction, SignalMetadata, SeededRng};
+use neuromorphic_core::logging::{init_logging, LogFormat};
+use neuromorphic_barter_bridge::{BridgeConfig, NeuromorphicBarterBridge};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter("info")
-        .init();
+    // Initialize logging; NEUROMORPHIC_LOG_FORMAT=json emits one JSON object per line
+    init_logging(LogFormat::from_env(), "info")?;
 
     info!("🚀 Starting Neuromorphic Paper Trading System (Hybrid with Barter-rs)");
 
+    let symbols = vec![
+        Symbol::new("BTCUSDT"),
+        Symbol::new("ETHUSDT"),
+        Symbol::new("ADAUSDT"),
+    ];
+
     // Create the neuromorphic-barter bridge
-    let mut bridge = NeuromorphicBarterBridge::new().await?;
+    let mut bridge = NeuromorphicBarterBridge::with_config(BridgeConfig {
+        symbols: symbols.clone(),
+        ..BridgeConfig::default()
+    }).await?;
     bridge.start().await?;
+    let bridge = Arc::new(bridge);
     info!("✅ Neuromorphic-Barter bridge started");
 
     // Create WebSocket manager for real-time data
@@ -32,11 +42,6 @@ async fn main() -> Result<()> {
     info!("✅ Binance WebSocket manager started");
 
     // Subscribe to market data
-    let symbols = vec![
-        Symbol::new("BTCUSDT"),
-        Symbol::new("ETHUSDT"),
-        Symbol::new("ADAUSDT"),
-    ];
 
     for symbol in &symbols {
         let trade_subscription = StreamSubscription::trade(symbol.clone());
@@ -50,8 +55,13 @@ async fn main() -> Result<()> {
 
     info!("🧠 Starting neuromorphic signal generation and market data processing...");
 
+    // NEUROMORPHIC_SEED=<n> repeats the same demo signals run after run
+    let seed = std::env::var("NEUROMORPHIC_SEED").ok().and_then(|seed| seed.parse().ok());
+    let rng = SeededRng::seeded_or_random(seed);
+    info!(seed = rng.seed(), "🎲 Demo signal seed");
+
     // Spawn market data processing task
-    let bridge_handle = bridge;
+    let bridge_handle = bridge.clone();
     let market_data_task = tokio::spawn(async move {
         let mut message_count = 0;
         
@@ -65,7 +75,7 @@ async fn main() -> Result<()> {
             
             // Generate neuromorphic signals based on market data
             if message_count % 10 == 0 { // Generate signal every 10 market events
-                let signal = generate_demo_signal(&symbols).await;
+                let signal = generate_demo_signal(&symbols, &rng).await;
                 
                 if let Err(e) = bridge_handle.send_signal(signal).await {
                     error!("Failed to send neuromorphic signal: {}", e);
@@ -87,9 +97,16 @@ async fn main() -> Result<()> {
         loop {
             interval.tick().await;
             
-            // Note: This would need a reference to the bridge to get actual stats
-            // For now, just log that we're monitoring
-            info!("💰 Portfolio monitoring - checking stats...");
+            match bridge.get_portfolio_stats() {
+                Ok(stats) => info!(
+                    total_value = stats.total_value,
+                    cash = stats.cash,
+                    unrealized_pnl = stats.unrealized_pnl,
+                    realized_pnl = stats.realized_pnl,
+                    "💰 Portfolio"
+                ),
+                Err(e) => warn!("Failed to get portfolio stats: {}", e),
+            }
         }
     });
 
@@ -120,22 +137,14 @@ async fn main() -> Result<()> {
 }
 
 /// Generate a demo neuromorphic trading signal
-async fn generate_demo_signal(symbols: &[Symbol]) -> TradingSignal {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    
+async fn generate_demo_signal(symbols: &[Symbol], rng: &SeededRng) -> TradingSignal {
     // Simple demo signal generation (in production this would use ARES)
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-    
-    let symbol_idx = (timestamp % symbols.len() as u128) as usize;
-    let symbol = symbols[symbol_idx].clone();
+    let symbol = symbols[rng.below(symbols.len() as u64) as usize].clone();
     
-    // Generate pseudo-random signal parameters
-    let action_type = timestamp % 4;
-    let confidence = 0.6 + ((timestamp % 40) as f64 / 100.0);
-    let urgency = (timestamp % 100) as f64 / 100.0;
+    // Generate seeded random signal parameters
+    let action_type = rng.below(4);
+    let confidence = rng.range(0.6, 1.0);
+    let urgency = rng.next_f64();
     
     let action = match action_type {
         0 => SignalAction::Buy { size_hint: Some(1000.0) },
@@ -151,10 +160,11 @@ async fn generate_demo_signal(symbols: &[Symbol]) -> TradingSignal {
         confidence,
         urgency,
         metadata: SignalMetadata {
-            spike_count: (timestamp % 1000) as u64,
+            spike_count: rng.below(1000),
             pattern_strength: confidence,
             market_regime: "demo_trending".to_string(),
             volatility: 0.02,
+            ..Default::default()
         },
     }
 }
\ No newline at end of file